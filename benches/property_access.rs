@@ -0,0 +1,78 @@
+//! Runs property heavy loops in the bytecode VM with and without inline caches.
+//!
+//! `cargo bench --bench property_access`
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use swc_common::BytePos;
+use swc_common::input::StringInput;
+use swc_ecma_parser::{EsSyntax, Parser, Syntax};
+use yavashark_vm::yavashark_bytecode::data::DataSection;
+use yavashark_vm::{OwnedVM, inline_cache};
+
+const RUNS: usize = 5;
+
+const CASES: &[(&str, &str)] = &[
+    (
+        "own properties",
+        "let o = {x: 1, y: 2, z: 3};
+        let s = 0;
+        for (let i = 0; i < 200000; i++) { s = s + o.x + o.z; o.y = s; }",
+    ),
+    (
+        "prototype chain",
+        "let base = {v: 1};
+        let mid = Object.create(base);
+        let leaf = Object.create(mid);
+        let s = 0;
+        for (let i = 0; i < 200000; i++) { s = s + leaf.v; }",
+    ),
+    (
+        "method calls",
+        "let m = {get() { return 1; }};
+        let s = 0;
+        for (let i = 0; i < 200000; i++) { s = s + m.get(); }",
+    ),
+    (
+        "polymorphic",
+        "let objs = [{k: 1}, {a: 0, k: 2}, {b: 0, c: 0, k: 3}];
+        let s = 0;
+        for (let i = 0; i < 200000; i++) { s = s + objs[i % 3].k; }",
+    ),
+];
+
+#[allow(clippy::expect_used)]
+fn run(code: &str) -> Duration {
+    let input = StringInput::new(code, BytePos(0), BytePos(code.len() as u32));
+    let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+    let script = parser.parse_script().expect("failed to parse benchmark");
+
+    let bc = yavashark_compiler::Compiler::compile(&script.body).expect("failed to compile");
+    let data = DataSection::new(bc.variables, Vec::new(), bc.literals, bc.control);
+    let mut vm = OwnedVM::new(bc.instructions, data, PathBuf::from("bench.js"))
+        .expect("failed to create VM");
+
+    let start = Instant::now();
+    vm.run().expect("benchmark threw");
+    start.elapsed()
+}
+
+fn best_of(code: &str, inline_caches: bool) -> Duration {
+    inline_cache::set_enabled(inline_caches);
+
+    (0..RUNS).map(|_| run(code)).min().unwrap_or_default()
+}
+
+fn main() {
+    println!("{:<20} {:>12} {:>12} {:>8}", "", "no IC", "IC", "speedup");
+
+    for (name, code) in CASES {
+        let without = best_of(code, false);
+        let with = best_of(code, true);
+
+        println!(
+            "{name:<20} {without:>12.2?} {with:>12.2?} {:>7.2}x",
+            without.as_secs_f64() / with.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Compares the NaN-boxed `BoxedValue` with the `Value` enum in size and in
+//! arithmetic speed.
+//!
+//! `cargo bench --bench value_repr`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use yavashark_env::{BoxedValue, Realm, Value};
+
+const RUNS: usize = 5;
+const ITERATIONS: i32 = 1_000_000;
+const VALUES: usize = 1_000_000;
+
+fn best_of(mut f: impl FnMut() -> Duration) -> Duration {
+    (0..RUNS).map(|_| f()).min().unwrap_or_default()
+}
+
+#[allow(clippy::expect_used)]
+fn sum_enum(realm: &mut Realm) -> Duration {
+    let start = Instant::now();
+
+    let mut sum = Value::Number(0.0);
+    for i in 0..ITERATIONS {
+        let i = Value::Number(f64::from(i % 100));
+        sum = black_box(&sum).add(&i, realm).expect("add failed");
+        sum = sum.sub(&Value::Number(1.0), realm).expect("sub failed");
+    }
+
+    black_box(sum);
+    start.elapsed()
+}
+
+#[allow(clippy::expect_used)]
+fn sum_boxed(realm: &mut Realm) -> Duration {
+    let start = Instant::now();
+
+    let mut sum = BoxedValue::from_int32(0);
+    for i in 0..ITERATIONS {
+        let i = BoxedValue::from_int32(i % 100);
+        sum = black_box(&sum).add(&i, realm).expect("add failed");
+        sum = sum
+            .sub(&BoxedValue::from_int32(1), realm)
+            .expect("sub failed");
+    }
+
+    black_box(sum);
+    start.elapsed()
+}
+
+fn mixed(i: usize) -> Value {
+    match i % 4 {
+        0 => Value::Number(i as f64),
+        1 => Value::Boolean(i % 2 == 0),
+        2 => Value::string("short"),
+        _ => Value::Undefined,
+    }
+}
+
+fn fill_enum() -> Duration {
+    let start = Instant::now();
+    let values = (0..VALUES).map(mixed).collect::<Vec<_>>();
+    black_box(values);
+    start.elapsed()
+}
+
+fn fill_boxed() -> Duration {
+    let start = Instant::now();
+    let values = (0..VALUES)
+        .map(|i| BoxedValue::from(mixed(i)))
+        .collect::<Vec<_>>();
+    black_box(values);
+    start.elapsed()
+}
+
+#[allow(clippy::expect_used)]
+fn main() {
+    let mut realm = Realm::new().expect("failed to create realm");
+
+    let enum_size = size_of::<Value>();
+    let boxed_size = size_of::<BoxedValue>();
+
+    println!(
+        "{:<24} {:>12} {:>12} {:>8}",
+        "", "Value", "BoxedValue", "ratio"
+    );
+    println!(
+        "{:<24} {enum_size:>12} {boxed_size:>12} {:>7.2}x",
+        "size (bytes)",
+        enum_size as f64 / boxed_size as f64
+    );
+    println!(
+        "{:<24} {:>12} {:>12} {:>7.2}x",
+        format!("{VALUES} values (KiB)"),
+        enum_size * VALUES / 1024,
+        boxed_size * VALUES / 1024,
+        enum_size as f64 / boxed_size as f64
+    );
+
+    let cases: [(&str, Duration, Duration); 2] = [
+        (
+            "int arithmetic",
+            best_of(|| sum_enum(&mut realm)),
+            best_of(|| sum_boxed(&mut realm)),
+        ),
+        ("fill vec", best_of(fill_enum), best_of(fill_boxed)),
+    ];
+
+    for (name, old, new) in cases {
+        println!(
+            "{name:<24} {old:>12.2?} {new:>12.2?} {:>7.2}x",
+            old.as_secs_f64() / new.as_secs_f64()
+        );
+    }
+}
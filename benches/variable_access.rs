@@ -0,0 +1,104 @@
+//! Runs variable heavy code in the tree-walk interpreter with and without the
+//! static scope resolution.
+//!
+//! `cargo bench --bench variable_access`
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use swc_common::BytePos;
+use swc_common::input::StringInput;
+use swc_ecma_ast::Stmt;
+use swc_ecma_parser::{EsSyntax, Parser, Syntax};
+use yavashark_env::Realm;
+use yavashark_env::scope::{Resolution, Scope};
+use yavashark_interpreter::Interpreter;
+
+const RUNS: usize = 5;
+
+const CASES: &[(&str, &str)] = &[
+    (
+        "locals",
+        "function f() {
+            let a = 0, b = 1, c = 2;
+            for (let i = 0; i < 100000; i++) { a = a + b * c; b = c - i; c = a % 7; }
+            return a;
+        }
+        f();",
+    ),
+    (
+        "closure",
+        "function outer() {
+            let count = 0, step = 3;
+            function inner() { { let x = step; count += x; } }
+            for (let i = 0; i < 50000; i++) { inner(); }
+            return count;
+        }
+        outer();",
+    ),
+    (
+        "deep nesting",
+        "function f(n) {
+            let total = 0;
+            for (let i = 0; i < n; i++) {
+                for (let j = 0; j < 10; j++) {
+                    { let k = j; { total = total + i + k + n; } }
+                }
+            }
+            return total;
+        }
+        f(10000);",
+    ),
+];
+
+#[allow(clippy::expect_used)]
+fn parse(code: &str) -> Vec<Stmt> {
+    let input = StringInput::new(code, BytePos(1), BytePos(code.len() as u32 + 1));
+    let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+
+    parser
+        .parse_script()
+        .expect("failed to parse benchmark")
+        .body
+}
+
+#[allow(clippy::expect_used)]
+fn run(script: &[Stmt], resolve: bool) -> Duration {
+    let realm = &mut Realm::new().expect("failed to create realm");
+    let mut scope = Scope::global(realm, PathBuf::from("bench.js"));
+
+    let start = Instant::now();
+
+    if resolve {
+        scope = scope.with_resolution(Rc::new(Resolution::script(script)));
+    }
+
+    Interpreter::run_statements(realm, script, &mut scope).expect("benchmark threw");
+    start.elapsed()
+}
+
+fn best_of(script: &[Stmt], resolve: bool) -> Duration {
+    (0..RUNS)
+        .map(|_| run(script, resolve))
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    println!(
+        "{:<20} {:>12} {:>12} {:>8}",
+        "", "by name", "resolved", "speedup"
+    );
+
+    for (name, code) in CASES {
+        let script = parse(code);
+
+        let by_name = best_of(&script, false);
+        let resolved = best_of(&script, true);
+
+        println!(
+            "{name:<20} {by_name:>12.2?} {resolved:>12.2?} {:>7.2}x",
+            by_name.as_secs_f64() / resolved.as_secs_f64()
+        );
+    }
+}
@@ -58,6 +58,17 @@ pub struct ArrayLiteralBlueprint {
 pub struct BytecodeFunctionCode {
     pub instructions: Vec<instructions::Instruction>,
     pub ds: DataSection,
+    /// Sorted by `pc`, there can be several marks for one instruction.
+    pub spans: Vec<SpanMark>,
+}
+
+/// The instruction a statement, function body or branch arm starts at, the
+/// VM reports the span to the coverage of the realm when it gets there.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SpanMark {
+    pub pc: u32,
+    pub lo: u32,
+    pub hi: u32,
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
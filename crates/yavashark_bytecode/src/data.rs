@@ -4,6 +4,10 @@ use crate::control::ControlBlock;
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct DataSection {
     pub var_names: Vec<String>,
+    /// For each variable, the span start of the identifier it was compiled
+    /// from if the resolver bound that identifier statically. Missing entries
+    /// are looked up by name.
+    pub var_bindings: Vec<Option<u32>>,
     pub labels: Vec<String>,
     pub constants: Vec<ConstValue>,
     pub control: Vec<ControlBlock>,
@@ -19,11 +23,22 @@ impl DataSection {
     ) -> Self {
         Self {
             var_names,
+            var_bindings: Vec::new(),
             labels,
             constants,
             control,
         }
     }
+
+    /// The name of the variable `var` and where its binding starts, see
+    /// [`Self::var_bindings`].
+    #[must_use]
+    pub fn var(&self, var: u32) -> Option<(&str, Option<u32>)> {
+        let name = self.var_names.get(var as usize)?;
+        let binding = self.var_bindings.get(var as usize).copied().flatten();
+
+        Some((name, binding))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
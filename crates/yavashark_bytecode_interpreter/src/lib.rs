@@ -40,14 +40,20 @@ impl ByteCodeInterpreter {
     ) -> Res<ObjectHandle> {
         let mut compiled: Option<Rc<BytecodeFunctionCode>> = None;
         if let Some(body) = &func.body {
-            let code = Compiler::compile(&body.stmts)
+            let mut code = Compiler::with_resolution(scope.resolution().cloned());
+            code.mark(body.span);
+            code.compile_stmts(&body.stmts)
                 .map_err(|e| Error::syn_error(format!("Failed to compile: {e:?}")))?;
 
-            let ds = DataSection::new(code.variables, Vec::new(), code.literals, code.control);
+            let ds = DataSection {
+                var_bindings: code.var_bindings,
+                ..DataSection::new(code.variables, Vec::new(), code.literals, code.control)
+            };
 
             compiled = Some(Rc::new(BytecodeFunctionCode {
                 instructions: code.instructions,
                 ds,
+                spans: code.spans,
             }));
         }
 
@@ -60,16 +66,21 @@ impl ByteCodeInterpreter {
         });
 
         let params = {
-            let (params_code, params_defs) =
-                Compiler::compile_params(func.params.iter().map(|p| &p.pat))
-                    .map_err(|e| Error::syn_error(format!("Failed to compile: {e:?}")))?;
-
-            let ds = DataSection::new(
-                params_code.variables,
-                Vec::new(),
-                params_code.literals,
-                params_code.control,
-            );
+            let (params_code, params_defs) = Compiler::compile_params(
+                func.params.iter().map(|p| &p.pat),
+                scope.resolution().cloned(),
+            )
+            .map_err(|e| Error::syn_error(format!("Failed to compile: {e:?}")))?;
+
+            let ds = DataSection {
+                var_bindings: params_code.var_bindings,
+                ..DataSection::new(
+                    params_code.variables,
+                    Vec::new(),
+                    params_code.literals,
+                    params_code.control,
+                )
+            };
 
             BytecodeFunctionParams {
                 instructions: params_code.instructions,
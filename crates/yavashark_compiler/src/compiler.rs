@@ -3,16 +3,21 @@ mod pat;
 mod statement;
 
 use crate::Res;
+use std::rc::Rc;
 use swc_ecma_ast::{Pat, Stmt};
-use yavashark_bytecode::ConstValue;
+use swc_common::Span;
+use yavashark_bytecode::{ConstValue, SpanMark};
 use yavashark_bytecode::control::ControlBlock;
 use yavashark_bytecode::data::{Acc, Label, Stack};
 use yavashark_bytecode::instructions::Instruction;
+use yavashark_env::scope::Resolution;
 
 #[derive(Debug, Clone, Default)]
 pub struct Compiler {
     pub instructions: Vec<Instruction>,
     pub variables: Vec<String>,
+    /// See [`DataSection::var_bindings`](yavashark_bytecode::data::DataSection::var_bindings).
+    pub var_bindings: Vec<Option<u32>>,
     pub labeled: Vec<String>,
     pub active_labeled: Vec<Label>,
     pub literals: Vec<ConstValue>,
@@ -25,6 +30,9 @@ pub struct Compiler {
     pub max_stack_size: u32,
     pub stack_to_deallloc: Vec<Stack>,
     pub current_fn_name: Option<String>,
+    pub spans: Vec<SpanMark>,
+    /// The resolution of the script the compiled code comes from.
+    pub resolution: Option<Rc<Resolution>>,
 }
 
 impl Compiler {
@@ -33,6 +41,16 @@ impl Compiler {
         Self::default()
     }
 
+    /// A compiler that binds identifiers through `resolution`, which has to
+    /// come from the same AST.
+    #[must_use]
+    pub fn with_resolution(resolution: Option<Rc<Resolution>>) -> Self {
+        Self {
+            resolution,
+            ..Self::default()
+        }
+    }
+
     pub fn compile(stmt: &[Stmt]) -> Res<Self> {
         let mut this = Self::new();
 
@@ -41,8 +59,11 @@ impl Compiler {
         Ok(this)
     }
 
-    pub fn compile_params<'a>(params: impl Iterator<Item = &'a Pat>) -> Res<(Self, Vec<u32>)> {
-        let mut this = Self::new();
+    pub fn compile_params<'a>(
+        params: impl Iterator<Item = &'a Pat>,
+        resolution: Option<Rc<Resolution>>,
+    ) -> Res<(Self, Vec<u32>)> {
+        let mut this = Self::with_resolution(resolution);
 
         let (low, high) = params.size_hint();
         let num_params = high.unwrap_or(low);
@@ -61,6 +82,15 @@ impl Compiler {
         Ok((this, param_defs))
     }
 
+    /// Marks the next instruction as the start of `span`.
+    pub fn mark(&mut self, span: Span) {
+        self.spans.push(SpanMark {
+            pc: self.instructions.len() as u32,
+            lo: span.lo.0,
+            hi: span.hi.0,
+        });
+    }
+
     pub fn reset_allocs(&mut self) {
         self.labeled.clear();
         self.active_labeled.clear();
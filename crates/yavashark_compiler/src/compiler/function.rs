@@ -24,43 +24,53 @@ impl Compiler {
             params: f.params.clone(),
             is_async: f.is_async,
             is_generator: f.is_generator,
-            code: Rc::new(Self::create_bytecode(f)?),
+            code: Rc::new(self.create_bytecode(f)?),
         };
 
         Ok(bp)
     }
 
-    pub fn create_bytecode(f: &Function) -> Res<BytecodeFunctionCode> {
+    pub fn create_bytecode(&self, f: &Function) -> Res<BytecodeFunctionCode> {
         if let Some(body) = &f.body {
-            return Self::create_function_bytecode(body);
+            return self.create_function_bytecode(body);
         }
 
         Ok(BytecodeFunctionCode::default())
     }
 
-    pub fn create_bytecode_from_block(b: &BlockStmt) -> Res<BytecodeFunctionCode> {
-        let mut this = Self::new();
+    pub fn create_bytecode_from_block(&self, b: &BlockStmt) -> Res<BytecodeFunctionCode> {
+        let mut this = Self::with_resolution(self.resolution.clone());
 
+        this.mark(b.span);
         this.compile_block(b)?;
 
-        let ds = DataSection::new(this.variables, this.labeled, this.literals, this.control);
+        let ds = DataSection {
+            var_bindings: this.var_bindings,
+            ..DataSection::new(this.variables, this.labeled, this.literals, this.control)
+        };
 
         Ok(BytecodeFunctionCode {
             instructions: this.instructions,
             ds,
+            spans: this.spans,
         })
     }
 
-    pub fn create_function_bytecode(body: &FunctionBody) -> Res<BytecodeFunctionCode> {
-        let mut this = Self::new();
+    pub fn create_function_bytecode(&self, body: &FunctionBody) -> Res<BytecodeFunctionCode> {
+        let mut this = Self::with_resolution(self.resolution.clone());
 
+        this.mark(body.span);
         this.compile_stmt_block(&body.stmts)?;
 
-        let ds = DataSection::new(this.variables, this.labeled, this.literals, this.control);
+        let ds = DataSection {
+            var_bindings: this.var_bindings,
+            ..DataSection::new(this.variables, this.labeled, this.literals, this.control)
+        };
 
         Ok(BytecodeFunctionCode {
             instructions: this.instructions,
             ds,
+            spans: this.spans,
         })
     }
 }
@@ -19,10 +19,15 @@ mod while_;
 mod with;
 
 use crate::{Compiler, Res};
+use swc_common::Spanned;
 use swc_ecma_ast::Stmt;
 
 impl Compiler {
     pub fn compile_stmt(&mut self, stmt: &Stmt) -> Res {
+        if !matches!(stmt, Stmt::Empty(_)) {
+            self.mark(stmt.span());
+        }
+
         match stmt {
             Stmt::Block(block) => self.compile_block(block),
             Stmt::Empty(_) => Ok(()),
@@ -47,6 +52,10 @@ impl Compiler {
     }
 
     pub fn compile_stmt_last(&mut self, stmt: &Stmt) -> Res {
+        if !matches!(stmt, Stmt::Empty(_)) {
+            self.mark(stmt.span());
+        }
+
         match stmt {
             Stmt::Block(block) => self.compile_block(block),
             Stmt::Empty(_) => Ok(()),
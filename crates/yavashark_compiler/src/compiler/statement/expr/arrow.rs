@@ -2,6 +2,7 @@ use crate::compiler::statement::expr::MoveOptimization;
 use crate::{Compiler, Res};
 use anyhow::anyhow;
 use std::rc::Rc;
+use swc_common::Spanned;
 use swc_ecma_ast::{ArrowExpr, ArrowFunctionBody, Param, Pat};
 use yavashark_bytecode::data::{DataSection, OutputData};
 use yavashark_bytecode::instructions::Instruction;
@@ -17,7 +18,9 @@ impl Compiler {
             return Ok(None);
         };
 
-        let mut this = Self::new();
+        let mut this = Self::with_resolution(self.resolution.clone());
+
+        this.mark(expr.body.span());
 
         match &*expr.body {
             ArrowFunctionBody::FunctionBody(body) => {
@@ -30,11 +33,15 @@ impl Compiler {
             }
         }
 
-        let ds = DataSection::new(this.variables, this.labeled, this.literals, this.control);
+        let ds = DataSection {
+            var_bindings: this.var_bindings,
+            ..DataSection::new(this.variables, this.labeled, this.literals, this.control)
+        };
 
         let code = BytecodeFunctionCode {
             instructions: this.instructions,
             ds,
+            spans: this.spans,
         };
 
         let name = self.current_fn_name.take();
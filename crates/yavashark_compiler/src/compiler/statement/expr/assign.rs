@@ -1,3 +1,4 @@
+use crate::compiler::statement::expr::member::MemberKey;
 use crate::{Compiler, Res};
 use anyhow::anyhow;
 use swc_ecma_ast::{AssignExpr, AssignOp, AssignTarget, AssignTargetPat, SimpleAssignTarget};
@@ -35,8 +36,40 @@ impl Compiler {
                         OutputData::data_type(self.get_ident(&ident.id))
                     }
                     SimpleAssignTarget::Member(m) => {
-                        self.compile_member(m, Some(Acc))?;
-                        OutputData::data_type(Acc) //TODO: we need to update the member again...
+                        let member = self.compile_member_prop(&m.prop)?;
+                        let obj = self.compile_expr_data_acc(&m.obj)?;
+                        let loc = self.alloc_reg_or_stack();
+
+                        if expr.op != AssignOp::Assign {
+                            self.instructions.push(match member {
+                                MemberKey::Public(key) => Instruction::load_member(obj, key, loc),
+                                MemberKey::Private(key) => {
+                                    Instruction::load_private_member(obj, key, loc)
+                                }
+                            });
+                        }
+
+                        self.instructions.push(x(val, loc));
+                        self.instructions.push(match member {
+                            MemberKey::Public(key) => Instruction::store_member(obj, key, loc),
+                            MemberKey::Private(key) => {
+                                Instruction::store_private_member(obj, key, loc)
+                            }
+                        });
+
+                        if let Some(output) = output {
+                            self.instructions.push(Instruction::move_(loc, output));
+                        }
+
+                        self.dealloc(loc);
+                        self.dealloc(obj);
+                        self.dealloc(member.data_type());
+                        if val != Data::data_type(val_) {
+                            self.dealloc(val);
+                        }
+                        self.dealloc(val_);
+
+                        return Ok(());
                     }
                     _ => todo!(),
                 }
@@ -65,7 +98,9 @@ impl Compiler {
                     }
                 }
 
-                self.dealloc(val);
+                if val != Data::data_type(val_) {
+                    self.dealloc(val);
+                }
                 self.dealloc(val_);
 
                 return Ok(());
@@ -80,9 +115,45 @@ impl Compiler {
             self.instructions.push(Instruction::move_(out, output));
         }
 
-        self.dealloc(val);
+        if val != Data::data_type(val_) {
+            self.dealloc(val);
+        }
         self.dealloc(val_);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Compiler;
+    use swc_common::BytePos;
+    use swc_common::input::StringInput;
+    use swc_ecma_parser::{EsSyntax, Parser, Syntax};
+
+    fn compile(src: &str) -> Compiler {
+        let input = StringInput::new(src, BytePos(0), BytePos(src.len() as u32));
+        let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+
+        let script = parser.parse_script().unwrap();
+
+        Compiler::compile(&script.body).unwrap()
+    }
+
+    #[test]
+    fn frees_the_value_slot_once() {
+        // these are compiled straight into the slot allocated for the value,
+        // freeing both the value and that slot used to underflow the stack
+        for src in [
+            "let a, b; a = -b;",
+            "let a, b; a = b.c;",
+            "let a, b; a = typeof b;",
+            "let a; a = f();",
+        ] {
+            let compiler = compile(src);
+
+            assert_eq!(compiler.stack_ptr, 0, "{src}");
+            assert!(compiler.stack_to_deallloc.is_empty(), "{src}");
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{Compiler, Res};
+use swc_common::Spanned;
 use swc_ecma_ast::{BinExpr, BinaryOp};
 use yavashark_bytecode::data::{DataType, OutputData};
 use yavashark_bytecode::instructions::Instruction;
@@ -10,8 +11,20 @@ impl Compiler {
         let reg1 = self.alloc_reg_or_stack();
         let reg2 = self.alloc_reg_or_stack();
 
+        let logical = matches!(
+            expr.op,
+            BinaryOp::LogicalOr | BinaryOp::LogicalAnd | BinaryOp::NullishCoalescing
+        );
+
         //TODO: in theory we can optimize some things out here...
+        if logical {
+            self.mark(expr.left.span());
+        }
         let left = self.compile_expr_data(&expr.left, Some(reg1))?;
+
+        if logical {
+            self.mark(expr.right.span());
+        }
         let right = self.compile_expr_data(&expr.right, Some(reg2))?;
 
         self.instructions
@@ -1,4 +1,5 @@
 use crate::{Compiler, Res};
+use swc_common::Spanned;
 use swc_ecma_ast::CondExpr;
 use yavashark_bytecode::JmpAddr;
 use yavashark_bytecode::data::OutputData;
@@ -10,13 +11,16 @@ impl Compiler {
         let cond = self.compile_test_expr(&expr.test)?;
 
         if cond == Test::Always {
+            self.mark(expr.alt.span());
             self.compile_expr(&expr.alt, out)?;
         } else if cond == Test::Never {
+            self.mark(expr.cons.span());
             self.compile_expr(&expr.cons, out)?;
         } else {
             let jmp = self.instructions.len();
             self.instructions.push(Instruction::JmpRel(0));
 
+            self.mark(expr.cons.span());
             self.compile_expr(&expr.cons, out)?;
 
             if let Some(inst) = cond.get(self.instructions.len() + 1 as JmpAddr) {
@@ -25,6 +29,7 @@ impl Compiler {
             let jmp = self.instructions.len();
             self.instructions.push(Instruction::JmpRel(0));
 
+            self.mark(expr.alt.span());
             self.compile_expr(&expr.alt, out)?;
 
             if let Some(inst) = Test::Always.get(self.instructions.len() as JmpAddr) {
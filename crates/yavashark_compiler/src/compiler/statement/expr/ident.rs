@@ -11,15 +11,64 @@ impl Compiler {
         out: Option<impl OutputData>,
     ) -> Option<MoveOptimization> {
         out.map(|out| {
-            let name = ident.sym.as_str();
-            let var = self.alloc_var(name);
+            let var = self.alloc_ident(ident);
 
             MoveOptimization::new(var, vec![Instruction::load_var(var, out)])
         })
     }
 
     pub fn get_ident(&mut self, ident: &Ident) -> VarName {
-        let name = ident.sym.as_str();
-        self.alloc_var(name)
+        self.alloc_ident(ident)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Compiler;
+    use std::rc::Rc;
+    use swc_common::BytePos;
+    use swc_common::input::StringInput;
+    use swc_ecma_ast::{Decl, Stmt};
+    use swc_ecma_parser::{EsSyntax, Parser, Syntax};
+    use yavashark_env::scope::Resolution;
+
+    #[test]
+    fn resolved_identifiers_get_their_binding() {
+        let src = "function f(a) { return a + a + g; }";
+
+        // spans start at 1, `BytePos(0)` is the dummy position
+        let input = StringInput::new(src, BytePos(1), BytePos(src.len() as u32 + 1));
+        let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+        let script = parser.parse_script().unwrap();
+
+        let f = script
+            .body
+            .iter()
+            .find_map(|stmt| match stmt {
+                Stmt::Decl(Decl::Fn(f)) => Some(f),
+                _ => None,
+            })
+            .unwrap();
+
+        let compiler = Compiler::with_resolution(Some(Rc::new(Resolution::script(&script.body))));
+        let code = compiler.create_bytecode(&f.function).unwrap();
+
+        let vars = |name: &str| {
+            code.ds
+                .var_names
+                .iter()
+                .zip(&code.ds.var_bindings)
+                .filter(|(var, _)| *var == name)
+                .map(|(_, binding)| *binding)
+                .collect::<Vec<_>>()
+        };
+
+        // every reference to `a` goes through its own binding, `g` is a global
+        let a = vars("a");
+        assert_eq!(a.len(), 2);
+        assert!(a.iter().all(Option::is_some));
+        assert_ne!(a[0], a[1]);
+
+        assert_eq!(vars("g"), [None]);
     }
 }
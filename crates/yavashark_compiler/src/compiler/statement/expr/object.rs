@@ -26,7 +26,7 @@ impl Compiler {
             match prop {
                 PropOrSpread::Prop(p) => match &**p {
                     Prop::Shorthand(ident) => {
-                        let var = self.alloc_var(ident.sym.as_str());
+                        let var = self.alloc_ident(ident);
                         let dt = DataTypeValue::Var(var);
                         let id = DataTypeValue::String(ident.sym.to_string());
                         properties.push((id, dt));
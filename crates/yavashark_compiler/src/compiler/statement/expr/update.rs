@@ -13,7 +13,7 @@ impl Compiler {
         };
 
         let (source, member) = match &*expr.arg {
-            Expr::Ident(ident) => (self.alloc_var(ident.sym.as_str()).data_type(), None),
+            Expr::Ident(ident) => (self.alloc_ident(ident).data_type(), None),
             Expr::Member(member) => {
                 let m = self.compile_member_prop(&member.prop)?;
                 let prop = self.compile_expr_data_acc(&member.obj)?;
@@ -1,5 +1,6 @@
 use crate::Compiler;
 use std::borrow::Cow;
+use swc_ecma_ast::Ident;
 use yavashark_bytecode::ConstValue;
 use yavashark_bytecode::control::{ControlBlock, TryBlock};
 use yavashark_bytecode::data::{
@@ -13,13 +14,39 @@ impl Compiler {
         if let Some(var) = self
             .variables
             .iter()
-            .rposition(|x| x.as_str() == name.as_ref())
+            .zip(&self.var_bindings)
+            .rposition(|(x, binding)| binding.is_none() && x.as_str() == name.as_ref())
         {
             return VarName(var as u32);
         }
 
         let var = self.variables.len();
         self.variables.push(name.into_owned());
+        self.var_bindings.push(None);
+
+        VarName(var as u32)
+    }
+
+    /// A variable for a reference to `ident`. Statically resolved identifiers
+    /// get their own variable, so the VM can go through their binding.
+    pub fn alloc_ident(&mut self, ident: &Ident) -> VarName {
+        let pos = ident.span.lo.0;
+
+        if self
+            .resolution
+            .as_ref()
+            .is_none_or(|resolution| resolution.get_at(pos).is_none())
+        {
+            return self.alloc_var(ident.sym.as_str());
+        }
+
+        if let Some(var) = self.var_bindings.iter().position(|b| *b == Some(pos)) {
+            return VarName(var as u32);
+        }
+
+        let var = self.variables.len();
+        self.variables.push(ident.sym.to_string());
+        self.var_bindings.push(Some(pos));
 
         VarName(var as u32)
     }
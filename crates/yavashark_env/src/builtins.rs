@@ -4,8 +4,10 @@ mod async_disposable_stack;
 mod atomics;
 mod bigint;
 mod boolean;
+mod crypto;
 mod date;
 mod disposable_stack;
+mod encoding;
 mod error;
 mod escape;
 #[cfg(feature = "icu")]
@@ -20,12 +22,14 @@ mod proxy;
 mod reflect;
 mod regex;
 mod set;
+mod shadow_realm;
 pub mod signal;
 mod string;
 mod symbol;
 #[cfg(feature = "temporal")]
 pub mod temporal;
 mod throw_type_error;
+mod url;
 mod weakmap;
 mod weakref;
 mod weakset;
@@ -36,8 +40,10 @@ pub use async_disposable_stack::*;
 pub use atomics::*;
 pub use bigint::*;
 pub use boolean::*;
+pub use crypto::*;
 pub use date::*;
 pub use disposable_stack::*;
+pub use encoding::*;
 pub use error::*;
 pub use escape::*;
 pub use iterator::*;
@@ -50,11 +56,13 @@ pub use proxy::*;
 pub use reflect::*;
 pub use regex::*;
 pub use set::*;
+pub use shadow_realm::*;
 pub use string::*;
 pub use symbol::*;
 #[cfg(feature = "temporal")]
 pub use temporal::*;
 pub use throw_type_error::*;
+pub use url::*;
 pub use weakmap::*;
 pub use weakref::*;
 pub use weakset::*;
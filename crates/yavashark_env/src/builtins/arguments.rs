@@ -98,6 +98,11 @@ impl ObjectImpl for Arguments {
         self.inner.borrow_mut()
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>> {
+        self.inner.try_borrow().ok()
+    }
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
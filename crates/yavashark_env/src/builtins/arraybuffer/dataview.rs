@@ -73,6 +73,16 @@ impl DataView {
         })
     }
 
+    /// Copies the bytes currently viewed by this data view.
+    pub fn to_bytes(&self) -> Res<Vec<u8>> {
+        let slice = self.buffer.get_slice()?;
+
+        slice
+            .get(self.byte_offset..self.byte_offset + self.byte_length)
+            .map(<[u8]>::to_vec)
+            .ok_or(Error::ty("DataView is out of bounds"))
+    }
+
     pub fn extract<T: FromBytes>(&self, offset: isize, le: bool) -> Res<T> {
         if offset < 0 || offset == isize::MAX {
             return Err(Error::range("Out of bounds"));
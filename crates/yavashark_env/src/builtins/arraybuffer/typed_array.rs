@@ -100,6 +100,11 @@ impl crate::value::ObjectImpl for TypedArray {
         self.inner.borrow_mut()
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl value::MutObj>> {
+        self.inner.try_borrow().ok()
+    }
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
@@ -470,6 +475,13 @@ impl TypedArray {
         })
     }
 
+    /// Copies the bytes currently viewed by this typed array.
+    pub fn to_bytes(&self) -> Res<Vec<u8>> {
+        let slice = self.buffer.get_slice()?;
+
+        Ok(self.apply_offsets(&slice)?.to_vec())
+    }
+
     pub fn apply_offsets<'a>(&self, slice: &'a [u8]) -> Res<&'a [u8]> {
         let start = self.byte_offset;
 
@@ -1677,7 +1689,7 @@ impl TypedArray {
     }
 }
 
-fn create_ta(realm: &mut Realm, ty: Type, bytes: Vec<u8>) -> Res<ObjectHandle> {
+pub(crate) fn create_ta(realm: &mut Realm, ty: Type, bytes: Vec<u8>) -> Res<ObjectHandle> {
     let buffer = ArrayBuffer::from_buffer(realm, bytes)?;
 
     create_ta_from_buffer(realm, ty, buffer)
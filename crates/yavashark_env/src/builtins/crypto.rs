@@ -0,0 +1,114 @@
+mod hash;
+mod key;
+mod subtle;
+
+pub use hash::*;
+pub use key::*;
+pub use subtle::*;
+
+use crate::builtins::typed_array::{Type, TypedArray};
+use crate::partial_init::Initializer;
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res};
+use std::cell::RefCell;
+use std::fmt::Write;
+use yavashark_macro::{object, props};
+
+/// The maximum number of bytes `getRandomValues` fills in one call.
+const MAX_RANDOM_BYTES: usize = 65536;
+
+#[object]
+#[derive(Debug)]
+pub struct Crypto {
+    subtle: ObjectHandle,
+}
+
+impl Crypto {
+    pub fn new(realm: &mut Realm) -> Res<Self> {
+        let subtle = SubtleCrypto::new(realm)?.into_object();
+
+        Ok(Self {
+            inner: RefCell::new(MutableCrypto {
+                object: MutObject::with_proto(
+                    realm.intrinsics.clone_public().crypto.get(realm)?.clone(),
+                ),
+            }),
+            subtle,
+        })
+    }
+}
+
+impl Initializer<ObjectHandle> for Crypto {
+    fn initialize(realm: &mut Realm) -> Res<ObjectHandle> {
+        Ok(Self::new(realm)?.into_object())
+    }
+}
+
+#[props(intrinsic_name = crypto, to_string_tag = "Crypto")]
+impl Crypto {
+    #[constructor]
+    fn construct() -> Res<ObjectHandle> {
+        Err(Error::ty("Illegal constructor"))
+    }
+
+    #[get("subtle")]
+    fn subtle(&self) -> ObjectHandle {
+        self.subtle.clone()
+    }
+
+    #[prop("getRandomValues")]
+    #[allow(clippy::unused_self)]
+    fn get_random_values(&self, array: ObjectHandle) -> Res<ObjectHandle> {
+        {
+            let ta = array
+                .downcast::<TypedArray>()
+                .filter(|ta| !matches!(ta.ty, Type::F16 | Type::F32 | Type::F64))
+                .ok_or(Error::ty(
+                    "Failed to execute 'getRandomValues' on 'Crypto': The provided ArrayBufferView is not an integer array type",
+                ))?;
+
+            let mut buf = ta.buffer.get_slice_mut()?;
+            let slice = ta.apply_offsets_mut(&mut buf)?;
+
+            if slice.len() > MAX_RANDOM_BYTES {
+                return Err(Error::range_error(format!(
+                    "Failed to execute 'getRandomValues' on 'Crypto': The ArrayBufferView's byte length ({}) exceeds the number of bytes of entropy available via this API ({MAX_RANDOM_BYTES})",
+                    slice.len()
+                )));
+            }
+
+            rand::fill(slice);
+        }
+
+        Ok(array)
+    }
+
+    #[prop("randomUUID")]
+    #[allow(clippy::unused_self)]
+    fn random_uuid_js(&self) -> String {
+        random_uuid()
+    }
+}
+
+/// A version 4 UUID from the thread's cryptographically secure generator.
+#[must_use]
+pub fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    rand::fill(&mut bytes);
+
+    // version 4, variant 10xx
+    bytes[6] = (bytes[6] & 0x0F) | 0x40;
+    bytes[8] = (bytes[8] & 0x3F) | 0x80;
+
+    let mut uuid = String::with_capacity(36);
+
+    for (i, byte) in bytes.iter().enumerate() {
+        if matches!(i, 4 | 6 | 8 | 10) {
+            uuid.push('-');
+        }
+
+        let _ = write!(uuid, "{byte:02x}");
+    }
+
+    uuid
+}
@@ -0,0 +1,145 @@
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::digest::core_api::BlockSizeUser;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Looks up a hash by its (case-insensitive) Web Crypto algorithm name.
+    #[must_use]
+    pub fn for_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "SHA-1" => Self::Sha1,
+            "SHA-256" => Self::Sha256,
+            "SHA-384" => Self::Sha384,
+            "SHA-512" => Self::Sha512,
+            _ => return None,
+        })
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "SHA-1",
+            Self::Sha256 => "SHA-256",
+            Self::Sha384 => "SHA-384",
+            Self::Sha512 => "SHA-512",
+        }
+    }
+
+    /// The block size in bytes, which is also the default HMAC key length.
+    #[must_use]
+    pub fn block_size(self) -> usize {
+        match self {
+            Self::Sha1 => Sha1::block_size(),
+            Self::Sha256 => Sha256::block_size(),
+            Self::Sha384 => Sha384::block_size(),
+            Self::Sha512 => Sha512::block_size(),
+        }
+    }
+
+    #[must_use]
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha384 => Sha384::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    #[must_use]
+    pub fn hmac_sign(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => mac::<Hmac<Sha1>>(key, data)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            Self::Sha256 => mac::<Hmac<Sha256>>(key, data)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            Self::Sha384 => mac::<Hmac<Sha384>>(key, data)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            Self::Sha512 => mac::<Hmac<Sha512>>(key, data)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+        }
+    }
+
+    /// Checks `signature` in constant time.
+    #[must_use]
+    pub fn hmac_verify(self, key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+        match self {
+            Self::Sha1 => mac::<Hmac<Sha1>>(key, data).verify_slice(signature),
+            Self::Sha256 => mac::<Hmac<Sha256>>(key, data).verify_slice(signature),
+            Self::Sha384 => mac::<Hmac<Sha384>>(key, data).verify_slice(signature),
+            Self::Sha512 => mac::<Hmac<Sha512>>(key, data).verify_slice(signature),
+        }
+        .is_ok()
+    }
+}
+
+fn mac<M: Mac + KeyInit>(key: &[u8], data: &[u8]) -> M {
+    #[allow(clippy::expect_used)]
+    let mut mac = <M as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length");
+
+    mac.update(data);
+
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests() {
+        assert_eq!(
+            hex::encode(HashAlgorithm::Sha1.digest(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex::encode(HashAlgorithm::Sha256.digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(HashAlgorithm::Sha384.digest(b"").len(), 48);
+        assert_eq!(HashAlgorithm::Sha512.digest(b"").len(), 64);
+    }
+
+    #[test]
+    fn hmac_rfc_4231() {
+        let key = [0x0b; 20];
+        let signature = HashAlgorithm::Sha256.hmac_sign(&key, b"Hi There");
+
+        assert_eq!(
+            hex::encode(&signature),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert!(HashAlgorithm::Sha256.hmac_verify(&key, b"Hi There", &signature));
+        assert!(!HashAlgorithm::Sha256.hmac_verify(&key, b"Hi there", &signature));
+        assert!(!HashAlgorithm::Sha256.hmac_verify(&key, b"Hi There", &signature[..31]));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            HashAlgorithm::for_name("sha-384"),
+            Some(HashAlgorithm::Sha384)
+        );
+        assert_eq!(HashAlgorithm::for_name("SHA256"), None);
+        assert_eq!(HashAlgorithm::Sha1.block_size(), 64);
+        assert_eq!(HashAlgorithm::Sha512.block_size(), 128);
+    }
+}
@@ -0,0 +1,130 @@
+use super::HashAlgorithm;
+use crate::array::Array;
+use crate::value::Obj;
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use std::cell::RefCell;
+use yavashark_macro::{object, props};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyUsage {
+    Sign,
+    Verify,
+}
+
+impl KeyUsage {
+    #[must_use]
+    pub fn for_name(name: &str) -> Option<Self> {
+        match name {
+            "sign" => Some(Self::Sign),
+            "verify" => Some(Self::Verify),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sign => "sign",
+            Self::Verify => "verify",
+        }
+    }
+}
+
+/// An HMAC secret key.
+#[object]
+#[derive(Debug)]
+pub struct CryptoKey {
+    pub hash: HashAlgorithm,
+    pub key: Vec<u8>,
+    pub extractable: bool,
+    pub usages: Vec<KeyUsage>,
+    algorithm_obj: ObjectHandle,
+    usages_obj: ObjectHandle,
+}
+
+impl CryptoKey {
+    pub fn new(
+        hash: HashAlgorithm,
+        key: Vec<u8>,
+        extractable: bool,
+        usages: Vec<KeyUsage>,
+        realm: &mut Realm,
+    ) -> Res<Self> {
+        let hash_obj = Object::new(realm);
+        hash_obj.define_property("name".into(), hash.name().into(), realm)?;
+
+        let algorithm_obj = Object::new(realm);
+        algorithm_obj.define_property("name".into(), "HMAC".into(), realm)?;
+        algorithm_obj.define_property("hash".into(), hash_obj.into(), realm)?;
+        algorithm_obj.define_property("length".into(), (key.len() * 8).into(), realm)?;
+
+        let names = usages.iter().map(|u| u.name().into()).collect();
+        let usages_obj = Array::with_elements(realm, names)?.into_object();
+
+        Ok(Self {
+            inner: RefCell::new(MutableCryptoKey {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .crypto_key
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+            hash,
+            key,
+            extractable,
+            usages,
+            algorithm_obj,
+            usages_obj,
+        })
+    }
+
+    /// Checks that the key may be used for `usage` with the algorithm `name`.
+    pub fn check(&self, name: &str, usage: KeyUsage) -> Res {
+        if !name.eq_ignore_ascii_case("HMAC") {
+            return Err(Error::ty_error(format!(
+                "Key algorithm HMAC does not match the requested algorithm {name}"
+            )));
+        }
+
+        if !self.usages.contains(&usage) {
+            return Err(Error::ty_error(format!(
+                "Key usages do not permit this operation: {}",
+                usage.name()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[props(intrinsic_name = crypto_key, to_string_tag = "CryptoKey")]
+impl CryptoKey {
+    #[constructor]
+    fn construct() -> Res<ObjectHandle> {
+        Err(Error::ty("Illegal constructor"))
+    }
+
+    #[get("type")]
+    #[allow(clippy::unused_self)]
+    const fn ty(&self) -> &'static str {
+        "secret"
+    }
+
+    #[get("extractable")]
+    const fn extractable(&self) -> bool {
+        self.extractable
+    }
+
+    #[get("algorithm")]
+    fn algorithm(&self) -> Value {
+        self.algorithm_obj.clone().into()
+    }
+
+    #[get("usages")]
+    fn usages(&self) -> Value {
+        self.usages_obj.clone().into()
+    }
+}
@@ -0,0 +1,293 @@
+use super::{CryptoKey, HashAlgorithm, KeyUsage};
+use crate::builtins::Promise;
+use crate::builtins::array_buf::ArrayBuffer;
+use crate::builtins::dataview::DataView;
+use crate::builtins::typed_array::TypedArray;
+use crate::utils::ValueIterator;
+use crate::value::IntoValue;
+use crate::{Error, GCd, MutObject, ObjectHandle, Realm, Res, Value};
+use std::cell::RefCell;
+use yavashark_macro::{object, props};
+
+#[object]
+#[derive(Debug)]
+pub struct SubtleCrypto {}
+
+impl SubtleCrypto {
+    pub fn new(realm: &mut Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableSubtleCrypto {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .subtle_crypto
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+        })
+    }
+}
+
+/// Runs `f` and settles a new promise with its result, so argument errors
+/// reject instead of throwing.
+fn promise(realm: &mut Realm, f: impl FnOnce(&mut Realm) -> Res<Value>) -> Res<ObjectHandle> {
+    match f(realm) {
+        Ok(value) => Promise::resolved(&value, realm),
+        Err(err) => Promise::from_error(err, realm),
+    }
+}
+
+/// Gets the name of an `AlgorithmIdentifier`, which is either a string or
+/// an object with a `name` property.
+fn algorithm_name(algorithm: &Value, realm: &mut Realm) -> Res<String> {
+    let name = match algorithm {
+        Value::Object(obj) => obj.get("name", realm)?,
+        _ => algorithm.clone(),
+    };
+
+    Ok(name.to_string(realm)?.as_str_lossy().into_owned())
+}
+
+fn hash(algorithm: &Value, realm: &mut Realm) -> Res<HashAlgorithm> {
+    let name = algorithm_name(algorithm, realm)?;
+
+    HashAlgorithm::for_name(&name)
+        .ok_or_else(|| Error::ty_error(format!("Unrecognized algorithm name: {name}")))
+}
+
+/// Parses `HmacImportParams`/`HmacKeyGenParams`, returning the hash and the
+/// optional key length in bits.
+fn hmac_params(algorithm: &Value, realm: &mut Realm) -> Res<(HashAlgorithm, Option<usize>)> {
+    let name = algorithm_name(algorithm, realm)?;
+
+    if !name.eq_ignore_ascii_case("HMAC") {
+        return Err(Error::ty_error(format!(
+            "Unrecognized algorithm name: {name}"
+        )));
+    }
+
+    let Value::Object(obj) = algorithm else {
+        return Err(Error::ty("HMAC parameters require a hash"));
+    };
+
+    let hash_param = obj.get("hash", realm)?;
+
+    if hash_param.is_undefined() {
+        return Err(Error::ty("HMAC parameters require a hash"));
+    }
+
+    let hash = hash(&hash_param, realm)?;
+
+    let length = obj.get("length", realm)?;
+    let length = if length.is_undefined() {
+        None
+    } else {
+        let length = length.to_number(realm)?;
+
+        if length <= 0.0 || length % 8.0 != 0.0 {
+            return Err(Error::ty(
+                "HMAC key length must be a positive multiple of 8",
+            ));
+        }
+
+        Some(length as usize)
+    };
+
+    Ok((hash, length))
+}
+
+fn usages(usages: &Value, realm: &mut Realm) -> Res<Vec<KeyUsage>> {
+    let iter = ValueIterator::new(usages, realm)?;
+    let mut list = Vec::new();
+
+    while let Some(usage) = iter.next(realm)? {
+        let usage = usage.to_string(realm)?;
+        let usage = usage.as_str_lossy();
+
+        let usage = KeyUsage::for_name(&usage)
+            .ok_or_else(|| Error::syn_error(format!("Unsupported key usage: {usage}")))?;
+
+        if !list.contains(&usage) {
+            list.push(usage);
+        }
+    }
+
+    if list.is_empty() {
+        return Err(Error::syn("Usages cannot be empty when creating a key"));
+    }
+
+    Ok(list)
+}
+
+fn check_format(format: &Value, realm: &mut Realm) -> Res {
+    let format = format.to_string(realm)?;
+
+    if format.as_str_lossy() != "raw" {
+        return Err(Error::ty_error(format!(
+            "Unsupported key format: {}",
+            format.as_str_lossy()
+        )));
+    }
+
+    Ok(())
+}
+
+fn crypto_key(value: &Value) -> Res<GCd<CryptoKey>> {
+    match value {
+        Value::Object(obj) => obj.downcast::<CryptoKey>(),
+        _ => None,
+    }
+    .ok_or(Error::ty("Parameter is not of type 'CryptoKey'"))
+}
+
+/// Copies the bytes of an `ArrayBuffer` or `ArrayBufferView`.
+fn buffer_source_bytes(value: &Value) -> Res<Vec<u8>> {
+    if let Value::Object(obj) = value {
+        if let Some(ta) = obj.downcast::<TypedArray>() {
+            return ta.to_bytes();
+        }
+
+        if let Some(buf) = obj.downcast::<ArrayBuffer>() {
+            return Ok(buf.get_slice()?.to_vec());
+        }
+
+        if let Some(view) = obj.downcast::<DataView>() {
+            return view.to_bytes();
+        }
+    }
+
+    Err(Error::ty(
+        "The provided value is not of type '(ArrayBuffer or ArrayBufferView)'",
+    ))
+}
+
+#[props(intrinsic_name = subtle_crypto, to_string_tag = "SubtleCrypto")]
+impl SubtleCrypto {
+    #[constructor]
+    fn construct() -> Res<ObjectHandle> {
+        Err(Error::ty("Illegal constructor"))
+    }
+
+    #[allow(clippy::unused_self)]
+    fn digest(&self, algorithm: &Value, data: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        promise(realm, |realm| {
+            let hash = hash(algorithm, realm)?;
+            let data = buffer_source_bytes(data)?;
+
+            Ok(ArrayBuffer::from_buffer(realm, hash.digest(&data))?.into_value())
+        })
+    }
+
+    #[prop("importKey")]
+    #[allow(clippy::unused_self)]
+    fn import_key(
+        &self,
+        format: &Value,
+        key_data: &Value,
+        algorithm: &Value,
+        extractable: &Value,
+        key_usages: &Value,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        promise(realm, |realm| {
+            check_format(format, realm)?;
+
+            let key = buffer_source_bytes(key_data)?;
+            let (hash, length) = hmac_params(algorithm, realm)?;
+
+            if key.is_empty() {
+                return Err(Error::ty("HMAC key data must not be empty"));
+            }
+
+            if length.is_some_and(|length| length != key.len() * 8) {
+                return Err(Error::ty("HMAC key length does not match the key data"));
+            }
+
+            let usages = usages(key_usages, realm)?;
+
+            Ok(CryptoKey::new(hash, key, extractable.is_truthy(), usages, realm)?.into_value())
+        })
+    }
+
+    #[prop("exportKey")]
+    #[allow(clippy::unused_self)]
+    fn export_key(&self, format: &Value, key: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        promise(realm, |realm| {
+            check_format(format, realm)?;
+
+            let key = crypto_key(key)?;
+
+            if !key.extractable {
+                return Err(Error::ty("The key is not extractable"));
+            }
+
+            Ok(ArrayBuffer::from_buffer(realm, key.key.clone())?.into_value())
+        })
+    }
+
+    #[prop("generateKey")]
+    #[allow(clippy::unused_self)]
+    fn generate_key(
+        &self,
+        algorithm: &Value,
+        extractable: &Value,
+        key_usages: &Value,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        promise(realm, |realm| {
+            let (hash, length) = hmac_params(algorithm, realm)?;
+            let usages = usages(key_usages, realm)?;
+
+            let mut key = vec![0; length.map_or_else(|| hash.block_size(), |length| length / 8)];
+            rand::fill(key.as_mut_slice());
+
+            Ok(CryptoKey::new(hash, key, extractable.is_truthy(), usages, realm)?.into_value())
+        })
+    }
+
+    #[allow(clippy::unused_self)]
+    fn sign(
+        &self,
+        algorithm: &Value,
+        key: &Value,
+        data: &Value,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        promise(realm, |realm| {
+            let name = algorithm_name(algorithm, realm)?;
+            let data = buffer_source_bytes(data)?;
+
+            let key = crypto_key(key)?;
+
+            key.check(&name, KeyUsage::Sign)?;
+
+            let signature = key.hash.hmac_sign(&key.key, &data);
+
+            Ok(ArrayBuffer::from_buffer(realm, signature)?.into_value())
+        })
+    }
+
+    #[allow(clippy::unused_self)]
+    fn verify(
+        &self,
+        algorithm: &Value,
+        key: &Value,
+        signature: &Value,
+        data: &Value,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        promise(realm, |realm| {
+            let name = algorithm_name(algorithm, realm)?;
+            let signature = buffer_source_bytes(signature)?;
+            let data = buffer_source_bytes(data)?;
+
+            let key = crypto_key(key)?;
+
+            key.check(&name, KeyUsage::Verify)?;
+
+            Ok(key.hash.hmac_verify(&key.key, &data, &signature).into())
+        })
+    }
+}
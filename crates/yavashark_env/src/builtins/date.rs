@@ -2,6 +2,8 @@
 use crate::Error;
 #[cfg(feature = "temporal")]
 use crate::builtins::Instant;
+#[cfg(feature = "icu")]
+use crate::builtins::intl;
 use crate::conversion::downcast_obj;
 use crate::print::{PrettyObjectOverride, fmt_properties_to};
 use crate::value::Hint;
@@ -9,7 +11,6 @@ use crate::value::Obj;
 use crate::{MutObject, ObjectHandle, Realm, Res, Symbol, Value, ValueResult};
 use chrono::{DateTime, Datelike, Local, LocalResult, Offset, TimeZone, Timelike, Utc};
 use std::cell::RefCell;
-use std::str::FromStr;
 use yavashark_macro::{object, props};
 
 #[object]
@@ -36,9 +37,9 @@ impl Date {
         Ok(Local::now().timestamp_millis().into())
     }
 
-    pub fn parse(s: &str) -> ValueResult {
-        let date = DateTime::from_str(s).unwrap_or(Local::now());
-        Ok(date.timestamp_millis().into())
+    #[must_use]
+    pub fn parse(s: &str) -> f64 {
+        parse_date(s).map_or(f64::NAN, |d| d.timestamp_millis() as f64)
     }
 
     #[prop("UTC")]
@@ -1083,24 +1084,90 @@ impl Date {
     }
 
     #[prop("toLocaleDateString")]
-    pub fn to_locale_date_string(&self) -> String {
-        self.date().map_or("Invalid Date".to_string(), |d| {
-            d.format("%m/%d/%Y").to_string()
-        })
+    pub fn to_locale_date_string(
+        &self,
+        locales: &Value,
+        options: Value,
+        #[realm] realm: &mut Realm,
+    ) -> Res<String> {
+        #[cfg(feature = "icu")]
+        {
+            intl::format_date_time(
+                self.value_of(),
+                locales,
+                options,
+                intl::Required::Date,
+                intl::Defaults::Date,
+                realm,
+            )
+        }
+
+        #[cfg(not(feature = "icu"))]
+        {
+            let _ = (locales, options, realm);
+
+            Ok(self.date().map_or("Invalid Date".to_string(), |d| {
+                d.format("%m/%d/%Y").to_string()
+            }))
+        }
     }
 
     #[prop("toLocaleString")]
-    pub fn to_locale_string(&self) -> String {
-        self.date().map_or("Invalid Date".to_string(), |d| {
-            d.format("%m/%d/%Y, %I:%M:%S %p").to_string()
-        })
+    pub fn to_locale_string(
+        &self,
+        locales: &Value,
+        options: Value,
+        #[realm] realm: &mut Realm,
+    ) -> Res<String> {
+        #[cfg(feature = "icu")]
+        {
+            intl::format_date_time(
+                self.value_of(),
+                locales,
+                options,
+                intl::Required::Any,
+                intl::Defaults::All,
+                realm,
+            )
+        }
+
+        #[cfg(not(feature = "icu"))]
+        {
+            let _ = (locales, options, realm);
+
+            Ok(self.date().map_or("Invalid Date".to_string(), |d| {
+                d.format("%m/%d/%Y, %I:%M:%S %p").to_string()
+            }))
+        }
     }
 
     #[prop("toLocaleTimeString")]
-    pub fn to_locale_time_string(&self) -> String {
-        self.date().map_or("Invalid Date".to_string(), |d| {
-            d.format("%I:%M:%S %p").to_string()
-        })
+    pub fn to_locale_time_string(
+        &self,
+        locales: &Value,
+        options: Value,
+        #[realm] realm: &mut Realm,
+    ) -> Res<String> {
+        #[cfg(feature = "icu")]
+        {
+            intl::format_date_time(
+                self.value_of(),
+                locales,
+                options,
+                intl::Required::Time,
+                intl::Defaults::Time,
+                realm,
+            )
+        }
+
+        #[cfg(not(feature = "icu"))]
+        {
+            let _ = (locales, options, realm);
+
+            Ok(self.date().map_or("Invalid Date".to_string(), |d| {
+                d.format("%I:%M:%S %p").to_string()
+            }))
+        }
     }
 
     #[prop("toString")]
@@ -1160,11 +1227,7 @@ impl Date {
                 let arg = &args[0];
 
                 match arg {
-                    Value::String(s) => match parse_date_string(&s.as_str_lossy()) {
-                        ParseResult::Parsed(dt) => Some(dt),
-                        ParseResult::Invalid => None,
-                        ParseResult::NotMatched => DateTime::from_str(&s.as_str_lossy()).ok(),
-                    },
+                    Value::String(s) => parse_date(&s.as_str_lossy()),
                     Value::Number(time) => {
                         let time = *time;
                         if !time.is_finite() {
@@ -1184,13 +1247,7 @@ impl Date {
                             let prim = obj.to_primitive(Hint::None, realm)?;
 
                             match prim {
-                                Value::String(s) => match parse_date_string(&s.as_str_lossy()) {
-                                    ParseResult::Parsed(dt) => Some(dt),
-                                    ParseResult::Invalid => None,
-                                    ParseResult::NotMatched => {
-                                        DateTime::from_str(&s.as_str_lossy()).ok()
-                                    }
-                                },
+                                Value::String(s) => parse_date(&s.as_str_lossy()),
                                 Value::Symbol(_) => {
                                     return Err(crate::Error::ty(
                                         "Cannot convert a Symbol value to a number",
@@ -1844,3 +1901,276 @@ fn parse_date_string(s: &str) -> ParseResult {
         ParseResult::Parsed(local_dt)
     }
 }
+
+/// Parses a date string the way `Date.parse` does: first as the ECMAScript
+/// date-time string format, then as one of the legacy formats.
+fn parse_date(s: &str) -> Option<DateTime<Local>> {
+    match parse_date_string(s) {
+        ParseResult::Parsed(dt) => Some(dt),
+        ParseResult::Invalid | ParseResult::NotMatched => parse_legacy_date(s),
+    }
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// North American time zone names accepted by the legacy parser, with their
+/// offset from UTC in hours.
+const US_ZONES: [(&str, i32); 8] = [
+    ("est", -5),
+    ("edt", -4),
+    ("cst", -6),
+    ("cdt", -5),
+    ("mst", -7),
+    ("mdt", -6),
+    ("pst", -8),
+    ("pdt", -7),
+];
+
+/// Parse one of the legacy date formats other engines accept, e.g.
+/// - `Tue Jan 02 2024 10:00:00 GMT+0100 (Central European Standard Time)`
+/// - `Tue, 02 Jan 2024 09:00:00 GMT` (RFC 2822, `toUTCString`)
+/// - `January 2, 2024 10:00 PM`, `2 Jan 2024`
+/// - `1/2/2024 10:00`, `2024/01/02`, `2024-01-02 10:00:00.123 +01:00`
+///
+/// Strings without a time zone are interpreted as local time.
+fn parse_legacy_date(s: &str) -> Option<DateTime<Local>> {
+    let bytes = s.as_bytes();
+
+    // (value, number of digits)
+    let mut numbers: Vec<(u32, usize)> = Vec::new();
+    let mut month = None;
+    let mut time = None;
+    let mut offset: Option<i32> = None;
+    let mut pm = None;
+
+    let read_number = |i: &mut usize| -> Option<(u32, usize)> {
+        let start = *i;
+
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+
+        let digits = &s[start..*i];
+
+        Some((digits.parse().ok()?, digits.len()))
+    };
+
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        match c {
+            b'(' => {
+                let mut depth = 0;
+
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        _ => {}
+                    }
+
+                    i += 1;
+
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            b'0'..=b'9' => {
+                let (n, len) = read_number(&mut i)?;
+
+                if time.is_none() && bytes.get(i) == Some(&b':') {
+                    i += 1;
+                    let (minute, _) = read_number(&mut i)?;
+                    let mut second = 0;
+                    let mut ms = 0;
+
+                    if bytes.get(i) == Some(&b':') {
+                        i += 1;
+                        second = read_number(&mut i)?.0;
+
+                        if bytes.get(i) == Some(&b'.') {
+                            i += 1;
+                            let start = i;
+                            read_number(&mut i)?;
+
+                            let digits = &s[start..i.min(start + 3)];
+                            ms = digits.parse::<u32>().ok()? * 10u32.pow(3 - digits.len() as u32);
+                        }
+                    }
+
+                    time = Some((n, minute, second, ms));
+                } else {
+                    numbers.push((n, len));
+                }
+            }
+            b'+' | b'-' if time.is_some() || offset.is_some() => {
+                i += 1;
+                let (n, len) = read_number(&mut i)?;
+
+                let (hours, minutes) = if len <= 2 {
+                    let minutes = if bytes.get(i) == Some(&b':') {
+                        i += 1;
+                        read_number(&mut i)?.0
+                    } else {
+                        0
+                    };
+
+                    (n, minutes)
+                } else if len == 4 {
+                    (n / 100, n % 100)
+                } else {
+                    return None;
+                };
+
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+
+                let sign = if c == b'-' { -1 } else { 1 };
+                offset = Some(sign * (hours * 60 + minutes) as i32);
+            }
+            b'-' | b'/' | b'.' if !numbers.is_empty() || month.is_some() => i += 1,
+            b' ' | b',' | b'\t' | b'\n' | b'\r' => i += 1,
+            _ if c.is_ascii_alphabetic() => {
+                let start = i;
+
+                while i < bytes.len() && (bytes[i].is_ascii_alphabetic() || bytes[i] == b'.') {
+                    i += 1;
+                }
+
+                let word = s[start..i].trim_end_matches('.').to_ascii_lowercase();
+
+                match word.as_str() {
+                    "am" | "a.m" => pm = Some(false),
+                    "pm" | "p.m" => pm = Some(true),
+                    "z" | "gmt" | "utc" | "ut" => offset = Some(offset.unwrap_or(0)),
+                    zone if US_ZONES.iter().any(|(name, _)| *name == zone) => {
+                        offset = US_ZONES
+                            .iter()
+                            .find_map(|(name, hours)| (*name == zone).then_some(hours * 60));
+                    }
+                    "t" if time.is_none() && !numbers.is_empty() => {}
+                    word if word.len() >= 3 => {
+                        let prefix = &word[..3];
+
+                        if let Some(m) = MONTH_NAMES.iter().position(|name| *name == prefix) {
+                            if month.is_some() {
+                                return None;
+                            }
+
+                            month = Some(m as u32 + 1);
+                        } else if !WEEKDAY_NAMES.contains(&prefix) && !numbers.is_empty() {
+                            return None;
+                        }
+                    }
+                    _ if numbers.is_empty() && month.is_none() => {}
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    let is_year = |(n, len): (u32, usize)| len >= 3 || n > 31;
+
+    let (year, month, day) = match (month, numbers.as_slice()) {
+        (Some(month), &[first, second]) => {
+            if is_year(first) {
+                (first, month, second.0)
+            } else {
+                (second, month, first.0)
+            }
+        }
+        (Some(month), &[year]) if is_year(year) => (year, month, 1),
+        (None, &[first, second, third]) => {
+            if is_year(first) {
+                (first, second.0, third.0)
+            } else {
+                (third, first.0, second.0)
+            }
+        }
+        _ => return None,
+    };
+
+    let year = match year {
+        (n, len) if len <= 2 && n < 50 => 2000 + n,
+        (n, len) if len <= 2 => 1900 + n,
+        (n, _) => n,
+    };
+
+    let (mut hour, minute, second, ms) = time.unwrap_or_default();
+
+    if let Some(pm) = pm {
+        if hour > 12 {
+            return None;
+        }
+
+        hour = hour % 12 + if pm { 12 } else { 0 };
+    }
+
+    let naive = chrono::NaiveDate::from_ymd_opt(year as i32, month, day)?
+        .and_hms_milli_opt(hour, minute, second, ms)?;
+
+    let Some(offset) = offset else {
+        return Local.from_local_datetime(&naive).earliest();
+    };
+
+    Some(
+        (naive - chrono::Duration::minutes(i64::from(offset)))
+            .and_utc()
+            .with_timezone(&Local),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc_millis(s: &str) -> Option<i64> {
+        parse_date(s).map(|d| d.timestamp_millis())
+    }
+
+    #[test]
+    fn iso_strings() {
+        assert_eq!(utc_millis("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(utc_millis("2024-01-02"), Some(1_704_153_600_000));
+        assert_eq!(
+            utc_millis("2024-01-02T03:04:05.678+01:00"),
+            Some(1_704_161_045_678)
+        );
+        assert_eq!(utc_millis("-000000-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn legacy_strings() {
+        let expected = Some(1_704_186_000_000);
+
+        assert_eq!(utc_millis("Tue, 02 Jan 2024 09:00:00 GMT"), expected);
+        assert_eq!(
+            utc_millis("Tue Jan 02 2024 10:00:00 GMT+0100 (Central European Standard Time)"),
+            expected
+        );
+        assert_eq!(utc_millis("January 2, 2024 9:00 AM UTC"), expected);
+        assert_eq!(utc_millis("1/2/2024 04:00:00 EST"), expected);
+        assert_eq!(utc_millis("2024/01/02 09:00 Z"), expected);
+        assert_eq!(utc_millis("2024-01-02 10:00:00.000 +01:00"), expected);
+        assert_eq!(utc_millis("2 Jan 24 09:00 GMT"), expected);
+    }
+
+    #[test]
+    fn invalid_strings() {
+        assert_eq!(utc_millis(""), None);
+        assert_eq!(utc_millis("not a date"), None);
+        assert_eq!(utc_millis("2024-02-30"), None);
+        assert_eq!(utc_millis("Jan 2024 2 3"), None);
+        assert_eq!(utc_millis("13/45/2024"), None);
+        assert_eq!(utc_millis("2024-01-02T25:00"), None);
+    }
+}
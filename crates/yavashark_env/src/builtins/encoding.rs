@@ -0,0 +1,7 @@
+mod codec;
+mod decoder;
+mod encoder;
+
+pub use codec::{DecodeError, Decoder, Encoding, encode_utf8, encode_utf8_into};
+pub use decoder::*;
+pub use encoder::*;
@@ -0,0 +1,375 @@
+use yavashark_string::{CodePoint, YSString};
+
+const REPLACEMENT: u16 = 0xFFFD;
+const BOM: u16 = 0xFEFF;
+
+/// `windows-1252` code points for the bytes `0x80..=0x9F`, every other byte
+/// maps to the code point of the same value.
+const WINDOWS_1252_HIGH: [u16; 32] = [
+    0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+}
+
+impl Encoding {
+    /// Looks up an encoding by one of its WHATWG labels.
+    #[must_use]
+    pub fn for_label(label: &str) -> Option<Self> {
+        let label = label
+            .trim_matches(['\t', '\n', '\x0C', '\r', ' '])
+            .to_ascii_lowercase();
+
+        Some(match label.as_str() {
+            "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+            | "x-unicode20utf8" => Self::Utf8,
+            "csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff" | "utf-16"
+            | "utf-16le" => Self::Utf16Le,
+            "unicodefffe" | "utf-16be" => Self::Utf16Be,
+            "ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1" | "ibm819"
+            | "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591" | "iso_8859-1"
+            | "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii" | "windows-1252" | "x-cp1252" => {
+                Self::Windows1252
+            }
+            _ => return None,
+        })
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+}
+
+/// A malformed sequence was found while decoding in fatal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError;
+
+/// A streaming decoder, keeping incomplete sequences around between calls.
+#[derive(Debug)]
+pub struct Decoder {
+    encoding: Encoding,
+    fatal: bool,
+    ignore_bom: bool,
+    bom_seen: bool,
+    pending: Vec<u8>,
+}
+
+impl Decoder {
+    #[must_use]
+    pub const fn new(encoding: Encoding, fatal: bool, ignore_bom: bool) -> Self {
+        Self {
+            encoding,
+            fatal,
+            ignore_bom,
+            bom_seen: false,
+            pending: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    #[must_use]
+    pub const fn fatal(&self) -> bool {
+        self.fatal
+    }
+
+    #[must_use]
+    pub const fn ignore_bom(&self) -> bool {
+        self.ignore_bom
+    }
+
+    /// Decodes `input` to UTF-16. With `stream` set, a trailing incomplete
+    /// sequence is kept for the next call instead of being treated as an error.
+    pub fn decode(&mut self, input: &[u8], stream: bool) -> Result<Vec<u16>, DecodeError> {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(input);
+
+        let mut out = Vec::with_capacity(bytes.len());
+
+        let res = match self.encoding {
+            Encoding::Utf8 => self.decode_utf8(&bytes, stream, &mut out),
+            Encoding::Utf16Le => self.decode_utf16(&bytes, u16::from_le_bytes, stream, &mut out),
+            Encoding::Utf16Be => self.decode_utf16(&bytes, u16::from_be_bytes, stream, &mut out),
+            Encoding::Windows1252 => {
+                out.extend(bytes.iter().map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(b - 0x80)],
+                    _ => u16::from(b),
+                }));
+
+                Ok(())
+            }
+        };
+
+        if let Err(err) = res {
+            self.pending.clear();
+            self.bom_seen = false;
+
+            return Err(err);
+        }
+
+        if !out.is_empty() && !self.bom_seen {
+            self.bom_seen = true;
+
+            if !self.ignore_bom && self.encoding != Encoding::Windows1252 && out[0] == BOM {
+                out.remove(0);
+            }
+        }
+
+        if !stream {
+            self.bom_seen = false;
+        }
+
+        Ok(out)
+    }
+
+    fn error(&self, out: &mut Vec<u16>) -> Result<(), DecodeError> {
+        if self.fatal {
+            return Err(DecodeError);
+        }
+
+        out.push(REPLACEMENT);
+
+        Ok(())
+    }
+
+    fn decode_utf8(
+        &mut self,
+        bytes: &[u8],
+        stream: bool,
+        out: &mut Vec<u16>,
+    ) -> Result<(), DecodeError> {
+        let mut i = 0;
+
+        'outer: while i < bytes.len() {
+            let first = bytes[i];
+
+            if first < 0x80 {
+                out.push(u16::from(first));
+                i += 1;
+                continue;
+            }
+
+            let (needed, mut lower, mut upper, init) = match first {
+                0xC2..=0xDF => (1, 0x80, 0xBF, first & 0x1F),
+                0xE0 => (2, 0xA0, 0xBF, first & 0x0F),
+                0xED => (2, 0x80, 0x9F, first & 0x0F),
+                0xE1..=0xEF => (2, 0x80, 0xBF, first & 0x0F),
+                0xF0 => (3, 0x90, 0xBF, first & 0x07),
+                0xF4 => (3, 0x80, 0x8F, first & 0x07),
+                0xF1..=0xF3 => (3, 0x80, 0xBF, first & 0x07),
+                _ => {
+                    self.error(out)?;
+                    i += 1;
+                    continue;
+                }
+            };
+
+            let mut cp = u32::from(init);
+
+            for seen in 1..=needed {
+                let Some(&byte) = bytes.get(i + seen) else {
+                    if stream {
+                        self.pending = bytes[i..].to_vec();
+                    } else {
+                        self.error(out)?;
+                    }
+
+                    break 'outer;
+                };
+
+                if !(lower..=upper).contains(&byte) {
+                    self.error(out)?;
+                    i += seen;
+                    continue 'outer;
+                }
+
+                cp = (cp << 6) | u32::from(byte & 0x3F);
+                lower = 0x80;
+                upper = 0xBF;
+            }
+
+            let ch = char::from_u32(cp).unwrap_or(char::REPLACEMENT_CHARACTER);
+            out.extend_from_slice(ch.encode_utf16(&mut [0; 2]));
+
+            i += needed + 1;
+        }
+
+        Ok(())
+    }
+
+    fn decode_utf16(
+        &mut self,
+        bytes: &[u8],
+        unit: fn([u8; 2]) -> u16,
+        stream: bool,
+        out: &mut Vec<u16>,
+    ) -> Result<(), DecodeError> {
+        let mut lead = None;
+        let mut i = 0;
+
+        while let Some(&[a, b]) = bytes.get(i..i + 2) {
+            let u = unit([a, b]);
+            i += 2;
+
+            if let Some(lead) = lead.take() {
+                if (0xDC00..=0xDFFF).contains(&u) {
+                    out.extend_from_slice(&[lead, u]);
+                    continue;
+                }
+
+                self.error(out)?;
+            }
+
+            match u {
+                0xD800..=0xDBFF => lead = Some(u),
+                0xDC00..=0xDFFF => self.error(out)?,
+                _ => out.push(u),
+            }
+        }
+
+        let rest = if lead.is_some() { i - 2 } else { i };
+
+        if rest < bytes.len() {
+            if stream {
+                self.pending = bytes[rest..].to_vec();
+            } else {
+                self.error(out)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Encodes a string as UTF-8, replacing lone surrogates with U+FFFD.
+#[must_use]
+pub fn encode_utf8(input: &YSString) -> Vec<u8> {
+    if let Some(bytes) = input.as_bytes() {
+        return bytes.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(input.len());
+
+    for cp in input.code_points() {
+        let ch = scalar(cp);
+        out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    out
+}
+
+/// Encodes as much of `input` as fits into `dest` without splitting a code
+/// point, returning the UTF-16 code units read and the bytes written.
+pub fn encode_utf8_into(input: &YSString, dest: &mut [u8]) -> (usize, usize) {
+    let mut read = 0;
+    let mut written = 0;
+
+    for cp in input.code_points() {
+        let ch = scalar(cp);
+        let len = ch.len_utf8();
+
+        let Some(slot) = dest.get_mut(written..written + len) else {
+            break;
+        };
+
+        ch.encode_utf8(slot);
+
+        written += len;
+        read += match cp {
+            CodePoint::Unicode(ch) => ch.len_utf16(),
+            CodePoint::UnpairedSurrogate(_) => 1,
+        };
+    }
+
+    (read, written)
+}
+
+const fn scalar(cp: CodePoint) -> char {
+    match cp {
+        CodePoint::Unicode(ch) => ch,
+        CodePoint::UnpairedSurrogate(_) => char::REPLACEMENT_CHARACTER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoding: Encoding, chunks: &[&[u8]]) -> String {
+        let mut decoder = Decoder::new(encoding, false, false);
+        let mut out = Vec::new();
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            out.extend(decoder.decode(chunk, i + 1 < chunks.len()).unwrap());
+        }
+
+        String::from_utf16(&out).unwrap()
+    }
+
+    #[test]
+    fn utf8_streaming_and_bom() {
+        let bytes = "\u{FEFF}aé€😀".as_bytes();
+
+        for split in 0..bytes.len() {
+            let chunks = [&bytes[..split], &bytes[split..]];
+            assert_eq!(decode(Encoding::Utf8, &chunks), "aé€😀");
+        }
+    }
+
+    #[test]
+    fn utf8_replaces_maximal_subparts() {
+        assert_eq!(
+            decode(Encoding::Utf8, &[b"a\xF0\x9F\x98b\xC0\xED\xA0\x80"]),
+            "a\u{FFFD}b\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}"
+        );
+        assert_eq!(decode(Encoding::Utf8, &[b"\xE2\x82"]), "\u{FFFD}");
+
+        let mut fatal = Decoder::new(Encoding::Utf8, true, false);
+        assert_eq!(fatal.decode(b"\xFF", false), Err(DecodeError));
+        assert_eq!(fatal.decode(b"ok", false), Ok(vec![0x6F, 0x6B]));
+    }
+
+    #[test]
+    fn utf16_both_endians() {
+        assert_eq!(
+            decode(Encoding::Utf16Le, &[b"\xFF\xFEa\x00=\xD8", b"\x00\xDE"]),
+            "a😀"
+        );
+        assert_eq!(decode(Encoding::Utf16Be, &[b"\x00a\xD8"]), "a\u{FFFD}");
+        assert_eq!(decode(Encoding::Utf16Be, &[b"\xDC\x00\x00b"]), "\u{FFFD}b");
+    }
+
+    #[test]
+    fn windows_1252() {
+        assert_eq!(Encoding::for_label(" Latin1 "), Some(Encoding::Windows1252));
+        assert_eq!(decode(Encoding::Windows1252, &[b"caf\xE9 \x80"]), "café €");
+    }
+
+    #[test]
+    fn encode_into_does_not_split_code_points() {
+        let input = YSString::from_ref("a€😀");
+        let mut dest = [0; 5];
+
+        assert_eq!(encode_utf8_into(&input, &mut dest), (2, 4));
+        assert_eq!(&dest[..4], "a€".as_bytes());
+
+        let lone = YSString::from_utf16(&[0x61, 0xD800]);
+        assert_eq!(encode_utf8(&lone), "a\u{FFFD}".as_bytes());
+    }
+}
@@ -0,0 +1,129 @@
+use super::codec::{Decoder, Encoding};
+use crate::builtins::array_buf::ArrayBuffer;
+use crate::builtins::dataview::DataView;
+use crate::builtins::typed_array::TypedArray;
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value};
+use std::cell::RefCell;
+use yavashark_macro::{data_object, object, props};
+use yavashark_string::YSString;
+
+#[data_object]
+#[derive(Debug, Default)]
+pub struct TextDecoderOptions {
+    pub fatal: Option<bool>,
+    #[prop("ignoreBOM")]
+    pub ignore_bom: Option<bool>,
+}
+
+#[data_object]
+#[derive(Debug, Default)]
+pub struct TextDecodeOptions {
+    pub stream: Option<bool>,
+}
+
+#[object]
+#[derive(Debug)]
+pub struct TextDecoder {
+    #[mutable]
+    decoder: Decoder,
+}
+
+impl TextDecoder {
+    pub fn new(decoder: Decoder, realm: &mut Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableTextDecoder {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .text_decoder
+                        .get(realm)?
+                        .clone(),
+                ),
+                decoder,
+            }),
+        })
+    }
+}
+
+#[props(intrinsic_name = text_decoder, to_string_tag = "TextDecoder")]
+impl TextDecoder {
+    #[constructor]
+    fn construct(
+        label: Option<String>,
+        options: Option<TextDecoderOptions>,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        let label = label.unwrap_or_else(|| "utf-8".to_string());
+
+        let encoding = Encoding::for_label(&label).ok_or_else(|| {
+            Error::range_error(format!(
+                "Failed to construct 'TextDecoder': The encoding label provided ('{label}') is invalid."
+            ))
+        })?;
+
+        let options = options.unwrap_or_default();
+
+        let decoder = Decoder::new(
+            encoding,
+            options.fatal.unwrap_or(false),
+            options.ignore_bom.unwrap_or(false),
+        );
+
+        Ok(Self::new(decoder, realm)?.into_object())
+    }
+
+    #[get("encoding")]
+    fn encoding(&self) -> &'static str {
+        self.inner.borrow().decoder.encoding().name()
+    }
+
+    #[get("fatal")]
+    fn fatal(&self) -> bool {
+        self.inner.borrow().decoder.fatal()
+    }
+
+    #[get("ignoreBOM")]
+    fn ignore_bom(&self) -> bool {
+        self.inner.borrow().decoder.ignore_bom()
+    }
+
+    fn decode(&self, input: Option<Value>, options: Option<TextDecodeOptions>) -> Res<YSString> {
+        let bytes = match input {
+            Some(input) if !input.is_undefined() => buffer_source_bytes(&input)?,
+            _ => Vec::new(),
+        };
+
+        let stream = options.and_then(|o| o.stream).unwrap_or(false);
+
+        let units = self
+            .inner
+            .try_borrow_mut()?
+            .decoder
+            .decode(&bytes, stream)
+            .map_err(|_| Error::ty("The encoded data was not valid."))?;
+
+        Ok(YSString::from_utf16(&units))
+    }
+}
+
+fn buffer_source_bytes(value: &Value) -> Res<Vec<u8>> {
+    if let Value::Object(obj) = value {
+        if let Some(ta) = obj.downcast::<TypedArray>() {
+            return ta.to_bytes();
+        }
+
+        if let Some(buf) = obj.downcast::<ArrayBuffer>() {
+            return Ok(buf.get_slice()?.to_vec());
+        }
+
+        if let Some(view) = obj.downcast::<DataView>() {
+            return view.to_bytes();
+        }
+    }
+
+    Err(Error::ty(
+        "Failed to execute 'decode' on 'TextDecoder': The provided value is not of type '(ArrayBuffer or ArrayBufferView)'",
+    ))
+}
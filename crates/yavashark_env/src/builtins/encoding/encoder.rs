@@ -0,0 +1,83 @@
+use super::codec::{encode_utf8, encode_utf8_into};
+use crate::builtins::typed_array::{Type, TypedArray, create_ta};
+use crate::value::Obj;
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use std::cell::RefCell;
+use yavashark_macro::{object, props};
+
+#[object]
+#[derive(Debug)]
+pub struct TextEncoder {}
+
+impl TextEncoder {
+    pub fn new(realm: &mut Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableTextEncoder {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .text_encoder
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+        })
+    }
+}
+
+#[props(intrinsic_name = text_encoder, to_string_tag = "TextEncoder")]
+impl TextEncoder {
+    #[constructor]
+    fn construct(realm: &mut Realm) -> Res<ObjectHandle> {
+        Ok(Self::new(realm)?.into_object())
+    }
+
+    #[get("encoding")]
+    #[allow(clippy::unused_self)]
+    const fn encoding(&self) -> &'static str {
+        "utf-8"
+    }
+
+    #[allow(clippy::unused_self)]
+    fn encode(&self, input: Option<Value>, realm: &mut Realm) -> Res<ObjectHandle> {
+        let bytes = match input {
+            Some(input) if !input.is_undefined() => encode_utf8(&input.to_string(realm)?),
+            _ => Vec::new(),
+        };
+
+        create_ta(realm, Type::U8, bytes)
+    }
+
+    #[prop("encodeInto")]
+    #[allow(clippy::unused_self)]
+    fn encode_into(
+        &self,
+        source: &Value,
+        destination: &ObjectHandle,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        let source = source.to_string(realm)?;
+
+        let dest = destination
+            .downcast::<TypedArray>()
+            .filter(|ta| ta.ty == Type::U8)
+            .ok_or(Error::ty(
+                "Failed to execute 'encodeInto' on 'TextEncoder': destination is not a Uint8Array",
+            ))?;
+
+        let (read, written) = {
+            let mut buf = dest.buffer.get_slice_mut()?;
+            let slice = dest.apply_offsets_mut(&mut buf)?;
+
+            encode_utf8_into(&source, slice)
+        };
+
+        let res = Object::new(realm);
+
+        res.define_property("read".into(), read.into(), realm)?;
+        res.define_property("written".into(), written.into(), realm)?;
+
+        Ok(res)
+    }
+}
@@ -1,5 +1,6 @@
 mod collator;
 mod date_time_format;
+mod digit_options;
 mod display_names;
 mod duration_format;
 mod get_canonical_locales;
@@ -1,4 +1,4 @@
-use crate::builtins::intl::utils::LocaleMatcherOptions;
+use crate::builtins::intl::utils::{LocaleMatcherOptions, canonicalize_locale_list};
 use crate::value::{IntoValue, Obj};
 use crate::{Error, MutObject, NativeFunction, Object, ObjectHandle, Realm, Res, Value};
 use icu::collator::options::{AlternateHandling, CaseLevel, CollatorOptions, Strength};
@@ -306,29 +306,3 @@ impl Collator {
         Ok(supported)
     }
 }
-
-/// Canonicalize a locale list from a Value (string or array)
-fn canonicalize_locale_list(locales: &Value, realm: &mut Realm) -> Res<Vec<String>> {
-    if locales.is_undefined() {
-        return Ok(Vec::new());
-    }
-
-    let mut result = Vec::new();
-
-    if locales.is_string() {
-        // Single string locale
-        result.push(locales.to_string(realm)?.to_string());
-    } else if let Value::Object(obj) = &locales {
-        let length = obj.get("length", realm)?.to_number(realm)? as usize;
-
-        for i in 0..length {
-            let locale_value = obj.get(i, realm)?;
-            if !locale_value.is_undefined() && !locale_value.is_null() {
-                let locale_str = locale_value.to_string(realm)?;
-                result.push(locale_str.to_string());
-            }
-        }
-    }
-
-    Ok(result)
-}
@@ -1,12 +1,18 @@
 use crate::array::Array;
-use crate::builtins::intl::utils::{HourCycle, LocaleMatcher, LocaleMatcherOptions, Style};
+use crate::builtins::intl::utils::{
+    HourCycle, LocaleMatcher, LocaleMatcherOptions, Style, canonicalize_locale_list,
+};
+use crate::conversion::FromValueOutput;
 use crate::value::{IntoValue, Obj};
 use crate::{Error, MutObject, NativeFunction, Object, ObjectHandle, Realm, Res, Value};
 use icu::datetime::fieldsets;
+use icu::datetime::fieldsets::builder::{DateFields, FieldSetBuilder};
+use icu::datetime::fieldsets::enums::CompositeDateTimeFieldSet;
 use icu::datetime::input::{Date, DateTime, Time};
-use icu::datetime::options::Length;
+use icu::datetime::options::{Length, SubsecondDigits, TimePrecision, YearStyle};
 use icu::datetime::{DateTimeFormatter, DateTimeFormatterPreferences};
-use icu::locale::Locale;
+use icu::locale::preferences::extensions::unicode::keywords::HourCycle as IcuHourCycle;
+use icu::locale::{Locale, locale};
 use std::cell::RefCell;
 use std::sync::Arc;
 use yavashark_macro::{data_object, object, props};
@@ -130,6 +136,7 @@ impl crate::value::IntoValue for FractionalSecondDigits {
     }
 }
 
+#[derive(Default)]
 #[data_object]
 pub struct DateTimeFormatOptions {
     #[prop("localeMatcher")]
@@ -162,6 +169,22 @@ pub struct DateTimeFormatOptions {
     pub time_style: Option<DateTimeStyle>,
 }
 
+/// The components `ToDateTimeOptions` checks for before applying defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Required {
+    Date,
+    Time,
+    Any,
+}
+
+/// The components `ToDateTimeOptions` fills in when none were requested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Defaults {
+    Date,
+    Time,
+    All,
+}
+
 #[derive(Clone, Debug)]
 struct DateTimeFormatConfig {
     locale: Locale,
@@ -184,48 +207,155 @@ struct DateTimeFormatConfig {
 }
 
 impl DateTimeFormatConfig {
-    fn format_date_time(&self, timestamp_ms: f64) -> String {
-        if timestamp_ms.is_nan() || timestamp_ms.is_infinite() {
-            return "Invalid Date".to_string();
+    // https://tc39.es/ecma402/#sec-createdatetimeformat
+    fn new(
+        locales: &Value,
+        options: Option<DateTimeFormatOptions>,
+        required: Required,
+        defaults: Defaults,
+        realm: &mut Realm,
+    ) -> Res<Self> {
+        let opts = options.unwrap_or_default();
+
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .unwrap_or(locale!("en"));
+
+        let has_date_components = opts.weekday.is_some()
+            || opts.year.is_some()
+            || opts.month.is_some()
+            || opts.day.is_some();
+        let has_time_components = opts.hour.is_some()
+            || opts.minute.is_some()
+            || opts.second.is_some()
+            || opts.fractional_second_digits.is_some();
+        let has_style = opts.date_style.is_some() || opts.time_style.is_some();
+
+        if has_style && (has_date_components || has_time_components) {
+            return Err(Error::ty_error(
+                "Can't set option weekday, year, month, day, hour, minute, second, fractionalSecondDigits when dateStyle or timeStyle is used".to_string()
+            ));
+        }
+
+        if required == Required::Date && opts.time_style.is_some() {
+            return Err(Error::ty("timeStyle can't be used to format a date"));
         }
 
-        let timestamp_secs = (timestamp_ms / 1000.0) as i64;
-        let subsec_nanos = ((timestamp_ms % 1000.0) * 1_000_000.0) as u32;
+        if required == Required::Time && opts.date_style.is_some() {
+            return Err(Error::ty("dateStyle can't be used to format a time"));
+        }
+
+        let has_required_components = match required {
+            Required::Date => has_date_components,
+            Required::Time => has_time_components,
+            Required::Any => has_date_components || has_time_components,
+        };
+
+        let need_defaults = !has_style && !has_required_components;
+
+        let time_zone = opts.time_zone.clone().unwrap_or_else(|| {
+            iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_string())
+        });
 
-        let chrono_dt = chrono::DateTime::from_timestamp(timestamp_secs, subsec_nanos)
-            .unwrap_or_else(|| chrono::DateTime::from_timestamp(0, 0).unwrap_or_default());
+        let calendar = opts
+            .calendar
+            .clone()
+            .unwrap_or_else(|| "gregory".to_string());
+
+        let hour_cycle = if let Some(h12) = opts.hour12 {
+            if h12 {
+                Some(HourCycle::H12)
+            } else {
+                Some(HourCycle::H23)
+            }
+        } else {
+            opts.hour_cycle
+        };
 
-        let (year, month, day, hour, minute, second) = if self.time_zone == "UTC" {
-            let dt = chrono_dt.naive_utc();
+        let (year, month, day) = if need_defaults && defaults != Defaults::Time {
             (
-                dt.year(),
-                dt.month(),
-                dt.day(),
-                dt.hour(),
-                dt.minute(),
-                dt.second(),
+                Some(NumberDigit::Numeric),
+                Some(Month::Numeric),
+                Some(NumberDigit::Numeric),
             )
         } else {
-            let dt = chrono_dt.naive_local();
+            (opts.year, opts.month, opts.day)
+        };
+
+        let (hour, minute, second) = if need_defaults && defaults != Defaults::Date {
             (
-                dt.year(),
-                dt.month(),
-                dt.day(),
-                dt.hour(),
-                dt.minute(),
-                dt.second(),
+                Some(NumberDigit::Numeric),
+                Some(NumberDigit::Numeric),
+                Some(NumberDigit::Numeric),
             )
+        } else {
+            (opts.hour, opts.minute, opts.second)
         };
 
+        Ok(Self {
+            locale,
+            calendar,
+            numbering_system: opts.numbering_system,
+            time_zone,
+            hour_cycle,
+            weekday: opts.weekday,
+            era: opts.era,
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            fractional_second_digits: opts.fractional_second_digits,
+            time_zone_name: opts.time_zone_name,
+            date_style: opts.date_style,
+            time_style: opts.time_style,
+        })
+    }
+
+    fn format_date_time(&self, timestamp_ms: f64) -> String {
+        if timestamp_ms.is_nan() || timestamp_ms.is_infinite() {
+            return "Invalid Date".to_string();
+        }
+
+        let Some(chrono_dt) = chrono::DateTime::from_timestamp_millis(timestamp_ms as i64) else {
+            return "Invalid Date".to_string();
+        };
+
+        let dt = if self.time_zone == "UTC" {
+            chrono_dt.naive_utc()
+        } else {
+            chrono_dt.with_timezone(&chrono::Local).naive_local()
+        };
+
+        let (year, month, day, hour, minute, second) = (
+            dt.year(),
+            dt.month(),
+            dt.day(),
+            dt.hour(),
+            dt.minute(),
+            dt.second(),
+        );
+
         let Ok(date) = Date::try_new_iso(year, month as u8, day as u8) else {
             return "Invalid Date".to_string();
         };
-        let Ok(time) = Time::try_new(hour as u8, minute as u8, second as u8, 0) else {
+        let Ok(time) = Time::try_new(hour as u8, minute as u8, second as u8, dt.nanosecond())
+        else {
             return "Invalid Date".to_string();
         };
         let datetime = DateTime { date, time };
 
-        let prefs: DateTimeFormatterPreferences = (&self.locale).into();
+        let mut prefs: DateTimeFormatterPreferences = (&self.locale).into();
+
+        if let Some(hour_cycle) = self.hour_cycle {
+            prefs.hour_cycle = Some(match hour_cycle {
+                HourCycle::H11 => IcuHourCycle::H11,
+                HourCycle::H12 => IcuHourCycle::H12,
+                HourCycle::H23 | HourCycle::H24 => IcuHourCycle::H23,
+            });
+        }
 
         if let (Some(date_style), Some(time_style)) = (&self.date_style, &self.time_style) {
             return match (date_style.to_length(), time_style.to_length()) {
@@ -357,26 +487,10 @@ impl DateTimeFormatConfig {
             };
         }
 
-        let has_date = self.year.is_some()
-            || self.month.is_some()
-            || self.day.is_some()
-            || self.weekday.is_some();
-        let has_time = self.hour.is_some() || self.minute.is_some() || self.second.is_some();
-
-        if has_date && has_time {
-            if let Ok(dtf) =
-                DateTimeFormatter::try_new(prefs, fieldsets::YMD::medium().with_time_hms())
-            {
-                return dtf.format(&datetime).to_string();
-            }
-        } else if has_date {
-            if let Ok(dtf) = DateTimeFormatter::try_new(prefs, fieldsets::YMD::medium()) {
-                return dtf.format(&date).to_string();
-            }
-        } else if has_time {
-            if let Ok(dtf) = DateTimeFormatter::try_new(prefs, fieldsets::T::medium()) {
-                return dtf.format(&time).to_string();
-            }
+        if let Some(field_set) = self.component_field_set()
+            && let Ok(dtf) = DateTimeFormatter::try_new(prefs, field_set)
+        {
+            return dtf.format(&datetime).to_string();
         }
 
         if let Ok(dtf) = DateTimeFormatter::try_new(prefs, fieldsets::YMD::medium()) {
@@ -386,6 +500,65 @@ impl DateTimeFormatConfig {
         self.fallback_format(year, month, day, hour, minute, second)
     }
 
+    /// Picks the ICU field set closest to the requested date-time components.
+    // https://tc39.es/ecma402/#sec-basicformatmatcher
+    fn component_field_set(&self) -> Option<CompositeDateTimeFieldSet> {
+        let date_fields = match (
+            self.year.is_some(),
+            self.month.is_some(),
+            self.day.is_some(),
+            self.weekday.is_some(),
+        ) {
+            (false, false, false, false) => None,
+            (false, false, false, true) => Some(DateFields::E),
+            (false, false, true, false) => Some(DateFields::D),
+            (false, false, true, true) => Some(DateFields::DE),
+            (false, true, false, _) => Some(DateFields::M),
+            (false, true, true, false) => Some(DateFields::MD),
+            (false, true, true, true) => Some(DateFields::MDE),
+            (true, false, false, false) => Some(DateFields::Y),
+            (true, true, false, _) => Some(DateFields::YM),
+            (true, _, _, true) => Some(DateFields::YMDE),
+            (true, _, _, false) => Some(DateFields::YMD),
+        };
+
+        let time_precision = match self.fractional_second_digits {
+            Some(FractionalSecondDigits::One) => {
+                Some(TimePrecision::Subsecond(SubsecondDigits::S1))
+            }
+            Some(FractionalSecondDigits::Two) => {
+                Some(TimePrecision::Subsecond(SubsecondDigits::S2))
+            }
+            Some(FractionalSecondDigits::Three) => {
+                Some(TimePrecision::Subsecond(SubsecondDigits::S3))
+            }
+            None if self.second.is_some() => Some(TimePrecision::Second),
+            None if self.minute.is_some() => Some(TimePrecision::Minute),
+            None if self.hour.is_some() => Some(TimePrecision::Hour),
+            None => None,
+        };
+
+        // numeric months use the short pattern, textual ones the medium or
+        // long one, e.g. "1/2/2024", "Jan 2, 2024" and "January 2, 2024"
+        let length = match self.month {
+            Some(Month::Long) => Length::Long,
+            Some(Month::Short | Month::Narrow) => Length::Medium,
+            _ => Length::Short,
+        };
+
+        let mut builder = FieldSetBuilder::new();
+
+        builder.length = Some(length);
+        builder.date_fields = date_fields;
+        builder.time_precision = time_precision;
+
+        if self.year.is_some() && length == Length::Short {
+            builder.year_style = Some(YearStyle::Full);
+        }
+
+        builder.build_composite_datetime().ok()
+    }
+
     fn fallback_format(
         &self,
         year: i32,
@@ -438,103 +611,14 @@ impl DateTimeFormat {
     // https://tc39.es/ecma402/#sec-intl.datetimeformat
     #[constructor]
     fn construct(
-        locales: Option<String>,
+        locales: &Value,
         options: Option<DateTimeFormatOptions>,
         #[realm] realm: &mut Realm,
     ) -> Res<ObjectHandle> {
-        let opts = options.unwrap_or(DateTimeFormatOptions {
-            locale_matcher: None,
-            calendar: None,
-            numbering_system: None,
-            hour12: None,
-            hour_cycle: None,
-            time_zone: None,
-            format_matcher: None,
-            weekday: None,
-            era: None,
-            year: None,
-            month: None,
-            day: None,
-            hour: None,
-            minute: None,
-            second: None,
-            fractional_second_digits: None,
-            time_zone_name: None,
-            date_style: None,
-            time_style: None,
-        });
+        let config =
+            DateTimeFormatConfig::new(locales, options, Required::Any, Defaults::Date, realm)?;
 
-        let locale_str = locales.unwrap_or_else(|| "en".to_string());
-        let locale: Locale = locale_str
-            .parse()
-            .unwrap_or_else(|_| "en".parse().expect("en is a valid locale"));
-
-        let has_date_components = opts.weekday.is_some()
-            || opts.year.is_some()
-            || opts.month.is_some()
-            || opts.day.is_some();
-        let has_time_components = opts.hour.is_some()
-            || opts.minute.is_some()
-            || opts.second.is_some()
-            || opts.fractional_second_digits.is_some();
-        let has_style = opts.date_style.is_some() || opts.time_style.is_some();
-
-        if has_style && (has_date_components || has_time_components) {
-            return Err(Error::ty_error(
-                "Can't set option weekday, year, month, day, hour, minute, second, fractionalSecondDigits when dateStyle or timeStyle is used".to_string()
-            ));
-        }
-
-        let time_zone = opts.time_zone.clone().unwrap_or_else(|| {
-            iana_time_zone::get_timezone().unwrap_or_else(|_| "UTC".to_string())
-        });
-
-        let calendar = opts
-            .calendar
-            .clone()
-            .unwrap_or_else(|| "gregory".to_string());
-
-        let hour_cycle = if let Some(h12) = opts.hour12 {
-            if h12 {
-                Some(HourCycle::H12)
-            } else {
-                Some(HourCycle::H23)
-            }
-        } else {
-            opts.hour_cycle
-        };
-
-        let (year, month, day) = if !has_style && !has_date_components && !has_time_components {
-            (
-                Some(NumberDigit::Numeric),
-                Some(Month::Numeric),
-                Some(NumberDigit::Numeric),
-            )
-        } else {
-            (opts.year, opts.month, opts.day)
-        };
-
-        let config = Arc::new(DateTimeFormatConfig {
-            locale,
-            calendar,
-            numbering_system: opts.numbering_system.clone(),
-            time_zone,
-            hour_cycle,
-            weekday: opts.weekday,
-            era: opts.era,
-            year,
-            month,
-            day,
-            hour: opts.hour,
-            minute: opts.minute,
-            second: opts.second,
-            fractional_second_digits: opts.fractional_second_digits,
-            time_zone_name: opts.time_zone_name,
-            date_style: opts.date_style,
-            time_style: opts.time_style,
-        });
-
-        Ok(Self::create(realm, config)?.into_object())
+        Ok(Self::create(realm, Arc::new(config))?.into_object())
     }
 
     // https://tc39.es/ecma402/#sec-intl.datetimeformat.prototype.format
@@ -784,33 +868,30 @@ impl DateTimeFormat {
     }
 }
 
+/// Formats a time value the way the `Date.prototype.toLocale*String` methods
+/// do, with `required` and `defaults` as passed to `CreateDateTimeFormat`.
+// https://tc39.es/ecma402/#sup-date.prototype.tolocalestring
+pub fn format_date_time(
+    time: f64,
+    locales: &Value,
+    options: Value,
+    required: Required,
+    defaults: Defaults,
+    realm: &mut Realm,
+) -> Res<String> {
+    if !time.is_finite() {
+        return Ok("Invalid Date".to_string());
+    }
+
+    let options = Option::<DateTimeFormatOptions>::from_value_out(options, realm)?;
+    let config = DateTimeFormatConfig::new(locales, options, required, defaults, realm)?;
+
+    Ok(config.format_date_time(time))
+}
+
 fn get_current_time_ms() -> f64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_millis() as f64)
         .unwrap_or(0.0)
 }
-
-fn canonicalize_locale_list(locales: &Value, realm: &mut Realm) -> Res<Vec<String>> {
-    if locales.is_undefined() {
-        return Ok(Vec::new());
-    }
-
-    let mut result = Vec::new();
-
-    if locales.is_string() {
-        result.push(locales.to_string(realm)?.to_string());
-    } else if let Value::Object(obj) = &locales {
-        let length = obj.get("length", realm)?.to_number(realm)? as usize;
-
-        for i in 0..length {
-            let locale_value = obj.get(i, realm)?;
-            if !locale_value.is_undefined() && !locale_value.is_null() {
-                let locale_str = locale_value.to_string(realm)?;
-                result.push(locale_str.to_string());
-            }
-        }
-    }
-
-    Ok(result)
-}
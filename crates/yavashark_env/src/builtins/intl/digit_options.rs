@@ -0,0 +1,480 @@
+use crate::value::IntoValue;
+use crate::{Error, ObjectHandle, Realm, Res};
+use fixed_decimal::{
+    Decimal, FloatPrecision, RoundingIncrement, SignedRoundingMode, UnsignedRoundingMode,
+};
+use yavashark_macro::data_object;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
+pub enum RoundingMode {
+    Ceil,
+    Floor,
+    Expand,
+    Trunc,
+    HalfCeil,
+    HalfFloor,
+    #[default]
+    HalfExpand,
+    HalfTrunc,
+    HalfEven,
+}
+
+impl From<RoundingMode> for SignedRoundingMode {
+    fn from(value: RoundingMode) -> Self {
+        match value {
+            RoundingMode::Ceil => Self::Ceil,
+            RoundingMode::Floor => Self::Floor,
+            RoundingMode::Expand => Self::Unsigned(UnsignedRoundingMode::Expand),
+            RoundingMode::Trunc => Self::Unsigned(UnsignedRoundingMode::Trunc),
+            RoundingMode::HalfCeil => Self::HalfCeil,
+            RoundingMode::HalfFloor => Self::HalfFloor,
+            RoundingMode::HalfExpand => Self::Unsigned(UnsignedRoundingMode::HalfExpand),
+            RoundingMode::HalfTrunc => Self::Unsigned(UnsignedRoundingMode::HalfTrunc),
+            RoundingMode::HalfEven => Self::Unsigned(UnsignedRoundingMode::HalfEven),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
+pub enum RoundingPriority {
+    #[default]
+    Auto,
+    MorePrecision,
+    LessPrecision,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
+pub enum TrailingZeroDisplay {
+    #[default]
+    Auto,
+    StripIfInteger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingType {
+    FractionDigits,
+    SignificantDigits,
+    MorePrecision,
+    LessPrecision,
+}
+
+const ROUNDING_INCREMENTS: [u16; 15] = [
+    1, 2, 5, 10, 20, 25, 50, 100, 200, 250, 500, 1000, 2000, 2500, 5000,
+];
+
+/// The digit options of an `Intl.NumberFormat` or `Intl.PluralRules` options
+/// bag, before they are resolved.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DigitOptionsInput {
+    pub minimum_integer_digits: Option<u8>,
+    pub minimum_fraction_digits: Option<u8>,
+    pub maximum_fraction_digits: Option<u8>,
+    pub minimum_significant_digits: Option<u8>,
+    pub maximum_significant_digits: Option<u8>,
+    pub rounding_mode: Option<RoundingMode>,
+    pub rounding_priority: Option<RoundingPriority>,
+    pub rounding_increment: Option<u16>,
+    pub trailing_zero_display: Option<TrailingZeroDisplay>,
+}
+
+/// The resolved digit options shared by `Intl.NumberFormat` and
+/// `Intl.PluralRules`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigitOptions {
+    pub minimum_integer_digits: u8,
+    pub minimum_fraction_digits: Option<u8>,
+    pub maximum_fraction_digits: Option<u8>,
+    pub minimum_significant_digits: Option<u8>,
+    pub maximum_significant_digits: Option<u8>,
+    pub rounding_type: RoundingType,
+    /// The priority reported by `resolvedOptions`, which is `morePrecision`
+    /// for compact notation without explicit digits.
+    pub rounding_priority: RoundingPriority,
+    pub rounding_mode: RoundingMode,
+    pub rounding_increment: u16,
+    pub trailing_zero_display: TrailingZeroDisplay,
+}
+
+impl Default for DigitOptions {
+    fn default() -> Self {
+        Self {
+            minimum_integer_digits: 1,
+            minimum_fraction_digits: Some(0),
+            maximum_fraction_digits: Some(3),
+            minimum_significant_digits: None,
+            maximum_significant_digits: None,
+            rounding_type: RoundingType::FractionDigits,
+            rounding_priority: RoundingPriority::Auto,
+            rounding_mode: RoundingMode::HalfExpand,
+            rounding_increment: 1,
+            trailing_zero_display: TrailingZeroDisplay::Auto,
+        }
+    }
+}
+
+impl DigitOptions {
+    // https://tc39.es/ecma402/#sec-setnfdigitoptions
+    #[allow(clippy::similar_names)]
+    pub fn resolve(
+        input: DigitOptionsInput,
+        mnfd_default: u8,
+        mxfd_default: u8,
+        compact: bool,
+    ) -> Res<Self> {
+        let minimum_integer_digits = input.minimum_integer_digits.unwrap_or(1);
+
+        if !(1..=21).contains(&minimum_integer_digits) {
+            return Err(Error::range(
+                "minimumIntegerDigits must be between 1 and 21",
+            ));
+        }
+
+        let rounding_increment = input.rounding_increment.unwrap_or(1);
+
+        if !ROUNDING_INCREMENTS.contains(&rounding_increment) {
+            return Err(Error::range_error(format!(
+                "Invalid rounding increment: {rounding_increment}"
+            )));
+        }
+
+        let rounding_mode = input.rounding_mode.unwrap_or_default();
+        let rounding_priority = input.rounding_priority.unwrap_or_default();
+        let trailing_zero_display = input.trailing_zero_display.unwrap_or_default();
+
+        let mxfd_default = if rounding_increment == 1 {
+            mxfd_default
+        } else {
+            mnfd_default
+        };
+
+        let has_sd = input.minimum_significant_digits.is_some()
+            || input.maximum_significant_digits.is_some();
+        let has_fd =
+            input.minimum_fraction_digits.is_some() || input.maximum_fraction_digits.is_some();
+
+        let mut need_sd = true;
+        let mut need_fd = true;
+
+        if rounding_priority == RoundingPriority::Auto {
+            need_sd = has_sd;
+
+            if need_sd || (!has_fd && compact) {
+                need_fd = false;
+            }
+        }
+
+        let (mut mnsd, mut mxsd) = (None, None);
+
+        if need_sd {
+            let min = input.minimum_significant_digits.unwrap_or(1);
+            let max = input.maximum_significant_digits.unwrap_or(21);
+
+            if !(1..=21).contains(&min) || !(min..=21).contains(&max) {
+                return Err(Error::range(
+                    "significantDigits must be between 1 and 21, and min <= max",
+                ));
+            }
+
+            mnsd = Some(min);
+            mxsd = Some(max);
+        }
+
+        let (mut mnfd, mut mxfd) = (None, None);
+
+        if need_fd {
+            let (min, max) = match (input.minimum_fraction_digits, input.maximum_fraction_digits) {
+                (None, None) => (mnfd_default, mxfd_default),
+                (None, Some(max)) => (mnfd_default.min(max), max),
+                (Some(min), None) => (min, mxfd_default.max(min)),
+                (Some(min), Some(max)) => (min, max),
+            };
+
+            if min > 100 || max > 100 {
+                return Err(Error::range("fractionDigits must be between 0 and 100"));
+            }
+
+            if min > max {
+                return Err(Error::range(
+                    "minimumFractionDigits cannot be greater than maximumFractionDigits",
+                ));
+            }
+
+            mnfd = Some(min);
+            mxfd = Some(max);
+        }
+
+        let (rounding_type, rounding_priority) = if !need_sd && !need_fd {
+            mnfd = Some(0);
+            mxfd = Some(0);
+            mnsd = Some(1);
+            mxsd = Some(2);
+
+            (RoundingType::MorePrecision, RoundingPriority::MorePrecision)
+        } else {
+            let ty = match rounding_priority {
+                RoundingPriority::Auto if need_sd => RoundingType::SignificantDigits,
+                RoundingPriority::Auto => RoundingType::FractionDigits,
+                RoundingPriority::MorePrecision => RoundingType::MorePrecision,
+                RoundingPriority::LessPrecision => RoundingType::LessPrecision,
+            };
+
+            (ty, rounding_priority)
+        };
+
+        if rounding_increment != 1 {
+            if rounding_type != RoundingType::FractionDigits {
+                return Err(Error::ty(
+                    "roundingIncrement requires fraction digit rounding",
+                ));
+            }
+
+            if mxfd != mnfd {
+                return Err(Error::range(
+                    "roundingIncrement requires maximumFractionDigits to equal minimumFractionDigits",
+                ));
+            }
+        }
+
+        Ok(Self {
+            minimum_integer_digits,
+            minimum_fraction_digits: mnfd,
+            maximum_fraction_digits: mxfd,
+            minimum_significant_digits: mnsd,
+            maximum_significant_digits: mxsd,
+            rounding_type,
+            rounding_priority,
+            rounding_mode,
+            rounding_increment,
+            trailing_zero_display,
+        })
+    }
+
+    /// Rounds and pads a finite `value` according to these options.
+    // https://tc39.es/ecma402/#sec-formatnumberstring
+    #[must_use]
+    pub fn round(&self, value: f64) -> Decimal {
+        let decimal =
+            Decimal::try_from_f64(value, FloatPrecision::RoundTrip).unwrap_or_else(|_| 0.into());
+
+        let mut result = match self.rounding_type {
+            RoundingType::FractionDigits => self.round_fraction(decimal).0,
+            RoundingType::SignificantDigits => self.round_significant(decimal).0,
+            RoundingType::MorePrecision | RoundingType::LessPrecision => {
+                let (significant, s_magnitude) = self.round_significant(decimal.clone());
+                let (fraction, f_magnitude) = self.round_fraction(decimal);
+
+                let more_precise = s_magnitude <= f_magnitude;
+
+                if more_precise == (self.rounding_type == RoundingType::MorePrecision) {
+                    significant
+                } else {
+                    fraction
+                }
+            }
+        };
+
+        if self.trailing_zero_display == TrailingZeroDisplay::StripIfInteger {
+            result.trim_end_if_integer();
+        }
+
+        result.pad_start(i16::from(self.minimum_integer_digits));
+
+        result
+    }
+
+    /// Rounds to the fraction digits, returning the result and the rounding
+    /// magnitude.
+    fn round_fraction(&self, mut decimal: Decimal) -> (Decimal, i16) {
+        let min = i16::from(self.minimum_fraction_digits.unwrap_or(0));
+        let max = i16::from(self.maximum_fraction_digits.unwrap_or(0));
+
+        let mut increment = self.rounding_increment;
+        let mut position = -max;
+
+        while increment.is_multiple_of(10) {
+            increment /= 10;
+            position += 1;
+        }
+
+        let increment = match increment {
+            2 => RoundingIncrement::MultiplesOf2,
+            5 => RoundingIncrement::MultiplesOf5,
+            25 => RoundingIncrement::MultiplesOf25,
+            _ => RoundingIncrement::MultiplesOf1,
+        };
+
+        decimal.round_with_mode_and_increment(position, self.rounding_mode.into(), increment);
+        decimal.trim_end();
+        decimal.pad_end(-min);
+
+        (decimal, -max)
+    }
+
+    /// Rounds to the significant digits, returning the result and the
+    /// rounding magnitude.
+    fn round_significant(&self, mut decimal: Decimal) -> (Decimal, i16) {
+        let min = i16::from(self.minimum_significant_digits.unwrap_or(1));
+        let max = i16::from(self.maximum_significant_digits.unwrap_or(21));
+
+        let position = decimal.absolute.nonzero_magnitude_start() - max + 1;
+
+        decimal.round_with_mode(position, self.rounding_mode.into());
+        decimal.trim_end();
+
+        let start = decimal.absolute.nonzero_magnitude_start();
+        decimal.pad_end(start - min + 1);
+
+        (decimal, start - max + 1)
+    }
+
+    /// Defines `minimumIntegerDigits` through `maximumSignificantDigits` for
+    /// `resolvedOptions`.
+    pub fn define_digits(&self, options: &ObjectHandle, realm: &mut Realm) -> Res {
+        options.define_property(
+            "minimumIntegerDigits".into(),
+            self.minimum_integer_digits.into(),
+            realm,
+        )?;
+
+        let digits = [
+            ("minimumFractionDigits", self.minimum_fraction_digits),
+            ("maximumFractionDigits", self.maximum_fraction_digits),
+            ("minimumSignificantDigits", self.minimum_significant_digits),
+            ("maximumSignificantDigits", self.maximum_significant_digits),
+        ];
+
+        for (name, value) in digits {
+            if let Some(value) = value {
+                options.define_property(name.into(), value.into(), realm)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Defines `roundingIncrement` through `trailingZeroDisplay` for
+    /// `resolvedOptions`.
+    pub fn define_rounding(&self, options: &ObjectHandle, realm: &mut Realm) -> Res {
+        options.define_property(
+            "roundingIncrement".into(),
+            self.rounding_increment.into(),
+            realm,
+        )?;
+        options.define_property(
+            "roundingMode".into(),
+            self.rounding_mode.into_value(),
+            realm,
+        )?;
+        options.define_property(
+            "roundingPriority".into(),
+            self.rounding_priority.into_value(),
+            realm,
+        )?;
+        options.define_property(
+            "trailingZeroDisplay".into(),
+            self.trailing_zero_display.into_value(),
+            realm,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(input: DigitOptionsInput, value: f64) -> String {
+        DigitOptions::resolve(input, 0, 3, false)
+            .expect("valid options")
+            .round(value)
+            .to_string()
+    }
+
+    #[test]
+    fn fraction_digits() {
+        let default = DigitOptionsInput::default();
+
+        assert_eq!(round(default, 1.23456), "1.235");
+        assert_eq!(round(default, 1.5), "1.5");
+        assert_eq!(round(default, -0.0004), "-0");
+
+        let fixed = DigitOptionsInput {
+            minimum_fraction_digits: Some(2),
+            minimum_integer_digits: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(round(fixed, 1.0), "001.00");
+    }
+
+    #[test]
+    fn significant_digits() {
+        let input = DigitOptionsInput {
+            minimum_significant_digits: Some(3),
+            maximum_significant_digits: Some(4),
+            ..Default::default()
+        };
+
+        assert_eq!(round(input, 123_456.0), "123500");
+        assert_eq!(round(input, 1.0), "1.00");
+        assert_eq!(round(input, 0.0), "0.00");
+        assert_eq!(round(input, 9.9999), "10.0");
+    }
+
+    #[test]
+    fn rounding_priority_and_increment() {
+        let more = DigitOptionsInput {
+            maximum_fraction_digits: Some(2),
+            maximum_significant_digits: Some(2),
+            rounding_priority: Some(RoundingPriority::MorePrecision),
+            ..Default::default()
+        };
+
+        assert_eq!(round(more, 1.234), "1.23");
+
+        let less = DigitOptionsInput {
+            rounding_priority: Some(RoundingPriority::LessPrecision),
+            ..more
+        };
+
+        assert_eq!(round(less, 1.234), "1.2");
+
+        let increment = DigitOptionsInput {
+            maximum_fraction_digits: Some(2),
+            minimum_fraction_digits: Some(2),
+            rounding_increment: Some(25),
+            trailing_zero_display: Some(TrailingZeroDisplay::StripIfInteger),
+            ..Default::default()
+        };
+
+        assert_eq!(round(increment, 1.3), "1.25");
+        assert_eq!(round(increment, 0.9), "1");
+    }
+
+    #[test]
+    fn invalid_options() {
+        let increment = DigitOptionsInput {
+            rounding_increment: Some(3),
+            ..Default::default()
+        };
+        assert!(DigitOptions::resolve(increment, 0, 3, false).is_err());
+
+        let significant = DigitOptionsInput {
+            minimum_significant_digits: Some(5),
+            maximum_significant_digits: Some(2),
+            ..Default::default()
+        };
+        assert!(DigitOptions::resolve(significant, 0, 3, false).is_err());
+
+        let mismatched = DigitOptionsInput {
+            rounding_increment: Some(5),
+            maximum_fraction_digits: Some(2),
+            ..Default::default()
+        };
+        assert!(DigitOptions::resolve(mismatched, 0, 3, false).is_err());
+    }
+}
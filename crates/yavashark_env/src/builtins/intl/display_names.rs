@@ -1,10 +1,24 @@
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, Style};
-use crate::value::Obj;
-use crate::{MutObject, Object, ObjectHandle, Realm, Res};
+use crate::builtins::intl::utils::{
+    LocaleMatcher, LocaleMatcherOptions, Style, canonicalize_locale_list,
+};
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use icu::experimental::dimension::provider::currency::displayname::CurrencyDisplaynameV1;
+use icu::experimental::displaynames::multi::{
+    LanguageDisplayNames, LocaleDisplayNamesFormatter, RegionDisplayNames, ScriptDisplayNames,
+};
+use icu::experimental::displaynames::{
+    DisplayNamesOptions as IcuOptions, LanguageDisplay as IcuLanguageDisplay, Style as IcuStyle,
+};
+use icu::experimental::provider::Baked;
+use icu::locale::subtags::{Region, Script};
+use icu::locale::{LanguageIdentifier, Locale, locale};
+use icu_provider::{DataIdentifierBorrowed, DataMarkerAttributes, DataProvider, DataRequest};
 use std::cell::RefCell;
 use yavashark_macro::{data_object, object, props};
 
-#[data_object]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Type {
     Language,
     Region,
@@ -14,18 +28,23 @@ pub enum Type {
     DateTimeField,
 }
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum LanguageDisplay {
+    #[default]
     Dialect,
     Standard,
 }
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Fallback {
+    #[default]
     Code,
     None,
 }
 
+#[derive(Default)]
 #[data_object]
 pub struct DisplayNamesOptions {
     #[prop("localeMatcher")]
@@ -38,12 +57,124 @@ pub struct DisplayNamesOptions {
     pub fallback: Option<Fallback>,
 }
 
+const DATE_TIME_FIELDS: [&str; 12] = [
+    "era",
+    "year",
+    "quarter",
+    "month",
+    "weekOfYear",
+    "weekday",
+    "day",
+    "dayPeriod",
+    "hour",
+    "minute",
+    "second",
+    "timeZoneName",
+];
+
+/// English calendar names from CLDR, ICU4X doesn't ship calendar display
+/// names yet.
+fn calendar_name(calendar: &str) -> Option<&'static str> {
+    Some(match calendar {
+        "buddhist" => "Buddhist Calendar",
+        "chinese" => "Chinese Calendar",
+        "coptic" => "Coptic Calendar",
+        "dangi" => "Dangi Calendar",
+        "ethioaa" => "Ethiopic Amete Alem Calendar",
+        "ethiopic" => "Ethiopic Calendar",
+        "gregory" => "Gregorian Calendar",
+        "hebrew" => "Hebrew Calendar",
+        "indian" => "Indian National Calendar",
+        "islamic" => "Hijri Calendar",
+        "islamic-civil" => "Hijri Calendar (tabular, civil epoch)",
+        "islamic-rgsa" => "Hijri Calendar (Saudi Arabia, sighting)",
+        "islamic-tbla" => "Hijri Calendar (tabular, astronomical epoch)",
+        "islamic-umalqura" => "Hijri Calendar (Umm al-Qura)",
+        "iso8601" => "ISO-8601 Calendar",
+        "japanese" => "Japanese Calendar",
+        "persian" => "Persian Calendar",
+        "roc" => "Minguo Calendar",
+        _ => return None,
+    })
+}
+
+/// English date-time field names from CLDR, ICU4X doesn't ship field
+/// display names yet.
+fn date_time_field_name(field: &str, style: Style) -> Option<&'static str> {
+    Some(match (field, style) {
+        ("era", _) => "era",
+        ("year", Style::Long) => "year",
+        ("year", Style::Short) => "yr.",
+        ("year", Style::Narrow) => "yr",
+        ("quarter", Style::Long) => "quarter",
+        ("quarter", Style::Short) => "qtr.",
+        ("quarter", Style::Narrow) => "qtr",
+        ("month", Style::Long) => "month",
+        ("month", Style::Short) => "mo.",
+        ("month", Style::Narrow) => "mo",
+        ("weekOfYear", Style::Long) => "week",
+        ("weekOfYear", Style::Short) => "wk.",
+        ("weekOfYear", Style::Narrow) => "wk",
+        ("weekday", Style::Long) => "day of the week",
+        ("weekday", _) => "day of wk.",
+        ("day", _) => "day",
+        ("dayPeriod", _) => "AM/PM",
+        ("hour", Style::Long) => "hour",
+        ("hour", Style::Short) => "hr.",
+        ("hour", Style::Narrow) => "hr",
+        ("minute", Style::Long) => "minute",
+        ("minute", Style::Short) => "min.",
+        ("minute", Style::Narrow) => "min",
+        ("second", Style::Long) => "second",
+        ("second", Style::Short) => "sec.",
+        ("second", Style::Narrow) => "sec",
+        ("timeZoneName", Style::Long) => "time zone",
+        ("timeZoneName", _) => "zone",
+        _ => return None,
+    })
+}
+
+/// Checks the `type` production of a Unicode locale extension value, which
+/// calendar identifiers follow.
+fn is_unicode_type(code: &str) -> bool {
+    code.split('-').all(|part| {
+        (3..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+fn currency_name(locale: &Locale, currency: &str) -> Option<String> {
+    let attributes = DataMarkerAttributes::try_from_str(currency).ok()?;
+    let locale = locale.into();
+
+    let response: icu_provider::DataResponse<CurrencyDisplaynameV1> = Baked
+        .load(DataRequest {
+            id: DataIdentifierBorrowed::for_marker_attributes_and_locale(attributes, &locale),
+            ..Default::default()
+        })
+        .ok()?;
+
+    Some(response.payload.get().display_name.to_string())
+}
+
 #[object]
 #[derive(Debug)]
-pub struct DisplayNames {}
+pub struct DisplayNames {
+    locale: Locale,
+    style: Style,
+    ty: Type,
+    fallback: Fallback,
+    language_display: LanguageDisplay,
+}
 
 impl DisplayNames {
-    pub fn new(realm: &mut Realm) -> Res<Self> {
+    pub fn new(
+        locale: Locale,
+        style: Style,
+        ty: Type,
+        fallback: Fallback,
+        language_display: LanguageDisplay,
+        realm: &mut Realm,
+    ) -> Res<Self> {
         Ok(Self {
             inner: RefCell::new(MutableDisplayNames {
                 object: MutObject::with_proto(
@@ -55,35 +186,233 @@ impl DisplayNames {
                         .clone(),
                 ),
             }),
+            locale,
+            style,
+            ty,
+            fallback,
+            language_display,
+        })
+    }
+
+    fn icu_options(&self) -> IcuOptions {
+        let mut options = IcuOptions::default();
+
+        options.style = Some(match self.style {
+            Style::Narrow => IcuStyle::Narrow,
+            Style::Short => IcuStyle::Short,
+            Style::Long => IcuStyle::Long,
+        });
+
+        options.language_display = match self.language_display {
+            LanguageDisplay::Dialect => IcuLanguageDisplay::Dialect,
+            LanguageDisplay::Standard => IcuLanguageDisplay::Standard,
+        };
+
+        options
+    }
+
+    /// Validates and canonicalizes `code`, returning it together with its
+    /// display name if there is one.
+    // https://tc39.es/ecma402/#sec-canonicalcodefordisplaynames
+    fn lookup(&self, code: &str) -> Res<(String, Option<String>)> {
+        let prefs = (&self.locale).into();
+        let options = self.icu_options();
+        let is_english = self.locale.id.language.as_str() == "en";
+
+        let load_error =
+            |e: icu_provider::DataError| Error::range_error(format!("Failed to load data: {e}"));
+
+        Ok(match self.ty {
+            Type::Language => {
+                let id = (!code.contains('_'))
+                    .then(|| LanguageIdentifier::try_from_str(code).ok())
+                    .flatten()
+                    .ok_or_else(|| Error::range_error(format!("Invalid language code: {code}")))?;
+
+                let known = LanguageDisplayNames::try_new(prefs, options)
+                    .map_err(load_error)?
+                    .of(id.language)
+                    .is_some();
+
+                let name = known
+                    .then(|| {
+                        LocaleDisplayNamesFormatter::try_new(prefs, options)
+                            .map(|formatter| formatter.of(&Locale::from(id.clone())).into_owned())
+                    })
+                    .transpose()
+                    .map_err(load_error)?;
+
+                (id.to_string(), name)
+            }
+            Type::Region => {
+                let region = Region::try_from_str(code)
+                    .map_err(|_| Error::range_error(format!("Invalid region code: {code}")))?;
+
+                let name = RegionDisplayNames::try_new(prefs, options)
+                    .map_err(load_error)?
+                    .of(region)
+                    .map(ToString::to_string);
+
+                (region.to_string(), name)
+            }
+            Type::Script => {
+                let script = Script::try_from_str(code)
+                    .map_err(|_| Error::range_error(format!("Invalid script code: {code}")))?;
+
+                let name = ScriptDisplayNames::try_new(prefs, options)
+                    .map_err(load_error)?
+                    .of(script)
+                    .map(ToString::to_string);
+
+                (script.to_string(), name)
+            }
+            Type::Currency => {
+                if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Err(Error::range_error(format!("Invalid currency code: {code}")));
+                }
+
+                let code = code.to_ascii_uppercase();
+                let name = currency_name(&self.locale, &code);
+
+                (code, name)
+            }
+            Type::Calendar => {
+                if !is_unicode_type(code) {
+                    return Err(Error::range_error(format!("Invalid calendar code: {code}")));
+                }
+
+                let code = code.to_ascii_lowercase();
+                let name = calendar_name(&code)
+                    .filter(|_| is_english)
+                    .map(ToString::to_string);
+
+                (code, name)
+            }
+            Type::DateTimeField => {
+                if !DATE_TIME_FIELDS.contains(&code) {
+                    return Err(Error::range_error(format!(
+                        "Invalid date-time field: {code}"
+                    )));
+                }
+
+                let name = date_time_field_name(code, self.style)
+                    .filter(|_| is_english)
+                    .map(ToString::to_string);
+
+                (code.to_string(), name)
+            }
         })
     }
 }
 
+// https://tc39.es/ecma402/#sec-intl-displaynames-constructor
 #[props(intrinsic_name = intl_display_names, to_string_tag = "Intl.DisplayNames")]
 impl DisplayNames {
     #[constructor]
     fn construct(
-        _locales: String,
-        _options: DisplayNamesOptions,
+        locales: &Value,
+        options: Option<DisplayNamesOptions>,
         realm: &mut Realm,
     ) -> Res<ObjectHandle> {
-        Ok(Self::new(realm)?.into_object())
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .unwrap_or(locale!("en"));
+
+        let opts = options.unwrap_or_default();
+
+        let ty = opts
+            .type_
+            .ok_or(Error::ty("Intl.DisplayNames requires a type option"))?;
+
+        Ok(Self::new(
+            locale,
+            opts.style.unwrap_or(Style::Long),
+            ty,
+            opts.fallback.unwrap_or_default(),
+            opts.language_display.unwrap_or_default(),
+            realm,
+        )?
+        .into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.DisplayNames.supportedLocalesOf
     #[prop("supportedLocalesOf")]
     fn supported_locales_of(
-        _locales: String,
+        locales: &Value,
         _options: Option<LocaleMatcherOptions>,
-    ) -> Vec<String> {
-        Vec::new()
+        realm: &mut Realm,
+    ) -> Res<Vec<String>> {
+        Ok(canonicalize_locale_list(locales, realm)?
+            .iter()
+            .filter_map(|locale| locale.parse::<Locale>().ok())
+            .filter(|locale| {
+                LanguageDisplayNames::try_new(locale.into(), IcuOptions::default()).is_ok()
+            })
+            .map(|locale| locale.to_string())
+            .collect())
     }
 
-    fn of(&self, _code: String) -> String {
-        String::new()
+    // https://tc39.es/ecma402/#sec-Intl.DisplayNames.prototype.of
+    fn of(&self, code: &Value, realm: &mut Realm) -> Res<Value> {
+        let code = code.to_string(realm)?;
+        let (code, name) = self.lookup(&code.as_str_lossy())?;
+
+        Ok(match (name, self.fallback) {
+            (Some(name), _) => name.into(),
+            (None, Fallback::Code) => code.into(),
+            (None, Fallback::None) => Value::Undefined,
+        })
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.DisplayNames.prototype.resolvedOptions
     #[prop("resolvedOptions")]
-    fn resolved_options(&self, realm: &Realm) -> ObjectHandle {
-        Object::new(realm)
+    fn resolved_options(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let options = Object::new(realm);
+
+        options.define_property("locale".into(), self.locale.to_string().into(), realm)?;
+        options.define_property("style".into(), self.style.into_value(), realm)?;
+        options.define_property("type".into(), self.ty.into_value(), realm)?;
+        options.define_property("fallback".into(), self.fallback.into_value(), realm)?;
+
+        if self.ty == Type::Language {
+            options.define_property(
+                "languageDisplay".into(),
+                self.language_display.into_value(),
+                realm,
+            )?;
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn currency_names() {
+        assert_eq!(
+            currency_name(&locale!("en"), "USD").as_deref(),
+            Some("US Dollar")
+        );
+        assert_eq!(
+            currency_name(&locale!("de"), "EUR").as_deref(),
+            Some("Euro")
+        );
+        assert_eq!(currency_name(&locale!("en"), "XYZ"), None);
+    }
+
+    #[test]
+    fn codes() {
+        assert!(is_unicode_type("islamic-civil"));
+        assert!(!is_unicode_type("ab"));
+        assert!(!is_unicode_type("gregory-"));
+        assert_eq!(calendar_name("gregory"), Some("Gregorian Calendar"));
+        assert_eq!(
+            date_time_field_name("weekOfYear", Style::Short),
+            Some("wk.")
+        );
     }
 }
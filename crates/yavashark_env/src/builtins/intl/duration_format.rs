@@ -1,24 +1,42 @@
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions};
-use crate::value::Obj;
-use crate::{MutObject, Object, ObjectHandle, Realm, Res};
+use crate::array::Array;
+use crate::builtins::intl::number_format::icu_part_to_js_type;
+use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, canonicalize_locale_list};
+use crate::conversion::FromValueOutput;
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use icu::experimental::duration::options::{
+    BaseStyle, DayStyle, DurationFormatterOptions, FieldDisplay, FractionalDigits, HourStyle,
+    MicroSecondStyle, MilliSecondStyle, MinuteStyle, MonthStyle, NanoSecondStyle, SecondStyle,
+    WeekStyle, YearStyle,
+};
+use icu::experimental::duration::{
+    Duration as IcuDuration, DurationFormatter, DurationSign, ValidatedDurationFormatterOptions,
+};
+use icu::locale::{Locale, locale};
 use std::cell::RefCell;
+use std::fmt;
+use writeable::{Part, PartsWrite, Writeable};
 use yavashark_macro::{data_object, object, props};
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Style {
     Long,
+    #[default]
     Short,
     Narrow,
     Digital,
 }
 
-#[data_object]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Display {
     Auto,
     Always,
 }
 
-#[data_object]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum UnitStyle {
     Long,
     Short,
@@ -28,11 +46,11 @@ pub enum UnitStyle {
     TwoDigit,
 }
 
+#[derive(Default)]
 #[data_object]
 pub struct DurationFormatOptions {
     #[prop("localeMatcher")]
     pub locale_matcher: Option<LocaleMatcher>,
-    pub calendar: Option<String>,
     #[prop("numberingSystem")]
     pub numbering_system: Option<String>,
     pub style: Option<Style>,
@@ -70,12 +88,512 @@ pub struct DurationFormatOptions {
     pub fractional_digits: Option<u8>,
 }
 
+impl DurationFormatOptions {
+    const fn unit(&self, unit: Unit) -> (Option<UnitStyle>, Option<Display>) {
+        match unit {
+            Unit::Years => (self.years, self.years_display),
+            Unit::Months => (self.months, self.months_display),
+            Unit::Weeks => (self.weeks, self.weeks_display),
+            Unit::Days => (self.days, self.days_display),
+            Unit::Hours => (self.hours, self.hours_display),
+            Unit::Minutes => (self.minutes, self.minutes_display),
+            Unit::Seconds => (self.seconds, self.seconds_display),
+            Unit::Milliseconds => (self.milliseconds, self.milliseconds_display),
+            Unit::Microseconds => (self.microseconds, self.microseconds_display),
+            Unit::Nanoseconds => (self.nanoseconds, self.nanoseconds_display),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Years,
+    Months,
+    Weeks,
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl Unit {
+    const ALL: [Self; 10] = [
+        Self::Years,
+        Self::Months,
+        Self::Weeks,
+        Self::Days,
+        Self::Hours,
+        Self::Minutes,
+        Self::Seconds,
+        Self::Milliseconds,
+        Self::Microseconds,
+        Self::Nanoseconds,
+    ];
+
+    /// The order in which `ToDurationRecord` reads the fields.
+    const ALPHABETICAL: [Self; 10] = [
+        Self::Days,
+        Self::Hours,
+        Self::Microseconds,
+        Self::Milliseconds,
+        Self::Minutes,
+        Self::Months,
+        Self::Nanoseconds,
+        Self::Seconds,
+        Self::Weeks,
+        Self::Years,
+    ];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Years => "years",
+            Self::Months => "months",
+            Self::Weeks => "weeks",
+            Self::Days => "days",
+            Self::Hours => "hours",
+            Self::Minutes => "minutes",
+            Self::Seconds => "seconds",
+            Self::Milliseconds => "milliseconds",
+            Self::Microseconds => "microseconds",
+            Self::Nanoseconds => "nanoseconds",
+        }
+    }
+
+    const fn display_name(self) -> &'static str {
+        match self {
+            Self::Years => "yearsDisplay",
+            Self::Months => "monthsDisplay",
+            Self::Weeks => "weeksDisplay",
+            Self::Days => "daysDisplay",
+            Self::Hours => "hoursDisplay",
+            Self::Minutes => "minutesDisplay",
+            Self::Seconds => "secondsDisplay",
+            Self::Milliseconds => "millisecondsDisplay",
+            Self::Microseconds => "microsecondsDisplay",
+            Self::Nanoseconds => "nanosecondsDisplay",
+        }
+    }
+
+    const fn is_time(self) -> bool {
+        matches!(self, Self::Hours | Self::Minutes | Self::Seconds)
+    }
+
+    const fn is_sub_second(self) -> bool {
+        matches!(
+            self,
+            Self::Milliseconds | Self::Microseconds | Self::Nanoseconds
+        )
+    }
+
+    // https://tc39.es/ecma402/#table-validstyles
+    const fn accepts(self, style: UnitStyle) -> bool {
+        match style {
+            UnitStyle::Long | UnitStyle::Short | UnitStyle::Narrow => true,
+            UnitStyle::Numeric => self.is_time() || self.is_sub_second(),
+            UnitStyle::TwoDigit => self.is_time(),
+        }
+    }
+
+    // https://tc39.es/ecma402/#table-validstyles
+    const fn digital_default(self) -> FieldStyle {
+        if self.is_time() || self.is_sub_second() {
+            FieldStyle::Numeric
+        } else {
+            FieldStyle::Short
+        }
+    }
+}
+
+/// The resolved style of a unit, which unlike [`UnitStyle`] can be
+/// `fractional`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldStyle {
+    Long,
+    Short,
+    Narrow,
+    Numeric,
+    TwoDigit,
+    Fractional,
+}
+
+impl FieldStyle {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Long => "long",
+            Self::Short => "short",
+            Self::Narrow => "narrow",
+            Self::Numeric | Self::Fractional => "numeric",
+            Self::TwoDigit => "2-digit",
+        }
+    }
+
+    const fn is_numeric(self) -> bool {
+        matches!(self, Self::Numeric | Self::TwoDigit | Self::Fractional)
+    }
+}
+
+impl From<UnitStyle> for FieldStyle {
+    fn from(value: UnitStyle) -> Self {
+        match value {
+            UnitStyle::Long => Self::Long,
+            UnitStyle::Short => Self::Short,
+            UnitStyle::Narrow => Self::Narrow,
+            UnitStyle::Numeric => Self::Numeric,
+            UnitStyle::TwoDigit => Self::TwoDigit,
+        }
+    }
+}
+
+impl From<Style> for FieldStyle {
+    fn from(value: Style) -> Self {
+        match value {
+            Style::Long => Self::Long,
+            Style::Short => Self::Short,
+            Style::Narrow => Self::Narrow,
+            Style::Digital => Self::Numeric,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UnitOptions {
+    style: FieldStyle,
+    display: Display,
+}
+
+// https://tc39.es/ecma402/#sec-getdurationunitoptions
+fn unit_options(
+    unit: Unit,
+    style: Option<UnitStyle>,
+    display: Option<Display>,
+    base: Style,
+    prev: Option<FieldStyle>,
+) -> Res<UnitOptions> {
+    if let Some(style) = style
+        && !unit.accepts(style)
+    {
+        return Err(Error::range_error(format!(
+            "Invalid style {} for {}",
+            style.as_str(),
+            unit.name()
+        )));
+    }
+
+    let mut display_default = Display::Always;
+
+    let mut style = match style {
+        Some(style) => style.into(),
+        None if base == Style::Digital => {
+            if !unit.is_time() {
+                display_default = Display::Auto;
+            }
+
+            unit.digital_default()
+        }
+        None if prev.is_some_and(FieldStyle::is_numeric) => {
+            if !matches!(unit, Unit::Minutes | Unit::Seconds) {
+                display_default = Display::Auto;
+            }
+
+            FieldStyle::Numeric
+        }
+        None => {
+            display_default = Display::Auto;
+
+            base.into()
+        }
+    };
+
+    if style == FieldStyle::Numeric && unit.is_sub_second() {
+        style = FieldStyle::Fractional;
+        display_default = Display::Auto;
+    }
+
+    let display = display.unwrap_or(display_default);
+
+    if display == Display::Always && style == FieldStyle::Fractional {
+        return Err(Error::range_error(format!(
+            "{} can't always be displayed with a numeric style",
+            unit.name()
+        )));
+    }
+
+    match prev {
+        Some(FieldStyle::Fractional) if style != FieldStyle::Fractional => {
+            return Err(Error::range_error(format!(
+                "{} must be numeric after a fractional unit",
+                unit.name()
+            )));
+        }
+        Some(FieldStyle::Numeric | FieldStyle::TwoDigit) => {
+            if !style.is_numeric() {
+                return Err(Error::range_error(format!(
+                    "{} must be numeric after a numeric unit",
+                    unit.name()
+                )));
+            }
+
+            if matches!(unit, Unit::Minutes | Unit::Seconds) {
+                style = FieldStyle::TwoDigit;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(UnitOptions { style, display })
+}
+
+macro_rules! icu_style {
+    ($style:expr, $ty:ident { $($from:ident => $to:ident),* $(,)? }) => {
+        match $style {
+            $(FieldStyle::$from => Some($ty::$to),)*
+            _ => None,
+        }
+    };
+}
+
+const fn icu_display(display: Display) -> Option<FieldDisplay> {
+    Some(match display {
+        Display::Auto => FieldDisplay::Auto,
+        Display::Always => FieldDisplay::Always,
+    })
+}
+
+// https://tc39.es/ecma402/#sec-todurationrecord
+fn to_duration_record(input: &Value, realm: &mut Realm) -> Res<IcuDuration> {
+    let obj = match input {
+        Value::Object(obj) => obj,
+        Value::String(_) => {
+            return Err(Error::range(
+                "Intl.DurationFormat doesn't accept duration strings",
+            ));
+        }
+        _ => return Err(Error::ty("Duration must be an object")),
+    };
+
+    let mut values = [0.0; 10];
+    let mut any = false;
+
+    for unit in Unit::ALPHABETICAL {
+        let value = obj.get(unit.name(), realm)?;
+
+        if value.is_undefined() {
+            continue;
+        }
+
+        let n = value.to_number(realm)?;
+
+        if !n.is_finite() || n.fract() != 0.0 {
+            return Err(Error::range_error(format!(
+                "{} must be an integer",
+                unit.name()
+            )));
+        }
+
+        values[unit as usize] = n;
+        any = true;
+    }
+
+    if !any {
+        return Err(Error::ty("Duration must have at least one unit"));
+    }
+
+    duration_from_values(values)
+}
+
+/// Builds an ICU duration from per-unit values, checking them like
+/// `IsValidDuration` does.
+// https://tc39.es/ecma402/#sec-isvalidduration
+fn duration_from_values(values: [f64; 10]) -> Res<IcuDuration> {
+    let negative = values.iter().any(|v| *v < 0.0);
+
+    if values.iter().any(|v| *v != 0.0 && (*v < 0.0) != negative) {
+        return Err(Error::range("Duration units must all have the same sign"));
+    }
+
+    if values[..3].iter().any(|v| v.abs() >= 2f64.powi(32)) {
+        return Err(Error::range("Duration is out of range"));
+    }
+
+    let seconds: f64 = [
+        (Unit::Days, 86_400.0),
+        (Unit::Hours, 3_600.0),
+        (Unit::Minutes, 60.0),
+        (Unit::Seconds, 1.0),
+        (Unit::Milliseconds, 1e-3),
+        (Unit::Microseconds, 1e-6),
+        (Unit::Nanoseconds, 1e-9),
+    ]
+    .iter()
+    .map(|(unit, factor)| values[*unit as usize].abs() * factor)
+    .sum();
+
+    if seconds >= 2f64.powi(53) {
+        return Err(Error::range("Duration is out of range"));
+    }
+
+    let field = |unit: Unit| {
+        let value = values[unit as usize].abs();
+
+        if value >= u64::MAX as f64 {
+            return Err(Error::range_error(format!(
+                "{} is too large to format",
+                unit.name()
+            )));
+        }
+
+        Ok(value as u64)
+    };
+
+    Ok(IcuDuration {
+        sign: if negative {
+            DurationSign::Negative
+        } else {
+            DurationSign::Positive
+        },
+        years: field(Unit::Years)?,
+        months: field(Unit::Months)?,
+        weeks: field(Unit::Weeks)?,
+        days: field(Unit::Days)?,
+        hours: field(Unit::Hours)?,
+        minutes: field(Unit::Minutes)?,
+        seconds: field(Unit::Seconds)?,
+        milliseconds: field(Unit::Milliseconds)?,
+        microseconds: field(Unit::Microseconds)?,
+        nanoseconds: field(Unit::Nanoseconds)?,
+    })
+}
+
+/// A part of a formatted duration as returned by `formatToParts`.
+#[derive(Debug, PartialEq, Eq)]
+struct DurationPart {
+    ty: &'static str,
+    value: String,
+    unit: Option<&'static str>,
+}
+
+/// Flattens the nested list, unit and number parts ICU writes into the
+/// `formatToParts` records.
+#[derive(Default)]
+struct DurationPartsWriter {
+    stack: Vec<Part>,
+    parts: Vec<DurationPart>,
+}
+
+impl DurationPartsWriter {
+    fn push(&mut self, ty: &'static str, value: &str, unit: Option<&'static str>) {
+        if value.is_empty() {
+            return;
+        }
+
+        if let Some(last) = self.parts.last_mut()
+            && last.ty == ty
+            && last.unit == unit
+        {
+            last.value.push_str(value);
+            return;
+        }
+
+        self.parts.push(DurationPart {
+            ty,
+            value: value.to_string(),
+            unit,
+        });
+    }
+}
+
+impl fmt::Write for DurationPartsWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let unit = self
+            .stack
+            .iter()
+            .rev()
+            .find(|part| part.category == "unit")
+            .map(|part| part.value);
+
+        let number = self
+            .stack
+            .iter()
+            .rev()
+            .map(icu_part_to_js_type)
+            .find(|ty| *ty != "unknown");
+
+        match (number, unit) {
+            (Some(ty), _) => self.push(ty, s, unit),
+            (None, Some(_)) => {
+                // the unit pattern around the number, e.g. " hr" in "1 hr"
+                let rest = s.trim_start();
+                let name = rest.trim_end();
+
+                self.push("literal", &s[..s.len() - rest.len()], unit);
+                self.push("unit", name, unit);
+                self.push("literal", &rest[name.len()..], unit);
+            }
+            (None, None) => self.push("literal", s, None),
+        }
+
+        Ok(())
+    }
+}
+
+impl PartsWrite for DurationPartsWriter {
+    type SubPartsWrite = Self;
+
+    fn with_part(
+        &mut self,
+        part: Part,
+        mut f: impl FnMut(&mut Self::SubPartsWrite) -> fmt::Result,
+    ) -> fmt::Result {
+        self.stack.push(part);
+        let res = f(self);
+        self.stack.pop();
+
+        res
+    }
+}
+
 #[object]
 #[derive(Debug)]
-pub struct DurationFormat {}
+pub struct DurationFormat {
+    locale: Locale,
+    style: Style,
+    units: [UnitOptions; 10],
+    fractional_digits: Option<u8>,
+    formatter: DurationFormatter,
+}
 
 impl DurationFormat {
-    pub fn new(realm: &mut Realm) -> Res<Self> {
+    pub fn new(
+        locale: Locale,
+        options: Option<DurationFormatOptions>,
+        realm: &mut Realm,
+    ) -> Res<Self> {
+        let opts = options.unwrap_or_default();
+        let style = opts.style.unwrap_or_default();
+
+        let mut units = [UnitOptions {
+            style: FieldStyle::Short,
+            display: Display::Auto,
+        }; 10];
+        let mut prev = None;
+
+        for unit in Unit::ALL {
+            let (unit_style, display) = opts.unit(unit);
+            let resolved = unit_options(unit, unit_style, display, style, prev)?;
+
+            units[unit as usize] = resolved;
+            prev = Some(resolved.style);
+        }
+
+        if opts.fractional_digits.is_some_and(|digits| digits > 9) {
+            return Err(Error::range("fractionalDigits must be between 0 and 9"));
+        }
+
+        let formatter = Self::icu_formatter(&locale, style, &units, opts.fractional_digits)?;
+
         Ok(Self {
             inner: RefCell::new(MutableDurationFormat {
                 object: MutObject::with_proto(
@@ -87,40 +605,325 @@ impl DurationFormat {
                         .clone(),
                 ),
             }),
+            locale,
+            style,
+            units,
+            fractional_digits: opts.fractional_digits,
+            formatter,
         })
     }
+
+    /// Creates a formatter the way `new Intl.DurationFormat(locales, options)`
+    /// does.
+    pub fn with_locales(locales: &Value, options: Value, realm: &mut Realm) -> Res<Self> {
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .unwrap_or(locale!("en"));
+
+        let options = Option::<DurationFormatOptions>::from_value_out(options, realm)?;
+
+        Self::new(locale, options, realm)
+    }
+
+    fn icu_formatter(
+        locale: &Locale,
+        style: Style,
+        units: &[UnitOptions; 10],
+        fractional_digits: Option<u8>,
+    ) -> Res<DurationFormatter> {
+        let unit = |unit: Unit| units[unit as usize];
+
+        let mut options = DurationFormatterOptions::default();
+
+        options.base = match style {
+            Style::Long => BaseStyle::Long,
+            Style::Short => BaseStyle::Short,
+            Style::Narrow => BaseStyle::Narrow,
+            Style::Digital => BaseStyle::Digital,
+        };
+
+        options.year = icu_style!(unit(Unit::Years).style, YearStyle {
+            Long => Long, Short => Short, Narrow => Narrow,
+        });
+        options.month = icu_style!(unit(Unit::Months).style, MonthStyle {
+            Long => Long, Short => Short, Narrow => Narrow,
+        });
+        options.week = icu_style!(unit(Unit::Weeks).style, WeekStyle {
+            Long => Long, Short => Short, Narrow => Narrow,
+        });
+        options.day = icu_style!(unit(Unit::Days).style, DayStyle {
+            Long => Long, Short => Short, Narrow => Narrow,
+        });
+        options.hour = icu_style!(unit(Unit::Hours).style, HourStyle {
+            Long => Long, Short => Short, Narrow => Narrow, Numeric => Numeric, TwoDigit => TwoDigit,
+        });
+        options.minute = icu_style!(unit(Unit::Minutes).style, MinuteStyle {
+            Long => Long, Short => Short, Narrow => Narrow, Numeric => Numeric, TwoDigit => TwoDigit,
+        });
+        options.second = icu_style!(unit(Unit::Seconds).style, SecondStyle {
+            Long => Long, Short => Short, Narrow => Narrow, Numeric => Numeric, TwoDigit => TwoDigit,
+        });
+        options.millisecond = icu_style!(unit(Unit::Milliseconds).style, MilliSecondStyle {
+            Long => Long, Short => Short, Narrow => Narrow, Fractional => Numeric,
+        });
+        options.microsecond = icu_style!(unit(Unit::Microseconds).style, MicroSecondStyle {
+            Long => Long, Short => Short, Narrow => Narrow, Fractional => Numeric,
+        });
+        options.nanosecond = icu_style!(unit(Unit::Nanoseconds).style, NanoSecondStyle {
+            Long => Long, Short => Short, Narrow => Narrow, Fractional => Numeric,
+        });
+
+        options.year_visibility = icu_display(unit(Unit::Years).display);
+        options.month_visibility = icu_display(unit(Unit::Months).display);
+        options.week_visibility = icu_display(unit(Unit::Weeks).display);
+        options.day_visibility = icu_display(unit(Unit::Days).display);
+        options.hour_visibility = icu_display(unit(Unit::Hours).display);
+        options.minute_visibility = icu_display(unit(Unit::Minutes).display);
+        options.second_visibility = icu_display(unit(Unit::Seconds).display);
+        options.millisecond_visibility = icu_display(unit(Unit::Milliseconds).display);
+        options.microsecond_visibility = icu_display(unit(Unit::Microseconds).display);
+        options.nanosecond_visibility = icu_display(unit(Unit::Nanoseconds).display);
+
+        options.fractional_digits =
+            fractional_digits.map_or(FractionalDigits::ShowAll, FractionalDigits::Fixed);
+
+        let options = ValidatedDurationFormatterOptions::validate(options)
+            .map_err(|e| Error::range_error(e.to_string()))?;
+
+        DurationFormatter::try_new(locale.into(), options)
+            .map_err(|e| Error::range_error(format!("Failed to load duration data: {e}")))
+    }
+
+    /// Formats a duration with fields in the order of years to nanoseconds.
+    pub fn format_values(&self, values: [f64; 10]) -> Res<String> {
+        let duration = duration_from_values(values)?;
+
+        Ok(self
+            .formatter
+            .format(&duration)
+            .write_to_string()
+            .into_owned())
+    }
+
+    fn parts(&self, duration: &IcuDuration) -> Vec<DurationPart> {
+        let mut writer = DurationPartsWriter::default();
+
+        // writing into a `String` can't fail
+        let _ = self.formatter.format(duration).write_to_parts(&mut writer);
+
+        writer.parts
+    }
 }
 
+// https://tc39.es/ecma402/#sec-intl-durationformat-constructor
 #[props(intrinsic_name = intl_duration_format, to_string_tag = "Intl.DurationFormat")]
 impl DurationFormat {
     #[constructor]
-    fn construct(
-        _locales: Option<String>,
-        _options: Option<DurationFormatOptions>,
-        realm: &mut Realm,
-    ) -> Res<ObjectHandle> {
-        Ok(DurationFormat::new(realm)?.into_object())
+    fn construct(locales: &Value, options: Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        Ok(Self::with_locales(locales, options, realm)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.DurationFormat.supportedLocalesOf
     #[prop("supportedLocalesOf")]
     fn supported_locales_of(
-        _locales: String,
+        locales: &Value,
         _options: Option<LocaleMatcherOptions>,
-    ) -> Vec<String> {
-        Vec::new()
+        realm: &mut Realm,
+    ) -> Res<Vec<String>> {
+        Ok(canonicalize_locale_list(locales, realm)?
+            .iter()
+            .filter_map(|locale| locale.parse::<Locale>().ok())
+            .filter(|locale| {
+                ValidatedDurationFormatterOptions::validate(DurationFormatterOptions::default())
+                    .is_ok_and(|options| DurationFormatter::try_new(locale.into(), options).is_ok())
+            })
+            .map(|locale| locale.to_string())
+            .collect())
     }
 
-    fn format(&self, _duration: ObjectHandle) -> String {
-        String::new()
+    // https://tc39.es/ecma402/#sec-Intl.DurationFormat.prototype.format
+    fn format(&self, duration: &Value, realm: &mut Realm) -> Res<String> {
+        let duration = to_duration_record(duration, realm)?;
+
+        Ok(self
+            .formatter
+            .format(&duration)
+            .write_to_string()
+            .into_owned())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.DurationFormat.prototype.formatToParts
     #[prop("formatToParts")]
-    fn format_to_parts(&self, _duration: ObjectHandle) -> Vec<String> {
-        Vec::new()
+    fn format_to_parts(&self, duration: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        let duration = to_duration_record(duration, realm)?;
+
+        let mut elements = Vec::new();
+
+        for part in self.parts(&duration) {
+            let obj = Object::new(realm);
+
+            obj.define_property("type".into(), part.ty.into(), realm)?;
+            obj.define_property("value".into(), part.value.into(), realm)?;
+
+            if let Some(unit) = part.unit {
+                obj.define_property("unit".into(), unit.into(), realm)?;
+            }
+
+            elements.push(obj.into());
+        }
+
+        Ok(Array::with_elements(realm, elements)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.DurationFormat.prototype.resolvedOptions
     #[prop("resolvedOptions")]
-    fn resolved_options(&self, realm: &Realm) -> ObjectHandle {
-        Object::new(realm)
+    fn resolved_options(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let options = Object::new(realm);
+
+        options.define_property("locale".into(), self.locale.to_string().into(), realm)?;
+        options.define_property("numberingSystem".into(), "latn".into(), realm)?;
+        options.define_property("style".into(), self.style.into_value(), realm)?;
+
+        for unit in Unit::ALL {
+            let UnitOptions { style, display } = self.units[unit as usize];
+
+            options.define_property(unit.name().into(), style.name().into(), realm)?;
+            options.define_property(unit.display_name().into(), display.into_value(), realm)?;
+        }
+
+        if let Some(digits) = self.fractional_digits {
+            options.define_property("fractionalDigits".into(), digits.into(), realm)?;
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter(locale: &Locale, style: Style, opts: &DurationFormatOptions) -> DurationFormatter {
+        let mut units = [UnitOptions {
+            style: FieldStyle::Short,
+            display: Display::Auto,
+        }; 10];
+        let mut prev = None;
+
+        for unit in Unit::ALL {
+            let (unit_style, display) = opts.unit(unit);
+            let resolved =
+                unit_options(unit, unit_style, display, style, prev).expect("valid options");
+
+            units[unit as usize] = resolved;
+            prev = Some(resolved.style);
+        }
+
+        DurationFormat::icu_formatter(locale, style, &units, opts.fractional_digits)
+            .expect("duration data")
+    }
+
+    fn duration(values: [f64; 10]) -> IcuDuration {
+        duration_from_values(values).expect("valid duration")
+    }
+
+    #[test]
+    fn styles() {
+        let en = locale!("en");
+        let opts = DurationFormatOptions::default();
+        let d = duration([1.0, 0.0, 0.0, 0.0, 2.0, 30.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let long = formatter(&en, Style::Long, &opts);
+        assert_eq!(long.format(&d).to_string(), "1 year, 2 hours, 30 minutes");
+
+        let digital = formatter(&en, Style::Digital, &opts);
+        assert_eq!(digital.format(&d).to_string(), "1 yr, 2:30:00");
+
+        let opts = DurationFormatOptions {
+            fractional_digits: Some(2),
+            ..Default::default()
+        };
+        let d = duration([0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 5.0, 250.0, 0.0, 0.0]);
+
+        let digital = formatter(&en, Style::Digital, &opts);
+        assert_eq!(digital.format(&d).to_string(), "0:01:05.25");
+    }
+
+    #[test]
+    fn unit_option_validation() {
+        assert!(
+            unit_options(
+                Unit::Years,
+                Some(UnitStyle::Numeric),
+                None,
+                Style::Short,
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            unit_options(
+                Unit::Milliseconds,
+                Some(UnitStyle::Numeric),
+                Some(Display::Always),
+                Style::Short,
+                Some(FieldStyle::TwoDigit),
+            )
+            .is_err()
+        );
+        assert!(
+            unit_options(
+                Unit::Minutes,
+                Some(UnitStyle::Long),
+                None,
+                Style::Short,
+                Some(FieldStyle::Numeric),
+            )
+            .is_err()
+        );
+
+        let seconds = unit_options(
+            Unit::Seconds,
+            None,
+            None,
+            Style::Short,
+            Some(FieldStyle::Numeric),
+        )
+        .expect("valid options");
+        assert_eq!(seconds.style, FieldStyle::TwoDigit);
+        assert_eq!(seconds.display, Display::Always);
+    }
+
+    #[test]
+    fn parts() {
+        let formatter = formatter(
+            &locale!("en"),
+            Style::Short,
+            &DurationFormatOptions::default(),
+        );
+        let d = duration([0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let mut writer = DurationPartsWriter::default();
+        let _ = formatter.format(&d).write_to_parts(&mut writer);
+
+        let part = |ty, value: &str, unit| DurationPart {
+            ty,
+            value: value.to_string(),
+            unit,
+        };
+
+        assert_eq!(
+            writer.parts,
+            [
+                part("integer", "1", Some("hour")),
+                part("literal", " ", Some("hour")),
+                part("unit", "hr", Some("hour")),
+                part("literal", ", ", None),
+                part("integer", "2", Some("minute")),
+                part("literal", " ", Some("minute")),
+                part("unit", "min", Some("minute")),
+            ]
+        );
     }
 }
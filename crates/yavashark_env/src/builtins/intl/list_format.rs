@@ -1,16 +1,28 @@
 use crate::array::Array;
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, Style};
-use crate::value::Obj;
-use crate::{MutObject, Object, ObjectHandle, Realm, Res};
+use crate::builtins::intl::utils::{
+    LocaleMatcher, LocaleMatcherOptions, Style, canonicalize_locale_list,
+};
+use crate::utils::ValueIterator;
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use icu::list::options::{ListFormatterOptions, ListLength};
+use icu::list::{ListFormatter, parts as list_parts};
+use icu::locale::{Locale, locale};
 use std::cell::RefCell;
+use std::fmt;
+use writeable::{Part, PartsWrite, Writeable};
 use yavashark_macro::{data_object, object, props};
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Type {
+    #[default]
     Conjunction,
     Disjunction,
     Unit,
 }
+
+#[derive(Default)]
 #[data_object]
 pub struct ListFormatOptions {
     #[prop("localeMatcher")]
@@ -20,12 +32,90 @@ pub struct ListFormatOptions {
     pub style: Option<Style>,
 }
 
+/// Collects the `element` and `literal` parts of a formatted list, keeping
+/// empty elements.
+#[derive(Default)]
+struct ListPartsWriter {
+    string: String,
+    parts: Vec<(&'static str, String)>,
+}
+
+impl fmt::Write for ListPartsWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.string.write_str(s)
+    }
+}
+
+impl PartsWrite for ListPartsWriter {
+    type SubPartsWrite = Self;
+
+    fn with_part(
+        &mut self,
+        part: Part,
+        mut f: impl FnMut(&mut Self::SubPartsWrite) -> fmt::Result,
+    ) -> fmt::Result {
+        let start = self.string.len();
+        f(self)?;
+        let value = self.string[start..].to_string();
+
+        if part == list_parts::ELEMENT {
+            self.parts.push(("element", value));
+        } else if !value.is_empty() {
+            self.parts.push(("literal", value));
+        }
+
+        Ok(())
+    }
+}
+
+// https://tc39.es/ecma402/#sec-createstringlistfromiterable
+fn string_list(list: &Value, realm: &mut Realm) -> Res<Vec<String>> {
+    if list.is_undefined() {
+        return Ok(Vec::new());
+    }
+
+    let iter = ValueIterator::new(list, realm)?;
+    let mut strings = Vec::new();
+
+    while let Some(value) = iter.next(realm)? {
+        let Value::String(string) = value else {
+            iter.close(realm)?;
+            return Err(Error::ty("Intl.ListFormat only accepts lists of strings"));
+        };
+
+        strings.push(string.as_str_lossy().into_owned());
+    }
+
+    Ok(strings)
+}
+
 #[object]
 #[derive(Debug)]
-pub struct ListFormat {}
+pub struct ListFormat {
+    locale: Locale,
+    ty: Type,
+    style: Style,
+    formatter: ListFormatter,
+}
 
 impl ListFormat {
-    pub fn new(realm: &mut Realm) -> Res<Self> {
+    pub fn new(locale: Locale, ty: Type, style: Style, realm: &mut Realm) -> Res<Self> {
+        let length = match style {
+            Style::Long => ListLength::Wide,
+            Style::Short => ListLength::Short,
+            Style::Narrow => ListLength::Narrow,
+        };
+
+        let prefs = (&locale).into();
+        let options = ListFormatterOptions::default().with_length(length);
+
+        let formatter = match ty {
+            Type::Conjunction => ListFormatter::try_new_and(prefs, options),
+            Type::Disjunction => ListFormatter::try_new_or(prefs, options),
+            Type::Unit => ListFormatter::try_new_unit(prefs, options),
+        }
+        .map_err(|e| Error::range_error(format!("Failed to load list data: {e}")))?;
+
         Ok(Self {
             inner: RefCell::new(MutableListFormat {
                 object: MutObject::with_proto(
@@ -37,40 +127,170 @@ impl ListFormat {
                         .clone(),
                 ),
             }),
+            locale,
+            ty,
+            style,
+            formatter,
         })
     }
+
+    // https://tc39.es/ecma402/#sec-createpartsfromlist
+    fn parts(&self, list: &[String]) -> Vec<(&'static str, String)> {
+        let mut writer = ListPartsWriter::default();
+
+        // writing into a `String` can't fail
+        let _ = self
+            .formatter
+            .format(list.iter().map(String::as_str))
+            .write_to_parts(&mut writer);
+
+        writer.parts
+    }
 }
 
+// https://tc39.es/ecma402/#sec-intl-listformat-constructor
 #[props(intrinsic_name = intl_list_format, to_string_tag = "Intl.ListFormat")]
 impl ListFormat {
     #[constructor]
     fn construct(
-        _locales: Option<String>,
-        _options: Option<ListFormatOptions>,
+        locales: &Value,
+        options: Option<ListFormatOptions>,
         realm: &mut Realm,
     ) -> Res<ObjectHandle> {
-        Ok(Self::new(realm)?.into_object())
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .unwrap_or(locale!("en"));
+
+        let opts = options.unwrap_or_default();
+
+        let ty = opts.type_.unwrap_or_default();
+        let style = opts.style.unwrap_or(Style::Long);
+
+        Ok(Self::new(locale, ty, style, realm)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.ListFormat.supportedLocalesOf
     #[prop("supportedLocalesOf")]
     fn supported_locales_of(
-        _locales: String,
+        locales: &Value,
         _options: Option<LocaleMatcherOptions>,
-    ) -> Vec<String> {
-        Vec::new()
+        realm: &mut Realm,
+    ) -> Res<Vec<String>> {
+        Ok(canonicalize_locale_list(locales, realm)?
+            .iter()
+            .filter_map(|locale| locale.parse::<Locale>().ok())
+            .filter(|locale| {
+                ListFormatter::try_new_and(locale.into(), ListFormatterOptions::default()).is_ok()
+            })
+            .map(|locale| locale.to_string())
+            .collect())
     }
 
-    fn format(&self, _list: ObjectHandle) -> String {
-        String::new()
+    // https://tc39.es/ecma402/#sec-Intl.ListFormat.prototype.format
+    fn format(&self, list: &Value, realm: &mut Realm) -> Res<String> {
+        let list = string_list(list, realm)?;
+
+        Ok(self
+            .formatter
+            .format_to_string(list.iter().map(String::as_str)))
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.ListFormat.prototype.formatToParts
     #[prop("formatToParts")]
-    fn format_to_parts(&self, _list: ObjectHandle, realm: &mut Realm) -> Res<ObjectHandle> {
-        Ok(Array::from_realm(realm)?.into_object())
+    fn format_to_parts(&self, list: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        let list = string_list(list, realm)?;
+
+        let mut elements = Vec::new();
+
+        for (ty, value) in self.parts(&list) {
+            let part = Object::new(realm);
+
+            part.define_property("type".into(), ty.into(), realm)?;
+            part.define_property("value".into(), value.into(), realm)?;
+
+            elements.push(part.into());
+        }
+
+        Ok(Array::with_elements(realm, elements)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.ListFormat.prototype.resolvedoptions
     #[prop("resolvedOptions")]
-    fn resolved_options(&self, realm: &Realm) -> ObjectHandle {
-        Object::new(realm)
+    fn resolved_options(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let options = Object::new(realm);
+
+        options.define_property("locale".into(), self.locale.to_string().into(), realm)?;
+        options.define_property("type".into(), self.ty.into_value(), realm)?;
+        options.define_property("style".into(), self.style.into_value(), realm)?;
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(locale: &Locale, ty: Type, length: ListLength, list: &[&str]) -> String {
+        let options = ListFormatterOptions::default().with_length(length);
+
+        let formatter = match ty {
+            Type::Conjunction => ListFormatter::try_new_and(locale.into(), options),
+            Type::Disjunction => ListFormatter::try_new_or(locale.into(), options),
+            Type::Unit => ListFormatter::try_new_unit(locale.into(), options),
+        }
+        .expect("list data");
+
+        formatter.format_to_string(list.iter().copied())
+    }
+
+    #[test]
+    fn lists() {
+        let en = locale!("en");
+
+        assert_eq!(
+            format(&en, Type::Conjunction, ListLength::Wide, &["a", "b", "c"]),
+            "a, b, and c"
+        );
+        assert_eq!(
+            format(&en, Type::Disjunction, ListLength::Wide, &["a", "b"]),
+            "a or b"
+        );
+        assert_eq!(
+            format(&en, Type::Unit, ListLength::Narrow, &["1m", "2s"]),
+            "1m 2s"
+        );
+        assert_eq!(
+            format(
+                &locale!("de"),
+                Type::Conjunction,
+                ListLength::Wide,
+                &["a", "b", "c"]
+            ),
+            "a, b und c"
+        );
+    }
+
+    #[test]
+    fn parts_keep_empty_elements() {
+        let mut writer = ListPartsWriter::default();
+
+        let formatter =
+            ListFormatter::try_new_and(locale!("en").into(), ListFormatterOptions::default())
+                .expect("list data");
+
+        let _ = formatter
+            .format(["", "b"].into_iter())
+            .write_to_parts(&mut writer);
+
+        assert_eq!(
+            writer.parts,
+            [
+                ("element", String::new()),
+                ("literal", " and ".to_string()),
+                ("element", "b".to_string()),
+            ]
+        );
     }
 }
@@ -1,8 +1,10 @@
 use crate::array::Array;
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions};
+use crate::builtins::intl::digit_options::{
+    DigitOptions, DigitOptionsInput, RoundingMode, RoundingPriority, TrailingZeroDisplay,
+};
+use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, canonicalize_locale_list};
 use crate::value::{IntoValue, Obj, fmt_num};
 use crate::{Error, MutObject, NativeFunction, Object, ObjectHandle, Realm, Res, Value};
-use icu::decimal::input::Decimal;
 use icu::decimal::options::{DecimalFormatterOptions, GroupingStrategy};
 use icu::decimal::{DecimalFormatter, parts as icu_parts};
 use icu::locale::Locale;
@@ -77,38 +79,6 @@ pub enum SignDisplay {
     Negative,
 }
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-#[data_object(error = "range")]
-pub enum RoundingMode {
-    Ceil,
-    Floor,
-    Expand,
-    Trunc,
-    HalfCeil,
-    HalfFloor,
-    #[default]
-    HalfExpand,
-    HalfTrunc,
-    HalfEven,
-}
-
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-#[data_object(error = "range")]
-pub enum RoundingPriority {
-    #[default]
-    Auto,
-    MorePrecision,
-    LessPrecision,
-}
-
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-#[data_object(error = "range")]
-pub enum TrailingZeroDisplay {
-    #[default]
-    Auto,
-    StripIfInteger,
-}
-
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 #[data_object(error = "range")]
 pub enum UseGrouping {
@@ -172,19 +142,11 @@ struct NumberFormatConfig {
     currency_sign: CurrencySign,
     unit: Option<String>,
     unit_display: UnitDisplay,
-    minimum_integer_digits: u8,
-    minimum_fraction_digits: Option<u8>,
-    maximum_fraction_digits: Option<u8>,
-    minimum_significant_digits: Option<u8>,
-    maximum_significant_digits: Option<u8>,
+    digits: DigitOptions,
     use_grouping: UseGrouping,
     notation: Notation,
     compact_display: CompactDisplay,
     sign_display: SignDisplay,
-    rounding_mode: RoundingMode,
-    rounding_priority: RoundingPriority,
-    rounding_increment: u16,
-    trailing_zero_display: TrailingZeroDisplay,
     grouping_strategy: GroupingStrategy,
 }
 
@@ -233,7 +195,7 @@ impl NumberFormat {
             formatted_value *= 100.0;
         }
 
-        let decimal = value_to_decimal(formatted_value);
+        let decimal = config.digits.round(formatted_value);
 
         let result = formatter.format(&decimal).to_string();
 
@@ -266,15 +228,6 @@ impl NumberFormat {
     }
 }
 
-fn value_to_decimal(value: f64) -> Decimal {
-    if value.is_nan() || value.is_infinite() {
-        return Decimal::from(0);
-    }
-
-    let rounded = value.round() as i64;
-    Decimal::from(rounded)
-}
-
 fn get_currency_symbol(currency: &str) -> &'static str {
     match currency.to_uppercase().as_str() {
         "USD" => "$",
@@ -490,20 +443,20 @@ struct NumberPart {
 }
 
 /// Writer that collects parts from ICU's write_to_parts
-struct PartsWriter {
+pub(super) struct PartsWriter {
     string: String,
     parts: Vec<(usize, usize, Part)>,
 }
 
 impl PartsWriter {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             string: String::new(),
             parts: Vec::new(),
         }
     }
 
-    fn finish(mut self) -> (String, Vec<(usize, usize, Part)>) {
+    pub(super) fn finish(mut self) -> (String, Vec<(usize, usize, Part)>) {
         // Sort by first open and last closed
         self.parts
             .sort_unstable_by_key(|(begin, end, _)| (*begin, end.wrapping_neg()));
@@ -538,7 +491,7 @@ impl PartsWrite for PartsWriter {
 }
 
 /// Convert ICU part type to JavaScript NumberFormat part type
-fn icu_part_to_js_type(part: &Part) -> &'static str {
+pub(super) fn icu_part_to_js_type(part: &Part) -> &'static str {
     if *part == icu_parts::INTEGER {
         "integer"
     } else if *part == icu_parts::FRACTION {
@@ -682,54 +635,22 @@ impl NumberFormat {
                 (0u8, 3u8)
             };
 
-        let minimum_integer_digits = opts.minimum_integer_digits.unwrap_or(1);
-        if !(1..=21).contains(&minimum_integer_digits) {
-            return Err(Error::range_error(
-                "minimumIntegerDigits must be between 1 and 21".to_string(),
-            ));
-        }
-
-        let has_sd =
-            opts.minimum_significant_digits.is_some() || opts.maximum_significant_digits.is_some();
-        let has_fd =
-            opts.minimum_fraction_digits.is_some() || opts.maximum_fraction_digits.is_some();
-
-        let rounding_priority = opts.rounding_priority.unwrap_or_default();
-
-        let (minimum_fraction_digits, maximum_fraction_digits) = if has_fd {
-            let min = opts.minimum_fraction_digits;
-            let max = opts.maximum_fraction_digits;
-
-            let min_val = min.unwrap_or(mnfd_default);
-            let max_val = max.unwrap_or_else(|| u8::max(mxfd_default, min_val));
-
-            if min_val > max_val {
-                return Err(Error::range_error(
-                    "minimumFractionDigits cannot be greater than maximumFractionDigits"
-                        .to_string(),
-                ));
-            }
-            (Some(min_val), Some(max_val))
-        } else if !has_sd || rounding_priority != RoundingPriority::Auto {
-            (Some(mnfd_default), Some(mxfd_default))
-        } else {
-            (None, None)
-        };
-
-        let (minimum_significant_digits, maximum_significant_digits) = if has_sd {
-            let min = opts.minimum_significant_digits.unwrap_or(1);
-            let max = opts.maximum_significant_digits.unwrap_or(21);
-            if !(1..=21).contains(&min) || !(1..=21).contains(&max) || min > max {
-                return Err(Error::range_error(
-                    "significantDigits must be between 1 and 21, and min <= max".to_string(),
-                ));
-            }
-            (Some(min), Some(max))
-        } else if rounding_priority != RoundingPriority::Auto {
-            (Some(1), Some(21))
-        } else {
-            (None, None)
-        };
+        let digits = DigitOptions::resolve(
+            DigitOptionsInput {
+                minimum_integer_digits: opts.minimum_integer_digits,
+                minimum_fraction_digits: opts.minimum_fraction_digits,
+                maximum_fraction_digits: opts.maximum_fraction_digits,
+                minimum_significant_digits: opts.minimum_significant_digits,
+                maximum_significant_digits: opts.maximum_significant_digits,
+                rounding_mode: opts.rounding_mode,
+                rounding_priority: opts.rounding_priority,
+                rounding_increment: opts.rounding_increment,
+                trailing_zero_display: opts.trailing_zero_display,
+            },
+            mnfd_default,
+            mxfd_default,
+            notation == Notation::Compact,
+        )?;
 
         let default_use_grouping = if notation == Notation::Compact {
             UseGrouping::Min2
@@ -747,17 +668,6 @@ impl NumberFormat {
             UseGrouping::False => GroupingStrategy::Never,
         };
 
-        let rounding_increment = opts.rounding_increment.unwrap_or(1);
-        let valid_increments = [
-            1, 2, 5, 10, 20, 25, 50, 100, 200, 250, 500, 1000, 2000, 2500, 5000,
-        ];
-        if !valid_increments.contains(&rounding_increment) {
-            return Err(Error::range_error(format!(
-                "Invalid rounding increment: {}",
-                rounding_increment
-            )));
-        }
-
         let config = Arc::new(NumberFormatConfig {
             locale,
             numbering_system: opts.numbering_system.clone(),
@@ -767,19 +677,11 @@ impl NumberFormat {
             currency_sign: opts.currency_sign.unwrap_or_default(),
             unit: opts.unit.clone(),
             unit_display: opts.unit_display.unwrap_or_default(),
-            minimum_integer_digits,
-            minimum_fraction_digits,
-            maximum_fraction_digits,
-            minimum_significant_digits,
-            maximum_significant_digits,
+            digits,
             use_grouping,
             notation,
             compact_display: opts.compact_display.unwrap_or_default(),
             sign_display: opts.sign_display.unwrap_or_default(),
-            rounding_mode: opts.rounding_mode.unwrap_or_default(),
-            rounding_priority,
-            rounding_increment,
-            trailing_zero_display: opts.trailing_zero_display.unwrap_or_default(),
             grouping_strategy,
         });
 
@@ -830,7 +732,7 @@ impl NumberFormat {
                     formatted_value *= 100.0;
                 }
 
-                let decimal = value_to_decimal(formatted_value);
+                let decimal = config.digits.round(formatted_value);
 
                 let result = formatter.format(&decimal).to_string();
 
@@ -918,28 +820,7 @@ impl NumberFormat {
             )?;
         }
 
-        options.define_property(
-            "minimumIntegerDigits".into(),
-            config.minimum_integer_digits.into(),
-            realm,
-        )?;
-
-        if let Some(min) = config.minimum_fraction_digits {
-            options.define_property("minimumFractionDigits".into(), min.into(), realm)?;
-        }
-        if let Some(max) = config.maximum_fraction_digits {
-            options.define_property("maximumFractionDigits".into(), max.into(), realm)?;
-        }
-        if let Some(min) = config.minimum_significant_digits {
-            options.define_property(
-                "minimumSignificantDigits".into(),
-                (min as f64).into(),
-                realm,
-            )?;
-        }
-        if let Some(max) = config.maximum_significant_digits {
-            options.define_property("maximumSignificantDigits".into(), max.into(), realm)?;
-        }
+        config.digits.define_digits(&options, realm)?;
 
         options.define_property(
             "useGrouping".into(),
@@ -963,29 +844,7 @@ impl NumberFormat {
             realm,
         )?;
 
-        options.define_property(
-            "roundingIncrement".into(),
-            config.rounding_increment.into(),
-            realm,
-        )?;
-
-        options.define_property(
-            "roundingMode".into(),
-            config.rounding_mode.into_value(),
-            realm,
-        )?;
-
-        options.define_property(
-            "roundingPriority".into(),
-            config.rounding_priority.into_value(),
-            realm,
-        )?;
-
-        options.define_property(
-            "trailingZeroDisplay".into(),
-            config.trailing_zero_display.into_value(),
-            realm,
-        )?;
+        config.digits.define_rounding(&options, realm)?;
 
         Ok(options)
     }
@@ -1060,7 +919,7 @@ impl NumberFormat {
         };
 
         // Create decimal and formatter
-        let decimal = value_to_decimal(formatted_value);
+        let decimal = config.digits.round(formatted_value);
         let mut options = DecimalFormatterOptions::default();
         options.grouping_strategy = Some(config.grouping_strategy);
 
@@ -1227,27 +1086,3 @@ impl NumberFormat {
         Ok(parts.into_object())
     }
 }
-
-fn canonicalize_locale_list(locales: &Value, realm: &mut Realm) -> Res<Vec<String>> {
-    if locales.is_undefined() {
-        return Ok(Vec::new());
-    }
-
-    let mut result = Vec::new();
-
-    if locales.is_string() {
-        result.push(locales.to_string(realm)?.to_string());
-    } else if let Value::Object(obj) = &locales {
-        let length = obj.get("length", realm)?.to_number(realm)? as usize;
-
-        for i in 0..length {
-            let locale_value = obj.get(i, realm)?;
-            if !locale_value.is_undefined() && !locale_value.is_null() {
-                let locale_str = locale_value.to_string(realm)?;
-                result.push(locale_str.to_string());
-            }
-        }
-    }
-
-    Ok(result)
-}
@@ -1,40 +1,36 @@
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions};
-use crate::{MutObject, Object, ObjectHandle, Realm, Res};
+use crate::array::Array;
+use crate::builtins::intl::digit_options::{
+    DigitOptions, DigitOptionsInput, RoundingMode, RoundingPriority, TrailingZeroDisplay,
+};
+use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, canonicalize_locale_list};
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use icu::locale::{Locale, locale};
+use icu::plurals::{
+    PluralCategory, PluralRuleType, PluralRules as IcuPluralRules,
+    PluralRulesOptions as IcuOptions, PluralRulesWithRanges,
+};
 use std::cell::RefCell;
 use yavashark_macro::{data_object, object, props};
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Type {
+    #[default]
     Cardinal,
     Ordinal,
 }
 
-#[data_object]
-pub enum RoundingMode {
-    Ceil,
-    Floor,
-    Expand,
-    Trunc,
-    HalfCeil,
-    HalfFloor,
-    HalfExpand,
-    HalfTrunc,
-    HalfEven,
-}
-
-#[data_object]
-pub enum RoundingPriority {
-    Auto,
-    MorePrecision,
-    LessPrecision,
-}
-
-#[data_object]
-pub enum TrailingZeroDisplay {
-    Auto,
-    StripIfInteger,
+impl From<Type> for PluralRuleType {
+    fn from(value: Type) -> Self {
+        match value {
+            Type::Cardinal => Self::Cardinal,
+            Type::Ordinal => Self::Ordinal,
+        }
+    }
 }
 
+#[derive(Default)]
 #[data_object]
 pub struct PluralRulesOptions {
     #[prop("localeMatcher")]
@@ -56,17 +52,40 @@ pub struct PluralRulesOptions {
     #[prop("roundingPriority")]
     pub rounding_priority: Option<RoundingPriority>,
     #[prop("roundingIncrement")]
-    pub rounding_increment: Option<u64>,
+    pub rounding_increment: Option<u16>,
     #[prop("trailingZeroDisplay")]
     pub trailing_zero_display: Option<TrailingZeroDisplay>,
 }
 
+#[must_use]
+pub const fn category_name(category: PluralCategory) -> &'static str {
+    match category {
+        PluralCategory::Zero => "zero",
+        PluralCategory::One => "one",
+        PluralCategory::Two => "two",
+        PluralCategory::Few => "few",
+        PluralCategory::Many => "many",
+        PluralCategory::Other => "other",
+    }
+}
+
 #[object]
 #[derive(Debug)]
-pub struct PluralRules {}
+pub struct PluralRules {
+    locale: String,
+    ty: Type,
+    digits: DigitOptions,
+    rules: PluralRulesWithRanges<IcuPluralRules>,
+}
 
 impl PluralRules {
-    pub fn new(realm: &mut Realm) -> Res<Self> {
+    pub fn new(locale: &Locale, ty: Type, digits: DigitOptions, realm: &mut Realm) -> Res<Self> {
+        let rules = PluralRulesWithRanges::try_new(
+            locale.into(),
+            IcuOptions::default().with_type(ty.into()),
+        )
+        .map_err(|e| Error::range_error(format!("Failed to load plural rules: {e}")))?;
+
         Ok(Self {
             inner: RefCell::new(MutablePluralRules {
                 object: MutObject::with_proto(
@@ -78,41 +97,166 @@ impl PluralRules {
                         .clone(),
                 ),
             }),
+            locale: locale.to_string(),
+            ty,
+            digits,
+            rules,
         })
     }
+
+    // https://tc39.es/ecma402/#sec-resolveplural
+    fn resolve(&self, n: f64) -> PluralCategory {
+        if !n.is_finite() {
+            return PluralCategory::Other;
+        }
+
+        self.rules.rules().category_for(&self.digits.round(n))
+    }
 }
 
+// https://tc39.es/ecma402/#sec-intl-pluralrules-constructor
 #[props(intrinsic_name = intl_plural_rules, to_string_tag = "Intl.PluralRules")]
 impl PluralRules {
     #[constructor]
     fn construct(
-        _locales: Option<String>,
-        _options: Option<PluralRulesOptions>,
+        locales: &Value,
+        options: Option<PluralRulesOptions>,
         realm: &mut Realm,
-    ) -> Res<Self> {
-        Self::new(realm)
+    ) -> Res<ObjectHandle> {
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .unwrap_or(locale!("en"));
+
+        let opts = options.unwrap_or_default();
+
+        let digits = DigitOptions::resolve(
+            DigitOptionsInput {
+                minimum_integer_digits: opts.minimum_integer_digits,
+                minimum_fraction_digits: opts.minimum_fraction_digits,
+                maximum_fraction_digits: opts.maximum_fraction_digits,
+                minimum_significant_digits: opts.minimum_significant_digits,
+                maximum_significant_digits: opts.maximum_significant_digits,
+                rounding_mode: opts.rounding_mode,
+                rounding_priority: opts.rounding_priority,
+                rounding_increment: opts.rounding_increment,
+                trailing_zero_display: opts.trailing_zero_display,
+            },
+            0,
+            3,
+            false,
+        )?;
+
+        Ok(Self::new(&locale, opts.type_.unwrap_or_default(), digits, realm)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-intl.pluralrules.supportedlocalesof
     #[prop("supportedLocalesOf")]
-    pub fn supported_locales_of(
-        _locales: String,
+    fn supported_locales_of(
+        locales: &Value,
         _options: Option<LocaleMatcherOptions>,
-    ) -> Vec<String> {
-        Vec::new()
+        realm: &mut Realm,
+    ) -> Res<Vec<String>> {
+        Ok(canonicalize_locale_list(locales, realm)?
+            .iter()
+            .filter_map(|locale| locale.parse::<Locale>().ok())
+            .filter(|locale| IcuPluralRules::try_new(locale.into(), IcuOptions::default()).is_ok())
+            .map(|locale| locale.to_string())
+            .collect())
     }
 
+    // https://tc39.es/ecma402/#sec-intl.pluralrules.prototype.resolvedoptions
     #[prop("resolvedOptions")]
-    fn resolved_options(&self, realm: &Realm) -> ObjectHandle {
-        Object::new(realm)
+    fn resolved_options(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let options = Object::new(realm);
+
+        options.define_property("locale".into(), self.locale.clone().into(), realm)?;
+        options.define_property("type".into(), self.ty.into_value(), realm)?;
+
+        self.digits.define_digits(&options, realm)?;
+
+        let categories = self
+            .rules
+            .rules()
+            .categories()
+            .map(|category| category_name(category).into())
+            .collect();
+
+        let categories = Array::with_elements(realm, categories)?.into_object();
+        options.define_property("pluralCategories".into(), categories.into(), realm)?;
+
+        self.digits.define_rounding(&options, realm)?;
+
+        Ok(options)
     }
 
-    #[prop("select")]
-    fn select(&self, _number: f32) -> String {
-        String::new()
+    // https://tc39.es/ecma402/#sec-intl.pluralrules.prototype.select
+    fn select(&self, number: &Value, realm: &mut Realm) -> Res<&'static str> {
+        let n = number.to_number(realm)?;
+
+        Ok(category_name(self.resolve(n)))
     }
 
+    // https://tc39.es/ecma402/#sec-intl.pluralrules.prototype.selectrange
     #[prop("selectRange")]
-    fn select_range(&self, _start: f32, _end: f32) -> String {
-        String::new()
+    fn select_range(&self, start: &Value, end: &Value, realm: &mut Realm) -> Res<&'static str> {
+        if start.is_undefined() || end.is_undefined() {
+            return Err(Error::ty("start and end must not be undefined"));
+        }
+
+        let x = start.to_number(realm)?;
+        let y = end.to_number(realm)?;
+
+        if x.is_nan() || y.is_nan() {
+            return Err(Error::range("start and end must not be NaN"));
+        }
+
+        let start = self.resolve(x);
+        let end = self.resolve(y);
+
+        Ok(category_name(self.rules.resolve_range(start, end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(locale: &str, ty: Type, n: f64, digits: DigitOptionsInput) -> &'static str {
+        let locale = locale.parse::<Locale>().expect("valid locale");
+        let rules =
+            IcuPluralRules::try_new((&locale).into(), IcuOptions::default().with_type(ty.into()))
+                .expect("plural data");
+        let digits = DigitOptions::resolve(digits, 0, 3, false).expect("valid options");
+
+        category_name(rules.category_for(&digits.round(n)))
+    }
+
+    #[test]
+    fn cardinal_and_ordinal() {
+        let default = DigitOptionsInput::default();
+
+        assert_eq!(select("en", Type::Cardinal, 1.0, default), "one");
+        assert_eq!(select("en", Type::Cardinal, 2.0, default), "other");
+        assert_eq!(select("en", Type::Ordinal, 22.0, default), "two");
+        assert_eq!(select("en", Type::Ordinal, 13.0, default), "other");
+        assert_eq!(select("ar", Type::Cardinal, 0.0, default), "zero");
+    }
+
+    #[test]
+    fn digits_affect_category() {
+        let fraction = DigitOptionsInput {
+            minimum_fraction_digits: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(select("en", Type::Cardinal, 1.0, fraction), "other");
+
+        let integer = DigitOptionsInput {
+            maximum_fraction_digits: Some(0),
+            ..Default::default()
+        };
+
+        assert_eq!(select("en", Type::Cardinal, 1.2, integer), "one");
     }
 }
@@ -1,15 +1,39 @@
 use crate::array::Array;
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, Style};
-use crate::value::Obj;
-use crate::{MutObject, Object, ObjectHandle, Realm, Res};
+use crate::builtins::intl::digit_options::DigitOptions;
+use crate::builtins::intl::number_format::{PartsWriter, icu_part_to_js_type};
+use crate::builtins::intl::utils::{
+    LocaleMatcher, LocaleMatcherOptions, Style, canonicalize_locale_list,
+};
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use fixed_decimal::Sign;
+use icu::decimal::DecimalFormatter;
+use icu::decimal::options::DecimalFormatterOptions;
+use icu::experimental::relativetime::options::Numeric as IcuNumeric;
+use icu::experimental::relativetime::{RelativeTimeFormatter, RelativeTimeFormatterOptions};
+use icu::locale::{Locale, locale};
 use std::cell::RefCell;
+use writeable::Writeable;
 use yavashark_macro::{data_object, object, props};
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Numeric {
+    #[default]
     Always,
     Auto,
 }
+
+impl From<Numeric> for IcuNumeric {
+    fn from(value: Numeric) -> Self {
+        match value {
+            Numeric::Always => Self::Always,
+            Numeric::Auto => Self::Auto,
+        }
+    }
+}
+
+#[derive(Default)]
 #[data_object]
 pub struct RelativeTimeFormatOptions {
     #[prop("localeMatcher")]
@@ -18,12 +42,184 @@ pub struct RelativeTimeFormatOptions {
     pub style: Option<Style>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Unit {
+    // https://tc39.es/ecma402/#sec-singularrelativetimeunit
+    #[must_use]
+    pub fn for_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "second" | "seconds" => Self::Second,
+            "minute" | "minutes" => Self::Minute,
+            "hour" | "hours" => Self::Hour,
+            "day" | "days" => Self::Day,
+            "week" | "weeks" => Self::Week,
+            "month" | "months" => Self::Month,
+            "quarter" | "quarters" => Self::Quarter,
+            "year" | "years" => Self::Year,
+            _ => return None,
+        })
+    }
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Second => "second",
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Quarter => "quarter",
+            Self::Year => "year",
+        }
+    }
+}
+
+/// Creates the ICU4X formatter for a style and unit, which each have their
+/// own data.
+fn icu_formatter(
+    locale: &Locale,
+    style: Style,
+    unit: Unit,
+    numeric: Numeric,
+) -> Res<RelativeTimeFormatter> {
+    let prefs = locale.into();
+    let mut options = RelativeTimeFormatterOptions::default();
+    options.numeric = numeric.into();
+
+    let formatter = match (style, unit) {
+        (Style::Long, Unit::Second) => RelativeTimeFormatter::try_new_long_second(prefs, options),
+        (Style::Long, Unit::Minute) => RelativeTimeFormatter::try_new_long_minute(prefs, options),
+        (Style::Long, Unit::Hour) => RelativeTimeFormatter::try_new_long_hour(prefs, options),
+        (Style::Long, Unit::Day) => RelativeTimeFormatter::try_new_long_day(prefs, options),
+        (Style::Long, Unit::Week) => RelativeTimeFormatter::try_new_long_week(prefs, options),
+        (Style::Long, Unit::Month) => RelativeTimeFormatter::try_new_long_month(prefs, options),
+        (Style::Long, Unit::Quarter) => RelativeTimeFormatter::try_new_long_quarter(prefs, options),
+        (Style::Long, Unit::Year) => RelativeTimeFormatter::try_new_long_year(prefs, options),
+        (Style::Short, Unit::Second) => RelativeTimeFormatter::try_new_short_second(prefs, options),
+        (Style::Short, Unit::Minute) => RelativeTimeFormatter::try_new_short_minute(prefs, options),
+        (Style::Short, Unit::Hour) => RelativeTimeFormatter::try_new_short_hour(prefs, options),
+        (Style::Short, Unit::Day) => RelativeTimeFormatter::try_new_short_day(prefs, options),
+        (Style::Short, Unit::Week) => RelativeTimeFormatter::try_new_short_week(prefs, options),
+        (Style::Short, Unit::Month) => RelativeTimeFormatter::try_new_short_month(prefs, options),
+        (Style::Short, Unit::Quarter) => {
+            RelativeTimeFormatter::try_new_short_quarter(prefs, options)
+        }
+        (Style::Short, Unit::Year) => RelativeTimeFormatter::try_new_short_year(prefs, options),
+        (Style::Narrow, Unit::Second) => {
+            RelativeTimeFormatter::try_new_narrow_second(prefs, options)
+        }
+        (Style::Narrow, Unit::Minute) => {
+            RelativeTimeFormatter::try_new_narrow_minute(prefs, options)
+        }
+        (Style::Narrow, Unit::Hour) => RelativeTimeFormatter::try_new_narrow_hour(prefs, options),
+        (Style::Narrow, Unit::Day) => RelativeTimeFormatter::try_new_narrow_day(prefs, options),
+        (Style::Narrow, Unit::Week) => RelativeTimeFormatter::try_new_narrow_week(prefs, options),
+        (Style::Narrow, Unit::Month) => RelativeTimeFormatter::try_new_narrow_month(prefs, options),
+        (Style::Narrow, Unit::Quarter) => {
+            RelativeTimeFormatter::try_new_narrow_quarter(prefs, options)
+        }
+        (Style::Narrow, Unit::Year) => RelativeTimeFormatter::try_new_narrow_year(prefs, options),
+    };
+
+    formatter.map_err(|e| Error::range_error(format!("Failed to load relative time data: {e}")))
+}
+
+/// Formats `value` and splits the result into non-overlapping `(type, value)`
+/// parts, with everything outside the number being a literal.
+fn format_relative(
+    locale: &Locale,
+    style: Style,
+    unit: Unit,
+    numeric: Numeric,
+    value: f64,
+) -> Res<Vec<(&'static str, String)>> {
+    let decimal = DigitOptions::default().round(value);
+
+    let string = icu_formatter(locale, style, unit, numeric)?
+        .format(decimal.clone())
+        .write_to_string()
+        .into_owned();
+
+    // ICU4X doesn't report the parts of the interpolated number, so format it
+    // on its own and find it in the pattern
+    let mut number = decimal;
+    number.set_sign(Sign::None);
+
+    let decimal_formatter =
+        DecimalFormatter::try_new(locale.into(), DecimalFormatterOptions::default())
+            .map_err(|e| Error::range_error(format!("Failed to load number data: {e}")))?;
+
+    let mut writer = PartsWriter::new();
+    decimal_formatter
+        .format(&number)
+        .write_to_parts(&mut writer)
+        .map_err(|_| Error::new("Failed to format relative time"))?;
+
+    let (number, number_parts) = writer.finish();
+
+    let parts = string
+        .find(&number)
+        .map(|offset| {
+            number_parts
+                .into_iter()
+                .map(|(start, end, part)| (start + offset, end + offset, part))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let mut bounds = parts
+        .iter()
+        .flat_map(|(start, end, _)| [*start, *end])
+        .chain([0, string.len()])
+        .collect::<Vec<_>>();
+
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut result: Vec<(&'static str, String)> = Vec::new();
+
+    for range in bounds.windows(2) {
+        let (start, end) = (range[0], range[1]);
+
+        let ty = parts
+            .iter()
+            .filter(|(s, e, _)| *s <= start && end <= *e)
+            .min_by_key(|(s, e, _)| e - s)
+            .map_or("literal", |(_, _, part)| icu_part_to_js_type(part));
+
+        match result.last_mut() {
+            Some((last, value)) if *last == "literal" && ty == "literal" => {
+                value.push_str(&string[start..end]);
+            }
+            _ => result.push((ty, string[start..end].to_string())),
+        }
+    }
+
+    Ok(result)
+}
+
 #[object]
 #[derive(Debug)]
-pub struct RelativeTimeFormat {}
+pub struct RelativeTimeFormat {
+    locale: Locale,
+    style: Style,
+    numeric: Numeric,
+}
 
 impl RelativeTimeFormat {
-    pub fn new(realm: &mut Realm) -> Res<Self> {
+    pub fn new(locale: Locale, style: Style, numeric: Numeric, realm: &mut Realm) -> Res<Self> {
         Ok(Self {
             inner: RefCell::new(MutableRelativeTimeFormat {
                 object: MutObject::with_proto(
@@ -35,40 +231,188 @@ impl RelativeTimeFormat {
                         .clone(),
                 ),
             }),
+            locale,
+            style,
+            numeric,
         })
     }
+
+    // https://tc39.es/ecma402/#sec-PartitionRelativeTimePattern
+    fn partition(
+        &self,
+        value: &Value,
+        unit: &Value,
+        realm: &mut Realm,
+    ) -> Res<(Unit, Vec<(&'static str, String)>)> {
+        let value = value.to_number(realm)?;
+        let unit = unit.to_string(realm)?;
+
+        if !value.is_finite() {
+            return Err(Error::range("Invalid value: must be a finite number"));
+        }
+
+        let unit = Unit::for_name(&unit.as_str_lossy()).ok_or_else(|| {
+            Error::range_error(format!("Invalid unit argument: {}", unit.as_str_lossy()))
+        })?;
+
+        let parts = format_relative(&self.locale, self.style, unit, self.numeric, value)?;
+
+        Ok((unit, parts))
+    }
 }
 
+// https://tc39.es/ecma402/#sec-intl-relativetimeformat-constructor
 #[props(intrinsic_name = intl_relative_time_format, to_string_tag = "Intl.RelativeTimeFormat")]
 impl RelativeTimeFormat {
     #[constructor]
     fn construct(
-        _locales: Option<String>,
-        _options: Option<RelativeTimeFormatOptions>,
+        locales: &Value,
+        options: Option<RelativeTimeFormatOptions>,
         realm: &mut Realm,
     ) -> Res<ObjectHandle> {
-        Ok(Self::new(realm)?.into_object())
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .unwrap_or(locale!("en"));
+
+        let opts = options.unwrap_or_default();
+
+        let style = opts.style.unwrap_or(Style::Long);
+        let numeric = opts.numeric.unwrap_or_default();
+
+        Ok(Self::new(locale, style, numeric, realm)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.RelativeTimeFormat.supportedLocalesOf
     #[prop("supportedLocalesOf")]
     fn supported_locales_of(
-        _locales: String,
+        locales: &Value,
         _options: Option<LocaleMatcherOptions>,
-    ) -> Vec<String> {
-        Vec::new()
+        realm: &mut Realm,
+    ) -> Res<Vec<String>> {
+        Ok(canonicalize_locale_list(locales, realm)?
+            .iter()
+            .filter_map(|locale| locale.parse::<Locale>().ok())
+            .filter(|locale| {
+                icu_formatter(locale, Style::Long, Unit::Second, Numeric::Always).is_ok()
+            })
+            .map(|locale| locale.to_string())
+            .collect())
     }
 
-    fn format(&self, _value: f32, _unit: &str) -> String {
-        String::new()
+    // https://tc39.es/ecma402/#sec-Intl.RelativeTimeFormat.prototype.format
+    fn format(&self, value: &Value, unit: &Value, realm: &mut Realm) -> Res<String> {
+        let (_, parts) = self.partition(value, unit, realm)?;
+
+        Ok(parts.into_iter().map(|(_, value)| value).collect())
     }
 
+    // https://tc39.es/ecma402/#sec-Intl.RelativeTimeFormat.prototype.formatToParts
     #[prop("formatToParts")]
-    fn format_to_parts(&self, _value: f32, _unit: &str, realm: &mut Realm) -> Res<ObjectHandle> {
-        Ok(Array::from_realm(realm)?.into_object())
+    fn format_to_parts(&self, value: &Value, unit: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        let (unit, parts) = self.partition(value, unit, realm)?;
+
+        let mut elements = Vec::with_capacity(parts.len());
+
+        for (ty, value) in parts {
+            let part = Object::new(realm);
+
+            part.define_property("type".into(), ty.into(), realm)?;
+            part.define_property("value".into(), value.into(), realm)?;
+
+            if ty != "literal" {
+                part.define_property("unit".into(), unit.name().into(), realm)?;
+            }
+
+            elements.push(part.into());
+        }
+
+        Ok(Array::with_elements(realm, elements)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-intl.relativetimeformat.prototype.resolvedoptions
     #[prop("resolvedOptions")]
-    fn resolved_options(&self, realm: &Realm) -> ObjectHandle {
-        Object::new(realm)
+    fn resolved_options(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let options = Object::new(realm);
+
+        options.define_property("locale".into(), self.locale.to_string().into(), realm)?;
+        options.define_property("style".into(), self.style.into_value(), realm)?;
+        options.define_property("numeric".into(), self.numeric.into_value(), realm)?;
+        options.define_property("numberingSystem".into(), "latn".into(), realm)?;
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(
+        style: Style,
+        unit: Unit,
+        numeric: Numeric,
+        value: f64,
+    ) -> Vec<(&'static str, String)> {
+        format_relative(&locale!("en"), style, unit, numeric, value).expect("relative time data")
+    }
+
+    fn join(parts: &[(&'static str, String)]) -> String {
+        parts.iter().map(|(_, value)| value.as_str()).collect()
+    }
+
+    #[test]
+    fn numeric() {
+        assert_eq!(
+            join(&format(Style::Long, Unit::Day, Numeric::Always, -3.0)),
+            "3 days ago"
+        );
+        assert_eq!(
+            join(&format(Style::Long, Unit::Hour, Numeric::Always, 1.0)),
+            "in 1 hour"
+        );
+        assert_eq!(
+            join(&format(Style::Long, Unit::Day, Numeric::Always, -0.0)),
+            "0 days ago"
+        );
+    }
+
+    #[test]
+    fn auto() {
+        assert_eq!(
+            join(&format(Style::Long, Unit::Day, Numeric::Auto, -1.0)),
+            "yesterday"
+        );
+        assert_eq!(
+            join(&format(Style::Long, Unit::Day, Numeric::Auto, 2.0)),
+            "in 2 days"
+        );
+    }
+
+    #[test]
+    fn parts() {
+        assert_eq!(
+            format(Style::Long, Unit::Second, Numeric::Always, 1234.5),
+            [
+                ("literal", "in ".to_string()),
+                ("integer", "1".to_string()),
+                ("group", ",".to_string()),
+                ("integer", "234".to_string()),
+                ("decimal", ".".to_string()),
+                ("fraction", "5".to_string()),
+                ("literal", " seconds".to_string()),
+            ]
+        );
+        assert_eq!(
+            format(Style::Long, Unit::Day, Numeric::Auto, 0.0),
+            [("literal", "today".to_string())]
+        );
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Unit::for_name("quarters"), Some(Unit::Quarter));
+        assert_eq!(Unit::for_name("Days"), None);
+        assert_eq!(Unit::Week.name(), "week");
     }
 }
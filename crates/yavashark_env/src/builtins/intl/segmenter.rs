@@ -1,16 +1,27 @@
-use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions};
-use crate::{MutObject, Object, ObjectHandle, Realm, Res};
-use std::cell::RefCell;
+use crate::builtins::intl::utils::{LocaleMatcher, LocaleMatcherOptions, canonicalize_locale_list};
+use crate::builtins::iterator::Iterator as IteratorIntrinsic;
+use crate::builtins::{create_iter_result, to_integer_or_infinity};
+use crate::value::{IntoValue, Obj};
+use crate::{MutObject, Object, ObjectHandle, Realm, Res, Value, ValueResult};
+use icu::locale::Locale;
+use icu::segmenter::options::{SentenceBreakInvariantOptions, WordBreakInvariantOptions};
+use icu::segmenter::{GraphemeClusterSegmenter, SentenceSegmenter, WordSegmenter};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 use yavashark_macro::{data_object, object, props};
+use yavashark_string::YSString;
 
-#[data_object]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[data_object(error = "range")]
 pub enum Granularity {
+    #[default]
     Grapheme,
     Word,
     Sentence,
 }
 
 #[data_object]
+#[derive(Debug, Default)]
 pub struct SegmenterOptions {
     #[prop("localeMatcher")]
     pub locale_matcher: Option<LocaleMatcher>,
@@ -19,10 +30,13 @@ pub struct SegmenterOptions {
 
 #[object]
 #[derive(Debug)]
-pub struct Segmenter {}
+pub struct Segmenter {
+    locale: String,
+    granularity: Granularity,
+}
 
 impl Segmenter {
-    pub fn new(realm: &mut Realm) -> Res<Self> {
+    pub fn new(locale: String, granularity: Granularity, realm: &mut Realm) -> Res<Self> {
         Ok(Self {
             inner: RefCell::new(MutableSegmenter {
                 object: MutObject::with_proto(
@@ -34,35 +48,346 @@ impl Segmenter {
                         .clone(),
                 ),
             }),
+            locale,
+            granularity,
         })
     }
 }
 
+// https://tc39.es/ecma402/#sec-intl-segmenter-constructor
 #[props(intrinsic_name = intl_segmenter, to_string_tag = "Intl.Segmenter")]
 impl Segmenter {
     #[constructor]
     fn construct(
-        _locales: Option<String>,
-        _options: Option<SegmenterOptions>,
+        locales: &Value,
+        options: Option<SegmenterOptions>,
         realm: &mut Realm,
-    ) -> Res<Self> {
-        Self::new(realm)
+    ) -> Res<ObjectHandle> {
+        let locale = canonicalize_locale_list(locales, realm)?
+            .iter()
+            .find_map(|locale| locale.parse::<Locale>().ok())
+            .map_or_else(|| "en".to_string(), |locale| locale.to_string());
+
+        let granularity = options.unwrap_or_default().granularity.unwrap_or_default();
+
+        Ok(Self::new(locale, granularity, realm)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-intl.segmenter.supportedlocalesof
     #[prop("supportedLocalesOf")]
     fn supported_locales_of(
-        _locales: String,
+        locales: &Value,
         _options: Option<LocaleMatcherOptions>,
-    ) -> Vec<String> {
-        Vec::new()
+        realm: &mut Realm,
+    ) -> Res<Vec<String>> {
+        // the segmentation rules are locale-independent, so every valid locale is supported
+        Ok(canonicalize_locale_list(locales, realm)?
+            .iter()
+            .filter_map(|locale| locale.parse::<Locale>().ok())
+            .map(|locale| locale.to_string())
+            .collect())
     }
 
-    fn segment(&self, _duration: ObjectHandle) -> Vec<String> {
-        Vec::new()
+    // https://tc39.es/ecma402/#sec-intl.segmenter.prototype.segment
+    fn segment(&self, string: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        let string = string.to_string(realm)?;
+        let data = SegmentData::new(string, self.granularity);
+
+        Ok(Segments::new(Rc::new(data), realm)?.into_object())
     }
 
+    // https://tc39.es/ecma402/#sec-intl.segmenter.prototype.resolvedoptions
     #[prop("resolvedOptions")]
-    fn resolved_options(&self, realm: &Realm) -> ObjectHandle {
-        Object::new(realm)
+    fn resolved_options(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let options = Object::new(realm);
+
+        options.define_property("locale".into(), self.locale.clone().into(), realm)?;
+        options.define_property("granularity".into(), self.granularity.into_value(), realm)?;
+
+        Ok(options)
+    }
+}
+
+/// A segmented string, shared by a `Segments` object and its iterators.
+#[derive(Debug)]
+pub struct SegmentData {
+    string: YSString,
+    units: Vec<u16>,
+    granularity: Granularity,
+    /// Segment boundaries as UTF-16 indices, starting with 0 and ending with
+    /// the length of the string.
+    breaks: Vec<usize>,
+    /// Whether the segment ending at the same index in `breaks` is word-like,
+    /// only filled for word granularity.
+    word_like: Vec<bool>,
+}
+
+impl SegmentData {
+    #[must_use]
+    pub fn new(string: YSString, granularity: Granularity) -> Self {
+        let units = string.code_units().collect::<Vec<_>>();
+
+        let mut word_like = Vec::new();
+
+        let mut breaks = match granularity {
+            Granularity::Grapheme => GraphemeClusterSegmenter::new()
+                .segment_utf16(&units)
+                .collect(),
+            Granularity::Word => WordSegmenter::new_auto(WordBreakInvariantOptions::default())
+                .segment_utf16(&units)
+                .iter_with_word_type()
+                .map(|(idx, ty)| {
+                    word_like.push(ty.is_word_like());
+                    idx
+                })
+                .collect(),
+            Granularity::Sentence => {
+                SentenceSegmenter::new(SentenceBreakInvariantOptions::default())
+                    .segment_utf16(&units)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // ICU4X doesn't report any boundary for the empty string
+        if breaks.is_empty() {
+            breaks.push(0);
+        }
+
+        // ICU4X loses the word type of the last word of a dictionary-segmented
+        // run (e.g. CJK), so treat segments starting with a letter or digit as
+        // word-like too
+        for (i, word_like) in word_like.iter_mut().enumerate().skip(1) {
+            if !*word_like {
+                *word_like = char::decode_utf16(units[breaks[i - 1]..breaks[i]].iter().copied())
+                    .next()
+                    .and_then(Result::ok)
+                    .is_some_and(char::is_alphanumeric);
+            }
+        }
+
+        Self {
+            string,
+            units,
+            granularity,
+            breaks,
+            word_like,
+        }
+    }
+
+    /// The number of segments.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.breaks.len() - 1
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Finds the segment containing the UTF-16 index `idx`.
+    #[must_use]
+    pub fn containing(&self, idx: usize) -> Option<usize> {
+        if idx >= self.units.len() {
+            return None;
+        }
+
+        Some(self.breaks.partition_point(|&b| b <= idx) - 1)
+    }
+
+    // https://tc39.es/ecma402/#sec-createsegmentdataobject
+    fn segment_object(&self, segment: usize, realm: &mut Realm) -> Res<ObjectHandle> {
+        let start = self.breaks[segment];
+        let end = self.breaks[segment + 1];
+
+        let obj = Object::new(realm);
+
+        obj.define_property(
+            "segment".into(),
+            YSString::from_utf16(&self.units[start..end]).into(),
+            realm,
+        )?;
+        obj.define_property("index".into(), start.into(), realm)?;
+        obj.define_property("input".into(), self.string.clone().into(), realm)?;
+
+        if self.granularity == Granularity::Word {
+            let word_like = self.word_like.get(segment + 1).copied().unwrap_or(false);
+
+            obj.define_property("isWordLike".into(), word_like.into(), realm)?;
+        }
+
+        Ok(obj)
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct Segments {
+    data: Rc<SegmentData>,
+}
+
+impl Segments {
+    pub fn new(data: Rc<SegmentData>, realm: &mut Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableSegments {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .intl_segments
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+            data,
+        })
+    }
+}
+
+// https://tc39.es/ecma402/#sec-%segmentsprototype%-object
+#[props(intrinsic_name = intl_segments)]
+impl Segments {
+    // https://tc39.es/ecma402/#sec-%segmentsprototype%.containing
+    fn containing(&self, index: &Value, realm: &mut Realm) -> ValueResult {
+        let n = to_integer_or_infinity(index.to_number(realm)?);
+
+        if n < 0.0 {
+            return Ok(Value::Undefined);
+        }
+
+        match self.data.containing(n as usize) {
+            Some(segment) => Ok(self.data.segment_object(segment, realm)?.into()),
+            None => Ok(Value::Undefined),
+        }
+    }
+
+    #[prop(crate::Symbol::ITERATOR)]
+    fn iterator(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        Ok(SegmentIterator::new(Rc::clone(&self.data), realm)?.into_object())
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct SegmentIterator {
+    data: Rc<SegmentData>,
+    /// The index of the next segment to yield.
+    next: Cell<usize>,
+}
+
+impl SegmentIterator {
+    pub fn new(data: Rc<SegmentData>, realm: &mut Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableSegmentIterator {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .intl_segment_iterator
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+            data,
+            next: Cell::new(0),
+        })
+    }
+}
+
+// https://tc39.es/ecma402/#sec-%segmentiteratorprototype%-object
+#[props(
+    intrinsic_name = intl_segment_iterator,
+    extends = IteratorIntrinsic,
+    to_string_tag = "Segmenter String Iterator"
+)]
+impl SegmentIterator {
+    // https://tc39.es/ecma402/#sec-%segmentiteratorprototype%.next
+    fn next(&self, realm: &mut Realm) -> ValueResult {
+        let segment = self.next.get();
+
+        if segment >= self.data.len() {
+            return create_iter_result(Value::Undefined, true, realm);
+        }
+
+        self.next.set(segment + 1);
+
+        let obj = self.data.segment_object(segment, realm)?;
+
+        create_iter_result(obj.into(), false, realm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(input: &str, granularity: Granularity) -> Vec<(String, Option<bool>)> {
+        let data = SegmentData::new(YSString::from_ref(input), granularity);
+
+        (0..data.len())
+            .map(|i| {
+                let segment =
+                    String::from_utf16_lossy(&data.units[data.breaks[i]..data.breaks[i + 1]]);
+                let word_like = data.word_like.get(i + 1).copied();
+
+                (segment, word_like)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn graphemes() {
+        let graphemes = segments("e\u{301}👨‍👩‍👧🇩🇪", Granularity::Grapheme);
+
+        assert_eq!(
+            graphemes
+                .iter()
+                .map(|(s, _)| s.as_str())
+                .collect::<Vec<_>>(),
+            ["e\u{301}", "👨‍👩‍👧", "🇩🇪"]
+        );
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(
+            segments("Hi, 世界!", Granularity::Word),
+            [
+                ("Hi".to_string(), Some(true)),
+                (",".to_string(), Some(false)),
+                (" ".to_string(), Some(false)),
+                ("世界".to_string(), Some(true)),
+                ("!".to_string(), Some(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn sentences() {
+        let sentences = segments("One. Two? Three", Granularity::Sentence);
+
+        assert_eq!(
+            sentences
+                .iter()
+                .map(|(s, _)| s.as_str())
+                .collect::<Vec<_>>(),
+            ["One. ", "Two? ", "Three"]
+        );
+    }
+
+    #[test]
+    fn containing_uses_utf16_indices() {
+        let data = SegmentData::new(YSString::from_ref("a😀b"), Granularity::Grapheme);
+
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.containing(0), Some(0));
+        assert_eq!(data.containing(1), Some(1));
+        assert_eq!(data.containing(2), Some(1));
+        assert_eq!(data.containing(3), Some(2));
+        assert_eq!(data.containing(4), None);
+
+        let empty = SegmentData::new(YSString::new(), Granularity::Word);
+        assert!(empty.is_empty());
+        assert_eq!(empty.containing(0), None);
     }
 }
@@ -1,3 +1,4 @@
+use crate::{Realm, Res, Value};
 use yavashark_macro::data_object;
 
 #[derive(Clone, Copy, Debug)]
@@ -29,3 +30,28 @@ pub enum Style {
 pub struct LocaleMatcherOptions {
     options: LocaleMatcher,
 }
+
+/// Canonicalize a locale list from a Value (string or array)
+pub fn canonicalize_locale_list(locales: &Value, realm: &mut Realm) -> Res<Vec<String>> {
+    if locales.is_undefined() {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+
+    if locales.is_string() {
+        result.push(locales.to_string(realm)?.to_string());
+    } else if let Value::Object(obj) = &locales {
+        let length = obj.get("length", realm)?.to_number(realm)? as usize;
+
+        for i in 0..length {
+            let locale_value = obj.get(i, realm)?;
+            if !locale_value.is_undefined() && !locale_value.is_null() {
+                let locale_str = locale_value.to_string(realm)?;
+                result.push(locale_str.to_string());
+            }
+        }
+    }
+
+    Ok(result)
+}
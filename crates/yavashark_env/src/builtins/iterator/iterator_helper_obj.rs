@@ -45,6 +45,11 @@ impl<T: IteratorHelperImpl> ObjectImpl for IteratorHelperObject<T> {
         self.inner.borrow_mut()
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>> {
+        self.inner.try_borrow().ok()
+    }
+
     fn get_inner(&self) -> impl std::ops::Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
@@ -25,7 +25,7 @@ impl JSON {
         Ok(this.into_object())
     }
 
-    fn value_from_serde(value: serde_json::Value, realm: &mut Realm) -> Res<Value> {
+    pub(crate) fn value_from_serde(value: serde_json::Value, realm: &mut Realm) -> Res<Value> {
         Ok(match value {
             serde_json::Value::Null => Value::Null,
             serde_json::Value::Bool(b) => Value::Boolean(b),
@@ -53,7 +53,7 @@ impl JSON {
         })
     }
 
-    fn value_to_serde(
+    pub(crate) fn value_to_serde(
         value: Value,
         realm: &mut Realm,
         visited: &mut Vec<usize>,
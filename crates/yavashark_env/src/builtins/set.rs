@@ -8,7 +8,7 @@ use yavashark_macro::{object, props};
 #[object]
 #[derive(Debug)]
 pub struct Set {
-    // #[gc(untyped)] //TODO: this is a memleak!
+    #[gc(multi)]
     #[mutable]
     pub set: IndexSet<Value>,
 }
@@ -0,0 +1,248 @@
+use crate::builtins::Promise;
+use crate::error::ErrorKind;
+use crate::value::{Func, Obj};
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value, ValueResult, Variable};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use yavashark_macro::{object, props};
+
+/// A realm a wrapped value lives in. `None` is the realm that created the
+/// `ShadowRealm`.
+///
+/// That realm is borrowed further up the stack while the shadow realm runs,
+/// so functions living in it are called with the realm they got invoked
+/// with. They still see their own globals through their scope.
+type RealmRef = Option<Rc<RefCell<Realm>>>;
+
+// https://tc39.es/proposal-shadowrealm/#sec-getwrappedvalue
+fn get_wrapped_value(
+    value: Value,
+    from: &RealmRef,
+    into: &RealmRef,
+    realm: &mut Realm,
+) -> ValueResult {
+    match value {
+        Value::Object(obj) if obj.is_callable() => {
+            Ok(WrappedFunction::new(obj, from.clone(), into.clone(), realm)?.into())
+        }
+        Value::Object(_) => Err(Error::ty(
+            "only primitives and callables can cross a ShadowRealm boundary",
+        )),
+        value => Ok(value),
+    }
+}
+
+fn boundary_error(err: &Error) -> Error {
+    Error::ty_error(format!(
+        "error thrown across a ShadowRealm boundary: {}",
+        err.message_internal()
+    ))
+}
+
+// https://tc39.es/proposal-shadowrealm/#sec-wrapped-function-exotic-objects
+#[object(function)]
+#[derive(Debug)]
+pub struct WrappedFunction {
+    #[gc]
+    target: ObjectHandle,
+    target_realm: RealmRef,
+    caller_realm: RealmRef,
+}
+
+impl WrappedFunction {
+    // https://tc39.es/proposal-shadowrealm/#sec-wrappedfunctioncreate
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        target: ObjectHandle,
+        target_realm: RealmRef,
+        caller_realm: RealmRef,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        let length = match target
+            .get_opt("length", realm)
+            .map_err(|e| boundary_error(&e))?
+        {
+            Some(Value::Number(n)) if n == f64::INFINITY => n,
+            Some(Value::Number(n)) if n.is_nan() => 0.0,
+            Some(Value::Number(n)) => n.trunc().max(0.0),
+            _ => 0.0,
+        };
+
+        let name = match target
+            .get_opt("name", realm)
+            .map_err(|e| boundary_error(&e))?
+        {
+            Some(name @ Value::String(_)) => name,
+            _ => Value::from(""),
+        };
+
+        let obj = ObjectHandle::new(Self {
+            inner: RefCell::new(MutableWrappedFunction {
+                object: MutObject::with_proto(realm.intrinsics.func.clone()),
+            }),
+            target,
+            target_realm,
+            caller_realm,
+        });
+
+        obj.define_property_attributes("length".into(), Variable::config(length.into()), realm)?;
+        obj.define_property_attributes("name".into(), Variable::config(name), realm)?;
+
+        Ok(obj)
+    }
+
+    fn call_in(&self, args: Vec<Value>, this: Value, target_realm: &mut Realm) -> ValueResult {
+        let args = args
+            .into_iter()
+            .map(|arg| get_wrapped_value(arg, &self.caller_realm, &self.target_realm, target_realm))
+            .collect::<Res<Vec<_>>>()?;
+
+        let this = get_wrapped_value(this, &self.caller_realm, &self.target_realm, target_realm)?;
+
+        self.target
+            .call(args, this, target_realm)
+            .map_err(|e| boundary_error(&e))
+    }
+}
+
+impl Func for WrappedFunction {
+    // https://tc39.es/proposal-shadowrealm/#sec-wrapped-function-exotic-objects-call-thisargument-argumentslist
+    fn call(&self, realm: &mut Realm, args: Vec<Value>, this: Value) -> ValueResult {
+        // plain calls get the global object as `this`, which never crosses
+        let this = match this {
+            Value::Object(obj) if obj == realm.global => Value::Undefined,
+            this => this,
+        };
+
+        let result = match &self.target_realm {
+            Some(target) => match target.try_borrow_mut() {
+                Ok(mut target_realm) => {
+                    let result = self.call_in(args, this, &mut target_realm);
+                    realm.queue.append(&mut target_realm.queue);
+                    result
+                }
+                // we are already running inside the target realm
+                Err(_) => self.call_in(args, this, realm),
+            },
+            None => self.call_in(args, this, realm),
+        }?;
+
+        get_wrapped_value(result, &self.target_realm, &self.caller_realm, realm)
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct ShadowRealm {
+    realm: Rc<RefCell<Realm>>,
+}
+
+impl ShadowRealm {
+    pub fn new(realm: &mut Realm) -> Res<Self> {
+        let shadow_realm = realm.new_child()?;
+
+        Ok(Self {
+            inner: RefCell::new(MutableShadowRealm {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .shadow_realm
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+            realm: Rc::new(RefCell::new(shadow_realm)),
+        })
+    }
+
+    fn run<T>(&self, realm: &mut Realm, f: impl FnOnce(&mut Realm) -> Res<T>) -> Res<T> {
+        let mut shadow_realm = self
+            .realm
+            .try_borrow_mut()
+            .map_err(|_| Error::ty("ShadowRealm is already running"))?;
+
+        let result = f(&mut shadow_realm);
+
+        // the shadow realm shares the job queue of its creator
+        realm.queue.append(&mut shadow_realm.queue);
+
+        result
+    }
+}
+
+#[props(intrinsic_name = shadow_realm, to_string_tag = "ShadowRealm")]
+impl ShadowRealm {
+    #[constructor]
+    fn construct(realm: &mut Realm) -> Res<ObjectHandle> {
+        Ok(Self::new(realm)?.into_object())
+    }
+
+    // https://tc39.es/proposal-shadowrealm/#sec-shadowrealm.prototype.evaluate
+    fn evaluate(&self, source: &Value, #[realm] realm: &mut Realm) -> ValueResult {
+        let Value::String(source) = source else {
+            return Err(Error::ty("ShadowRealm.prototype.evaluate expects a string"));
+        };
+
+        let target = Some(Rc::clone(&self.realm));
+
+        let result = self.run(realm, |shadow_realm| {
+            shadow_realm.eval_script(&source.as_str_lossy(), PathBuf::from("ShadowRealm"))
+        });
+
+        match result {
+            Ok(value) => get_wrapped_value(value, &target, &None, realm),
+            Err(Error {
+                kind: ErrorKind::Syntax(msg),
+                ..
+            }) => Err(Error::syn_error(msg.to_string())),
+            Err(e) => Err(boundary_error(&e)),
+        }
+    }
+
+    // https://tc39.es/proposal-shadowrealm/#sec-shadowrealm.prototype.importvalue
+    #[prop("importValue")]
+    fn import_value(
+        &self,
+        specifier: &Value,
+        export_name: &Value,
+        #[realm] realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        let specifier = specifier.to_string(realm)?;
+
+        let Value::String(export_name) = export_name else {
+            return Err(Error::ty(
+                "ShadowRealm.prototype.importValue expects a string export name",
+            ));
+        };
+
+        let export_name = export_name.as_str_lossy().into_owned();
+        let target = Some(Rc::clone(&self.realm));
+
+        let value = self.run(realm, |shadow_realm| {
+            let cur_path = std::env::current_dir().map_err(|e| Error::new_error(e.to_string()))?;
+            let module = shadow_realm.import_module(&specifier.as_str_lossy(), &cur_path)?;
+
+            let value = if export_name == "default" {
+                module.default
+            } else {
+                module.exports.get_opt(export_name.clone(), shadow_realm)?
+            };
+
+            value.ok_or_else(|| Error::ty_error(format!("module does not export `{export_name}`")))
+        });
+
+        let value = value
+            .map_err(|e| match e.kind {
+                ErrorKind::Type(_) => e,
+                _ => boundary_error(&e),
+            })
+            .and_then(|value| get_wrapped_value(value, &target, &None, realm));
+
+        match value {
+            Ok(value) => Promise::resolved(&value, realm),
+            Err(e) => Promise::from_error(e, realm),
+        }
+    }
+}
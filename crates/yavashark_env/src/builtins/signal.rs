@@ -1,17 +1,24 @@
-#![allow(unused)]
-
 mod computed;
+mod node;
 mod state;
-
-use crate::value::{BoxedObj, Obj};
-use crate::{Object, ObjectHandle, Realm, Res, Value};
-use std::cell::RefCell;
-use yavashark_garbage::Gc;
+mod subtle;
+mod watcher;
 
 use crate::partial_init::Initializer;
-use crate::realm::Intrinsic;
+use crate::{Error, Object, ObjectHandle, Realm, Res, Symbol, Value};
+use std::rc::Rc;
+
 pub use computed::*;
+pub use node::*;
 pub use state::*;
+pub use subtle::*;
+pub use watcher::*;
+
+/// Key of the option that gets called when a signal gains its first live sink.
+pub const WATCHED: &Symbol = &Symbol::new("Signal.subtle.watched");
+
+/// Key of the option that gets called when a signal loses its last live sink.
+pub const UNWATCHED: &Symbol = &Symbol::new("Signal.subtle.unwatched");
 
 pub struct Signal;
 
@@ -26,19 +33,13 @@ pub fn get_signal(realm: &mut Realm) -> Res<ObjectHandle> {
 
     let intrinsics = realm.intrinsics.clone_public();
 
-    let computed = intrinsics.signal_computed.get(realm)?;
-    let proto = ComputedProtoObj {
-        obj: Object::raw_with_proto(realm.intrinsics.obj.clone()),
-        current_dep: RefCell::default(),
-    };
-
-    computed.set_prototype(proto.into_object().into(), realm)?; //TODO find a better way for this
-
-    let computed_constructor = computed
+    let computed_constructor = intrinsics
+        .signal_computed
+        .get(realm)?
         .resolve_property("constructor", realm)?
         .unwrap_or(Value::Undefined);
 
-    obj.define_property("Computed".into(), computed_constructor, realm);
+    obj.define_property("Computed".into(), computed_constructor, realm)?;
 
     let state_constructor = intrinsics
         .signal_state
@@ -46,23 +47,51 @@ pub fn get_signal(realm: &mut Realm) -> Res<ObjectHandle> {
         .resolve_property("constructor", realm)?
         .unwrap_or(Value::Undefined);
 
-    obj.define_property("State".into(), state_constructor, realm);
+    obj.define_property("State".into(), state_constructor, realm)?;
+
+    let subtle = Subtle::new(realm)?;
+
+    obj.define_property("subtle".into(), subtle.into(), realm)?;
 
     Ok(obj)
 }
 
-pub fn notify_dependent(dep: &ObjectHandle, realm: &mut Realm) -> Res<()> {
-    let Some(computed) = dep.downcast::<Computed>() else {
-        return Ok(());
+/// Reads the `equals`, `[Signal.subtle.watched]` and `[Signal.subtle.unwatched]`
+/// options of the `State` and `Computed` constructors.
+pub fn signal_options(options: Option<ObjectHandle>, realm: &mut Realm) -> Res<SignalOptions> {
+    let Some(options) = options else {
+        return Ok(SignalOptions::default());
     };
 
-    computed.dirty.set(true);
+    let callable = |value: Option<Value>, name: &str| match value {
+        None | Some(Value::Undefined) => Ok(None),
+        Some(Value::Object(obj)) if obj.is_callable() => Ok(Some(obj)),
+        Some(_) => Err(Error::ty_error(format!(
+            "Signal option `{name}` must be a function"
+        ))),
+    };
 
-    Ok(())
+    Ok(SignalOptions {
+        equals: callable(options.get_opt("equals", realm)?, "equals")?,
+        watched: callable(options.get_opt(WATCHED, realm)?, "watched")?,
+        unwatched: callable(options.get_opt(UNWATCHED, realm)?, "unwatched")?,
+    })
 }
 
-pub const fn make_dependent(dep: &ObjectHandle, realm: &mut Realm) -> Res<()> {
-    //TODO
+/// The graph node of a `Signal.State`, `Signal.Computed` or `Signal.subtle.Watcher`.
+fn node_of(value: &Value) -> Option<Rc<Node>> {
+    let Value::Object(obj) = value else {
+        return None;
+    };
+
+    if let Some(state) = obj.downcast::<State>() {
+        return Some(Rc::clone(&state.node));
+    }
+
+    if let Some(computed) = obj.downcast::<Computed>() {
+        return Some(Rc::clone(&computed.node));
+    }
 
-    Ok(())
+    obj.downcast::<Watcher>()
+        .map(|watcher| Rc::clone(&watcher.node))
 }
@@ -1,34 +1,18 @@
-use crate::builtins::signal::notify_dependent;
-use crate::value::{BoxedObj, MutObj, Obj};
-use crate::{ControlFlow, GCd, Object};
-use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value, ValueResult};
-use std::cell::{Cell, RefCell};
-use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
-use yavashark_garbage::{Gc, OwningGcGuard, Weak};
+use crate::builtins::signal::{Node, signal_options};
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
 use yavashark_macro::{object, props};
 
 #[object]
 #[derive(Debug)]
 pub struct Computed {
-    #[mutable]
-    pub value: Value,
-
-    pub compute_fn: ObjectHandle,
-
-    pub dirty: Cell<bool>,
-    #[mutable]
-    pub dependents: Vec<Weak<BoxedObj>>, //TODO: this should be Vec<Weak<Computed>> or maybe even Vec<Weak<dyn Signal>> in the future
+    pub node: Rc<Node>,
 }
 
 impl Computed {
-    pub fn new(compute_fn: ObjectHandle, realm: &mut Realm) -> Res<Self> {
-        if !compute_fn.is_callable() {
-            return Err(Error::ty(
-                "Computed constructor expects a function as the first argument",
-            ));
-        }
-
+    pub fn new(node: Rc<Node>, realm: &mut Realm) -> Res<Self> {
         Ok(Self {
             inner: RefCell::new(MutableComputed {
                 object: MutObject::with_proto(
@@ -39,136 +23,43 @@ impl Computed {
                         .get(realm)?
                         .clone(),
                 ),
-                value: Value::Undefined,
-                dependents: Vec::new(),
             }),
-
-            compute_fn,
-            dirty: Cell::new(true),
+            node,
         })
     }
-
-    pub fn get_proto(realm: &mut Realm) -> Res<GCd<ComputedProtoObj>> {
-        let proto = realm
-            .intrinsics
-            .clone_public()
-            .signal_computed
-            .get(realm)?
-            .clone();
-
-        proto
-            .downcast::<ComputedProtoObj>()
-            .ok_or_else(|| Error::ty("Computed prototype is not a ComputedProtoObj"))
-    }
-
-    fn setup_dependency_tracking(realm: &mut Realm, this: &ObjectHandle) -> Res<Option<GCd<Self>>> {
-        let p = Self::get_proto(realm)?;
-
-        let mut dep = p.current_dep.borrow_mut();
-
-        let old = dep.take();
-
-        let new = this
-            .downcast::<Self>()
-            .ok_or_else(|| Error::ty("Computed.get called on non-Computed object"))?;
-
-        //TODO: we somehow also need to remove this from all dependencies of the old computed
-
-        *dep = Some(new);
-
-        Ok(old)
-    }
-
-    fn restore_dependency_tracking(realm: &mut Realm, old: Option<GCd<Self>>) -> Res<()> {
-        let p = Self::get_proto(realm)?;
-
-        let mut dep = p.current_dep.borrow_mut();
-
-        *dep = old;
-
-        Ok(())
-    }
-
-    pub fn add_dependent(&self, dependent: &ObjectHandle) {
-        let mut inner = self.inner.borrow_mut();
-
-        let weak = Gc::downgrade(dependent);
-
-        inner.dependents.push(weak);
-
-        drop(inner);
-    }
 }
 
-#[derive(Debug)]
-pub struct ComputedProtoObj {
-    pub(crate) obj: Object,
-    pub(crate) current_dep: RefCell<Option<GCd<Computed>>>,
-}
-
-impl Deref for ComputedProtoObj {
-    type Target = Object;
-
-    fn deref(&self) -> &Self::Target {
-        &self.obj
-    }
-}
-
-impl crate::value::ObjectImpl for ComputedProtoObj {
-    type Inner = Option<GCd<Computed>>;
-
-    fn get_wrapped_object(&self) -> impl DerefMut<Target = impl MutObj> {
-        self.obj
-            .inner_mut()
-            .expect("TODO: handle this case properly")
-    }
-
-    fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
-        self.current_dep.borrow()
-    }
-
-    fn get_inner_mut(&self) -> impl DerefMut<Target = Self::Inner> {
-        self.current_dep.borrow_mut()
-    }
-}
-
-#[props(intrinsic_name = signal_computed, override_object=ComputedProtoObj)]
+#[props(intrinsic_name = signal_computed)]
 impl Computed {
     #[constructor]
-    pub fn construct(cb: ObjectHandle, realm: &mut Realm) -> Res<ObjectHandle> {
-        let obj = Self::new(cb, realm)?;
-
-        Ok(obj.into_object())
-    }
-
-    pub fn get(&self, realm: &mut Realm, this: Value) -> ValueResult {
-        if self.dirty.replace(false) {
-            let this = this.as_object()?;
-
-            let old = Self::setup_dependency_tracking(realm, this)?;
-
-            if let Some(old) = &old {
-                old.add_dependent(this);
-            }
-
-            let new = self.compute_fn.call(Vec::new(), Value::Undefined, realm)?;
-            Self::restore_dependency_tracking(realm, old)?;
+    pub fn construct(
+        cb: ObjectHandle,
+        options: Option<ObjectHandle>,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        if !cb.is_callable() {
+            return Err(Error::ty(
+                "Computed constructor expects a function as the first argument",
+            ));
+        }
 
-            let mut inner = self.inner.try_borrow_mut()?;
+        let node = Node::computed(cb, signal_options(options, realm)?);
+        let computed = Self::new(Rc::clone(&node), realm)?.into_object();
 
-            inner.value = new;
+        node.set_wrapper(&computed);
 
-            // TODO: what to do if the value is the same?
+        Ok(computed)
+    }
 
-            for dep in &inner.dependents {
-                if let Some(dep) = dep.upgrade() {
-                    notify_dependent(&dep.into(), realm)?;
-                }
-            }
+    pub fn get(&self, #[realm] realm: &mut Realm) -> Res<Value> {
+        if super::in_notification() {
+            return Err(Error::ty(
+                "Reading signals is not permitted during a Watcher callback",
+            ));
         }
 
-        let inner = self.inner.try_borrow()?;
-
-        Ok(inner.value.clone())
+        self.node.update_value_version(realm)?;
+        self.node.accessed(realm)?;
+        self.node.value()
     }
 }
@@ -0,0 +1,710 @@
+//! The reactive graph behind `Signal.State`, `Signal.Computed` and
+//! `Signal.subtle.Watcher`.
+//!
+//! Consumers (computed signals and watchers) hold strong edges to the
+//! producers they read, together with the version they last saw. Producers
+//! only know their *live* consumers, i.e. the ones that are (transitively)
+//! watched by a watcher. Setting a state pushes a dirty flag through the live
+//! consumers and notifies watchers, while values are pulled lazily: a
+//! computed signal only recomputes when one of its producers changed its
+//! version. This keeps the graph glitch-free.
+
+use crate::{Error, ObjectHandle, Realm, Res, Value, WeakObjectHandle};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+thread_local! {
+    static CONTEXT: Context = const {
+        Context {
+            active_consumer: RefCell::new(None),
+            in_notification: Cell::new(false),
+            epoch: Cell::new(0),
+        }
+    };
+}
+
+struct Context {
+    active_consumer: RefCell<Option<Rc<Node>>>,
+    in_notification: Cell<bool>,
+    epoch: Cell<u64>,
+}
+
+fn epoch() -> u64 {
+    CONTEXT.with(|ctx| ctx.epoch.get())
+}
+
+fn increment_epoch() {
+    CONTEXT.with(|ctx| ctx.epoch.set(ctx.epoch.get() + 1));
+}
+
+/// Sets the consumer that records all signal reads and returns the previous one.
+fn set_active_consumer(consumer: Option<Rc<Node>>) -> Option<Rc<Node>> {
+    CONTEXT.with(|ctx| ctx.active_consumer.replace(consumer))
+}
+
+#[must_use]
+pub fn active_consumer() -> Option<Rc<Node>> {
+    CONTEXT.with(|ctx| ctx.active_consumer.borrow().clone())
+}
+
+/// Whether watcher `notify` callbacks are currently running. Signals can't be
+/// read or written during that phase.
+#[must_use]
+pub fn in_notification() -> bool {
+    CONTEXT.with(|ctx| ctx.in_notification.get())
+}
+
+/// Runs `f` without recording signal reads.
+pub fn untracked<T>(f: impl FnOnce() -> T) -> T {
+    let prev = set_active_consumer(None);
+    let res = f();
+    set_active_consumer(prev);
+
+    res
+}
+
+#[derive(Debug)]
+pub enum NodeKind {
+    State,
+    Computed(ObjectHandle),
+    Watcher(ObjectHandle),
+}
+
+#[derive(Debug, Clone)]
+enum NodeValue {
+    Unset,
+    Computing,
+    Value(Value),
+    Errored(Error),
+}
+
+#[derive(Debug)]
+struct Edge {
+    node: Rc<Node>,
+    seen_version: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct SignalOptions {
+    pub equals: Option<ObjectHandle>,
+    pub watched: Option<ObjectHandle>,
+    pub unwatched: Option<ObjectHandle>,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    kind: NodeKind,
+    options: SignalOptions,
+    wrapper: RefCell<Option<WeakObjectHandle>>,
+    value: RefCell<NodeValue>,
+    version: Cell<u64>,
+    last_clean_epoch: Cell<u64>,
+    dirty: Cell<bool>,
+    producers: RefCell<Vec<Edge>>,
+    next_producer: Cell<usize>,
+    live_consumers: RefCell<Vec<Weak<Self>>>,
+}
+
+impl Node {
+    fn new(kind: NodeKind, value: NodeValue, options: SignalOptions) -> Rc<Self> {
+        Rc::new(Self {
+            kind,
+            options,
+            wrapper: RefCell::new(None),
+            value: RefCell::new(value),
+            version: Cell::new(0),
+            last_clean_epoch: Cell::new(0),
+            dirty: Cell::new(true),
+            producers: RefCell::new(Vec::new()),
+            next_producer: Cell::new(0),
+            live_consumers: RefCell::new(Vec::new()),
+        })
+    }
+
+    #[must_use]
+    pub fn state(value: Value, options: SignalOptions) -> Rc<Self> {
+        let node = Self::new(NodeKind::State, NodeValue::Value(value), options);
+        node.dirty.set(false);
+
+        node
+    }
+
+    #[must_use]
+    pub fn computed(compute: ObjectHandle, options: SignalOptions) -> Rc<Self> {
+        Self::new(NodeKind::Computed(compute), NodeValue::Unset, options)
+    }
+
+    #[must_use]
+    pub fn watcher(notify: ObjectHandle) -> Rc<Self> {
+        let node = Self::new(
+            NodeKind::Watcher(notify),
+            NodeValue::Unset,
+            SignalOptions::default(),
+        );
+        node.dirty.set(false);
+
+        node
+    }
+
+    pub fn set_wrapper(&self, wrapper: &ObjectHandle) {
+        *self.wrapper.borrow_mut() = Some(wrapper.downgrade());
+    }
+
+    /// The JS object of this node, if it is still alive.
+    #[must_use]
+    pub fn wrapper(&self) -> Option<ObjectHandle> {
+        self.wrapper
+            .borrow()
+            .as_ref()
+            .and_then(WeakObjectHandle::upgrade)
+    }
+
+    fn this(&self) -> Value {
+        self.wrapper().map_or(Value::Undefined, Into::into)
+    }
+
+    #[must_use]
+    pub const fn is_computed(&self) -> bool {
+        matches!(self.kind, NodeKind::Computed(_))
+    }
+
+    #[must_use]
+    pub const fn is_watcher(&self) -> bool {
+        matches!(self.kind, NodeKind::Watcher(_))
+    }
+
+    #[must_use]
+    pub const fn is_consumer(&self) -> bool {
+        !matches!(self.kind, NodeKind::State)
+    }
+
+    #[must_use]
+    pub const fn is_producer(&self) -> bool {
+        !matches!(self.kind, NodeKind::Watcher(_))
+    }
+
+    #[must_use]
+    pub const fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    fn is_live(&self) -> bool {
+        self.is_watcher() || !self.live_consumers.borrow().is_empty()
+    }
+
+    #[must_use]
+    pub fn sources(&self) -> Vec<Rc<Self>> {
+        self.producers
+            .borrow()
+            .iter()
+            .map(|edge| Rc::clone(&edge.node))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn sinks(&self) -> Vec<Rc<Self>> {
+        self.live_consumers
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    fn equals(&self, old: &Value, new: &Value, realm: &mut Realm) -> Res<bool> {
+        match &self.options.equals {
+            Some(equals) => Ok(equals
+                .call(vec![old.clone(), new.clone()], self.this(), realm)?
+                .is_truthy()),
+            None => Ok(old.same_value(new)),
+        }
+    }
+
+    fn call_hook(&self, hook: Option<&ObjectHandle>, realm: &mut Realm) -> Res {
+        if let Some(hook) = hook {
+            untracked(|| hook.call(Vec::new(), self.this(), realm))?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers a read of this producer in the active consumer.
+    pub fn accessed(self: &Rc<Self>, realm: &mut Realm) -> Res {
+        if in_notification() {
+            return Err(Error::ty(
+                "Reading signals is not permitted during a Watcher callback",
+            ));
+        }
+
+        let Some(consumer) = active_consumer() else {
+            return Ok(());
+        };
+
+        let idx = consumer.next_producer.get();
+
+        let stale = {
+            let mut producers = consumer.producers.borrow_mut();
+            let version = self.version.get();
+
+            match producers
+                .iter()
+                .position(|edge| Rc::ptr_eq(&edge.node, self))
+            {
+                // read twice during the same computation
+                Some(pos) if pos < idx => {
+                    producers[pos].seen_version = version;
+                    return Ok(());
+                }
+                // read in a different order than during the last computation
+                Some(pos) => {
+                    producers.swap(idx, pos);
+                    producers[idx].seen_version = version;
+                    consumer.next_producer.set(idx + 1);
+                    return Ok(());
+                }
+                None => {}
+            }
+
+            consumer.next_producer.set(idx + 1);
+
+            let edge = Edge {
+                node: Rc::clone(self),
+                seen_version: version,
+            };
+
+            if idx < producers.len() {
+                Some(std::mem::replace(&mut producers[idx], edge).node)
+            } else {
+                producers.push(edge);
+                None
+            }
+        };
+
+        if consumer.is_live() {
+            if let Some(stale) = stale {
+                stale.remove_live_consumer(&consumer, realm)?;
+            }
+
+            self.add_live_consumer(&consumer, realm)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_live_consumer(self: &Rc<Self>, consumer: &Rc<Self>, realm: &mut Realm) -> Res {
+        let first = {
+            let mut consumers = self.live_consumers.borrow_mut();
+            consumers.retain(|c| c.strong_count() > 0);
+            consumers.is_empty()
+        };
+
+        if first {
+            self.call_hook(self.options.watched.as_ref(), realm)?;
+
+            for producer in self.sources() {
+                producer.add_live_consumer(self, realm)?;
+            }
+        }
+
+        self.live_consumers
+            .borrow_mut()
+            .push(Rc::downgrade(consumer));
+
+        Ok(())
+    }
+
+    fn remove_live_consumer(self: &Rc<Self>, consumer: &Rc<Self>, realm: &mut Realm) -> Res {
+        let last = {
+            let mut consumers = self.live_consumers.borrow_mut();
+
+            if let Some(idx) = consumers
+                .iter()
+                .position(|c| std::ptr::eq(c.as_ptr(), Rc::as_ptr(consumer)))
+            {
+                consumers.swap_remove(idx);
+            }
+
+            consumers.retain(|c| c.strong_count() > 0);
+            consumers.is_empty()
+        };
+
+        if last {
+            self.call_hook(self.options.unwatched.as_ref(), realm)?;
+
+            for producer in self.sources() {
+                producer.remove_live_consumer(self, realm)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Brings the value of a computed signal up to date, recomputing it only
+    /// if one of its sources changed.
+    pub fn update_value_version(self: &Rc<Self>, realm: &mut Realm) -> Res {
+        if !self.is_computed() {
+            return Ok(());
+        }
+
+        if self.is_live() && !self.dirty.get() {
+            return Ok(());
+        }
+
+        if !self.dirty.get() && self.last_clean_epoch.get() == epoch() {
+            return Ok(());
+        }
+
+        let must_recompute = matches!(
+            *self.value.borrow(),
+            NodeValue::Unset | NodeValue::Computing
+        );
+
+        if must_recompute || self.poll_producers_for_change(realm)? {
+            self.recompute(realm)?;
+        }
+
+        self.dirty.set(false);
+        self.last_clean_epoch.set(epoch());
+
+        Ok(())
+    }
+
+    fn poll_producers_for_change(&self, realm: &mut Realm) -> Res<bool> {
+        let edges = self
+            .producers
+            .borrow()
+            .iter()
+            .map(|edge| (Rc::clone(&edge.node), edge.seen_version))
+            .collect::<Vec<_>>();
+
+        for (producer, seen_version) in edges {
+            if producer.version.get() != seen_version {
+                return Ok(true);
+            }
+
+            producer.update_value_version(realm)?;
+
+            if producer.version.get() != seen_version {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn recompute(self: &Rc<Self>, realm: &mut Realm) -> Res {
+        let NodeKind::Computed(compute) = &self.kind else {
+            return Ok(());
+        };
+
+        let old = self.value.replace(NodeValue::Computing);
+
+        if matches!(old, NodeValue::Computing) {
+            return Err(Error::new("Detected cycle in computations"));
+        }
+
+        let prev = set_active_consumer(Some(Rc::clone(self)));
+        self.next_producer.set(0);
+
+        let mut was_equal = false;
+
+        let new = match compute.call(Vec::new(), self.this(), realm) {
+            Ok(new) => {
+                let equals = match &old {
+                    NodeValue::Value(old) => self.equals(old, &new, realm),
+                    _ => Ok(false),
+                };
+
+                match equals {
+                    Ok(equal) => {
+                        was_equal = equal;
+                        NodeValue::Value(new)
+                    }
+                    Err(e) => NodeValue::Errored(e),
+                }
+            }
+            Err(e) => NodeValue::Errored(e),
+        };
+
+        set_active_consumer(prev);
+        let trimmed = self.trim_producers(realm);
+
+        if was_equal {
+            *self.value.borrow_mut() = old;
+        } else {
+            *self.value.borrow_mut() = new;
+            self.version.set(self.version.get() + 1);
+        }
+
+        trimmed
+    }
+
+    /// Drops the edges to producers that weren't read during the last computation.
+    fn trim_producers(self: &Rc<Self>, realm: &mut Realm) -> Res {
+        let stale = self
+            .producers
+            .borrow_mut()
+            .split_off(self.next_producer.get());
+
+        if self.is_live() {
+            for edge in stale {
+                edge.node.remove_live_consumer(self, realm)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The current value of a state or computed signal, rethrowing the error
+    /// of a failed computation.
+    pub fn value(&self) -> Res<Value> {
+        match &*self.value.borrow() {
+            NodeValue::Value(value) => Ok(value.clone()),
+            NodeValue::Errored(e) => Err(e.clone()),
+            NodeValue::Unset | NodeValue::Computing => Ok(Value::Undefined),
+        }
+    }
+
+    /// Sets the value of a state signal, notifying its live consumers when it
+    /// changed.
+    pub fn set_value(self: &Rc<Self>, value: Value, realm: &mut Realm) -> Res {
+        if in_notification() {
+            return Err(Error::ty(
+                "Writing to signals is not permitted during a Watcher callback",
+            ));
+        }
+
+        if active_consumer().is_some_and(|consumer| consumer.is_computed()) {
+            return Err(Error::ty(
+                "Writing to signals is not permitted during a Computed evaluation",
+            ));
+        }
+
+        let old = self.value()?;
+
+        if self.equals(&old, &value, realm)? {
+            return Ok(());
+        }
+
+        *self.value.borrow_mut() = NodeValue::Value(value);
+        self.version.set(self.version.get() + 1);
+        increment_epoch();
+
+        self.notify_consumers(realm)
+    }
+
+    fn notify_consumers(&self, realm: &mut Realm) -> Res {
+        let prev = CONTEXT.with(|ctx| ctx.in_notification.replace(true));
+
+        let res = self
+            .sinks()
+            .iter()
+            .filter(|consumer| !consumer.dirty.get())
+            .try_for_each(|consumer| consumer.mark_dirty(realm));
+
+        CONTEXT.with(|ctx| ctx.in_notification.set(prev));
+
+        res
+    }
+
+    fn mark_dirty(&self, realm: &mut Realm) -> Res {
+        self.dirty.set(true);
+        self.notify_consumers(realm)?;
+
+        if let NodeKind::Watcher(notify) = &self.kind {
+            notify.call(Vec::new(), self.this(), realm)?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts watching `producer` from this watcher.
+    pub fn watch(self: &Rc<Self>, producer: &Rc<Self>, realm: &mut Realm) -> Res {
+        let exists = self
+            .producers
+            .borrow()
+            .iter()
+            .any(|edge| Rc::ptr_eq(&edge.node, producer));
+
+        if exists {
+            return Ok(());
+        }
+
+        self.producers.borrow_mut().push(Edge {
+            node: Rc::clone(producer),
+            seen_version: producer.version.get(),
+        });
+
+        producer.add_live_consumer(self, realm)
+    }
+
+    /// Stops watching `producer` from this watcher.
+    pub fn unwatch(self: &Rc<Self>, producer: &Rc<Self>, realm: &mut Realm) -> Res {
+        let removed = {
+            let mut producers = self.producers.borrow_mut();
+
+            producers
+                .iter()
+                .position(|edge| Rc::ptr_eq(&edge.node, producer))
+                .map(|idx| producers.remove(idx))
+        };
+
+        if removed.is_some() {
+            producer.remove_live_consumer(self, realm)?;
+        }
+
+        Ok(())
+    }
+
+    /// Re-arms a watcher, so it gets notified again on the next change.
+    pub fn clear_dirty(&self) {
+        self.dirty.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NativeFunction;
+
+    fn computed(realm: &mut Realm, f: impl Fn(&mut Realm) -> Res<Value> + 'static) -> Rc<Node> {
+        let compute = NativeFunction::new("compute", move |_, _, realm| f(realm), realm);
+
+        Node::computed(compute, SignalOptions::default())
+    }
+
+    fn get(node: &Rc<Node>, realm: &mut Realm) -> Res<Value> {
+        node.update_value_version(realm)?;
+        node.accessed(realm)?;
+        node.value()
+    }
+
+    fn number(node: &Rc<Node>, realm: &mut Realm) -> Res<f64> {
+        match get(node, realm)? {
+            Value::Number(n) => Ok(n),
+            _ => Err(Error::ty("expected a number")),
+        }
+    }
+
+    #[test]
+    fn computed_is_lazy_and_cached() {
+        let mut realm = Realm::new().expect("realm");
+        let realm = &mut realm;
+
+        let runs = Rc::new(Cell::new(0));
+        let state = Node::state(Value::Number(1.0), SignalOptions::default());
+
+        let double = {
+            let state = Rc::clone(&state);
+            let runs = Rc::clone(&runs);
+
+            computed(realm, move |realm| {
+                runs.set(runs.get() + 1);
+                Ok(Value::Number(number(&state, realm)? * 2.0))
+            })
+        };
+
+        assert_eq!(runs.get(), 0);
+        assert_eq!(get(&double, realm), Ok(Value::Number(2.0)));
+        assert_eq!(get(&double, realm), Ok(Value::Number(2.0)));
+        assert_eq!(runs.get(), 1);
+
+        state.set_value(Value::Number(1.0), realm).expect("set");
+        assert_eq!(get(&double, realm), Ok(Value::Number(2.0)));
+        assert_eq!(runs.get(), 1);
+
+        state.set_value(Value::Number(5.0), realm).expect("set");
+        assert_eq!(get(&double, realm), Ok(Value::Number(10.0)));
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn diamond_recomputes_once() {
+        let mut realm = Realm::new().expect("realm");
+        let realm = &mut realm;
+
+        let runs = Rc::new(Cell::new(0));
+        let state = Node::state(Value::Number(1.0), SignalOptions::default());
+
+        let left = {
+            let state = Rc::clone(&state);
+            computed(realm, move |realm| get(&state, realm))
+        };
+
+        let right = {
+            let state = Rc::clone(&state);
+            computed(realm, move |realm| get(&state, realm))
+        };
+
+        let sum = {
+            let (left, right) = (Rc::clone(&left), Rc::clone(&right));
+            let runs = Rc::clone(&runs);
+
+            computed(realm, move |realm| {
+                runs.set(runs.get() + 1);
+                let left = number(&left, realm)?;
+                let right = number(&right, realm)?;
+
+                Ok(Value::Number(left + right))
+            })
+        };
+
+        assert_eq!(get(&sum, realm), Ok(Value::Number(2.0)));
+
+        state.set_value(Value::Number(2.0), realm).expect("set");
+        assert_eq!(get(&sum, realm), Ok(Value::Number(4.0)));
+        assert_eq!(runs.get(), 2);
+        assert_eq!(sum.sources().len(), 2);
+    }
+
+    #[test]
+    fn watcher_is_notified_once_until_rearmed() {
+        let mut realm = Realm::new().expect("realm");
+        let realm = &mut realm;
+
+        let notified = Rc::new(Cell::new(0));
+
+        let notify = {
+            let notified = Rc::clone(&notified);
+
+            NativeFunction::new(
+                "notify",
+                move |_, _, _| {
+                    notified.set(notified.get() + 1);
+                    Ok(Value::Undefined)
+                },
+                realm,
+            )
+        };
+
+        let watcher = Node::watcher(notify);
+        let state = Node::state(Value::Number(1.0), SignalOptions::default());
+
+        let plus_one = {
+            let state = Rc::clone(&state);
+            computed(realm, move |realm| {
+                Ok(Value::Number(number(&state, realm)? + 1.0))
+            })
+        };
+
+        watcher.watch(&plus_one, realm).expect("watch");
+        assert_eq!(state.sinks().len(), 0);
+
+        get(&plus_one, realm).expect("get");
+        assert_eq!(state.sinks().len(), 1);
+
+        state.set_value(Value::Number(2.0), realm).expect("set");
+        state.set_value(Value::Number(3.0), realm).expect("set");
+        assert_eq!(notified.get(), 1);
+        assert!(plus_one.is_dirty());
+
+        assert_eq!(get(&plus_one, realm), Ok(Value::Number(4.0)));
+        watcher.clear_dirty();
+
+        state.set_value(Value::Number(4.0), realm).expect("set");
+        assert_eq!(notified.get(), 2);
+
+        watcher.unwatch(&plus_one, realm).expect("unwatch");
+        assert!(state.sinks().is_empty());
+        assert!(plus_one.sinks().is_empty());
+    }
+}
@@ -1,23 +1,18 @@
-use crate::builtins::signal::computed::Computed;
-use crate::builtins::signal::notify_dependent;
-use crate::value::{BoxedObj, Obj};
+use crate::builtins::signal::{Node, signal_options};
+use crate::value::Obj;
 use crate::{MutObject, ObjectHandle, Realm, Res, Value};
 use std::cell::RefCell;
-use yavashark_garbage::Weak;
+use std::rc::Rc;
 use yavashark_macro::{object, props};
 
 #[object]
 #[derive(Debug)]
 pub struct State {
-    #[mutable]
-    pub value: Value,
-
-    #[mutable]
-    pub dependents: Vec<Weak<BoxedObj>>, //TODO: this should be Vec<Weak<Computed>> or maybe even Vec<Weak<dyn Signal>> in the future
+    pub node: Rc<Node>,
 }
 
 impl State {
-    pub fn new(value: Value, realm: &mut Realm) -> Res<Self> {
+    pub fn new(node: Rc<Node>, realm: &mut Realm) -> Res<Self> {
         Ok(Self {
             inner: RefCell::new(MutableState {
                 object: MutObject::with_proto(
@@ -28,9 +23,8 @@ impl State {
                         .get(realm)?
                         .clone(),
                 ),
-                value,
-                dependents: Vec::new(),
             }),
+            node,
         })
     }
 }
@@ -40,55 +34,23 @@ impl State {
     #[constructor]
     pub fn construct(
         value: Value,
-        _options: Option<ObjectHandle>,
+        options: Option<ObjectHandle>,
         realm: &mut Realm,
     ) -> Res<ObjectHandle> {
-        let state = Self::new(value, realm)?;
-
-        Ok(state.into_object())
-    }
-
-    pub fn get(&self, realm: &mut Realm, this: Value) -> Res<Value> {
-        let computed_proto = Computed::get_proto(realm)?;
-
-        if let Some(current_dep) = &*computed_proto.current_dep.borrow() {
-            let weak = current_dep.gc().downgrade();
-
-            let mut inner = self.inner.try_borrow_mut()?;
-
-            if !inner.dependents.iter().any(|d| d.ptr_eq(&weak)) {
-                inner.dependents.push(weak);
-            }
+        let node = Node::state(value, signal_options(options, realm)?);
+        let state = Self::new(Rc::clone(&node), realm)?.into_object();
 
-            return Ok(inner.value.clone());
-        }
+        node.set_wrapper(&state);
 
-        let inner = self.inner.try_borrow()?;
-
-        Ok(inner.value.clone())
+        Ok(state)
     }
 
-    pub fn set(&self, value: Value, realm: &mut Realm) -> Res<()> {
-        let mut inner = self.inner.try_borrow_mut()?;
-
-        inner.value = value;
-
-        let mut err = None;
-
-        inner.dependents.retain(|dep| {
-            if err.is_some() {
-                return false;
-            }
-
-            dep.upgrade().is_some_and(|dep| {
-                if let Err(error) = notify_dependent(&dep.into(), realm) {
-                    err = Some(error);
-                }
-
-                true
-            })
-        });
+    pub fn get(&self, #[realm] realm: &mut Realm) -> Res<Value> {
+        self.node.accessed(realm)?;
+        self.node.value()
+    }
 
-        err.map_or(Ok(()), Err)
+    pub fn set(&self, value: Value, #[realm] realm: &mut Realm) -> Res<()> {
+        self.node.set_value(value, realm)
     }
 }
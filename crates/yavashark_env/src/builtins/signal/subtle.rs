@@ -0,0 +1,102 @@
+use crate::array::Array;
+use crate::builtins::signal::{Node, UNWATCHED, WATCHED, active_consumer, node_of, untracked};
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Symbol, Value, ValueResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+use yavashark_macro::{object, properties_new};
+
+#[object]
+#[derive(Debug)]
+pub struct Subtle {}
+
+impl Subtle {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(realm: &mut Realm) -> Res<ObjectHandle> {
+        let mut this = Self {
+            inner: RefCell::new(MutableSubtle {
+                object: MutObject::with_proto(realm.intrinsics.obj.clone()),
+            }),
+        };
+
+        this.initialize(realm)?;
+
+        let this = this.into_object();
+
+        let watcher = realm
+            .intrinsics
+            .clone_public()
+            .signal_watcher
+            .get(realm)?
+            .resolve_property("constructor", realm)?
+            .unwrap_or(Value::Undefined);
+
+        this.define_property("Watcher".into(), watcher, realm)?;
+
+        Ok(this)
+    }
+}
+
+fn wrappers(nodes: &[Rc<Node>], realm: &mut Realm) -> Res<ObjectHandle> {
+    let values = nodes
+        .iter()
+        .filter_map(|node| node.wrapper().map(Into::into))
+        .collect();
+
+    Ok(Array::with_elements(realm, values)?.into_object())
+}
+
+fn producer(value: &Value) -> Res<Rc<Node>> {
+    node_of(value)
+        .filter(|node| node.is_producer())
+        .ok_or(Error::ty("expected a Signal.State or Signal.Computed"))
+}
+
+fn consumer(value: &Value) -> Res<Rc<Node>> {
+    node_of(value)
+        .filter(|node| node.is_consumer())
+        .ok_or(Error::ty(
+            "expected a Signal.Computed or Signal.subtle.Watcher",
+        ))
+}
+
+#[properties_new(raw)]
+impl Subtle {
+    #[prop("watched")]
+    const WATCHED: &'static Symbol = WATCHED;
+
+    #[prop("unwatched")]
+    const UNWATCHED: &'static Symbol = UNWATCHED;
+
+    pub fn untrack(cb: &ObjectHandle, #[realm] realm: &mut Realm) -> ValueResult {
+        untracked(|| cb.call(Vec::new(), Value::Undefined, realm))
+    }
+
+    #[prop("currentComputed")]
+    pub fn current_computed() -> Value {
+        active_consumer()
+            .filter(|node| node.is_computed())
+            .and_then(|node| node.wrapper())
+            .map_or(Value::Undefined, Into::into)
+    }
+
+    #[prop("introspectSources")]
+    pub fn introspect_sources(sink: &Value, #[realm] realm: &mut Realm) -> Res<ObjectHandle> {
+        wrappers(&consumer(sink)?.sources(), realm)
+    }
+
+    #[prop("introspectSinks")]
+    pub fn introspect_sinks(signal: &Value, #[realm] realm: &mut Realm) -> Res<ObjectHandle> {
+        wrappers(&producer(signal)?.sinks(), realm)
+    }
+
+    #[prop("hasSinks")]
+    pub fn has_sinks(signal: &Value) -> Res<bool> {
+        Ok(!producer(signal)?.sinks().is_empty())
+    }
+
+    #[prop("hasSources")]
+    pub fn has_sources(sink: &Value) -> Res<bool> {
+        Ok(!consumer(sink)?.sources().is_empty())
+    }
+}
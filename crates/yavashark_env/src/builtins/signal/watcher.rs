@@ -0,0 +1,102 @@
+use crate::array::Array;
+use crate::builtins::signal::{Node, in_notification, node_of};
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+use yavashark_macro::{object, props};
+
+#[object]
+#[derive(Debug)]
+pub struct Watcher {
+    pub node: Rc<Node>,
+}
+
+impl Watcher {
+    pub fn new(node: Rc<Node>, realm: &mut Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableWatcher {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .signal_watcher
+                        .get(realm)?
+                        .clone(),
+                ),
+            }),
+            node,
+        })
+    }
+
+    fn producers(signals: Vec<Value>) -> Res<Vec<Rc<Node>>> {
+        if in_notification() {
+            return Err(Error::ty(
+                "Watching signals is not permitted during a Watcher callback",
+            ));
+        }
+
+        signals
+            .into_iter()
+            .map(|signal| {
+                node_of(&signal)
+                    .filter(|node| node.is_producer())
+                    .ok_or(Error::ty(
+                        "Watcher expects Signal.State or Signal.Computed arguments",
+                    ))
+            })
+            .collect()
+    }
+}
+
+#[props(intrinsic_name = signal_watcher)]
+impl Watcher {
+    #[constructor]
+    pub fn construct(notify: ObjectHandle, realm: &mut Realm) -> Res<ObjectHandle> {
+        if !notify.is_callable() {
+            return Err(Error::ty(
+                "Watcher constructor expects a function as the first argument",
+            ));
+        }
+
+        let node = Node::watcher(notify);
+        let watcher = Self::new(Rc::clone(&node), realm)?.into_object();
+
+        node.set_wrapper(&watcher);
+
+        Ok(watcher)
+    }
+
+    pub fn watch(&self, signals: Vec<Value>, #[realm] realm: &mut Realm) -> Res<()> {
+        let producers = Self::producers(signals)?;
+
+        self.node.clear_dirty();
+
+        for producer in producers {
+            self.node.watch(&producer, realm)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn unwatch(&self, signals: Vec<Value>, #[realm] realm: &mut Realm) -> Res<()> {
+        for producer in Self::producers(signals)? {
+            self.node.unwatch(&producer, realm)?;
+        }
+
+        Ok(())
+    }
+
+    #[prop("getPending")]
+    pub fn get_pending(&self, #[realm] realm: &mut Realm) -> Res<ObjectHandle> {
+        let pending = self
+            .node
+            .sources()
+            .iter()
+            .filter(|node| node.is_dirty())
+            .filter_map(|node| node.wrapper().map(Into::into))
+            .collect();
+
+        Ok(Array::with_elements(realm, pending)?.into_object())
+    }
+}
@@ -66,6 +66,14 @@ impl crate::value::ObjectImpl for StringObj {
         RefMut::map(self.inner.borrow_mut(), |inner| &mut inner.object)
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>> {
+        self.inner
+            .try_borrow()
+            .ok()
+            .map(|inner| std::cell::Ref::map(inner, |inner| &inner.object))
+    }
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
@@ -275,10 +275,40 @@ impl Duration {
     }
 
     #[prop("toLocaleString")]
-    fn to_locale_string(&self) -> String {
-        let dur = self.dur;
+    fn to_locale_string(
+        &self,
+        locales: &Value,
+        options: Value,
+        #[realm] realm: &mut Realm,
+    ) -> Res<String> {
+        #[cfg(feature = "icu")]
+        {
+            let dur = self.dur;
+
+            #[allow(clippy::cast_precision_loss)]
+            let values = [
+                dur.years() as f64,
+                dur.months() as f64,
+                dur.weeks() as f64,
+                dur.days() as f64,
+                dur.hours() as f64,
+                dur.minutes() as f64,
+                dur.seconds() as f64,
+                dur.milliseconds() as f64,
+                dur.microseconds() as f64,
+                dur.nanoseconds() as f64,
+            ];
+
+            crate::builtins::intl::DurationFormat::with_locales(locales, options, realm)?
+                .format_values(values)
+        }
 
-        dur.to_string()
+        #[cfg(not(feature = "icu"))]
+        {
+            let _ = (locales, options, realm);
+
+            Ok(self.dur.to_string())
+        }
     }
 
     fn total(&self, obj: Value, #[realm] realm: &mut Realm) -> Res<f64> {
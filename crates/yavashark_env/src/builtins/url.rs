@@ -0,0 +1,431 @@
+mod search_params;
+
+pub use search_params::*;
+
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value};
+use search_params::usv_string;
+use std::cell::RefCell;
+use std::rc::Rc;
+use url::{Url, quirks};
+use yavashark_macro::{object, props};
+
+#[object]
+#[derive(Debug)]
+pub struct URL {
+    url: Rc<RefCell<Url>>,
+    search_params: ObjectHandle,
+}
+
+impl URL {
+    pub fn new(url: Url, realm: &mut Realm) -> Res<Self> {
+        let list = url.query().map(URLSearchParams::parse).unwrap_or_default();
+        let url = Rc::new(RefCell::new(url));
+
+        let search_params = URLSearchParams::new(list, Some(Rc::clone(&url)), realm)?.into_object();
+
+        Ok(Self {
+            inner: RefCell::new(MutableURL {
+                object: MutObject::with_proto(
+                    realm.intrinsics.clone_public().url.get(realm)?.clone(),
+                ),
+            }),
+            url,
+            search_params,
+        })
+    }
+
+    /// Parses `input`, resolving it against `base` if given.
+    #[must_use]
+    pub fn parse_with_base(input: &str, base: Option<&str>) -> Option<Url> {
+        match base {
+            Some(base) => Url::parse(base).ok()?.join(input).ok(),
+            None => Url::parse(input).ok(),
+        }
+    }
+
+    fn parse_args(url: &Value, base: Option<Value>, realm: &mut Realm) -> Res<Option<Url>> {
+        let url = usv_string(url, realm)?;
+        let base = match base {
+            Some(base) if !base.is_undefined() => Some(usv_string(&base, realm)?),
+            _ => None,
+        };
+
+        Ok(Self::parse_with_base(&url, base.as_deref()))
+    }
+
+    #[must_use]
+    pub fn href(&self) -> String {
+        self.url.borrow().to_string()
+    }
+
+    fn sync_search_params(&self) -> Res {
+        let params = self
+            .search_params
+            .downcast::<URLSearchParams>()
+            .ok_or(Error::new(
+                "URL searchParams is not a URLSearchParams object",
+            ))?;
+
+        params.reset(self.url.try_borrow()?.query())
+    }
+
+    fn get(&self, f: fn(&Url) -> &str) -> Res<String> {
+        Ok(f(&*self.url.try_borrow()?).to_string())
+    }
+
+    fn set<E>(&self, value: &Value, realm: &mut Realm, f: fn(&mut Url, &str) -> E) -> Res {
+        let value = usv_string(value, realm)?;
+
+        // setters ignore invalid input instead of throwing
+        let _ = f(&mut *self.url.try_borrow_mut()?, &value);
+
+        Ok(())
+    }
+}
+
+#[props(intrinsic_name = url, to_string_tag = "URL")]
+impl URL {
+    #[constructor]
+    fn construct(url: &Value, base: Option<Value>, realm: &mut Realm) -> Res<ObjectHandle> {
+        let url = Self::parse_args(url, base, realm)?.ok_or(Error::ty("Invalid URL"))?;
+
+        Ok(Self::new(url, realm)?.into_object())
+    }
+
+    #[prop("canParse")]
+    fn can_parse(url: &Value, base: Option<Value>, realm: &mut Realm) -> Res<bool> {
+        Ok(Self::parse_args(url, base, realm)?.is_some())
+    }
+
+    #[prop("parse")]
+    fn parse_js(url: &Value, base: Option<Value>, realm: &mut Realm) -> Res<Value> {
+        Ok(match Self::parse_args(url, base, realm)? {
+            Some(url) => Self::new(url, realm)?.into_object().into(),
+            None => Value::Null,
+        })
+    }
+
+    #[get("href")]
+    fn get_href(&self) -> Res<String> {
+        self.get(quirks::href)
+    }
+
+    #[set("href")]
+    fn set_href(&self, value: &Value, realm: &mut Realm) -> Res {
+        let value = usv_string(value, realm)?;
+
+        quirks::set_href(&mut *self.url.try_borrow_mut()?, &value)
+            .map_err(|_| Error::ty("Invalid URL"))?;
+
+        self.sync_search_params()
+    }
+
+    #[get("origin")]
+    fn origin(&self) -> Res<String> {
+        Ok(quirks::origin(&*self.url.try_borrow()?))
+    }
+
+    #[get("protocol")]
+    fn protocol(&self) -> Res<String> {
+        self.get(quirks::protocol)
+    }
+
+    #[set("protocol")]
+    fn set_protocol(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_protocol)
+    }
+
+    #[get("username")]
+    fn username(&self) -> Res<String> {
+        self.get(quirks::username)
+    }
+
+    #[set("username")]
+    fn set_username(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_username)
+    }
+
+    #[get("password")]
+    fn password(&self) -> Res<String> {
+        self.get(quirks::password)
+    }
+
+    #[set("password")]
+    fn set_password(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_password)
+    }
+
+    #[get("host")]
+    fn host(&self) -> Res<String> {
+        self.get(quirks::host)
+    }
+
+    #[set("host")]
+    fn set_host(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_host)
+    }
+
+    #[get("hostname")]
+    fn hostname(&self) -> Res<String> {
+        self.get(quirks::hostname)
+    }
+
+    #[set("hostname")]
+    fn set_hostname(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_hostname)
+    }
+
+    #[get("port")]
+    fn port(&self) -> Res<String> {
+        self.get(quirks::port)
+    }
+
+    #[set("port")]
+    fn set_port(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_port)
+    }
+
+    #[get("pathname")]
+    fn pathname(&self) -> Res<String> {
+        self.get(quirks::pathname)
+    }
+
+    #[set("pathname")]
+    fn set_pathname(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_pathname)
+    }
+
+    #[get("search")]
+    fn search(&self) -> Res<String> {
+        self.get(quirks::search)
+    }
+
+    #[set("search")]
+    fn set_search(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_search)?;
+
+        self.sync_search_params()
+    }
+
+    #[get("searchParams")]
+    fn search_params(&self) -> ObjectHandle {
+        self.search_params.clone()
+    }
+
+    #[get("hash")]
+    fn hash(&self) -> Res<String> {
+        self.get(quirks::hash)
+    }
+
+    #[set("hash")]
+    fn set_hash(&self, value: &Value, realm: &mut Realm) -> Res {
+        self.set(value, realm, quirks::set_hash)
+    }
+
+    #[prop("toString")]
+    fn to_string_js(&self) -> String {
+        self.href()
+    }
+
+    #[prop("toJSON")]
+    fn to_json(&self) -> String {
+        self.href()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Realm, Res, Value};
+
+    pub fn construct(name: &'static str, args: Vec<Value>, realm: &mut Realm) -> Value {
+        let ctor = realm.global.clone().get(name, realm).unwrap();
+
+        ctor.as_object()
+            .unwrap()
+            .construct(args, realm)
+            .unwrap()
+            .into()
+    }
+
+    pub fn call(value: &Value, method: &'static str, args: Vec<Value>, realm: &mut Realm) -> Value {
+        value
+            .as_object()
+            .unwrap()
+            .call_method(method, realm, args)
+            .unwrap()
+    }
+
+    pub fn string(value: &Value, realm: &mut Realm) -> String {
+        value.to_string(realm).unwrap().to_string()
+    }
+
+    pub fn get(value: &Value, key: &'static str, realm: &mut Realm) -> String {
+        let prop = value.as_object().unwrap().get(key, realm).unwrap();
+
+        string(&prop, realm)
+    }
+
+    /// Calls the setter on `URL.prototype` directly, assignment does not look
+    /// for setters on the prototype chain yet.
+    fn try_set(value: &Value, key: &'static str, to: &str, realm: &mut Realm) -> Res {
+        let object = realm.global.clone().get("Object", realm)?;
+        let proto = realm.global.clone().get("URL", realm)?;
+        let proto = proto.as_object()?.get("prototype", realm)?;
+
+        let desc = call(
+            &object,
+            "getOwnPropertyDescriptor",
+            vec![proto, key.into()],
+            realm,
+        );
+        let setter = desc.as_object()?.get("set", realm)?;
+
+        setter.call(realm, vec![Value::from(to.to_string())], value.clone())?;
+
+        Ok(())
+    }
+
+    fn set(value: &Value, key: &'static str, to: &str, realm: &mut Realm) {
+        try_set(value, key, to, realm).unwrap();
+    }
+
+    fn url_static(method: &'static str, args: Vec<Value>, realm: &mut Realm) -> Value {
+        let ctor: Value = realm.global.clone().get("URL", realm).unwrap();
+
+        call(&ctor, method, args, realm)
+    }
+
+    #[test]
+    fn construct_with_and_without_base() {
+        let mut realm = Realm::new().unwrap();
+
+        let url = construct(
+            "URL",
+            vec!["https://user:pw@Example.com:8080/a/b?x=1#frag".into()],
+            &mut realm,
+        );
+
+        assert_eq!(
+            get(&url, "href", &mut realm),
+            "https://user:pw@example.com:8080/a/b?x=1#frag"
+        );
+        assert_eq!(get(&url, "origin", &mut realm), "https://example.com:8080");
+        assert_eq!(get(&url, "protocol", &mut realm), "https:");
+        assert_eq!(get(&url, "username", &mut realm), "user");
+        assert_eq!(get(&url, "password", &mut realm), "pw");
+        assert_eq!(get(&url, "host", &mut realm), "example.com:8080");
+        assert_eq!(get(&url, "hostname", &mut realm), "example.com");
+        assert_eq!(get(&url, "port", &mut realm), "8080");
+        assert_eq!(get(&url, "pathname", &mut realm), "/a/b");
+        assert_eq!(get(&url, "search", &mut realm), "?x=1");
+        assert_eq!(get(&url, "hash", &mut realm), "#frag");
+
+        let url = construct(
+            "URL",
+            vec!["../c?y".into(), "http://host/a/b/".into()],
+            &mut realm,
+        );
+        assert_eq!(get(&url, "href", &mut realm), "http://host/a/c?y");
+
+        let url = construct(
+            "URL",
+            vec!["http://other/".into(), Value::Undefined],
+            &mut realm,
+        );
+        assert_eq!(get(&url, "href", &mut realm), "http://other/");
+
+        let ctor = realm.global.clone().get("URL", &mut realm).unwrap();
+        let ctor = ctor.as_object().unwrap();
+
+        assert!(ctor.construct(vec!["relative".into()], &mut realm).is_err());
+        assert!(
+            ctor.construct(vec!["a".into(), "not a base".into()], &mut realm)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn can_parse_and_parse() {
+        let mut realm = Realm::new().unwrap();
+
+        let ok = url_static("canParse", vec!["http://host/".into()], &mut realm);
+        assert_eq!(ok, Value::Boolean(true));
+
+        let ok = url_static("canParse", vec!["/x".into()], &mut realm);
+        assert_eq!(ok, Value::Boolean(false));
+
+        let ok = url_static(
+            "canParse",
+            vec!["/x".into(), "http://host/".into()],
+            &mut realm,
+        );
+        assert_eq!(ok, Value::Boolean(true));
+
+        let parsed = url_static("parse", vec!["/x".into()], &mut realm);
+        assert_eq!(parsed, Value::Null);
+
+        let parsed = url_static(
+            "parse",
+            vec!["/x".into(), "http://host/a".into()],
+            &mut realm,
+        );
+        assert_eq!(get(&parsed, "href", &mut realm), "http://host/x");
+    }
+
+    #[test]
+    fn setters_ignore_invalid_input() {
+        let mut realm = Realm::new().unwrap();
+
+        let url = construct("URL", vec!["http://host:81/path".into()], &mut realm);
+
+        set(&url, "port", "not a port", &mut realm);
+        assert_eq!(get(&url, "port", &mut realm), "81");
+
+        set(&url, "protocol", "1nvalid", &mut realm);
+        assert_eq!(get(&url, "protocol", &mut realm), "http:");
+
+        set(&url, "hostname", "exa mple", &mut realm);
+        assert_eq!(get(&url, "hostname", &mut realm), "host");
+
+        set(&url, "port", "8080", &mut realm);
+        set(&url, "pathname", "/a b", &mut realm);
+        set(&url, "hash", "top", &mut realm);
+        assert_eq!(get(&url, "href", &mut realm), "http://host:8080/a%20b#top");
+
+        assert!(try_set(&url, "href", "not a url", &mut realm).is_err());
+        assert_eq!(get(&url, "href", &mut realm), "http://host:8080/a%20b#top");
+    }
+
+    #[test]
+    fn search_params_stay_live() {
+        let mut realm = Realm::new().unwrap();
+
+        let url = construct("URL", vec!["http://host/?a=1&b=2".into()], &mut realm);
+        let params = url
+            .as_object()
+            .unwrap()
+            .get("searchParams", &mut realm)
+            .unwrap();
+
+        call(
+            &params,
+            "append",
+            vec!["c".into(), "x y".into()],
+            &mut realm,
+        );
+        assert_eq!(get(&url, "href", &mut realm), "http://host/?a=1&b=2&c=x+y");
+
+        set(&url, "search", "?z=9", &mut realm);
+        let z = call(&params, "get", vec!["z".into()], &mut realm);
+        assert_eq!(string(&z, &mut realm), "9");
+        assert_eq!(get(&params, "size", &mut realm), "1");
+
+        set(&url, "href", "http://other/?q=1&q=2", &mut realm);
+        assert_eq!(string(&params, &mut realm), "q=1&q=2");
+
+        call(&params, "delete", vec!["q".into()], &mut realm);
+        assert_eq!(get(&url, "href", &mut realm), "http://other/");
+        assert_eq!(get(&url, "search", &mut realm), "");
+    }
+}
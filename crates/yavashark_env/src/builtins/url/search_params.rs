@@ -0,0 +1,415 @@
+use crate::array::Array;
+use crate::utils::ValueIterator;
+use crate::value::property_key::PropertyKey;
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value, ValueResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+use url::form_urlencoded;
+use yavashark_macro::{object, props};
+
+#[object]
+#[derive(Debug)]
+pub struct URLSearchParams {
+    #[mutable]
+    list: Vec<(String, String)>,
+    /// The URL whose query this object is the `searchParams` of.
+    url: Option<Rc<RefCell<url::Url>>>,
+}
+
+impl URLSearchParams {
+    pub fn new(
+        list: Vec<(String, String)>,
+        url: Option<Rc<RefCell<url::Url>>>,
+        realm: &mut Realm,
+    ) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableURLSearchParams {
+                object: MutObject::with_proto(
+                    realm
+                        .intrinsics
+                        .clone_public()
+                        .url_search_params
+                        .get(realm)?
+                        .clone(),
+                ),
+                list,
+            }),
+            url,
+        })
+    }
+
+    /// Parses an `application/x-www-form-urlencoded` string.
+    #[must_use]
+    pub fn parse(query: &str) -> Vec<(String, String)> {
+        form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect()
+    }
+
+    /// Replaces the list after the associated URL's query changed.
+    pub fn reset(&self, query: Option<&str>) -> Res {
+        self.inner.try_borrow_mut()?.list = query.map(Self::parse).unwrap_or_default();
+
+        Ok(())
+    }
+
+    fn serialize(list: &[(String, String)]) -> String {
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(list)
+            .finish()
+    }
+
+    /// Writes the list back to the associated URL's query.
+    fn update(&self) -> Res {
+        let Some(url) = &self.url else {
+            return Ok(());
+        };
+
+        let query = Self::serialize(&self.inner.try_borrow()?.list);
+
+        url.try_borrow_mut()?
+            .set_query((!query.is_empty()).then_some(query.as_str()));
+
+        Ok(())
+    }
+
+    fn list_from_value(init: &Value, realm: &mut Realm) -> Res<Vec<(String, String)>> {
+        let Value::Object(obj) = init else {
+            let query = init.to_string(realm)?;
+            let query = query.as_str_lossy();
+
+            return Ok(Self::parse(query.strip_prefix('?').unwrap_or(&query)));
+        };
+
+        if let Some(params) = obj.downcast::<Self>() {
+            return Ok(params.inner.try_borrow()?.list.clone());
+        }
+
+        let mut list = Vec::new();
+
+        if obj.contains_key(crate::Symbol::ITERATOR.into(), realm)? {
+            let iter = ValueIterator::new(init, realm)?;
+
+            while let Some(pair) = iter.next(realm)? {
+                let mut pair = crate::utils::ArrayLike::new(pair, realm)?;
+                let pair = pair.to_vec(realm)?;
+
+                let [name, value] = pair.as_slice() else {
+                    return Err(Error::ty(
+                        "Failed to construct 'URLSearchParams': Sequence initializer must only contain pair elements",
+                    ));
+                };
+
+                list.push((usv_string(name, realm)?, usv_string(value, realm)?));
+            }
+
+            return Ok(list);
+        }
+
+        for (key, value) in obj.enum_properties(realm)? {
+            if let PropertyKey::String(key) = key {
+                list.push((key.as_str_lossy().into_owned(), usv_string(&value, realm)?));
+            }
+        }
+
+        Ok(list)
+    }
+
+    fn list_iterator(
+        &self,
+        realm: &mut Realm,
+        f: impl Fn(String, String, &mut Realm) -> Res<Value>,
+    ) -> ValueResult {
+        let entries = self.inner.try_borrow()?.list.clone();
+
+        let values = entries
+            .into_iter()
+            .map(|(name, value)| f(name, value, realm))
+            .collect::<Res<Vec<_>>>()?;
+
+        let array = Array::with_elements(realm, values)?.into_value();
+
+        array.call_method(&"values".into(), realm, Vec::new())
+    }
+}
+
+/// Converts a value to a string, replacing lone surrogates with U+FFFD.
+pub(super) fn usv_string(value: &Value, realm: &mut Realm) -> Res<String> {
+    Ok(value.to_string(realm)?.as_str_lossy().into_owned())
+}
+
+#[props(intrinsic_name = url_search_params, to_string_tag = "URLSearchParams")]
+impl URLSearchParams {
+    #[constructor]
+    fn construct(init: Option<Value>, realm: &mut Realm) -> Res<ObjectHandle> {
+        let list = match init {
+            Some(init) if !init.is_undefined() => Self::list_from_value(&init, realm)?,
+            _ => Vec::new(),
+        };
+
+        Ok(Self::new(list, None, realm)?.into_object())
+    }
+
+    #[get("size")]
+    fn size(&self) -> Res<usize> {
+        Ok(self.inner.try_borrow()?.list.len())
+    }
+
+    fn append(&self, name: &Value, value: &Value, realm: &mut Realm) -> Res {
+        let pair = (usv_string(name, realm)?, usv_string(value, realm)?);
+
+        self.inner.try_borrow_mut()?.list.push(pair);
+
+        self.update()
+    }
+
+    fn delete(&self, name: &Value, value: Option<Value>, realm: &mut Realm) -> Res {
+        let name = usv_string(name, realm)?;
+        let value = match value {
+            Some(value) if !value.is_undefined() => Some(usv_string(&value, realm)?),
+            _ => None,
+        };
+
+        self.inner
+            .try_borrow_mut()?
+            .list
+            .retain(|(n, v)| *n != name || value.as_ref().is_some_and(|value| v != value));
+
+        self.update()
+    }
+
+    fn get(&self, name: &Value, realm: &mut Realm) -> Res<Value> {
+        let name = usv_string(name, realm)?;
+
+        Ok(self
+            .inner
+            .try_borrow()?
+            .list
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map_or(Value::Null, |(_, v)| v.clone().into()))
+    }
+
+    #[prop("getAll")]
+    fn get_all(&self, name: &Value, realm: &mut Realm) -> ValueResult {
+        let name = usv_string(name, realm)?;
+
+        let values = self
+            .inner
+            .try_borrow()?
+            .list
+            .iter()
+            .filter(|(n, _)| *n == name)
+            .map(|(_, v)| v.clone().into())
+            .collect();
+
+        Ok(Array::with_elements(realm, values)?.into_value())
+    }
+
+    fn has(&self, name: &Value, value: Option<Value>, realm: &mut Realm) -> Res<bool> {
+        let name = usv_string(name, realm)?;
+        let value = match value {
+            Some(value) if !value.is_undefined() => Some(usv_string(&value, realm)?),
+            _ => None,
+        };
+
+        Ok(self
+            .inner
+            .try_borrow()?
+            .list
+            .iter()
+            .any(|(n, v)| *n == name && value.as_ref().is_none_or(|value| v == value)))
+    }
+
+    fn set(&self, name: &Value, value: &Value, realm: &mut Realm) -> Res {
+        let name = usv_string(name, realm)?;
+        let value = usv_string(value, realm)?;
+
+        {
+            let mut inner = self.inner.try_borrow_mut()?;
+
+            match inner.list.iter().position(|(n, _)| *n == name) {
+                Some(idx) => {
+                    inner.list[idx].1 = value;
+
+                    let mut seen = false;
+                    inner.list.retain(|(n, _)| {
+                        if *n != name {
+                            return true;
+                        }
+
+                        !std::mem::replace(&mut seen, true)
+                    });
+                }
+                None => inner.list.push((name, value)),
+            }
+        }
+
+        self.update()
+    }
+
+    fn sort(&self) -> Res {
+        self.inner
+            .try_borrow_mut()?
+            .list
+            .sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+        self.update()
+    }
+
+    #[prop("toString")]
+    fn to_string_js(&self) -> Res<String> {
+        Ok(Self::serialize(&self.inner.try_borrow()?.list))
+    }
+
+    #[prop("forEach")]
+    fn for_each(
+        &self,
+        callback: &ObjectHandle,
+        this_arg: Option<Value>,
+        #[this] this: &Value,
+        realm: &mut Realm,
+    ) -> Res {
+        let this_arg = this_arg.unwrap_or(Value::Undefined);
+
+        let mut idx = 0;
+
+        loop {
+            let Some((name, value)) = self.inner.try_borrow()?.list.get(idx).cloned() else {
+                break;
+            };
+
+            callback.call(
+                vec![value.into(), name.into(), this.clone()],
+                this_arg.clone(),
+                realm,
+            )?;
+
+            idx += 1;
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self, realm: &mut Realm) -> ValueResult {
+        self.list_iterator(realm, |name, value, realm| {
+            Ok(Array::with_elements(realm, vec![name.into(), value.into()])?.into_value())
+        })
+    }
+
+    #[prop(crate::Symbol::ITERATOR)]
+    fn iterator(&self, realm: &mut Realm) -> ValueResult {
+        self.entries(realm)
+    }
+
+    fn keys(&self, realm: &mut Realm) -> ValueResult {
+        self.list_iterator(realm, |name, _, _| Ok(name.into()))
+    }
+
+    fn values(&self, realm: &mut Realm) -> ValueResult {
+        self.list_iterator(realm, |_, value, _| Ok(value.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{call, construct, get, string};
+    use crate::{Realm, Value};
+
+    fn values(iter: &Value, realm: &mut Realm) -> Vec<Value> {
+        let iter = iter.as_object().unwrap().iter_no_realm(realm).unwrap();
+        let mut values = Vec::new();
+
+        while let Some(value) = iter.next(realm).unwrap() {
+            values.push(value);
+        }
+
+        values
+    }
+
+    fn entries(params: &Value, realm: &mut Realm) -> Vec<String> {
+        values(params, realm)
+            .into_iter()
+            .map(|entry| {
+                let key = call(&entry, "at", vec![0.into()], realm);
+                let value = call(&entry, "at", vec![1.into()], realm);
+
+                format!("{}={}", string(&key, realm), string(&value, realm))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sort_is_stable_by_name() {
+        let mut realm = Realm::new().unwrap();
+
+        let params = construct(
+            "URLSearchParams",
+            vec!["?c=1&a=2&b=3&a=1&\u{1F600}=4&\u{FB03}=5".into()],
+            &mut realm,
+        );
+
+        call(&params, "sort", Vec::new(), &mut realm);
+
+        // sorted by UTF-16 code units, the emoji's surrogates sort before U+FB03
+        assert_eq!(
+            entries(&params, &mut realm),
+            ["a=2", "a=1", "b=3", "c=1", "\u{1F600}=4", "\u{FB03}=5"]
+        );
+    }
+
+    #[test]
+    fn iteration() {
+        let mut realm = Realm::new().unwrap();
+
+        let params = construct("URLSearchParams", vec!["a=1&b=2&a=3".into()], &mut realm);
+
+        assert_eq!(entries(&params, &mut realm), ["a=1", "b=2", "a=3"]);
+
+        let keys = call(&params, "keys", Vec::new(), &mut realm);
+        let names = values(&keys, &mut realm)
+            .iter()
+            .map(|key| string(key, &mut realm))
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["a", "b", "a"]);
+
+        let all = call(&params, "getAll", vec!["a".into()], &mut realm);
+        assert_eq!(string(&all, &mut realm), "1,3");
+        assert_eq!(get(&params, "size", &mut realm), "3");
+    }
+
+    #[test]
+    fn to_string_encoding() {
+        let mut realm = Realm::new().unwrap();
+
+        let params = construct("URLSearchParams", Vec::new(), &mut realm);
+
+        call(
+            &params,
+            "append",
+            vec!["a b".into(), "c&d=e".into()],
+            &mut realm,
+        );
+        call(
+            &params,
+            "append",
+            vec!["ä".into(), "*-._~+".into()],
+            &mut realm,
+        );
+
+        assert_eq!(
+            string(&params, &mut realm),
+            "a+b=c%26d%3De&%C3%A4=*-._%7E%2B"
+        );
+
+        let parsed = construct(
+            "URLSearchParams",
+            vec![string(&params, &mut realm).into()],
+            &mut realm,
+        );
+        let value = call(&parsed, "get", vec!["a b".into()], &mut realm);
+        assert_eq!(string(&value, &mut realm), "c&d=e");
+    }
+}
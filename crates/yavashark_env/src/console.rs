@@ -28,7 +28,7 @@ pub fn get_console(realm: &mut Realm) -> ObjectHandle {
             |args, _, realm| {
                 let mut str = String::new();
 
-                for arg in args {
+                for arg in &args {
                     str.push_str(&arg.pretty_print(realm));
                     str.push(' ');
                 }
@@ -42,6 +42,10 @@ pub fn get_console(realm: &mut Realm) -> ObjectHandle {
                     log::info!("YAVASHARK_LOG: {str}");
                 }
 
+                if let Some(debugger) = realm.debugger() {
+                    debugger.console(realm, "log", &args);
+                }
+
                 Ok(Value::Undefined)
             },
             realm,
@@ -48,6 +48,7 @@ fn error_message_pretty_circular(error: &Error, not: &mut Vec<usize>, realm: &mu
         | ErrorKind::URI(msg)
         | ErrorKind::Aggregate(msg)
         | ErrorKind::Suppressed(msg)
+        | ErrorKind::Terminated(msg)
         | ErrorKind::Syntax(msg) => msg.to_string(),
         ErrorKind::Throw(val) => val.pretty_print_circular(not, realm),
         ErrorKind::Error(msg) => msg
@@ -71,6 +72,7 @@ fn error_message_pretty_circular_nl(
         | ErrorKind::URI(msg)
         | ErrorKind::Aggregate(msg)
         | ErrorKind::Suppressed(msg)
+        | ErrorKind::Terminated(msg)
         | ErrorKind::Syntax(msg) => msg.to_string(),
         ErrorKind::Throw(val) => val.pretty_print_circular_nl(not, realm),
         ErrorKind::Error(msg) => msg
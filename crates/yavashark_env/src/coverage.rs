@@ -0,0 +1,738 @@
+//! Statement, branch and function coverage.
+//!
+//! The interpreter and the VM report the span of every statement, function
+//! body and branch arm they enter. The spans are matched against the items
+//! found in the registered source files when a [`CoverageReport`] is taken,
+//! items that were never entered are reported with zero hits.
+
+use crate::debugger::SourceSpan;
+use serde_json::{Map, Value as Json, json};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use swc_common::Spanned;
+use swc_ecma_ast::{
+    ArrowExpr, ArrowFunctionBody, BinExpr, BinaryOp, ClassMethod, CondExpr, Decl, Expr, FnDecl,
+    FnExpr, Function, GetterProp, IfStmt, MethodProp, ModuleItem, Pat, PropName, SetterProp, Stmt,
+    SwitchStmt, VarDeclarator,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// The kind of a branch, named like the Istanbul branch types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchKind {
+    If,
+    Conditional,
+    Switch,
+    Logical,
+}
+
+impl BranchKind {
+    #[must_use]
+    pub const fn istanbul_type(self) -> &'static str {
+        match self {
+            Self::If => "if",
+            Self::Conditional => "cond-expr",
+            Self::Switch => "switch",
+            Self::Logical => "binary-expr",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FunctionItem {
+    name: String,
+    /// The name of the function, or all of it for anonymous functions.
+    decl: SourceSpan,
+    span: SourceSpan,
+    /// What the engine reports when the function is called.
+    body: SourceSpan,
+}
+
+#[derive(Debug, Clone)]
+struct BranchItem {
+    kind: BranchKind,
+    span: SourceSpan,
+    arms: Vec<SourceSpan>,
+    /// An `if` without `else`, its second arm is taken whenever the first
+    /// one isn't.
+    implicit_else: bool,
+}
+
+/// The statements, functions and branches of a source file.
+#[derive(Debug, Clone, Default)]
+pub struct FileItems {
+    statements: Vec<SourceSpan>,
+    functions: Vec<FunctionItem>,
+    branches: Vec<BranchItem>,
+}
+
+impl FileItems {
+    #[must_use]
+    pub fn script(stmts: &[Stmt]) -> Self {
+        let mut collector = Collector::default();
+        stmts.visit_with(&mut collector);
+
+        collector.items
+    }
+
+    #[must_use]
+    pub fn module(items: &[ModuleItem]) -> Self {
+        let mut collector = Collector::default();
+        items.visit_with(&mut collector);
+
+        collector.items
+    }
+}
+
+fn span_of(node: &impl Spanned) -> SourceSpan {
+    node.span().into()
+}
+
+#[derive(Default)]
+struct Collector {
+    items: FileItems,
+    /// The name the next function expression gets from its declaration.
+    name: Option<String>,
+    anonymous: usize,
+}
+
+impl Collector {
+    fn function(
+        &mut self,
+        name: Option<String>,
+        decl: SourceSpan,
+        span: SourceSpan,
+        body: SourceSpan,
+    ) {
+        let name = name.unwrap_or_else(|| {
+            self.anonymous += 1;
+            format!("(anonymous_{})", self.anonymous - 1)
+        });
+
+        self.items.functions.push(FunctionItem {
+            name,
+            decl,
+            span,
+            body,
+        });
+    }
+
+    fn function_body(&mut self, name: Option<String>, decl: SourceSpan, function: &Function) {
+        if let Some(body) = &function.body {
+            self.function(name, decl, span_of(function), span_of(body));
+        }
+    }
+
+    fn branch(&mut self, kind: BranchKind, span: SourceSpan, arms: Vec<SourceSpan>) {
+        self.items.branches.push(BranchItem {
+            kind,
+            span,
+            arms,
+            implicit_else: false,
+        });
+    }
+}
+
+fn prop_name(name: &PropName) -> Option<String> {
+    match name {
+        PropName::Ident(ident) => Some(ident.sym.to_string()),
+        PropName::Str(s) => s.value.as_str().map(ToOwned::to_owned),
+        _ => None,
+    }
+}
+
+impl Visit for Collector {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(_) | Stmt::Empty(_) | Stmt::Decl(Decl::Fn(_)) => {}
+            _ => self.items.statements.push(span_of(stmt)),
+        }
+
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.name = None;
+        self.function_body(
+            Some(decl.ident.sym.to_string()),
+            span_of(&decl.ident),
+            &decl.function,
+        );
+
+        decl.visit_children_with(self);
+    }
+
+    fn visit_fn_expr(&mut self, expr: &FnExpr) {
+        let name = expr
+            .ident
+            .as_ref()
+            .map(|ident| ident.sym.to_string())
+            .or_else(|| self.name.take());
+
+        let decl = expr
+            .ident
+            .as_ref()
+            .map_or_else(|| span_of(&*expr.function), span_of);
+
+        self.name = None;
+        self.function_body(name, decl, &expr.function);
+
+        expr.visit_children_with(self);
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        let name = self.name.take();
+        let span = span_of(arrow);
+
+        let body = match &*arrow.body {
+            ArrowFunctionBody::FunctionBody(block) => span_of(block),
+            ArrowFunctionBody::Expr(expr) => span_of(&**expr),
+        };
+
+        self.function(name, span, span, body);
+
+        arrow.visit_children_with(self);
+    }
+
+    fn visit_class_method(&mut self, method: &ClassMethod) {
+        self.name = None;
+        self.function_body(
+            prop_name(&method.key),
+            span_of(&method.key),
+            &method.function,
+        );
+
+        method.visit_children_with(self);
+    }
+
+    fn visit_method_prop(&mut self, method: &MethodProp) {
+        self.name = None;
+        self.function_body(
+            prop_name(&method.key),
+            span_of(&method.key),
+            &method.function,
+        );
+
+        method.visit_children_with(self);
+    }
+
+    fn visit_getter_prop(&mut self, getter: &GetterProp) {
+        self.name = None;
+
+        self.function_body(
+            prop_name(&getter.key),
+            span_of(&getter.key),
+            &getter.function,
+        );
+
+        getter.visit_children_with(self);
+    }
+
+    fn visit_setter_prop(&mut self, setter: &SetterProp) {
+        self.name = None;
+
+        self.function_body(
+            prop_name(&setter.key),
+            span_of(&setter.key),
+            &setter.function,
+        );
+
+        setter.visit_children_with(self);
+    }
+
+    fn visit_var_declarator(&mut self, decl: &VarDeclarator) {
+        if let (Pat::Ident(ident), Some(init)) = (&decl.name, &decl.init)
+            && matches!(&**init, Expr::Fn(_) | Expr::Arrow(_))
+        {
+            self.name = Some(ident.id.sym.to_string());
+        }
+
+        decl.visit_children_with(self);
+        self.name = None;
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+        let mut arms = vec![span_of(&*stmt.cons)];
+
+        if let Some(alt) = &stmt.alt {
+            arms.push(span_of(&**alt));
+        }
+
+        self.items.branches.push(BranchItem {
+            kind: BranchKind::If,
+            span: span_of(stmt),
+            arms,
+            implicit_else: stmt.alt.is_none(),
+        });
+
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_cond_expr(&mut self, expr: &CondExpr) {
+        self.branch(
+            BranchKind::Conditional,
+            span_of(expr),
+            vec![span_of(&*expr.cons), span_of(&*expr.alt)],
+        );
+
+        expr.visit_children_with(self);
+    }
+
+    fn visit_switch_stmt(&mut self, stmt: &SwitchStmt) {
+        self.branch(
+            BranchKind::Switch,
+            span_of(stmt),
+            stmt.cases.iter().map(span_of).collect(),
+        );
+
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_bin_expr(&mut self, expr: &BinExpr) {
+        if matches!(
+            expr.op,
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing
+        ) {
+            self.branch(
+                BranchKind::Logical,
+                span_of(expr),
+                vec![span_of(&*expr.left), span_of(&*expr.right)],
+            );
+        }
+
+        expr.visit_children_with(self);
+    }
+}
+
+#[derive(Debug)]
+struct FileCoverage {
+    source: String,
+    items: FileItems,
+    hits: HashMap<SourceSpan, u64>,
+}
+
+/// Collects the hits of a realm, see [`crate::Realm::start_coverage`].
+#[derive(Debug, Default)]
+pub struct Coverage {
+    files: BTreeMap<PathBuf, FileCoverage>,
+}
+
+impl Coverage {
+    pub fn register(&mut self, path: PathBuf, source: &str, items: FileItems) {
+        self.files.entry(path).or_insert_with(|| FileCoverage {
+            source: source.to_owned(),
+            items,
+            hits: HashMap::new(),
+        });
+    }
+
+    /// Counts the statement, function body or branch arm at `span`.
+    pub fn hit(&mut self, path: &Path, span: SourceSpan) {
+        if let Some(file) = self.files.get_mut(path) {
+            *file.hits.entry(span).or_default() += 1;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for file in self.files.values_mut() {
+            file.hits.clear();
+        }
+    }
+
+    #[must_use]
+    pub fn report(&self) -> CoverageReport {
+        CoverageReport {
+            files: self
+                .files
+                .iter()
+                .map(|(path, file)| file.report(path.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// A position like Istanbul reports it, the line is one-based and the column
+/// zero-based in UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Location {
+    fn to_json(self) -> Json {
+        json!({
+            "start": { "line": self.start.line, "column": self.start.column },
+            "end": { "line": self.end.line, "column": self.end.column },
+        })
+    }
+}
+
+struct LineIndex<'a> {
+    source: &'a str,
+    /// Byte offset of the start of every line.
+    lines: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source: &'a str) -> Self {
+        let lines = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { source, lines }
+    }
+
+    fn position(&self, offset: u32) -> Position {
+        let offset = (offset as usize).min(self.source.len());
+        let line = self
+            .lines
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1);
+
+        let column = self
+            .source
+            .get(self.lines[line]..offset)
+            .map_or(0, |text| text.encode_utf16().count());
+
+        Position {
+            line: line + 1,
+            column,
+        }
+    }
+
+    fn location(&self, span: SourceSpan) -> Location {
+        Location {
+            start: self.position(span.lo),
+            end: self.position(span.hi),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionCoverage {
+    pub name: String,
+    pub decl: Location,
+    pub loc: Location,
+    pub hits: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BranchCoverage {
+    pub kind: BranchKind,
+    pub loc: Location,
+    pub arms: Vec<(Location, u64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub statements: Vec<(Location, u64)>,
+    pub functions: Vec<FunctionCoverage>,
+    pub branches: Vec<BranchCoverage>,
+}
+
+impl FileCoverage {
+    fn report(&self, path: PathBuf) -> FileReport {
+        let index = LineIndex::new(&self.source);
+        let hits = |span: &SourceSpan| self.hits.get(span).copied().unwrap_or_default();
+
+        let statements = self
+            .items
+            .statements
+            .iter()
+            .map(|span| (index.location(*span), hits(span)))
+            .collect();
+
+        let functions = self
+            .items
+            .functions
+            .iter()
+            .map(|function| FunctionCoverage {
+                name: function.name.clone(),
+                decl: index.location(function.decl),
+                loc: index.location(function.span),
+                hits: hits(&function.body),
+            })
+            .collect();
+
+        let branches = self
+            .items
+            .branches
+            .iter()
+            .map(|branch| {
+                let mut arms = branch
+                    .arms
+                    .iter()
+                    .map(|arm| (index.location(*arm), hits(arm)))
+                    .collect::<Vec<_>>();
+
+                if branch.implicit_else {
+                    let taken = arms.first().map_or(0, |(_, hits)| *hits);
+                    let count = hits(&branch.span).saturating_sub(taken);
+
+                    arms.push((index.location(branch.span), count));
+                }
+
+                BranchCoverage {
+                    kind: branch.kind,
+                    loc: index.location(branch.span),
+                    arms,
+                }
+            })
+            .collect();
+
+        FileReport {
+            path,
+            statements,
+            functions,
+            branches,
+        }
+    }
+}
+
+/// Hit counts of every registered file.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    pub files: Vec<FileReport>,
+}
+
+impl CoverageReport {
+    /// The report in the LCOV tracefile format.
+    #[must_use]
+    pub fn lcov(&self) -> String {
+        let mut out = String::new();
+
+        for file in &self.files {
+            _ = writeln!(out, "TN:");
+            _ = writeln!(out, "SF:{}", file.path.display());
+
+            for function in &file.functions {
+                _ = writeln!(out, "FN:{},{}", function.decl.start.line, function.name);
+            }
+
+            for function in &file.functions {
+                _ = writeln!(out, "FNDA:{},{}", function.hits, function.name);
+            }
+
+            _ = writeln!(out, "FNF:{}", file.functions.len());
+            _ = writeln!(
+                out,
+                "FNH:{}",
+                file.functions.iter().filter(|f| f.hits > 0).count()
+            );
+
+            let mut found = 0;
+            let mut hit = 0;
+
+            for (block, branch) in file.branches.iter().enumerate() {
+                let reached = branch.arms.iter().any(|(_, hits)| *hits > 0);
+
+                for (i, (_, hits)) in branch.arms.iter().enumerate() {
+                    found += 1;
+
+                    if *hits > 0 {
+                        hit += 1;
+                    }
+
+                    let taken = if reached {
+                        hits.to_string()
+                    } else {
+                        "-".to_owned()
+                    };
+
+                    _ = writeln!(out, "BRDA:{},{block},{i},{taken}", branch.loc.start.line);
+                }
+            }
+
+            _ = writeln!(out, "BRF:{found}");
+            _ = writeln!(out, "BRH:{hit}");
+
+            let mut lines = BTreeMap::<usize, u64>::new();
+
+            for (loc, hits) in &file.statements {
+                let line = lines.entry(loc.start.line).or_default();
+                *line = (*line).max(*hits);
+            }
+
+            for (line, hits) in &lines {
+                _ = writeln!(out, "DA:{line},{hits}");
+            }
+
+            _ = writeln!(out, "LF:{}", lines.len());
+            _ = writeln!(
+                out,
+                "LH:{}",
+                lines.values().filter(|hits| **hits > 0).count()
+            );
+            _ = writeln!(out, "end_of_record");
+        }
+
+        out
+    }
+
+    /// The report in the format of Istanbul's `coverage-final.json`.
+    #[must_use]
+    pub fn istanbul(&self) -> Json {
+        let mut files = Map::new();
+
+        for file in &self.files {
+            let path = file.path.display().to_string();
+
+            let mut statement_map = Map::new();
+            let mut s = Map::new();
+
+            for (i, (loc, hits)) in file.statements.iter().enumerate() {
+                statement_map.insert(i.to_string(), loc.to_json());
+                s.insert(i.to_string(), (*hits).into());
+            }
+
+            let mut fn_map = Map::new();
+            let mut f = Map::new();
+
+            for (i, function) in file.functions.iter().enumerate() {
+                fn_map.insert(
+                    i.to_string(),
+                    json!({
+                        "name": function.name,
+                        "decl": function.decl.to_json(),
+                        "loc": function.loc.to_json(),
+                        "line": function.decl.start.line,
+                    }),
+                );
+                f.insert(i.to_string(), function.hits.into());
+            }
+
+            let mut branch_map = Map::new();
+            let mut b = Map::new();
+
+            for (i, branch) in file.branches.iter().enumerate() {
+                branch_map.insert(
+                    i.to_string(),
+                    json!({
+                        "loc": branch.loc.to_json(),
+                        "type": branch.kind.istanbul_type(),
+                        "locations": branch
+                            .arms
+                            .iter()
+                            .map(|(loc, _)| loc.to_json())
+                            .collect::<Vec<_>>(),
+                        "line": branch.loc.start.line,
+                    }),
+                );
+                b.insert(
+                    i.to_string(),
+                    branch.arms.iter().map(|(_, hits)| *hits).collect(),
+                );
+            }
+
+            files.insert(
+                path.clone(),
+                json!({
+                    "path": path,
+                    "statementMap": statement_map,
+                    "fnMap": fn_map,
+                    "branchMap": branch_map,
+                    "s": s,
+                    "f": f,
+                    "b": b,
+                }),
+            );
+        }
+
+        Json::Object(files)
+    }
+
+    /// Writes `lcov.info` and `coverage-final.json` to `dir`, creating it if
+    /// needed.
+    pub fn write_to_dir(&self, dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join("lcov.info"), self.lcov())?;
+        std::fs::write(dir.join("coverage-final.json"), self.istanbul().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swc_common::BytePos;
+    use swc_common::input::StringInput;
+    use swc_ecma_parser::{EsSyntax, Parser, Syntax};
+
+    const SOURCE: &str =
+        "function f(a) {\n  if (a) {\n    return 1;\n  }\n  return a ? 2 : 3;\n}\nf(1);\n";
+
+    fn items() -> FileItems {
+        let input = StringInput::new(SOURCE, BytePos(0), BytePos(SOURCE.len() as u32));
+        let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+
+        let script = parser.parse_script().unwrap();
+
+        FileItems::script(&script.body)
+    }
+
+    fn report() -> CoverageReport {
+        let items = items();
+        let mut coverage = Coverage::default();
+
+        let function = items.functions[0].body;
+        let statements = items.statements.clone();
+        let branch = items.branches[0].clone();
+
+        coverage.register(PathBuf::from("test.js"), SOURCE, items);
+
+        let path = Path::new("test.js");
+        // `f(1)` runs the `if` and the `return 1` in it
+        coverage.hit(path, function);
+        coverage.hit(path, statements[0]);
+        coverage.hit(path, statements[1]);
+        coverage.hit(path, branch.arms[0]);
+        coverage.hit(path, statements[3]);
+
+        coverage.report()
+    }
+
+    #[test]
+    fn collects_items() {
+        let items = items();
+
+        assert_eq!(items.statements.len(), 4);
+        assert_eq!(items.functions.len(), 1);
+        assert_eq!(items.functions[0].name, "f");
+        assert_eq!(items.branches.len(), 2);
+        assert!(items.branches[0].implicit_else);
+        assert_eq!(items.branches[1].kind, BranchKind::Conditional);
+    }
+
+    #[test]
+    fn lcov_counts_lines_and_branches() {
+        let lcov = report().lcov();
+
+        assert!(lcov.contains("SF:test.js\n"));
+        assert!(lcov.contains("FN:1,f\nFNDA:1,f\nFNF:1\nFNH:1\n"));
+        assert!(lcov.contains("BRDA:2,0,0,1\nBRDA:2,0,1,0\nBRDA:5,1,0,-\nBRDA:5,1,1,-\n"));
+        assert!(lcov.contains("DA:2,1\nDA:3,1\nDA:5,0\nDA:7,1\nLF:4\nLH:3\n"));
+        assert!(lcov.ends_with("end_of_record\n"));
+    }
+
+    #[test]
+    fn istanbul_has_maps_and_counts() {
+        let json = report().istanbul();
+        let file = &json["test.js"];
+
+        assert_eq!(file["statementMap"]["0"]["start"]["line"], 2);
+        assert_eq!(file["statementMap"]["0"]["start"]["column"], 2);
+        assert_eq!(file["fnMap"]["0"]["name"], "f");
+        assert_eq!(file["branchMap"]["0"]["type"], "if");
+        assert_eq!(file["b"]["0"], json!([1, 0]));
+        assert_eq!(file["s"]["2"], 0);
+    }
+}
@@ -0,0 +1,92 @@
+//! Hooks an attached debugger uses to follow and pause the interpreter.
+//!
+//! The interpreter reports every statement, call and thrown error to the hook of
+//! the realm, which can block inside the callback for as long as it wants.
+
+use crate::scope::Scope;
+use crate::{Error, Realm, Res, Value};
+use swc_common::Span;
+
+/// Byte offsets of a statement in its source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceSpan {
+    pub lo: u32,
+    pub hi: u32,
+}
+
+impl SourceSpan {
+    #[must_use]
+    pub const fn contains(&self, other: &Self) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi
+    }
+}
+
+impl From<Span> for SourceSpan {
+    fn from(span: Span) -> Self {
+        Self {
+            lo: span.lo.0,
+            hi: span.hi.0,
+        }
+    }
+}
+
+pub trait DebugHook {
+    /// Called before a statement runs. Returning an error terminates the
+    /// script.
+    fn statement(&self, realm: &mut Realm, scope: &Scope, span: SourceSpan) -> Res;
+
+    /// Called for a `debugger` statement, after [`DebugHook::statement`].
+    fn debugger_statement(&self, realm: &mut Realm, scope: &Scope, span: SourceSpan) -> Res;
+
+    /// Called when a statement completes with an error.
+    fn exception(&self, realm: &mut Realm, error: &Error) -> Res;
+
+    fn enter_frame(&self, name: String);
+
+    fn leave_frame(&self);
+
+    /// Called around the block of a `try` statement, errors thrown inside are
+    /// caught.
+    fn enter_try(&self) {}
+
+    fn leave_try(&self) {}
+
+    /// Called for every `console` call, after it was printed.
+    fn console(&self, realm: &mut Realm, level: &str, args: &[Value]) {
+        _ = (realm, level, args);
+    }
+}
+
+/// Runs the body of a function as a new frame of the debugger.
+pub fn frame<T>(
+    realm: &mut Realm,
+    name: impl FnOnce() -> String,
+    f: impl FnOnce(&mut Realm) -> T,
+) -> T {
+    let Some(hook) = realm.debugger() else {
+        return f(realm);
+    };
+
+    hook.enter_frame(name());
+
+    let result = f(realm);
+
+    hook.leave_frame();
+
+    result
+}
+
+/// Runs the block of a `try` statement.
+pub fn try_block<T>(realm: &mut Realm, f: impl FnOnce(&mut Realm) -> T) -> T {
+    let Some(hook) = realm.debugger() else {
+        return f(realm);
+    };
+
+    hook.enter_try();
+
+    let result = f(realm);
+
+    hook.leave_try();
+
+    result
+}
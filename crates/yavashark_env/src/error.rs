@@ -143,6 +143,15 @@ impl Error {
         }
     }
 
+    /// An error that aborts the script, it can't be caught from JS.
+    #[must_use]
+    pub fn terminated(reason: &'static str) -> Self {
+        Self {
+            kind: ErrorKind::Terminated(YSString::new_static_ascii(reason)),
+            stacktrace: StackTrace { frames: vec![] },
+        }
+    }
+
     #[must_use]
     pub const fn throw(val: Value) -> Self {
         Self {
@@ -166,6 +175,7 @@ impl Error {
             ErrorKind::URI(_) => "URIError",
             ErrorKind::Aggregate(_) => "AggregateError",
             ErrorKind::Suppressed(_) => "SuppressedError",
+            ErrorKind::Terminated(_) => "Terminated",
         }
     }
 
@@ -180,6 +190,7 @@ impl Error {
             | ErrorKind::URI(msg)
             | ErrorKind::Aggregate(msg)
             | ErrorKind::Suppressed(msg)
+            | ErrorKind::Terminated(msg)
             | ErrorKind::Syntax(msg) => msg.clone(),
             ErrorKind::Throw(val) => val.to_string(realm)?,
             ErrorKind::Error(msg) => msg.clone().unwrap_or(YSString::new()),
@@ -198,12 +209,18 @@ impl Error {
             | ErrorKind::URI(msg)
             | ErrorKind::Aggregate(msg)
             | ErrorKind::Suppressed(msg)
+            | ErrorKind::Terminated(msg)
             | ErrorKind::Syntax(msg) => msg.clone(),
             ErrorKind::Throw(val) => val.to_ys_string(),
             ErrorKind::Error(msg) => msg.clone().unwrap_or(YSString::new()),
         }
     }
 
+    #[must_use]
+    pub const fn is_terminated(&self) -> bool {
+        matches!(self.kind, ErrorKind::Terminated(_))
+    }
+
     #[must_use]
     pub const fn stack(&self) -> &StackTrace {
         &self.stacktrace
@@ -270,6 +287,9 @@ pub enum ErrorKind {
     URI(YSString),
     Aggregate(YSString),
     Suppressed(YSString),
+    /// Execution was aborted by a limit or an interrupt, not catchable by
+    /// `try`/`catch`.
+    Terminated(YSString),
     Throw(Value),
     Error(Option<YSString>),
 }
@@ -1,4 +1,4 @@
-use crate::error::ErrorKind;
+use crate::error::{ErrorKind, StackTrace};
 use crate::realm::Realm;
 use crate::value::CustomName;
 use crate::{Error, MutObject, ObjectHandle, Res, Value, ValueResult};
@@ -12,6 +12,9 @@ use yavashark_string::{ToYSString, YSString};
 pub struct ErrorObj {
     #[mutable]
     pub(crate) error: Error,
+    /// Where the error was first thrown, kept when it's turned into a value.
+    #[mutable]
+    pub(crate) stack: StackTrace,
 }
 
 impl ErrorObj {
@@ -68,6 +71,7 @@ impl ErrorObj {
             inner: RefCell::new(MutableErrorObj {
                 object: MutObject::with_proto(proto),
                 error,
+                stack: StackTrace::default(),
             }),
         };
 
@@ -76,11 +80,33 @@ impl ErrorObj {
 
     pub fn error_to_value(err: Error, realm: &mut Realm) -> ValueResult {
         Ok(match err.kind {
-            ErrorKind::Throw(throw) => throw,
+            ErrorKind::Throw(throw) => {
+                if let Value::Object(obj) = &throw
+                    && let Some(error) = obj.downcast::<Self>()
+                    && let Ok(mut inner) = error.inner.try_borrow_mut()
+                    && inner.stack.frames.is_empty()
+                {
+                    inner.stack = err.stacktrace;
+                }
+
+                throw
+            }
             _ => Self::new(err, realm)?.into(),
         })
     }
 
+    /// The stack trace of the first `throw` of this error, or the one it was
+    /// created with if it came from the engine.
+    pub fn stack_trace(&self) -> Res<StackTrace> {
+        let inner = self.inner.try_borrow()?;
+
+        if inner.stack.frames.is_empty() {
+            Ok(inner.error.stacktrace.clone())
+        } else {
+            Ok(inner.stack.clone())
+        }
+    }
+
     pub fn new_from(message: YSString, realm: &mut Realm) -> Res<ObjectHandle> {
         let this = Self {
             inner: RefCell::new(MutableErrorObj {
@@ -88,6 +114,7 @@ impl ErrorObj {
                     realm.intrinsics.clone_public().error.get(realm)?.clone(),
                 ),
                 error: Error::unknown_error(message),
+                stack: StackTrace::default(),
             }),
         };
 
@@ -146,6 +173,7 @@ impl ErrorObj {
             inner: RefCell::new(MutableErrorObj {
                 object: MutObject::with_proto(proto),
                 error,
+                stack: StackTrace::default(),
             }),
         })
     }
@@ -157,6 +185,7 @@ impl ErrorObj {
                     realm.intrinsics.clone_public().error.get(realm)?.clone(),
                 ),
                 error: Error::unknown_error(message),
+                stack: StackTrace::default(),
             }),
         })
     }
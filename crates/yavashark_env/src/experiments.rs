@@ -3,12 +3,15 @@ use crate::experiments::http::Http;
 use crate::experiments::tcp::Tcp;
 use crate::{ObjectHandle, Realm, Res};
 
+pub mod fetch;
 mod fs;
 #[cfg(feature = "gui")]
 mod gui;
 mod http;
 mod io;
 mod tcp;
+#[cfg(test)]
+mod test_utils;
 mod time;
 mod timers;
 
@@ -26,6 +29,8 @@ pub fn init(obj: &ObjectHandle, realm: &mut Realm) -> Res {
         realm,
     )?;
 
+    fetch::init(realm)?;
+
     #[cfg(feature = "gui")]
     gui::init(realm)?;
 
@@ -0,0 +1,447 @@
+mod abort;
+mod body;
+mod client;
+mod headers;
+mod request;
+mod response;
+
+pub use abort::{AbortController, AbortSignal, AbortState};
+pub use body::{ReadableStream, ReadableStreamDefaultReader};
+pub use client::{FetchRequest, HttpUrl, RedirectMode, ResponseHead};
+pub use headers::Headers;
+pub use request::{Request, RequestInit};
+pub use response::Response;
+
+use crate::builtins::{IntoPromise, Promise};
+use crate::conversion::{FromValueOutput, TryIntoValue};
+use crate::realm::Intrinsic;
+use crate::value::IntoValue;
+use crate::{Error, NativeFunction, ObjectHandle, Realm, Res, Value, ValueResult};
+use abort::Abortable;
+use body::ReadableStreamAsyncIterator;
+use client::BodyReader;
+use std::rc::Rc;
+
+const MAX_REDIRECTS: usize = 20;
+
+/// What the embedder wants to happen with a request `fetch` is about to send.
+pub enum FetchDecision {
+    /// Send the (possibly modified) request over the network.
+    Continue(FetchRequest),
+    /// Answer the request without touching the network.
+    Respond(ResponseHead, Vec<u8>),
+    /// Fail the request, `fetch` rejects with a `TypeError`.
+    Deny(String),
+}
+
+/// Called for every request `fetch` sends, including each redirect hop.
+pub trait FetchHook {
+    fn on_request(&self, request: FetchRequest) -> FetchDecision;
+}
+
+impl<F: Fn(FetchRequest) -> FetchDecision> FetchHook for F {
+    fn on_request(&self, request: FetchRequest) -> FetchDecision {
+        self(request)
+    }
+}
+
+pub fn init(realm: &mut Realm) -> Res {
+    register::<AbortSignal>(realm, Some("AbortSignal"))?;
+    register::<AbortController>(realm, Some("AbortController"))?;
+    register::<Headers>(realm, Some("Headers"))?;
+    register::<ReadableStream>(realm, Some("ReadableStream"))?;
+    register::<ReadableStreamDefaultReader>(realm, Some("ReadableStreamDefaultReader"))?;
+    register::<ReadableStreamAsyncIterator>(realm, None)?;
+    register::<Request>(realm, Some("Request"))?;
+    register::<Response>(realm, Some("Response"))?;
+
+    let fetch = NativeFunction::with_len(
+        "fetch",
+        |mut args, _, realm| {
+            let input = if args.is_empty() {
+                Value::Undefined
+            } else {
+                args.remove(0)
+            };
+
+            let init = match args.into_iter().next() {
+                Some(init) => Option::<RequestInit>::from_value_out(init, realm)?,
+                None => None,
+            };
+
+            Ok(fetch(input, init, realm)?.into())
+        },
+        realm,
+        1,
+    );
+
+    let global = realm.global.clone();
+    global.set("fetch", fetch, realm)?;
+
+    Ok(())
+}
+
+fn register<T: Intrinsic + 'static>(realm: &mut Realm, global: Option<&'static str>) -> Res {
+    let proto = T::initialize(realm)?;
+
+    realm.intrinsics.insert::<T>(proto.clone());
+
+    if let Some(name) = global {
+        let constructor = proto.get("constructor", realm)?;
+
+        realm.global.clone().set(name, constructor, realm)?;
+    }
+
+    Ok(())
+}
+
+fn network_error(err: impl std::fmt::Display) -> Error {
+    Error::ty_error(format!("fetch failed: {err}"))
+}
+
+/// Starts a fetch, returning a promise for the `Response`.
+pub fn fetch(input: Value, init: Option<RequestInit>, realm: &mut Realm) -> Res<ObjectHandle> {
+    let request = match Request::from_input(input, init, realm) {
+        Ok(request) => request,
+        Err(err) => return Promise::from_error(err, realm),
+    };
+
+    let body = match request.body.take_reader() {
+        Ok(body) => body,
+        Err(err) => return Promise::from_error(err, realm),
+    };
+
+    let head = FetchRequest {
+        method: request.method.clone(),
+        url: request.url.clone(),
+        headers: request.header_list()?.to_vec(),
+        body: None,
+    };
+
+    let signal = Rc::clone(&request.signal_state);
+    let hook = realm.fetch_hook.clone();
+    let redirect = request.redirect;
+
+    Abortable::new(
+        async move {
+            let mut head = head;
+
+            if let Some(body) = body {
+                head.body = Some(body.lock().await.read_all().await.map_err(network_error)?);
+            }
+
+            let (url, redirected, res, body) = send(head, redirect, hook).await?;
+
+            Ok(FetchOutcome {
+                url,
+                redirected,
+                head: res,
+                body,
+                signal,
+            })
+        },
+        Some(Rc::clone(&request.signal_state)),
+    )
+    .into_promise(realm)
+}
+
+/// A plain `GET` that resolves with the response body as a string.
+pub fn get_text(url: &str, realm: &mut Realm) -> Res<ObjectHandle> {
+    let url = match HttpUrl::parse(url) {
+        Ok(url) => url,
+        Err(err) => return Promise::from_error(Error::ty_error(err), realm),
+    };
+
+    let request = FetchRequest {
+        method: "GET".to_string(),
+        url,
+        headers: Vec::new(),
+        body: None,
+    };
+
+    let hook = realm.fetch_hook.clone();
+
+    async move {
+        let (_, _, _, mut body) = send(request, RedirectMode::Follow, hook).await?;
+
+        let bytes = body.read_all().await.map_err(network_error)?;
+
+        Ok::<_, Error>(String::from_utf8_lossy(&bytes).into_owned())
+    }
+    .into_promise(realm)
+}
+
+/// Sends a request and follows redirects according to `redirect`.
+async fn send(
+    mut request: FetchRequest,
+    redirect: RedirectMode,
+    hook: Option<Rc<dyn FetchHook>>,
+) -> Res<(HttpUrl, bool, ResponseHead, BodyReader)> {
+    let mut redirected = false;
+
+    for _ in 0..=MAX_REDIRECTS {
+        if let Some(hook) = &hook {
+            let url = request.url.clone();
+
+            match hook.on_request(request) {
+                FetchDecision::Continue(req) => request = req,
+                FetchDecision::Respond(head, body) => {
+                    return Ok((url, redirected, head, BodyReader::from_bytes(body)));
+                }
+                FetchDecision::Deny(reason) => return Err(network_error(reason)),
+            }
+        }
+
+        let (head, body) = client::send(&request).await.map_err(network_error)?;
+
+        if !head.is_redirect() || redirect == RedirectMode::Manual {
+            return Ok((request.url, redirected, head, body));
+        }
+
+        if redirect == RedirectMode::Error {
+            return Err(network_error("unexpected redirect"));
+        }
+
+        let Some(location) = head.location() else {
+            return Ok((request.url, redirected, head, body));
+        };
+
+        request.url = request.url.join(location).map_err(network_error)?;
+        redirected = true;
+
+        if head.status == 303 || (matches!(head.status, 301 | 302) && request.method == "POST") {
+            if request.method != "HEAD" {
+                request.method = "GET".to_string();
+            }
+
+            request.body = None;
+            request.headers.retain(|(name, _)| {
+                !name.eq_ignore_ascii_case("content-type")
+                    && !name.eq_ignore_ascii_case("content-length")
+            });
+        }
+    }
+
+    Err(network_error("too many redirects"))
+}
+
+struct FetchOutcome {
+    url: HttpUrl,
+    redirected: bool,
+    head: ResponseHead,
+    body: BodyReader,
+    signal: Rc<AbortState>,
+}
+
+impl TryIntoValue for FetchOutcome {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        let response = Response::from_network(
+            self.head,
+            self.body,
+            self.url,
+            self.redirected,
+            Some(self.signal),
+            realm,
+        )?;
+
+        Ok(response.into_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Object;
+    use crate::error_obj::ErrorObj;
+    use crate::experiments::test_utils::{call, fulfilled, get, settle};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Serves one canned response per connection and returns the raw requests.
+    fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request = String::new();
+                let mut content_length = 0;
+
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+
+                    if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+
+                    request.push_str(&line);
+
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+
+                stream.write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+
+            requests
+        });
+
+        (base, handle)
+    }
+
+    fn text(response: &Value, realm: &mut Realm) -> String {
+        let promise = call(response, "text", Vec::new(), realm);
+
+        fulfilled(realm, promise)
+            .to_string(realm)
+            .unwrap()
+            .to_string()
+    }
+
+    fn fetch(args: Vec<Value>, realm: &mut Realm) -> Value {
+        let fetch = realm.global.clone().get("fetch", realm).unwrap();
+
+        fetch.call(realm, args, Value::Undefined).unwrap()
+    }
+
+    #[test]
+    fn fetch_text() {
+        let (base, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello",
+        ]);
+
+        let mut realm = Realm::new().unwrap();
+        let promise = fetch(vec![format!("{base}/hello").into()], &mut realm);
+        let response = fulfilled(&mut realm, promise);
+
+        assert_eq!(get(&response, "status", &mut realm), Value::Number(200.0));
+        assert_eq!(get(&response, "ok", &mut realm), Value::Boolean(true));
+
+        let headers = get(&response, "headers", &mut realm);
+        let content_type = call(&headers, "get", vec!["Content-Type".into()], &mut realm);
+        assert_eq!(content_type, Value::from("text/plain"));
+
+        assert_eq!(text(&response, &mut realm), "hello");
+        assert_eq!(get(&response, "bodyUsed", &mut realm), Value::Boolean(true));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /hello HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn fetch_follows_redirects_and_reads_chunked() {
+        let (base, server) = serve(vec![
+            "HTTP/1.1 303 See Other\r\nLocation: /next\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+        ]);
+
+        let mut realm = Realm::new().unwrap();
+
+        let init = Object::new(&realm);
+        init.define_property("method".into(), "POST".into(), &mut realm)
+            .unwrap();
+        init.define_property("body".into(), "data".into(), &mut realm)
+            .unwrap();
+
+        let promise = fetch(
+            vec![format!("{base}/start").into(), init.into()],
+            &mut realm,
+        );
+        let response = fulfilled(&mut realm, promise);
+
+        assert_eq!(
+            get(&response, "redirected", &mut realm),
+            Value::Boolean(true)
+        );
+        assert_eq!(
+            get(&response, "url", &mut realm),
+            Value::from(format!("{base}/next"))
+        );
+        assert_eq!(text(&response, &mut realm), "hello world");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /start HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\ndata"));
+        assert!(requests[1].starts_with("GET /next HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn fetch_rejects_when_aborted() {
+        let mut realm = Realm::new().unwrap();
+
+        let controller = realm
+            .global
+            .clone()
+            .get("AbortController", &mut realm)
+            .unwrap();
+        let controller: Value = controller
+            .as_object()
+            .unwrap()
+            .construct(Vec::new(), &mut realm)
+            .unwrap()
+            .into();
+
+        let signal = get(&controller, "signal", &mut realm);
+        call(&controller, "abort", Vec::new(), &mut realm);
+
+        let init = Object::new(&realm);
+        init.define_property("signal".into(), signal, &mut realm)
+            .unwrap();
+
+        let promise = fetch(vec!["http://127.0.0.1:1/".into(), init.into()], &mut realm);
+
+        let err = settle(&mut realm, promise).unwrap_err();
+
+        assert_eq!(get(&err, "name", &mut realm), Value::from("AbortError"));
+    }
+
+    #[test]
+    fn fetch_hook_can_respond_and_deny() {
+        let mut realm = Realm::new().unwrap();
+
+        realm.set_fetch_hook(|request: FetchRequest| {
+            if request.url.path() == "/mocked" {
+                FetchDecision::Respond(
+                    ResponseHead {
+                        status: 201,
+                        status_text: "Created".to_string(),
+                        headers: Vec::new(),
+                    },
+                    b"from hook".to_vec(),
+                )
+            } else {
+                FetchDecision::Deny("blocked".to_string())
+            }
+        });
+
+        let promise = fetch(vec!["http://example.invalid/mocked".into()], &mut realm);
+        let response = fulfilled(&mut realm, promise);
+
+        assert_eq!(get(&response, "status", &mut realm), Value::Number(201.0));
+        assert_eq!(text(&response, &mut realm), "from hook");
+
+        let promise = fetch(vec!["http://example.invalid/other".into()], &mut realm);
+
+        let err = settle(&mut realm, promise).unwrap_err();
+
+        let err = err.downcast::<ErrorObj>().unwrap().unwrap();
+        let message = err.override_to_string_internal().unwrap();
+        assert_eq!(
+            message.to_string().trim_end(),
+            "TypeError: fetch failed: blocked"
+        );
+    }
+}
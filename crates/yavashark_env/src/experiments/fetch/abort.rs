@@ -0,0 +1,327 @@
+use crate::error_obj::ErrorObj;
+use crate::experiments::timers::SleepDuration;
+use crate::task_queue::{AsyncTask, AsyncTaskQueue};
+use crate::utils::ValueIterator;
+use crate::value::Obj;
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value};
+use pin_project::pin_project;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use yavashark_macro::{object, props};
+
+/// Shared state of an `AbortSignal`. Native tasks keep an `Rc` to this to get
+/// woken up and cancel themselves once the signal fires.
+#[derive(Debug, Default)]
+pub struct AbortState {
+    reason: RefCell<Option<Value>>,
+    listeners: RefCell<Vec<ObjectHandle>>,
+    on_abort: RefCell<Option<ObjectHandle>>,
+    wakers: RefCell<Vec<Waker>>,
+    dependents: RefCell<Vec<Rc<Self>>>,
+}
+
+impl AbortState {
+    #[must_use]
+    pub fn aborted(&self) -> bool {
+        self.reason.borrow().is_some()
+    }
+
+    #[must_use]
+    pub fn reason(&self) -> Option<Value> {
+        self.reason.borrow().clone()
+    }
+
+    pub fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.borrow_mut();
+
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    pub fn abort(&self, reason: &Value, realm: &mut Realm) -> Res {
+        if self.aborted() {
+            return Ok(());
+        }
+
+        *self.reason.borrow_mut() = Some(reason.clone());
+
+        for waker in self.wakers.take() {
+            waker.wake();
+        }
+
+        let event = Object::new(realm);
+        event.define_property("type".into(), "abort".into(), realm)?;
+
+        let on_abort = self.on_abort.borrow().clone();
+
+        if let Some(on_abort) = on_abort {
+            on_abort.call(vec![event.clone().into()], Value::Undefined, realm)?;
+        }
+
+        for listener in self.listeners.take() {
+            listener.call(vec![event.clone().into()], Value::Undefined, realm)?;
+        }
+
+        for dependent in self.dependents.take() {
+            dependent.abort(reason, realm)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn abort_error(name: &'static str, message: &'static str, realm: &mut Realm) -> Res<Value> {
+        let err = ErrorObj::new_from(message.into(), realm)?;
+        err.define_property("name".into(), name.into(), realm)?;
+
+        Ok(err.into())
+    }
+}
+
+/// Wraps a future so it resolves with the signal's reason as soon as the
+/// signal is aborted.
+#[pin_project]
+pub struct Abortable<F> {
+    #[pin]
+    future: F,
+    signal: Option<Rc<AbortState>>,
+}
+
+impl<F> Abortable<F> {
+    pub const fn new(future: F, signal: Option<Rc<AbortState>>) -> Self {
+        Self { future, signal }
+    }
+}
+
+impl<T, F: Future<Output = Res<T>>> Future for Abortable<F> {
+    type Output = Res<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Some(signal) = this.signal {
+            if let Some(reason) = signal.reason() {
+                return Poll::Ready(Err(Error::throw(reason)));
+            }
+
+            signal.register(cx.waker());
+        }
+
+        this.future.poll(cx)
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct AbortSignal {
+    pub state: Rc<AbortState>,
+}
+
+impl AbortSignal {
+    pub fn new(state: Rc<AbortState>, realm: &Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableAbortSignal {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            state,
+        })
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "AbortSignal")]
+impl AbortSignal {
+    #[constructor]
+    fn construct() -> Res<ObjectHandle> {
+        Err(Error::ty("Illegal constructor"))
+    }
+
+    #[get("aborted")]
+    fn aborted(&self) -> bool {
+        self.state.aborted()
+    }
+
+    #[get("reason")]
+    fn reason(&self) -> Value {
+        self.state.reason().unwrap_or(Value::Undefined)
+    }
+
+    #[get("onabort")]
+    fn on_abort(&self) -> Value {
+        self.state
+            .on_abort
+            .borrow()
+            .clone()
+            .map_or(Value::Null, Into::into)
+    }
+
+    #[set("onabort")]
+    fn set_on_abort(&self, handler: Value) {
+        *self.state.on_abort.borrow_mut() = match handler {
+            Value::Object(obj) if obj.is_callable() => Some(obj),
+            _ => None,
+        };
+    }
+
+    #[prop("throwIfAborted")]
+    fn throw_if_aborted(&self) -> Res {
+        self.state
+            .reason()
+            .map_or(Ok(()), |reason| Err(Error::throw(reason)))
+    }
+
+    #[prop("addEventListener")]
+    fn add_event_listener(&self, ty: &str, listener: Value) {
+        let Value::Object(listener) = listener else {
+            return;
+        };
+
+        if ty != "abort" || !listener.is_callable() || self.state.aborted() {
+            return;
+        }
+
+        let mut listeners = self.state.listeners.borrow_mut();
+
+        if !listeners.contains(&listener) {
+            listeners.push(listener);
+        }
+    }
+
+    #[prop("removeEventListener")]
+    fn remove_event_listener(&self, ty: &str, listener: Value) {
+        let Value::Object(listener) = listener else {
+            return;
+        };
+
+        if ty == "abort" {
+            self.state.listeners.borrow_mut().retain(|l| *l != listener);
+        }
+    }
+
+    #[prop("abort")]
+    fn abort_static(reason: Option<Value>, realm: &mut Realm) -> Res<ObjectHandle> {
+        let reason = match reason {
+            Some(reason) => reason,
+            None => AbortState::abort_error("AbortError", "This operation was aborted", realm)?,
+        };
+
+        let state = Rc::new(AbortState::default());
+        *state.reason.borrow_mut() = Some(reason);
+
+        Ok(Self::new(state, realm)?.into_object())
+    }
+
+    fn timeout(ms: f64, realm: &mut Realm) -> Res<ObjectHandle> {
+        if !ms.is_finite() || ms < 0.0 {
+            return Err(Error::ty("timeout must be a non-negative finite number"));
+        }
+
+        let state = Rc::new(AbortState::default());
+
+        AsyncTaskQueue::queue_task(
+            AbortTimeoutTask {
+                timer: SleepDuration::new(Duration::from_millis(ms as u64)),
+                state: Rc::clone(&state),
+            },
+            realm,
+        );
+
+        Ok(Self::new(state, realm)?.into_object())
+    }
+
+    fn any(signals: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        let state = Rc::new(AbortState::default());
+
+        let iter = ValueIterator::new(signals, realm)?;
+
+        while let Some(signal) = iter.next(realm)? {
+            let signal = signal.downcast::<Self>()?.ok_or(Error::ty(
+                "AbortSignal.any expects an iterable of AbortSignals",
+            ))?;
+
+            if let Some(reason) = signal.state.reason() {
+                *state.reason.borrow_mut() = Some(reason);
+                state.dependents.borrow_mut().clear();
+                break;
+            }
+
+            signal.state.dependents.borrow_mut().push(Rc::clone(&state));
+        }
+
+        Ok(Self::new(state, realm)?.into_object())
+    }
+}
+
+#[pin_project(!Unpin)]
+struct AbortTimeoutTask {
+    timer: SleepDuration,
+    state: Rc<AbortState>,
+}
+
+impl AsyncTask for AbortTimeoutTask {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context, realm: &mut Realm) -> Poll<Res> {
+        let proj = self.project();
+
+        if proj.state.aborted() {
+            return Poll::Ready(Ok(()));
+        }
+
+        let sleep = unsafe { Pin::new_unchecked(proj.timer.get_sleep()) };
+
+        match sleep.poll(cx) {
+            Poll::Ready(()) => {
+                let reason =
+                    AbortState::abort_error("TimeoutError", "The operation timed out", realm)?;
+
+                Poll::Ready(proj.state.abort(&reason, realm))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn run_first_sync(&mut self, _realm: &mut Realm) -> Poll<Res> {
+        Poll::Pending
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct AbortController {
+    signal: ObjectHandle,
+    state: Rc<AbortState>,
+}
+
+#[props(no_intrinsic, to_string_tag = "AbortController")]
+impl AbortController {
+    #[constructor]
+    fn construct(realm: &Realm) -> Res<ObjectHandle> {
+        let state = Rc::new(AbortState::default());
+        let signal = AbortSignal::new(Rc::clone(&state), realm)?.into_object();
+
+        Ok(Self {
+            inner: RefCell::new(MutableAbortController {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            signal,
+            state,
+        }
+        .into_object())
+    }
+
+    #[get("signal")]
+    fn signal(&self) -> ObjectHandle {
+        self.signal.clone()
+    }
+
+    fn abort(&self, reason: Option<Value>, realm: &mut Realm) -> Res {
+        let reason = match reason {
+            Some(reason) => reason,
+            None => AbortState::abort_error("AbortError", "This operation was aborted", realm)?,
+        };
+
+        self.state.abort(&reason, realm)
+    }
+}
@@ -0,0 +1,462 @@
+use super::abort::{AbortState, Abortable};
+use super::client::{BodyReader, SharedReader};
+use super::network_error;
+use crate::builtins::IntoPromise;
+use crate::builtins::array_buf::ArrayBuffer;
+use crate::builtins::dataview::DataView;
+use crate::builtins::typed_array::{Type, TypedArray, create_ta};
+use crate::builtins::{JSON, Promise};
+use crate::conversion::TryIntoValue;
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value, ValueResult};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use yavashark_macro::{object, props};
+
+/// The state behind a body, shared between the `Request` / `Response` it
+/// belongs to and the `ReadableStream` exposed through `.body`.
+#[derive(Debug)]
+pub struct BodyState {
+    reader: RefCell<Option<SharedReader>>,
+    disturbed: Cell<bool>,
+    locked: Cell<bool>,
+    signal: Option<Rc<AbortState>>,
+}
+
+impl BodyState {
+    fn new(reader: BodyReader, signal: Option<Rc<AbortState>>) -> Rc<Self> {
+        Rc::new(Self {
+            reader: RefCell::new(Some(reader.shared())),
+            disturbed: Cell::new(false),
+            locked: Cell::new(false),
+            signal,
+        })
+    }
+
+    const fn unusable(&self) -> bool {
+        self.disturbed.get() || self.locked.get()
+    }
+
+    fn read_chunk(self: &Rc<Self>, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.disturbed.set(true);
+
+        let reader = self.reader.borrow().clone();
+
+        Abortable::new(
+            async move {
+                let chunk = match reader {
+                    Some(reader) => reader.lock().await.next_chunk().await,
+                    None => Ok(None),
+                };
+
+                chunk.map(ReadResult).map_err(network_error)
+            },
+            self.signal.clone(),
+        )
+        .into_promise(realm)
+    }
+
+    fn cancel(&self) {
+        self.disturbed.set(true);
+        self.reader.borrow_mut().take();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BodyKind {
+    Text,
+    Json,
+    ArrayBuffer,
+    Bytes,
+}
+
+/// A fully read body, converted to a JS value once the read completes.
+pub struct BodyConversion {
+    bytes: Vec<u8>,
+    kind: BodyKind,
+}
+
+impl TryIntoValue for BodyConversion {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        Ok(match self.kind {
+            BodyKind::Text => decode_utf8(&self.bytes).into(),
+            BodyKind::Json => {
+                let json = serde_json::from_slice(strip_bom(&self.bytes))
+                    .map_err(|e| Error::syn_error(e.to_string()))?;
+
+                JSON::value_from_serde(json, realm)?
+            }
+            BodyKind::ArrayBuffer => ArrayBuffer::from_buffer(realm, self.bytes)?.into_value(),
+            BodyKind::Bytes => create_ta(realm, Type::U8, self.bytes)?.into(),
+        })
+    }
+}
+
+struct ReadResult(Option<Vec<u8>>);
+
+impl TryIntoValue for ReadResult {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        let res = Object::new(realm);
+
+        let done = self.0.is_none();
+
+        let value = match self.0 {
+            Some(chunk) => create_ta(realm, Type::U8, chunk)?.into(),
+            None => Value::Undefined,
+        };
+
+        res.define_property("value".into(), value, realm)?;
+        res.define_property("done".into(), done.into(), realm)?;
+
+        Ok(res.into())
+    }
+}
+
+fn strip_bom(bytes: &[u8]) -> &[u8] {
+    bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes)
+}
+
+fn decode_utf8(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(strip_bom(bytes)).into_owned()
+}
+
+/// The body of a `Request` or `Response`, `None` is a null body.
+#[derive(Debug, Default)]
+pub struct Body {
+    state: Option<Rc<BodyState>>,
+    stream: RefCell<Option<ObjectHandle>>,
+}
+
+impl Body {
+    #[must_use]
+    pub fn new(reader: BodyReader, signal: Option<Rc<AbortState>>) -> Self {
+        Self {
+            state: Some(BodyState::new(reader, signal)),
+            stream: RefCell::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self::new(BodyReader::from_bytes(bytes), None)
+    }
+
+    /// Extracts a body from a `BodyInit`, also returning the content type that
+    /// goes with it.
+    pub fn extract(value: &Value, realm: &mut Realm) -> Res<(Self, Option<&'static str>)> {
+        if let Value::Object(obj) = value {
+            if let Some(buf) = obj.downcast::<ArrayBuffer>() {
+                return Ok((Self::from_bytes(buf.get_slice()?.to_vec()), None));
+            }
+
+            if let Some(ta) = obj.downcast::<TypedArray>() {
+                return Ok((Self::from_bytes(ta.to_bytes()?), None));
+            }
+
+            if let Some(view) = obj.downcast::<DataView>() {
+                return Ok((Self::from_bytes(view.to_bytes()?), None));
+            }
+
+            if let Some(stream) = obj.downcast::<ReadableStream>() {
+                if stream.state.unusable() {
+                    return Err(Error::ty("ReadableStream is locked or disturbed"));
+                }
+
+                return Ok((
+                    Self {
+                        state: Some(Rc::clone(&stream.state)),
+                        stream: RefCell::new(Some(obj.clone())),
+                    },
+                    None,
+                ));
+            }
+        }
+
+        let text = value.to_string(realm)?;
+
+        Ok((
+            Self::from_bytes(text.as_str_lossy().into_owned().into_bytes()),
+            Some("text/plain;charset=UTF-8"),
+        ))
+    }
+
+    #[must_use]
+    pub const fn is_null(&self) -> bool {
+        self.state.is_none()
+    }
+
+    #[must_use]
+    pub fn used(&self) -> bool {
+        self.state.as_ref().is_some_and(|s| s.disturbed.get())
+    }
+
+    /// Returns the `ReadableStream` for this body, creating it on first access.
+    pub fn stream(&self, realm: &Realm) -> ValueResult {
+        let Some(state) = &self.state else {
+            return Ok(Value::Null);
+        };
+
+        let mut stream = self.stream.borrow_mut();
+
+        if let Some(stream) = &*stream {
+            return Ok(stream.clone().into());
+        }
+
+        let obj = ReadableStream::new(Rc::clone(state), realm)?.into_object();
+        *stream = Some(obj.clone());
+
+        Ok(obj.into())
+    }
+
+    /// Reads the whole body and converts it, returning a promise.
+    pub fn consume(&self, kind: BodyKind, realm: &mut Realm) -> Res<ObjectHandle> {
+        let Some(state) = &self.state else {
+            let value = BodyConversion {
+                bytes: Vec::new(),
+                kind,
+            }
+            .try_into_value(realm);
+
+            return match value {
+                Ok(value) => Promise::resolved(&value, realm),
+                Err(err) => Promise::from_error(err, realm),
+            };
+        };
+
+        if state.unusable() {
+            return Promise::from_error(Error::ty("Body has already been consumed"), realm);
+        }
+
+        state.disturbed.set(true);
+
+        let reader = state.reader.borrow_mut().take();
+
+        Abortable::new(
+            async move {
+                let bytes = match reader {
+                    Some(reader) => reader.lock().await.read_all().await,
+                    None => Ok(Vec::new()),
+                };
+
+                bytes
+                    .map(|bytes| BodyConversion { bytes, kind })
+                    .map_err(network_error)
+            },
+            state.signal.clone(),
+        )
+        .into_promise(realm)
+    }
+
+    /// Takes the reader out of the body to send it, marking it as used.
+    pub fn take_reader(&self) -> Res<Option<SharedReader>> {
+        let Some(state) = &self.state else {
+            return Ok(None);
+        };
+
+        if state.unusable() {
+            return Err(Error::ty("Body has already been consumed"));
+        }
+
+        state.disturbed.set(true);
+
+        Ok(state.reader.borrow_mut().take())
+    }
+
+    /// Moves the body to a new owner, leaving this one used.
+    pub fn transfer(&self) -> Res<Self> {
+        let Some(state) = &self.state else {
+            return Ok(Self::default());
+        };
+
+        let reader = self.take_reader()?;
+
+        Ok(Self {
+            state: Some(Rc::new(BodyState {
+                reader: RefCell::new(reader),
+                disturbed: Cell::new(false),
+                locked: Cell::new(false),
+                signal: state.signal.clone(),
+            })),
+            stream: RefCell::default(),
+        })
+    }
+
+    /// Tees the body, keeping one branch and returning the other.
+    pub fn try_clone(&self) -> Res<Self> {
+        let Some(state) = &self.state else {
+            return Ok(Self::default());
+        };
+
+        if state.unusable() {
+            return Err(Error::ty("Body has already been consumed"));
+        }
+
+        let Some(reader) = state.reader.borrow_mut().take() else {
+            return Ok(Self::from_bytes(Vec::new()));
+        };
+
+        let (ours, theirs) = BodyReader::tee(reader);
+
+        *state.reader.borrow_mut() = Some(ours.shared());
+
+        Ok(Self::new(theirs, state.signal.clone()))
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct ReadableStream {
+    state: Rc<BodyState>,
+}
+
+impl ReadableStream {
+    pub fn new(state: Rc<BodyState>, realm: &Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableReadableStream {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            state,
+        })
+    }
+
+    fn lock(&self) -> Res {
+        if self.state.locked.get() {
+            return Err(Error::ty("ReadableStream is already locked"));
+        }
+
+        self.state.locked.set(true);
+
+        Ok(())
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "ReadableStream")]
+impl ReadableStream {
+    #[constructor]
+    fn construct() -> Res<ObjectHandle> {
+        Err(Error::ty(
+            "ReadableStream can currently only be obtained from a fetch body",
+        ))
+    }
+
+    #[get("locked")]
+    fn locked(&self) -> bool {
+        self.state.locked.get()
+    }
+
+    #[prop("getReader")]
+    fn get_reader(&self, realm: &Realm) -> Res<ObjectHandle> {
+        self.lock()?;
+
+        Ok(ReadableStreamDefaultReader::new(Rc::clone(&self.state), realm)?.into_object())
+    }
+
+    fn cancel(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        if self.state.locked.get() {
+            return Promise::from_error(Error::ty("ReadableStream is locked"), realm);
+        }
+
+        self.state.cancel();
+
+        Promise::resolved(&Value::Undefined, realm)
+    }
+
+    #[prop(crate::Symbol::ASYNC_ITERATOR)]
+    fn values(&self, realm: &Realm) -> Res<ObjectHandle> {
+        self.lock()?;
+
+        Ok(ReadableStreamAsyncIterator::new(Rc::clone(&self.state), realm)?.into_object())
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct ReadableStreamDefaultReader {
+    state: Rc<BodyState>,
+    released: Cell<bool>,
+}
+
+impl ReadableStreamDefaultReader {
+    pub fn new(state: Rc<BodyState>, realm: &Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableReadableStreamDefaultReader {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            state,
+            released: Cell::new(false),
+        })
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "ReadableStreamDefaultReader")]
+impl ReadableStreamDefaultReader {
+    #[constructor]
+    fn construct(stream: &Value, realm: &Realm) -> Res<ObjectHandle> {
+        let stream = stream
+            .downcast::<ReadableStream>()?
+            .ok_or(Error::ty("Expected a ReadableStream"))?;
+
+        stream.lock()?;
+
+        Ok(Self::new(Rc::clone(&stream.state), realm)?.into_object())
+    }
+
+    fn read(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        if self.released.get() {
+            return Promise::from_error(Error::ty("Reader has been released"), realm);
+        }
+
+        self.state.read_chunk(realm)
+    }
+
+    #[prop("releaseLock")]
+    fn release_lock(&self) {
+        if !self.released.replace(true) {
+            self.state.locked.set(false);
+        }
+    }
+
+    fn cancel(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        if self.released.get() {
+            return Promise::from_error(Error::ty("Reader has been released"), realm);
+        }
+
+        self.state.cancel();
+
+        Promise::resolved(&Value::Undefined, realm)
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct ReadableStreamAsyncIterator {
+    state: Rc<BodyState>,
+}
+
+impl ReadableStreamAsyncIterator {
+    pub fn new(state: Rc<BodyState>, realm: &Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableReadableStreamAsyncIterator {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            state,
+        })
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "ReadableStream AsyncIterator")]
+impl ReadableStreamAsyncIterator {
+    fn next(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.state.read_chunk(realm)
+    }
+
+    #[prop("return")]
+    fn return_(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.state.cancel();
+        self.state.locked.set(false);
+
+        let res = ReadResult(None).try_into_value(realm)?;
+
+        Promise::resolved(&res, realm)
+    }
+}
@@ -0,0 +1,580 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Write as _};
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use url::{Host, Position, Url};
+use yavashark_macro::data_object;
+
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+/// An `http:` URL, the only kind of URL we can open a connection for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl(Url);
+
+impl HttpUrl {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let url = url.trim();
+
+        Url::parse(url)
+            .map_err(|e| format!("Invalid URL: {url} ({e})"))
+            .and_then(Self::from_url)
+    }
+
+    fn from_url(mut url: Url) -> Result<Self, String> {
+        match url.scheme() {
+            "http" => {}
+            "https" => return Err("https is not supported by fetch yet".to_string()),
+            scheme => return Err(format!("Unsupported URL scheme: {scheme}")),
+        }
+
+        if url.host().is_none() {
+            return Err(format!("Invalid URL: {url}"));
+        }
+
+        url.set_fragment(None);
+        _ = url.set_username("");
+        _ = url.set_password(None);
+
+        Ok(Self(url))
+    }
+
+    /// Resolves a `Location` header value against this URL.
+    pub fn join(&self, location: &str) -> Result<Self, String> {
+        self.0
+            .join(location)
+            .map_err(|e| format!("Invalid redirect location: {location} ({e})"))
+            .and_then(Self::from_url)
+    }
+
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.0.port_or_known_default().unwrap_or(80)
+    }
+
+    /// Path and query string, percent-encoded and always starting with `/`
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.0[Position::BeforePath..Position::AfterQuery]
+    }
+
+    fn host_header(&self) -> &str {
+        &self.0[Position::BeforeHost..Position::AfterPort]
+    }
+
+    async fn connect(&self) -> io::Result<TcpStream> {
+        let port = self.port();
+
+        match self.0.host() {
+            Some(Host::Domain(domain)) => TcpStream::connect((domain, port)).await,
+            Some(Host::Ipv4(addr)) => TcpStream::connect((addr, port)).await,
+            Some(Host::Ipv6(addr)) => TcpStream::connect((addr, port)).await,
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "URL has no host",
+            )),
+        }
+    }
+}
+
+impl Display for HttpUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[data_object]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectMode {
+    Follow,
+    Error,
+    Manual,
+}
+
+/// A request as it is sent over the wire, this is also what the embedder hook
+/// gets to see.
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub method: String,
+    pub url: HttpUrl,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+/// The head of a response, the body is read separately through a
+/// [`BodyReader`].
+#[derive(Debug, Clone)]
+pub struct ResponseHead {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl ResponseHead {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[must_use]
+    pub fn location(&self) -> Option<&str> {
+        self.header("location")
+    }
+
+    #[must_use]
+    pub const fn is_redirect(&self) -> bool {
+        matches!(self.status, 301 | 302 | 303 | 307 | 308)
+    }
+}
+
+#[derive(Debug)]
+enum Framing {
+    Length(u64),
+    Chunked(ChunkState),
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChunkState {
+    Size,
+    Data(u64),
+    Done,
+}
+
+#[derive(Debug)]
+struct HttpBody {
+    conn: BufReader<TcpStream>,
+    framing: Framing,
+}
+
+impl HttpBody {
+    async fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match &mut self.framing {
+            Framing::Length(0) => Ok(None),
+            Framing::Length(remaining) => {
+                let len = (*remaining).min(READ_CHUNK_SIZE as u64) as usize;
+                let mut buf = vec![0; len];
+                let read = self.conn.read(&mut buf).await?;
+
+                if read == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "connection closed before the body was complete",
+                    ));
+                }
+
+                *remaining -= read as u64;
+                buf.truncate(read);
+
+                Ok(Some(buf))
+            }
+            Framing::Eof => {
+                let mut buf = vec![0; READ_CHUNK_SIZE];
+                let read = self.conn.read(&mut buf).await?;
+
+                if read == 0 {
+                    return Ok(None);
+                }
+
+                buf.truncate(read);
+
+                Ok(Some(buf))
+            }
+            Framing::Chunked(state) => loop {
+                match *state {
+                    ChunkState::Done => return Ok(None),
+                    ChunkState::Size => {
+                        let line = read_line(&mut self.conn).await?;
+                        let size = line.split(';').next().unwrap_or_default().trim();
+                        let size = u64::from_str_radix(size, 16).map_err(|_| {
+                            io::Error::new(io::ErrorKind::InvalidData, "invalid chunk size")
+                        })?;
+
+                        if size == 0 {
+                            while !read_line(&mut self.conn).await?.is_empty() {}
+                            *state = ChunkState::Done;
+                        } else {
+                            *state = ChunkState::Data(size);
+                        }
+                    }
+                    ChunkState::Data(remaining) => {
+                        let len = remaining.min(READ_CHUNK_SIZE as u64) as usize;
+                        let mut buf = vec![0; len];
+                        let read = self.conn.read(&mut buf).await?;
+
+                        if read == 0 {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "connection closed inside a chunk",
+                            ));
+                        }
+
+                        buf.truncate(read);
+
+                        let remaining = remaining - read as u64;
+
+                        if remaining == 0 {
+                            read_line(&mut self.conn).await?;
+                            *state = ChunkState::Size;
+                        } else {
+                            *state = ChunkState::Data(remaining);
+                        }
+
+                        return Ok(Some(buf));
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TeeState {
+    pending: [VecDeque<Vec<u8>>; 2],
+    done: bool,
+}
+
+#[derive(Debug)]
+enum Source {
+    Memory(Option<Vec<u8>>),
+    Http(HttpBody),
+    Tee(SharedReader, Arc<Mutex<TeeState>>, usize),
+}
+
+/// Produces the chunks of a request or response body, independent of where
+/// they come from.
+#[derive(Debug)]
+pub struct BodyReader {
+    source: Source,
+}
+
+pub type SharedReader = Arc<Mutex<BodyReader>>;
+
+impl BodyReader {
+    #[must_use]
+    pub const fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            source: Source::Memory(Some(bytes)),
+        }
+    }
+
+    #[must_use]
+    pub fn shared(self) -> SharedReader {
+        #[allow(clippy::arc_with_non_send_sync)]
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Returns the bytes of an in-memory body without reading anything.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.source {
+            Source::Memory(bytes) => Some(bytes.as_deref().unwrap_or_default()),
+            _ => None,
+        }
+    }
+
+    pub async fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match &mut self.source {
+            Source::Memory(bytes) => Ok(bytes.take().filter(|b| !b.is_empty())),
+            Source::Http(body) => body.next_chunk().await,
+            Source::Tee(source, state, idx) => {
+                let mut state = state.lock().await;
+
+                if let Some(chunk) = state.pending[*idx].pop_front() {
+                    return Ok(Some(chunk));
+                }
+
+                if state.done {
+                    return Ok(None);
+                }
+
+                let chunk = Box::pin(async { source.lock().await.next_chunk().await }).await?;
+
+                match &chunk {
+                    Some(chunk) => state.pending[1 - *idx].push_back(chunk.clone()),
+                    None => state.done = true,
+                }
+
+                drop(state);
+
+                Ok(chunk)
+            }
+        }
+    }
+
+    pub async fn read_all(&mut self) -> io::Result<Vec<u8>> {
+        if let Source::Memory(bytes) = &mut self.source {
+            return Ok(bytes.take().unwrap_or_default());
+        }
+
+        let mut buf = Vec::new();
+
+        while let Some(chunk) = self.next_chunk().await? {
+            buf.extend_from_slice(&chunk);
+        }
+
+        Ok(buf)
+    }
+
+    /// Splits a reader into two independent readers yielding the same chunks.
+    #[must_use]
+    pub fn tee(reader: SharedReader) -> (Self, Self) {
+        let state = Arc::new(Mutex::new(TeeState::default()));
+
+        (
+            Self {
+                source: Source::Tee(Arc::clone(&reader), Arc::clone(&state), 0),
+            },
+            Self {
+                source: Source::Tee(reader, state, 1),
+            },
+        )
+    }
+}
+
+async fn read_line(conn: &mut BufReader<TcpStream>) -> io::Result<String> {
+    let mut line = Vec::new();
+
+    let read = (&mut *conn)
+        .take(MAX_HEADER_SIZE as u64)
+        .read_until(b'\n', &mut line)
+        .await?;
+
+    if read == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed unexpectedly",
+        ));
+    }
+
+    if line.last() != Some(&b'\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "header line too long",
+        ));
+    }
+
+    let line = String::from_utf8_lossy(&line);
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn serialize_request(request: &FetchRequest) -> Vec<u8> {
+    let mut head = format!("{} {} HTTP/1.1\r\n", request.method, request.url.path());
+
+    let _ = write!(head, "Host: {}\r\n", request.url.host_header());
+    head.push_str("Connection: close\r\n");
+
+    let mut has_accept = false;
+    let mut has_user_agent = false;
+
+    for (key, value) in &request.headers {
+        if key.eq_ignore_ascii_case("host")
+            || key.eq_ignore_ascii_case("connection")
+            || key.eq_ignore_ascii_case("content-length")
+            || key.eq_ignore_ascii_case("transfer-encoding")
+        {
+            continue;
+        }
+
+        has_accept |= key.eq_ignore_ascii_case("accept");
+        has_user_agent |= key.eq_ignore_ascii_case("user-agent");
+
+        let _ = write!(head, "{key}: {value}\r\n");
+    }
+
+    if !has_accept {
+        head.push_str("Accept: */*\r\n");
+    }
+
+    if !has_user_agent {
+        head.push_str("User-Agent: yavashark\r\n");
+    }
+
+    if let Some(body) = &request.body {
+        let _ = write!(head, "Content-Length: {}\r\n", body.len());
+    } else if matches!(request.method.as_str(), "POST" | "PUT" | "PATCH") {
+        head.push_str("Content-Length: 0\r\n");
+    }
+
+    head.push_str("\r\n");
+
+    let mut bytes = head.into_bytes();
+
+    if let Some(body) = &request.body {
+        bytes.extend_from_slice(body);
+    }
+
+    bytes
+}
+
+/// Sends a single request (no redirect handling) and reads the response head.
+pub async fn send(request: &FetchRequest) -> io::Result<(ResponseHead, BodyReader)> {
+    let stream = request.url.connect().await?;
+    let mut conn = BufReader::new(stream);
+
+    conn.get_mut()
+        .write_all(&serialize_request(request))
+        .await?;
+    conn.get_mut().flush().await?;
+
+    let mut head = read_head(&mut conn).await?;
+
+    while (100..200).contains(&head.status) {
+        head = read_head(&mut conn).await?;
+    }
+
+    let framing =
+        if request.method == "HEAD" || matches!(head.status, 204 | 304) {
+            Framing::Length(0)
+        } else if head
+            .header("transfer-encoding")
+            .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"))
+        {
+            Framing::Chunked(ChunkState::Size)
+        } else if let Some(len) = head.header("content-length") {
+            Framing::Length(len.trim().parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid content-length")
+            })?)
+        } else {
+            Framing::Eof
+        };
+
+    let body = BodyReader {
+        source: Source::Http(HttpBody { conn, framing }),
+    };
+
+    Ok((head, body))
+}
+
+async fn read_head(conn: &mut BufReader<TcpStream>) -> io::Result<ResponseHead> {
+    let status_line = read_line(conn).await?;
+
+    let mut parts = status_line.splitn(3, ' ');
+
+    let version = parts.next().unwrap_or_default();
+
+    if !version.starts_with("HTTP/") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid status line: {status_line}"),
+        ));
+    }
+
+    let status = parts
+        .next()
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid status line: {status_line}"),
+            )
+        })?;
+
+    let status_text = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    let mut size = status_line.len();
+
+    loop {
+        let line = read_line(conn).await?;
+
+        if line.is_empty() {
+            break;
+        }
+
+        size += line.len();
+
+        if size > MAX_HEADER_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "response headers too large",
+            ));
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    Ok(ResponseHead {
+        status,
+        status_text,
+        headers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url() {
+        let url = HttpUrl::parse("http://user:pw@Example.com:8080/a/b?c=d#frag").unwrap();
+
+        assert_eq!(url.host_header(), "example.com:8080");
+        assert_eq!(url.port(), 8080);
+        assert_eq!(url.path(), "/a/b?c=d");
+        assert_eq!(url.to_string(), "http://example.com:8080/a/b?c=d");
+
+        let url = HttpUrl::parse("http://localhost?x").unwrap();
+        assert_eq!(url.port(), 80);
+        assert_eq!(url.host_header(), "localhost");
+        assert_eq!(url.path(), "/?x");
+
+        let url = HttpUrl::parse("http://[::1]:81/").unwrap();
+        assert_eq!(url.host_header(), "[::1]:81");
+
+        assert!(HttpUrl::parse("https://example.com").is_err());
+        assert!(HttpUrl::parse("file:///etc/passwd").is_err());
+        assert!(HttpUrl::parse("example.com").is_err());
+        assert!(HttpUrl::parse("http://").is_err());
+    }
+
+    #[test]
+    fn encodes_path() {
+        let url = HttpUrl::parse("http://localhost/a b/ä?q=x y&r=\"").unwrap();
+
+        assert_eq!(url.path(), "/a%20b/%C3%A4?q=x%20y&r=%22");
+    }
+
+    #[test]
+    fn request_line_is_encoded() {
+        let request = FetchRequest {
+            method: "GET".to_string(),
+            url: HttpUrl::parse("http://localhost:1234/a b?c d").unwrap(),
+            headers: Vec::new(),
+            body: None,
+        };
+
+        let bytes = serialize_request(&request);
+        let head = String::from_utf8(bytes).unwrap();
+
+        assert!(head.starts_with("GET /a%20b?c%20d HTTP/1.1\r\nHost: localhost:1234\r\n"));
+    }
+
+    #[test]
+    fn join_location() {
+        let base = HttpUrl::parse("http://localhost:1234/a/b?q").unwrap();
+
+        assert_eq!(base.join("/c").unwrap().path(), "/c");
+        assert_eq!(base.join("c").unwrap().path(), "/a/c");
+        assert_eq!(base.join("?x").unwrap().path(), "/a/b?x");
+        assert_eq!(base.join("../../../c").unwrap().path(), "/c");
+        assert_eq!(base.join("./c d").unwrap().path(), "/a/c%20d");
+        assert_eq!(
+            base.join("//other:81/x").unwrap().to_string(),
+            "http://other:81/x"
+        );
+        assert_eq!(
+            base.join("http://other/").unwrap().to_string(),
+            "http://other/"
+        );
+        assert!(base.join("https://other/").is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use crate::array::Array;
+use crate::utils::ValueIterator;
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value, ValueResult};
+use std::cell::RefCell;
+use yavashark_macro::{object, props};
+
+/// An ordered, case-insensitive header list. Names are stored lowercased,
+/// which is also how they are exposed to JS.
+#[derive(Debug, Default, Clone)]
+pub struct HeaderList(Vec<(String, String)>);
+
+impl HeaderList {
+    #[must_use]
+    pub const fn new(list: Vec<(String, String)>) -> Self {
+        Self(list)
+    }
+
+    pub fn append(&mut self, name: &str, value: &str) -> Res {
+        let (name, value) = Self::normalize(name, value)?;
+
+        self.0.push((name, value));
+
+        Ok(())
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Res {
+        let (name, value) = Self::normalize(name, value)?;
+
+        match self.0.iter().position(|(n, _)| *n == name) {
+            Some(idx) => {
+                self.0[idx].1 = value;
+
+                let mut i = 0;
+                self.0.retain(|(n, _)| {
+                    i += 1;
+                    i <= idx + 1 || *n != name
+                });
+            }
+            None => self.0.push((name, value)),
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, name: &str) {
+        let name = name.to_ascii_lowercase();
+
+        self.0.retain(|(n, _)| *n != name);
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<String> {
+        let name = name.to_ascii_lowercase();
+
+        let values = self
+            .0
+            .iter()
+            .filter(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+            .collect::<Vec<_>>();
+
+        if values.is_empty() {
+            return None;
+        }
+
+        Some(values.join(", "))
+    }
+
+    #[must_use]
+    pub fn has(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+
+        self.0.iter().any(|(n, _)| *n == name)
+    }
+
+    #[must_use]
+    pub fn set_cookies(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|(n, _)| n == "set-cookie")
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    /// The "sort and combine" view the iterators expose: sorted by name,
+    /// duplicate names joined, except for `set-cookie`.
+    #[must_use]
+    pub fn sorted(&self) -> Vec<(String, String)> {
+        let mut names = self.0.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+
+        let mut res = Vec::with_capacity(names.len());
+
+        for name in names {
+            if name == "set-cookie" {
+                for cookie in self.set_cookies() {
+                    res.push((name.to_string(), cookie));
+                }
+            } else if let Some(value) = self.get(name) {
+                res.push((name.to_string(), value));
+            }
+        }
+
+        res
+    }
+
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<(String, String)> {
+        self.0.clone()
+    }
+
+    fn normalize(name: &str, value: &str) -> Res<(String, String)> {
+        if name.is_empty() || !name.bytes().all(is_token_byte) {
+            return Err(Error::ty_error(format!("Invalid header name: '{name}'")));
+        }
+
+        let value = value.trim_matches([' ', '\t', '\r', '\n']);
+
+        if value.contains(['\0', '\r', '\n']) {
+            return Err(Error::ty_error(format!(
+                "Invalid header value for '{name}'"
+            )));
+        }
+
+        Ok((name.to_ascii_lowercase(), value.to_string()))
+    }
+}
+
+const fn is_token_byte(b: u8) -> bool {
+    matches!(b,
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_'
+        | b'`' | b'|' | b'~' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z')
+}
+
+#[object]
+#[derive(Debug)]
+pub struct Headers {
+    #[mutable]
+    pub list: HeaderList,
+}
+
+impl Headers {
+    pub fn new(list: HeaderList, realm: &Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableHeaders {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+                list,
+            }),
+        })
+    }
+
+    /// Builds a header list from a `HeadersInit`: another `Headers`, an
+    /// iterable of name/value pairs or a record.
+    pub fn list_from_value(init: &Value, realm: &mut Realm) -> Res<HeaderList> {
+        let mut list = HeaderList::default();
+
+        let Value::Object(obj) = init else {
+            if init.is_nullish() {
+                return Ok(list);
+            }
+
+            return Err(Error::ty("Headers init must be an object"));
+        };
+
+        if let Some(headers) = obj.downcast::<Self>() {
+            return Ok(headers.inner.try_borrow()?.list.clone());
+        }
+
+        if obj.contains_key(crate::Symbol::ITERATOR.into(), realm)? {
+            let iter = ValueIterator::new(init, realm)?;
+
+            while let Some(pair) = iter.next(realm)? {
+                let mut pair = crate::utils::ArrayLike::new(pair, realm)?;
+                let pair = pair.to_vec(realm)?;
+
+                let [name, value] = pair.as_slice() else {
+                    return Err(Error::ty("Header pairs must contain exactly two items"));
+                };
+
+                list.append(
+                    &name.to_string(realm)?.as_str_lossy(),
+                    &value.to_string(realm)?.as_str_lossy(),
+                )?;
+            }
+
+            return Ok(list);
+        }
+
+        for (key, value) in obj.properties(realm)? {
+            list.append(&key.to_string(), &value.to_string(realm)?.as_str_lossy())?;
+        }
+
+        Ok(list)
+    }
+
+    fn list_iterator(
+        &self,
+        realm: &mut Realm,
+        f: impl Fn(String, String, &mut Realm) -> Res<Value>,
+    ) -> ValueResult {
+        let entries = self.inner.try_borrow()?.list.sorted();
+
+        let values = entries
+            .into_iter()
+            .map(|(name, value)| f(name, value, realm))
+            .collect::<Res<Vec<_>>>()?;
+
+        let array = Array::with_elements(realm, values)?.into_value();
+
+        array.call_method(&"values".into(), realm, Vec::new())
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "Headers")]
+impl Headers {
+    #[constructor]
+    fn construct(init: Option<Value>, realm: &mut Realm) -> Res<ObjectHandle> {
+        let list = match init {
+            Some(init) => Self::list_from_value(&init, realm)?,
+            None => HeaderList::default(),
+        };
+
+        Ok(Self::new(list, realm)?.into_object())
+    }
+
+    fn append(&self, name: &str, value: &str) -> Res {
+        self.inner.try_borrow_mut()?.list.append(name, value)
+    }
+
+    fn delete(&self, name: &str) -> Res {
+        self.inner.try_borrow_mut()?.list.delete(name);
+
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Res<Value> {
+        Ok(self
+            .inner
+            .try_borrow()?
+            .list
+            .get(name)
+            .map_or(Value::Null, Into::into))
+    }
+
+    #[prop("getSetCookie")]
+    fn get_set_cookie(&self, realm: &mut Realm) -> ValueResult {
+        let cookies = self
+            .inner
+            .try_borrow()?
+            .list
+            .set_cookies()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(Array::with_elements(realm, cookies)?.into_value())
+    }
+
+    fn has(&self, name: &str) -> Res<bool> {
+        Ok(self.inner.try_borrow()?.list.has(name))
+    }
+
+    fn set(&self, name: &str, value: &str) -> Res {
+        self.inner.try_borrow_mut()?.list.set(name, value)
+    }
+
+    #[prop("forEach")]
+    fn for_each(
+        &self,
+        callback: &ObjectHandle,
+        this_arg: Option<Value>,
+        #[this] this: &Value,
+        realm: &mut Realm,
+    ) -> Res {
+        let this_arg = this_arg.unwrap_or(Value::Undefined);
+
+        let entries = self.inner.try_borrow()?.list.sorted();
+
+        for (name, value) in entries {
+            callback.call(
+                vec![value.into(), name.into(), this.clone()],
+                this_arg.clone(),
+                realm,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self, realm: &mut Realm) -> ValueResult {
+        self.list_iterator(realm, |name, value, realm| {
+            Ok(Array::with_elements(realm, vec![name.into(), value.into()])?.into_value())
+        })
+    }
+
+    #[prop(crate::Symbol::ITERATOR)]
+    fn iterator(&self, realm: &mut Realm) -> ValueResult {
+        self.entries(realm)
+    }
+
+    fn keys(&self, realm: &mut Realm) -> ValueResult {
+        self.list_iterator(realm, |name, _, _| Ok(name.into()))
+    }
+
+    fn values(&self, realm: &mut Realm) -> ValueResult {
+        self.list_iterator(realm, |_, value, _| Ok(value.into()))
+    }
+}
@@ -0,0 +1,237 @@
+use super::abort::{AbortSignal, AbortState};
+use super::body::{Body, BodyKind};
+use super::client::{HttpUrl, RedirectMode};
+use super::headers::{HeaderList, Headers};
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value, ValueResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+use yavashark_macro::{data_object, object, props};
+
+#[data_object]
+#[derive(Debug, Default)]
+pub struct RequestInit {
+    pub method: Option<String>,
+    pub headers: Option<Value>,
+    pub body: Option<Value>,
+    pub redirect: Option<RedirectMode>,
+    pub signal: Option<Value>,
+}
+
+#[object]
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub url: HttpUrl,
+    pub headers: ObjectHandle,
+    pub signal: ObjectHandle,
+    pub signal_state: Rc<AbortState>,
+    pub redirect: RedirectMode,
+    pub body: Body,
+}
+
+impl Request {
+    /// Creates a request the same way `new Request(input, init)` does.
+    pub fn from_input(input: Value, init: Option<RequestInit>, realm: &mut Realm) -> Res<Self> {
+        let init = init.unwrap_or_default();
+        let input = match &input {
+            Value::Object(obj) => obj.downcast::<Self>().ok_or(input),
+            _ => Err(input),
+        };
+
+        let (url, mut method, list, mut redirect, mut signal_state) = match &input {
+            Ok(req) => (
+                req.url.clone(),
+                req.method.clone(),
+                req.header_list()?,
+                req.redirect,
+                Rc::clone(&req.signal_state),
+            ),
+            Err(url) => {
+                let url = url.to_string(realm)?;
+                let url = HttpUrl::parse(&url.as_str_lossy()).map_err(Error::ty_error)?;
+
+                (
+                    url,
+                    "GET".to_string(),
+                    HeaderList::default(),
+                    RedirectMode::Follow,
+                    Rc::new(AbortState::default()),
+                )
+            }
+        };
+
+        if let Some(m) = init.method {
+            method = normalize_method(&m)?;
+        }
+
+        if let Some(mode) = init.redirect {
+            redirect = mode;
+        }
+
+        if let Some(signal) = init.signal.filter(|s| !s.is_null()) {
+            let signal = signal.downcast::<AbortSignal>()?.ok_or(Error::ty(
+                "Failed to construct 'Request': signal is not an AbortSignal",
+            ))?;
+
+            signal_state = Rc::clone(&signal.state);
+        }
+
+        let mut list = match init.headers {
+            Some(headers) => Headers::list_from_value(&headers, realm)?,
+            None => list,
+        };
+
+        let body = match init.body.filter(|b| !b.is_nullish()) {
+            Some(body) => {
+                if method == "GET" || method == "HEAD" {
+                    return Err(Error::ty("Request with GET/HEAD method cannot have body"));
+                }
+
+                let (body, content_type) = Body::extract(&body, realm)?;
+
+                if let Some(content_type) = content_type
+                    && !list.has("content-type")
+                {
+                    list.append("content-type", content_type)?;
+                }
+
+                body
+            }
+            None => match &input {
+                Ok(req) => req.body.transfer()?,
+                Err(_) => Body::default(),
+            },
+        };
+
+        let signal = AbortSignal::new(Rc::clone(&signal_state), realm)?.into_object();
+        let headers = Headers::new(list, realm)?.into_object();
+
+        Ok(Self {
+            inner: RefCell::new(MutableRequest {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            method,
+            url,
+            headers,
+            signal,
+            signal_state,
+            redirect,
+            body,
+        })
+    }
+
+    pub fn header_list(&self) -> Res<HeaderList> {
+        let headers = self
+            .headers
+            .downcast::<Headers>()
+            .ok_or(Error::new("Request headers are not a Headers object"))?;
+
+        let list = headers.inner.try_borrow()?.list.clone();
+
+        Ok(list)
+    }
+}
+
+fn normalize_method(method: &str) -> Res<String> {
+    if method.is_empty()
+        || !method
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&b))
+    {
+        return Err(Error::ty_error(format!(
+            "'{method}' is not a valid HTTP method"
+        )));
+    }
+
+    let upper = method.to_ascii_uppercase();
+
+    match upper.as_str() {
+        "CONNECT" | "TRACE" | "TRACK" => Err(Error::ty_error(format!(
+            "'{method}' HTTP method is unsupported"
+        ))),
+        "DELETE" | "GET" | "HEAD" | "OPTIONS" | "POST" | "PUT" => Ok(upper),
+        _ => Ok(method.to_string()),
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "Request")]
+impl Request {
+    #[constructor]
+    fn construct(input: Value, init: Option<RequestInit>, realm: &mut Realm) -> Res<ObjectHandle> {
+        Ok(Self::from_input(input, init, realm)?.into_object())
+    }
+
+    #[get("method")]
+    fn method(&self) -> String {
+        self.method.clone()
+    }
+
+    #[get("url")]
+    fn url(&self) -> String {
+        self.url.to_string()
+    }
+
+    #[get("headers")]
+    fn headers(&self) -> ObjectHandle {
+        self.headers.clone()
+    }
+
+    #[get("signal")]
+    fn signal(&self) -> ObjectHandle {
+        self.signal.clone()
+    }
+
+    #[get("redirect")]
+    fn redirect(&self) -> &'static str {
+        self.redirect.as_str()
+    }
+
+    #[get("body")]
+    fn body(&self, realm: &Realm) -> ValueResult {
+        self.body.stream(realm)
+    }
+
+    #[get("bodyUsed")]
+    fn body_used(&self) -> bool {
+        self.body.used()
+    }
+
+    fn text(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::Text, realm)
+    }
+
+    fn json(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::Json, realm)
+    }
+
+    #[prop("arrayBuffer")]
+    fn array_buffer(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::ArrayBuffer, realm)
+    }
+
+    fn bytes(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::Bytes, realm)
+    }
+
+    #[prop("clone")]
+    fn clone_js(&self, realm: &Realm) -> Res<ObjectHandle> {
+        let body = self.body.try_clone()?;
+        let headers = Headers::new(self.header_list()?, realm)?.into_object();
+        let signal = AbortSignal::new(Rc::clone(&self.signal_state), realm)?.into_object();
+
+        Ok(Self {
+            inner: RefCell::new(MutableRequest {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers,
+            signal,
+            signal_state: Rc::clone(&self.signal_state),
+            redirect: self.redirect,
+            body,
+        }
+        .into_object())
+    }
+}
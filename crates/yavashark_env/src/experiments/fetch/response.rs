@@ -0,0 +1,317 @@
+use super::abort::AbortState;
+use super::body::{Body, BodyKind};
+use super::client::{BodyReader, HttpUrl, ResponseHead};
+use super::headers::{HeaderList, Headers};
+use crate::builtins::JSON;
+use crate::value::Obj;
+use crate::{Error, MutObject, ObjectHandle, Realm, Res, Value, ValueResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+use yavashark_macro::{data_object, object, props};
+
+#[data_object]
+#[derive(Debug, Default)]
+pub struct ResponseInit {
+    pub status: Option<f64>,
+    #[prop("statusText")]
+    pub status_text: Option<String>,
+    pub headers: Option<Value>,
+}
+
+#[data_object]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseType {
+    Basic,
+    Default,
+    Error,
+}
+
+#[object]
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: ObjectHandle,
+    pub url: Option<HttpUrl>,
+    pub redirected: bool,
+    pub ty: ResponseType,
+    pub body: Body,
+}
+
+impl Response {
+    fn create(
+        status: u16,
+        status_text: String,
+        list: HeaderList,
+        ty: ResponseType,
+        body: Body,
+        realm: &Realm,
+    ) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableResponse {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            status,
+            status_text,
+            headers: Headers::new(list, realm)?.into_object(),
+            url: None,
+            redirected: false,
+            ty,
+            body,
+        })
+    }
+
+    /// Creates the response `fetch` resolves with.
+    pub fn from_network(
+        head: ResponseHead,
+        body: BodyReader,
+        url: HttpUrl,
+        redirected: bool,
+        signal: Option<Rc<AbortState>>,
+        realm: &Realm,
+    ) -> Res<Self> {
+        let body = if is_null_body_status(head.status) {
+            Body::default()
+        } else {
+            Body::new(body, signal)
+        };
+
+        let mut this = Self::create(
+            head.status,
+            head.status_text,
+            HeaderList::new(head.headers),
+            ResponseType::Basic,
+            body,
+            realm,
+        )?;
+
+        this.url = Some(url);
+        this.redirected = redirected;
+
+        Ok(this)
+    }
+
+    fn from_init(
+        body: Option<(Body, Option<&'static str>)>,
+        init: Option<ResponseInit>,
+        realm: &mut Realm,
+    ) -> Res<Self> {
+        let init = init.unwrap_or_default();
+
+        let status = init.status.unwrap_or(200.0);
+
+        if !(200.0..=599.0).contains(&status) || status.fract() != 0.0 {
+            return Err(Error::range(
+                "Response status must be an integer in the range 200 to 599",
+            ));
+        }
+
+        let status = status as u16;
+
+        let status_text = init.status_text.unwrap_or_default();
+
+        if status_text.contains(['\r', '\n']) {
+            return Err(Error::ty("Invalid statusText"));
+        }
+
+        let mut list = match init.headers {
+            Some(headers) => Headers::list_from_value(&headers, realm)?,
+            None => HeaderList::default(),
+        };
+
+        let body = match body {
+            Some((body, content_type)) => {
+                if is_null_body_status(status) {
+                    return Err(Error::ty_error(format!(
+                        "Response with status {status} cannot have a body"
+                    )));
+                }
+
+                if let Some(content_type) = content_type
+                    && !list.has("content-type")
+                {
+                    list.append("content-type", content_type)?;
+                }
+
+                body
+            }
+            None => Body::default(),
+        };
+
+        Self::create(
+            status,
+            status_text,
+            list,
+            ResponseType::Default,
+            body,
+            realm,
+        )
+    }
+
+    fn header_list(&self) -> Res<HeaderList> {
+        let headers = self
+            .headers
+            .downcast::<Headers>()
+            .ok_or(Error::new("Response headers are not a Headers object"))?;
+
+        let list = headers.inner.try_borrow()?.list.clone();
+
+        Ok(list)
+    }
+}
+
+const fn is_null_body_status(status: u16) -> bool {
+    matches!(status, 101 | 103 | 204 | 205 | 304)
+}
+
+#[props(no_intrinsic, to_string_tag = "Response")]
+impl Response {
+    #[constructor]
+    fn construct(
+        body: Option<Value>,
+        init: Option<ResponseInit>,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        let body = match body.filter(|b| !b.is_nullish()) {
+            Some(body) => Some(Body::extract(&body, realm)?),
+            None => None,
+        };
+
+        Ok(Self::from_init(body, init, realm)?.into_object())
+    }
+
+    fn error(realm: &Realm) -> Res<ObjectHandle> {
+        Ok(Self::create(
+            0,
+            String::new(),
+            HeaderList::default(),
+            ResponseType::Error,
+            Body::default(),
+            realm,
+        )?
+        .into_object())
+    }
+
+    fn redirect(url: &str, status: Option<u16>, realm: &Realm) -> Res<ObjectHandle> {
+        let url = HttpUrl::parse(url).map_err(Error::ty_error)?;
+        let status = status.unwrap_or(302);
+
+        if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+            return Err(Error::range("Invalid redirect status code"));
+        }
+
+        let mut list = HeaderList::default();
+        list.append("location", &url.to_string())?;
+
+        Ok(Self::create(
+            status,
+            String::new(),
+            list,
+            ResponseType::Default,
+            Body::default(),
+            realm,
+        )?
+        .into_object())
+    }
+
+    #[prop("json")]
+    fn json_static(
+        data: Value,
+        init: Option<ResponseInit>,
+        realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        let json = JSON::value_to_serde(data, realm, &mut Vec::new())?
+            .ok_or(Error::ty("Value is not JSON serializable"))?;
+
+        let json = serde_json::to_vec(&json).map_err(|e| Error::ty_error(e.to_string()))?;
+
+        Ok(Self::from_init(
+            Some((Body::from_bytes(json), Some("application/json"))),
+            init,
+            realm,
+        )?
+        .into_object())
+    }
+
+    #[get("type")]
+    fn ty(&self) -> &'static str {
+        self.ty.as_str()
+    }
+
+    #[get("url")]
+    fn url(&self) -> String {
+        self.url
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+
+    #[get("redirected")]
+    const fn redirected(&self) -> bool {
+        self.redirected
+    }
+
+    #[get("status")]
+    const fn status(&self) -> u16 {
+        self.status
+    }
+
+    #[get("ok")]
+    fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    #[get("statusText")]
+    fn status_text(&self) -> String {
+        self.status_text.clone()
+    }
+
+    #[get("headers")]
+    fn headers(&self) -> ObjectHandle {
+        self.headers.clone()
+    }
+
+    #[get("body")]
+    fn body(&self, realm: &Realm) -> ValueResult {
+        self.body.stream(realm)
+    }
+
+    #[get("bodyUsed")]
+    fn body_used(&self) -> bool {
+        self.body.used()
+    }
+
+    fn text(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::Text, realm)
+    }
+
+    fn json(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::Json, realm)
+    }
+
+    #[prop("arrayBuffer")]
+    fn array_buffer(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::ArrayBuffer, realm)
+    }
+
+    fn bytes(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        self.body.consume(BodyKind::Bytes, realm)
+    }
+
+    #[prop("clone")]
+    fn clone_js(&self, realm: &Realm) -> Res<ObjectHandle> {
+        let mut clone = Self::create(
+            self.status,
+            self.status_text.clone(),
+            self.header_list()?,
+            self.ty,
+            self.body.try_clone()?,
+            realm,
+        )?;
+
+        clone.url.clone_from(&self.url);
+        clone.redirected = self.redirected;
+
+        Ok(clone.into_object())
+    }
+}
@@ -107,6 +107,11 @@ impl ObjectImpl for JSCodeEditor {
         self.mut_object.borrow_mut()
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>> {
+        self.mut_object.try_borrow().ok()
+    }
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
         self.mut_object.borrow()
     }
@@ -22,7 +22,7 @@ impl Http {
             }),
         };
 
-        this.initialize(realm.intrinsics.func.clone().into(), realm)?;
+        this.initialize(realm)?;
 
         Ok(this.into_object())
     }
@@ -30,8 +30,8 @@ impl Http {
 
 #[properties_new(raw)]
 impl Http {
-    fn get(_url: String) -> crate::Res<String> {
-        Ok(String::new()) //TODO
+    fn get(url: &str, #[realm] realm: &mut Realm) -> crate::Res<ObjectHandle> {
+        crate::experiments::fetch::get_text(url, realm)
     }
 
     fn server(
@@ -96,7 +96,7 @@ impl HttpResponseWriter {
             }),
         };
 
-        this.initialize(realm.intrinsics.func.clone().into(), realm)?;
+        this.initialize(realm)?;
 
         Ok(this)
     }
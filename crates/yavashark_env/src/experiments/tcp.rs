@@ -1,7 +1,27 @@
-use crate::value::Obj;
-use crate::{MutObject, ObjectHandle, Realm};
-use std::cell::RefCell;
-use yavashark_macro::{object, properties_new};
+use crate::builtins::array_buf::ArrayBuffer;
+use crate::builtins::dataview::DataView;
+use crate::builtins::typed_array::{Type, TypedArray, create_ta};
+use crate::builtins::{IntoPromise, Promise};
+use crate::conversion::TryIntoValue;
+use crate::realm::Intrinsic;
+use crate::task_queue::{AsyncTask, AsyncTaskQueue};
+use crate::value::{IntoValue, Obj};
+use crate::{Error, MutObject, Object, ObjectHandle, Realm, Res, Value, ValueResult};
+use futures::future::{Either, select};
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::{Pin, pin};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, Notify};
+use yavashark_macro::{object, properties_new, props};
+
+const READ_CHUNK_SIZE: usize = 16 * 1024;
 
 #[object]
 #[derive(Debug)]
@@ -10,17 +30,688 @@ pub struct Tcp {}
 impl Tcp {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(realm: &mut Realm) -> crate::Res<ObjectHandle> {
+        let proto = TcpSocket::initialize(realm)?;
+        realm.intrinsics.insert::<TcpSocket>(proto);
+        let proto = TcpServer::initialize(realm)?;
+        realm.intrinsics.insert::<TcpServer>(proto);
+        let proto = TcpIterator::initialize(realm)?;
+        realm.intrinsics.insert::<TcpIterator>(proto);
+
         let mut this = Self {
             inner: RefCell::new(MutableTcp {
                 object: MutObject::new(realm),
             }),
         };
 
-        this.initialize(realm.intrinsics.func.clone().into(), realm)?;
+        this.initialize(realm)?;
 
         Ok(this.into_object())
     }
 }
 
 #[properties_new(raw)]
-impl Tcp {}
+impl Tcp {
+    fn connect(host: String, port: u16, #[realm] realm: &mut Realm) -> Res<ObjectHandle> {
+        async move {
+            TcpStream::connect((host.as_str(), port))
+                .await
+                .map(Accepted)
+                .map_err(io_error)
+        }
+        .into_promise(realm)
+    }
+
+    fn listen(
+        port: u16,
+        handler: Option<ObjectHandle>,
+        #[realm] realm: &mut Realm,
+    ) -> Res<ObjectHandle> {
+        if handler.as_ref().is_some_and(|h| !h.is_callable()) {
+            return Err(Error::ty("tcp.listen handler must be a function"));
+        }
+
+        async move {
+            TcpListener::bind(("0.0.0.0", port))
+                .await
+                .map(|listener| Listening { listener, handler })
+                .map_err(io_error)
+        }
+        .into_promise(realm)
+    }
+}
+
+fn io_error(err: impl std::fmt::Display) -> Error {
+    Error::new_error(format!("tcp: {err}"))
+}
+
+/// The two halves of a connection, shared between a `TcpSocket` and its
+/// pending reads and writes.
+#[derive(Debug)]
+struct SocketState {
+    reader: Mutex<Option<OwnedReadHalf>>,
+    writer: Mutex<Option<OwnedWriteHalf>>,
+    closed: Cell<bool>,
+    close_notify: Notify,
+}
+
+impl SocketState {
+    /// Reads the next chunk, `None` once the peer or we closed the connection.
+    async fn read(self: Rc<Self>) -> io::Result<Option<Vec<u8>>> {
+        if self.closed.get() {
+            return Ok(None);
+        }
+
+        let closed = self.close_notify.notified();
+
+        let read = async {
+            let mut guard = self.reader.lock().await;
+
+            let Some(reader) = guard.as_mut() else {
+                return Ok(None);
+            };
+
+            let mut buf = vec![0; READ_CHUNK_SIZE];
+            let n = reader.read(&mut buf).await?;
+            drop(guard);
+
+            if n == 0 {
+                return Ok(None);
+            }
+
+            buf.truncate(n);
+
+            Ok(Some(buf))
+        };
+
+        match select(pin!(read), pin!(closed)).await {
+            Either::Left((res, _)) => res,
+            Either::Right(_) => Ok(None),
+        }
+    }
+
+    async fn write(self: Rc<Self>, bytes: Vec<u8>) -> io::Result<usize> {
+        let mut guard = self.writer.lock().await;
+
+        let writer = guard
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+
+        writer.write_all(&bytes).await?;
+        drop(guard);
+
+        Ok(bytes.len())
+    }
+
+    fn close(self: &Rc<Self>) -> impl Future<Output = io::Result<()>> + 'static {
+        self.closed.set(true);
+        self.close_notify.notify_waiters();
+
+        let this = Rc::clone(self);
+
+        async move {
+            let writer = this.writer.lock().await.take();
+
+            if let Some(mut writer) = writer {
+                writer.shutdown().await?;
+            }
+
+            this.reader.lock().await.take();
+
+            Ok(())
+        }
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct TcpSocket {
+    state: Rc<SocketState>,
+    local: SocketAddr,
+    remote: SocketAddr,
+}
+
+impl TcpSocket {
+    fn new(stream: TcpStream, realm: &Realm) -> Res<Self> {
+        let local = stream.local_addr().map_err(io_error)?;
+        let remote = stream.peer_addr().map_err(io_error)?;
+
+        let (reader, writer) = stream.into_split();
+
+        Ok(Self {
+            inner: RefCell::new(MutableTcpSocket {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            state: Rc::new(SocketState {
+                reader: Mutex::new(Some(reader)),
+                writer: Mutex::new(Some(writer)),
+                closed: Cell::new(false),
+                close_notify: Notify::new(),
+            }),
+            local,
+            remote,
+        })
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "TcpSocket")]
+impl TcpSocket {
+    #[get("remoteAddress")]
+    fn remote_address(&self) -> String {
+        self.remote.ip().to_string()
+    }
+
+    #[get("remotePort")]
+    const fn remote_port(&self) -> u16 {
+        self.remote.port()
+    }
+
+    #[get("localAddress")]
+    fn local_address(&self) -> String {
+        self.local.ip().to_string()
+    }
+
+    #[get("localPort")]
+    const fn local_port(&self) -> u16 {
+        self.local.port()
+    }
+
+    #[get("closed")]
+    fn closed(&self) -> bool {
+        self.state.closed.get()
+    }
+
+    fn read(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let read = Rc::clone(&self.state).read();
+
+        async move { read.await.map(|chunk| chunk.map(Chunk)).map_err(io_error) }
+            .into_promise(realm)
+    }
+
+    fn write(&self, data: &Value, realm: &mut Realm) -> Res<ObjectHandle> {
+        if self.state.closed.get() {
+            return Promise::from_error(Error::new("tcp: socket is closed"), realm);
+        }
+
+        let bytes = match to_bytes(data, realm) {
+            Ok(bytes) => bytes,
+            Err(err) => return Promise::from_error(err, realm),
+        };
+
+        let write = Rc::clone(&self.state).write(bytes);
+
+        async move { write.await.map(|n| n as f64).map_err(io_error) }.into_promise(realm)
+    }
+
+    fn close(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let close = self.state.close();
+
+        async move { close.await.map_err(io_error) }.into_promise(realm)
+    }
+
+    #[prop(crate::Symbol::ASYNC_ITERATOR)]
+    fn values(&self, realm: &Realm) -> Res<ObjectHandle> {
+        Ok(TcpIterator::new(IterSource::Socket(Rc::clone(&self.state)), realm)?.into_object())
+    }
+}
+
+/// A bound listener, shared between a `TcpServer`, its pending accepts and
+/// the accept loop driving a `listen` handler.
+#[derive(Debug)]
+struct ServerState {
+    listener: RefCell<Option<Rc<TcpListener>>>,
+    close_notify: Notify,
+}
+
+impl ServerState {
+    /// Accepts the next connection, `None` once the server is closed.
+    async fn accept(self: Rc<Self>) -> io::Result<Option<TcpStream>> {
+        let Some(listener) = self.listener.borrow().clone() else {
+            return Ok(None);
+        };
+
+        let closed = self.close_notify.notified();
+
+        match select(pin!(listener.accept()), pin!(closed)).await {
+            Either::Left((res, _)) => res.map(|(stream, _)| Some(stream)),
+            Either::Right(_) => Ok(None),
+        }
+    }
+
+    fn close(&self) {
+        self.listener.borrow_mut().take();
+        self.close_notify.notify_waiters();
+    }
+}
+
+#[object]
+#[derive(Debug)]
+pub struct TcpServer {
+    state: Rc<ServerState>,
+    port: u16,
+}
+
+impl TcpServer {
+    fn new(listener: TcpListener, realm: &Realm) -> Res<Self> {
+        let port = listener.local_addr().map_err(io_error)?.port();
+
+        Ok(Self {
+            inner: RefCell::new(MutableTcpServer {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            state: Rc::new(ServerState {
+                listener: RefCell::new(Some(Rc::new(listener))),
+                close_notify: Notify::new(),
+            }),
+            port,
+        })
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "TcpServer")]
+impl TcpServer {
+    #[get("port")]
+    const fn port(&self) -> u16 {
+        self.port
+    }
+
+    #[get("closed")]
+    fn closed(&self) -> bool {
+        self.state.listener.borrow().is_none()
+    }
+
+    fn accept(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        let accept = Rc::clone(&self.state).accept();
+
+        async move {
+            accept
+                .await
+                .map(|stream| stream.map(Accepted))
+                .map_err(io_error)
+        }
+        .into_promise(realm)
+    }
+
+    fn close(&self) {
+        self.state.close();
+    }
+
+    #[prop(crate::Symbol::ASYNC_ITERATOR)]
+    fn values(&self, realm: &Realm) -> Res<ObjectHandle> {
+        Ok(TcpIterator::new(IterSource::Server(Rc::clone(&self.state)), realm)?.into_object())
+    }
+}
+
+#[derive(Debug)]
+enum IterSource {
+    Socket(Rc<SocketState>),
+    Server(Rc<ServerState>),
+}
+
+/// Async iterator over the chunks of a socket or the connections of a server.
+#[object]
+#[derive(Debug)]
+pub struct TcpIterator {
+    source: IterSource,
+}
+
+impl TcpIterator {
+    fn new(source: IterSource, realm: &Realm) -> Res<Self> {
+        Ok(Self {
+            inner: RefCell::new(MutableTcpIterator {
+                object: MutObject::with_proto(realm.intrinsics.get_of::<Self>()?),
+            }),
+            source,
+        })
+    }
+}
+
+#[props(no_intrinsic, to_string_tag = "Tcp AsyncIterator")]
+impl TcpIterator {
+    fn next(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        match &self.source {
+            IterSource::Socket(state) => {
+                let read = Rc::clone(state).read();
+
+                async move {
+                    read.await
+                        .map(|chunk| IterStep(chunk.map(Chunk)))
+                        .map_err(io_error)
+                }
+                .into_promise(realm)
+            }
+            IterSource::Server(state) => {
+                let accept = Rc::clone(state).accept();
+
+                async move {
+                    accept
+                        .await
+                        .map(|stream| IterStep(stream.map(Accepted)))
+                        .map_err(io_error)
+                }
+                .into_promise(realm)
+            }
+        }
+    }
+
+    #[prop("return")]
+    fn return_(&self, realm: &mut Realm) -> Res<ObjectHandle> {
+        match &self.source {
+            IterSource::Socket(state) => {
+                let close = state.close();
+
+                async move {
+                    close
+                        .await
+                        .map(|()| IterStep::<Chunk>(None))
+                        .map_err(io_error)
+                }
+                .into_promise(realm)
+            }
+            IterSource::Server(state) => {
+                state.close();
+
+                let res = IterStep::<Accepted>(None).try_into_value(realm)?;
+
+                Promise::resolved(&res, realm)
+            }
+        }
+    }
+}
+
+fn to_bytes(value: &Value, realm: &mut Realm) -> Res<Vec<u8>> {
+    if let Value::Object(obj) = value {
+        if let Some(ta) = obj.downcast::<TypedArray>() {
+            return ta.to_bytes();
+        }
+
+        if let Some(buf) = obj.downcast::<ArrayBuffer>() {
+            return Ok(buf.get_slice()?.to_vec());
+        }
+
+        if let Some(view) = obj.downcast::<DataView>() {
+            return view.to_bytes();
+        }
+    }
+
+    Ok(value
+        .to_string(realm)?
+        .as_str_lossy()
+        .into_owned()
+        .into_bytes())
+}
+
+/// A chunk read from a socket, surfaced as a `Uint8Array`.
+struct Chunk(Vec<u8>);
+
+impl TryIntoValue for Chunk {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        Ok(create_ta(realm, Type::U8, self.0)?.into())
+    }
+}
+
+impl TryIntoValue for Option<Chunk> {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        self.map_or(Ok(Value::Null), |chunk| chunk.try_into_value(realm))
+    }
+}
+
+/// A freshly opened connection, surfaced as a `TcpSocket`.
+struct Accepted(TcpStream);
+
+impl TryIntoValue for Accepted {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        Ok(TcpSocket::new(self.0, realm)?.into_value())
+    }
+}
+
+impl TryIntoValue for Option<Accepted> {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        self.map_or(Ok(Value::Null), |stream| stream.try_into_value(realm))
+    }
+}
+
+/// An async iterator result, `done` once there is no value left.
+struct IterStep<T>(Option<T>);
+
+impl<T: TryIntoValue> TryIntoValue for IterStep<T> {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        let res = Object::new(realm);
+
+        let done = self.0.is_none();
+
+        let value = match self.0 {
+            Some(value) => value.try_into_value(realm)?,
+            None => Value::Undefined,
+        };
+
+        res.define_property("value".into(), value, realm)?;
+        res.define_property("done".into(), done.into(), realm)?;
+
+        Ok(res.into())
+    }
+}
+
+/// A bound listener, surfaced as a `TcpServer`. If `listen` got a handler, it
+/// is called for every accepted connection until the server is closed.
+struct Listening {
+    listener: TcpListener,
+    handler: Option<ObjectHandle>,
+}
+
+impl TryIntoValue for Listening {
+    fn try_into_value(self, realm: &mut Realm) -> ValueResult {
+        let server = TcpServer::new(self.listener, realm)?;
+        let state = Rc::clone(&server.state);
+        let server = server.into_object();
+
+        if let Some(handler) = self.handler {
+            let accept = Box::pin(Rc::clone(&state).accept());
+
+            AsyncTaskQueue::queue_task(
+                AcceptLoop {
+                    state,
+                    server: server.clone(),
+                    handler,
+                    accept,
+                },
+                realm,
+            );
+        }
+
+        Ok(server.into())
+    }
+}
+
+type AcceptFuture = Pin<Box<dyn Future<Output = io::Result<Option<TcpStream>>>>>;
+
+/// Calls the `listen` handler with each accepted socket, `this` being the server.
+struct AcceptLoop {
+    state: Rc<ServerState>,
+    server: ObjectHandle,
+    handler: ObjectHandle,
+    accept: AcceptFuture,
+}
+
+impl AsyncTask for AcceptLoop {
+    fn poll(self: Pin<&mut Self>, cx: &mut Context, realm: &mut Realm) -> Poll<Res> {
+        let this = self.get_mut();
+
+        loop {
+            let stream = match this.accept.as_mut().poll(cx) {
+                Poll::Ready(Ok(Some(stream))) => stream,
+                Poll::Ready(Ok(None)) => return Poll::Ready(Ok(())),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(io_error(err))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            this.accept = Box::pin(Rc::clone(&this.state).accept());
+
+            let socket = TcpSocket::new(stream, realm).map(|socket| socket.into_object().into());
+
+            let res = socket.and_then(|socket| {
+                this.handler
+                    .call(vec![socket], this.server.clone().into(), realm)
+            });
+
+            if let Err(err) = res {
+                eprintln!("Error in tcp handler: {err:?}");
+            }
+        }
+    }
+
+    fn run_first_sync(&mut self, _realm: &mut Realm) -> Poll<Res> {
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NativeFunction;
+    use crate::experiments::test_utils::{call, fulfilled, get};
+    use std::io::{Read, Write};
+
+    fn tcp(realm: &mut Realm) -> Value {
+        Tcp::new(realm).unwrap().into()
+    }
+
+    fn bytes(chunk: &Value) -> Vec<u8> {
+        let ta = chunk.as_object().unwrap().downcast::<TypedArray>().unwrap();
+
+        ta.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn connect_writes_and_reads() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4];
+            stream.read_exact(&mut buf).unwrap();
+            stream.write_all(&buf).unwrap();
+        });
+
+        let mut realm = Realm::new().unwrap();
+        let tcp = tcp(&mut realm);
+
+        let promise = call(
+            &tcp,
+            "connect",
+            vec!["127.0.0.1".into(), port.into()],
+            &mut realm,
+        );
+        let socket = fulfilled(&mut realm, promise);
+
+        assert_eq!(
+            get(&socket, "remotePort", &mut realm),
+            Value::Number(f64::from(port))
+        );
+
+        let promise = call(&socket, "write", vec!["ping".into()], &mut realm);
+        assert_eq!(fulfilled(&mut realm, promise), Value::Number(4.0));
+
+        let promise = call(&socket, "read", Vec::new(), &mut realm);
+        assert_eq!(bytes(&fulfilled(&mut realm, promise)), b"ping");
+
+        let promise = call(&socket, "read", Vec::new(), &mut realm);
+        assert_eq!(fulfilled(&mut realm, promise), Value::Null);
+
+        let promise = call(&socket, "close", Vec::new(), &mut realm);
+        fulfilled(&mut realm, promise);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn listen_accepts_connections() {
+        let mut realm = Realm::new().unwrap();
+        let tcp = tcp(&mut realm);
+
+        let promise = call(&tcp, "listen", vec![0.into()], &mut realm);
+        let server = fulfilled(&mut realm, promise);
+
+        let port = get(&server, "port", &mut realm)
+            .to_number(&mut realm)
+            .unwrap() as u16;
+
+        let client = std::thread::spawn(move || {
+            let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+            stream.write_all(b"hello").unwrap();
+        });
+
+        let values = server
+            .as_object()
+            .unwrap()
+            .get(crate::Symbol::ASYNC_ITERATOR, &mut realm)
+            .unwrap();
+        let iter = values.call(&mut realm, Vec::new(), server.clone()).unwrap();
+        let promise = call(&iter, "next", Vec::new(), &mut realm);
+        let step = fulfilled(&mut realm, promise);
+
+        assert_eq!(get(&step, "done", &mut realm), Value::Boolean(false));
+        let socket = get(&step, "value", &mut realm);
+
+        client.join().unwrap();
+
+        let promise = call(&socket, "read", Vec::new(), &mut realm);
+        assert_eq!(bytes(&fulfilled(&mut realm, promise)), b"hello");
+
+        call(&server, "close", Vec::new(), &mut realm);
+
+        let promise = call(&server, "accept", Vec::new(), &mut realm);
+        assert_eq!(fulfilled(&mut realm, promise), Value::Null);
+    }
+
+    #[test]
+    fn listen_calls_handler() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let client = std::thread::spawn(move || {
+            let mut stream = loop {
+                if let Ok(stream) = std::net::TcpStream::connect(("127.0.0.1", port)) {
+                    break stream;
+                }
+
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            };
+
+            let mut reply = String::new();
+            stream.read_to_string(&mut reply).unwrap();
+            reply
+        });
+
+        let mut realm = Realm::new().unwrap();
+        let tcp = tcp(&mut realm);
+
+        let handler = NativeFunction::new(
+            "handler",
+            |args, this, realm| {
+                let socket = args.first().cloned().unwrap_or(Value::Undefined);
+
+                call(&socket, "write", vec!["hi".into()], realm);
+                call(&socket, "close", Vec::new(), realm);
+                call(&this, "close", Vec::new(), realm);
+
+                Ok(Value::Undefined)
+            },
+            &mut realm,
+        );
+
+        let promise = call(
+            &tcp,
+            "listen",
+            vec![port.into(), handler.into()],
+            &mut realm,
+        );
+        let server = fulfilled(&mut realm, promise);
+
+        assert_eq!(get(&server, "closed", &mut realm), Value::Boolean(true));
+        assert_eq!(client.join().unwrap(), "hi");
+    }
+}
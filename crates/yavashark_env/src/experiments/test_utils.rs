@@ -0,0 +1,51 @@
+use crate::builtins::{Promise, PromiseState};
+use crate::conversion::downcast_obj;
+use crate::error_obj::ErrorObj;
+use crate::{Realm, Value};
+use tokio::runtime::Runtime;
+
+thread_local! {
+    /// Sockets and timers are bound to the runtime they were created on, so
+    /// every event loop run of a test has to share one.
+    static RUNTIME: Runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+}
+
+/// Runs the event loop and returns the settled value of `promise`.
+pub fn settle(realm: &mut Realm, promise: Value) -> Result<Value, Value> {
+    RUNTIME.with(|rt| rt.block_on(realm.run_event_loop()));
+
+    let promise = downcast_obj::<Promise>(promise).unwrap();
+    let value = promise.inner.borrow().value.clone();
+
+    match promise.state.get() {
+        PromiseState::Fulfilled => Ok(value.unwrap()),
+        state => {
+            assert_eq!(state, PromiseState::Rejected, "promise is still pending");
+            Err(value.unwrap())
+        }
+    }
+}
+
+pub fn fulfilled(realm: &mut Realm, promise: Value) -> Value {
+    settle(realm, promise)
+        .map_err(|err| {
+            let err = err.downcast::<ErrorObj>().unwrap().unwrap();
+            err.override_to_string_internal().unwrap()
+        })
+        .unwrap()
+}
+
+pub fn get(value: &Value, key: &'static str, realm: &mut Realm) -> Value {
+    value.as_object().unwrap().get(key, realm).unwrap()
+}
+
+pub fn call(value: &Value, method: &'static str, args: Vec<Value>, realm: &mut Realm) -> Value {
+    value
+        .as_object()
+        .unwrap()
+        .call_method(method, realm, args)
+        .unwrap()
+}
@@ -40,7 +40,7 @@ pub struct TimeoutTask {
 impl TimeoutTask {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(timer: SleepDuration, cb: ObjectHandle, realm: &mut Realm) -> Res<ObjectHandle> {
-        let promise_obj = Promise::new(realm).into_object();
+        let promise_obj = Promise::new(realm)?.into_object();
         let promise = downcast_obj::<Promise>(promise_obj.clone().into())?;
 
         let this = Self { timer, promise, cb };
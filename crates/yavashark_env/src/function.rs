@@ -53,6 +53,14 @@ impl ObjectImpl for NativeFunction {
         RefMut::map(self.inner.borrow_mut(), |inner| &mut inner.object)
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>> {
+        self.inner
+            .try_borrow()
+            .ok()
+            .map(|inner| std::cell::Ref::map(inner, |inner| &inner.object))
+    }
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
@@ -10,8 +10,8 @@ pub struct BoundFunction {
     func: Value,
     #[gc]
     bound_this: Value,
-    // #[gc]
-    bound_args: Vec<Value>, //TODO: this is a memleak!
+    #[gc(multi)]
+    bound_args: Vec<Value>,
 }
 
 impl Func for BoundFunction {
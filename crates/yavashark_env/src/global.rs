@@ -21,11 +21,12 @@ use crate::builtins::uint16array::Uint16Array;
 use crate::builtins::uint32array::Uint32Array;
 use crate::builtins::unit8array::Uint8Array;
 use crate::builtins::{
-    AggregateError, AsyncDisposableStack, Atomics, BigIntObj, BooleanObj, Date, DecodeURI,
-    DecodeURIComponent, DisposableStack, EncodeURI, EncodeURIComponent, EvalError, IsFinite, IsNan,
-    JSON, Map, Math, NumberObj, Promise, Proxy, RangeError, ReferenceError, Reflect, RegExp, Set,
-    StringObj, SuppressedError, SymbolObj, SyntaxError, TypeError, URIError, WeakMap, WeakRef,
-    WeakSet,
+    AggregateError, AsyncDisposableStack, Atomics, BigIntObj, BooleanObj, Crypto, CryptoKey, Date,
+    DecodeURI, DecodeURIComponent, DisposableStack, EncodeURI, EncodeURIComponent, EvalError,
+    IsFinite, IsNan, JSON, Map, Math, NumberObj, Promise, Proxy, RangeError, ReferenceError,
+    Reflect, RegExp, Set, ShadowRealm, StringObj, SubtleCrypto, SuppressedError, SymbolObj,
+    SyntaxError, TextDecoder, TextEncoder, TypeError, URIError, URL, URLSearchParams, WeakMap,
+    WeakRef, WeakSet,
 };
 #[cfg(feature = "annex_b")]
 use crate::builtins::{Escape, Unescape};
@@ -213,6 +214,9 @@ pub struct GlobalProperties {
     #[prop("WeakRef")]
     weak_ref: Partial<ObjectHandle, GlobalInitializer<WeakRef>>,
 
+    #[prop("ShadowRealm")]
+    shadow_realm: Partial<ObjectHandle, GlobalInitializer<ShadowRealm>>,
+
     #[prop("Set")]
     set: Partial<ObjectHandle, GlobalInitializer<Set>>,
 
@@ -262,6 +266,29 @@ pub struct GlobalProperties {
 
     #[prop("AsyncDisposableStack")]
     async_disposable_stack: Partial<ObjectHandle, GlobalInitializer<AsyncDisposableStack>>,
+
+    #[prop("TextEncoder")]
+    text_encoder: Partial<ObjectHandle, GlobalInitializer<TextEncoder>>,
+
+    #[prop("TextDecoder")]
+    text_decoder: Partial<ObjectHandle, GlobalInitializer<TextDecoder>>,
+
+    #[prop("URL")]
+    url: Partial<ObjectHandle, GlobalInitializer<URL>>,
+
+    #[prop("URLSearchParams")]
+    url_search_params: Partial<ObjectHandle, GlobalInitializer<URLSearchParams>>,
+
+    crypto: Partial<ObjectHandle, Crypto>,
+
+    #[prop("Crypto")]
+    crypto_constructor: Partial<ObjectHandle, GlobalInitializer<Crypto>>,
+
+    #[prop("SubtleCrypto")]
+    subtle_crypto: Partial<ObjectHandle, GlobalInitializer<SubtleCrypto>>,
+
+    #[prop("CryptoKey")]
+    crypto_key: Partial<ObjectHandle, GlobalInitializer<CryptoKey>>,
 }
 
 pub fn new_global_obj(proto: ObjectHandle) -> Res<ObjectHandle> {
@@ -322,6 +349,7 @@ pub fn new_global_obj(proto: ObjectHandle) -> Res<ObjectHandle> {
         map: Partial::default(),
         weak_map: Partial::default(),
         weak_ref: Partial::default(),
+        shadow_realm: Partial::default(),
         set: Partial::default(),
         weak_set: Partial::default(),
         date: Partial::default(),
@@ -340,6 +368,14 @@ pub fn new_global_obj(proto: ObjectHandle) -> Res<ObjectHandle> {
         intl: Partial::default(),
         disposable_stack: Partial::default(),
         async_disposable_stack: Partial::default(),
+        text_encoder: Partial::default(),
+        text_decoder: Partial::default(),
+        url: Partial::default(),
+        url_search_params: Partial::default(),
+        crypto: Partial::default(),
+        crypto_constructor: Partial::default(),
+        subtle_crypto: Partial::default(),
+        crypto_key: Partial::default(),
 
         __deleted_properties: Cell::default(),
         __written_properties: Cell::default(),
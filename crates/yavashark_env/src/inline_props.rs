@@ -114,6 +114,11 @@ impl<P: PropertiesHook + Debug + 'static> ObjectImpl for InlineObject<P> {
         self.inner.borrow_mut()
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>> {
+        self.inner.try_borrow().ok()
+    }
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
@@ -286,7 +291,10 @@ impl<P: PropertiesHook + Debug + 'static> ObjectImpl for InlineObject<P> {
     }
     #[cfg(feature = "actual_gc")]
     fn gc_refs(&self) -> Vec<GcRef<BoxedObj>> {
-        let mut inner_refs = self.get_inner().gc_refs();
+        let mut inner_refs = self
+            .try_get_wrapped_object()
+            .map(|inner| inner.gc_refs())
+            .unwrap_or_default();
         let props_refs = self.props.gc_refs();
 
         inner_refs.extend(props_refs);
@@ -12,10 +12,13 @@ mod function;
 pub mod object;
 pub mod profiler;
 pub mod scope;
+pub mod snapshot;
 
 pub mod args;
 pub mod builtins;
 pub mod conversion;
+pub mod coverage;
+pub mod debugger;
 pub mod error;
 #[cfg(feature = "out-of-spec-experiments")]
 pub mod experiments;
@@ -40,9 +43,12 @@ pub mod partial_init;
 use crate::error_obj::ErrorObj;
 pub use crate::realm::Realm;
 pub use crate::value::property_key::{InternalPropertyKey, PropertyKey};
-pub use crate::value::{ObjectOrNull, PrimitiveValue};
+pub use crate::value::{BoxedValue, ObjectOrNull, PrimitiveValue};
 use error::Location;
 use value::BoxedObj;
+#[cfg(feature = "heap_stats")]
+pub use yavashark_garbage::heap;
+pub use yavashark_garbage::usage;
 
 pub type Value = value::Value;
 pub type WeakValue = value::WeakValue;
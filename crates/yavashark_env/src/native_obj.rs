@@ -49,6 +49,11 @@ impl<T: ?Sized + Debug + 'static> ObjectImpl for NativeObject<T> {
         self.inner.borrow_mut()
     }
 
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl crate::value::MutObj>> {
+        self.inner.try_borrow().ok()
+    }
+
     fn get_inner(&self) -> impl std::ops::Deref<Target = Self::Inner> {
         self.inner.borrow()
     }
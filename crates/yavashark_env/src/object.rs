@@ -11,9 +11,11 @@ use indexmap::IndexMap;
 use indexmap::map::Entry;
 pub use prototype::Prototype;
 use rustc_hash::FxBuildHasher;
+pub use shape::Shape;
 use std::cell::{Ref, RefCell, RefMut};
 use std::fmt::Debug;
 use std::mem;
+use std::rc::Rc;
 #[cfg(feature = "actual_gc")]
 use yavashark_garbage::GcRef;
 
@@ -42,6 +44,8 @@ pub struct MutObject {
     pub sealed: bool,
     pub frozen: bool,
     pub extensible: bool,
+    pub shape: Rc<Shape>,
+    deletions: u16,
 }
 
 impl Object {
@@ -252,7 +256,18 @@ impl Obj for Object {
 
     #[cfg(feature = "actual_gc")]
     fn gc_refs(&self) -> Vec<GcRef<BoxedObj>> {
-        self.inner.borrow().gc_refs()
+        self.inner
+            .try_borrow()
+            .map(|inner| inner.gc_refs())
+            .unwrap_or_default()
+    }
+
+    fn shaped_object(&self) -> Option<Ref<'_, MutObject>> {
+        self.inner.try_borrow().ok()
+    }
+
+    fn shaped_object_mut(&self) -> Option<RefMut<'_, MutObject>> {
+        self.inner.try_borrow_mut().ok()
     }
 
     fn is_extensible(&self) -> bool {
@@ -333,6 +348,36 @@ impl MutObject {
         .copied()
     }
 
+    /// The position of the named own property `key` in the object's shape.
+    #[must_use]
+    pub fn slot_of(&self, key: &PropertyKey) -> Option<usize> {
+        self.properties.get_index_of(key)
+    }
+
+    /// The named own property at `slot` in the object's shape.
+    #[must_use]
+    pub fn slot(&self, slot: usize) -> Option<&ObjectProperty> {
+        let (_, idx) = self.properties.get_index(slot)?;
+
+        self.values.get(*idx)
+    }
+
+    pub fn slot_mut(&mut self, slot: usize) -> Option<&mut ObjectProperty> {
+        let (_, idx) = self.properties.get_index(slot)?;
+
+        self.values.get_mut(*idx)
+    }
+
+    fn shape_removed(&mut self, slot: usize) {
+        self.deletions = self.deletions.saturating_add(1);
+
+        self.shape = if self.deletions > shape::MAX_SHAPE_DELETIONS && self.shape.is_cacheable() {
+            Shape::dictionary()
+        } else {
+            self.shape.removed(slot, self.properties.keys())
+        };
+    }
+
     #[must_use]
     pub fn new(realm: &Realm) -> Self {
         let prototype = realm.intrinsics.obj.clone().into();
@@ -345,6 +390,8 @@ impl MutObject {
             sealed: false,
             frozen: false,
             extensible: true,
+            shape: Shape::root(),
+            deletions: 0,
         }
     }
 
@@ -358,6 +405,8 @@ impl MutObject {
             sealed: false,
             frozen: false,
             extensible: true,
+            shape: Shape::root(),
+            deletions: 0,
         }
     }
 
@@ -371,6 +420,8 @@ impl MutObject {
             sealed: false,
             frozen: false,
             extensible: true,
+            shape: Shape::root(),
+            deletions: 0,
         }
     }
 
@@ -670,6 +721,7 @@ impl MutObject {
 
                 let idx = self.values.len();
                 self.values.push(ObjectProperty::new(val));
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -707,6 +759,7 @@ impl MutObject {
             Entry::Vacant(entry) => {
                 let idx = self.values.len();
                 self.values.push(ObjectProperty::new(value));
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -781,6 +834,7 @@ impl MutObj for MutObject {
 
                 let idx = self.values.len();
                 self.values.push(ObjectProperty::new(value));
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -838,6 +892,7 @@ impl MutObj for MutObject {
             Entry::Vacant(entry) => {
                 let idx = self.values.len();
                 self.values.push(value.into());
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -935,6 +990,7 @@ impl MutObj for MutObject {
             Entry::Vacant(entry) => {
                 let idx = self.values.len();
                 self.values.push(ObjectProperty::getter(value.into()));
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -978,6 +1034,7 @@ impl MutObj for MutObject {
             Entry::Vacant(entry) => {
                 let idx = self.values.len();
                 self.values.push(ObjectProperty::setter(value.into()));
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -1005,8 +1062,12 @@ impl MutObj for MutObject {
                 .ok_or_else(|| Error::new("Failed to get value for property"))?;
 
             return if prop.attributes.is_configurable() {
+                let prop = mem::replace(prop, Value::Undefined.into()).property();
+                let slot = occ.index();
                 occ.shift_remove();
-                Ok(Some(mem::replace(prop, Value::Undefined.into()).property()))
+                self.shape_removed(slot);
+
+                Ok(Some(prop))
             } else {
                 // Err(Error::ty("Property is not configurable")) // this is only in strict mode
                 Ok(None)
@@ -1161,6 +1222,8 @@ impl MutObj for MutObject {
         self.properties.clear();
         self.array.clear();
         self.values.clear();
+        self.shape = Shape::root();
+        self.deletions = 0;
 
         Ok(())
     }
@@ -1312,6 +1375,7 @@ impl MutObj for MutObject {
                 prop.set = Value::Undefined;
                 prop.attributes = attributes;
                 self.values.push(prop);
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -1378,6 +1442,7 @@ impl MutObj for MutObject {
                     attributes,
                 };
                 self.values.push(prop);
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
@@ -1444,6 +1509,7 @@ impl MutObj for MutObject {
                     attributes,
                 };
                 self.values.push(prop);
+                self.shape = self.shape.transition(entry.key());
                 entry.insert(idx);
             }
         }
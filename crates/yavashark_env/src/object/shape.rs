@@ -1,2 +1,197 @@
-#![allow(dead_code)]
-pub struct Shape {}
+use crate::value::property_key::PropertyKey;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::{Rc, Weak};
+
+/// Objects with more named properties than this don't share shapes.
+const MAX_SHAPE_PROPERTIES: usize = 128;
+
+/// Objects that had more of their named properties deleted than this go into
+/// dictionary mode.
+pub(crate) const MAX_SHAPE_DELETIONS: u16 = 8;
+
+thread_local! {
+    static ROOT: Rc<Shape> = Rc::new(Shape::new(None, None, false));
+}
+
+/// The hidden class of an ordinary object.
+///
+/// A shape describes the named own properties of an object in insertion
+/// order, which is also their order in the object's property map. Objects that
+/// got the same properties added in the same order share a shape, so the
+/// position ("slot") of a property is known from the shape alone. Adding a
+/// property moves the object along a transition to a child shape.
+///
+/// Dictionary shapes are unique to one object and never cached against.
+pub struct Shape {
+    parent: Option<Rc<Shape>>,
+    key: Option<PropertyKey>,
+    len: usize,
+    dictionary: bool,
+    transitions: RefCell<FxHashMap<PropertyKey, Weak<Shape>>>,
+}
+
+impl Shape {
+    fn new(parent: Option<Rc<Self>>, key: Option<PropertyKey>, dictionary: bool) -> Self {
+        let len = parent.as_ref().map_or(0, |p| p.len + 1);
+
+        Self {
+            parent,
+            key,
+            len,
+            dictionary,
+            transitions: RefCell::default(),
+        }
+    }
+
+    /// The shape of objects without named properties.
+    #[must_use]
+    pub fn root() -> Rc<Self> {
+        ROOT.with(Rc::clone)
+    }
+
+    /// A fresh shape for an object in dictionary mode.
+    #[must_use]
+    pub fn dictionary() -> Rc<Self> {
+        Rc::new(Self::new(None, None, true))
+    }
+
+    /// Builds the shape of an object with the named properties `keys`.
+    pub fn from_keys<'a>(keys: impl IntoIterator<Item = &'a PropertyKey>) -> Rc<Self> {
+        keys.into_iter()
+            .fold(Self::root(), |shape, key| shape.transition(key))
+    }
+
+    /// The shape after adding the property `key`.
+    #[must_use]
+    pub fn transition(self: &Rc<Self>, key: &PropertyKey) -> Rc<Self> {
+        if self.dictionary {
+            return Rc::clone(self);
+        }
+
+        if self.len >= MAX_SHAPE_PROPERTIES {
+            return Self::dictionary();
+        }
+
+        let mut transitions = self.transitions.borrow_mut();
+
+        if let Some(shape) = transitions.get(key).and_then(Weak::upgrade) {
+            return shape;
+        }
+
+        let shape = Rc::new(Self::new(Some(Rc::clone(self)), Some(key.clone()), false));
+        transitions.insert(key.clone(), Rc::downgrade(&shape));
+
+        shape
+    }
+
+    /// The shape after removing the property at `slot` from an object with
+    /// the remaining named properties `keys`.
+    pub fn removed<'a>(
+        self: &Rc<Self>,
+        slot: usize,
+        keys: impl IntoIterator<Item = &'a PropertyKey>,
+    ) -> Rc<Self> {
+        if self.dictionary {
+            return Rc::clone(self);
+        }
+
+        if slot + 1 == self.len {
+            if let Some(parent) = &self.parent {
+                return Rc::clone(parent);
+            }
+        }
+
+        Self::from_keys(keys)
+    }
+
+    /// The number of named properties objects with this shape have.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether objects with this shape may be cached against.
+    #[must_use]
+    pub const fn is_cacheable(&self) -> bool {
+        !self.dictionary
+    }
+
+    /// The property that was added by the transition to this shape.
+    #[must_use]
+    pub const fn key(&self) -> Option<&PropertyKey> {
+        self.key.as_ref()
+    }
+}
+
+impl PartialEq for Shape {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for Shape {}
+
+impl Debug for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shape")
+            .field("len", &self.len)
+            .field("dictionary", &self.dictionary)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &'static str) -> PropertyKey {
+        PropertyKey::from_static(name)
+    }
+
+    #[test]
+    fn same_insertion_order_shares_shapes() {
+        let a = Shape::root().transition(&key("x")).transition(&key("y"));
+        let b = Shape::from_keys(&[key("x"), key("y")]);
+        let c = Shape::from_keys(&[key("y"), key("x")]);
+
+        assert!(Rc::ptr_eq(&a, &b));
+        assert!(!Rc::ptr_eq(&a, &c));
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.key(), Some(&key("y")));
+    }
+
+    #[test]
+    fn removing_properties() {
+        let x = Shape::from_keys(&[key("x")]);
+        let xy = x.transition(&key("y"));
+
+        assert!(Rc::ptr_eq(&xy.removed(1, &[key("x")]), &x));
+
+        let y = xy.removed(0, &[key("y")]);
+        assert!(Rc::ptr_eq(&y, &Shape::from_keys(&[key("y")])));
+    }
+
+    #[test]
+    fn dictionary_shapes_are_unique() {
+        let a = Shape::dictionary();
+        let b = Shape::dictionary();
+
+        assert!(!a.is_cacheable());
+        assert_ne!(a, b);
+        assert!(Rc::ptr_eq(&a.transition(&key("x")), &a));
+
+        let mut shape = Shape::root();
+        for i in 0..=MAX_SHAPE_PROPERTIES {
+            shape = shape.transition(&PropertyKey::String(i.to_string().into()));
+        }
+
+        assert!(!shape.is_cacheable());
+    }
+}
@@ -21,7 +21,7 @@ use yavashark_macro::object;
 #[object(function, constructor, direct(prototype), name)]
 #[derive(Debug)]
 pub struct OptimFunction {
-    // #[gc(untyped)] //TODO: this is a memleak!
+    #[gc(untyped)]
     pub raw: RawOptimFunction,
 }
 
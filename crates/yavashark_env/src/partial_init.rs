@@ -139,6 +139,10 @@ impl<T> DynamicPartial<T> {
         self.func.set(init_func);
     }
 
+    pub fn initializer(&self) -> InitializeFn<T> {
+        self.func.get()
+    }
+
     pub fn set_from_initializer<I: Initializer<T>>(&self) {
         self.func.set(I::initialize);
     }
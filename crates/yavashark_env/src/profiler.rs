@@ -1,16 +1,72 @@
-use crate::Realm;
+use crate::{Realm, Res};
 #[cfg(feature = "profiler")]
 use std::time::Instant;
 
 #[cfg(feature = "profiler")]
-pub use yavashark_profiler::{FileProfileWriter, FrameId, Profile};
+pub use yavashark_profiler::{FileProfileWriter, FrameId, Profile, SuspendedFrame};
 
+/// Placeholder for [`yavashark_profiler::SuspendedFrame`] without the profiler.
+#[cfg(not(feature = "profiler"))]
+#[derive(Debug, Clone, Copy)]
+pub struct SuspendedFrame;
+
+/// The function that is running right now, to attribute the work after it
+/// resumes from an `await` or `yield` to it with [`profile_resume`].
 #[cfg(feature = "profiler")]
-pub fn profile_call<T>(
+#[must_use]
+pub fn suspended_frame(realm: &Realm) -> Option<SuspendedFrame> {
+    realm.profile.top_frame()
+}
+
+/// The function that is running right now, to attribute the work after it
+/// resumes from an `await` or `yield` to it with [`profile_resume`].
+#[cfg(not(feature = "profiler"))]
+#[must_use]
+pub const fn suspended_frame(_realm: &Realm) -> Option<SuspendedFrame> {
+    None
+}
+
+/// Resumes a suspended function, it doesn't count against the call depth
+/// limit since it's run from the event loop or the caller of `next`.
+#[cfg(feature = "profiler")]
+pub fn profile_resume<T>(
     realm: &mut Realm,
-    fn_name: impl FnOnce() -> String,
+    frame: Option<&SuspendedFrame>,
     f: impl FnOnce(&mut Realm) -> T,
 ) -> T {
+    let Some(frame) = frame else {
+        return f(realm);
+    };
+
+    let frame_id = realm.profile.resume_frame(frame, Instant::now());
+
+    let result = f(realm);
+
+    realm.profile_end_frame(frame_id, Instant::now());
+
+    result
+}
+
+/// Resumes a suspended function, it doesn't count against the call depth
+/// limit since it's run from the event loop or the caller of `next`.
+#[cfg(not(feature = "profiler"))]
+pub fn profile_resume<T>(
+    realm: &mut Realm,
+    _frame: Option<&SuspendedFrame>,
+    f: impl FnOnce(&mut Realm) -> T,
+) -> T {
+    f(realm)
+}
+
+/// Runs a call, it counts against the call depth limit of the realm.
+#[cfg(feature = "profiler")]
+pub fn profile_call<T>(
+    realm: &mut Realm,
+    fn_name: impl FnOnce() -> String,
+    f: impl FnOnce(&mut Realm) -> Res<T>,
+) -> Res<T> {
+    realm.enter_call()?;
+
     let fn_name = fn_name();
 
     let start = Instant::now();
@@ -19,15 +75,23 @@ pub fn profile_call<T>(
     let result = f(realm);
 
     realm.profile_end_frame(frame_id, Instant::now());
+    realm.leave_call();
 
     result
 }
 
+/// Runs a call, it counts against the call depth limit of the realm.
 #[cfg(not(feature = "profiler"))]
 pub fn profile_call<T>(
     realm: &mut Realm,
     _fn_name: impl FnOnce() -> String,
-    f: impl FnOnce(&mut Realm) -> T,
-) -> T {
-    f(realm)
+    f: impl FnOnce(&mut Realm) -> Res<T>,
+) -> Res<T> {
+    realm.enter_call()?;
+
+    let result = f(realm);
+
+    realm.leave_call();
+
+    result
 }
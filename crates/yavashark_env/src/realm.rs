@@ -2,21 +2,30 @@ mod env;
 mod initialize;
 mod intrinsics;
 
+pub mod limits;
 pub mod resolve;
 
+use crate::coverage::{Coverage, CoverageReport, FileItems};
+use crate::debugger::{DebugHook, SourceSpan};
 use crate::global::{init_global_obj, new_global_obj};
 use crate::realm::env::Environment;
 use crate::realm::intrinsics::Intrinsics;
-use crate::scope::Scope;
+use crate::realm::limits::LimitState;
+use crate::scope::{Module, Scope};
+use crate::snapshot::SnapshotFunction;
 use crate::task_queue::AsyncTaskQueue;
-use crate::{NativeFunction, Object, ObjectHandle, Res, Value, ValueResult, Variable};
+use crate::{Error, NativeFunction, Object, ObjectHandle, Res, Value, ValueResult, Variable};
 pub use initialize::*;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 #[cfg(feature = "profiler")]
 use std::time::Instant;
 
+#[cfg(feature = "out-of-spec-experiments")]
+use crate::experiments::fetch::FetchHook;
 use crate::utils::private_rc::PrivateRc;
 #[cfg(feature = "profiler")]
 use yavashark_profiler::{FileProfileWriter, FrameId, Profile};
@@ -30,6 +39,12 @@ pub struct Realm {
     pub profile: Profile,
     #[cfg(feature = "profiler")]
     profile_writer: Option<Box<FileProfileWriter>>,
+    #[cfg(feature = "out-of-spec-experiments")]
+    pub fetch_hook: Option<Rc<dyn FetchHook>>,
+    eval: Option<(Rc<dyn Eval>, bool)>,
+    limits: LimitState,
+    debugger: Option<Rc<dyn DebugHook>>,
+    coverage: Option<Rc<RefCell<Coverage>>>,
 }
 
 impl Debug for Realm {
@@ -54,6 +69,12 @@ impl Realm {
             profile: Profile::new(),
             #[cfg(feature = "profiler")]
             profile_writer: None,
+            #[cfg(feature = "out-of-spec-experiments")]
+            fetch_hook: None,
+            eval: None,
+            limits: LimitState::default(),
+            debugger: None,
+            coverage: None,
         };
 
         init_global_obj(&mut realm)?;
@@ -61,7 +82,53 @@ impl Realm {
         Ok(realm)
     }
 
+    /// Creates a new realm with its own intrinsics and global object that uses
+    /// the same host hooks (`eval`, module loading, engine specific intrinsics)
+    /// as this one.
+    pub fn new_child(&self) -> Res<Self> {
+        let mut realm = Self::new()?;
+
+        for (parent, child) in [
+            (
+                &self.intrinsics.generator_function,
+                &realm.intrinsics.generator_function,
+            ),
+            (&self.intrinsics.generator, &realm.intrinsics.generator),
+            (
+                &self.intrinsics.async_generator_function,
+                &realm.intrinsics.async_generator_function,
+            ),
+            (
+                &self.intrinsics.async_generator,
+                &realm.intrinsics.async_generator,
+            ),
+        ] {
+            child.set_initializer(parent.initializer());
+        }
+
+        #[cfg(feature = "out-of-spec-experiments")]
+        {
+            realm.fetch_hook.clone_from(&self.fetch_hook);
+        }
+
+        realm.limits = self.limits.child();
+        realm.debugger.clone_from(&self.debugger);
+        realm.coverage.clone_from(&self.coverage);
+
+        if let Some((eval, strict)) = &self.eval {
+            realm.set_eval_rc(Rc::clone(eval), *strict)?;
+        }
+
+        Ok(realm)
+    }
+
     pub fn set_eval(&mut self, eval: impl Eval + 'static, strict: bool) -> Res {
+        self.set_eval_rc(Rc::new(eval), strict)
+    }
+
+    fn set_eval_rc(&mut self, eval: Rc<dyn Eval>, strict: bool) -> Res {
+        self.eval = Some((Rc::clone(&eval), strict));
+
         let eval_func = NativeFunction::with_len(
             "eval",
             move |args, _, realm| {
@@ -99,6 +166,31 @@ impl Realm {
         Ok(())
     }
 
+    /// Evaluates `code` as a script in a fresh global scope of this realm.
+    pub fn eval_script(&mut self, code: &str, path: PathBuf) -> ValueResult {
+        let Some((eval, _)) = self.eval.clone() else {
+            return Err(Error::new("eval is not available in this realm"));
+        };
+
+        let mut scope = Scope::global(self, path);
+
+        eval.eval(code, self, &mut scope)
+    }
+
+    /// The host hooks set with [`Realm::set_eval`].
+    pub(crate) fn eval_hook(&self) -> Option<Rc<dyn Eval>> {
+        self.eval.as_ref().map(|(eval, _)| Rc::clone(eval))
+    }
+
+    /// Loads the module `spec`, resolved relative to `cur_path`, into this realm.
+    pub fn import_module(&mut self, spec: &str, cur_path: &Path) -> Res<Module> {
+        let Some((eval, _)) = self.eval.clone() else {
+            return Err(Error::new("module loading is not available in this realm"));
+        };
+
+        eval.import(spec, cur_path, self)
+    }
+
     pub async fn run_event_loop(&mut self) {
         self.queue.runner().run(self).await;
     }
@@ -108,6 +200,70 @@ impl Realm {
         !self.queue.is_empty()
     }
 
+    /// Lets the embedder inspect, answer or deny every request made by `fetch`.
+    #[cfg(feature = "out-of-spec-experiments")]
+    pub fn set_fetch_hook(&mut self, hook: impl FetchHook + 'static) {
+        self.fetch_hook = Some(Rc::new(hook));
+    }
+
+    /// Lets a debugger follow and pause the code running in this realm.
+    pub fn set_debugger(&mut self, hook: Rc<dyn DebugHook>) {
+        self.debugger = Some(hook);
+    }
+
+    #[must_use]
+    pub fn debugger(&self) -> Option<Rc<dyn DebugHook>> {
+        self.debugger.clone()
+    }
+
+    /// Starts counting hits for the files registered with
+    /// [`Realm::register_coverage`].
+    pub fn start_coverage(&mut self) {
+        if self.coverage.is_none() {
+            self.coverage = Some(Rc::new(RefCell::new(Coverage::default())));
+        }
+    }
+
+    /// Stops collecting and returns what was collected.
+    pub fn stop_coverage(&mut self) -> Option<CoverageReport> {
+        self.coverage
+            .take()
+            .map(|coverage| coverage.borrow().report())
+    }
+
+    /// Returns what was collected so far and starts counting from zero again.
+    #[must_use]
+    pub fn take_coverage(&self) -> Option<CoverageReport> {
+        let mut coverage = self.coverage.as_ref()?.borrow_mut();
+        let report = coverage.report();
+        coverage.reset();
+
+        Some(report)
+    }
+
+    #[must_use]
+    pub const fn is_collecting_coverage(&self) -> bool {
+        self.coverage.is_some()
+    }
+
+    /// Adds a source file to the coverage report, only files registered
+    /// before they run are counted.
+    pub fn register_coverage(&self, path: PathBuf, source: &str, items: FileItems) {
+        if let Some(coverage) = &self.coverage {
+            coverage.borrow_mut().register(path, source, items);
+        }
+    }
+
+    /// Counts the statement, function body or branch arm at `span` of the
+    /// file `scope` runs in.
+    pub fn cover(&self, scope: &Scope, span: SourceSpan) {
+        if let Some(coverage) = &self.coverage
+            && let Ok(path) = scope.get_current_path()
+        {
+            coverage.borrow_mut().hit(&path, span);
+        }
+    }
+
     #[cfg(feature = "profiler")]
     pub fn set_profile_writer(&mut self, writer: FileProfileWriter) {
         self.profile_writer = Some(Box::new(writer));
@@ -151,12 +307,39 @@ impl Default for Realm {
             profile: Profile::new(),
             #[cfg(feature = "profiler")]
             profile_writer: None,
+            #[cfg(feature = "out-of-spec-experiments")]
+            fetch_hook: None,
+            eval: None,
+            limits: LimitState::default(),
+            debugger: None,
+            coverage: None,
         }
     }
 }
 
 pub trait Eval {
     fn eval(&self, code: &str, realm: &mut Realm, scope: &mut Scope) -> ValueResult;
+
+    fn import(&self, spec: &str, cur_path: &Path, realm: &mut Realm) -> Res<Module> {
+        let _ = (spec, cur_path, realm);
+
+        Err(Error::new("module loading is not supported"))
+    }
+
+    /// What a [`Snapshot`](crate::snapshot::Snapshot) needs to recreate `func`, `None` if it isn't a
+    /// function of this host or can't be snapshotted.
+    fn snapshot_function(&self, func: &ObjectHandle) -> Option<SnapshotFunction> {
+        let _ = func;
+
+        None
+    }
+
+    /// Recreates a function from what [`Eval::snapshot_function`] returned.
+    fn restore_function(&self, func: SnapshotFunction, realm: &mut Realm) -> Res<ObjectHandle> {
+        let _ = (func, realm);
+
+        Err(Error::new("functions can't be restored in this realm"))
+    }
 }
 
 // impl Eq for Realm {}
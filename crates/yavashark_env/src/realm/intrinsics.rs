@@ -20,10 +20,11 @@ use crate::builtins::uint16array::Uint16Array;
 use crate::builtins::uint32array::Uint32Array;
 use crate::builtins::unit8array::Uint8Array;
 use crate::builtins::{
-    AggregateError, Arguments, AsyncDisposableStack, Atomics, BigIntObj, BooleanObj, Date,
-    DisposableStack, EvalError, Map, NumberObj, Promise, Proxy, RangeError, ReferenceError, RegExp,
-    Set, StringObj, SuppressedError, SymbolObj, SyntaxError, ThrowTypeError, TypeError, URIError,
-    WeakMap, WeakRef, WeakSet, iterator, signal,
+    AggregateError, Arguments, AsyncDisposableStack, Atomics, BigIntObj, BooleanObj, Crypto,
+    CryptoKey, Date, DisposableStack, EvalError, Map, NumberObj, Promise, Proxy, RangeError,
+    ReferenceError, RegExp, Set, ShadowRealm, StringObj, SubtleCrypto, SuppressedError, SymbolObj,
+    SyntaxError, TextDecoder, TextEncoder, ThrowTypeError, TypeError, URIError, URL,
+    URLSearchParams, WeakMap, WeakRef, WeakSet, iterator, signal,
 };
 use crate::error_obj::ErrorObj;
 use crate::partial_init::{DynamicPartial, Partial};
@@ -80,6 +81,7 @@ pub struct Intrinsics {
     pub set: PartialIntrinsic<Set>,
     pub weak_set: PartialIntrinsic<WeakSet>,
     pub weak_ref: PartialIntrinsic<WeakRef>,
+    pub shadow_realm: PartialIntrinsic<ShadowRealm>,
     pub date: PartialIntrinsic<Date>,
     #[cfg(feature = "temporal")]
     pub temporal_duration: PartialIntrinsic<temporal::Duration>,
@@ -106,6 +108,7 @@ pub struct Intrinsics {
     pub async_generator_function: DynamicPartial<ObjectHandle>,
     pub signal_state: PartialIntrinsic<signal::State>,
     pub signal_computed: PartialIntrinsic<signal::Computed>,
+    pub signal_watcher: PartialIntrinsic<signal::Watcher>,
     pub arguments: PartialIntrinsic<Arguments>,
     pub proxy: PartialIntrinsic<Proxy>,
     #[cfg(feature = "icu")]
@@ -128,6 +131,10 @@ pub struct Intrinsics {
     pub intl_relative_time_format: PartialIntrinsic<intl::RelativeTimeFormat>,
     #[cfg(feature = "icu")]
     pub intl_segmenter: PartialIntrinsic<intl::Segmenter>,
+    #[cfg(feature = "icu")]
+    pub intl_segments: PartialIntrinsic<intl::Segments>,
+    #[cfg(feature = "icu")]
+    pub intl_segment_iterator: PartialIntrinsic<intl::SegmentIterator>,
     pub throw_type_error: Partial<ObjectHandle, ThrowTypeError>,
 
     pub iterator: PartialIntrinsic<iterator::Iterator>,
@@ -139,6 +146,13 @@ pub struct Intrinsics {
 
     pub disposable_stack: PartialIntrinsic<DisposableStack>,
     pub async_disposable_stack: PartialIntrinsic<AsyncDisposableStack>,
+    pub text_encoder: PartialIntrinsic<TextEncoder>,
+    pub text_decoder: PartialIntrinsic<TextDecoder>,
+    pub url: PartialIntrinsic<URL>,
+    pub url_search_params: PartialIntrinsic<URLSearchParams>,
+    pub crypto: PartialIntrinsic<Crypto>,
+    pub subtle_crypto: PartialIntrinsic<SubtleCrypto>,
+    pub crypto_key: PartialIntrinsic<CryptoKey>,
 
     pub other: FxHashMap<TypeId, ObjectHandle>,
 }
@@ -194,6 +208,7 @@ impl Intrinsics {
             set: Partial::default(),
             weak_set: Partial::default(),
             weak_ref: Partial::default(),
+            shadow_realm: Partial::default(),
             date: Partial::default(),
             #[cfg(feature = "temporal")]
             temporal_duration: Partial::default(),
@@ -220,6 +235,7 @@ impl Intrinsics {
             async_generator: DynamicPartial::from_initializer::<NullObjInitializer>(),
             signal_state: Partial::default(),
             signal_computed: Partial::default(),
+            signal_watcher: Partial::default(),
             arguments: Partial::default(),
             proxy: Partial::default(),
             #[cfg(feature = "icu")]
@@ -242,6 +258,10 @@ impl Intrinsics {
             intl_relative_time_format: Partial::default(),
             #[cfg(feature = "icu")]
             intl_segmenter: Partial::default(),
+            #[cfg(feature = "icu")]
+            intl_segments: Partial::default(),
+            #[cfg(feature = "icu")]
+            intl_segment_iterator: Partial::default(),
             throw_type_error: Partial::default(),
             iterator: Partial::default(),
             iterator_prototype: Partial::default(),
@@ -250,6 +270,13 @@ impl Intrinsics {
             parse_float: Partial::default(),
             disposable_stack: Partial::default(),
             async_disposable_stack: Partial::default(),
+            text_encoder: Partial::default(),
+            text_decoder: Partial::default(),
+            url: Partial::default(),
+            url_search_params: Partial::default(),
+            crypto: Partial::default(),
+            subtle_crypto: Partial::default(),
+            crypto_key: Partial::default(),
             other: FxHashMap::default(),
         }
     }
@@ -313,6 +340,7 @@ impl Default for Intrinsics {
             set: Default::default(),
             weak_set: Default::default(),
             weak_ref: Default::default(),
+            shadow_realm: Default::default(),
             date: Default::default(),
             #[cfg(feature = "temporal")]
             temporal_duration: Default::default(),
@@ -339,6 +367,7 @@ impl Default for Intrinsics {
             async_generator: DynamicPartial::from_initializer::<NullObjInitializer>(),
             signal_state: Default::default(),
             signal_computed: Default::default(),
+            signal_watcher: Default::default(),
             arguments: Default::default(),
             proxy: Default::default(),
             #[cfg(feature = "icu")]
@@ -361,6 +390,10 @@ impl Default for Intrinsics {
             intl_relative_time_format: Default::default(),
             #[cfg(feature = "icu")]
             intl_segmenter: Default::default(),
+            #[cfg(feature = "icu")]
+            intl_segments: Default::default(),
+            #[cfg(feature = "icu")]
+            intl_segment_iterator: Default::default(),
             throw_type_error: Default::default(),
             iterator: Default::default(),
             iterator_prototype: Default::default(),
@@ -369,6 +402,13 @@ impl Default for Intrinsics {
             parse_float: Default::default(),
             disposable_stack: Default::default(),
             async_disposable_stack: Default::default(),
+            text_encoder: Default::default(),
+            text_decoder: Default::default(),
+            url: Default::default(),
+            url_search_params: Default::default(),
+            crypto: Default::default(),
+            subtle_crypto: Default::default(),
+            crypto_key: Default::default(),
             other: FxHashMap::default(),
         }
     }
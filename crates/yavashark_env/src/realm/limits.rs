@@ -0,0 +1,162 @@
+//! Limits for running untrusted code.
+//!
+//! Every limit is off by default. The interpreter charges a step for every
+//! statement and the VM for every instruction, every function call counts
+//! against the call depth. Running out of steps or heap and an interrupt abort
+//! the script with an [`ErrorKind::Terminated`](crate::error::ErrorKind)
+//! error, which isn't seen by `catch` or `finally`. The realm stays terminated
+//! until [`Realm::reset_limits`] is called, so queued jobs can't pick up where
+//! the script left off.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{Error, Realm, Res};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Bytes the heap of this thread may take, see
+    /// [`yavashark_garbage::usage::heap_bytes`]. Without a
+    /// [`CountingAllocator`](yavashark_garbage::usage::CountingAllocator) only
+    /// the boxes of garbage collected values are counted, not their buffers.
+    pub max_heap_bytes: Option<usize>,
+    /// Statements and instructions the realm may execute.
+    pub max_steps: Option<u64>,
+    /// Nested calls before a `RangeError` is thrown.
+    pub max_call_depth: Option<usize>,
+}
+
+/// Aborts the execution of a realm, can be sent to other threads.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// Stops the realm at its next step.
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn clear(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct LimitState {
+    limits: Limits,
+    steps: u64,
+    depth: usize,
+    interrupt: InterruptHandle,
+}
+
+impl LimitState {
+    /// A child realm has the same limits and can be interrupted with the
+    /// same handle.
+    pub(crate) fn child(&self) -> Self {
+        Self {
+            limits: self.limits,
+            steps: 0,
+            depth: 0,
+            interrupt: self.interrupt.clone(),
+        }
+    }
+}
+
+impl Realm {
+    pub const fn set_limits(&mut self, limits: Limits) {
+        self.limits.limits = limits;
+    }
+
+    #[must_use]
+    pub const fn limits(&self) -> Limits {
+        self.limits.limits
+    }
+
+    #[must_use]
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.limits.interrupt.clone()
+    }
+
+    /// Steps charged since the realm was created or the limits were reset.
+    #[must_use]
+    pub const fn steps(&self) -> u64 {
+        self.limits.steps
+    }
+
+    /// Starts a new step budget and clears a pending interrupt.
+    pub fn reset_limits(&mut self) {
+        self.limits.steps = 0;
+        self.limits.interrupt.clear();
+    }
+
+    /// Charges a step and checks the interrupt, the step budget and the heap.
+    /// This is also where the sampling profiler takes its samples.
+    pub fn step(&mut self) -> Res {
+        #[cfg(feature = "profiler")]
+        self.profile.sample_if_due();
+
+        let state = &mut self.limits;
+        state.steps += 1;
+
+        if state.interrupt.is_interrupted() {
+            return Err(Error::terminated("Execution interrupted"));
+        }
+
+        if state.limits.max_steps.is_some_and(|max| state.steps > max) {
+            return Err(Error::terminated("Step limit exceeded"));
+        }
+
+        if let Some(max) = state.limits.max_heap_bytes {
+            check_heap(max)?;
+        }
+
+        Ok(())
+    }
+
+    /// Called before a function is entered, every successful call has to be
+    /// followed by [`Realm::leave_call`].
+    pub fn enter_call(&mut self) -> Res {
+        let state = &mut self.limits;
+
+        if state
+            .limits
+            .max_call_depth
+            .is_some_and(|max| state.depth >= max)
+        {
+            return Err(Error::range("Maximum call stack size exceeded"));
+        }
+
+        state.depth += 1;
+
+        Ok(())
+    }
+
+    pub const fn leave_call(&mut self) {
+        self.limits.depth = self.limits.depth.saturating_sub(1);
+    }
+}
+
+fn check_heap(max: usize) -> Res {
+    use yavashark_garbage::usage::heap_bytes;
+
+    if heap_bytes() <= max {
+        return Ok(());
+    }
+
+    // unreachable cycles might still count against the limit
+    #[cfg(feature = "actual_gc")]
+    {
+        yavashark_garbage::collector::collect();
+
+        if heap_bytes() <= max {
+            return Ok(());
+        }
+    }
+
+    Err(Error::terminated("Heap limit exceeded"))
+}
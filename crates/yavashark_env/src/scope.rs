@@ -1,12 +1,14 @@
 use crate::value::{CustomGcRefUntyped, DefinePropertyResult};
-use rustc_hash::{FxHashMap, FxHashSet};
+use indexmap::IndexMap;
+use indexmap::map::Entry;
+use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet};
 use std::cell::RefCell;
 use std::collections::HashSet;
-use std::collections::hash_map::Entry;
 #[cfg(feature = "actual_gc")]
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::rc::Rc;
+use swc_ecma_ast::Ident;
 use yavashark_garbage::Gc;
 use yavashark_garbage::collectable::CellCollectable;
 #[cfg(feature = "actual_gc")]
@@ -17,6 +19,11 @@ use crate::realm::Realm;
 use crate::value::property_key::IntoPropertyKey;
 use crate::{Error, InternalPropertyKey, Object, ObjectHandle, PropertyKey, Res, Value, Variable};
 
+use resolver::Location;
+pub use resolver::{Binding, Resolution};
+
+mod resolver;
+
 pub struct MutValue {
     pub name: String,
     pub scope: Rc<RefCell<ScopeInternal>>,
@@ -147,11 +154,20 @@ impl ScopeState {
     pub const fn is_strict_mode(&self) -> bool {
         self.state & Self::STRICT_MODE != 0
     }
+
+    pub(crate) const fn bits(self) -> u8 {
+        self.state
+    }
+
+    pub(crate) const fn from_bits(state: u8) -> Self {
+        Self { state }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Scope {
     scope: Gc<RefCell<ScopeInternal>>,
+    resolution: Option<Rc<Resolution>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -179,8 +195,8 @@ pub struct ModuleScope {
 
 #[derive(Debug)]
 pub struct VariableReference {
-    name: YSString,
-    object: ObjectHandle,
+    pub(crate) name: YSString,
+    pub(crate) object: ObjectHandle,
 }
 
 #[derive(Debug)]
@@ -246,10 +262,12 @@ impl VariableOrRef {
     }
 }
 
+/// Variables are kept in declaration order, so a variable keeps its slot for
+/// the lifetime of the scope.
 #[derive(Debug)]
 pub enum ObjectOrVariables {
     Object(ObjectHandle),
-    Variables(FxHashMap<String, VariableOrRef>),
+    Variables(IndexMap<String, VariableOrRef, FxBuildHasher>),
 }
 
 impl From<Variable> for VariableOrRef {
@@ -350,9 +368,9 @@ impl ObjectOrVariables {
 
 #[derive(Debug)]
 pub struct ScopeInternal {
-    parent: Option<Gc<RefCell<ScopeInternal>>>,
-    variables: ObjectOrVariables,
-    hoisted: FxHashSet<String>,
+    pub(crate) parent: Option<Gc<RefCell<ScopeInternal>>>,
+    pub(crate) variables: ObjectOrVariables,
+    pub(crate) hoisted: FxHashSet<String>,
     pub available_labels: Vec<String>,
     pub last_label_is_current: bool,
     pub state: ScopeState,
@@ -435,7 +453,7 @@ impl ScopeInternal {
     }
 
     pub fn with_parent(parent: Gc<RefCell<Self>>) -> Res<Self> {
-        let variables = FxHashMap::with_capacity_and_hasher(8, Default::default());
+        let variables = IndexMap::with_capacity_and_hasher(8, FxBuildHasher);
 
         let par_scope = parent.borrow()?;
         let state = par_scope.state.copy();
@@ -555,6 +573,93 @@ impl ScopeInternal {
         }
     }
 
+    /// Finds the scope and slot that [`Self::resolve`] would get `name` from.
+    ///
+    /// Returns `None` if the variable lives in an object backed scope or is
+    /// still uninitialized, neither of which can be cached.
+    fn locate(&self, name: &str, hops: u32) -> Res<Option<Location>> {
+        let ObjectOrVariables::Variables(v) = &self.variables else {
+            return Ok(None);
+        };
+
+        if let Some(slot) = v.get_index_of(name) {
+            return Ok(Some(Location { hops, slot }));
+        }
+
+        if self.hoisted.contains(name) {
+            return Ok(None);
+        }
+
+        match &self.parent {
+            Some(parent) => parent.borrow()?.locate(name, hops + 1),
+            None => Ok(None),
+        }
+    }
+
+    fn get_at(&self, location: Location, name: &str, realm: &mut Realm) -> Res<Option<Value>> {
+        if location.hops > 0 {
+            let Some(parent) = &self.parent else {
+                return Ok(None);
+            };
+
+            let location = Location {
+                hops: location.hops - 1,
+                ..location
+            };
+
+            return parent.borrow()?.get_at(location, name, realm);
+        }
+
+        let ObjectOrVariables::Variables(v) = &self.variables else {
+            return Ok(None);
+        };
+
+        Ok(v.get_index(location.slot)
+            .filter(|(key, _)| *key == name)
+            .map(|(_, var)| var.copy_value(realm)))
+    }
+
+    fn update_at(
+        &mut self,
+        location: Location,
+        name: &str,
+        value: &Value,
+        realm: &mut Realm,
+    ) -> Res<bool> {
+        if location.hops > 0 {
+            let Some(parent) = &self.parent else {
+                return Ok(false);
+            };
+
+            let location = Location {
+                hops: location.hops - 1,
+                ..location
+            };
+
+            return parent.borrow_mut()?.update_at(location, name, value, realm);
+        }
+
+        let ObjectOrVariables::Variables(v) = &mut self.variables else {
+            return Ok(false);
+        };
+
+        let Some((key, var)) = v.get_index_mut(location.slot) else {
+            return Ok(false);
+        };
+
+        if key != name {
+            return Ok(false);
+        }
+
+        if !var.is_writable(realm) {
+            return Err(Error::ty("Assignment to constant variable"));
+        }
+
+        var.update(value.copy(), realm)?;
+
+        Ok(true)
+    }
+
     pub fn has_value(&self, name: &str, realm: &mut Realm) -> Res<bool> {
         if self.variables.contains_key(name, realm) {
             Ok(true)
@@ -795,6 +900,7 @@ impl Scope {
     pub fn new(realm: &Realm, path: PathBuf) -> Self {
         Self {
             scope: Gc::new(RefCell::new(ScopeInternal::new(realm, path))),
+            resolution: None,
         }
     }
 
@@ -802,6 +908,7 @@ impl Scope {
     pub fn global(realm: &Realm, path: PathBuf) -> Self {
         Self {
             scope: Gc::new(RefCell::new(ScopeInternal::global(realm, path))),
+            resolution: None,
         }
     }
 
@@ -810,6 +917,7 @@ impl Scope {
             scope: Gc::new(RefCell::new(ScopeInternal::with_parent(Gc::clone(
                 &parent.scope,
             ))?)),
+            resolution: parent.resolution.clone(),
         })
     }
 
@@ -831,6 +939,7 @@ impl Scope {
                 new_target,
                 file: None,
             })),
+            resolution: parent.resolution.clone(),
         })
     }
 
@@ -840,6 +949,7 @@ impl Scope {
                 Gc::clone(&parent.scope),
                 this,
             )?)),
+            resolution: parent.resolution.clone(),
         })
     }
 
@@ -852,6 +962,7 @@ impl Scope {
 
         Ok(Self {
             scope: Gc::new(RefCell::new(scope)),
+            resolution: parent.resolution.clone(),
         })
     }
 
@@ -881,6 +992,104 @@ impl Scope {
         self.scope.borrow()?.resolve(name, realm)
     }
 
+    /// The same scope, with identifiers resolved through `resolution`.
+    ///
+    /// The resolution belongs to this handle and the scopes created from it,
+    /// so code from different scripts (e.g. `eval`) can share a scope.
+    #[must_use]
+    pub fn with_resolution(&self, resolution: Rc<Resolution>) -> Self {
+        Self {
+            scope: Gc::clone(&self.scope),
+            resolution: Some(resolution),
+        }
+    }
+
+    /// A scope handle for `scope`, without resolved identifiers.
+    pub(crate) const fn from_internal(scope: Gc<RefCell<ScopeInternal>>) -> Self {
+        Self {
+            scope,
+            resolution: None,
+        }
+    }
+
+    pub(crate) const fn internal(&self) -> &Gc<RefCell<ScopeInternal>> {
+        &self.scope
+    }
+
+    #[must_use]
+    pub const fn resolution(&self) -> Option<&Rc<Resolution>> {
+        self.resolution.as_ref()
+    }
+
+    fn binding(&self, pos: u32) -> Option<&Binding> {
+        self.resolution.as_ref()?.get_at(pos)
+    }
+
+    /// Like [`Self::resolve`], but uses the slot of a statically resolved
+    /// identifier instead of looking it up by name.
+    pub fn resolve_ident(&self, ident: &Ident, realm: &mut Realm) -> Res<Option<Value>> {
+        self.resolve_at(ident.span.lo.0, ident.sym.as_str(), realm)
+    }
+
+    /// Resolves the identifier `name` whose span starts at `pos`, through its
+    /// slot if the [`Resolution`] of this scope has a binding for it.
+    pub fn resolve_at(&self, pos: u32, name: &str, realm: &mut Realm) -> Res<Option<Value>> {
+        let scope = self.scope.borrow()?;
+
+        if let Some(binding) = self.binding(pos) {
+            if let Some(location) = binding.location()
+                && let Some(value) = scope.get_at(location, name, realm)?
+            {
+                return Ok(Some(value));
+            }
+
+            if let Some(location) = scope.locate(name, 0)? {
+                binding.set_location(location);
+
+                return scope.get_at(location, name, realm);
+            }
+        }
+
+        scope.resolve(name, realm)
+    }
+
+    /// Assigns to a statically resolved identifier through its slot.
+    ///
+    /// Returns `false` if the identifier has to be assigned by name.
+    pub fn try_update_ident(&self, ident: &Ident, value: &Value, realm: &mut Realm) -> Res<bool> {
+        self.try_update_at(ident.span.lo.0, ident.sym.as_str(), value, realm)
+    }
+
+    /// Like [`Self::try_update_ident`], for the identifier `name` whose span
+    /// starts at `pos`.
+    pub fn try_update_at(
+        &self,
+        pos: u32,
+        name: &str,
+        value: &Value,
+        realm: &mut Realm,
+    ) -> Res<bool> {
+        let Some(binding) = self.binding(pos) else {
+            return Ok(false);
+        };
+
+        let mut scope = self.scope.borrow_mut()?;
+
+        if let Some(location) = binding.location()
+            && scope.update_at(location, name, value, realm)?
+        {
+            return Ok(true);
+        }
+
+        let Some(location) = scope.locate(name, 0)? else {
+            return Ok(false);
+        };
+
+        binding.set_location(location);
+
+        scope.update_at(location, name, value, realm)
+    }
+
     pub fn has_label(&self, label: &str) -> Res<bool> {
         let Ok(scope) = self.scope.borrow() else {
             return Ok(false);
@@ -1087,13 +1296,17 @@ impl From<ScopeInternal> for Scope {
     fn from(scope: ScopeInternal) -> Self {
         Self {
             scope: Gc::new(RefCell::new(scope)),
+            resolution: None,
         }
     }
 }
 
 impl From<Gc<RefCell<ScopeInternal>>> for Scope {
     fn from(scope: Gc<RefCell<ScopeInternal>>) -> Self {
-        Self { scope }
+        Self {
+            scope,
+            resolution: None,
+        }
     }
 }
 
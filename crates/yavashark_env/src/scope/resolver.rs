@@ -0,0 +1,556 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::Cell;
+use swc_ecma_ast::{
+    ArrowExpr, ArrowFunctionBody, BlockStmt, CallExpr, Callee, CatchClause, Class, Decl, Expr,
+    FnExpr, ForHead, ForInStmt, ForOfStmt, ForStmt, Function, Ident, ModuleItem, ObjectPatProp,
+    Pat, Stmt, SwitchStmt, VarDecl, VarDeclKind, VarDeclOrExpr, WithStmt,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+
+type Names = FxHashSet<String>;
+
+/// The statically known bindings of a script.
+///
+/// Every identifier that refers to a function or block scoped declaration gets
+/// a [`Binding`], keyed by the start of its span (the span survives cloning
+/// the AST, its address doesn't). Identifiers that refer to globals,
+/// `arguments` or anything that goes through a `with` statement, a class body
+/// or a function with a direct `eval` are left out and go through the dynamic
+/// lookup by name.
+///
+/// The interpreter and the bytecode compiler share the resolution of a script,
+/// see [`Scope::resolve_at`](crate::scope::Scope::resolve_at).
+#[derive(Debug, Default)]
+pub struct Resolution {
+    bindings: FxHashMap<u32, Binding>,
+}
+
+/// A statically resolved identifier, it always refers to the same
+/// declaration.
+///
+/// The interpreter creates more scopes than there are lexical scopes (loop
+/// iterations, parameter scopes, ...), so the binding doesn't know where the
+/// variable lives at runtime. Instead, the first lookup remembers the
+/// [`Location`] that the dynamic lookup found, and later lookups check that
+/// the variable at that location still has the expected name.
+#[derive(Debug, Default)]
+pub struct Binding {
+    location: Cell<Option<Location>>,
+}
+
+/// Where a binding was found at runtime: the number of parent scopes to walk
+/// up and the slot of the variable in that scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub hops: u32,
+    pub slot: usize,
+}
+
+impl Resolution {
+    #[must_use]
+    pub fn script(stmts: &[Stmt]) -> Self {
+        let mut resolver = Resolver::new(contains_direct_eval(stmts));
+
+        stmts.visit_with(&mut resolver);
+
+        resolver.finish()
+    }
+
+    #[must_use]
+    pub fn module(items: &[ModuleItem]) -> Self {
+        let mut resolver = Resolver::new(contains_direct_eval(items));
+
+        items.visit_with(&mut resolver);
+
+        resolver.finish()
+    }
+
+    #[must_use]
+    pub fn get(&self, ident: &Ident) -> Option<&Binding> {
+        self.get_at(ident.span.lo.0)
+    }
+
+    /// The binding of the identifier whose span starts at `pos`.
+    #[must_use]
+    pub fn get_at(&self, pos: u32) -> Option<&Binding> {
+        self.bindings.get(&pos)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+}
+
+impl Binding {
+    pub(crate) const fn location(&self) -> Option<Location> {
+        self.location.get()
+    }
+
+    pub(crate) fn set_location(&self, location: Location) {
+        self.location.set(Some(location));
+    }
+}
+
+struct LexicalScope {
+    names: Names,
+    /// Whether bindings can show up in this scope at runtime that aren't
+    /// visible in the source (`with`, class bodies, direct `eval`, the global
+    /// scope).
+    dynamic: bool,
+}
+
+struct Resolver {
+    scopes: Vec<LexicalScope>,
+    /// Whether the function we're currently in contains a direct `eval`.
+    eval: bool,
+    bindings: FxHashMap<u32, Binding>,
+}
+
+impl Resolver {
+    fn new(eval: bool) -> Self {
+        Self {
+            scopes: vec![LexicalScope {
+                names: Names::default(),
+                dynamic: true,
+            }],
+            eval,
+            bindings: FxHashMap::default(),
+        }
+    }
+
+    fn finish(self) -> Resolution {
+        Resolution {
+            bindings: self.bindings,
+        }
+    }
+
+    fn with_scope(&mut self, names: Names, dynamic: bool, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(LexicalScope { names, dynamic });
+        f(self);
+        self.scopes.pop();
+    }
+
+    fn with_block(&mut self, names: Names, f: impl FnOnce(&mut Self)) {
+        if names.is_empty() {
+            f(self);
+            return;
+        }
+
+        self.with_scope(names, self.eval, f);
+    }
+
+    fn with_function(&mut self, names: Names, eval: bool, f: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.eval, eval);
+        self.with_scope(names, eval, f);
+        self.eval = outer;
+    }
+
+    /// Whether `name` is declared in a scope that no runtime binding can
+    /// shadow.
+    fn is_static(&self, name: &str) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if scope.dynamic {
+                return false;
+            }
+
+            if scope.names.contains(name) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl Visit for Resolver {
+    fn visit_ident(&mut self, ident: &Ident) {
+        // `DUMMY_SP` idents don't come from the source
+        if ident.span.lo.0 == 0 || ident.sym == "arguments" {
+            return;
+        }
+
+        if self.is_static(&ident.sym) {
+            self.bindings.insert(ident.span.lo.0, Binding::default());
+        }
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let mut names = Names::default();
+
+        for param in &function.params {
+            pat_names(&param.pat, &mut names);
+        }
+
+        let mut eval = function
+            .params
+            .iter()
+            .any(|param| contains_direct_eval(&param.pat));
+
+        if let Some(body) = &function.body {
+            var_names(&body.stmts, &mut names);
+            lexical_names(&body.stmts, &mut names);
+            eval |= contains_direct_eval(&body.stmts);
+        }
+
+        self.with_function(names, eval, |this| function.visit_children_with(this));
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        let mut names = Names::default();
+
+        for param in &arrow.params {
+            pat_names(param, &mut names);
+        }
+
+        if let ArrowFunctionBody::FunctionBody(body) = &*arrow.body {
+            var_names(&body.stmts, &mut names);
+            lexical_names(&body.stmts, &mut names);
+        }
+
+        let eval = contains_direct_eval(&arrow.params) || contains_direct_eval(&arrow.body);
+
+        self.with_function(names, eval, |this| arrow.visit_children_with(this));
+    }
+
+    fn visit_fn_expr(&mut self, expr: &FnExpr) {
+        let mut names = Names::default();
+
+        if let Some(ident) = &expr.ident {
+            names.insert(ident.sym.to_string());
+        }
+
+        self.with_block(names, |this| expr.function.visit_with(this));
+    }
+
+    fn visit_block_stmt(&mut self, block: &BlockStmt) {
+        let mut names = Names::default();
+        lexical_names(&block.stmts, &mut names);
+
+        self.with_block(names, |this| block.visit_children_with(this));
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt) {
+        let mut names = Names::default();
+
+        if let Some(VarDeclOrExpr::VarDecl(decl)) = &stmt.init {
+            lexical_decl_names(decl, &mut names);
+        }
+
+        self.with_block(names, |this| stmt.visit_children_with(this));
+    }
+
+    fn visit_for_in_stmt(&mut self, stmt: &ForInStmt) {
+        let mut names = Names::default();
+        head_names(&stmt.left, &mut names);
+
+        self.with_block(names, |this| stmt.visit_children_with(this));
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        let mut names = Names::default();
+        head_names(&stmt.left, &mut names);
+
+        self.with_block(names, |this| stmt.visit_children_with(this));
+    }
+
+    fn visit_catch_clause(&mut self, clause: &CatchClause) {
+        let mut names = Names::default();
+
+        if let Some(param) = &clause.param {
+            pat_names(param, &mut names);
+        }
+
+        self.with_block(names, |this| clause.visit_children_with(this));
+    }
+
+    fn visit_switch_stmt(&mut self, stmt: &SwitchStmt) {
+        stmt.discriminant.visit_with(self);
+
+        let mut names = Names::default();
+
+        for case in &stmt.cases {
+            lexical_names(&case.cons, &mut names);
+        }
+
+        self.with_block(names, |this| stmt.cases.visit_with(this));
+    }
+
+    fn visit_with_stmt(&mut self, stmt: &WithStmt) {
+        stmt.obj.visit_with(self);
+
+        self.with_scope(Names::default(), true, |this| stmt.body.visit_with(this));
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        // field initializers and static blocks run in a scope backed by an object
+        self.with_scope(Names::default(), true, |this| {
+            class.visit_children_with(this);
+        });
+    }
+}
+
+/// Names declared with `var` and function declarations, without descending
+/// into nested functions.
+fn var_names(stmts: &[Stmt], names: &mut Names) {
+    for stmt in stmts {
+        stmt_var_names(stmt, names);
+    }
+}
+
+fn stmt_var_names(stmt: &Stmt, names: &mut Names) {
+    match stmt {
+        Stmt::Decl(Decl::Var(decl)) if decl.kind == VarDeclKind::Var => {
+            decl_names(decl, names);
+        }
+        Stmt::Decl(Decl::Fn(decl)) => {
+            names.insert(decl.ident.sym.to_string());
+        }
+        Stmt::Block(block) => var_names(&block.stmts, names),
+        Stmt::If(stmt) => {
+            stmt_var_names(&stmt.cons, names);
+
+            if let Some(alt) = &stmt.alt {
+                stmt_var_names(alt, names);
+            }
+        }
+        Stmt::For(stmt) => {
+            if let Some(VarDeclOrExpr::VarDecl(decl)) = &stmt.init
+                && decl.kind == VarDeclKind::Var
+            {
+                decl_names(decl, names);
+            }
+
+            stmt_var_names(&stmt.body, names);
+        }
+        Stmt::ForIn(stmt) => {
+            head_var_names(&stmt.left, names);
+            stmt_var_names(&stmt.body, names);
+        }
+        Stmt::ForOf(stmt) => {
+            head_var_names(&stmt.left, names);
+            stmt_var_names(&stmt.body, names);
+        }
+        Stmt::While(stmt) => stmt_var_names(&stmt.body, names),
+        Stmt::DoWhile(stmt) => stmt_var_names(&stmt.body, names),
+        Stmt::Labeled(stmt) => stmt_var_names(&stmt.body, names),
+        Stmt::With(stmt) => stmt_var_names(&stmt.body, names),
+        Stmt::Switch(stmt) => {
+            for case in &stmt.cases {
+                var_names(&case.cons, names);
+            }
+        }
+        Stmt::Try(stmt) => {
+            var_names(&stmt.block.stmts, names);
+
+            if let Some(handler) = &stmt.handler {
+                var_names(&handler.body.stmts, names);
+            }
+
+            if let Some(finalizer) = &stmt.finalizer {
+                var_names(&finalizer.stmts, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Names declared directly in a block with `let`, `const`, `class` or
+/// `function`.
+fn lexical_names(stmts: &[Stmt], names: &mut Names) {
+    for stmt in stmts {
+        if let Stmt::Decl(decl) = stmt {
+            match decl {
+                Decl::Var(decl) => lexical_decl_names(decl, names),
+                Decl::Fn(decl) => {
+                    names.insert(decl.ident.sym.to_string());
+                }
+                Decl::Class(decl) => {
+                    names.insert(decl.ident.sym.to_string());
+                }
+                Decl::Using(decl) => {
+                    for decl in &decl.decls {
+                        pat_names(&decl.name, names);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn lexical_decl_names(decl: &VarDecl, names: &mut Names) {
+    if decl.kind != VarDeclKind::Var {
+        decl_names(decl, names);
+    }
+}
+
+fn head_names(head: &ForHead, names: &mut Names) {
+    match head {
+        ForHead::VarDecl(decl) => lexical_decl_names(decl, names),
+        ForHead::UsingDecl(decl) => {
+            for decl in &decl.decls {
+                pat_names(&decl.name, names);
+            }
+        }
+        ForHead::Pat(_) => {}
+    }
+}
+
+fn head_var_names(head: &ForHead, names: &mut Names) {
+    if let ForHead::VarDecl(decl) = head
+        && decl.kind == VarDeclKind::Var
+    {
+        decl_names(decl, names);
+    }
+}
+
+fn decl_names(decl: &VarDecl, names: &mut Names) {
+    for decl in &decl.decls {
+        pat_names(&decl.name, names);
+    }
+}
+
+fn pat_names(pat: &Pat, names: &mut Names) {
+    match pat {
+        Pat::Ident(ident) => {
+            names.insert(ident.id.sym.to_string());
+        }
+        Pat::Array(array) => {
+            for elem in array.elems.iter().flatten() {
+                pat_names(elem, names);
+            }
+        }
+        Pat::Object(object) => {
+            for prop in &object.props {
+                match prop {
+                    ObjectPatProp::KeyValue(kv) => pat_names(&kv.value, names),
+                    ObjectPatProp::Assign(assign) => {
+                        names.insert(assign.key.sym.to_string());
+                    }
+                    ObjectPatProp::Rest(rest) => pat_names(&rest.arg, names),
+                }
+            }
+        }
+        Pat::Rest(rest) => pat_names(&rest.arg, names),
+        Pat::Assign(assign) => pat_names(&assign.left, names),
+        Pat::Expr(_) | Pat::Invalid(_) => {}
+    }
+}
+
+/// Whether `node` calls `eval` directly, ignoring nested functions (their
+/// `eval` can't add bindings to our scopes).
+fn contains_direct_eval<N: VisitWith<DirectEval> + ?Sized>(node: &N) -> bool {
+    let mut finder = DirectEval(false);
+    node.visit_with(&mut finder);
+
+    finder.0
+}
+
+struct DirectEval(bool);
+
+impl Visit for DirectEval {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee
+            && let Expr::Ident(ident) = &**callee
+            && ident.sym == "eval"
+        {
+            self.0 = true;
+            return;
+        }
+
+        call.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swc_common::BytePos;
+    use swc_ecma_parser::{EsSyntax, Parser, StringInput, Syntax};
+
+    fn resolve(src: &str) -> (Resolution, Vec<Ident>) {
+        // spans start at 1, `BytePos(0)` is the dummy position
+        let input = StringInput::new(src, BytePos(1), BytePos(src.len() as u32 + 1));
+        let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+        let script = parser.parse_script().unwrap();
+
+        let mut idents = IdentCollector(Vec::new());
+        script.body.visit_with(&mut idents);
+
+        (Resolution::script(&script.body), idents.0)
+    }
+
+    struct IdentCollector(Vec<Ident>);
+
+    impl Visit for IdentCollector {
+        fn visit_ident(&mut self, ident: &Ident) {
+            self.0.push(ident.clone());
+        }
+    }
+
+    /// Whether the last occurrence of `name` is resolved statically.
+    fn is_bound(src: &str, name: &str) -> bool {
+        let (resolution, idents) = resolve(src);
+        let ident = idents.iter().rev().find(|ident| ident.sym == name).unwrap();
+
+        resolution.get(ident).is_some()
+    }
+
+    #[test]
+    fn locals() {
+        let src = "function f(a, b) { var c; let d; return a + b + c + d; }";
+
+        for name in ["a", "b", "c", "d"] {
+            assert!(is_bound(src, name), "{name}");
+        }
+    }
+
+    #[test]
+    fn nested_scopes() {
+        let src = "function f(a) { for (let i = 0; i < a; i++) { let x; (() => x + i + a)(); } }";
+
+        for name in ["x", "i", "a"] {
+            assert!(is_bound(src, name), "{name}");
+        }
+    }
+
+    #[test]
+    fn globals_are_dynamic() {
+        assert!(!is_bound("let x = 1; x", "x"));
+        assert!(!is_bound("function f() { return x; }", "x"));
+        assert!(!is_bound("function f() { return arguments; }", "arguments"));
+    }
+
+    #[test]
+    fn with_and_eval_are_dynamic() {
+        assert!(!is_bound("function f(o, a) { with (o) { a; } }", "a"));
+        assert!(!is_bound("function f(a) { eval(''); return a; }", "a"));
+        assert!(!is_bound(
+            "function f(a) { eval(''); return () => a; }",
+            "a"
+        ));
+        assert!(is_bound(
+            "function f() { (function () { eval('') })(); let a; a }",
+            "a"
+        ));
+    }
+
+    #[test]
+    fn class_bodies_are_dynamic() {
+        assert!(!is_bound("function f(a) { class A { x = a; } }", "a"));
+        assert!(is_bound(
+            "function f() { class A { m() { let a; a } } }",
+            "a"
+        ));
+    }
+}
@@ -0,0 +1,409 @@
+//! Snapshots of the user state of a realm.
+//!
+//! A [`Snapshot`] stores what running code added to a realm: the user objects
+//! reachable from the global object and the loaded modules, the scopes the
+//! functions close over and every change made to the builtins. It is not an
+//! image of the heap. Builtins aren't stored, they are referenced by the path
+//! they are found at from the global object of a fresh realm, so a snapshot is
+//! restored on top of a realm from [`Realm::new`] and startup still pays for
+//! setting up the builtins. What it saves is running the code that built the
+//! user state again.
+//!
+//! Native functions keep working that way, functions of the host (e.g. the
+//! interpreter) are stored and recreated through
+//! [`Eval::snapshot_function`](crate::realm::Eval::snapshot_function) and
+//! [`Eval::restore_function`](crate::realm::Eval::restore_function).
+//!
+//! Maps, promises, classes, their instances and other objects with internal state
+//! can't be snapshotted, [`Snapshot::capture`] fails with the path to the
+//! first one it finds.
+
+mod builtins;
+mod capture;
+mod format;
+mod restore;
+
+use crate::scope::Scope;
+use crate::value::Attributes;
+use crate::{Realm, Res, Value};
+use std::path::PathBuf;
+use yavashark_string::YSString;
+
+/// What a host function is made of: the code to recreate it from, the scope
+/// it closes over and the values it holds on to.
+#[derive(Debug, Clone)]
+pub struct SnapshotFunction {
+    pub code: Vec<u8>,
+    pub scope: Option<Scope>,
+    pub values: Vec<Value>,
+}
+
+/// The user state of a realm, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// The builtins besides the global object, which is builtin `0`.
+    builtins: Vec<Builtin>,
+    /// The descriptions of the symbols created with `Symbol()`.
+    symbols: Vec<YSString>,
+    nodes: Vec<Node>,
+    scopes: Vec<ScopeNode>,
+    fixups: Vec<Fixup>,
+    modules: Vec<ModuleNode>,
+}
+
+impl Snapshot {
+    /// Captures the current state of `realm`, this fails if it still has jobs
+    /// to run or objects that can't be snapshotted.
+    pub fn capture(realm: &mut Realm) -> Res<Self> {
+        capture::capture(realm)
+    }
+
+    /// Recreates the captured state in `realm`, which has to be a fresh realm
+    /// set up like the captured one.
+    pub fn restore(&self, realm: &mut Realm) -> Res {
+        restore::restore(self, realm)
+    }
+
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format::write(self)
+    }
+
+    /// Reads a snapshot written by [`Snapshot::to_bytes`] of the same version
+    /// of the engine.
+    pub fn from_bytes(bytes: &[u8]) -> Res<Self> {
+        format::read(bytes)
+    }
+}
+
+const WRITABLE: u8 = 0b1;
+const ENUMERABLE: u8 = 0b10;
+const CONFIGURABLE: u8 = 0b100;
+
+const fn attribute_bits(writable: bool, enumerable: bool, configurable: bool) -> u8 {
+    let mut bits = 0;
+
+    if writable {
+        bits |= WRITABLE;
+    }
+    if enumerable {
+        bits |= ENUMERABLE;
+    }
+    if configurable {
+        bits |= CONFIGURABLE;
+    }
+
+    bits
+}
+
+const fn attributes(bits: u8) -> Attributes {
+    Attributes::from_values(
+        bits & WRITABLE != 0,
+        bits & ENUMERABLE != 0,
+        bits & CONFIGURABLE != 0,
+    )
+}
+
+/// A builtin object, reached by `segment` from the builtin `parent`.
+#[derive(Debug, Clone, PartialEq)]
+struct Builtin {
+    parent: u32,
+    segment: Segment,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Value(Key),
+    Getter(Key),
+    Setter(Key),
+    Prototype,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SymbolRef {
+    /// One of the well known symbols like `Symbol.iterator`.
+    WellKnown(YSString),
+    /// A symbol from `Symbol.for`.
+    Registered(YSString),
+    Local(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Key {
+    String(YSString),
+    Symbol(SymbolRef),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ObjRef {
+    Builtin(u32),
+    Node(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Val {
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(YSString),
+    /// Two's complement, little endian.
+    BigInt(Vec<u8>),
+    Symbol(SymbolRef),
+    Object(ObjRef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Prop {
+    Data {
+        value: Val,
+        attributes: u8,
+    },
+    Accessor {
+        get: Option<ObjRef>,
+        set: Option<ObjRef>,
+        attributes: u8,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Integrity {
+    Extensible,
+    NonExtensible,
+    Sealed,
+    Frozen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+    Object,
+    Array {
+        len: u64,
+    },
+    Function {
+        code: Vec<u8>,
+        scope: Option<u32>,
+        values: Vec<Val>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    kind: NodeKind,
+    prototype: Option<ObjRef>,
+    properties: Vec<(Key, Prop)>,
+    integrity: Integrity,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ScopeVariables {
+    Object(ObjRef),
+    Variables(Vec<(String, ScopeVariable)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ScopeVariable {
+    Value(Val, u8),
+    Reference { name: YSString, object: ObjRef },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ScopeNode {
+    parent: Option<u32>,
+    variables: ScopeVariables,
+    hoisted: Vec<String>,
+    labels: Vec<String>,
+    last_label_is_current: bool,
+    state: u8,
+    this: Val,
+    new_target: Val,
+    file: Option<PathBuf>,
+}
+
+/// A change to the builtin `target`.
+#[derive(Debug, Clone, PartialEq)]
+struct Fixup {
+    target: u32,
+    change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Define(Key, Prop),
+    Delete(Key),
+    Prototype(Option<ObjRef>),
+    Integrity(Integrity),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ModuleNode {
+    path: PathBuf,
+    default: Option<Val>,
+    exports: ObjRef,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::array::Array;
+    use crate::{Object, ObjectHandle, ObjectOrNull, PropertyKey, Symbol};
+
+    fn global(realm: &mut Realm, name: &'static str) -> ObjectHandle {
+        let global = realm.global.clone();
+
+        global.get(name, realm).unwrap().to_object().unwrap()
+    }
+
+    fn round_trip(realm: &mut Realm) -> Realm {
+        let snapshot = Snapshot::capture(realm).unwrap();
+
+        assert_eq!(
+            Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(),
+            snapshot
+        );
+
+        let mut restored = Realm::new().unwrap();
+        snapshot.restore(&mut restored).unwrap();
+
+        restored
+    }
+
+    #[test]
+    fn restores_user_objects() {
+        let mut realm = Realm::new().unwrap();
+
+        let array_proto = global(&mut realm, "Array")
+            .get("prototype", &mut realm)
+            .unwrap()
+            .to_object()
+            .unwrap();
+
+        let obj = Object::new(&realm);
+        let list = ObjectHandle::new(Array::new(array_proto));
+        let symbol = Symbol::new_str("local");
+
+        list.define_property(0.into(), Value::Number(-0.0), &mut realm)
+            .unwrap();
+        list.define_property(1.into(), obj.clone().into(), &mut realm)
+            .unwrap();
+        obj.define_property("list".into(), list.into(), &mut realm)
+            .unwrap();
+        obj.define_property(symbol.clone().into(), symbol.into(), &mut realm)
+            .unwrap();
+        obj.freeze().unwrap();
+
+        let global_obj = realm.global.clone();
+        global_obj
+            .define_property("obj".into(), obj.into(), &mut realm)
+            .unwrap();
+
+        let mut realm = round_trip(&mut realm);
+
+        let obj = global(&mut realm, "obj");
+        assert!(obj.is_frozen());
+
+        let list = obj.get("list", &mut realm).unwrap().to_object().unwrap();
+        assert_eq!(list.downcast::<Array>().unwrap().len(), 2);
+        assert_eq!(list.get(1, &mut realm).unwrap(), Value::Object(obj.clone()));
+        assert!(
+            matches!(list.get(0, &mut realm).unwrap(), Value::Number(n) if n.to_bits() == (-0.0f64).to_bits())
+        );
+
+        let array_proto = global(&mut realm, "Array")
+            .get("prototype", &mut realm)
+            .unwrap()
+            .to_object()
+            .unwrap();
+        assert_eq!(
+            list.prototype(&mut realm).unwrap(),
+            ObjectOrNull::Object(array_proto)
+        );
+
+        // the symbol is recreated once, it is both the key and the value
+        let symbol = obj
+            .keys(&mut realm)
+            .unwrap()
+            .into_iter()
+            .find_map(|key| match key {
+                PropertyKey::Symbol(symbol) => Some(symbol),
+                PropertyKey::String(_) => None,
+            })
+            .unwrap();
+        assert_eq!(
+            obj.get(symbol.clone(), &mut realm).unwrap(),
+            Value::Symbol(symbol)
+        );
+    }
+
+    #[test]
+    fn restores_changes_to_builtins() {
+        let mut realm = Realm::new().unwrap();
+
+        let math = global(&mut realm, "Math");
+        let max = math.get("max", &mut realm).unwrap();
+        math.define_property("biggest".into(), max, &mut realm)
+            .unwrap();
+        math.delete_property("min".into(), &mut realm).unwrap();
+        math.set_prototype(ObjectOrNull::Null, &mut realm).unwrap();
+
+        let mut realm = round_trip(&mut realm);
+
+        let math = global(&mut realm, "Math");
+        assert_eq!(
+            math.get("biggest", &mut realm).unwrap(),
+            math.get("max", &mut realm).unwrap()
+        );
+        assert!(!math.contains_own_key("min".into(), &mut realm).unwrap());
+        assert_eq!(math.prototype(&mut realm).unwrap(), ObjectOrNull::Null);
+    }
+
+    #[test]
+    fn unchanged_realm_has_no_fixups() {
+        let mut realm = Realm::new().unwrap();
+        let snapshot = Snapshot::capture(&mut realm).unwrap();
+
+        assert_eq!(snapshot.fixups, Vec::new());
+        assert!(snapshot.nodes.is_empty());
+    }
+
+    #[test]
+    fn rejects_objects_with_internal_state() {
+        let mut realm = Realm::new().unwrap();
+
+        let map = global(&mut realm, "Map")
+            .construct(Vec::new(), &mut realm)
+            .unwrap();
+        let obj = Object::new(&realm);
+        obj.define_property("map".into(), map.into(), &mut realm)
+            .unwrap();
+
+        let global_obj = realm.global.clone();
+        global_obj
+            .define_property("state".into(), obj.into(), &mut realm)
+            .unwrap();
+
+        let err = Snapshot::capture(&mut realm).unwrap_err();
+
+        assert_eq!(
+            err.message_internal(),
+            "can't snapshot Map objects (found at globalThis.state.map)"
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_files() {
+        let mut realm = Realm::new().unwrap();
+        let global_obj = realm.global.clone();
+        global_obj
+            .define_property("answer".into(), Value::Number(42.0), &mut realm)
+            .unwrap();
+
+        let bytes = Snapshot::capture(&mut realm).unwrap().to_bytes();
+
+        assert!(Snapshot::from_bytes(b"not a snapshot").is_err());
+
+        for len in 0..bytes.len() {
+            assert!(Snapshot::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+}
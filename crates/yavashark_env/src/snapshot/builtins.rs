@@ -0,0 +1,209 @@
+//! Finding the builtins of a realm by walking its global object together with
+//! the one of a fresh realm: objects found at the same place in both are the
+//! same builtin, everything else was created by running code.
+
+use super::{Builtin, Key, Segment, SymbolRef};
+use crate::builtins::signal::{UNWATCHED, WATCHED};
+use crate::value::PropertyDescriptor;
+use crate::{ObjectHandle, ObjectOrNull, PropertyKey, Realm, Res, Symbol, Value};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use yavashark_string::YSString;
+
+/// The symbols with a [`Symbol::static_name`].
+const STATIC_SYMBOLS: [&Symbol; 17] = [
+    Symbol::ASYNC_ITERATOR,
+    Symbol::HAS_INSTANCE,
+    Symbol::IS_CONCAT_SPREADABLE,
+    Symbol::ITERATOR,
+    Symbol::MATCH,
+    Symbol::MATCH_ALL,
+    Symbol::REPLACE,
+    Symbol::SEARCH,
+    Symbol::SPECIES,
+    Symbol::SPLIT,
+    Symbol::TO_PRIMITIVE,
+    Symbol::TO_STRING_TAG,
+    Symbol::UNSCOPABLES,
+    Symbol::DISPOSE,
+    Symbol::ASYNC_DISPOSE,
+    WATCHED,
+    UNWATCHED,
+];
+
+pub fn static_symbol(name: &str) -> Option<Symbol> {
+    STATIC_SYMBOLS
+        .iter()
+        .find(|symbol| symbol.as_str() == name)
+        .map(|symbol| (*symbol).clone())
+}
+
+/// `key` as part of the path to a builtin, only keys that are the same in
+/// every realm can be.
+fn path_key(key: &PropertyKey) -> Option<Key> {
+    match key {
+        PropertyKey::String(s) => Some(Key::String(s.clone())),
+        PropertyKey::Symbol(symbol) => symbol
+            .static_name()
+            .map(|name| Key::Symbol(SymbolRef::WellKnown(YSString::new_static(name)))),
+    }
+}
+
+pub struct Builtins {
+    pub entries: Vec<Builtin>,
+    /// The builtin in the captured realm and in the fresh realm, by id.
+    pub pairs: Vec<(ObjectHandle, ObjectHandle)>,
+    user: HashMap<ObjectHandle, u32>,
+    fresh: HashMap<ObjectHandle, u32>,
+}
+
+impl Builtins {
+    pub fn discover(realm: &mut Realm, fresh: &mut Realm) -> Res<Self> {
+        let mut builtins = Self {
+            entries: Vec::new(),
+            pairs: vec![(realm.global.clone(), fresh.global.clone())],
+            user: HashMap::from([(realm.global.clone(), 0)]),
+            fresh: HashMap::from([(fresh.global.clone(), 0)]),
+        };
+
+        let mut next = 0;
+
+        while let Some((user, fresh_obj)) = builtins.pairs.get(next).cloned() {
+            let id = next as u32;
+            next += 1;
+
+            if let (ObjectOrNull::Object(u), ObjectOrNull::Object(f)) =
+                (user.prototype(realm)?, fresh_obj.prototype(fresh)?)
+            {
+                builtins.pair(id, Segment::Prototype, u, f);
+            }
+
+            for key in fresh_obj.keys(fresh)? {
+                let Some(path) = path_key(&key) else {
+                    continue;
+                };
+
+                let (Some(u), Some(f)) = (
+                    user.get_property_descriptor(key.clone().into(), realm)?,
+                    fresh_obj.get_property_descriptor(key.into(), fresh)?,
+                ) else {
+                    continue;
+                };
+
+                match (u, f) {
+                    (
+                        PropertyDescriptor::Data {
+                            value: Value::Object(u),
+                            ..
+                        },
+                        PropertyDescriptor::Data {
+                            value: Value::Object(f),
+                            ..
+                        },
+                    ) => builtins.pair(id, Segment::Value(path), u, f),
+                    (
+                        PropertyDescriptor::Accessor {
+                            get: user_get,
+                            set: user_set,
+                            ..
+                        },
+                        PropertyDescriptor::Accessor {
+                            get: fresh_get,
+                            set: fresh_set,
+                            ..
+                        },
+                    ) => {
+                        if let (Some(u), Some(f)) = (user_get, fresh_get) {
+                            builtins.pair(id, Segment::Getter(path.clone()), u, f);
+                        }
+
+                        if let (Some(u), Some(f)) = (user_set, fresh_set) {
+                            builtins.pair(id, Segment::Setter(path), u, f);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(builtins)
+    }
+
+    /// Records `user` and `fresh` as the same builtin, unless one of them
+    /// already is a builtin or they are of a different kind, which happens
+    /// when code replaced the builtin.
+    fn pair(&mut self, parent: u32, segment: Segment, user: ObjectHandle, fresh: ObjectHandle) {
+        if self.user.contains_key(&user)
+            || self.fresh.contains_key(&fresh)
+            || user.class_name() != fresh.class_name()
+            || user.name() != fresh.name()
+        {
+            return;
+        }
+
+        let id = self.pairs.len() as u32;
+
+        self.user.insert(user.clone(), id);
+        self.fresh.insert(fresh.clone(), id);
+        self.entries.push(Builtin { parent, segment });
+        self.pairs.push((user, fresh));
+    }
+
+    pub fn user(&self, obj: &ObjectHandle) -> Option<u32> {
+        self.user.get(obj).copied()
+    }
+
+    pub fn fresh(&self, obj: &ObjectHandle) -> Option<u32> {
+        self.fresh.get(obj).copied()
+    }
+
+    pub fn describe(&self, id: u32) -> String {
+        describe(&self.entries, id)
+    }
+}
+
+/// The path to the builtin `id`, like `globalThis.Array.prototype`.
+pub fn describe(builtins: &[Builtin], id: u32) -> String {
+    let mut segments = Vec::new();
+    let mut id = id;
+
+    // parents always come first, a corrupt snapshot must not loop forever
+    while let Some(builtin) = id.checked_sub(1).and_then(|i| builtins.get(i as usize))
+        && builtin.parent < id
+    {
+        segments.push(&builtin.segment);
+        id = builtin.parent;
+    }
+
+    let mut path = "globalThis".to_owned();
+
+    for segment in segments.into_iter().rev() {
+        match segment {
+            Segment::Value(key) => describe_key(&mut path, key),
+            Segment::Getter(key) => {
+                describe_key(&mut path, key);
+                path.push_str(".[[Get]]");
+            }
+            Segment::Setter(key) => {
+                describe_key(&mut path, key);
+                path.push_str(".[[Set]]");
+            }
+            Segment::Prototype => path.push_str(".[[Prototype]]"),
+        }
+    }
+
+    path
+}
+
+/// Appends `.key` or `[Symbol(key)]` to `path`.
+pub fn describe_key(path: &mut String, key: &Key) {
+    match key {
+        Key::String(s) => {
+            _ = write!(path, ".{s}");
+        }
+        Key::Symbol(SymbolRef::WellKnown(name) | SymbolRef::Registered(name)) => {
+            _ = write!(path, "[Symbol({name})]");
+        }
+        Key::Symbol(SymbolRef::Local(_)) => path.push_str("[Symbol()]"),
+    }
+}
@@ -0,0 +1,506 @@
+use super::builtins::{Builtins, describe_key};
+use super::{
+    Change, Fixup, Integrity, Key, ModuleNode, Node, NodeKind, ObjRef, Prop, ScopeNode,
+    ScopeVariable, ScopeVariables, Snapshot, SymbolRef, Val, attribute_bits,
+};
+use crate::array::Array;
+use crate::realm::Eval;
+use crate::scope::{ObjectOrVariables, ScopeInternal, VariableOrRef};
+use crate::value::PropertyDescriptor;
+use crate::{Error, Object, ObjectHandle, ObjectOrNull, PropertyKey, Realm, Res, Symbol, Value};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use yavashark_garbage::Gc;
+use yavashark_string::YSString;
+
+pub fn capture(realm: &mut Realm) -> Res<Snapshot> {
+    if realm.has_pending_jobs() {
+        return Err(Error::new(
+            "can't snapshot a realm with pending jobs, run the event loop first",
+        ));
+    }
+
+    let mut fresh = realm.new_child()?;
+    let builtins = Builtins::discover(realm, &mut fresh)?;
+
+    let mut capture = Capture {
+        builtins,
+        hook: realm.eval_hook(),
+        nodes: Vec::new(),
+        node_ids: HashMap::new(),
+        pending_nodes: VecDeque::new(),
+        scopes: Vec::new(),
+        scope_ids: HashMap::new(),
+        pending_scopes: VecDeque::new(),
+        symbols: Vec::new(),
+        symbol_ids: HashMap::new(),
+    };
+
+    let mut fixups = Vec::new();
+
+    for id in 0..capture.builtins.pairs.len() as u32 {
+        capture.diff(id, &mut fixups, realm, &mut fresh)?;
+    }
+
+    let mut modules = Vec::new();
+
+    for module in realm.env.modules.values() {
+        let loc = format!("the module {}", module.path.display());
+
+        modules.push(ModuleNode {
+            path: module.path.clone(),
+            default: module
+                .default
+                .as_ref()
+                .map(|value| capture.val(value, &loc)),
+            exports: capture.obj(&module.exports, &loc),
+        });
+    }
+
+    loop {
+        if let Some((obj, id, loc)) = capture.pending_nodes.pop_front() {
+            let node = capture.node(&obj, &loc, realm)?;
+            capture.nodes[id as usize] = node;
+        } else if let Some((scope, id, loc)) = capture.pending_scopes.pop_front() {
+            let node = capture.scope_node(&scope, &loc)?;
+            capture.scopes[id as usize] = node;
+        } else {
+            break;
+        }
+    }
+
+    Ok(Snapshot {
+        builtins: capture.builtins.entries,
+        symbols: capture.symbols,
+        nodes: capture.nodes,
+        scopes: capture.scopes,
+        fixups,
+        modules,
+    })
+}
+
+fn integrity(obj: &ObjectHandle) -> Integrity {
+    if obj.is_frozen() {
+        Integrity::Frozen
+    } else if obj.is_sealed() {
+        Integrity::Sealed
+    } else if obj.is_extensible() {
+        Integrity::Extensible
+    } else {
+        Integrity::NonExtensible
+    }
+}
+
+/// `Map` for `yavashark_env::builtins::map::Map`.
+fn short_class_name(obj: &ObjectHandle) -> &'static str {
+    let name = obj.class_name();
+
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+const fn same_number(a: f64, b: f64) -> bool {
+    a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+}
+
+/// Objects and scopes get their id when they are found and are captured
+/// later, so deeply nested objects don't need a deep stack.
+struct Capture {
+    builtins: Builtins,
+    hook: Option<Rc<dyn Eval>>,
+    nodes: Vec<Node>,
+    node_ids: HashMap<ObjectHandle, u32>,
+    pending_nodes: VecDeque<(ObjectHandle, u32, String)>,
+    scopes: Vec<ScopeNode>,
+    scope_ids: HashMap<usize, u32>,
+    pending_scopes: VecDeque<(Gc<RefCell<ScopeInternal>>, u32, String)>,
+    symbols: Vec<YSString>,
+    symbol_ids: HashMap<Symbol, u32>,
+}
+
+impl Capture {
+    /// The changes made to the builtin `id`, compared to the fresh realm.
+    fn diff(
+        &mut self,
+        id: u32,
+        fixups: &mut Vec<Fixup>,
+        realm: &mut Realm,
+        fresh: &mut Realm,
+    ) -> Res {
+        let Some((user, fresh_obj)) = self.builtins.pairs.get(id as usize).cloned() else {
+            return Ok(());
+        };
+
+        let loc = self.builtins.describe(id);
+
+        for key in user.keys(realm)? {
+            let Some(desc) = user.get_property_descriptor(key.clone().into(), realm)? else {
+                continue;
+            };
+
+            if let Some(fresh_desc) =
+                fresh_obj.get_property_descriptor(key.clone().into(), fresh)?
+                && self.same_descriptor(&desc, &fresh_desc)
+            {
+                continue;
+            }
+
+            let key = self.key(&key);
+            let mut prop_loc = loc.clone();
+            describe_key(&mut prop_loc, &key);
+
+            let prop = self.prop(&desc, &prop_loc);
+
+            fixups.push(Fixup {
+                target: id,
+                change: Change::Define(key, prop),
+            });
+        }
+
+        for key in fresh_obj.keys(fresh)? {
+            if !user.contains_own_key(key.clone().into(), realm)? {
+                fixups.push(Fixup {
+                    target: id,
+                    change: Change::Delete(self.key(&key)),
+                });
+            }
+        }
+
+        let proto = user.prototype(realm)?;
+
+        let same_proto = match (&proto, fresh_obj.prototype(fresh)?) {
+            (ObjectOrNull::Object(u), ObjectOrNull::Object(f)) => self.same_object(u, &f),
+            (ObjectOrNull::Null, ObjectOrNull::Null) => true,
+            _ => false,
+        };
+
+        if !same_proto {
+            let proto = self.proto(&proto, &format!("{loc}.[[Prototype]]"));
+
+            fixups.push(Fixup {
+                target: id,
+                change: Change::Prototype(proto),
+            });
+        }
+
+        let integrity = integrity(&user);
+
+        if integrity != self::integrity(&fresh_obj) {
+            fixups.push(Fixup {
+                target: id,
+                change: Change::Integrity(integrity),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether `user` is the builtin `fresh` is in the fresh realm.
+    fn same_object(&self, user: &ObjectHandle, fresh: &ObjectHandle) -> bool {
+        self.builtins
+            .user(user)
+            .is_some_and(|id| self.builtins.fresh(fresh) == Some(id))
+    }
+
+    fn same_value(&self, user: &Value, fresh: &Value) -> bool {
+        match (user, fresh) {
+            (Value::Object(u), Value::Object(f)) => self.same_object(u, f),
+            (Value::Number(u), Value::Number(f)) => same_number(*u, *f),
+            _ => user == fresh,
+        }
+    }
+
+    fn same_accessor(&self, user: Option<&ObjectHandle>, fresh: Option<&ObjectHandle>) -> bool {
+        match (user, fresh) {
+            (Some(u), Some(f)) => self.same_object(u, f),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn same_descriptor(&self, user: &PropertyDescriptor, fresh: &PropertyDescriptor) -> bool {
+        match (user, fresh) {
+            (
+                PropertyDescriptor::Data {
+                    value: u,
+                    writable: uw,
+                    enumerable: ue,
+                    configurable: uc,
+                },
+                PropertyDescriptor::Data {
+                    value: f,
+                    writable: fw,
+                    enumerable: fe,
+                    configurable: fc,
+                },
+            ) => (uw, ue, uc) == (fw, fe, fc) && self.same_value(u, f),
+            (
+                PropertyDescriptor::Accessor {
+                    get: ug,
+                    set: us,
+                    enumerable: ue,
+                    configurable: uc,
+                },
+                PropertyDescriptor::Accessor {
+                    get: fg,
+                    set: fs,
+                    enumerable: fe,
+                    configurable: fc,
+                },
+            ) => {
+                (ue, uc) == (fe, fc)
+                    && self.same_accessor(ug.as_ref(), fg.as_ref())
+                    && self.same_accessor(us.as_ref(), fs.as_ref())
+            }
+            _ => false,
+        }
+    }
+
+    fn symbol(&mut self, symbol: &Symbol) -> SymbolRef {
+        if let Some(name) = symbol.static_name() {
+            return SymbolRef::WellKnown(YSString::new_static(name));
+        }
+
+        if symbol.is_registered() {
+            return SymbolRef::Registered(YSString::from(symbol.as_str().to_owned()));
+        }
+
+        let next = self.symbols.len() as u32;
+
+        let id = *self.symbol_ids.entry(symbol.clone()).or_insert(next);
+
+        if id == next {
+            self.symbols
+                .push(YSString::from(symbol.as_str().to_owned()));
+        }
+
+        SymbolRef::Local(id)
+    }
+
+    fn key(&mut self, key: &PropertyKey) -> Key {
+        match key {
+            PropertyKey::String(s) => Key::String(s.clone()),
+            PropertyKey::Symbol(symbol) => Key::Symbol(self.symbol(symbol)),
+        }
+    }
+
+    /// A reference to `obj`, which is captured later if it isn't a builtin.
+    /// `loc` is where it was found, for errors.
+    fn obj(&mut self, obj: &ObjectHandle, loc: &str) -> ObjRef {
+        if let Some(id) = self.builtins.user(obj) {
+            return ObjRef::Builtin(id);
+        }
+
+        if let Some(id) = self.node_ids.get(obj) {
+            return ObjRef::Node(*id);
+        }
+
+        let id = self.nodes.len() as u32;
+
+        self.node_ids.insert(obj.clone(), id);
+        self.nodes.push(Node {
+            kind: NodeKind::Object,
+            prototype: None,
+            properties: Vec::new(),
+            integrity: Integrity::Extensible,
+        });
+        self.pending_nodes
+            .push_back((obj.clone(), id, loc.to_owned()));
+
+        ObjRef::Node(id)
+    }
+
+    fn proto(&mut self, proto: &ObjectOrNull, loc: &str) -> Option<ObjRef> {
+        match proto {
+            ObjectOrNull::Object(obj) => Some(self.obj(obj, loc)),
+            ObjectOrNull::Null => None,
+        }
+    }
+
+    fn val(&mut self, value: &Value, loc: &str) -> Val {
+        match value {
+            Value::Undefined => Val::Undefined,
+            Value::Null => Val::Null,
+            Value::Boolean(b) => Val::Bool(*b),
+            Value::Number(n) => Val::Number(*n),
+            Value::String(s) => Val::String(s.clone()),
+            Value::BigInt(b) => Val::BigInt(b.to_signed_bytes_le()),
+            Value::Symbol(symbol) => Val::Symbol(self.symbol(symbol)),
+            Value::Object(obj) => Val::Object(self.obj(obj, loc)),
+        }
+    }
+
+    fn prop(&mut self, desc: &PropertyDescriptor, loc: &str) -> Prop {
+        match desc {
+            PropertyDescriptor::Data {
+                value,
+                writable,
+                enumerable,
+                configurable,
+            } => Prop::Data {
+                value: self.val(value, loc),
+                attributes: attribute_bits(*writable, *enumerable, *configurable),
+            },
+            PropertyDescriptor::Accessor {
+                get,
+                set,
+                enumerable,
+                configurable,
+            } => Prop::Accessor {
+                get: get.as_ref().map(|get| self.obj(get, loc)),
+                set: set.as_ref().map(|set| self.obj(set, loc)),
+                attributes: attribute_bits(false, *enumerable, *configurable),
+            },
+        }
+    }
+
+    fn scope(&mut self, scope: &Gc<RefCell<ScopeInternal>>, loc: &str) -> u32 {
+        if let Some(id) = self.scope_ids.get(&scope.ptr_id()) {
+            return *id;
+        }
+
+        let id = self.scopes.len() as u32;
+
+        self.scope_ids.insert(scope.ptr_id(), id);
+        self.scopes.push(ScopeNode {
+            parent: None,
+            variables: ScopeVariables::Variables(Vec::new()),
+            hoisted: Vec::new(),
+            labels: Vec::new(),
+            last_label_is_current: false,
+            state: 0,
+            this: Val::Undefined,
+            new_target: Val::Undefined,
+            file: None,
+        });
+        self.pending_scopes
+            .push_back((Gc::clone(scope), id, loc.to_owned()));
+
+        id
+    }
+
+    fn node(&mut self, obj: &ObjectHandle, loc: &str, realm: &mut Realm) -> Res<Node> {
+        // not `downcast`, wrappers like classes downcast to their inner object
+        let ty = obj.object_type_id();
+
+        let kind = if ty == TypeId::of::<Object>() {
+            NodeKind::Object
+        } else if ty == TypeId::of::<Array>()
+            && let Some(array) = obj.downcast::<Array>()
+        {
+            NodeKind::Array {
+                len: array.len() as u64,
+            }
+        } else if obj.is_callable() {
+            let func = self
+                .hook
+                .as_ref()
+                .and_then(|hook| hook.snapshot_function(obj))
+                .ok_or_else(|| {
+                    Error::new_error(format!(
+                        "can't snapshot the function {} (found at {loc})",
+                        obj.name()
+                    ))
+                })?;
+
+            let scope_loc = format!("the scope of {loc}");
+
+            NodeKind::Function {
+                code: func.code,
+                scope: func
+                    .scope
+                    .map(|scope| self.scope(scope.internal(), &scope_loc)),
+                values: func
+                    .values
+                    .iter()
+                    .map(|value| self.val(value, &scope_loc))
+                    .collect(),
+            }
+        } else {
+            return Err(Error::new_error(format!(
+                "can't snapshot {} objects (found at {loc})",
+                short_class_name(obj)
+            )));
+        };
+
+        let is_array = matches!(kind, NodeKind::Array { .. });
+        let mut properties = Vec::new();
+
+        for key in obj.keys(realm)? {
+            if is_array && matches!(&key, PropertyKey::String(s) if s == "length") {
+                continue;
+            }
+
+            let Some(desc) = obj.get_property_descriptor(key.clone().into(), realm)? else {
+                continue;
+            };
+
+            let key = self.key(&key);
+            let mut prop_loc = loc.to_owned();
+            describe_key(&mut prop_loc, &key);
+
+            let prop = self.prop(&desc, &prop_loc);
+            properties.push((key, prop));
+        }
+
+        let proto = obj.prototype(realm)?;
+
+        Ok(Node {
+            kind,
+            prototype: self.proto(&proto, &format!("{loc}.[[Prototype]]")),
+            properties,
+            integrity: integrity(obj),
+        })
+    }
+
+    fn scope_node(&mut self, scope: &Gc<RefCell<ScopeInternal>>, loc: &str) -> Res<ScopeNode> {
+        let scope = scope.borrow()?;
+
+        let parent = scope.parent.as_ref().map(|parent| self.scope(parent, loc));
+
+        let variables = match &scope.variables {
+            ObjectOrVariables::Object(obj) => ScopeVariables::Object(self.obj(obj, loc)),
+            ObjectOrVariables::Variables(variables) => ScopeVariables::Variables(
+                variables
+                    .iter()
+                    .map(|(name, variable)| {
+                        let var_loc = format!("{name} in {loc}");
+
+                        let variable = match variable {
+                            VariableOrRef::Variable(var) => ScopeVariable::Value(
+                                self.val(&var.value, &var_loc),
+                                attribute_bits(
+                                    var.properties.is_writable(),
+                                    var.properties.is_enumerable(),
+                                    var.properties.is_configurable(),
+                                ),
+                            ),
+                            VariableOrRef::Ref(reference) => ScopeVariable::Reference {
+                                name: reference.name.clone(),
+                                object: self.obj(&reference.object, &var_loc),
+                            },
+                        };
+
+                        (name.clone(), variable)
+                    })
+                    .collect(),
+            ),
+        };
+
+        let mut hoisted = scope.hoisted.iter().cloned().collect::<Vec<_>>();
+        hoisted.sort_unstable();
+
+        Ok(ScopeNode {
+            parent,
+            variables,
+            hoisted,
+            labels: scope.available_labels.clone(),
+            last_label_is_current: scope.last_label_is_current,
+            state: scope.state.bits(),
+            this: self.val(&scope.this, loc),
+            new_target: self.val(&scope.new_target, loc),
+            file: scope.file.clone(),
+        })
+    }
+}
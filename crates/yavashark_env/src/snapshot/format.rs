@@ -0,0 +1,617 @@
+//! The binary encoding of a [`Snapshot`]: a header with the format and engine
+//! version followed by the sections of the snapshot. Integers are little
+//! endian, lengths are `u32` and every read is bounds checked, so a truncated
+//! or corrupt file is an error and never a panic.
+
+use super::{
+    Builtin, Change, Fixup, Integrity, Key, ModuleNode, Node, NodeKind, ObjRef, Prop, ScopeNode,
+    ScopeVariable, ScopeVariables, Segment, Snapshot, SymbolRef, Val,
+};
+use crate::{Error, Res};
+use std::path::{Path, PathBuf};
+use yavashark_string::YSString;
+
+const MAGIC: &[u8; 8] = b"YSSNAP\0\0";
+const FORMAT_VERSION: u32 = 1;
+const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn write(snapshot: &Snapshot) -> Vec<u8> {
+    let mut w = Writer::default();
+
+    w.buf.extend_from_slice(MAGIC);
+    w.u32(FORMAT_VERSION);
+    w.str(ENGINE_VERSION);
+
+    w.list(&snapshot.builtins, |w, builtin| {
+        w.u32(builtin.parent);
+        w.segment(&builtin.segment);
+    });
+    w.list(&snapshot.symbols, Writer::ys_str);
+    w.list(&snapshot.nodes, Writer::node);
+    w.list(&snapshot.scopes, Writer::scope);
+    w.list(&snapshot.fixups, |w, fixup| {
+        w.u32(fixup.target);
+        w.change(&fixup.change);
+    });
+    w.list(&snapshot.modules, |w, module| {
+        w.path(&module.path);
+        w.option(module.default.as_ref(), Writer::val);
+        w.obj(module.exports);
+    });
+
+    w.buf
+}
+
+pub fn read(bytes: &[u8]) -> Res<Snapshot> {
+    let mut r = Reader { bytes, pos: 0 };
+
+    if r.take(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err(Error::new("not a snapshot file"));
+    }
+
+    let format = r.u32()?;
+    let engine = r.str()?;
+
+    if format != FORMAT_VERSION || engine != ENGINE_VERSION {
+        return Err(Error::new_error(format!(
+            "the snapshot was written by version {engine} (format {format}) of the engine, this is version {ENGINE_VERSION} (format {FORMAT_VERSION})"
+        )));
+    }
+
+    let snapshot = Snapshot {
+        builtins: r.list(|r| {
+            Ok(Builtin {
+                parent: r.u32()?,
+                segment: r.segment()?,
+            })
+        })?,
+        symbols: r.list(Reader::ys_str)?,
+        nodes: r.list(Reader::node)?,
+        scopes: r.list(Reader::scope)?,
+        fixups: r.list(|r| {
+            Ok(Fixup {
+                target: r.u32()?,
+                change: r.change()?,
+            })
+        })?,
+        modules: r.list(|r| {
+            Ok(ModuleNode {
+                path: r.path()?,
+                default: r.option(Reader::val)?,
+                exports: r.obj()?,
+            })
+        })?,
+    };
+
+    if r.pos != bytes.len() {
+        return Err(corrupt());
+    }
+
+    Ok(snapshot)
+}
+
+pub fn corrupt() -> Error {
+    Error::new("the snapshot file is corrupt")
+}
+
+fn len_u32(len: usize) -> u32 {
+    u32::try_from(len).unwrap_or(u32::MAX)
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, n: u8) {
+        self.buf.push(n);
+    }
+
+    fn bool(&mut self, b: bool) {
+        self.u8(u8::from(b));
+    }
+
+    fn u32(&mut self, n: u32) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.buf.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(len_u32(bytes.len()));
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.bytes(s.as_bytes());
+    }
+
+    fn string(&mut self, s: &str) {
+        self.str(s);
+    }
+
+    /// Strings with lone surrogates are stored as UTF-16.
+    fn ys_str(&mut self, s: &YSString) {
+        if let Some(units) = s.as_utf16() {
+            self.u8(1);
+            self.u32(len_u32(units.len()));
+
+            for unit in units {
+                self.buf.extend_from_slice(&unit.to_le_bytes());
+            }
+        } else {
+            self.u8(0);
+            self.str(&s.as_str_lossy());
+        }
+    }
+
+    fn path(&mut self, path: &Path) {
+        self.str(&path.to_string_lossy());
+    }
+
+    fn list<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        self.u32(len_u32(items.len()));
+
+        for item in items {
+            f(self, item);
+        }
+    }
+
+    fn option<T>(&mut self, item: Option<T>, f: impl FnOnce(&mut Self, T)) {
+        match item {
+            Some(item) => {
+                self.u8(1);
+                f(self, item);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn obj(&mut self, obj: ObjRef) {
+        match obj {
+            ObjRef::Builtin(id) => {
+                self.u8(0);
+                self.u32(id);
+            }
+            ObjRef::Node(id) => {
+                self.u8(1);
+                self.u32(id);
+            }
+        }
+    }
+
+    fn symbol(&mut self, symbol: &SymbolRef) {
+        match symbol {
+            SymbolRef::WellKnown(name) => {
+                self.u8(0);
+                self.ys_str(name);
+            }
+            SymbolRef::Registered(name) => {
+                self.u8(1);
+                self.ys_str(name);
+            }
+            SymbolRef::Local(id) => {
+                self.u8(2);
+                self.u32(*id);
+            }
+        }
+    }
+
+    fn key(&mut self, key: &Key) {
+        match key {
+            Key::String(s) => {
+                self.u8(0);
+                self.ys_str(s);
+            }
+            Key::Symbol(symbol) => {
+                self.u8(1);
+                self.symbol(symbol);
+            }
+        }
+    }
+
+    fn segment(&mut self, segment: &Segment) {
+        match segment {
+            Segment::Value(key) => {
+                self.u8(0);
+                self.key(key);
+            }
+            Segment::Getter(key) => {
+                self.u8(1);
+                self.key(key);
+            }
+            Segment::Setter(key) => {
+                self.u8(2);
+                self.key(key);
+            }
+            Segment::Prototype => self.u8(3),
+        }
+    }
+
+    fn val(&mut self, val: &Val) {
+        match val {
+            Val::Undefined => self.u8(0),
+            Val::Null => self.u8(1),
+            Val::Bool(b) => {
+                self.u8(2);
+                self.bool(*b);
+            }
+            Val::Number(n) => {
+                self.u8(3);
+                self.u64(n.to_bits());
+            }
+            Val::String(s) => {
+                self.u8(4);
+                self.ys_str(s);
+            }
+            Val::BigInt(bytes) => {
+                self.u8(5);
+                self.bytes(bytes);
+            }
+            Val::Symbol(symbol) => {
+                self.u8(6);
+                self.symbol(symbol);
+            }
+            Val::Object(obj) => {
+                self.u8(7);
+                self.obj(*obj);
+            }
+        }
+    }
+
+    fn prop(&mut self, prop: &Prop) {
+        match prop {
+            Prop::Data { value, attributes } => {
+                self.u8(0);
+                self.val(value);
+                self.u8(*attributes);
+            }
+            Prop::Accessor {
+                get,
+                set,
+                attributes,
+            } => {
+                self.u8(1);
+                self.option(*get, Self::obj);
+                self.option(*set, Self::obj);
+                self.u8(*attributes);
+            }
+        }
+    }
+
+    fn integrity(&mut self, integrity: Integrity) {
+        self.u8(match integrity {
+            Integrity::Extensible => 0,
+            Integrity::NonExtensible => 1,
+            Integrity::Sealed => 2,
+            Integrity::Frozen => 3,
+        });
+    }
+
+    fn node(&mut self, node: &Node) {
+        match &node.kind {
+            NodeKind::Object => self.u8(0),
+            NodeKind::Array { len } => {
+                self.u8(1);
+                self.u64(*len);
+            }
+            NodeKind::Function {
+                code,
+                scope,
+                values,
+            } => {
+                self.u8(2);
+                self.bytes(code);
+                self.option(*scope, Self::u32);
+                self.list(values, Self::val);
+            }
+        }
+
+        self.option(node.prototype, Self::obj);
+        self.list(&node.properties, |w, (key, prop)| {
+            w.key(key);
+            w.prop(prop);
+        });
+        self.integrity(node.integrity);
+    }
+
+    fn scope(&mut self, scope: &ScopeNode) {
+        self.option(scope.parent, Self::u32);
+
+        match &scope.variables {
+            ScopeVariables::Object(obj) => {
+                self.u8(0);
+                self.obj(*obj);
+            }
+            ScopeVariables::Variables(variables) => {
+                self.u8(1);
+                self.list(variables, |w, (name, variable)| {
+                    w.str(name);
+
+                    match variable {
+                        ScopeVariable::Value(value, attributes) => {
+                            w.u8(0);
+                            w.val(value);
+                            w.u8(*attributes);
+                        }
+                        ScopeVariable::Reference { name, object } => {
+                            w.u8(1);
+                            w.ys_str(name);
+                            w.obj(*object);
+                        }
+                    }
+                });
+            }
+        }
+
+        self.list(&scope.hoisted, |w, s| w.string(s));
+        self.list(&scope.labels, |w, s| w.string(s));
+        self.bool(scope.last_label_is_current);
+        self.u8(scope.state);
+        self.val(&scope.this);
+        self.val(&scope.new_target);
+        self.option(scope.file.as_deref(), Self::path);
+    }
+
+    fn change(&mut self, change: &Change) {
+        match change {
+            Change::Define(key, prop) => {
+                self.u8(0);
+                self.key(key);
+                self.prop(prop);
+            }
+            Change::Delete(key) => {
+                self.u8(1);
+                self.key(key);
+            }
+            Change::Prototype(proto) => {
+                self.u8(2);
+                self.option(*proto, Self::obj);
+            }
+            Change::Integrity(integrity) => {
+                self.u8(3);
+                self.integrity(*integrity);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Res<&'a [u8]> {
+        let end = self.pos.checked_add(len).ok_or_else(corrupt)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or_else(corrupt)?;
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Res<[u8; N]> {
+        self.take(N)?.try_into().map_err(|_| corrupt())
+    }
+
+    fn u8(&mut self) -> Res<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn bool(&mut self) -> Res<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn u32(&mut self) -> Res<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Res<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn len(&mut self) -> Res<usize> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn bytes(&mut self) -> Res<&'a [u8]> {
+        let len = self.len()?;
+
+        self.take(len)
+    }
+
+    fn str(&mut self) -> Res<&'a str> {
+        std::str::from_utf8(self.bytes()?).map_err(|_| corrupt())
+    }
+
+    fn string(&mut self) -> Res<String> {
+        self.str().map(ToOwned::to_owned)
+    }
+
+    fn ys_str(&mut self) -> Res<YSString> {
+        match self.u8()? {
+            0 => Ok(YSString::from(self.string()?)),
+            1 => {
+                let len = self.len()?;
+                let units = self
+                    .take(len.checked_mul(2).ok_or_else(corrupt)?)?
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]));
+
+                Ok(YSString::from_utf16_iter(units))
+            }
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn path(&mut self) -> Res<PathBuf> {
+        self.str().map(PathBuf::from)
+    }
+
+    fn list<T>(&mut self, mut f: impl FnMut(&mut Self) -> Res<T>) -> Res<Vec<T>> {
+        let len = self.len()?;
+        // every item is at least one byte, don't trust the length before
+        // reading them
+        let mut items = Vec::with_capacity(len.min(self.bytes.len() - self.pos));
+
+        for _ in 0..len {
+            items.push(f(self)?);
+        }
+
+        Ok(items)
+    }
+
+    fn option<T>(&mut self, f: impl FnOnce(&mut Self) -> Res<T>) -> Res<Option<T>> {
+        if self.bool()? {
+            f(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn obj(&mut self) -> Res<ObjRef> {
+        match self.u8()? {
+            0 => Ok(ObjRef::Builtin(self.u32()?)),
+            1 => Ok(ObjRef::Node(self.u32()?)),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn symbol(&mut self) -> Res<SymbolRef> {
+        match self.u8()? {
+            0 => Ok(SymbolRef::WellKnown(self.ys_str()?)),
+            1 => Ok(SymbolRef::Registered(self.ys_str()?)),
+            2 => Ok(SymbolRef::Local(self.u32()?)),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn key(&mut self) -> Res<Key> {
+        match self.u8()? {
+            0 => Ok(Key::String(self.ys_str()?)),
+            1 => Ok(Key::Symbol(self.symbol()?)),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn segment(&mut self) -> Res<Segment> {
+        match self.u8()? {
+            0 => Ok(Segment::Value(self.key()?)),
+            1 => Ok(Segment::Getter(self.key()?)),
+            2 => Ok(Segment::Setter(self.key()?)),
+            3 => Ok(Segment::Prototype),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn val(&mut self) -> Res<Val> {
+        Ok(match self.u8()? {
+            0 => Val::Undefined,
+            1 => Val::Null,
+            2 => Val::Bool(self.bool()?),
+            3 => Val::Number(f64::from_bits(self.u64()?)),
+            4 => Val::String(self.ys_str()?),
+            5 => Val::BigInt(self.bytes()?.to_vec()),
+            6 => Val::Symbol(self.symbol()?),
+            7 => Val::Object(self.obj()?),
+            _ => return Err(corrupt()),
+        })
+    }
+
+    fn prop(&mut self) -> Res<Prop> {
+        match self.u8()? {
+            0 => Ok(Prop::Data {
+                value: self.val()?,
+                attributes: self.u8()?,
+            }),
+            1 => Ok(Prop::Accessor {
+                get: self.option(Self::obj)?,
+                set: self.option(Self::obj)?,
+                attributes: self.u8()?,
+            }),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn integrity(&mut self) -> Res<Integrity> {
+        match self.u8()? {
+            0 => Ok(Integrity::Extensible),
+            1 => Ok(Integrity::NonExtensible),
+            2 => Ok(Integrity::Sealed),
+            3 => Ok(Integrity::Frozen),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn node(&mut self) -> Res<Node> {
+        let kind = match self.u8()? {
+            0 => NodeKind::Object,
+            1 => NodeKind::Array { len: self.u64()? },
+            2 => NodeKind::Function {
+                code: self.bytes()?.to_vec(),
+                scope: self.option(Self::u32)?,
+                values: self.list(Self::val)?,
+            },
+            _ => return Err(corrupt()),
+        };
+
+        Ok(Node {
+            kind,
+            prototype: self.option(Self::obj)?,
+            properties: self.list(|r| Ok((r.key()?, r.prop()?)))?,
+            integrity: self.integrity()?,
+        })
+    }
+
+    fn scope(&mut self) -> Res<ScopeNode> {
+        let parent = self.option(Self::u32)?;
+
+        let variables = match self.u8()? {
+            0 => ScopeVariables::Object(self.obj()?),
+            1 => ScopeVariables::Variables(self.list(|r| {
+                let name = r.string()?;
+
+                let variable = match r.u8()? {
+                    0 => ScopeVariable::Value(r.val()?, r.u8()?),
+                    1 => ScopeVariable::Reference {
+                        name: r.ys_str()?,
+                        object: r.obj()?,
+                    },
+                    _ => return Err(corrupt()),
+                };
+
+                Ok((name, variable))
+            })?),
+            _ => return Err(corrupt()),
+        };
+
+        Ok(ScopeNode {
+            parent,
+            variables,
+            hoisted: self.list(Self::string)?,
+            labels: self.list(Self::string)?,
+            last_label_is_current: self.bool()?,
+            state: self.u8()?,
+            this: self.val()?,
+            new_target: self.val()?,
+            file: self.option(Self::path)?,
+        })
+    }
+
+    fn change(&mut self) -> Res<Change> {
+        match self.u8()? {
+            0 => Ok(Change::Define(self.key()?, self.prop()?)),
+            1 => Ok(Change::Delete(self.key()?)),
+            2 => Ok(Change::Prototype(self.option(Self::obj)?)),
+            3 => Ok(Change::Integrity(self.integrity()?)),
+            _ => Err(corrupt()),
+        }
+    }
+}
@@ -0,0 +1,476 @@
+use super::builtins::{describe, static_symbol};
+use super::format::corrupt;
+use super::{
+    Change, Integrity, Key, NodeKind, ObjRef, Prop, ScopeVariable, ScopeVariables, Segment,
+    Snapshot, SnapshotFunction, SymbolRef, Val, attributes,
+};
+use crate::array::Array;
+use crate::realm::Eval;
+use crate::scope::{
+    Module, ObjectOrVariables, Scope, ScopeInternal, ScopeState, VariableOrRef, VariableReference,
+};
+use crate::value::{Attributes, PropertyDescriptor};
+use crate::{
+    Error, Object, ObjectHandle, ObjectOrNull, PropertyKey, Realm, Res, Symbol, Value, Variable,
+};
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+use rustc_hash::FxHashSet;
+use std::cell::RefCell;
+use std::rc::Rc;
+use yavashark_garbage::Gc;
+
+pub fn restore(snapshot: &Snapshot, realm: &mut Realm) -> Res {
+    let mut builtins = vec![None; snapshot.builtins.len() + 1];
+    builtins[0] = Some(realm.global.clone());
+
+    let mut restore = Restore {
+        snapshot,
+        hook: realm.eval_hook(),
+        builtins,
+        nodes: Vec::with_capacity(snapshot.nodes.len()),
+        creating: vec![false; snapshot.nodes.len()],
+        scopes: Vec::with_capacity(snapshot.scopes.len()),
+        symbols: snapshot
+            .symbols
+            .iter()
+            .map(|description| Symbol::new_str(&description.as_str_lossy()))
+            .collect(),
+    };
+
+    for node in &snapshot.nodes {
+        restore.nodes.push(match node.kind {
+            NodeKind::Object => Some(Object::with_proto(None)),
+            NodeKind::Array { .. } => Some(ObjectHandle::new(Array::new(ObjectOrNull::Null))),
+            NodeKind::Function { .. } => None,
+        });
+    }
+
+    for scope in &snapshot.scopes {
+        restore.scopes.push(Gc::new(RefCell::new(ScopeInternal {
+            parent: None,
+            variables: ObjectOrVariables::Variables(IndexMap::default()),
+            hoisted: scope.hoisted.iter().cloned().collect::<FxHashSet<_>>(),
+            available_labels: scope.labels.clone(),
+            last_label_is_current: scope.last_label_is_current,
+            state: ScopeState::from_bits(scope.state),
+            this: Value::Undefined,
+            new_target: Value::Undefined,
+            file: scope.file.clone(),
+        })));
+    }
+
+    for id in 0..snapshot.nodes.len() as u32 {
+        restore.node(id, realm)?;
+    }
+
+    for (id, node) in snapshot.nodes.iter().enumerate() {
+        let obj = restore.node(id as u32, realm)?;
+
+        if matches!(node.kind, NodeKind::Function { .. }) {
+            // the host created the function with properties of its own
+            for key in obj.keys(realm)? {
+                obj.delete_property(key.into(), realm)?;
+            }
+        }
+
+        for (key, prop) in &node.properties {
+            restore.define(&obj, key, prop, realm)?;
+        }
+
+        if let NodeKind::Array { len } = node.kind
+            && let Some(array) = obj.downcast::<Array>()
+        {
+            array.set_len(usize::try_from(len).map_err(|_| corrupt())?)?;
+        }
+
+        let proto = restore.proto(node.prototype, realm)?;
+        obj.set_prototype(proto, realm)?;
+
+        apply_integrity(&obj, node.integrity)?;
+    }
+
+    for (id, node) in snapshot.scopes.iter().enumerate() {
+        let parent = node
+            .parent
+            .map(|parent| restore.scope(parent).cloned())
+            .transpose()?;
+
+        let variables = match &node.variables {
+            ScopeVariables::Object(obj) => ObjectOrVariables::Object(restore.obj(*obj, realm)?),
+            ScopeVariables::Variables(variables) => {
+                let mut map = IndexMap::default();
+
+                for (name, variable) in variables {
+                    let variable = match variable {
+                        ScopeVariable::Value(value, bits) => {
+                            VariableOrRef::Variable(Variable::with_attributes(
+                                restore.val(value, realm)?,
+                                attributes(*bits),
+                            ))
+                        }
+                        ScopeVariable::Reference { name, object } => {
+                            VariableOrRef::Ref(VariableReference {
+                                name: name.clone(),
+                                object: restore.obj(*object, realm)?,
+                            })
+                        }
+                    };
+
+                    map.insert(name.clone(), variable);
+                }
+
+                ObjectOrVariables::Variables(map)
+            }
+        };
+
+        let this = restore.val(&node.this, realm)?;
+        let new_target = restore.val(&node.new_target, realm)?;
+
+        let mut scope = restore.scope(id as u32)?.borrow_mut()?;
+        scope.parent = parent;
+        scope.variables = variables;
+        scope.this = this;
+        scope.new_target = new_target;
+    }
+
+    // a fixup can replace an object that the path to another builtin goes
+    // through, so everything is resolved before the first one is applied
+    let mut fixups = Vec::with_capacity(snapshot.fixups.len());
+
+    for fixup in &snapshot.fixups {
+        let target = restore.builtin(fixup.target, realm)?;
+
+        let change = match &fixup.change {
+            Change::Define(key, prop) => {
+                Resolved::Define(restore.key(key, realm)?, restore.prop(prop, realm)?)
+            }
+            Change::Delete(key) => Resolved::Delete(restore.key(key, realm)?),
+            Change::Prototype(proto) => Resolved::Prototype(restore.proto(*proto, realm)?),
+            Change::Integrity(integrity) => Resolved::Integrity(*integrity),
+        };
+
+        fixups.push((target, change));
+    }
+
+    for (target, change) in fixups {
+        match change {
+            Resolved::Define(key, prop) => {
+                target.delete_property(key.clone().into(), realm)?;
+                define_resolved(&target, key, prop, realm)?;
+            }
+            Resolved::Delete(key) => {
+                target.delete_property(key.into(), realm)?;
+            }
+            Resolved::Prototype(proto) => target.set_prototype(proto, realm)?,
+            Resolved::Integrity(integrity) => apply_integrity(&target, integrity)?,
+        }
+    }
+
+    for module in &snapshot.modules {
+        let default = module
+            .default
+            .as_ref()
+            .map(|value| restore.val(value, realm))
+            .transpose()?;
+
+        let exports = restore.obj(module.exports, realm)?;
+
+        realm.env.modules.insert(
+            module.path.clone(),
+            Module {
+                default,
+                exports,
+                path: module.path.clone(),
+            },
+        );
+    }
+
+    Ok(())
+}
+
+fn apply_integrity(obj: &ObjectHandle, integrity: Integrity) -> Res {
+    match integrity {
+        Integrity::Extensible => Ok(()),
+        Integrity::NonExtensible => obj.prevent_extensions(),
+        Integrity::Sealed => obj.seal(),
+        Integrity::Frozen => obj.freeze(),
+    }
+}
+
+fn define_resolved(
+    obj: &ObjectHandle,
+    key: PropertyKey,
+    prop: PropertyDescriptor,
+    realm: &mut Realm,
+) -> Res {
+    match prop {
+        PropertyDescriptor::Data {
+            value,
+            writable,
+            enumerable,
+            configurable,
+        } => {
+            obj.define_property_attributes(
+                key.into(),
+                Variable::new_with_attributes(value, writable, enumerable, configurable),
+                realm,
+            )?;
+        }
+        PropertyDescriptor::Accessor {
+            get,
+            set,
+            enumerable,
+            configurable,
+        } => {
+            let attributes = Attributes::from_values(false, enumerable, configurable);
+
+            if get.is_none() && set.is_none() {
+                obj.define_empty_accessor(key.clone().into(), attributes, realm)?;
+            }
+
+            if let Some(get) = get {
+                obj.define_getter_attributes(key.clone().into(), get, attributes, realm)?;
+            }
+
+            if let Some(set) = set {
+                obj.define_setter_attributes(key.into(), set, attributes, realm)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A change to a builtin with the objects and symbols it refers to.
+enum Resolved {
+    Define(PropertyKey, PropertyDescriptor),
+    Delete(PropertyKey),
+    Prototype(ObjectOrNull),
+    Integrity(Integrity),
+}
+
+struct Restore<'a> {
+    snapshot: &'a Snapshot,
+    hook: Option<Rc<dyn Eval>>,
+    builtins: Vec<Option<ObjectHandle>>,
+    /// Functions are created on first use, objects up front.
+    nodes: Vec<Option<ObjectHandle>>,
+    creating: Vec<bool>,
+    scopes: Vec<Gc<RefCell<ScopeInternal>>>,
+    symbols: Vec<Symbol>,
+}
+
+impl Restore<'_> {
+    /// Looks up the builtin `id` by its path, the first time it's needed.
+    fn builtin(&mut self, id: u32, realm: &mut Realm) -> Res<ObjectHandle> {
+        if let Some(obj) = self.builtins.get(id as usize).ok_or_else(corrupt)? {
+            return Ok(obj.clone());
+        }
+
+        let entry = self
+            .snapshot
+            .builtins
+            .get(id as usize - 1)
+            .ok_or_else(corrupt)?;
+
+        if entry.parent >= id {
+            return Err(corrupt());
+        }
+
+        let parent = self.builtin(entry.parent, realm)?;
+
+        let obj = match &entry.segment {
+            Segment::Value(key) => {
+                let key = self.key(key, realm)?;
+
+                match parent.get_property_descriptor(key.into(), realm)? {
+                    Some(PropertyDescriptor::Data {
+                        value: Value::Object(obj),
+                        ..
+                    }) => Some(obj),
+                    _ => None,
+                }
+            }
+            Segment::Getter(key) | Segment::Setter(key) => {
+                let key = self.key(key, realm)?;
+                let getter = matches!(entry.segment, Segment::Getter(_));
+
+                match parent.get_property_descriptor(key.into(), realm)? {
+                    Some(PropertyDescriptor::Accessor { get, set, .. }) => {
+                        if getter {
+                            get
+                        } else {
+                            set
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            Segment::Prototype => match parent.prototype(realm)? {
+                ObjectOrNull::Object(obj) => Some(obj),
+                ObjectOrNull::Null => None,
+            },
+        };
+
+        let obj = obj.ok_or_else(|| {
+            Error::new_error(format!(
+                "{} doesn't exist in this realm, the snapshot was taken with a different setup",
+                describe(&self.snapshot.builtins, id)
+            ))
+        })?;
+
+        self.builtins[id as usize] = Some(obj.clone());
+
+        Ok(obj)
+    }
+
+    /// The object of node `id`, creating it if it is a function that doesn't
+    /// exist yet.
+    fn node(&mut self, id: u32, realm: &mut Realm) -> Res<ObjectHandle> {
+        if let Some(obj) = self.nodes.get(id as usize).ok_or_else(corrupt)? {
+            return Ok(obj.clone());
+        }
+
+        let NodeKind::Function {
+            code,
+            scope,
+            values,
+        } = &self.snapshot.nodes[id as usize].kind
+        else {
+            return Err(corrupt());
+        };
+
+        if self.creating[id as usize] {
+            return Err(Error::new(
+                "can't restore a function that holds on to itself",
+            ));
+        }
+
+        self.creating[id as usize] = true;
+
+        let scope = scope
+            .map(|scope| {
+                self.scope(scope)
+                    .map(|scope| Scope::from_internal(Gc::clone(scope)))
+            })
+            .transpose()?;
+
+        let values = values
+            .iter()
+            .map(|value| self.val(value, realm))
+            .collect::<Res<Vec<_>>>()?;
+
+        let hook = self
+            .hook
+            .clone()
+            .ok_or_else(|| Error::new("functions can't be restored in this realm"))?;
+
+        let func = hook.restore_function(
+            SnapshotFunction {
+                code: code.clone(),
+                scope,
+                values,
+            },
+            realm,
+        )?;
+
+        self.nodes[id as usize] = Some(func.clone());
+
+        Ok(func)
+    }
+
+    fn scope(&self, id: u32) -> Res<&Gc<RefCell<ScopeInternal>>> {
+        self.scopes.get(id as usize).ok_or_else(corrupt)
+    }
+
+    fn obj(&mut self, obj: ObjRef, realm: &mut Realm) -> Res<ObjectHandle> {
+        match obj {
+            ObjRef::Builtin(id) => self.builtin(id, realm),
+            ObjRef::Node(id) => self.node(id, realm),
+        }
+    }
+
+    fn proto(&mut self, proto: Option<ObjRef>, realm: &mut Realm) -> Res<ObjectOrNull> {
+        Ok(match proto {
+            Some(proto) => ObjectOrNull::Object(self.obj(proto, realm)?),
+            None => ObjectOrNull::Null,
+        })
+    }
+
+    fn symbol(&self, symbol: &SymbolRef, realm: &mut Realm) -> Res<Symbol> {
+        match symbol {
+            SymbolRef::WellKnown(name) => static_symbol(&name.as_str_lossy()).ok_or_else(corrupt),
+            SymbolRef::Registered(name) => {
+                let global = realm.global.clone();
+                let constructor = global.get("Symbol", realm)?;
+                let symbol_for = constructor.as_object()?.get("for", realm)?;
+
+                match symbol_for.call(realm, vec![Value::String(name.clone())], constructor)? {
+                    Value::Symbol(symbol) => Ok(symbol),
+                    _ => Err(Error::new("Symbol.for didn't return a symbol")),
+                }
+            }
+            SymbolRef::Local(id) => self.symbols.get(*id as usize).cloned().ok_or_else(corrupt),
+        }
+    }
+
+    fn key(&self, key: &Key, realm: &mut Realm) -> Res<PropertyKey> {
+        Ok(match key {
+            Key::String(s) => PropertyKey::String(s.clone()),
+            Key::Symbol(symbol) => PropertyKey::Symbol(self.symbol(symbol, realm)?),
+        })
+    }
+
+    fn val(&mut self, val: &Val, realm: &mut Realm) -> Res<Value> {
+        Ok(match val {
+            Val::Undefined => Value::Undefined,
+            Val::Null => Value::Null,
+            Val::Bool(b) => Value::Boolean(*b),
+            Val::Number(n) => Value::Number(*n),
+            Val::String(s) => Value::String(s.clone()),
+            Val::BigInt(bytes) => Value::BigInt(Rc::new(BigInt::from_signed_bytes_le(bytes))),
+            Val::Symbol(symbol) => Value::Symbol(self.symbol(symbol, realm)?),
+            Val::Object(obj) => Value::Object(self.obj(*obj, realm)?),
+        })
+    }
+
+    fn prop(&mut self, prop: &Prop, realm: &mut Realm) -> Res<PropertyDescriptor> {
+        Ok(match prop {
+            Prop::Data {
+                value,
+                attributes: bits,
+            } => {
+                let attributes = attributes(*bits);
+
+                PropertyDescriptor::Data {
+                    value: self.val(value, realm)?,
+                    writable: attributes.is_writable(),
+                    enumerable: attributes.is_enumerable(),
+                    configurable: attributes.is_configurable(),
+                }
+            }
+            Prop::Accessor {
+                get,
+                set,
+                attributes: bits,
+            } => {
+                let attributes = attributes(*bits);
+
+                PropertyDescriptor::Accessor {
+                    get: get.map(|get| self.obj(get, realm)).transpose()?,
+                    set: set.map(|set| self.obj(set, realm)).transpose()?,
+                    enumerable: attributes.is_enumerable(),
+                    configurable: attributes.is_configurable(),
+                }
+            }
+        })
+    }
+
+    fn define(&mut self, obj: &ObjectHandle, key: &Key, prop: &Prop, realm: &mut Realm) -> Res {
+        let key = self.key(key, realm)?;
+        let prop = self.prop(prop, realm)?;
+
+        define_resolved(obj, key, prop, realm)
+    }
+}
@@ -61,6 +61,12 @@ impl AsyncTaskQueue {
         realm.queue.queue.push(pinned);
     }
 
+    /// Moves all pending jobs of `other` to the end of this queue.
+    pub fn append(&mut self, other: &mut Self) {
+        self.microtasks.append(&mut other.microtasks);
+        self.queue.append(&mut other.queue);
+    }
+
     pub fn flush_microtasks(&mut self) -> Microtasks {
         Microtasks {
             queue: mem::take(&mut self.microtasks),
@@ -2,6 +2,7 @@ use crate::builtins::StringObj;
 use crate::error::Error;
 use crate::value::property_key::IntoPropertyKey;
 use crate::{GCd, ObjectHandle, PropertyKey, Realm, Res};
+pub use boxed::BoxedValue;
 pub use constructor::*;
 pub use conversion::*;
 pub use function::*;
@@ -22,6 +23,7 @@ use yavashark_garbage::GcRef;
 use yavashark_string::{ToYSString, YSString};
 
 mod bigint;
+mod boxed;
 mod constructor;
 mod conversion;
 mod function;
@@ -0,0 +1,373 @@
+use super::nan_v2::{JSBigInt, JSString, ValueInner};
+use super::{Symbol, Value};
+use crate::ObjectHandle;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::fmt::{Debug, Formatter};
+use std::mem::ManuallyDrop;
+use std::ptr::NonNull;
+use std::rc::Rc;
+use yavashark_string::YSString;
+
+/// Largest and smallest `BigInt` that is stored inline.
+const BIGINT48_MAX: i64 = (1 << 47) - 1;
+const BIGINT48_MIN: i64 = -(1 << 47);
+
+/// Strings up to this many bytes are stored inline.
+const INLINE_STRING_LEN: usize = 6;
+
+/// A [`Value`] in 8 bytes.
+///
+/// Numbers are stored as `f64` bits and every other value lives in the payload
+/// of a NaN, see [`ValueInner`] for the layout. Integral numbers that fit into
+/// an `i32` are stored as `Int32`, so arithmetic on them doesn't need to touch
+/// floats. Short ASCII strings and `BigInt`s that fit into 48 bits are stored
+/// inline, everything else is a pointer to a reference counted allocation that
+/// is owned by the `BoxedValue`.
+pub struct BoxedValue(ValueInner);
+
+const _ASSERT_SIZE: () = assert!(size_of::<BoxedValue>() == 8);
+
+impl BoxedValue {
+    pub const UNDEFINED: Self = Self(ValueInner::undefined());
+    pub const NULL: Self = Self(ValueInner::null());
+
+    #[must_use]
+    pub const fn from_int32(val: i32) -> Self {
+        Self(ValueInner::from_int32(val))
+    }
+
+    #[must_use]
+    pub const fn from_bool(val: bool) -> Self {
+        Self(ValueInner::from_bool(val))
+    }
+
+    #[must_use]
+    pub fn from_f64(val: f64) -> Self {
+        f64_to_int32(val).map_or_else(|| Self(ValueInner::from_f64(val)), Self::from_int32)
+    }
+
+    #[must_use]
+    pub fn from_string(val: YSString) -> Self {
+        if let Some(bytes) = inline_string(&val) {
+            return Self(ValueInner::from_inline_string(bytes));
+        }
+
+        Self(ValueInner::from_heap_string(into_raw(Rc::new(val))))
+    }
+
+    #[must_use]
+    pub fn from_symbol(val: Symbol) -> Self {
+        Self(ValueInner::from_symbol(into_raw(Rc::new(val))))
+    }
+
+    #[must_use]
+    pub fn from_object(val: ObjectHandle) -> Self {
+        Self(ValueInner::from_object(check_ptr(val.into_raw())))
+    }
+
+    #[must_use]
+    pub fn from_big_int(val: Rc<BigInt>) -> Self {
+        match val.to_i64() {
+            Some(int @ BIGINT48_MIN..=BIGINT48_MAX) => Self(ValueInner::from_inline_big_int(int)),
+            _ => Self(ValueInner::from_heap_big_int(into_raw(val))),
+        }
+    }
+
+    /// The number stored in this value, if it is an `Int32`.
+    #[must_use]
+    pub const fn as_int32(&self) -> Option<i32> {
+        self.0.as_int32()
+    }
+
+    /// The number stored in this value, regardless of its representation.
+    #[must_use]
+    pub fn as_number(&self) -> Option<f64> {
+        self.0
+            .as_int32()
+            .map_or_else(|| self.0.as_f64(), |int| Some(f64::from(int)))
+    }
+
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        self.0.as_bool()
+    }
+
+    #[must_use]
+    pub const fn is_undefined(&self) -> bool {
+        self.0.is_undefined()
+    }
+
+    #[must_use]
+    pub const fn is_null(&self) -> bool {
+        self.0.is_null()
+    }
+
+    #[must_use]
+    pub const fn is_object(&self) -> bool {
+        self.0.is_object()
+    }
+
+    /// A [`Value`] that shares the allocations of this one.
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let inner = self.0;
+
+        if let Some(int) = inner.as_int32() {
+            return Value::Number(f64::from(int));
+        }
+
+        if let Some(num) = inner.as_f64() {
+            return Value::Number(num);
+        }
+
+        if let Some(b) = inner.as_bool() {
+            return Value::Boolean(b);
+        }
+
+        if inner.is_null() {
+            return Value::Null;
+        }
+
+        // SAFETY: the pointers are owned by `self` and stay valid while it is
+        // borrowed, `ManuallyDrop` keeps us from releasing them
+        unsafe {
+            if let Some(obj) = inner.as_object() {
+                let obj = ManuallyDrop::new(ObjectHandle::from_raw(obj));
+
+                return Value::Object((*obj).clone());
+            }
+
+            if let Some(string) = inner.as_string() {
+                return Value::String(match string {
+                    JSString::Inline(bytes) => inline_to_ys_string(bytes),
+                    JSString::Heap(ptr) => borrow_rc::<YSString>(ptr).as_ref().clone(),
+                });
+            }
+
+            if let Some(sym) = inner.as_symbol() {
+                return Value::Symbol(borrow_rc::<Symbol>(sym).as_ref().clone());
+            }
+
+            if let Some(big) = inner.as_big_int() {
+                return Value::BigInt(match big {
+                    JSBigInt::Inline(int) => Rc::new(BigInt::from(int)),
+                    JSBigInt::Heap(ptr) => Rc::clone(&borrow_rc::<BigInt>(ptr)),
+                });
+            }
+        }
+
+        Value::Undefined
+    }
+
+    #[must_use]
+    pub fn into_value(self) -> Value {
+        self.to_value()
+    }
+}
+
+impl From<Value> for BoxedValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Self::NULL,
+            Value::Undefined => Self::UNDEFINED,
+            Value::Number(n) => Self::from_f64(n),
+            Value::String(s) => Self::from_string(s),
+            Value::Boolean(b) => Self::from_bool(b),
+            Value::Object(o) => Self::from_object(o),
+            Value::Symbol(s) => Self::from_symbol(s),
+            Value::BigInt(b) => Self::from_big_int(b),
+        }
+    }
+}
+
+impl From<BoxedValue> for Value {
+    fn from(value: BoxedValue) -> Self {
+        value.into_value()
+    }
+}
+
+impl From<i32> for BoxedValue {
+    fn from(value: i32) -> Self {
+        Self::from_int32(value)
+    }
+}
+
+impl Default for BoxedValue {
+    fn default() -> Self {
+        Self::UNDEFINED
+    }
+}
+
+impl Clone for BoxedValue {
+    fn clone(&self) -> Self {
+        let inner = self.0;
+
+        unsafe {
+            if let Some(obj) = inner.as_object() {
+                let obj = ManuallyDrop::new(ObjectHandle::from_raw(obj));
+                let _ = (*obj).clone().into_raw();
+            } else if let Some(ptr) = inner.as_heap_string() {
+                Rc::increment_strong_count(ptr.cast::<YSString>().as_ptr());
+            } else if let Some(ptr) = inner.as_symbol() {
+                Rc::increment_strong_count(ptr.cast::<Symbol>().as_ptr());
+            } else if let Some(ptr) = inner.as_heap_big_int() {
+                Rc::increment_strong_count(ptr.cast::<BigInt>().as_ptr());
+            }
+        }
+
+        Self(inner)
+    }
+}
+
+impl Drop for BoxedValue {
+    fn drop(&mut self) {
+        let inner = self.0;
+
+        unsafe {
+            if let Some(obj) = inner.as_object() {
+                drop(ObjectHandle::from_raw(obj));
+            } else if let Some(ptr) = inner.as_heap_string() {
+                Rc::decrement_strong_count(ptr.cast::<YSString>().as_ptr());
+            } else if let Some(ptr) = inner.as_symbol() {
+                Rc::decrement_strong_count(ptr.cast::<Symbol>().as_ptr());
+            } else if let Some(ptr) = inner.as_heap_big_int() {
+                Rc::decrement_strong_count(ptr.cast::<BigInt>().as_ptr());
+            }
+        }
+    }
+}
+
+impl Debug for BoxedValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.to_value(), f)
+    }
+}
+
+fn f64_to_int32(val: f64) -> Option<i32> {
+    #[allow(clippy::float_cmp)]
+    if val.fract() == 0.0
+        && (f64::from(i32::MIN)..=f64::from(i32::MAX)).contains(&val)
+        && !(val == 0.0 && val.is_sign_negative())
+    {
+        return Some(val as i32);
+    }
+
+    None
+}
+
+fn inline_string(val: &YSString) -> Option<[u8; INLINE_STRING_LEN]> {
+    if val.len() > INLINE_STRING_LEN {
+        return None;
+    }
+
+    let bytes = val.as_bytes()?;
+
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    let mut inline = [0; INLINE_STRING_LEN];
+    inline.get_mut(..bytes.len())?.copy_from_slice(bytes);
+
+    Some(inline)
+}
+
+fn inline_to_ys_string(bytes: [u8; INLINE_STRING_LEN]) -> YSString {
+    let len = bytes
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(INLINE_STRING_LEN);
+    let s = std::str::from_utf8(&bytes[..len]).unwrap_or_default();
+
+    YSString::new_inline(s).unwrap_or_else(|| YSString::from_ref(s))
+}
+
+/// # Safety
+/// `ptr` must come from [`into_raw`] with the same `T` and still be alive.
+unsafe fn borrow_rc<T>(ptr: NonNull<()>) -> ManuallyDrop<Rc<T>> {
+    ManuallyDrop::new(unsafe { Rc::from_raw(ptr.cast::<T>().as_ptr()) })
+}
+
+fn into_raw<T>(rc: Rc<T>) -> NonNull<()> {
+    // SAFETY: `Rc::into_raw` never returns a null pointer
+    let ptr = unsafe { NonNull::new_unchecked(Rc::into_raw(rc).cast_mut()) };
+
+    check_ptr(ptr.cast())
+}
+
+/// Pointers have to fit into the 48 bit payload of a NaN.
+fn check_ptr(ptr: NonNull<()>) -> NonNull<()> {
+    debug_assert!(
+        ptr.addr().get() >> 48 == 0,
+        "pointer does not fit into 48 bits"
+    );
+
+    ptr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(value: Value) -> Value {
+        BoxedValue::from(value).into_value()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(BoxedValue::from_f64(42.0).as_int32(), Some(42));
+        assert_eq!(BoxedValue::from_f64(-0.0).as_int32(), None);
+        assert_eq!(BoxedValue::from_f64(1.5).as_int32(), None);
+        assert_eq!(BoxedValue::from_f64(4_294_967_296.0).as_int32(), None);
+
+        assert_eq!(roundtrip(Value::Number(1.5)), Value::Number(1.5));
+        assert_eq!(roundtrip(Value::Number(-7.0)), Value::Number(-7.0));
+        assert!(matches!(roundtrip(Value::Number(-0.0)), Value::Number(n) if n.is_sign_negative()));
+        assert!(roundtrip(Value::Number(f64::NAN)).is_nan());
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(roundtrip(Value::Null), Value::Null);
+        assert_eq!(roundtrip(Value::Undefined), Value::Undefined);
+        assert_eq!(roundtrip(Value::Boolean(true)), Value::Boolean(true));
+        assert_eq!(roundtrip(Value::Boolean(false)), Value::Boolean(false));
+    }
+
+    #[test]
+    fn strings() {
+        for s in ["", "a", "luna!?", "longer string", "äöü"] {
+            assert_eq!(roundtrip(Value::string(s)), Value::string(s));
+        }
+
+        assert!(inline_string(&YSString::from_ref("luna!?")).is_some());
+        assert!(inline_string(&YSString::from_ref("seven!!")).is_none());
+    }
+
+    #[test]
+    fn big_ints() {
+        for n in [BigInt::from(0), BigInt::from(-42), BigInt::from(i64::MAX)] {
+            let value = Value::BigInt(Rc::new(n));
+
+            assert_eq!(roundtrip(value.copy()), value);
+        }
+    }
+
+    #[test]
+    fn heap_values_are_reference_counted() {
+        let big = Rc::new(BigInt::from(i64::MAX));
+        let boxed = BoxedValue::from_big_int(Rc::clone(&big));
+        assert_eq!(Rc::strong_count(&big), 2);
+
+        let cloned = boxed.clone();
+        assert_eq!(Rc::strong_count(&big), 3);
+
+        drop(boxed);
+        let value = cloned.into_value();
+        assert_eq!(Rc::strong_count(&big), 2);
+
+        drop(value);
+        assert_eq!(Rc::strong_count(&big), 1);
+    }
+}
@@ -155,7 +155,7 @@ mod bits {
     }
 
     pub const fn box_int32(val: i32) -> u64 {
-        (val as u64) | INT32_TAG
+        (val as u32 as u64) | INT32_TAG
     }
 
     pub const fn box_bool(val: bool) -> u64 {
@@ -572,6 +572,10 @@ mod tests {
         let value = ValueInner::from_int32(42);
         assert!(value.is_int32());
         assert_eq!(value.as_int32(), Some(42));
+
+        let negative = ValueInner::from_int32(-42);
+        assert!(negative.is_int32());
+        assert_eq!(negative.as_int32(), Some(-42));
     }
 
     #[test]
@@ -4,11 +4,12 @@ use crate::conversion::FromValueOutput;
 use crate::error::Error;
 use crate::value::property_key::IntoPropertyKey;
 use crate::{
-    GCd, InternalPropertyKey, ObjectHandle, PreHashedPropertyKey, PropertyKey, Realm, Res, Symbol,
-    ValueResult,
+    GCd, InternalPropertyKey, MutObject, ObjectHandle, PreHashedPropertyKey, PropertyKey, Realm,
+    Res, Symbol, ValueResult,
 };
 use indexmap::Equivalent;
 use std::any::TypeId;
+use std::cell::{Ref, RefMut};
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 use std::ops::{Deref, DerefMut};
@@ -665,6 +666,16 @@ pub trait Obj: Debug + 'static {
         None
     }
 
+    /// The ordinary property storage of this object, if all of its named
+    /// properties live there. Inline caches only work with these objects.
+    fn shaped_object(&self) -> Option<Ref<'_, MutObject>> {
+        None
+    }
+
+    fn shaped_object_mut(&self) -> Option<RefMut<'_, MutObject>> {
+        None
+    }
+
     fn is_extensible(&self) -> bool {
         true
     }
@@ -1023,7 +1034,7 @@ unsafe impl Collectable for BoxedObj {
         self.gc_refs()
     }
 
-    #[cfg(any(feature = "obj_dbg", feature = "obj_trace"))]
+    #[cfg(any(feature = "obj_dbg", feature = "obj_trace", feature = "heap_stats"))]
     fn trace_name(&self) -> &'static str {
         self.0.class_name()
     }
@@ -1748,6 +1759,19 @@ impl Object {
         Self(Gc::new(BoxedObj::new(Box::new(obj))))
     }
 
+    /// Consumes the handle without releasing it, see [`Gc::into_raw`].
+    #[must_use]
+    pub fn into_raw(self) -> NonNull<()> {
+        Gc::into_raw(self.0)
+    }
+
+    /// # Safety
+    /// `ptr` must come from [`Object::into_raw`] and may only be turned back once.
+    #[must_use]
+    pub const unsafe fn from_raw(ptr: NonNull<()>) -> Self {
+        Self(unsafe { Gc::from_raw(ptr) })
+    }
+
     pub fn to_string(&self, realm: &mut Realm) -> Res<YSString> {
         let Some(to_string) = self.get_opt("toString", realm)? else {
             if let Some(to_string_tag) = self.get_opt(Symbol::TO_STRING_TAG, realm)? {
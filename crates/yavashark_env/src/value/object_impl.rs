@@ -20,6 +20,12 @@ pub trait ObjectImpl: Debug + 'static {
     /// the returned object should NOT be a reference to self, but a reference to the object that is wrapped by self
     fn get_wrapped_object(&self) -> impl DerefMut<Target = impl MutObj>;
 
+    /// Like `get_wrapped_object`, but `None` instead of panicking while the
+    /// object is borrowed mutably - the garbage collector may ask for the
+    /// references of any object at any allocation
+    #[cfg(feature = "actual_gc")]
+    fn try_get_wrapped_object(&self) -> Option<impl Deref<Target = impl MutObj>>;
+
     fn get_inner(&self) -> impl Deref<Target = Self::Inner>;
 
     fn get_inner_mut(&self) -> impl DerefMut<Target = Self::Inner>;
@@ -305,7 +311,9 @@ pub trait ObjectImpl: Debug + 'static {
 
     #[cfg(feature = "actual_gc")]
     fn gc_refs(&self) -> Vec<GcRef<BoxedObj>> {
-        self.get_wrapped_object().gc_refs()
+        self.try_get_wrapped_object()
+            .map(|obj| obj.gc_refs())
+            .unwrap_or_default()
     }
 }
 
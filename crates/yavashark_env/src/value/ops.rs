@@ -2,6 +2,7 @@
 
 mod add;
 mod and;
+mod boxed;
 mod div;
 mod exp;
 mod mul;
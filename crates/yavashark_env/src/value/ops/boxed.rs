@@ -0,0 +1,66 @@
+use crate::value::BoxedValue;
+use crate::{Realm, Res};
+use std::cmp::Ordering;
+
+impl BoxedValue {
+    pub fn add(&self, other: &Self, realm: &mut Realm) -> Res<Self> {
+        if let (Some(left), Some(right)) = (self.as_int32(), other.as_int32())
+            && let Some(res) = left.checked_add(right)
+        {
+            return Ok(Self::from_int32(res));
+        }
+
+        if let (Some(left), Some(right)) = (self.as_number(), other.as_number()) {
+            return Ok(Self::from_f64(left + right));
+        }
+
+        self.to_value()
+            .add(&other.to_value(), realm)
+            .map(Self::from)
+    }
+
+    pub fn sub(&self, other: &Self, realm: &mut Realm) -> Res<Self> {
+        if let (Some(left), Some(right)) = (self.as_int32(), other.as_int32())
+            && let Some(res) = left.checked_sub(right)
+        {
+            return Ok(Self::from_int32(res));
+        }
+
+        if let (Some(left), Some(right)) = (self.as_number(), other.as_number()) {
+            return Ok(Self::from_f64(left - right));
+        }
+
+        self.to_value()
+            .sub(&other.to_value(), realm)
+            .map(Self::from)
+    }
+
+    pub fn mul(&self, other: &Self, realm: &mut Realm) -> Res<Self> {
+        // `0 * -1` is `-0`, which isn't an `Int32`
+        if let (Some(left), Some(right)) = (self.as_int32(), other.as_int32())
+            && let Some(res) = left.checked_mul(right).filter(|res| *res != 0)
+        {
+            return Ok(Self::from_int32(res));
+        }
+
+        if let (Some(left), Some(right)) = (self.as_number(), other.as_number()) {
+            return Ok(Self::from_f64(left * right));
+        }
+
+        self.to_value()
+            .mul(&other.to_value(), realm)
+            .map(Self::from)
+    }
+
+    pub fn relational_cmp(&self, other: &Self, realm: &mut Realm) -> Res<Option<Ordering>> {
+        if let (Some(left), Some(right)) = (self.as_int32(), other.as_int32()) {
+            return Ok(Some(left.cmp(&right)));
+        }
+
+        if let (Some(left), Some(right)) = (self.as_number(), other.as_number()) {
+            return Ok(left.partial_cmp(&right));
+        }
+
+        self.to_value().relational_cmp(&other.to_value(), realm)
+    }
+}
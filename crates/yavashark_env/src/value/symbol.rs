@@ -137,6 +137,15 @@ impl Symbol {
     pub const fn is_registered(&self) -> bool {
         self.is_registered
     }
+
+    /// The description of a symbol the engine creates at compile time, like
+    /// the well known symbols. These are the same in every realm.
+    pub(crate) const fn static_name(&self) -> Option<&'static str> {
+        match self.inner {
+            SymbolInner::Static(s) => Some(s),
+            SymbolInner::Str(_) => None,
+        }
+    }
 }
 
 impl Symbol {
@@ -49,7 +49,7 @@ pub unsafe trait CellCollectable<T: Collectable> {
     #[cfg(feature = "actual_gc")]
     fn get_refs(&self) -> Vec<GcRef<T>>;
 
-    #[cfg(feature = "easy_debug")]
+    #[cfg(any(feature = "easy_debug", feature = "heap_stats"))]
     #[must_use]
     fn trace_name(&self) -> &'static str {
         std::any::type_name::<Self>()
@@ -64,7 +64,7 @@ macro_rules! cell {
                 self.$lock().map(|x| x.get_refs()).unwrap_or_default()
             }
 
-            #[cfg(feature = "easy_debug")]
+            #[cfg(any(feature = "easy_debug", feature = "heap_stats"))]
             fn trace_name(&self) -> &'static str {
                 self.$lock().map(|x| x.trace_name()).unwrap_or("<unknown>")
             }
@@ -73,9 +73,9 @@ macro_rules! cell {
 }
 
 cell!(RefCell, try_borrow);
-cell!(StdRwLock, read);
+cell!(StdRwLock, try_read);
 cell!(RwLock, try_read);
-cell!(StdMutex, lock);
+cell!(StdMutex, try_lock);
 cell!(Mutex, try_lock);
 
 pub struct GcRefCellGuard<'a, T: CellCollectable<RefCell<T>>, V = T> {
@@ -0,0 +1,322 @@
+//! Whole-heap tracing collector.
+//!
+//! The reference counting in [`Gc`](crate::Gc) frees most objects as soon as
+//! they become unreachable and `shake_tree` catches simple cycles, but both
+//! rely on the `ref_to`/`ref_by` lists that are only updated through guards.
+//! A cycle created by mutating a value without a guard (a closure that ends up
+//! in its own scope, for example) is never noticed and leaks.
+//!
+//! This collector is the backup for those cases: every live `GcBox` is tracked
+//! here and once the allocation budget is used up, the whole heap is traced.
+//! The roots don't have to be known up front, a box is a root when it is
+//! flagged as one with [`Gc::root`](crate::Gc::root) or when it has more strong
+//! references than other boxes report through `get_refs` - in that case
+//! something outside the heap holds it, like the realm's globals, a scope
+//! kept on the Rust stack, a queued task or a register of the VM. Everything
+//! reachable from the roots is marked and the rest is swept, which reclaims
+//! every unreachable cycle.
+//!
+//! Values that can't report their references right now (because they are
+//! mutably borrowed) report none, which only keeps more boxes alive.
+//!
+//! Setting the `YAVASHARK_GC_STRESS` environment variable or calling
+//! [`set_stress`] collects on every allocation, which is slow but shakes out
+//! missing references in `get_refs` implementations.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ptr::NonNull;
+
+#[cfg(feature = "heap_stats")]
+use crate::heap;
+#[cfg(feature = "easy_debug")]
+use crate::trace::TRACER;
+use crate::{Collectable, DeallocBoxFn, DeallocFn, GcBox, GcRef};
+
+/// Number of allocations between two collections, unless more boxes survived
+/// the last one.
+pub const DEFAULT_BUDGET: usize = 16 * 1024;
+
+thread_local! {
+    static COLLECTOR: RefCell<Collector> = RefCell::new(Collector::new());
+}
+
+struct Collector {
+    boxes: HashMap<usize, Tracked>,
+    /// Allocations since the last collection.
+    allocated: usize,
+    threshold: usize,
+    budget: usize,
+    stress: bool,
+    collecting: bool,
+}
+
+impl Collector {
+    fn new() -> Self {
+        Self {
+            boxes: HashMap::new(),
+            allocated: 0,
+            threshold: DEFAULT_BUDGET,
+            budget: DEFAULT_BUDGET,
+            stress: std::env::var_os("YAVASHARK_GC_STRESS").is_some(),
+            collecting: false,
+        }
+    }
+}
+
+/// Type-erased handle to a live `GcBox`.
+#[derive(Clone, Copy)]
+struct Tracked {
+    ptr: NonNull<GcBox<()>>,
+    refs: RefsFn,
+    dealloc_value: DeallocFn,
+    dealloc_box: DeallocBoxFn,
+}
+
+type RefsFn = unsafe fn(NonNull<GcBox<()>>) -> Vec<NonNull<GcBox<()>>>;
+
+/// Asks the value for the boxes it references right now.
+unsafe fn current_refs<T: Collectable>(this: NonNull<GcBox<()>>) -> Vec<NonNull<GcBox<()>>> {
+    let value = unsafe { &*GcBox::value_ptr(this.cast::<GcBox<T>>()) };
+
+    value.get_refs().iter().map(GcRef::box_ptr).collect()
+}
+
+pub(crate) fn register<T: Collectable>(ptr: NonNull<GcBox<T>>) {
+    let tracked = Tracked {
+        ptr: ptr.cast(),
+        refs: current_refs::<T>,
+        dealloc_value: GcBox::<T>::deallocate_value,
+        dealloc_box: GcBox::<T>::deallocate,
+    };
+
+    _ = COLLECTOR.try_with(|collector| {
+        collector
+            .borrow_mut()
+            .boxes
+            .insert(ptr.as_ptr() as usize, tracked);
+    });
+}
+
+/// Called when the value of a `GcBox` is dropped, the box itself might live
+/// on for weak references.
+pub(crate) fn unregister<T: Collectable>(ptr: *const GcBox<T>) {
+    _ = COLLECTOR.try_with(|collector| {
+        if let Ok(mut collector) = collector.try_borrow_mut() {
+            collector.boxes.remove(&(ptr as usize));
+        }
+    });
+}
+
+/// Counts an allocation against the budget and collects when it is used up.
+pub(crate) fn allocated() {
+    let due = COLLECTOR
+        .try_with(|collector| {
+            let Ok(mut collector) = collector.try_borrow_mut() else {
+                return false;
+            };
+
+            collector.allocated += 1;
+
+            !collector.collecting
+                && (collector.stress || collector.allocated >= collector.threshold)
+        })
+        .unwrap_or(false);
+
+    if due {
+        collect();
+    }
+}
+
+/// Sets how many allocations may happen between two collections. The budget
+/// grows with the number of boxes that survived the last collection.
+pub fn set_budget(allocations: usize) {
+    _ = COLLECTOR.try_with(|collector| {
+        let mut collector = collector.borrow_mut();
+
+        collector.budget = allocations.max(1);
+        collector.threshold = collector.budget;
+    });
+}
+
+/// Collects on every allocation when enabled.
+pub fn set_stress(enabled: bool) {
+    _ = COLLECTOR.try_with(|collector| {
+        collector.borrow_mut().stress = enabled;
+    });
+}
+
+/// Number of boxes with a live value.
+#[must_use]
+pub fn live() -> usize {
+    COLLECTOR
+        .try_with(|collector| collector.borrow().boxes.len())
+        .unwrap_or_default()
+}
+
+/// Traces the whole heap and frees every box that isn't reachable from a
+/// root. Returns the number of freed boxes.
+#[allow(clippy::must_use_candidate)]
+pub fn collect() -> usize {
+    let Some(tracked) = COLLECTOR
+        .try_with(|collector| {
+            let mut collector = collector.try_borrow_mut().ok()?;
+
+            if collector.collecting {
+                return None;
+            }
+
+            collector.collecting = true;
+
+            Some(collector.boxes.values().copied().collect::<Vec<_>>())
+        })
+        .ok()
+        .flatten()
+    else {
+        return 0;
+    };
+
+    let garbage = unsafe { find_garbage(&tracked) };
+    drop(tracked);
+
+    unsafe {
+        sweep(&garbage);
+    }
+
+    #[cfg(feature = "heap_stats")]
+    heap::collected(garbage.len());
+
+    _ = COLLECTOR.try_with(|collector| {
+        let mut collector = collector.borrow_mut();
+
+        collector.collecting = false;
+        collector.allocated = 0;
+        collector.threshold = collector.budget.max(collector.boxes.len());
+    });
+
+    garbage.len()
+}
+
+/// Marks everything reachable from the roots and returns the rest.
+unsafe fn find_garbage(tracked: &[Tracked]) -> Vec<Tracked> {
+    let index = tracked
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.ptr.as_ptr() as usize, i))
+        .collect::<HashMap<_, _>>();
+
+    let edges = tracked
+        .iter()
+        .map(|t| {
+            let refs = unsafe { (t.refs)(t.ptr) };
+
+            refs.into_iter()
+                .filter_map(|r| index.get(&(r.as_ptr() as usize)).copied())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut internal = vec![0u32; tracked.len()];
+
+    for &target in edges.iter().flatten() {
+        internal[target] += 1;
+    }
+
+    let mut marked = vec![false; tracked.len()];
+    let mut stack = Vec::new();
+
+    for (i, t) in tracked.iter().enumerate() {
+        let this = t.ptr.as_ptr();
+
+        let (strong, flags) = unsafe { ((*this).refs.strong(), (*this).flags) };
+
+        // more internal references than strong ones means that `get_refs`
+        // reports something it doesn't own, we'd rather keep it than free it
+        if flags.is_root() || strong != internal[i] || flags.is_externally_dropped() {
+            marked[i] = true;
+            stack.push(i);
+        }
+    }
+
+    while let Some(i) = stack.pop() {
+        for &target in &edges[i] {
+            if !marked[target] {
+                marked[target] = true;
+                stack.push(target);
+            }
+        }
+    }
+
+    tracked
+        .iter()
+        .zip(marked)
+        .filter_map(|(t, marked)| (!marked).then_some(*t))
+        .collect()
+}
+
+/// Frees `garbage` like `shake_tree` does: first the boxes are detached from
+/// the reference lists, then the values are dropped and last the boxes are
+/// freed, so the drops never see a freed box.
+unsafe fn sweep(garbage: &[Tracked]) {
+    unsafe {
+        for g in garbage {
+            (*g.ptr.as_ptr()).flags.set_externally_dropped();
+        }
+
+        for g in garbage {
+            GcBox::<()>::nuke_refs(g.ptr);
+            detach_ref_by(g.ptr);
+        }
+
+        for g in garbage {
+            let this = g.ptr.as_ptr();
+
+            if (*this).flags.is_value_dropped() {
+                continue;
+            }
+
+            #[cfg(feature = "easy_debug")]
+            TRACER.remove((*this).refs.trace);
+
+            (g.dealloc_value)(g.ptr);
+            (*this).flags.set_value_dropped();
+
+            unregister(this);
+            #[cfg(feature = "heap_stats")]
+            heap::unregister(this);
+        }
+
+        for g in garbage {
+            let this = g.ptr.as_ptr();
+
+            if (*this).refs.weak() == 0 {
+                (g.dealloc_box)(g.ptr);
+            } else {
+                // the last weak reference frees the box
+                (*this).refs.clear_strong();
+            }
+        }
+    }
+}
+
+/// Removes `this_ptr` from the `ref_to` lists of the boxes that think they
+/// reference it, so they don't touch it after it is freed.
+unsafe fn detach_ref_by(this_ptr: NonNull<GcBox<()>>) {
+    unsafe {
+        let Some(ref_by) = (*this_ptr.as_ptr()).refs.read_ref_by() else {
+            return;
+        };
+
+        let ref_by = ref_by.iter().map(GcRef::box_ptr).collect::<Vec<_>>();
+
+        for r in ref_by {
+            if r == this_ptr {
+                continue;
+            }
+
+            if let Some(mut refs) = (*r.as_ptr()).refs.write_refs() {
+                refs.retain(|x| x.box_ptr() != this_ptr);
+            }
+        }
+    }
+}
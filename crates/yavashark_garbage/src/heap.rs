@@ -0,0 +1,506 @@
+//! Headless heap introspection.
+//!
+//! Every `GcBox` registers itself here while its value is alive, so the heap
+//! can be walked without a GUI: [`stats`] counts the live objects per type and
+//! [`snapshot`] captures the object graph, which can be exported in the V8
+//! `.heapsnapshot` format and opened in the memory tab of the devtools.
+//!
+//! Edges between objects are whatever [`Collectable::get_refs`] reports at the
+//! time of the snapshot, which is why `heap_stats` turns on `actual_gc`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::ptr::NonNull;
+
+use crate::{Collectable, GcBox};
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::default());
+}
+
+#[derive(Default)]
+struct Heap {
+    live: HashMap<usize, Allocation>,
+    allocations: u64,
+    frees: u64,
+    collections: u64,
+    collections_that_freed: u64,
+}
+
+struct Allocation {
+    /// Sequence number of the allocation, used as a stable node id.
+    seq: u64,
+    name: &'static str,
+    size: usize,
+    refs: RefsFn,
+}
+
+type RefsFn = unsafe fn(usize) -> Vec<usize>;
+
+/// Asks the value of the box at `ptr` for the boxes it references right now.
+unsafe fn current_refs<T: Collectable>(ptr: usize) -> Vec<usize> {
+    // SAFETY: the caller guarantees that `ptr` is a registered `GcBox<T>`
+    let value = unsafe { &*GcBox::value_ptr(NonNull::new_unchecked(ptr as *mut GcBox<T>)) };
+
+    value
+        .get_refs()
+        .iter()
+        .map(|r| r.box_ptr().as_ptr() as usize)
+        .collect()
+}
+
+pub(crate) fn register<T: Collectable>(ptr: NonNull<GcBox<T>>, name: &'static str) {
+    _ = HEAP.try_with(|heap| {
+        let mut heap = heap.borrow_mut();
+
+        heap.allocations += 1;
+        let seq = heap.allocations;
+
+        heap.live.insert(
+            ptr.as_ptr() as usize,
+            Allocation {
+                seq,
+                name,
+                size: size_of::<GcBox<T>>(),
+                refs: current_refs::<T>,
+            },
+        );
+    });
+}
+
+/// Called when the value of a `GcBox` is dropped, the box itself might live
+/// on for weak references.
+pub(crate) fn unregister<T: Collectable>(ptr: *const GcBox<T>) {
+    _ = HEAP.try_with(|heap| {
+        let mut heap = heap.borrow_mut();
+
+        if heap.live.remove(&(ptr as usize)).is_some() {
+            heap.frees += 1;
+        }
+    });
+}
+
+/// Called after the cycle collector ran and freed `freed` boxes.
+pub(crate) fn collected(freed: usize) {
+    _ = HEAP.try_with(|heap| {
+        let mut heap = heap.borrow_mut();
+
+        heap.collections += 1;
+
+        if freed > 0 {
+            heap.collections_that_freed += 1;
+        }
+    });
+}
+
+/// Counters about the heap of the current thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    pub live_objects: usize,
+    /// Shallow size of the live objects, including the `GcBox` header.
+    pub live_bytes: usize,
+    pub allocations: u64,
+    pub frees: u64,
+    /// How often the cycle collector ran.
+    pub collections: u64,
+    /// How many of the collections found something unreachable to free.
+    pub collections_that_freed: u64,
+    /// Live objects grouped by their `trace_name`, most bytes first.
+    pub by_type: Vec<TypeStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeStats {
+    pub name: &'static str,
+    pub count: usize,
+    pub bytes: usize,
+}
+
+#[must_use]
+pub fn stats() -> HeapStats {
+    HEAP.try_with(|heap| {
+        let heap = heap.borrow();
+
+        let mut by_type = HashMap::<&'static str, TypeStats>::new();
+
+        for alloc in heap.live.values() {
+            let ty = by_type.entry(alloc.name).or_insert(TypeStats {
+                name: alloc.name,
+                count: 0,
+                bytes: 0,
+            });
+
+            ty.count += 1;
+            ty.bytes += alloc.size;
+        }
+
+        let mut by_type = by_type.into_values().collect::<Vec<_>>();
+        by_type.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(b.name)));
+
+        HeapStats {
+            live_objects: heap.live.len(),
+            live_bytes: by_type.iter().map(|ty| ty.bytes).sum(),
+            allocations: heap.allocations,
+            frees: heap.frees,
+            collections: heap.collections,
+            collections_that_freed: heap.collections_that_freed,
+            by_type,
+        }
+    })
+    .unwrap_or_default()
+}
+
+impl Display for HeapStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "live objects:            {}", self.live_objects)?;
+        writeln!(f, "live bytes:              {}", self.live_bytes)?;
+        writeln!(f, "allocations:             {}", self.allocations)?;
+        writeln!(f, "frees:                   {}", self.frees)?;
+        writeln!(f, "collections:             {}", self.collections)?;
+        writeln!(
+            f,
+            "collections that freed:  {}",
+            self.collections_that_freed
+        )?;
+
+        if self.by_type.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:>10} {:>12}  type", "count", "bytes")?;
+
+        for ty in &self.by_type {
+            writeln!(f, "{:>10} {:>12}  {}", ty.count, ty.bytes, ty.name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A live object in a [`HeapSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotNode {
+    /// Stays the same for an object across snapshots.
+    pub id: u64,
+    pub name: &'static str,
+    pub size: usize,
+    /// Whether something outside of the heap holds a reference to the object.
+    pub root: bool,
+    /// Indices of the referenced nodes.
+    pub edges: Vec<usize>,
+}
+
+/// The graph of the live objects of the current thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapSnapshot {
+    pub nodes: Vec<SnapshotNode>,
+}
+
+#[must_use]
+pub fn snapshot() -> HeapSnapshot {
+    let Ok(mut live) = HEAP.try_with(|heap| {
+        heap.borrow()
+            .live
+            .iter()
+            .map(|(ptr, alloc)| (*ptr, alloc.seq, alloc.name, alloc.size, alloc.refs))
+            .collect::<Vec<_>>()
+    }) else {
+        return HeapSnapshot::default();
+    };
+
+    live.sort_by_key(|(_, seq, ..)| *seq);
+
+    let index = live
+        .iter()
+        .enumerate()
+        .map(|(i, (ptr, ..))| (*ptr, i))
+        .collect::<HashMap<_, _>>();
+
+    // `get_refs` may borrow the values or even allocate, so the heap must not
+    // be borrowed while asking for the edges
+    let mut nodes = live
+        .iter()
+        .map(|(ptr, seq, name, size, refs)| SnapshotNode {
+            id: seq * 2 + 1,
+            name,
+            size: *size,
+            root: false,
+            // SAFETY: the box was registered as the type `refs` was created
+            // for and nothing was freed since
+            edges: unsafe { refs(*ptr) }
+                .into_iter()
+                .filter_map(|r| index.get(&r).copied())
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut incoming = vec![0u32; nodes.len()];
+    for node in &nodes {
+        for edge in &node.edges {
+            incoming[*edge] += 1;
+        }
+    }
+
+    for ((ptr, ..), (node, incoming)) in live.iter().zip(nodes.iter_mut().zip(incoming)) {
+        let gc_box = *ptr as *const GcBox<()>;
+
+        // SAFETY: the box is registered, so it is alive, and the header
+        // has the same layout for every `T`
+        node.root = unsafe { (*gc_box).flags.is_root() || (*gc_box).refs.strong() > incoming };
+    }
+
+    HeapSnapshot { nodes }
+}
+
+const NODE_FIELDS: usize = 7;
+const NODE_TYPE_OBJECT: usize = 3;
+const NODE_TYPE_SYNTHETIC: usize = 9;
+const EDGE_TYPE_ELEMENT: usize = 1;
+
+impl HeapSnapshot {
+    /// Writes the snapshot in the V8 `.heapsnapshot` format. A synthetic
+    /// `(GC roots)` node references all roots.
+    pub fn write_json(&self, mut w: impl io::Write) -> io::Result<()> {
+        let mut strings = Strings::default();
+        let roots = strings.add("(GC roots)");
+
+        let root_edges = self.nodes.iter().filter(|node| node.root).count();
+        let edge_count = root_edges + self.nodes.iter().map(|n| n.edges.len()).sum::<usize>();
+
+        let mut nodes = Vec::with_capacity(self.nodes.len() + 1);
+        nodes.push([NODE_TYPE_SYNTHETIC, roots, 1, 0, root_edges, 0, 0]);
+
+        for node in &self.nodes {
+            nodes.push([
+                NODE_TYPE_OBJECT,
+                strings.add(node.name),
+                node.id as usize,
+                node.size,
+                node.edges.len(),
+                0,
+                0,
+            ]);
+        }
+
+        // nodes are shifted by one because of the synthetic root
+        let to_node = |index: usize| (index + 1) * NODE_FIELDS;
+
+        let mut edges = Vec::with_capacity(edge_count);
+        edges.extend(
+            self.nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.root)
+                .enumerate()
+                .map(|(i, (index, _))| [EDGE_TYPE_ELEMENT, i + 1, to_node(index)]),
+        );
+
+        for node in &self.nodes {
+            edges.extend(
+                node.edges
+                    .iter()
+                    .enumerate()
+                    .map(|(i, edge)| [EDGE_TYPE_ELEMENT, i, to_node(*edge)]),
+            );
+        }
+
+        write!(
+            w,
+            r#"{{"snapshot":{{"meta":{{"node_fields":["type","name","id","self_size","edge_count","trace_node_id","detachedness"],"node_types":[["hidden","array","string","object","code","closure","regexp","number","native","synthetic","concatenated string","sliced string","symbol","bigint","object shape"],"string","number","number","number","number","number"],"edge_fields":["type","name_or_index","to_node"],"edge_types":[["context","element","property","internal","hidden","shortcut","weak"],"string_or_number","node"],"trace_function_info_fields":["function_id","name","script_name","script_id","line","column"],"trace_node_fields":["id","function_info_index","count","size","children"],"sample_fields":["timestamp_us","last_assigned_id"],"location_fields":["object_index","script_id","line","column"]}},"node_count":{},"edge_count":{},"trace_function_count":0}},"#,
+            nodes.len(),
+            edges.len(),
+        )?;
+
+        write!(w, "\n\"nodes\":[")?;
+        write_rows(&mut w, &nodes)?;
+        write!(w, "],\n\"edges\":[")?;
+        write_rows(&mut w, &edges)?;
+        write!(
+            w,
+            "],\n\"trace_function_infos\":[],\"trace_tree\":[],\"samples\":[],\"locations\":[],\n\"strings\":["
+        )?;
+
+        for (i, s) in strings.list.iter().enumerate() {
+            if i > 0 {
+                write!(w, ",")?;
+            }
+
+            write_json_string(&mut w, s)?;
+        }
+
+        writeln!(w, "]}}")
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut buf = Vec::new();
+        _ = self.write_json(&mut buf);
+
+        String::from_utf8(buf).unwrap_or_default()
+    }
+}
+
+fn write_rows<const N: usize>(w: &mut impl io::Write, rows: &[[usize; N]]) -> io::Result<()> {
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            writeln!(w, ",")?;
+        }
+
+        for (j, field) in row.iter().enumerate() {
+            if j > 0 {
+                write!(w, ",")?;
+            }
+
+            write!(w, "{field}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_json_string(w: &mut impl io::Write, s: &str) -> io::Result<()> {
+    write!(w, "\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => write!(w, "\\\"")?,
+            '\\' => write!(w, "\\\\")?,
+            '\n' => write!(w, "\\n")?,
+            c if c.is_control() => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{c}")?,
+        }
+    }
+
+    write!(w, "\"")
+}
+
+#[derive(Default)]
+struct Strings {
+    list: Vec<&'static str>,
+    index: HashMap<&'static str, usize>,
+}
+
+impl Strings {
+    fn add(&mut self, s: &'static str) -> usize {
+        *self.index.entry(s).or_insert_with(|| {
+            self.list.push(s);
+            self.list.len() - 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gc;
+
+    #[test]
+    fn counts_live_objects() {
+        let before = stats();
+
+        let a = Gc::new(String::from("a"));
+        let b = Gc::new(5u64);
+
+        let during = stats();
+        assert_eq!(during.live_objects, before.live_objects + 2);
+        assert_eq!(during.allocations, before.allocations + 2);
+        assert!(during.live_bytes > before.live_bytes);
+        assert!(
+            during
+                .by_type
+                .iter()
+                .any(|ty| ty.name == std::any::type_name::<u64>())
+        );
+
+        drop(a);
+        drop(b);
+
+        let after = stats();
+        assert_eq!(after.live_objects, before.live_objects);
+        assert_eq!(after.frees, before.frees + 2);
+    }
+
+    #[test]
+    fn clones_are_not_counted_twice() {
+        let before = stats();
+
+        let a = Gc::new(1u8);
+        let b = a.clone();
+
+        assert_eq!(stats().live_objects, before.live_objects + 1);
+
+        drop(a);
+        assert_eq!(stats().live_objects, before.live_objects + 1);
+
+        drop(b);
+        assert_eq!(stats().live_objects, before.live_objects);
+    }
+
+    #[test]
+    fn snapshot_json() {
+        let value = Gc::new(String::from("snapshot"));
+
+        let snapshot = snapshot();
+        let node = snapshot
+            .nodes
+            .iter()
+            .find(|node| node.name == std::any::type_name::<String>())
+            .unwrap();
+
+        assert!(node.root);
+        assert_eq!(node.id % 2, 1);
+
+        let json = snapshot.to_json();
+        assert!(json.starts_with(r#"{"snapshot":{"meta":"#));
+        assert!(json.contains(r#""(GC roots)""#));
+        assert!(json.contains(&format!("\"node_count\":{}", snapshot.nodes.len() + 1)));
+
+        drop(value);
+    }
+
+    #[test]
+    fn snapshot_edges() {
+        use crate::GcRef;
+
+        struct Holder(Gc<u32>);
+
+        unsafe impl Collectable for Holder {
+            fn get_refs(&self) -> Vec<GcRef<Self>> {
+                vec![self.0.get_untyped_ref()]
+            }
+        }
+
+        let held = Gc::new(7u32);
+        let holder = Gc::new(Holder(held.clone()));
+        drop(held);
+
+        let snapshot = snapshot();
+        let find = |name: &str| {
+            snapshot
+                .nodes
+                .iter()
+                .rposition(|node| node.name == name)
+                .unwrap()
+        };
+
+        let holder_node = find(std::any::type_name::<Holder>());
+        let held_node = find(std::any::type_name::<u32>());
+
+        assert_eq!(snapshot.nodes[holder_node].edges, [held_node]);
+        assert!(snapshot.nodes[holder_node].root);
+        assert!(!snapshot.nodes[held_node].root);
+
+        drop(holder);
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let mut buf = Vec::new();
+        write_json_string(&mut buf, "a\"b\\c\n\u{1}").unwrap();
+
+        assert_eq!(String::from_utf8(buf).unwrap(), r#""a\"b\\c\n\u0001""#);
+    }
+}
@@ -28,6 +28,10 @@ use crate::trace::{TRACER, TraceID};
 pub(crate) mod spin_lock;
 
 pub mod collectable;
+#[cfg(feature = "actual_gc")]
+pub mod collector;
+#[cfg(feature = "heap_stats")]
+pub mod heap;
 mod open;
 #[cfg(feature = "actual_gc")]
 pub(crate) mod tagged_ptr;
@@ -38,6 +42,7 @@ pub(crate) mod tagged_ptr;
 mod trace;
 #[cfg(feature = "trace")]
 mod trace_gui;
+pub mod usage;
 
 /// # Safety
 /// The implementer must guarantee that all references are valid and all references are returned by `get_refs`
@@ -56,7 +61,7 @@ pub unsafe trait Collectable: Sized {
         }
     }
 
-    #[cfg(feature = "easy_debug")]
+    #[cfg(any(feature = "easy_debug", feature = "heap_stats"))]
     fn trace_name(&self) -> &'static str {
         std::any::type_name::<Self>()
     }
@@ -543,6 +548,24 @@ impl<T: Collectable> Gc<T> {
         GcBox::value_ptr(self.inner)
     }
 
+    /// Consumes the `Gc` without decrementing the reference count. The pointer
+    /// has to be turned back into a `Gc` with [`Gc::from_raw`] to release it.
+    #[must_use]
+    pub fn into_raw(this: Self) -> NonNull<()> {
+        let this = ManuallyDrop::new(this);
+
+        this.inner.cast()
+    }
+
+    /// Takes back ownership of a pointer returned by [`Gc::into_raw`].
+    ///
+    /// # Safety
+    /// `ptr` must come from `Gc::<T>::into_raw` and may only be turned back once.
+    #[must_use]
+    pub const unsafe fn from_raw(ptr: NonNull<()>) -> Self {
+        Self { inner: ptr.cast() }
+    }
+
     #[cfg(feature = "actual_gc")]
     ///Just a reference without incrementing the reference count.
     #[must_use]
@@ -626,7 +649,10 @@ impl<T: Collectable> From<NonNull<GcBox<T>>> for Gc<T> {
 
 impl<T: Collectable> Gc<T> {
     pub fn new(value: T) -> Self {
-        #[cfg(feature = "easy_debug")]
+        #[cfg(feature = "actual_gc")]
+        collector::allocated();
+
+        #[cfg(any(feature = "easy_debug", feature = "heap_stats"))]
         let name = value.trace_name();
 
         #[cfg(feature = "actual_gc")]
@@ -645,6 +671,12 @@ impl<T: Collectable> Gc<T> {
         let gc_box = Box::new(gc_box);
         let gc_box = unsafe { NonNull::new_unchecked(Box::into_raw(gc_box)) }; //Unsafe, since we know that Box::into_raw will not return null
 
+        usage::allocated::<T>();
+        #[cfg(feature = "heap_stats")]
+        heap::register(gc_box, name);
+        #[cfg(feature = "actual_gc")]
+        collector::register(gc_box);
+
         #[cfg(feature = "actual_gc")]
         unsafe {
             for x in &ref_to {
@@ -665,7 +697,7 @@ impl<T: Collectable> Gc<T> {
     }
 
     pub fn root(value: T) -> Self {
-        #[cfg(feature = "easy_debug")]
+        #[cfg(any(feature = "easy_debug", feature = "heap_stats"))]
         let name = value.trace_name();
 
         let gc_box = GcBox {
@@ -681,6 +713,12 @@ impl<T: Collectable> Gc<T> {
         let gc_box = Box::new(gc_box);
         let gc_box = unsafe { NonNull::new_unchecked(Box::into_raw(gc_box)) }; //Unsafe, since we know that Box::into_raw will not return null
 
+        usage::allocated::<T>();
+        #[cfg(feature = "heap_stats")]
+        heap::register(gc_box, name);
+        #[cfg(feature = "actual_gc")]
+        collector::register(gc_box);
+
         Self { inner: gc_box }
     }
 
@@ -838,6 +876,12 @@ impl<T: Collectable> Refs<T> {
         self.strong.load(Ordering::Relaxed)
     }
 
+    /// Lets weak references know that the value is gone, even though the
+    /// collector freed it while other boxes still pointed to it.
+    fn clear_strong(&self) {
+        self.strong.store(0, Ordering::Relaxed);
+    }
+
     #[cfg(feature = "actual_gc")]
     fn read_refs(&self) -> Option<RwLockReadGuard<'_, Vec<GcRef<T>>>> {
         self.ref_to.spin_read()
@@ -1000,6 +1044,8 @@ impl<T: Collectable> GcBox<T> {
         unsafe {
             T::deallocate(NonNull::new_unchecked(Self::value_ptr(this)).cast());
         }
+
+        usage::freed::<T>();
     }
 
     #[cfg(feature = "actual_gc")]
@@ -1021,6 +1067,9 @@ impl<T: Collectable> GcBox<T> {
                         (*r.box_ptr().as_ptr()).unmark();
                     }
 
+                    #[cfg(feature = "heap_stats")]
+                    heap::collected(0);
+
                     return;
                 }
 
@@ -1064,6 +1113,9 @@ impl<T: Collectable> GcBox<T> {
             for d in &drop {
                 Self::nuke(d.ptr);
             }
+
+            #[cfg(feature = "heap_stats")]
+            heap::collected(drop.len());
         }
     }
 
@@ -1148,6 +1200,9 @@ impl<T: Collectable> GcBox<T> {
                     TRACER.remove((*(*this).gc_box.as_ptr()).refs.trace);
                 }
                 ((*this).dealloc_value)((*this).gc_box);
+                #[cfg(feature = "heap_stats")]
+                heap::unregister((*this).gc_box.as_ptr());
+                collector::unregister((*this).gc_box.as_ptr());
             }
 
             (*(*this).gc_box.as_ptr()).flags.set_value_dropped();
@@ -1157,6 +1212,10 @@ impl<T: Collectable> GcBox<T> {
         unsafe {
             ManuallyDrop::drop(&mut (*this_ptr.as_ptr()).value);
             (*this_ptr.as_ptr()).flags.set_value_dropped();
+            usage::freed::<T>();
+            #[cfg(feature = "heap_stats")]
+            heap::unregister(this_ptr.as_ptr());
+            collector::unregister(this_ptr.as_ptr());
             #[cfg(feature = "easy_debug")]
             {
                 TRACER.remove((*this_ptr.as_ptr()).refs.trace);
@@ -1431,6 +1490,11 @@ impl<T: Collectable> Drop for GcBox<T> {
                     TRACER.remove(self.refs.trace);
                 }
             }
+            usage::freed::<T>();
+            #[cfg(feature = "heap_stats")]
+            heap::unregister(std::ptr::from_ref(self));
+            #[cfg(feature = "actual_gc")]
+            collector::unregister(std::ptr::from_ref(self));
             //we don't need to set the value dropped flag, since we are about to drop the complete GcBox
         }
     }
@@ -1479,6 +1543,11 @@ impl<T: Collectable> Drop for Gc<T> {
 
                 ManuallyDrop::drop(&mut (*self.inner.as_ptr()).value);
                 (*self.inner.as_ptr()).flags.set_value_dropped();
+                usage::freed::<T>();
+                #[cfg(feature = "heap_stats")]
+                heap::unregister(self.inner.as_ptr());
+                #[cfg(feature = "actual_gc")]
+                collector::unregister(self.inner.as_ptr());
 
                 #[cfg(feature = "actual_gc")]
                 let Some(refs) = (*self.inner.as_ptr()).refs.read_ref_by() else {
@@ -1523,6 +1592,23 @@ mod ref_count_tests {
         assert_eq!(second.strong(), 1);
         assert_eq!(&*second, "still alive");
     }
+
+    #[test]
+    fn live_bytes_follow_the_values() {
+        let before = crate::usage::live_bytes();
+
+        let first = Gc::new(String::from("counted"));
+        let second = first.clone();
+        let grown = crate::usage::live_bytes();
+
+        assert!(grown > before);
+
+        drop(first);
+        assert_eq!(crate::usage::live_bytes(), grown);
+
+        drop(second);
+        assert_eq!(crate::usage::live_bytes(), before);
+    }
 }
 
 #[cfg(all(test, feature = "actual_gc"))]
@@ -1926,4 +2012,90 @@ mod tests {
 
         assert_eq!(unsafe { NODES_LEFT }, 0);
     }
+
+    #[test]
+    fn collector_frees_unguarded_cycle() {
+        setup!();
+        {
+            let x = Node::add(5);
+            let y = Node::add_with_other(6, &x);
+
+            // without a guard the reference lists don't learn about the cycle
+            RefCell::borrow_mut(&x).other.push(y);
+        }
+
+        assert_eq!(unsafe { NODES_LEFT }, 2);
+        assert_eq!(collector::collect(), 2);
+        assert_eq!(unsafe { NODES_LEFT }, 0);
+    }
+
+    #[test]
+    fn collector_keeps_externally_held_cycle() {
+        setup!();
+
+        let keep = Node::add(5);
+        {
+            let x = Node::add_with_other(6, &keep);
+            RefCell::borrow_mut(&keep).other.push(x);
+        }
+
+        assert_eq!(collector::collect(), 0);
+        assert_eq!(unsafe { NODES_LEFT }, 2);
+        assert_eq!(RefCell::borrow(&keep).other[0].borrow().unwrap().data, 6);
+
+        drop(keep);
+
+        assert_eq!(collector::collect(), 2);
+        assert_eq!(unsafe { NODES_LEFT }, 0);
+    }
+
+    #[test]
+    fn collector_keeps_roots() {
+        setup!();
+
+        let root = setup!(root);
+        {
+            let x = Node::add_with_other(6, &root);
+            RefCell::borrow_mut(&root).other.push(x);
+        }
+
+        drop(root);
+
+        assert_eq!(collector::collect(), 0);
+        assert_eq!(unsafe { NODES_LEFT }, 2);
+    }
+
+    #[test]
+    fn collector_stress_collects_on_allocation() {
+        setup!();
+        collector::set_stress(true);
+        {
+            let x = Node::add(5);
+            RefCell::borrow_mut(&x).other.push(x.clone());
+        }
+
+        assert_eq!(unsafe { NODES_LEFT }, 1);
+
+        let _y = Node::add(6);
+        collector::set_stress(false);
+
+        assert_eq!(unsafe { NODES_LEFT }, 1);
+        assert_eq!(collector::live(), 1);
+    }
+
+    #[test]
+    fn collector_clears_weak_references() {
+        setup!();
+
+        let weak = {
+            let x = Node::add(5);
+            RefCell::borrow_mut(&x).other.push(x.clone());
+
+            x.downgrade()
+        };
+
+        assert_eq!(collector::collect(), 1);
+        assert_eq!(unsafe { NODES_LEFT }, 0);
+        assert!(weak.upgrade().is_none());
+    }
 }
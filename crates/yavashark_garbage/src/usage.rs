@@ -0,0 +1,168 @@
+//! Byte accounting of the live `GcBox`es.
+//!
+//! Unlike [`heap`](crate::heap) this is always enabled and only keeps a
+//! single counter per thread, so it is cheap enough to be checked by an
+//! embedder that wants to limit how much memory a script can allocate.
+//!
+//! [`live_bytes`] only counts the boxes themselves, memory the values allocate
+//! on their own (the buffer of a `Vec` for example) isn't. An embedder that
+//! installs a [`CountingAllocator`] gets all of it through [`heap_bytes`].
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{Collectable, GcBox};
+
+thread_local! {
+    static LIVE_BYTES: Cell<usize> = const { Cell::new(0) };
+    static ALLOCATED_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+/// Whether a [`CountingAllocator`] has allocated anything yet.
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// Bytes taken by the `GcBox`es of this thread whose value is still alive.
+#[must_use]
+pub fn live_bytes() -> usize {
+    LIVE_BYTES.try_with(Cell::get).unwrap_or_default()
+}
+
+/// Bytes allocated by this thread and not freed yet, if a
+/// [`CountingAllocator`] is the global allocator.
+///
+/// Memory that is freed by another thread than the one that allocated it is
+/// subtracted from the wrong thread.
+#[must_use]
+pub fn allocated_bytes() -> Option<usize> {
+    if !COUNTING.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let bytes = ALLOCATED_BYTES.try_with(Cell::get).unwrap_or_default();
+
+    Some(usize::try_from(bytes).unwrap_or_default())
+}
+
+/// What this thread's heap takes: [`allocated_bytes`] if it is counted,
+/// [`live_bytes`] otherwise.
+#[must_use]
+pub fn heap_bytes() -> usize {
+    allocated_bytes().unwrap_or_else(live_bytes)
+}
+
+pub(crate) fn allocated<T: Collectable>() {
+    _ = LIVE_BYTES.try_with(|bytes| bytes.set(bytes.get() + size_of::<GcBox<T>>()));
+}
+
+/// Called when the value of a `GcBox` is dropped, the box itself might live
+/// on for weak references, but that is only a few bytes.
+pub(crate) fn freed<T: Collectable>() {
+    _ = LIVE_BYTES.try_with(|bytes| bytes.set(bytes.get().saturating_sub(size_of::<GcBox<T>>())));
+}
+
+/// A global allocator that counts the bytes each thread allocates, see
+/// [`allocated_bytes`].
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator(System);
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingAllocator<A = System>(pub A);
+
+fn count(delta: isize) {
+    if !COUNTING.load(Ordering::Relaxed) {
+        COUNTING.store(true, Ordering::Relaxed);
+    }
+
+    // the thread local is const initialized and has no destructor, so this
+    // doesn't allocate and still works while the thread shuts down
+    _ = ALLOCATED_BYTES.try_with(|bytes| bytes.set(bytes.get().wrapping_add(delta)));
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.0.alloc(layout) };
+
+        if !ptr.is_null() {
+            count(layout.size().cast_signed());
+        }
+
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.0.alloc_zeroed(layout) };
+
+        if !ptr.is_null() {
+            count(layout.size().cast_signed());
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.dealloc(ptr, layout) };
+
+        count(-layout.size().cast_signed());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = unsafe { self.0.realloc(ptr, layout, new_size) };
+
+        if !new.is_null() {
+            count(new_size.cast_signed() - layout.size().cast_signed());
+        }
+
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gc;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator(System);
+
+    #[test]
+    fn counts_allocations_of_this_thread() {
+        // the test harness frees some of its memory on this thread, which
+        // could leave the count below zero
+        let ballast = vec![0u8; 1 << 16];
+        let before = allocated_bytes().unwrap();
+
+        let mut buf = Vec::<u8>::with_capacity(1024);
+        assert_eq!(allocated_bytes(), Some(before + 1024));
+
+        buf.reserve_exact(4096);
+        assert_eq!(allocated_bytes(), Some(before + buf.capacity()));
+
+        let grown = allocated_bytes().unwrap();
+
+        // spawning allocates a bit on both threads, but the buffer isn't ours
+        std::thread::spawn(|| drop(vec![0u8; 1 << 20]))
+            .join()
+            .unwrap();
+        assert!(allocated_bytes().unwrap().abs_diff(grown) < 1 << 16);
+
+        drop(buf);
+        assert!(allocated_bytes().unwrap().abs_diff(before) < 1 << 16);
+
+        drop(ballast);
+    }
+
+    #[test]
+    fn heap_bytes_include_what_values_own() {
+        let before = heap_bytes();
+
+        let value = Gc::new("x".repeat(1 << 16));
+
+        assert!(heap_bytes() >= before + (1 << 16));
+        assert!(live_bytes() < 1 << 16);
+
+        drop(value);
+    }
+}
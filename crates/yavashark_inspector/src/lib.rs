@@ -0,0 +1,1403 @@
+//! A server for the Chrome debugging protocol (CDP), for the tree-walk
+//! interpreter.
+//!
+//! The server thread accepts the WebSocket connection and forwards the
+//! messages, everything else happens on the thread running the script: the
+//! [`Inspector`] is the [`DebugHook`] of the realm, it answers commands at
+//! statement boundaries and blocks in the hook while the script is paused.
+//! Code that the inspector itself runs (evaluations, getters) never pauses.
+
+mod remote;
+mod scripts;
+mod server;
+mod ws;
+
+use crate::remote::{Remote, RemoteObjects, RemoteProperty};
+use crate::scripts::Scripts;
+use crate::server::{Client, Incoming, Target};
+use regress::Regex;
+use serde_json::{Map, Value as Json, json};
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{SystemTime, UNIX_EPOCH};
+use yavashark_env::builtins::random_uuid;
+use yavashark_env::debugger::{DebugHook, SourceSpan};
+use yavashark_env::error_obj::ErrorObj;
+use yavashark_env::realm::Eval;
+use yavashark_env::scope::Scope;
+use yavashark_env::{Error, Realm, Res, Value};
+use yavashark_interpreter::eval::InterpreterEval;
+
+pub const DEFAULT_PORT: u16 = 9229;
+
+/// How far `Runtime.getProperties` follows the prototype chain.
+const MAX_PROTOTYPES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    None,
+    Start,
+    Pause,
+    Into,
+    Over(usize),
+    Out(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExceptionPause {
+    None,
+    Uncaught,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Stay,
+    Resume,
+    Run,
+    Detached,
+}
+
+struct Frame {
+    name: String,
+    scope: Option<Scope>,
+    span: Option<SourceSpan>,
+    script: Option<usize>,
+}
+
+impl Frame {
+    const fn new(name: String) -> Self {
+        Self {
+            name,
+            scope: None,
+            span: None,
+            script: None,
+        }
+    }
+}
+
+enum BreakpointTarget {
+    Url(String),
+    UrlRegex(Regex),
+    Script(usize),
+}
+
+struct Breakpoint {
+    id: String,
+    target: BreakpointTarget,
+    line: usize,
+    column: usize,
+    condition: Option<String>,
+}
+
+impl Breakpoint {
+    fn matches(&self, index: usize, url: &str) -> bool {
+        match &self.target {
+            BreakpointTarget::Url(target) => target == url,
+            BreakpointTarget::UrlRegex(regex) => regex.find(url).is_some(),
+            BreakpointTarget::Script(target) => *target == index,
+        }
+    }
+}
+
+#[allow(clippy::struct_excessive_bools)]
+struct State {
+    client: Option<Client>,
+    scripts: Scripts,
+    /// The first frame is the top level code.
+    frames: Vec<Frame>,
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint: usize,
+    breakpoints_active: bool,
+    skip_pauses: bool,
+    step: Step,
+    exceptions: ExceptionPause,
+    try_depth: usize,
+    /// Set once an error was reported, so the statements it unwinds through
+    /// don't report it again.
+    exception_reported: bool,
+    /// Depth, statement and line of the last pause.
+    last_pause: Option<(usize, SourceSpan, usize)>,
+    /// Whether the current statement already paused.
+    stopped_here: bool,
+    paused: bool,
+    terminate: bool,
+    remote: RemoteObjects,
+    debugger_enabled: bool,
+    runtime_enabled: bool,
+    console_enabled: bool,
+}
+
+impl State {
+    fn detach(&mut self) {
+        self.client = None;
+        self.breakpoints.clear();
+        self.breakpoints_active = true;
+        self.skip_pauses = false;
+        self.step = Step::None;
+        self.exceptions = ExceptionPause::None;
+        self.remote.clear();
+        self.debugger_enabled = false;
+        self.runtime_enabled = false;
+        self.console_enabled = false;
+    }
+
+    /// Why the statement at `span` of the current frame should pause and the
+    /// breakpoints it hits, with their conditions.
+    fn should_pause(
+        &self,
+        span: SourceSpan,
+    ) -> (Option<&'static str>, Vec<(String, Option<String>)>) {
+        if self.client.is_none() || self.skip_pauses {
+            return (None, Vec::new());
+        }
+
+        let depth = self.frames.len();
+        let Some(frame) = self.frames.last() else {
+            return (None, Vec::new());
+        };
+
+        let script = frame.script.map(|index| (index, self.scripts.get(index)));
+        let position = script.map(|(_, script)| script.position(span.lo));
+
+        // a statement nested in the one we stopped at that starts on the same
+        // line, like the body of `if (a) return;`
+        if let (Some((d, last, line)), Some((current, _))) = (self.last_pause, position)
+            && d == depth
+            && last != span
+            && last.contains(&span)
+            && line == current
+        {
+            return (None, Vec::new());
+        }
+
+        let step = match self.step {
+            Step::None => None,
+            Step::Start => Some("Break on start"),
+            Step::Pause | Step::Into => Some("other"),
+            Step::Over(d) => (depth <= d).then_some("other"),
+            Step::Out(d) => (depth < d).then_some("other"),
+        };
+
+        let mut hit = Vec::new();
+
+        if self.breakpoints_active
+            && let (Some((index, script)), Some((line, _))) = (script, position)
+        {
+            for bp in &self.breakpoints {
+                if bp.line == line && bp.matches(index, &script.url) {
+                    hit.push((bp.id.clone(), bp.condition.clone()));
+                }
+            }
+        }
+
+        (step, hit)
+    }
+
+    /// Resolves the script of the current frame, returns its parsed event if
+    /// the client has to be told about it.
+    fn resolve_script(&mut self, scope: &Scope) -> Option<Json> {
+        let frame = self.frames.last_mut()?;
+
+        if frame.script.is_some() {
+            return None;
+        }
+
+        let path = scope.get_current_path().ok()?;
+        let (index, new) = self.scripts.load(&path)?;
+
+        frame.script = Some(index);
+
+        (new && self.debugger_enabled).then(|| self.scripts.get(index).parsed_event())
+    }
+
+    fn location(&self, frame: &Frame) -> Option<(Json, String)> {
+        let script = self.scripts.get(frame.script?);
+        let (line, column) = script.position(frame.span?.lo);
+
+        Some((
+            json!({
+                "scriptId": script.id,
+                "lineNumber": line,
+                "columnNumber": column,
+            }),
+            script.url.clone(),
+        ))
+    }
+}
+
+/// A frame as the client sees it, taken while the state isn't borrowed.
+struct FrameSnapshot {
+    index: usize,
+    name: String,
+    scope: Scope,
+    location: Json,
+    url: String,
+}
+
+pub struct Inspector {
+    state: RefCell<State>,
+    incoming: Receiver<Incoming>,
+    pending: Arc<AtomicBool>,
+    /// Set while the inspector handles commands, the code it runs doesn't
+    /// report to the hooks.
+    busy: Cell<bool>,
+    url: String,
+}
+
+impl Inspector {
+    /// Starts the server on localhost. `path` and `source` are the script
+    /// that is going to run.
+    pub fn listen(port: u16, path: &Path, source: &str) -> io::Result<Rc<Self>> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let addr = listener.local_addr()?;
+
+        let (tx, rx) = mpsc::channel();
+        let pending = Arc::new(AtomicBool::new(false));
+
+        let mut inspector = Self::new(rx, Arc::clone(&pending), path, source);
+
+        let target = Target {
+            id: random_uuid(),
+            title: path.display().to_string(),
+            url: inspector.state.get_mut().scripts.get(0).url.clone(),
+            addr,
+        };
+
+        inspector.url = target.ws_url();
+
+        server::spawn(listener, target, tx, pending);
+
+        Ok(Rc::new(inspector))
+    }
+
+    fn new(
+        incoming: Receiver<Incoming>,
+        pending: Arc<AtomicBool>,
+        path: &Path,
+        source: &str,
+    ) -> Self {
+        let mut scripts = Scripts::default();
+        scripts.add(path, source.to_owned());
+
+        let mut program = Frame::new(String::new());
+        program.script = Some(0);
+
+        Self {
+            state: RefCell::new(State {
+                client: None,
+                scripts,
+                frames: vec![program],
+                breakpoints: Vec::new(),
+                next_breakpoint: 1,
+                breakpoints_active: true,
+                skip_pauses: false,
+                step: Step::None,
+                exceptions: ExceptionPause::None,
+                try_depth: 0,
+                exception_reported: false,
+                last_pause: None,
+                stopped_here: false,
+                paused: false,
+                terminate: false,
+                remote: RemoteObjects::default(),
+                debugger_enabled: false,
+                runtime_enabled: false,
+                console_enabled: false,
+            }),
+            incoming,
+            pending,
+            busy: Cell::new(false),
+            url: String::new(),
+        }
+    }
+
+    /// The WebSocket URL clients connect to.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Blocks until a client connected and sent
+    /// `Runtime.runIfWaitingForDebugger`, so it can set its breakpoints
+    /// first. `scope` is where the script runs, with `brk` it pauses on its
+    /// first statement.
+    pub fn wait_for_debugger(&self, realm: &mut Realm, scope: &Scope, brk: bool) -> Res {
+        if let Some(program) = self.state.borrow_mut().frames.first_mut() {
+            program.scope = Some(scope.clone());
+        }
+
+        self.busy.set(true);
+
+        let result = loop {
+            let Ok(incoming) = self.incoming.recv() else {
+                break Ok(());
+            };
+
+            match self.receive(realm, incoming) {
+                Ok(Flow::Run) => break Ok(()),
+                Err(e) => break Err(e),
+                Ok(_) => {}
+            }
+        };
+
+        self.busy.set(false);
+
+        if brk {
+            self.state.borrow_mut().step = Step::Start;
+        }
+
+        result
+    }
+
+    fn send(&self, message: &Json) {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(client) = &mut state.client
+            && client(&message.to_string()).is_err()
+        {
+            state.detach();
+        }
+    }
+
+    fn event(&self, method: &str, params: Json) {
+        let mut message = Map::new();
+        message.insert("method".to_owned(), method.into());
+        message.insert("params".to_owned(), params);
+
+        self.send(&Json::Object(message));
+    }
+
+    /// Handles the messages that arrived while the script was running.
+    fn drain(&self, realm: &mut Realm) -> Res {
+        self.busy.set(true);
+
+        let mut result = Ok(());
+
+        while let Ok(incoming) = self.incoming.try_recv() {
+            if let Err(e) = self.receive(realm, incoming) {
+                result = Err(e);
+                break;
+            }
+        }
+
+        self.busy.set(false);
+
+        result
+    }
+
+    fn receive(&self, realm: &mut Realm, incoming: Incoming) -> Res<Flow> {
+        match incoming {
+            Incoming::Connected(client) => {
+                let mut state = self.state.borrow_mut();
+                state.detach();
+                state.client = Some(client);
+
+                Ok(Flow::Stay)
+            }
+            Incoming::Disconnected => {
+                self.state.borrow_mut().detach();
+
+                Ok(Flow::Detached)
+            }
+            Incoming::Message(text) => self.command(realm, &text),
+        }
+    }
+
+    fn command(&self, realm: &mut Realm, text: &str) -> Res<Flow> {
+        let Ok(message) = serde_json::from_str::<Json>(text) else {
+            return Ok(Flow::Stay);
+        };
+
+        let id = message["id"].clone();
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        let (result, flow) = match self.dispatch(realm, method, params) {
+            Ok(reply) => reply,
+            Err(e) if e.is_terminated() => return Err(e),
+            Err(e) => (Err(e.to_string()), Flow::Stay),
+        };
+
+        match result {
+            Ok(result) => self.send(&json!({ "id": id, "result": result })),
+            Err(message) => self.send(&json!({
+                "id": id,
+                "error": { "code": -32000, "message": message },
+            })),
+        }
+
+        Ok(flow)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn dispatch(
+        &self,
+        realm: &mut Realm,
+        method: &str,
+        params: &Json,
+    ) -> Res<(Result<Json, String>, Flow)> {
+        let ok = |result| Ok((Ok(result), Flow::Stay));
+
+        match method {
+            "Runtime.enable" => {
+                self.state.borrow_mut().runtime_enabled = true;
+                self.event(
+                    "Runtime.executionContextCreated",
+                    json!({
+                        "context": {
+                            "id": 1,
+                            "origin": "",
+                            "name": "yavashark",
+                            "uniqueId": "1",
+                            "auxData": { "isDefault": true },
+                        },
+                    }),
+                );
+
+                ok(json!({}))
+            }
+            "Runtime.disable" => {
+                self.state.borrow_mut().runtime_enabled = false;
+                ok(json!({}))
+            }
+            "Runtime.runIfWaitingForDebugger" => Ok((Ok(json!({})), Flow::Run)),
+            "Runtime.evaluate" => {
+                let scope = self.frame_scope(realm, 0);
+                ok(self.evaluate(realm, &scope, params)?)
+            }
+            "Runtime.callFunctionOn" => Ok((self.call_function_on(realm, params)?, Flow::Stay)),
+            "Runtime.getProperties" => Ok((self.get_properties(realm, params)?, Flow::Stay)),
+            "Runtime.globalLexicalScopeNames" => ok(json!({ "names": [] })),
+            "Runtime.getIsolateId" => ok(json!({ "id": "1" })),
+            "Runtime.terminateExecution" => {
+                self.state.borrow_mut().terminate = true;
+                Ok((Ok(json!({})), Flow::Resume))
+            }
+            "Console.enable" => {
+                self.state.borrow_mut().console_enabled = true;
+                ok(json!({}))
+            }
+            "Console.disable" => {
+                self.state.borrow_mut().console_enabled = false;
+                ok(json!({}))
+            }
+            "Runtime.releaseObject"
+            | "Runtime.releaseObjectGroup"
+            | "Runtime.discardConsoleEntries"
+            | "Runtime.setAsyncCallStackDepth"
+            | "Runtime.setCustomObjectFormatterEnabled"
+            | "Runtime.setMaxCallStackSizeToCapture"
+            | "Runtime.compileScript"
+            | "Runtime.addBinding"
+            | "Debugger.setAsyncCallStackDepth"
+            | "Debugger.setBlackboxPatterns"
+            | "Debugger.setBlackboxExecutionContexts"
+            | "Debugger.setBlackboxedRanges"
+            | "Profiler.enable"
+            | "Profiler.disable"
+            | "HeapProfiler.enable"
+            | "HeapProfiler.disable" => ok(json!({})),
+            "Debugger.enable" => {
+                let scripts = {
+                    let mut state = self.state.borrow_mut();
+                    state.debugger_enabled = true;
+
+                    state
+                        .scripts
+                        .iter()
+                        .map(|(_, script)| script.parsed_event())
+                        .collect::<Vec<_>>()
+                };
+
+                for script in scripts {
+                    self.event("Debugger.scriptParsed", script);
+                }
+
+                ok(json!({ "debuggerId": "yavashark" }))
+            }
+            "Debugger.disable" => {
+                let mut state = self.state.borrow_mut();
+                state.debugger_enabled = false;
+                state.step = Step::None;
+
+                ok(json!({}))
+            }
+            "Debugger.setBreakpointByUrl" => Ok((self.set_breakpoint_by_url(params), Flow::Stay)),
+            "Debugger.setBreakpoint" => Ok((self.set_breakpoint(params), Flow::Stay)),
+            "Debugger.removeBreakpoint" => {
+                let id = params["breakpointId"].as_str().unwrap_or_default();
+                self.state.borrow_mut().breakpoints.retain(|bp| bp.id != id);
+
+                ok(json!({}))
+            }
+            "Debugger.setBreakpointsActive" => {
+                self.state.borrow_mut().breakpoints_active =
+                    params["active"].as_bool().unwrap_or(true);
+
+                ok(json!({}))
+            }
+            "Debugger.setSkipAllPauses" => {
+                self.state.borrow_mut().skip_pauses = params["skip"].as_bool().unwrap_or(false);
+
+                ok(json!({}))
+            }
+            "Debugger.getPossibleBreakpoints" => ok(json!({ "locations": [] })),
+            "Debugger.getScriptSource" => {
+                let state = self.state.borrow();
+                let id = params["scriptId"].as_str().unwrap_or_default();
+
+                Ok((
+                    state
+                        .scripts
+                        .by_id(id)
+                        .map(|(_, script)| json!({ "scriptSource": script.source }))
+                        .ok_or_else(|| format!("No script for id: {id}")),
+                    Flow::Stay,
+                ))
+            }
+            "Debugger.setPauseOnExceptions" => {
+                self.state.borrow_mut().exceptions = match params["state"].as_str() {
+                    Some("uncaught") => ExceptionPause::Uncaught,
+                    Some("all" | "caught") => ExceptionPause::All,
+                    _ => ExceptionPause::None,
+                };
+
+                ok(json!({}))
+            }
+            "Debugger.pause" => {
+                self.state.borrow_mut().step = Step::Pause;
+                ok(json!({}))
+            }
+            "Debugger.resume" => self.resume(Step::None),
+            "Debugger.stepInto" => self.resume(Step::Into),
+            "Debugger.stepOver" => {
+                let depth = self.state.borrow().frames.len();
+                self.resume(Step::Over(depth))
+            }
+            "Debugger.stepOut" => {
+                let depth = self.state.borrow().frames.len();
+                self.resume(Step::Out(depth))
+            }
+            "Debugger.evaluateOnCallFrame" => {
+                let index = params["callFrameId"]
+                    .as_str()
+                    .and_then(|id| id.parse::<usize>().ok())
+                    .unwrap_or_default();
+
+                let scope = self.frame_scope(realm, index);
+
+                ok(self.evaluate(realm, &scope, params)?)
+            }
+            _ => Ok((Err(format!("'{method}' wasn't found")), Flow::Stay)),
+        }
+    }
+
+    #[allow(clippy::type_complexity, clippy::unnecessary_wraps)]
+    fn resume(&self, step: Step) -> Res<(Result<Json, String>, Flow)> {
+        let mut state = self.state.borrow_mut();
+
+        if !state.paused {
+            return Ok((
+                Err("Can only perform operation while paused.".to_owned()),
+                Flow::Stay,
+            ));
+        }
+
+        state.step = step;
+
+        Ok((Ok(json!({})), Flow::Resume))
+    }
+
+    fn add_breakpoint(
+        &self,
+        target: BreakpointTarget,
+        params: &Json,
+        line: usize,
+        column: usize,
+    ) -> (String, Vec<Json>) {
+        let mut state = self.state.borrow_mut();
+
+        let id = state.next_breakpoint.to_string();
+        state.next_breakpoint += 1;
+
+        let breakpoint = Breakpoint {
+            id: id.clone(),
+            target,
+            line,
+            column,
+            condition: params["condition"]
+                .as_str()
+                .filter(|condition| !condition.is_empty())
+                .map(ToOwned::to_owned),
+        };
+
+        let locations = state
+            .scripts
+            .iter()
+            .filter(|(index, script)| breakpoint.matches(*index, &script.url))
+            .map(|(_, script)| {
+                json!({
+                    "scriptId": script.id,
+                    "lineNumber": breakpoint.line,
+                    "columnNumber": breakpoint.column,
+                })
+            })
+            .collect();
+
+        state.breakpoints.push(breakpoint);
+
+        (id, locations)
+    }
+
+    fn set_breakpoint_by_url(&self, params: &Json) -> Result<Json, String> {
+        let target = if let Some(url) = params["url"].as_str() {
+            BreakpointTarget::Url(url.to_owned())
+        } else if let Some(regex) = params["urlRegex"].as_str() {
+            BreakpointTarget::UrlRegex(Regex::new(regex).map_err(|e| e.to_string())?)
+        } else {
+            return Err("Either url or urlRegex must be specified.".to_owned());
+        };
+
+        let (line, column) = line_and_column(params);
+        let (id, locations) = self.add_breakpoint(target, params, line, column);
+
+        Ok(json!({ "breakpointId": id, "locations": locations }))
+    }
+
+    fn set_breakpoint(&self, params: &Json) -> Result<Json, String> {
+        let location = &params["location"];
+        let id = location["scriptId"].as_str().unwrap_or_default();
+
+        let Some((index, _)) = self.state.borrow().scripts.by_id(id) else {
+            return Err(format!("No script for id: {id}"));
+        };
+
+        let (line, column) = line_and_column(location);
+        let (id, mut locations) =
+            self.add_breakpoint(BreakpointTarget::Script(index), params, line, column);
+
+        Ok(json!({ "breakpointId": id, "actualLocation": locations.pop() }))
+    }
+
+    /// The scope of the frame with the given index, the global scope if there
+    /// is none.
+    fn frame_scope(&self, realm: &Realm, index: usize) -> Scope {
+        let state = self.state.borrow();
+
+        state
+            .frames
+            .get(index)
+            .or_else(|| state.frames.first())
+            .and_then(|frame| frame.scope.clone())
+            .unwrap_or_else(|| Scope::global(realm, PathBuf::new()))
+    }
+
+    fn evaluate(&self, realm: &mut Realm, scope: &Scope, params: &Json) -> Res<Json> {
+        let expression = params["expression"].as_str().unwrap_or_default();
+        let by_value = params["returnByValue"].as_bool().unwrap_or(false);
+
+        let result = InterpreterEval.eval(expression, realm, &mut scope.clone());
+
+        self.completion(realm, result, by_value)
+    }
+
+    /// The result of an evaluation, thrown errors become exception details.
+    fn completion(&self, realm: &mut Realm, result: Res<Value>, by_value: bool) -> Res<Json> {
+        match result {
+            Ok(value) => Ok(json!({ "result": self.remote_result(realm, &value, by_value)? })),
+            Err(e) if e.is_terminated() => Err(e),
+            Err(e) => {
+                let text = format!("Uncaught {e}");
+                let value = ErrorObj::error_to_value(e, realm)?;
+                let exception = self.remote_object(realm, &value);
+
+                Ok(json!({
+                    "result": exception,
+                    "exceptionDetails": {
+                        "exceptionId": 1,
+                        "text": text,
+                        "lineNumber": 0,
+                        "columnNumber": 0,
+                        "exception": exception,
+                    },
+                }))
+            }
+        }
+    }
+
+    fn call_function_on(&self, realm: &mut Realm, params: &Json) -> Res<Result<Json, String>> {
+        let declaration = params["functionDeclaration"].as_str().unwrap_or_default();
+        let by_value = params["returnByValue"].as_bool().unwrap_or(false);
+
+        let this = match params["objectId"].as_str() {
+            Some(id) => match self.remote(id) {
+                Some(Remote::Value(value)) => value,
+                Some(Remote::Scope(_)) => Value::Undefined,
+                None => return Ok(Err(format!("Could not find object with given id: {id}"))),
+            },
+            None => Value::Undefined,
+        };
+
+        let mut args = Vec::new();
+
+        if let Some(arguments) = params["arguments"].as_array() {
+            for argument in arguments {
+                args.push(self.call_argument(argument));
+            }
+        }
+
+        let mut scope = self.frame_scope(realm, 0);
+        let function = InterpreterEval.eval(&format!("({declaration})"), realm, &mut scope);
+
+        let result = function.and_then(|function| {
+            let Value::Object(function) = function else {
+                return Err(Error::ty(
+                    "Given expression does not evaluate to a function",
+                ));
+            };
+
+            function.call(args, this, realm)
+        });
+
+        Ok(Ok(self.completion(realm, result, by_value)?))
+    }
+
+    fn call_argument(&self, argument: &Json) -> Value {
+        if let Some(id) = argument["objectId"].as_str() {
+            return match self.remote(id) {
+                Some(Remote::Value(value)) => value,
+                _ => Value::Undefined,
+            };
+        }
+
+        if let Some(value) = argument["unserializableValue"].as_str() {
+            return match value {
+                "NaN" => Value::Number(f64::NAN),
+                "Infinity" => Value::Number(f64::INFINITY),
+                "-Infinity" => Value::Number(f64::NEG_INFINITY),
+                "-0" => Value::Number(-0.0),
+                _ => Value::Undefined,
+            };
+        }
+
+        match &argument["value"] {
+            Json::Bool(b) => Value::Boolean(*b),
+            Json::Number(n) => Value::Number(n.as_f64().unwrap_or_default()),
+            Json::String(s) => Value::String(s.clone().into()),
+            Json::Null if argument.get("value").is_some() => Value::Null,
+            _ => Value::Undefined,
+        }
+    }
+
+    fn get_properties(&self, realm: &mut Realm, params: &Json) -> Res<Result<Json, String>> {
+        let id = params["objectId"].as_str().unwrap_or_default();
+        let own = params["ownProperties"].as_bool().unwrap_or(false);
+
+        if params["accessorPropertiesOnly"].as_bool().unwrap_or(false) {
+            return Ok(Ok(json!({ "result": [] })));
+        }
+
+        let Some(remote) = self.remote(id) else {
+            return Ok(Err(format!("Could not find object with given id: {id}")));
+        };
+
+        let mut result = Vec::new();
+        let mut internal = Vec::new();
+
+        match remote {
+            Remote::Value(Value::Object(obj)) => {
+                for prop in remote::own_properties(&obj, realm)? {
+                    result.push(self.property(realm, prop, true));
+                }
+
+                let mut proto = remote::prototype(&obj, realm)?;
+
+                if let Some(proto) = &proto {
+                    internal.push(json!({
+                        "name": "[[Prototype]]",
+                        "value": self.remote_object(realm, &proto.clone().into()),
+                    }));
+                }
+
+                if !own {
+                    let mut seen = result
+                        .iter()
+                        .filter_map(|prop| prop["name"].as_str().map(ToOwned::to_owned))
+                        .collect::<Vec<_>>();
+
+                    for _ in 0..MAX_PROTOTYPES {
+                        let Some(current) = proto else {
+                            break;
+                        };
+
+                        for prop in remote::own_properties(&current, realm)? {
+                            if seen.iter().any(|name| name == prop.name()) {
+                                continue;
+                            }
+
+                            seen.push(prop.name().to_owned());
+                            result.push(self.property(realm, prop, false));
+                        }
+
+                        proto = remote::prototype(&current, realm)?;
+                    }
+                }
+            }
+            Remote::Value(_) => {}
+            Remote::Scope(scope) => {
+                for prop in remote::scope_variables(&scope, realm)? {
+                    result.push(self.property(realm, prop, true));
+                }
+            }
+        }
+
+        Ok(Ok(
+            json!({ "result": result, "internalProperties": internal }),
+        ))
+    }
+
+    fn property(&self, realm: &mut Realm, prop: RemoteProperty, is_own: bool) -> Json {
+        match prop {
+            RemoteProperty::Value {
+                name,
+                value,
+                writable,
+                enumerable,
+                configurable,
+            } => json!({
+                "name": name,
+                "value": self.remote_object(realm, &value),
+                "writable": writable,
+                "enumerable": enumerable,
+                "configurable": configurable,
+                "isOwn": is_own,
+            }),
+            RemoteProperty::Accessor {
+                name,
+                get,
+                enumerable,
+                configurable,
+            } => json!({
+                "name": name,
+                "get": self.remote_object(realm, &get.into()),
+                "set": { "type": "undefined" },
+                "enumerable": enumerable,
+                "configurable": configurable,
+                "isOwn": is_own,
+            }),
+        }
+    }
+
+    fn remote(&self, id: &str) -> Option<Remote> {
+        self.state.borrow().remote.get(id).cloned()
+    }
+
+    fn remote_object(&self, realm: &mut Realm, value: &Value) -> Json {
+        let mut object = remote::describe(value, realm);
+
+        if remote::needs_id(value) {
+            let id = self
+                .state
+                .borrow_mut()
+                .remote
+                .add(Remote::Value(value.clone()));
+
+            object["objectId"] = id.into();
+        }
+
+        object
+    }
+
+    fn remote_result(&self, realm: &mut Realm, value: &Value, by_value: bool) -> Res<Json> {
+        if !by_value {
+            return Ok(self.remote_object(realm, value));
+        }
+
+        let mut object = remote::describe(value, realm);
+        object["value"] = remote::to_json(value, realm)?;
+
+        Ok(object)
+    }
+
+    fn scope_object(&self, kind: &str, scope: Scope) -> Json {
+        let id = self.state.borrow_mut().remote.add(Remote::Scope(scope));
+
+        json!({
+            "type": kind,
+            "object": {
+                "type": "object",
+                "className": "Object",
+                "description": "Object",
+                "objectId": id,
+            },
+        })
+    }
+
+    fn snapshot(&self) -> Vec<FrameSnapshot> {
+        let state = self.state.borrow();
+
+        state
+            .frames
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(index, frame)| {
+                let (location, url) = state.location(frame)?;
+
+                Some(FrameSnapshot {
+                    index,
+                    name: frame.name.clone(),
+                    scope: frame.scope.clone()?,
+                    location,
+                    url,
+                })
+            })
+            .collect()
+    }
+
+    fn call_frames(&self, realm: &mut Realm) -> Res<Vec<Json>> {
+        let global = realm.global.clone();
+        let mut frames = Vec::new();
+
+        for frame in self.snapshot() {
+            let this = frame.scope.this()?;
+            let local = self.scope_object(
+                if frame.index == 0 { "script" } else { "local" },
+                frame.scope,
+            );
+
+            frames.push(json!({
+                "callFrameId": frame.index.to_string(),
+                "functionName": frame.name,
+                "location": frame.location,
+                "url": frame.url,
+                "scopeChain": [
+                    local,
+                    {
+                        "type": "global",
+                        "object": self.remote_object(realm, &global.clone().into()),
+                    },
+                ],
+                "this": self.remote_object(realm, &this),
+            }));
+        }
+
+        Ok(frames)
+    }
+
+    /// Pauses until the client resumes.
+    fn pause(&self, realm: &mut Realm, reason: &str, data: Option<Value>, hit: &[String]) -> Res {
+        {
+            let mut state = self.state.borrow_mut();
+
+            if state.client.is_none() {
+                return Ok(());
+            }
+
+            state.step = Step::None;
+            state.paused = true;
+            state.stopped_here = true;
+
+            let depth = state.frames.len();
+            let last = state.frames.last().and_then(|frame| {
+                let span = frame.span?;
+                let line = state.scripts.get(frame.script?).position(span.lo).0;
+
+                Some((depth, span, line))
+            });
+
+            state.last_pause = last;
+        }
+
+        self.busy.set(true);
+        let result = self.paused(realm, reason, data, hit);
+        self.busy.set(false);
+
+        self.state.borrow_mut().paused = false;
+        self.event("Debugger.resumed", json!({}));
+
+        result
+    }
+
+    fn paused(&self, realm: &mut Realm, reason: &str, data: Option<Value>, hit: &[String]) -> Res {
+        let mut params = json!({
+            "callFrames": self.call_frames(realm)?,
+            "reason": reason,
+            "hitBreakpoints": hit,
+        });
+
+        if let Some(data) = data {
+            params["data"] = self.remote_object(realm, &data);
+        }
+
+        self.event("Debugger.paused", params);
+
+        loop {
+            let Ok(incoming) = self.incoming.recv() else {
+                return Ok(());
+            };
+
+            if matches!(
+                self.receive(realm, incoming)?,
+                Flow::Resume | Flow::Detached
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Evaluates the condition of a breakpoint, errors count as false.
+    fn condition(&self, realm: &mut Realm, scope: &Scope, condition: &str) -> Res<bool> {
+        self.busy.set(true);
+        let result = InterpreterEval.eval(condition, realm, &mut scope.clone());
+        self.busy.set(false);
+
+        match result {
+            Ok(value) => Ok(value.is_truthy()),
+            Err(e) if e.is_terminated() => Err(e),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+impl DebugHook for Inspector {
+    fn statement(&self, realm: &mut Realm, scope: &Scope, span: SourceSpan) -> Res {
+        if self.busy.get() {
+            return Ok(());
+        }
+
+        if self.pending.swap(false, Ordering::Acquire) {
+            self.drain(realm)?;
+        }
+
+        let parsed = {
+            let mut state = self.state.borrow_mut();
+
+            if state.terminate {
+                state.terminate = false;
+                return Err(Error::terminated("Execution terminated by the debugger"));
+            }
+
+            state.exception_reported = false;
+            state.stopped_here = false;
+
+            if let Some(frame) = state.frames.last_mut() {
+                frame.scope = Some(scope.clone());
+                frame.span = Some(span);
+            }
+
+            state.resolve_script(scope)
+        };
+
+        if let Some(parsed) = parsed {
+            self.event("Debugger.scriptParsed", parsed);
+        }
+
+        let (step, breakpoints) = self.state.borrow().should_pause(span);
+
+        let mut hit = Vec::new();
+
+        for (id, condition) in breakpoints {
+            let hits = match condition {
+                Some(condition) => self.condition(realm, scope, &condition)?,
+                None => true,
+            };
+
+            if hits {
+                hit.push(id);
+            }
+        }
+
+        let Some(reason) = step.or_else(|| (!hit.is_empty()).then_some("other")) else {
+            return Ok(());
+        };
+
+        self.pause(realm, reason, None, &hit)
+    }
+
+    fn debugger_statement(&self, realm: &mut Realm, _scope: &Scope, _span: SourceSpan) -> Res {
+        if self.busy.get() {
+            return Ok(());
+        }
+
+        {
+            let state = self.state.borrow();
+
+            if state.stopped_here || state.skip_pauses {
+                return Ok(());
+            }
+        }
+
+        self.pause(realm, "other", None, &[])
+    }
+
+    fn exception(&self, realm: &mut Realm, error: &Error) -> Res {
+        if self.busy.get() {
+            return Ok(());
+        }
+
+        {
+            let mut state = self.state.borrow_mut();
+
+            if state.exception_reported || state.skip_pauses {
+                return Ok(());
+            }
+
+            state.exception_reported = true;
+
+            let pause = match state.exceptions {
+                ExceptionPause::None => false,
+                ExceptionPause::Uncaught => state.try_depth == 0,
+                ExceptionPause::All => true,
+            };
+
+            if !pause {
+                return Ok(());
+            }
+        }
+
+        let value = ErrorObj::error_to_value(error.clone(), realm)?;
+
+        self.pause(realm, "exception", Some(value), &[])
+    }
+
+    fn enter_frame(&self, name: String) {
+        if !self.busy.get() {
+            self.state.borrow_mut().frames.push(Frame::new(name));
+        }
+    }
+
+    fn leave_frame(&self) {
+        if !self.busy.get() {
+            let mut state = self.state.borrow_mut();
+
+            if state.frames.len() > 1 {
+                state.frames.pop();
+            }
+        }
+    }
+
+    fn enter_try(&self) {
+        if !self.busy.get() {
+            self.state.borrow_mut().try_depth += 1;
+        }
+    }
+
+    fn leave_try(&self) {
+        if !self.busy.get() {
+            let mut state = self.state.borrow_mut();
+            state.try_depth = state.try_depth.saturating_sub(1);
+        }
+    }
+
+    fn console(&self, realm: &mut Realm, level: &str, args: &[Value]) {
+        let (runtime, console) = {
+            let state = self.state.borrow();
+
+            (state.runtime_enabled, state.console_enabled)
+        };
+
+        if !runtime && !console {
+            return;
+        }
+
+        let busy = self.busy.replace(true);
+
+        let remote_args = args
+            .iter()
+            .map(|arg| self.remote_object(realm, arg))
+            .collect::<Vec<_>>();
+
+        let stack = self
+            .snapshot()
+            .into_iter()
+            .map(|frame| {
+                json!({
+                    "functionName": frame.name,
+                    "scriptId": frame.location["scriptId"],
+                    "url": frame.url,
+                    "lineNumber": frame.location["lineNumber"],
+                    "columnNumber": frame.location["columnNumber"],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+            * 1000.0;
+
+        if runtime {
+            self.event(
+                "Runtime.consoleAPICalled",
+                json!({
+                    "type": level,
+                    "args": remote_args,
+                    "executionContextId": 1,
+                    "timestamp": timestamp,
+                    "stackTrace": { "callFrames": stack },
+                }),
+            );
+        }
+
+        if console {
+            let text = remote_args
+                .iter()
+                .map(|arg| {
+                    arg["value"]
+                        .as_str()
+                        .map(ToOwned::to_owned)
+                        .or_else(|| arg["description"].as_str().map(ToOwned::to_owned))
+                        .unwrap_or_else(|| arg["value"].to_string())
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            self.event(
+                "Console.messageAdded",
+                json!({
+                    "message": {
+                        "source": "console-api",
+                        "level": level,
+                        "text": text,
+                    },
+                }),
+            );
+        }
+
+        self.busy.set(busy);
+    }
+}
+
+fn line_and_column(params: &Json) -> (usize, usize) {
+    let get = |key: &str| {
+        params[key]
+            .as_u64()
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or_default()
+    };
+
+    (get("lineNumber"), get("columnNumber"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const SOURCE: &str = "let x = 1;\nx += 1;\nx += 2;\nx";
+
+    /// Runs `SOURCE` with the given client messages queued and returns the
+    /// messages sent to the client.
+    fn session(messages: &[Json]) -> Res<(Value, Vec<Json>)> {
+        let (tx, rx) = mpsc::channel();
+        let inspector = Rc::new(Inspector::new(
+            rx,
+            Arc::new(AtomicBool::new(false)),
+            Path::new("test.js"),
+            SOURCE,
+        ));
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let client_sent = Arc::clone(&sent);
+
+        let client: Client = Box::new(move |text| {
+            if let Ok(mut sent) = client_sent.lock() {
+                sent.push(serde_json::from_str(text).unwrap_or_default());
+            }
+
+            Ok(())
+        });
+
+        _ = tx.send(Incoming::Connected(client));
+
+        for message in messages {
+            _ = tx.send(Incoming::Message(message.to_string()));
+        }
+
+        let mut realm = Realm::new()?;
+        realm.set_eval(InterpreterEval, false)?;
+        let mut scope = Scope::global(&realm, PathBuf::from("test.js"));
+
+        realm.set_debugger(Rc::<Inspector>::clone(&inspector));
+
+        inspector.wait_for_debugger(&mut realm, &scope, false)?;
+        let result = InterpreterEval.eval(SOURCE, &mut realm, &mut scope)?;
+
+        let sent = sent.lock().map(|sent| sent.clone()).unwrap_or_default();
+
+        Ok((result, sent))
+    }
+
+    fn response(sent: &[Json], id: u64) -> &Json {
+        sent.iter()
+            .find(|message| message["id"] == id)
+            .map_or(&Json::Null, |message| &message["result"])
+    }
+
+    #[test]
+    fn breakpoint_evaluate_and_step() -> Res {
+        let (result, sent) = session(&[
+            json!({ "id": 1, "method": "Debugger.enable" }),
+            json!({
+                "id": 2,
+                "method": "Debugger.setBreakpointByUrl",
+                "params": { "url": "test.js", "lineNumber": 1 },
+            }),
+            json!({ "id": 3, "method": "Runtime.runIfWaitingForDebugger" }),
+            json!({
+                "id": 4,
+                "method": "Debugger.evaluateOnCallFrame",
+                "params": { "callFrameId": "0", "expression": "x * 10" },
+            }),
+            json!({ "id": 5, "method": "Debugger.stepOver" }),
+            json!({ "id": 6, "method": "Debugger.resume" }),
+        ])?;
+
+        assert_eq!(result, Value::Number(4.0));
+
+        assert_eq!(sent[0]["method"], "Debugger.scriptParsed");
+        assert_eq!(sent[0]["params"]["url"], "test.js");
+        assert_eq!(response(&sent, 2)["locations"][0]["lineNumber"], 1);
+
+        let paused = sent
+            .iter()
+            .filter(|message| message["method"] == "Debugger.paused")
+            .collect::<Vec<_>>();
+
+        assert_eq!(paused.len(), 2);
+        assert_eq!(paused[0]["params"]["hitBreakpoints"][0], "1");
+        assert_eq!(
+            paused[0]["params"]["callFrames"][0]["location"]["lineNumber"],
+            1
+        );
+        assert_eq!(
+            paused[1]["params"]["callFrames"][0]["location"]["lineNumber"],
+            2
+        );
+
+        assert_eq!(response(&sent, 4)["result"]["value"], 10.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn step_commands_need_a_pause() -> Res {
+        let (_, sent) = session(&[
+            json!({ "id": 1, "method": "Debugger.stepInto" }),
+            json!({ "id": 2, "method": "Runtime.runIfWaitingForDebugger" }),
+        ])?;
+
+        assert!(sent[0]["error"].is_object());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+//! Conversion of values to the `Runtime.RemoteObject` of the protocol.
+
+use serde_json::{Map, Value as Json, json};
+use std::ops::Deref;
+use yavashark_env::array::Array;
+use yavashark_env::console::print::PrettyPrint;
+use yavashark_env::error_obj::ErrorObj;
+use yavashark_env::scope::Scope;
+use yavashark_env::value::Property;
+use yavashark_env::{ObjectHandle, ObjectOrNull, Realm, Res, Value};
+
+/// How deep `returnByValue` results are serialized.
+const MAX_DEPTH: usize = 16;
+
+/// Something the client can refer to by an object id.
+#[derive(Clone)]
+pub enum Remote {
+    Value(Value),
+    /// The variables of a scope, without those of the global object.
+    Scope(Scope),
+}
+
+#[derive(Default)]
+pub struct RemoteObjects {
+    objects: Vec<Remote>,
+}
+
+impl RemoteObjects {
+    pub fn add(&mut self, remote: Remote) -> String {
+        self.objects.push(remote);
+
+        self.objects.len().to_string()
+    }
+
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&Remote> {
+        let index = id.parse::<usize>().ok()?.checked_sub(1)?;
+
+        self.objects.get(index)
+    }
+
+    pub fn clear(&mut self) {
+        self.objects.clear();
+    }
+}
+
+/// The remote object without its id, [`needs_id`] tells whether it needs one.
+pub fn describe(value: &Value, realm: &mut Realm) -> Json {
+    match value {
+        Value::Undefined => json!({ "type": "undefined" }),
+        Value::Null => json!({ "type": "object", "subtype": "null", "value": null }),
+        Value::Boolean(b) => json!({ "type": "boolean", "value": b }),
+        Value::Number(n) => {
+            let description = value.pretty_print(realm);
+
+            if n.is_finite() && !(*n == 0.0 && n.is_sign_negative()) {
+                json!({ "type": "number", "value": n, "description": description })
+            } else {
+                json!({
+                    "type": "number",
+                    "unserializableValue": description,
+                    "description": description,
+                })
+            }
+        }
+        Value::String(s) => json!({ "type": "string", "value": s.as_str_lossy() }),
+        Value::BigInt(b) => {
+            let description = format!("{b}n");
+
+            json!({
+                "type": "bigint",
+                "unserializableValue": description,
+                "description": description,
+            })
+        }
+        Value::Symbol(_) => json!({
+            "type": "symbol",
+            "description": value.pretty_print(realm),
+        }),
+        Value::Object(obj) => describe_object(obj, value, realm),
+    }
+}
+
+#[must_use]
+pub const fn needs_id(value: &Value) -> bool {
+    matches!(value, Value::Object(_) | Value::Symbol(_))
+}
+
+fn describe_object(obj: &ObjectHandle, value: &Value, realm: &mut Realm) -> Json {
+    let class_name = obj.class_name();
+
+    if obj.is_callable() {
+        return json!({
+            "type": "function",
+            "className": "Function",
+            "description": value.pretty_print(realm),
+        });
+    }
+
+    if obj.downcast::<Array>().is_some() {
+        let len = obj.get("length", realm).map_or(0.0, |len| len.as_number());
+
+        return json!({
+            "type": "object",
+            "subtype": "array",
+            "className": "Array",
+            "description": format!("Array({len})"),
+        });
+    }
+
+    if obj.downcast::<ErrorObj>().is_some() {
+        return json!({
+            "type": "object",
+            "subtype": "error",
+            "className": class_name,
+            "description": error_description(obj, realm),
+        });
+    }
+
+    json!({
+        "type": "object",
+        "className": class_name,
+        "description": class_name,
+    })
+}
+
+/// `name: message`, like the first line of a stack trace.
+fn error_description(obj: &ObjectHandle, realm: &mut Realm) -> String {
+    let mut get = |key: &'static str| {
+        obj.get(key, realm)
+            .and_then(|value| value.to_string(realm))
+            .map(|s| s.to_string())
+            .unwrap_or_default()
+    };
+
+    let name = get("name");
+    let message = get("message");
+
+    if message.is_empty() {
+        name
+    } else {
+        format!("{name}: {message}")
+    }
+}
+
+/// Serializes a value for `returnByValue`.
+pub fn to_json(value: &Value, realm: &mut Realm) -> Res<Json> {
+    to_json_depth(value, realm, 0)
+}
+
+fn to_json_depth(value: &Value, realm: &mut Realm, depth: usize) -> Res<Json> {
+    Ok(match value {
+        Value::Undefined | Value::Null | Value::Symbol(_) => Json::Null,
+        Value::Boolean(b) => Json::Bool(*b),
+        Value::Number(n) => serde_json::Number::from_f64(*n).map_or(Json::Null, Json::Number),
+        Value::String(s) => Json::String(s.as_str_lossy().into_owned()),
+        Value::BigInt(b) => Json::String(b.to_string()),
+        Value::Object(_) if depth >= MAX_DEPTH => Json::Null,
+        Value::Object(obj) => {
+            if obj.downcast::<Array>().is_some() {
+                let len = obj.get("length", realm)?.as_number() as usize;
+                let mut items = Vec::with_capacity(len);
+
+                for i in 0..len {
+                    let item = obj.get(i, realm)?;
+                    items.push(to_json_depth(&item, realm, depth + 1)?);
+                }
+
+                Json::Array(items)
+            } else {
+                let mut map = Map::new();
+
+                for (key, value) in obj.enum_properties(realm)? {
+                    map.insert(key.to_string(), to_json_depth(&value, realm, depth + 1)?);
+                }
+
+                Json::Object(map)
+            }
+        }
+    })
+}
+
+/// A property as `Runtime.getProperties` reports it.
+pub enum RemoteProperty {
+    Value {
+        name: String,
+        value: Value,
+        writable: bool,
+        enumerable: bool,
+        configurable: bool,
+    },
+    Accessor {
+        name: String,
+        get: ObjectHandle,
+        enumerable: bool,
+        configurable: bool,
+    },
+}
+
+pub fn own_properties(obj: &ObjectHandle, realm: &mut Realm) -> Res<Vec<RemoteProperty>> {
+    let props = obj.deref().properties(realm)?;
+
+    Ok(props
+        .into_iter()
+        .map(|(key, prop)| match prop {
+            Property::Value(value, attributes) => RemoteProperty::Value {
+                name: key.to_string(),
+                value,
+                writable: attributes.is_writable(),
+                enumerable: attributes.is_enumerable(),
+                configurable: attributes.is_configurable(),
+            },
+            Property::Getter(get, attributes) => RemoteProperty::Accessor {
+                name: key.to_string(),
+                get,
+                enumerable: attributes.is_enumerable(),
+                configurable: attributes.is_configurable(),
+            },
+        })
+        .collect())
+}
+
+pub fn prototype(obj: &ObjectHandle, realm: &mut Realm) -> Res<Option<ObjectHandle>> {
+    Ok(match obj.deref().prototype(realm)? {
+        ObjectOrNull::Object(proto) => Some(proto),
+        ObjectOrNull::Null => None,
+    })
+}
+
+/// The variables of `scope` that aren't properties of the global object.
+pub fn scope_variables(scope: &Scope, realm: &mut Realm) -> Res<Vec<RemoteProperty>> {
+    let global = realm.global.clone();
+    let mut variables = Vec::new();
+
+    for (name, variable) in scope.get_variables(realm)? {
+        if global.contains_own_key(name.clone().into(), realm)? {
+            continue;
+        }
+
+        variables.push(RemoteProperty::Value {
+            name,
+            value: variable.value,
+            writable: variable.properties.is_writable(),
+            enumerable: true,
+            configurable: false,
+        });
+    }
+
+    variables.sort_by(|a, b| a.name().cmp(b.name()));
+
+    Ok(variables)
+}
+
+impl RemoteProperty {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Value { name, .. } | Self::Accessor { name, .. } => name,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+pub struct Script {
+    pub id: String,
+    pub url: String,
+    pub source: String,
+    /// Byte offset of the start of every line.
+    lines: Vec<usize>,
+}
+
+impl Script {
+    fn new(id: String, path: &Path, source: String) -> Self {
+        let lines = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            id,
+            url: url_of(path),
+            source,
+            lines,
+        }
+    }
+
+    /// Zero-based line and column of a byte offset.
+    #[must_use]
+    pub fn position(&self, offset: u32) -> (usize, usize) {
+        let offset = (offset as usize).min(self.source.len());
+        let line = self
+            .lines
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1);
+
+        let start = self.lines[line];
+        let column = self
+            .source
+            .get(start..offset)
+            .map_or(0, |text| text.encode_utf16().count());
+
+        (line, column)
+    }
+
+    pub fn parsed_event(&self) -> serde_json::Value {
+        let end_line = self.lines.len() - 1;
+        let end_column = self.source.len() - self.lines[end_line];
+
+        let mut hash = String::new();
+        for byte in Sha1::digest(self.source.as_bytes()) {
+            _ = write!(hash, "{byte:02x}");
+        }
+
+        serde_json::json!({
+            "scriptId": self.id,
+            "url": self.url,
+            "startLine": 0,
+            "startColumn": 0,
+            "endLine": end_line,
+            "endColumn": end_column,
+            "executionContextId": 1,
+            "hash": hash,
+            "sourceMapURL": "",
+            "hasSourceURL": false,
+            "length": self.source.len(),
+        })
+    }
+}
+
+fn url_of(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    if path.is_absolute() {
+        format!("file://{}", path.display())
+    } else {
+        path.display().to_string()
+    }
+}
+
+#[derive(Default)]
+pub struct Scripts {
+    scripts: Vec<Script>,
+    by_path: HashMap<PathBuf, usize>,
+}
+
+impl Scripts {
+    /// Registers a script with its source, returns its index and whether it's
+    /// new.
+    pub fn add(&mut self, path: &Path, source: String) -> (usize, bool) {
+        if let Some(index) = self.by_path.get(path) {
+            return (*index, false);
+        }
+
+        let index = self.scripts.len();
+        let id = (index + 1).to_string();
+
+        self.scripts.push(Script::new(id, path, source));
+        self.by_path.insert(path.to_path_buf(), index);
+
+        (index, true)
+    }
+
+    /// Registers the script at `path` by reading it, if it isn't known yet.
+    pub fn load(&mut self, path: &Path) -> Option<(usize, bool)> {
+        if let Some(index) = self.by_path.get(path) {
+            return Some((*index, false));
+        }
+
+        let source = std::fs::read_to_string(path).ok()?;
+
+        Some(self.add(path, source))
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> &Script {
+        &self.scripts[index]
+    }
+
+    #[must_use]
+    pub fn by_id(&self, id: &str) -> Option<(usize, &Script)> {
+        self.scripts
+            .iter()
+            .enumerate()
+            .find(|(_, script)| script.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Script)> {
+        self.scripts.iter().enumerate()
+    }
+}
@@ -0,0 +1,253 @@
+use crate::ws::{self, HttpRequest};
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+
+/// Sends a text message to the client.
+pub type Client = Box<dyn FnMut(&str) -> io::Result<()> + Send>;
+
+pub enum Incoming {
+    Connected(Client),
+    Message(String),
+    Disconnected,
+}
+
+/// What the discovery endpoints report about the script.
+pub struct Target {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub addr: SocketAddr,
+}
+
+impl Target {
+    fn ws_path(&self) -> String {
+        format!("/{}", self.id)
+    }
+
+    #[must_use]
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}/{}", self.addr, self.id)
+    }
+
+    fn list(&self) -> String {
+        let ws = format!("{}/{}", self.addr, self.id);
+
+        serde_json::json!([{
+            "description": "yavashark instance",
+            "devtoolsFrontendUrl": format!("devtools://devtools/bundled/js_app.html?experiments=true&v8only=true&ws={ws}"),
+            "devtoolsFrontendUrlCompat": format!("devtools://devtools/bundled/inspector.html?experiments=true&v8only=true&ws={ws}"),
+            "faviconUrl": "",
+            "id": self.id,
+            "title": self.title,
+            "type": "node",
+            "url": self.url,
+            "webSocketDebuggerUrl": self.ws_url(),
+        }])
+        .to_string()
+    }
+
+    fn version() -> String {
+        serde_json::json!({
+            "Browser": concat!("yavashark/", env!("CARGO_PKG_VERSION")),
+            "Protocol-Version": "1.3",
+        })
+        .to_string()
+    }
+}
+
+/// Accepts connections on a background thread. One session is served at a
+/// time, its messages are sent to `tx` and `pending` is set for each of them.
+pub fn spawn(
+    listener: TcpListener,
+    target: Target,
+    tx: Sender<Incoming>,
+    pending: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+
+            if serve(stream, &target, &tx, &pending).is_err() {
+                // the engine is gone
+                break;
+            }
+        }
+    });
+}
+
+fn serve(
+    stream: TcpStream,
+    target: &Target,
+    tx: &Sender<Incoming>,
+    pending: &AtomicBool,
+) -> Result<(), ()> {
+    let mut reader = BufReader::new(stream);
+
+    let Ok(request) = HttpRequest::read(&mut reader) else {
+        return Ok(());
+    };
+
+    // a web page can make the browser connect here through a DNS rebinding,
+    // only hosts that can't be rebound are trusted
+    if !request.header("host").is_some_and(is_local_host) {
+        let mut stream = reader.into_inner();
+        _ = ws::respond(
+            &mut stream,
+            "403 Forbidden",
+            "text/plain",
+            "host not allowed",
+        );
+
+        return Ok(());
+    }
+
+    if !request.is_upgrade() {
+        let mut stream = reader.into_inner();
+
+        let path = request.path.trim_end_matches('/');
+
+        _ = match path {
+            "/json" | "/json/list" => ws::respond(
+                &mut stream,
+                "200 OK",
+                "application/json; charset=UTF-8",
+                &target.list(),
+            ),
+            "/json/version" => ws::respond(
+                &mut stream,
+                "200 OK",
+                "application/json; charset=UTF-8",
+                &Target::version(),
+            ),
+            _ => ws::respond(&mut stream, "404 Not Found", "text/plain", "not found"),
+        };
+
+        return Ok(());
+    }
+
+    if request.path != target.ws_path() {
+        let mut stream = reader.into_inner();
+        _ = ws::respond(&mut stream, "404 Not Found", "text/plain", "unknown target");
+
+        return Ok(());
+    }
+
+    let Ok((mut reader, sender)) = ws::accept(reader, &request) else {
+        return Ok(());
+    };
+
+    let send = |incoming| {
+        tx.send(incoming).map_err(|_| ())?;
+        pending.store(true, Ordering::Release);
+
+        Ok(())
+    };
+
+    send(Incoming::Connected(Box::new(move |text| sender.send(text))))?;
+
+    while let Ok(Some(message)) = reader.read_message() {
+        send(Incoming::Message(message))?;
+    }
+
+    send(Incoming::Disconnected)
+}
+
+/// Whether `host` (a Host header, optionally with a port) is a loopback name
+/// or address.
+fn is_local_host(host: &str) -> bool {
+    let name = if let Some(rest) = host.strip_prefix('[') {
+        match rest.split_once(']') {
+            Some((name, port)) if port.is_empty() || port.starts_with(':') => name,
+            _ => return false,
+        }
+    } else {
+        host.split_once(':').map_or(host, |(name, _)| name)
+    };
+
+    name.eq_ignore_ascii_case("localhost") || name == "127.0.0.1" || name == "::1"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::sync::mpsc;
+
+    fn request(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        response
+    }
+
+    #[test]
+    fn local_hosts() {
+        for host in [
+            "localhost",
+            "LOCALHOST:9229",
+            "127.0.0.1",
+            "127.0.0.1:9229",
+            "[::1]",
+            "[::1]:9229",
+        ] {
+            assert!(is_local_host(host), "{host}");
+        }
+
+        for host in [
+            "example.com",
+            "localhost.example.com",
+            "127.0.0.2",
+            "[::1]x",
+            "::1",
+            "",
+            "0.0.0.0:9229",
+        ] {
+            assert!(!is_local_host(host), "{host}");
+        }
+    }
+
+    #[test]
+    fn foreign_host_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let target = Target {
+            id: "target".to_owned(),
+            title: "test".to_owned(),
+            url: "file:///test.js".to_owned(),
+            addr,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        spawn(listener, target, tx, Arc::new(AtomicBool::new(false)));
+
+        let list = request(addr, &format!("GET /json HTTP/1.1\r\nHost: {addr}\r\n\r\n"));
+        assert!(list.starts_with("HTTP/1.1 200 OK"), "{list}");
+
+        let list = request(
+            addr,
+            "GET /json HTTP/1.1\r\nHost: evil.example:9229\r\n\r\n",
+        );
+        assert!(list.starts_with("HTTP/1.1 403 Forbidden"), "{list}");
+        assert!(!list.contains("webSocketDebuggerUrl"));
+
+        let list = request(addr, "GET /json HTTP/1.1\r\n\r\n");
+        assert!(list.starts_with("HTTP/1.1 403 Forbidden"), "{list}");
+
+        let upgrade = request(
+            addr,
+            "GET /target HTTP/1.1\r\nHost: evil.example\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        );
+        assert!(upgrade.starts_with("HTTP/1.1 403 Forbidden"), "{upgrade}");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,421 @@
+//! Just enough HTTP and WebSocket (RFC 6455) for debugger clients: the
+//! `/json` discovery endpoints and text messages over a single connection.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Messages bigger than this close the connection.
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_TOO_BIG: u16 = 1009;
+
+pub struct HttpRequest {
+    pub path: String,
+    headers: HashMap<String, String>,
+}
+
+impl HttpRequest {
+    pub fn read(reader: &mut impl BufRead) -> io::Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let mut parts = line.split_whitespace();
+        let (Some("GET"), Some(path)) = (parts.next(), parts.next()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported request: {}", line.trim()),
+            ));
+        };
+
+        let path = path.to_owned();
+        let mut headers = HashMap::new();
+
+        loop {
+            line.clear();
+
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+
+            let header = line.trim_end();
+
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_owned());
+            }
+        }
+
+        Ok(Self { path, headers })
+    }
+
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn is_upgrade(&self) -> bool {
+        self.header("upgrade")
+            .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"))
+    }
+}
+
+pub fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+
+    stream.flush()
+}
+
+#[must_use]
+pub fn accept_key(key: &str) -> String {
+    let mut sha = Sha1::new();
+    sha.update(key.as_bytes());
+    sha.update(WEBSOCKET_GUID.as_bytes());
+
+    STANDARD.encode(sha.finalize())
+}
+
+/// Completes the handshake of an upgrade request and splits the connection.
+pub fn accept(
+    reader: BufReader<TcpStream>,
+    request: &HttpRequest,
+) -> io::Result<(WsReader, WsSender)> {
+    let Some(key) = request.header("sec-websocket-key") else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing Sec-WebSocket-Key",
+        ));
+    };
+
+    let mut stream = reader.get_ref().try_clone()?;
+
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+    stream.flush()?;
+
+    let sender = WsSender(Arc::new(Mutex::new(stream)));
+
+    Ok((
+        WsReader {
+            reader,
+            sender: sender.clone(),
+        },
+        sender,
+    ))
+}
+
+/// The sending half of a connection, it can be used from any thread.
+#[derive(Clone)]
+pub struct WsSender(Arc<Mutex<TcpStream>>);
+
+impl WsSender {
+    pub fn send(&self, text: &str) -> io::Result<()> {
+        self.send_frame(OP_TEXT, text.as_bytes())
+    }
+
+    pub fn close(&self) -> io::Result<()> {
+        self.send_frame(OP_CLOSE, &[])
+    }
+
+    fn close_with(&self, status: u16) -> io::Result<()> {
+        self.send_frame(OP_CLOSE, &status.to_be_bytes())
+    }
+
+    fn send_frame(&self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut frame = Vec::with_capacity(payload.len() + 10);
+        frame.push(0x80 | opcode);
+
+        match payload.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        frame.extend_from_slice(payload);
+
+        let mut stream = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        stream.write_all(&frame)?;
+        stream.flush()
+    }
+}
+
+pub struct WsReader {
+    reader: BufReader<TcpStream>,
+    sender: WsSender,
+}
+
+impl WsReader {
+    /// Reads the next text message, `None` when the client closed the
+    /// connection.
+    ///
+    /// A frame that breaks the protocol closes the connection with status
+    /// 1002 and is returned as an error.
+    pub fn read_message(&mut self) -> io::Result<Option<String>> {
+        let mut message = Vec::new();
+        let mut fragmented = false;
+
+        loop {
+            let mut head = [0; 2];
+            self.reader.read_exact(&mut head)?;
+
+            let fin = head[0] & 0x80 != 0;
+            let rsv = head[0] & 0x70;
+            let opcode = head[0] & 0x0F;
+            let masked = head[1] & 0x80 != 0;
+            let control = opcode & 0x8 != 0;
+
+            if rsv != 0 {
+                return Err(self.fail("reserved bits set without an extension"));
+            }
+
+            if !masked {
+                return Err(self.fail("client frames must be masked"));
+            }
+
+            if control && (!fin || head[1] & 0x7F > 125) {
+                return Err(
+                    self.fail("control frames can't be fragmented or longer than 125 bytes")
+                );
+            }
+
+            match opcode {
+                OP_CONTINUATION if !fragmented => {
+                    return Err(self.fail("continuation frame without a message"));
+                }
+                OP_TEXT | OP_BINARY if fragmented => {
+                    return Err(self.fail("new message before the last one was finished"));
+                }
+                OP_CONTINUATION | OP_TEXT | OP_BINARY | OP_CLOSE | OP_PING | OP_PONG => {}
+                _ => return Err(self.fail("unknown opcode")),
+            }
+
+            let len = match head[1] & 0x7F {
+                126 => {
+                    let mut len = [0; 2];
+                    self.reader.read_exact(&mut len)?;
+                    u64::from(u16::from_be_bytes(len))
+                }
+                127 => {
+                    let mut len = [0; 8];
+                    self.reader.read_exact(&mut len)?;
+                    u64::from_be_bytes(len)
+                }
+                len => u64::from(len),
+            };
+
+            let Some(len) = usize::try_from(len)
+                .ok()
+                .filter(|len| message.len() + len <= MAX_MESSAGE)
+            else {
+                _ = self.sender.close_with(CLOSE_TOO_BIG);
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "message too big",
+                ));
+            };
+
+            let mut mask = [0; 4];
+            self.reader.read_exact(&mut mask)?;
+
+            let mut payload = vec![0; len];
+            self.reader.read_exact(&mut payload)?;
+
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+
+            match opcode {
+                OP_CLOSE => {
+                    _ = self.sender.close();
+                    return Ok(None);
+                }
+                OP_PING => self.sender.send_frame(OP_PONG, &payload)?,
+                OP_PONG => {}
+                _ => {
+                    message.extend_from_slice(&payload);
+                    fragmented = !fin;
+
+                    if fin {
+                        return Ok(Some(String::from_utf8_lossy(&message).into_owned()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Closes the connection because the client broke the protocol.
+    fn fail(&self, reason: &str) -> io::Error {
+        _ = self.sender.close_with(CLOSE_PROTOCOL_ERROR);
+
+        io::Error::new(io::ErrorKind::InvalidData, reason.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_key_matches_rfc() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    /// A connected client stream and the server's reader for it.
+    fn connection() -> (TcpStream, WsReader) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        let sender = WsSender(Arc::new(Mutex::new(server.try_clone().unwrap())));
+
+        (
+            client,
+            WsReader {
+                reader: BufReader::new(server),
+                sender,
+            },
+        )
+    }
+
+    /// A frame as a client sends it, `first` is the fin and RSV bits plus the
+    /// opcode.
+    fn frame(first: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+
+        let mut frame = vec![first];
+
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 0x7E);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+
+        frame
+    }
+
+    /// What the server sends back: opcode and payload of the first frame.
+    fn response(client: &mut TcpStream) -> (u8, Vec<u8>) {
+        let mut head = [0; 2];
+        client.read_exact(&mut head).unwrap();
+
+        let mut payload = vec![0; usize::from(head[1] & 0x7F)];
+        client.read_exact(&mut payload).unwrap();
+
+        (head[0] & 0x0F, payload)
+    }
+
+    fn rejects(bytes: &[u8]) {
+        let (mut client, mut reader) = connection();
+        client.write_all(bytes).unwrap();
+
+        let err = reader.read_message().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        assert_eq!(
+            response(&mut client),
+            (OP_CLOSE, CLOSE_PROTOCOL_ERROR.to_be_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn reads_fragmented_messages_around_control_frames() {
+        let (mut client, mut reader) = connection();
+
+        let mut bytes = frame(OP_TEXT, b"hel");
+        bytes.extend(frame(0x80 | OP_PING, b"ping"));
+        bytes.extend(frame(0x80 | OP_CONTINUATION, b"lo"));
+        bytes.extend(frame(0x80 | OP_TEXT, &[b'x'; 300]));
+        bytes.extend(frame(0x80 | OP_CLOSE, &[]));
+        client.write_all(&bytes).unwrap();
+
+        assert_eq!(reader.read_message().unwrap().as_deref(), Some("hello"));
+        assert_eq!(response(&mut client), (OP_PONG, b"ping".to_vec()));
+
+        assert_eq!(reader.read_message().unwrap(), Some("x".repeat(300)));
+        assert_eq!(reader.read_message().unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_unmasked_frames() {
+        rejects(&[0x80 | OP_TEXT, 2, b'h', b'i']);
+    }
+
+    #[test]
+    fn rejects_reserved_bits() {
+        rejects(&frame(0xC0 | OP_TEXT, b"hi"));
+        rejects(&frame(0x90 | OP_TEXT, b"hi"));
+    }
+
+    #[test]
+    fn rejects_fragmented_control_frames() {
+        rejects(&frame(OP_PING, b"ping"));
+        rejects(&frame(OP_CLOSE, &[]));
+    }
+
+    #[test]
+    fn rejects_long_control_frames() {
+        rejects(&frame(0x80 | OP_PING, &[0; 126]));
+    }
+
+    #[test]
+    fn rejects_unknown_opcodes() {
+        rejects(&frame(0x80 | 0x3, b"hi"));
+        rejects(&frame(0x80 | 0xB, b"hi"));
+    }
+
+    #[test]
+    fn rejects_broken_fragmentation() {
+        rejects(&frame(0x80 | OP_CONTINUATION, b"hi"));
+
+        let mut bytes = frame(OP_TEXT, b"hel");
+        bytes.extend(frame(0x80 | OP_TEXT, b"lo"));
+        rejects(&bytes);
+    }
+}
@@ -1,10 +1,12 @@
-use crate::Interpreter;
+use crate::{Interpreter, snapshot};
+use std::path::Path;
 use swc_common::BytePos;
 use swc_common::input::StringInput;
 use swc_ecma_parser::{EsSyntax, Parser, Syntax};
 use yavashark_env::realm::Eval;
-use yavashark_env::scope::Scope;
-use yavashark_env::{Error, Realm, Value, ValueResult};
+use yavashark_env::scope::{Module, Scope};
+use yavashark_env::snapshot::SnapshotFunction;
+use yavashark_env::{Error, ObjectHandle, Realm, Res, Value, ValueResult};
 use yavashark_swc_validator::Validator;
 
 pub struct InterpreterEval;
@@ -53,4 +55,106 @@ impl Eval for InterpreterEval {
 
         Interpreter::run_in(&script.body, realm, scope)
     }
+
+    fn import(&self, spec: &str, cur_path: &Path, realm: &mut Realm) -> Res<Module> {
+        Interpreter::resolve_module(spec, None, cur_path, realm).cloned()
+    }
+
+    fn snapshot_function(&self, func: &ObjectHandle) -> Option<SnapshotFunction> {
+        snapshot::save(func)
+    }
+
+    fn restore_function(&self, func: SnapshotFunction, realm: &mut Realm) -> Res<ObjectHandle> {
+        snapshot::load(func, realm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use yavashark_env::snapshot::Snapshot;
+
+    fn eval(code: &str) -> ValueResult {
+        let mut realm = Realm::new()?;
+        realm.set_eval(InterpreterEval, false)?;
+
+        realm.eval_script(code, PathBuf::from("test.js"))
+    }
+
+    #[test]
+    fn shadow_realm_is_isolated() {
+        let result = eval(
+            r"
+            globalThis.secret = 1;
+            const realm = new ShadowRealm();
+            realm.evaluate('globalThis.secret = 2');
+            realm.evaluate('typeof Array') + secret + realm.evaluate('secret')
+            ",
+        );
+
+        assert_eq!(result, Ok(Value::from("function12")));
+    }
+
+    #[test]
+    fn shadow_realm_wraps_functions() {
+        let result = eval(
+            r"
+            const realm = new ShadowRealm();
+            const apply = realm.evaluate('(f, x) => f(x) * 2');
+            apply((x) => x + 1, 20)
+            ",
+        );
+
+        assert_eq!(result, Ok(Value::Number(42.0)));
+    }
+
+    #[test]
+    fn shadow_realm_rejects_objects() {
+        let result = eval("new ShadowRealm().evaluate('({})')");
+
+        assert!(matches!(
+            result,
+            Err(Error {
+                kind: yavashark_env::error::ErrorKind::Type(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn snapshot_restores_functions_and_closures() {
+        let mut realm = Realm::new().unwrap();
+        realm.set_eval(InterpreterEval, false).unwrap();
+
+        realm
+            .eval_script(
+                r"
+                function counter(start) {
+                    let count = start;
+                    return { next: () => ++count };
+                }
+                var c = counter(40);
+                c.next();
+                const tag = Symbol.for('tag');
+                Array.prototype.last = function () { return this[this.length - 1]; };
+                ",
+                PathBuf::from("boot.js"),
+            )
+            .unwrap();
+
+        let snapshot = Snapshot::capture(&mut realm).unwrap();
+        let snapshot = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+
+        let mut restored = Realm::new().unwrap();
+        restored.set_eval(InterpreterEval, false).unwrap();
+        snapshot.restore(&mut restored).unwrap();
+
+        let result = restored.eval_script(
+            "c.next() + [1, 2, 3].last() + (tag === Symbol.for('tag') ? 100 : 0)",
+            PathBuf::from("main.js"),
+        );
+
+        assert_eq!(result, Ok(Value::Number(145.0)));
+    }
 }
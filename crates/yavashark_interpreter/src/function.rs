@@ -6,6 +6,7 @@ use std::iter;
 use swc_ecma_ast::{BlockStmt, Callee, Expr, FunctionBody, MemberProp, Param, Pat, Stmt};
 use yavashark_env::array::Array;
 use yavashark_env::builtins::Arguments;
+use yavashark_env::debugger;
 use yavashark_env::optimizer::FunctionCode;
 use yavashark_env::realm::Realm;
 use yavashark_env::scope::Scope;
@@ -27,7 +28,7 @@ use yavashark_string::YSString;
 #[object(function, constructor, direct(prototype), name)]
 #[derive(Debug)]
 pub struct JSFunction {
-    // #[gc(untyped)] //TODO: this is a memleak!
+    #[gc(untyped)]
     pub raw: RawJSFunction,
 }
 
@@ -160,11 +161,7 @@ impl JSFunction {
 
 impl Func for JSFunction {
     fn call(&self, realm: &mut Realm, args: Vec<Value>, this: Value) -> ValueResult {
-        yavashark_env::profiler::profile_call(
-            realm,
-            || self.name(),
-            |realm| self.raw.call(realm, args, this),
-        )
+        self.raw.call(realm, args, this)
     }
 }
 
@@ -213,8 +210,16 @@ impl RawJSFunction {
             scope.declare_var("arguments".to_string(), args.into(), realm);
         }
 
+        if let Some(block) = &self.block {
+            realm.cover(scope, block.span.into());
+        }
+
         if let Some(block) = &self.block
-            && let Err(e) = Interpreter::run_block_stmts(realm, &block.stmts, scope)
+            && let Err(e) = debugger::frame(
+                realm,
+                || self.name.borrow().clone(),
+                |realm| Interpreter::run_block_stmts(realm, &block.stmts, scope),
+            )
         {
             return match e {
                 ControlFlow::Error(e) => Err(e),
@@ -406,19 +411,13 @@ impl CustomGcRefUntyped for RawJSFunction {
 
 impl Constructor for JSFunction {
     fn construct(&self, realm: &mut Realm, args: Vec<Value>) -> Res<ObjectHandle> {
-        yavashark_env::profiler::profile_call(
-            realm,
-            || self.name(),
-            |realm| {
-                let this = self.new_instance(realm)?;
+        let this = self.new_instance(realm)?;
 
-                if let Value::Object(obj) = self.raw.call(realm, args, this.copy())? {
-                    return Ok(obj);
-                }
+        if let Value::Object(obj) = self.raw.call(realm, args, this.copy())? {
+            return Ok(obj);
+        }
 
-                this.to_object()
-            },
-        )
+        this.to_object()
     }
 
     // fn construct_proto(&self) -> Res<ObjectProperty> {
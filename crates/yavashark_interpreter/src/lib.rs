@@ -8,7 +8,7 @@ use std::rc::Rc;
 
 use swc_ecma_ast::{BlockStmt, Expr, ExprStmt, Lit, ModuleItem, Program, Stmt};
 
-use yavashark_env::scope::{ModuleScope, Scope};
+use yavashark_env::scope::{ModuleScope, Resolution, Scope};
 use yavashark_env::{ControlFlow, Realm, Res, Value, ValueResult, scope};
 
 mod class;
@@ -18,10 +18,17 @@ mod location;
 pub mod module;
 mod parse;
 mod pat;
+mod snapshot;
+mod stack;
 pub mod statement;
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: yavashark_garbage::usage::CountingAllocator =
+    yavashark_garbage::usage::CountingAllocator(std::alloc::System);
+
 pub struct Interpreter;
 
 impl Interpreter {
@@ -29,7 +36,8 @@ impl Interpreter {
         let mut realm = &mut Realm::new()?;
         #[cfg(feature = "vm")]
         yavashark_vm::init(realm)?;
-        let mut scope = Scope::global(realm, file);
+        let mut scope =
+            Scope::global(realm, file).with_resolution(Rc::new(Resolution::script(script)));
 
         Self::run_statements(realm, script, &mut scope).or_else(|e| match e {
             ControlFlow::Error(e) => Err(e),
@@ -62,6 +70,8 @@ impl Interpreter {
             scope.set_strict_mode()?;
         }
 
+        let scope = &mut scope.with_resolution(Rc::new(Resolution::script(script)));
+
         Self::run_statements(realm, script, scope).or_else(|e| match e {
             ControlFlow::Error(e) => Err(e),
             ControlFlow::Return(v) => Ok(v),
@@ -84,6 +94,9 @@ impl Interpreter {
         scope: &mut ModuleScope,
     ) -> Res<Value> {
         scope.scope.set_strict_mode()?;
+        scope.scope = scope
+            .scope
+            .with_resolution(Rc::new(Resolution::module(script)));
 
         Self::run_module_items(realm, script, scope).or_else(|e| match e {
             ControlFlow::Error(e) => Err(e),
@@ -98,7 +111,8 @@ impl Interpreter {
         let mut context = &mut Realm::new().unwrap();
         #[cfg(feature = "vm")]
         yavashark_vm::init(context).unwrap();
-        let mut scope = Scope::global(context, PathBuf::from("test.js"));
+        let mut scope = Scope::global(context, PathBuf::from("test.js"))
+            .with_resolution(Rc::new(Resolution::script(script)));
 
         let (mock, state) = yavashark_env::tests::mock_object(context);
 
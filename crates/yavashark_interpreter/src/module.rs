@@ -7,6 +7,7 @@ use swc_ecma_ast::{
     ExportAll, ExportDecl, ExportDefaultDecl, ExportDefaultExpr, ImportDecl, ModuleDecl,
     ModuleItem, NamedExport,
 };
+use yavashark_env::coverage::FileItems;
 use yavashark_env::scope::{Module, ModuleScope, Scope};
 use yavashark_env::{Error, Realm, Res, RuntimeResult, Value};
 
@@ -55,6 +56,10 @@ impl Interpreter {
     pub fn run_module_source(source: &str, path: PathBuf, realm: &mut Realm) -> Res<Module> {
         let module = crate::parse::parse_module(source)?;
 
+        if realm.is_collecting_coverage() {
+            realm.register_coverage(path.clone(), source, FileItems::module(&module.body));
+        }
+
         let scope = Scope::global(realm, path);
 
         let mut scope = ModuleScope {
@@ -0,0 +1,63 @@
+//! Functions of the interpreter in realm snapshots. They are stored as the
+//! JSON of their AST behind a tag byte and recreated from it.
+
+use crate::function::JSFunction;
+use crate::statement::expr::ArrowFunction;
+use swc_ecma_ast::{ArrowExpr, FunctionBody, Param};
+use yavashark_env::snapshot::SnapshotFunction;
+use yavashark_env::{Error, ObjectHandle, Realm, Res, Value};
+
+const FUNCTION: u8 = 0;
+const ARROW: u8 = 1;
+
+pub fn save(func: &ObjectHandle) -> Option<SnapshotFunction> {
+    if let Some(func) = func.downcast::<JSFunction>() {
+        let raw = &func.raw;
+        let name = raw.name.try_borrow().ok()?;
+
+        let mut code = vec![FUNCTION];
+        serde_json::to_writer(&mut code, &(&*name, &raw.params, &raw.block)).ok()?;
+
+        return Some(SnapshotFunction {
+            code,
+            scope: Some(raw.scope.clone()),
+            values: Vec::new(),
+        });
+    }
+
+    let arrow = func.downcast::<ArrowFunction>()?;
+
+    let mut code = vec![ARROW];
+    serde_json::to_writer(&mut code, &arrow.expr).ok()?;
+
+    Some(SnapshotFunction {
+        code,
+        scope: Some(arrow.scope.clone()),
+        values: vec![arrow.this.copy()],
+    })
+}
+
+pub fn load(func: SnapshotFunction, realm: &mut Realm) -> Res<ObjectHandle> {
+    let invalid =
+        |e: serde_json::Error| Error::new_error(format!("invalid function in snapshot: {e}"));
+
+    let (Some((tag, code)), Some(scope)) = (func.code.split_first(), func.scope) else {
+        return Err(Error::new("invalid function in snapshot"));
+    };
+
+    match *tag {
+        FUNCTION => {
+            let (name, params, block): (String, Vec<Param>, Option<FunctionBody>) =
+                serde_json::from_slice(code).map_err(invalid)?;
+
+            JSFunction::new(name, params, block, scope, realm)
+        }
+        ARROW => {
+            let expr: ArrowExpr = serde_json::from_slice(code).map_err(invalid)?;
+            let this = func.values.into_iter().next().unwrap_or(Value::Undefined);
+
+            Ok(ArrowFunction::new(expr, this, scope, realm))
+        }
+        _ => Err(Error::new("invalid function in snapshot")),
+    }
+}
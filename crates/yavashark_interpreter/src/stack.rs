@@ -0,0 +1,64 @@
+//! Native stack management.
+//!
+//! The interpreter recurses on the Rust stack for every statement, expression
+//! and JS call, so deep recursion in a script would overflow the native stack
+//! and abort the process. Before every statement and expression we check how
+//! much stack is left and switch to a new, bigger segment when it runs low.
+//! Once the segments add up to [`MAX_STACK`], the recursion is most likely
+//! infinite and a `RangeError` is thrown instead.
+
+use std::cell::Cell;
+use yavashark_env::Error;
+
+/// Stack that has to be left for a statement or expression to start.
+const RED_ZONE: usize = 256 * 1024;
+
+/// Size of each segment that gets allocated when the stack runs low.
+const SEGMENT_SIZE: usize = 8 * 1024 * 1024;
+
+/// Stack the segments may take together.
+const MAX_STACK: usize = 1024 * 1024 * 1024;
+
+thread_local! {
+    static SEGMENTS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs `f`, on a new stack segment if the current one is almost used up.
+pub fn ensure<T, E: From<Error>>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    if stacker::remaining_stack().is_none_or(|left| left >= RED_ZONE) {
+        return f();
+    }
+
+    let segments = SEGMENTS.get();
+
+    if (segments + 1) * SEGMENT_SIZE > MAX_STACK {
+        return Err(Error::range("Maximum call stack size exceeded").into());
+    }
+
+    SEGMENTS.set(segments + 1);
+    let res = stacker::grow(SEGMENT_SIZE, f);
+    SEGMENTS.set(segments);
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use yavashark_env::{Value, test_eval};
+
+    #[test]
+    fn recursion_100k_frames_deep() {
+        test_eval!(
+            r"
+            function depth(n) {
+                return n === 0 ? 0 : 1 + depth(n - 1);
+            }
+
+            depth(100000)
+            ",
+            0,
+            Vec::<Vec<Value>>::new(),
+            Value::Number(100_000.0)
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use swc_common::Spanned;
 use swc_ecma_ast::{Decl, Stmt};
 
-use yavashark_env::{Realm, Res, RuntimeResult, Value, scope::Scope};
+use yavashark_env::debugger::DebugHook;
+use yavashark_env::{ControlFlow, Realm, Res, RuntimeResult, Value, scope::Scope};
 
 use crate::Interpreter;
 use crate::location::get_location;
+use crate::stack;
 
 mod block;
 mod r#break;
@@ -27,6 +29,48 @@ mod with;
 
 impl Interpreter {
     pub fn run_statement(realm: &mut Realm, stmt: &Stmt, scope: &mut Scope) -> RuntimeResult {
+        #[cfg(feature = "profiler")]
+        realm.profile.set_position(stmt.span().lo.0, || {
+            scope
+                .get_current_path()
+                .ok()
+                .map(|path| path.to_string_lossy().into_owned())
+        });
+
+        realm.step()?;
+
+        realm.cover(scope, stmt.span().into());
+
+        if let Some(debugger) = realm.debugger() {
+            return Self::run_statement_debug(realm, stmt, scope, &*debugger);
+        }
+
+        stack::ensure(|| Self::run_statement_unchecked(realm, stmt, scope))
+    }
+
+    fn run_statement_debug(
+        realm: &mut Realm,
+        stmt: &Stmt,
+        scope: &mut Scope,
+        debugger: &dyn DebugHook,
+    ) -> RuntimeResult {
+        // blocks only group statements, the debugger stops at their content
+        if !matches!(stmt, Stmt::Block(_)) {
+            debugger.statement(realm, scope, stmt.span().into())?;
+        }
+
+        let res = stack::ensure(|| Self::run_statement_unchecked(realm, stmt, scope));
+
+        if let Err(ControlFlow::Error(e)) = &res
+            && !e.is_terminated()
+        {
+            debugger.exception(realm, e)?;
+        }
+
+        res
+    }
+
+    fn run_statement_unchecked(realm: &mut Realm, stmt: &Stmt, scope: &mut Scope) -> RuntimeResult {
         let res = match stmt {
             Stmt::Block(block) => Self::run_block(realm, block, scope),
             Stmt::Empty(_) => Ok(Value::Undefined),
@@ -1,4 +1,5 @@
 use crate::Interpreter;
+use swc_common::Spanned;
 use swc_ecma_ast::DebuggerStmt;
 use yavashark_env::scope::Scope;
 use yavashark_env::{Realm, RuntimeResult, Value};
@@ -9,7 +10,10 @@ impl Interpreter {
         stmt: &DebuggerStmt,
         scope: &mut Scope,
     ) -> RuntimeResult {
-        println!("Set breakpoint here");
+        if let Some(debugger) = realm.debugger() {
+            debugger.debugger_statement(realm, scope, stmt.span().into())?;
+        }
+
         Ok(Value::Undefined)
     }
 }
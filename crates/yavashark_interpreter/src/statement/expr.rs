@@ -2,6 +2,7 @@ use swc_common::Span;
 use swc_ecma_ast::{Expr, ExprStmt};
 
 use crate::Interpreter;
+use crate::stack;
 pub use r#array::*;
 pub use arrow::*;
 pub use assign::*;
@@ -92,6 +93,15 @@ impl Interpreter {
         expr: &Expr,
         span: Span,
         scope: &mut Scope,
+    ) -> RuntimeResult {
+        stack::ensure(|| Self::run_expr_unchecked(realm, expr, span, scope))
+    }
+
+    fn run_expr_unchecked(
+        realm: &mut Realm,
+        expr: &Expr,
+        span: Span,
+        scope: &mut Scope,
     ) -> RuntimeResult {
         match expr {
             Expr::This(stmt) => Self::run_this(realm, stmt, scope),
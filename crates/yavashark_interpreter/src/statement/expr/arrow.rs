@@ -1,6 +1,8 @@
 use std::cell::RefCell;
+use swc_common::Spanned;
 use swc_ecma_ast::{ArrowExpr, ArrowFunctionBody};
 
+use yavashark_env::debugger;
 use yavashark_env::scope::Scope;
 use yavashark_env::value::Func;
 use yavashark_env::{
@@ -14,11 +16,26 @@ use crate::Interpreter;
 #[derive(Debug)]
 #[allow(clippy::module_name_repetitions)]
 pub struct ArrowFunction {
-    expr: ArrowExpr,
+    pub(crate) expr: ArrowExpr,
     #[gc]
-    this: Value,
+    pub(crate) this: Value,
     #[gc(untyped)]
-    scope: Scope,
+    pub(crate) scope: Scope,
+}
+
+impl ArrowFunction {
+    /// An arrow function without its `name` and `length` properties.
+    #[allow(clippy::new_ret_no_self)]
+    pub(crate) fn new(expr: ArrowExpr, this: Value, scope: Scope, realm: &Realm) -> ObjectHandle {
+        ObjectHandle::new(Self {
+            inner: RefCell::new(MutableArrowFunction {
+                object: MutObject::with_proto(realm.intrinsics.func.clone()),
+            }),
+            expr,
+            this,
+            scope,
+        })
+    }
 }
 
 impl Func for ArrowFunction {
@@ -43,10 +60,12 @@ impl Func for ArrowFunction {
         scope.state_set_function()?;
         scope.state_set_returnable()?;
 
+        realm.cover(scope, self.expr.body.span().into());
+
         let res = match &*self.expr.body {
-            ArrowFunctionBody::FunctionBody(stmt) => {
+            ArrowFunctionBody::FunctionBody(stmt) => debugger::frame(realm, String::new, |realm| {
                 Interpreter::run_block_stmts(realm, &stmt.stmts, scope)
-            }
+            }),
             ArrowFunctionBody::Expr(expr) => {
                 match Interpreter::run_expr(realm, expr, self.expr.span, scope) {
                     Ok(value) => return Ok(value),
@@ -68,16 +87,7 @@ impl Interpreter {
     pub fn run_arrow(realm: &mut Realm, stmt: &ArrowExpr, scope: &mut Scope) -> RuntimeResult {
         let this = scope.this()?.copy();
 
-        let arrow = ArrowFunction {
-            inner: RefCell::new(MutableArrowFunction {
-                object: MutObject::with_proto(realm.intrinsics.func.clone()),
-            }),
-            expr: stmt.clone(),
-            this,
-            scope: scope.clone(),
-        };
-
-        let arrow = ObjectHandle::new(arrow);
+        let arrow = ArrowFunction::new(stmt.clone(), this, scope.clone(), realm);
 
         arrow.define_property("name".into(), "".into(), realm)?;
 
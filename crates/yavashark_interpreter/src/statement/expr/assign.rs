@@ -37,6 +37,10 @@ impl Interpreter {
         match target {
             AssignTarget::Simple(t) => match t {
                 SimpleAssignTarget::Ident(i) => {
+                    if scope.try_update_ident(&i.id, &value, realm)? {
+                        return Ok(());
+                    }
+
                     let name = i.sym.to_string();
                     if scope.is_strict_mode()? && !scope.has_value(&name, realm)? {
                         return Err(Error::reference_error(format!("{name} is not defined")));
@@ -271,12 +275,14 @@ impl Interpreter {
                     let name = i.sym.as_str();
 
                     let left = scope
-                        .resolve(name, realm)?
+                        .resolve_ident(&i.id, realm)?
                         .ok_or_else(|| Error::reference_error(format!("{name} is not defined")))?;
 
                     let value = Self::run_assign_op(op, left, right, realm)?;
 
-                    scope.update(name, value.copy(), realm)?;
+                    if !scope.try_update_ident(&i.id, &value, realm)? {
+                        scope.update(name, value.copy(), realm)?;
+                    }
 
                     Ok(value)
                 }
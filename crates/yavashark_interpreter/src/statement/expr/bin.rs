@@ -1,170 +1,102 @@
 use std::cmp::Ordering;
+use swc_common::Spanned;
 use swc_ecma_ast::{BinExpr, BinaryOp, Expr, PrivateName};
 
 use yavashark_env::scope::Scope;
-use yavashark_env::{Class, ClassInstance, Realm, Res, RuntimeResult, Value};
+use yavashark_env::{Class, ClassInstance, Error, Realm, Res, RuntimeResult, Value};
 
 use crate::Interpreter;
 
 impl Interpreter {
     pub fn run_bin(realm: &mut Realm, stmt: &BinExpr, scope: &mut Scope) -> RuntimeResult {
-        Ok(match stmt.op {
-            BinaryOp::EqEq => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-
-                Value::Boolean(left.normal_eq(&right, realm)?)
-            }
-            BinaryOp::NotEq => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(!left.normal_eq(&right, realm)?)
-            }
-            BinaryOp::EqEqEq => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(left == right)
-            }
-            BinaryOp::NotEqEq => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(left != right)
-            }
-            BinaryOp::Lt => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(matches!(
-                    left.relational_cmp(&right, realm)?,
-                    Some(Ordering::Less)
-                ))
-            }
-            BinaryOp::LtEq => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(matches!(
-                    left.relational_cmp(&right, realm)?,
-                    Some(Ordering::Less | Ordering::Equal)
-                ))
-            }
-            BinaryOp::Gt => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(matches!(
-                    left.relational_cmp(&right, realm)?,
-                    Some(Ordering::Greater)
-                ))
-            }
-            BinaryOp::GtEq => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                Value::Boolean(matches!(
-                    left.relational_cmp(&right, realm)?,
-                    Some(Ordering::Greater | Ordering::Equal)
-                ))
-            }
-            BinaryOp::LShift => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.shl(&right, realm)?
-            }
-            BinaryOp::RShift => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.shr(&right, realm)?
-            }
-            BinaryOp::ZeroFillRShift => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.ushr(&right, realm)?
-            }
-            BinaryOp::Add => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.add(&right, realm)?
-            }
-            BinaryOp::Sub => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.sub(&right, realm)?
-            }
-            BinaryOp::Mul => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.mul(&right, realm)?
-            }
-            BinaryOp::Div => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.div(&right, realm)?
-            }
-            BinaryOp::Mod => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.rem(&right, realm)?
-            }
-            BinaryOp::BitOr => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.or(&right, realm)?
-            }
-            BinaryOp::BitXor => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.xor(&right, realm)?
-            }
-            BinaryOp::BitAnd => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.and(&right, realm)?
-            }
+        match stmt.op {
             BinaryOp::LogicalOr => {
+                realm.cover(scope, stmt.left.span().into());
                 let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
 
                 if left.is_truthy() {
                     return Ok(left);
                 }
 
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-
-                return Ok(right);
+                realm.cover(scope, stmt.right.span().into());
+                return Self::run_expr(realm, &stmt.right, stmt.span, scope);
             }
             BinaryOp::LogicalAnd => {
+                realm.cover(scope, stmt.left.span().into());
                 let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
                 if left.is_falsey() {
                     return Ok(left);
                 }
 
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
+                realm.cover(scope, stmt.right.span().into());
+                return Self::run_expr(realm, &stmt.right, stmt.span, scope);
+            }
+            BinaryOp::NullishCoalescing => {
+                realm.cover(scope, stmt.left.span().into());
+                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
+                if !left.is_nullish() {
+                    return Ok(left);
+                }
 
-                return Ok(right);
+                realm.cover(scope, stmt.right.span().into());
+                return Self::run_expr(realm, &stmt.right, stmt.span, scope);
             }
             BinaryOp::In => {
                 if let Expr::PrivateName(pn) = &*stmt.left {
                     let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
                     return Ok(Self::contains_private_name(realm, pn, &right)?.into());
                 }
-
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                right.has_key(&left, realm)?.into()
-            }
-            BinaryOp::InstanceOf => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.instance_of(&right, realm)?.into()
-            }
-            BinaryOp::Exp => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
-                left.exp(&right, realm)?
             }
-            BinaryOp::NullishCoalescing => {
-                let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
-                if !left.is_nullish() {
-                    return Ok(left);
-                }
+            _ => {}
+        }
 
-                return Self::run_expr(realm, &stmt.right, stmt.span, scope);
+        let left = Self::run_expr(realm, &stmt.left, stmt.span, scope)?;
+        let right = Self::run_expr(realm, &stmt.right, stmt.span, scope)?;
+
+        Ok(Self::bin_op(stmt.op, &left, &right, realm)?)
+    }
+
+    /// Applies `op` to the already evaluated operands, this is kept out of
+    /// `run_bin` so the operators don't blow up its stack frame, which is
+    /// part of every recursion.
+    pub fn bin_op(op: BinaryOp, left: &Value, right: &Value, realm: &mut Realm) -> Res<Value> {
+        Ok(match op {
+            BinaryOp::EqEq => Value::Boolean(left.normal_eq(right, realm)?),
+            BinaryOp::NotEq => Value::Boolean(!left.normal_eq(right, realm)?),
+            BinaryOp::EqEqEq => Value::Boolean(left == right),
+            BinaryOp::NotEqEq => Value::Boolean(left != right),
+            BinaryOp::Lt => Value::Boolean(matches!(
+                left.relational_cmp(right, realm)?,
+                Some(Ordering::Less)
+            )),
+            BinaryOp::LtEq => Value::Boolean(matches!(
+                left.relational_cmp(right, realm)?,
+                Some(Ordering::Less | Ordering::Equal)
+            )),
+            BinaryOp::Gt => Value::Boolean(matches!(
+                left.relational_cmp(right, realm)?,
+                Some(Ordering::Greater)
+            )),
+            BinaryOp::GtEq => Value::Boolean(matches!(
+                left.relational_cmp(right, realm)?,
+                Some(Ordering::Greater | Ordering::Equal)
+            )),
+            BinaryOp::LShift => left.shl(right, realm)?,
+            BinaryOp::RShift => left.shr(right, realm)?,
+            BinaryOp::ZeroFillRShift => left.ushr(right, realm)?,
+            BinaryOp::Add => left.add(right, realm)?,
+            BinaryOp::Sub => left.sub(right, realm)?,
+            BinaryOp::Mul => left.mul(right, realm)?,
+            BinaryOp::Div => left.div(right, realm)?,
+            BinaryOp::Mod => left.rem(right, realm)?,
+            BinaryOp::BitOr => left.or(right, realm)?,
+            BinaryOp::BitXor => left.xor(right, realm)?,
+            BinaryOp::BitAnd => left.and(right, realm)?,
+            BinaryOp::InstanceOf => left.instance_of(right, realm)?.into(),
+            BinaryOp::Exp => left.exp(right, realm)?,
+            BinaryOp::In => right.has_key(left, realm)?.into(),
+            BinaryOp::LogicalOr | BinaryOp::LogicalAnd | BinaryOp::NullishCoalescing => {
+                return Err(Error::new("short-circuiting operator without its operands"));
             }
         })
     }
@@ -24,72 +24,77 @@ impl Interpreter {
                 Self::run_call_on(realm, &callee, this, &stmt.args, stmt.span, scope)
             }
 
-            Callee::Super(sup) => {
-                let this = scope.this()?;
-                let class = this
-                    .downcast::<ClassInstance>()?
-                    .ok_or(Error::ty("`super` can only be used in class constructor"))?;
+            Callee::Super(_) => Self::run_super_call(realm, stmt, scope),
+            Callee::Import(_) => Self::run_import_call(realm, stmt, scope),
+        }
+    }
+
+    // `super(...)` and `import(...)` have their own functions, so they don't
+    // add to the stack frame of `run_call`, which is part of every recursion.
+    fn run_super_call(realm: &mut Realm, stmt: &CallExpr, scope: &mut Scope) -> ValueResult {
+        let this = scope.this()?;
+        let class = this
+            .downcast::<ClassInstance>()?
+            .ok_or(Error::ty("`super` can only be used in class constructor"))?;
 
-                let proto = class.prototype(realm)?.to_object()?;
+        let proto = class.prototype(realm)?.to_object()?;
 
-                let sup = proto.prototype(realm)?;
+        let sup = proto.prototype(realm)?;
 
-                let constructor = sup.to_object()?.get("constructor", realm)?;
+        let constructor = sup.to_object()?.get("constructor", realm)?;
 
-                let constructor = constructor.as_object()?;
+        let constructor = constructor.as_object()?;
 
-                let mut values = Vec::with_capacity(stmt.args.len());
+        let mut values = Vec::with_capacity(stmt.args.len());
 
-                for arg in &stmt.args {
-                    let value = Self::run_expr(realm, &arg.expr, arg.span(), scope)?;
+        for arg in &stmt.args {
+            let value = Self::run_expr(realm, &arg.expr, arg.span(), scope)?;
 
-                    if arg.spread.is_some() {
-                        let iter = ValueIterator::new(&value, realm)?;
+            if arg.spread.is_some() {
+                let iter = ValueIterator::new(&value, realm)?;
 
-                        while let Some(value) = iter.next(realm)? {
-                            values.push(value);
-                        }
-                    } else {
-                        values.push(value);
-                    }
+                while let Some(value) = iter.next(realm)? {
+                    values.push(value);
                 }
+            } else {
+                values.push(value);
+            }
+        }
 
-                //TODO: we somehow need to run the constructor ON the super class
-                let instance = constructor
-                    .construct(values, realm) //In strict mode, this is undefined
-                    .map_err(|mut e| {
-                        e.attach_function_stack(constructor.name(), get_location(stmt.span, scope));
+        //TODO: we somehow need to run the constructor ON the super class
+        let instance = constructor
+            .construct(values, realm) //In strict mode, this is undefined
+            .map_err(|mut e| {
+                e.attach_function_stack(constructor.name(), get_location(stmt.span, scope));
 
-                        e
-                    })?;
+                e
+            })?;
 
-                *class.inner.try_borrow_mut()? = instance;
+        *class.inner.try_borrow_mut()? = instance;
 
-                Ok(Value::Undefined)
-            }
+        Ok(Value::Undefined)
+    }
 
-            Callee::Import(import) => {
-                let prom = DynamicImport::new_with_expr_callback(
-                    |realm| {
-                        if stmt.args.is_empty() {
-                            return Err(Error::ty("import() requires at least one argument"));
-                        }
+    fn run_import_call(realm: &mut Realm, stmt: &CallExpr, scope: &mut Scope) -> ValueResult {
+        let prom = DynamicImport::new_with_expr_callback(
+            |realm| {
+                if stmt.args.is_empty() {
+                    return Err(Error::ty("import() requires at least one argument"));
+                }
 
-                        let module_name =
-                            Self::run_expr(realm, &stmt.args[0].expr, stmt.args[0].span(), scope)?;
+                let module_name =
+                    Self::run_expr(realm, &stmt.args[0].expr, stmt.args[0].span(), scope)?;
 
-                        let name = module_name.to_string(realm)?;
-                        let path = scope.get_current_path()?;
+                let name = module_name.to_string(realm)?;
+                let path = scope.get_current_path()?;
 
-                        Ok((name, path))
-                    },
-                    |source, path, realm| Self::run_module_source(&source, path, realm),
-                    realm,
-                )?;
+                Ok((name, path))
+            },
+            |source, path, realm| Self::run_module_source(&source, path, realm),
+            realm,
+        )?;
 
-                Ok(prom.into())
-            }
-        }
+        Ok(prom.into())
     }
 
     pub fn run_call_on(
@@ -1,4 +1,5 @@
 use crate::Interpreter;
+use swc_common::Spanned;
 use swc_ecma_ast::CondExpr;
 use yavashark_env::scope::Scope;
 use yavashark_env::{Realm, RuntimeResult};
@@ -7,10 +8,14 @@ impl Interpreter {
     pub fn run_cond(realm: &mut Realm, stmt: &CondExpr, scope: &mut Scope) -> RuntimeResult {
         let test = Self::run_expr(realm, &stmt.test, stmt.span, scope)?;
 
-        if test.is_truthy() {
-            Self::run_expr(realm, &stmt.cons, stmt.span, scope)
+        let arm = if test.is_truthy() {
+            &stmt.cons
         } else {
-            Self::run_expr(realm, &stmt.alt, stmt.span, scope)
-        }
+            &stmt.alt
+        };
+
+        realm.cover(scope, arm.span().into());
+
+        Self::run_expr(realm, arm, stmt.span, scope)
     }
 }
@@ -6,7 +6,7 @@ use yavashark_env::{ControlFlow, Realm, RuntimeResult};
 impl Interpreter {
     pub fn run_ident(realm: &mut Realm, stmt: &Ident, scope: &mut Scope) -> RuntimeResult {
         let ident = stmt.sym.as_str();
-        let value = scope.resolve(ident, realm)?;
+        let value = scope.resolve_ident(stmt, realm)?;
         value.map_or_else(
             || {
                 Err(ControlFlow::error_reference(format!(
@@ -17,3 +17,94 @@ impl Interpreter {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use yavashark_env::{Value, test_eval};
+
+    #[test]
+    fn resolved_identifiers() {
+        test_eval!(
+            r"
+            function counter() {
+                let count = 0;
+                return () => {
+                    count = count + 1;
+                    return count;
+                };
+            }
+
+            function shadow(x) {
+                {
+                    let x = 2;
+                    x = x + 1;
+                }
+                return x;
+            }
+
+            let a = counter();
+            let b = counter();
+            a();
+            a();
+            b();
+            a() + b() + shadow(5) + shadow(7)
+            ",
+            0,
+            Vec::<Vec<Value>>::new(),
+            Value::Number(17.0)
+        );
+    }
+
+    #[test]
+    fn resolved_const_assignment() {
+        test_eval!(
+            r"
+            function f() {
+                const c = 1;
+                try {
+                    c = 2;
+                } catch (e) {
+                    return c + 1;
+                }
+                return 0;
+            }
+
+            f() + f()
+            ",
+            0,
+            Vec::<Vec<Value>>::new(),
+            Value::Number(4.0)
+        );
+    }
+
+    #[test]
+    fn resolved_identifiers_in_compiled_functions() {
+        // generators are compiled to bytecode when the VM is enabled, the
+        // compiler binds their identifiers through the same resolution
+        test_eval!(
+            r"
+            function make() {
+                let count = 0;
+
+                function* gen(step) {
+                    let local = step;
+
+                    while (count < 6) {
+                        count = count + local;
+                        yield count;
+                    }
+                }
+
+                return [gen, () => count];
+            }
+
+            let [gen, get] = make();
+            let it = gen(2);
+            it.next().value + it.next().value + it.next().value + get()
+            ",
+            0,
+            Vec::<Vec<Value>>::new(),
+            Value::Number(18.0)
+        );
+    }
+}
@@ -51,13 +51,15 @@ impl Interpreter {
             Expr::Ident(i) => {
                 let name = i.sym.as_str();
                 let value = scope
-                    .resolve(name, realm)?
+                    .resolve_ident(i, realm)?
                     .ok_or_else(|| Error::reference_error(format!("{name} is not defined")))?;
                 let up = update(value, stmt.op, realm)?;
 
                 let ret = if stmt.prefix { up.0.copy() } else { up.1 };
 
-                scope.update(name, up.0, realm)?;
+                if !scope.try_update_ident(i, &up.0, realm)? {
+                    scope.update(name, up.0, realm)?;
+                }
 
                 Ok(ret)
             }
@@ -28,6 +28,8 @@ impl Interpreter {
                 }
             }
 
+            realm.cover(scope, case.span.into());
+
             match Self::run_statements(realm, &case.cons, scope) {
                 Err(ControlFlow::Break(_)) => return Ok(ret.unwrap_or(Value::Undefined)),
                 Err(e) => return Err(e),
@@ -45,6 +47,8 @@ impl Interpreter {
             && let Some(default_index) = default
         {
             for case in stmt.cases.iter().skip(default_index) {
+                realm.cover(scope, case.span.into());
+
                 match Self::run_statements(realm, &case.cons, scope) {
                     Err(ControlFlow::Break(_)) => return Ok(ret.unwrap_or(Value::Undefined)),
                     Err(e) => return Err(e),
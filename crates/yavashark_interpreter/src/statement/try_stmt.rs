@@ -1,15 +1,21 @@
 use crate::Interpreter;
 use std::iter;
 use swc_ecma_ast::{ObjectPatProp, Pat, PropName, TryStmt};
+use yavashark_env::debugger;
 use yavashark_env::error::ErrorKind;
 use yavashark_env::error_obj::ErrorObj;
 use yavashark_env::scope::Scope;
-use yavashark_env::{Realm, RuntimeResult, Value};
+use yavashark_env::{ControlFlow, Realm, RuntimeResult, Value};
 
 impl Interpreter {
     pub fn run_try(realm: &mut Realm, stmt: &TryStmt, scope: &mut Scope) -> RuntimeResult {
         let res = catch(realm, stmt, scope);
 
+        // a terminated script must not run any more code
+        if matches!(&res, Err(ControlFlow::Error(e)) if e.is_terminated()) {
+            return res;
+        }
+
         if let Some(finalizer) = &stmt.finalizer {
             let _ = Self::run_block(realm, finalizer, scope)?;
         }
@@ -19,10 +25,20 @@ impl Interpreter {
 }
 
 fn catch(realm: &mut Realm, stmt: &TryStmt, scope: &mut Scope) -> RuntimeResult {
-    let try_block = Interpreter::run_block(realm, &stmt.block, scope);
+    let try_block = if stmt.handler.is_some() {
+        debugger::try_block(realm, |realm| {
+            Interpreter::run_block(realm, &stmt.block, scope)
+        })
+    } else {
+        Interpreter::run_block(realm, &stmt.block, scope)
+    };
 
     if let Err(e) = try_block {
         let err = e.get_error()?;
+        if err.is_terminated() {
+            return Err(err.into());
+        }
+
         if let Some(catch) = &stmt.handler {
             let scope = &mut Scope::with_parent(scope)?;
             if let Some(param) = &catch.param {
@@ -51,8 +67,24 @@ fn catch(realm: &mut Realm, stmt: &TryStmt, scope: &mut Scope) -> RuntimeResult
 
 #[cfg(test)]
 mod tests {
+    use crate::eval::InterpreterEval;
+    use std::path::PathBuf;
     use yavashark_env::error::ErrorKind;
-    use yavashark_env::{Value, test_eval};
+    use yavashark_env::realm::limits::Limits;
+    use yavashark_env::{Realm, Value, ValueResult, test_eval};
+    use yavashark_garbage::usage::heap_bytes;
+
+    fn eval_limited(realm: &mut Realm, code: &str) -> ValueResult {
+        realm.eval_script(code, PathBuf::from("limits.js"))
+    }
+
+    fn limited_realm(limits: Limits) -> Realm {
+        let mut realm = Realm::new().unwrap();
+        realm.set_eval(InterpreterEval, false).unwrap();
+        realm.set_limits(limits);
+
+        realm
+    }
 
     #[test]
     fn try_stmt() {
@@ -197,4 +229,87 @@ mod tests {
 
         assert_eq!(state.send_called, 1);
     }
+
+    #[test]
+    fn step_limit_is_not_catchable() {
+        let mut realm = limited_realm(Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        });
+
+        let result = eval_limited(
+            &mut realm,
+            r#"
+            var ran = "none";
+            try {
+                while (true) {}
+            } catch (e) {
+                ran = "catch";
+            } finally {
+                ran = "finally";
+            }
+            "#,
+        );
+
+        assert!(result.unwrap_err().is_terminated());
+
+        realm.reset_limits();
+
+        assert_eq!(
+            eval_limited(&mut realm, "ran"),
+            Ok(Value::String("none".into()))
+        );
+    }
+
+    #[test]
+    fn interrupt_from_another_thread() {
+        let mut realm = limited_realm(Limits::default());
+        let interrupt = realm.interrupt_handle();
+
+        std::thread::spawn(move || interrupt.interrupt())
+            .join()
+            .unwrap();
+
+        let result = eval_limited(&mut realm, "try { while (true) {} } catch {}");
+
+        assert!(result.unwrap_err().is_terminated());
+    }
+
+    #[test]
+    fn heap_limit_counts_array_elements() {
+        let mut realm = limited_realm(Limits::default());
+
+        realm.set_limits(Limits {
+            max_heap_bytes: Some(heap_bytes() + (8 << 20)),
+            ..Limits::default()
+        });
+
+        assert_eq!(
+            eval_limited(&mut realm, "new Array(1000).fill(0).length"),
+            Ok(Value::Number(1000.0))
+        );
+
+        // the array is a single box, only its elements go over the limit
+        let result = eval_limited(&mut realm, "var a = new Array(1 << 21).fill(0); a.length");
+
+        assert!(result.unwrap_err().is_terminated());
+    }
+
+    #[test]
+    fn call_depth_throws_range_error() {
+        let mut realm = limited_realm(Limits {
+            max_call_depth: Some(100),
+            ..Limits::default()
+        });
+
+        let result = eval_limited(
+            &mut realm,
+            r"
+            function f() { return f(); }
+            try { f() } catch (e) { e.name }
+            ",
+        );
+
+        assert_eq!(result, Ok(Value::String("RangeError".into())));
+    }
 }
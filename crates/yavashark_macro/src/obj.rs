@@ -3,7 +3,7 @@ pub mod args;
 use crate::config::Config;
 use crate::custom_props::{Act, List, match_list, match_prop};
 use crate::mutable_region::MutableRegion;
-use crate::obj::args::{ItemArgs, ObjArgs};
+use crate::obj::args::{GcItem, ItemArgs, ObjArgs};
 use darling::FromMeta;
 use darling::ast::NestedMeta;
 use proc_macro::TokenStream as TokenStream1;
@@ -123,6 +123,28 @@ pub fn object(attrs: TokenStream1, item: TokenStream1) -> TokenStream1 {
         TokenStream::new()
     };
 
+    // objects with direct properties or a parent object don't keep all of
+    // their named properties in `inner.object`
+    let shaped = if direct.is_empty() && args.extends.is_none() {
+        let mut_object = &conf.mut_object;
+
+        quote! {
+            fn shaped_object(&self) -> ::core::option::Option<::core::cell::Ref<'_, #mut_object>> {
+                let inner = self.inner.try_borrow().ok()?;
+                ::core::option::Option::Some(::core::cell::Ref::map(inner, |inner| &inner.object))
+            }
+
+            fn shaped_object_mut(&self) -> ::core::option::Option<::core::cell::RefMut<'_, #mut_object>> {
+                let inner = self.inner.try_borrow_mut().ok()?;
+                ::core::option::Option::Some(::core::cell::RefMut::map(inner, |inner| &mut inner.object))
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+
+    let has_extends = args.extends.is_some();
+
     let (obj_path, inner_drop, inner_borrow, inner_borrow_mut) = if let Some(extends) = args.extends
     {
         fields.named.push(syn::Field {
@@ -182,45 +204,112 @@ pub fn object(attrs: TokenStream1, item: TokenStream1) -> TokenStream1 {
         quote! {}
     };
 
-    // let custom_refs = if item_args.gc.is_empty() {
-    //     TokenStream::new()
-    // } else {
-    //     let len = item_args.gc.len();
-    //
-    //     let refs = item_args
-    //         .gc
-    //         .into_iter()
-    //         .map(|gc| {
-    //             let mut func = if gc.ty {
-    //                 if gc.multi {
-    //                     Ident::new("gc_ref_multi", Span::call_site())
-    //                 } else {
-    //                     Ident::new("gc_ref", Span::call_site())
-    //                 }
-    //             } else if gc.multi {
-    //                 Ident::new("gc_untyped_ref_multi", Span::call_site())
-    //             } else {
-    //                 Ident::new("gc_untyped_ref", Span::call_site())
-    //             };
-    //
-    //             if let Some(f) = gc.func {
-    //                 func = f;
-    //             }
-    //
-    //             let field = gc.name;
-    //
-    //             quote! {
-    //                 if let Some(r) = self.#field.#func() {
-    //                     refs.push(r);
-    //                 }
-    //             }
-    //         })
-    //         .collect::<TokenStream>();
-    //
-    //     quote! {
-    //         //TODO
-    //     }
-    // };
+    let gc_refs = {
+        let collect = |gc: &GcItem, path: TokenStream| {
+            // `gc_untyped_ref` is called through its trait, since the field
+            // might also implement `ConstructorFn`, which has a method with
+            // the same name
+            let call = |item: TokenStream| match &gc.func {
+                Some(func) => quote! { #item.#func() },
+                None if gc.ty => quote! { #item.gc_ref() },
+                None if gc.multi => {
+                    quote! { #env::value::CustomGcRefUntyped::gc_untyped_ref(#item) }
+                }
+                None => quote! { #env::value::CustomGcRefUntyped::gc_untyped_ref(&#item) },
+            };
+
+            if gc.multi {
+                let call = call(quote! { item });
+
+                quote! {
+                    for item in &#path {
+                        if let ::core::option::Option::Some(r) = #call {
+                            refs.push(r);
+                        }
+                    }
+                }
+            } else {
+                let call = call(path);
+
+                quote! {
+                    if let ::core::option::Option::Some(r) = #call {
+                        refs.push(r);
+                    }
+                }
+            }
+        };
+
+        let (mutable, own): (Vec<_>, Vec<_>) = item_args
+            .gc
+            .iter()
+            .partition(|gc| mutable_region.contains(&gc.name));
+
+        let direct_props = direct.iter().map(|item| {
+            let field = &item.field;
+
+            quote! {
+                for value in [&region.#field.value, &region.#field.get, &region.#field.set] {
+                    if let ::core::option::Option::Some(r) = value.gc_ref() {
+                        refs.push(r);
+                    }
+                }
+            }
+        });
+
+        let mutable = mutable
+            .into_iter()
+            .map(|gc| {
+                let field = &gc.name;
+                collect(gc, quote! { region.#field })
+            })
+            .chain(direct_props)
+            .collect::<TokenStream>();
+
+        let own = own
+            .into_iter()
+            .map(|gc| {
+                let field = &gc.name;
+                collect(gc, quote! { self.#field })
+            })
+            .collect::<TokenStream>();
+
+        // the collector asks for references at any allocation, even while the
+        // object is borrowed mutably, so nothing here may panic
+        let base = if has_extends && mutable.is_empty() {
+            quote! {
+                let mut refs = self.extends.gc_refs();
+            }
+        } else if has_extends {
+            quote! {
+                let mut refs = self.extends.gc_refs();
+
+                if let ::core::result::Result::Ok(region) = self.inner.try_borrow() {
+                    #mutable
+                }
+            }
+        } else {
+            quote! {
+                let mut refs = match self.inner.try_borrow() {
+                    ::core::result::Result::Ok(region) => {
+                        let mut refs = region.object.gc_refs();
+                        #mutable
+                        refs
+                    }
+                    ::core::result::Result::Err(_) => ::std::vec::Vec::new(),
+                };
+            }
+        };
+
+        quote! {
+            #[cfg(feature = "actual_gc")]
+            #[allow(unused_mut)]
+            fn gc_refs(&self) -> ::std::vec::Vec<yavashark_garbage::GcRef<#env::value::BoxedObj>> {
+                #base
+                #own
+                refs
+            }
+        }
+    };
 
     let constructor = if args.constructor {
         quote! {
@@ -452,6 +541,8 @@ pub fn object(attrs: TokenStream1, item: TokenStream1) -> TokenStream1 {
 
             #downcast
 
+            #shaped
+
             fn is_extensible(&self) -> bool {
                 #inner_borrow
                 #obj_path.is_extensible()
@@ -482,11 +573,7 @@ pub fn object(attrs: TokenStream1, item: TokenStream1) -> TokenStream1 {
                 #obj_path.seal()
             }
 
-            #[cfg(feature = "actual_gc")]
-            fn gc_refs(&self) -> ::std::vec::Vec<yavashark_garbage::GcRef<#env::value::BoxedObj>> {
-                #inner_borrow
-                #obj_path.gc_refs()
-            }
+            #gc_refs
         }
     };
 
@@ -95,7 +95,6 @@ impl FromMeta for DirectItem {
 }
 
 pub struct ItemArgs {
-    #[allow(unused)]
     pub gc: Vec<GcItem>,
     pub mutable_region: Vec<Ident>,
     pub primitive: Option<(Ident, TokenStream)>,
@@ -116,27 +115,24 @@ impl ItemArgs {
                     let mut multi = false;
 
                     if !matches!(attr.meta, syn::Meta::Path(_))
-                        && let Err(e) =
-                            attr.parse_nested_meta(|meta| {
-                                if meta.path.is_ident("untyped") {
-                                    ty = false;
-                                    return Ok(());
-                                }
-
-                                if meta.path.is_ident("func") {
-                                    func = Some(meta.path.get_ident().cloned().ok_or(
-                                        syn::Error::new(meta.path.span(), "Expected ident"),
-                                    )?);
-                                    return Ok(());
-                                }
-
-                                if meta.path.is_ident("multi") {
-                                    multi = true;
-                                    return Ok(());
-                                }
-
-                                Err(syn::Error::new(meta.path.span(), "Unknown attribute"))
-                            })
+                        && let Err(e) = attr.parse_nested_meta(|meta| {
+                            if meta.path.is_ident("untyped") {
+                                ty = false;
+                                return Ok(());
+                            }
+
+                            if meta.path.is_ident("func") {
+                                func = Some(meta.value()?.parse()?);
+                                return Ok(());
+                            }
+
+                            if meta.path.is_ident("multi") {
+                                multi = true;
+                                return Ok(());
+                            }
+
+                            Err(syn::Error::new(meta.path.span(), "Unknown attribute"))
+                        })
                     {
                         err = Some(e);
                         return false;
@@ -215,7 +211,6 @@ impl ItemArgs {
     }
 }
 
-#[allow(unused)]
 pub struct GcItem {
     pub name: Ident,
     pub ty: bool,
@@ -7,6 +7,9 @@ use std::collections::HashMap;
 use std::io::{self, Write};
 use std::mem;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -15,17 +18,35 @@ pub struct FrameId(u64);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StringId(i64);
 
+/// What a sample knows about a frame. The offset is the byte offset of the
+/// statement the frame is currently executing, it is only tracked while
+/// sampling and resolved to a line and column when the profile is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FrameKey {
+    name: StringId,
+    file: Option<StringId>,
+    offset: Option<u32>,
+}
+
 #[derive(Debug, Clone)]
 struct Frame {
     id: FrameId,
-    parent: Option<FrameId>,
-    fn_name: StringId,
+    key: FrameKey,
     start: Instant,
 }
 
+/// A frame that left the stack without finishing, like a suspended async
+/// function or generator. Resuming it with [`Profile::resume_frame`] attributes
+/// the work to the same function again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuspendedFrame {
+    key: FrameKey,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct SampleKey {
-    stack: Vec<StringId>,
+    /// Leaf first.
+    stack: Vec<FrameKey>,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -34,27 +55,73 @@ struct SampleValue {
     nanos: u64,
 }
 
+#[derive(Debug, Clone)]
+struct Sampler {
+    interval: Duration,
+    due: Arc<AtomicBool>,
+    last: Instant,
+}
+
+impl Sampler {
+    fn start(interval: Duration) -> Self {
+        let due = Arc::new(AtomicBool::new(false));
+        let timer = Arc::downgrade(&due);
+
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+
+                let Some(due) = timer.upgrade() else {
+                    break;
+                };
+
+                due.store(true, Ordering::Relaxed);
+            }
+        });
+
+        Self {
+            interval,
+            due,
+            last: Instant::now(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Profile {
     start_time: SystemTime,
     root_start: Instant,
     next_frame_id: FrameId,
-    roots: Vec<FrameId>,
-    active_frames: HashMap<FrameId, Frame>, //TODO: this could probably also just be a stack. Not fully sure about async though
-    finished_samples: HashMap<SampleKey, SampleValue>,
+    /// The first frame is the top level code and never ends.
+    stack: Vec<Frame>,
+    samples: HashMap<SampleKey, SampleValue>,
+    sampler: Option<Sampler>,
     strings: StringTable,
 }
 
 impl Default for Profile {
     fn default() -> Self {
+        let mut strings = StringTable::default();
+        let root_start = Instant::now();
+
+        let program = Frame {
+            id: FrameId(0),
+            key: FrameKey {
+                name: StringId(strings.intern("(program)".to_owned())),
+                file: None,
+                offset: None,
+            },
+            start: root_start,
+        };
+
         Self {
             start_time: SystemTime::now(),
-            root_start: Instant::now(),
+            root_start,
             next_frame_id: FrameId(1),
-            roots: Vec::new(),
-            active_frames: HashMap::new(),
-            finished_samples: HashMap::new(),
-            strings: StringTable::default(),
+            stack: vec![program],
+            samples: HashMap::new(),
+            sampler: None,
+            strings,
         }
     }
 }
@@ -65,49 +132,128 @@ impl Profile {
         Self::default()
     }
 
+    /// Switches from recording every call to recording the stack every
+    /// `interval`. The samples are taken at the next safepoint after the
+    /// interval elapsed, see [`Self::sample_if_due`].
+    pub fn start_sampling(&mut self, interval: Duration) {
+        self.sampler = Some(Sampler::start(interval));
+    }
+
+    #[must_use]
+    pub const fn is_sampling(&self) -> bool {
+        self.sampler.is_some()
+    }
+
+    /// The sampling interval, if sampling.
+    #[must_use]
+    pub fn interval(&self) -> Option<Duration> {
+        self.sampler.as_ref().map(|sampler| sampler.interval)
+    }
+
     pub fn add_frame(&mut self, fn_name: String, start: Instant) -> FrameId {
+        let key = FrameKey {
+            name: StringId(self.strings.intern(fn_name)),
+            file: None,
+            offset: None,
+        };
+
+        self.push_frame(key, start)
+    }
+
+    fn push_frame(&mut self, key: FrameKey, start: Instant) -> FrameId {
         let id = self.next_frame_id;
         self.next_frame_id.0 += 1;
-        let parent = self.roots.last().copied();
 
-        let fn_name = StringId(self.strings.intern(fn_name));
+        self.stack.push(Frame { id, key, start });
 
-        self.active_frames.insert(
-            id,
-            Frame {
-                id,
-                parent,
-                fn_name,
-                start,
-            },
-        );
-        self.roots.push(id);
         id
     }
 
     pub fn end_frame(&mut self, frame_id: FrameId, end: Instant) {
-        let Some(frame) = self.active_frames.remove(&frame_id) else {
+        let Some(index) = self.stack.iter().rposition(|frame| frame.id == frame_id) else {
             return;
         };
 
-        if frame.id != frame_id {
+        if index == 0 {
+            return;
+        }
+
+        if !self.is_sampling() {
+            let frame = &self.stack[index];
+            let nanos = end.duration_since(frame.start).as_nanos() as u64;
+
+            let stack = self.stack[1..=index]
+                .iter()
+                .rev()
+                .map(|frame| FrameKey {
+                    offset: None,
+                    file: None,
+                    ..frame.key
+                })
+                .collect();
+
+            let sample = self.samples.entry(SampleKey { stack }).or_default();
+            sample.count += 1;
+            sample.nanos += nanos;
+        }
+
+        // frames above this one didn't end properly, they can't be resumed anymore
+        self.stack.truncate(index);
+    }
+
+    /// The function that is currently running, to resume it later after it
+    /// suspended.
+    #[must_use]
+    pub fn top_frame(&self) -> Option<SuspendedFrame> {
+        if self.stack.len() <= 1 {
+            return None;
+        }
+
+        self.stack
+            .last()
+            .map(|frame| SuspendedFrame { key: frame.key })
+    }
+
+    pub fn resume_frame(&mut self, frame: &SuspendedFrame, start: Instant) -> FrameId {
+        self.push_frame(frame.key, start)
+    }
+
+    /// Records where the current frame is. Only does something while sampling,
+    /// `file` is only called the first time a frame reports a position.
+    pub fn set_position(&mut self, offset: u32, file: impl FnOnce() -> Option<String>) {
+        if self.sampler.is_none() {
             return;
         }
 
-        if self.roots.last().copied() == Some(frame_id) {
-            self.roots.pop();
-        } else if let Some(index) = self.roots.iter().rposition(|id| *id == frame_id) {
-            self.roots.remove(index);
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+
+        frame.key.offset = Some(offset);
+
+        if frame.key.file.is_none() {
+            frame.key.file = file().map(|file| StringId(self.strings.intern(file)));
+        }
+    }
+
+    /// Records the current stack if the sampling interval elapsed. This is
+    /// meant to be called often, it's only an atomic load when nothing is due.
+    pub fn sample_if_due(&mut self) {
+        let Some(sampler) = &mut self.sampler else {
+            return;
+        };
+
+        if !sampler.due.swap(false, Ordering::Relaxed) {
+            return;
         }
 
-        let elapsed = end.duration_since(frame.start);
-        let nanos = elapsed.as_nanos() as u64;
-        let stack = self.stack_for_frame(&frame);
+        let now = Instant::now();
+        let nanos = now.duration_since(sampler.last).as_nanos() as u64;
+        sampler.last = now;
 
-        let sample = self
-            .finished_samples
-            .entry(SampleKey { stack })
-            .or_default();
+        let stack = self.stack.iter().rev().map(|frame| frame.key).collect();
+
+        let sample = self.samples.entry(SampleKey { stack }).or_default();
         sample.count += 1;
         sample.nanos += nanos;
     }
@@ -117,25 +263,77 @@ impl Profile {
         self.root_start.elapsed()
     }
 
-    fn stack_for_frame(&self, frame: &Frame) -> Vec<StringId> {
-        let mut stack = vec![frame.fn_name];
-        let mut parent = frame.parent;
+    #[must_use]
+    pub fn take(&mut self) -> Self {
+        mem::take(self)
+    }
+
+    /// The value of each sample for formats with a single value: the number of
+    /// samples when sampling and the self time in nanoseconds otherwise,
+    /// since instrumented stacks include the time of their callees.
+    fn self_values(&self) -> HashMap<&SampleKey, u64> {
+        if self.is_sampling() {
+            return self
+                .samples
+                .iter()
+                .map(|(key, value)| (key, value.count))
+                .collect();
+        }
+
+        let mut values = self
+            .samples
+            .iter()
+            .map(|(key, value)| (key, value.nanos))
+            .collect::<HashMap<_, _>>();
 
-        while let Some(parent_id) = parent {
-            let Some(parent_frame) = self.active_frames.get(&parent_id) else {
-                break;
+        for (key, value) in &self.samples {
+            let Some((_, parent)) = key.stack.split_first() else {
+                continue;
             };
 
-            stack.push(parent_frame.fn_name);
-            parent = parent_frame.parent;
+            let parent = SampleKey {
+                stack: parent.to_vec(),
+            };
+
+            if let Some(parent) = values.get_mut(&parent) {
+                *parent = parent.saturating_sub(value.nanos);
+            }
         }
 
-        stack
+        values
     }
+}
 
-    #[must_use]
-    pub fn take(&mut self) -> Self {
-        mem::take(self)
+/// Resolves byte offsets to 1-based lines and columns by reading the source
+/// files, each file is only read once.
+#[derive(Default)]
+struct SourceMap {
+    files: HashMap<StringId, Option<(String, Vec<usize>)>>,
+}
+
+impl SourceMap {
+    fn position(&mut self, file: StringId, path: &str, offset: u32) -> Option<(u32, u32)> {
+        let (source, lines) = self
+            .files
+            .entry(file)
+            .or_insert_with(|| {
+                let source = std::fs::read_to_string(path).ok()?;
+
+                let lines = std::iter::once(0)
+                    .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                    .collect();
+
+                Some((source, lines))
+            })
+            .as_ref()?;
+
+        let offset = offset as usize;
+        let line = lines
+            .partition_point(|start| *start <= offset)
+            .checked_sub(1)?;
+        let col = source.get(lines[line]..offset)?.chars().count();
+
+        Some((line as u32 + 1, col as u32 + 1))
     }
 }
 
@@ -146,6 +344,7 @@ pub trait ProfileWriter: Send {
 #[derive(Debug, Clone)]
 pub enum ProfileWriterKind {
     Pprof,
+    Collapsed,
 }
 
 impl ProfileWriterKind {
@@ -156,6 +355,7 @@ impl ProfileWriterKind {
             .unwrap_or_default()
         {
             "pb" | "gz" => Ok(Self::Pprof),
+            "folded" | "collapsed" | "txt" => Ok(Self::Collapsed),
             other => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 format!("unsupported profile output format: {other}"),
@@ -178,41 +378,50 @@ impl ProfileWriter for PprofWriter {
 impl PprofWriter {
     fn build_pprof(mut profile: Profile) -> io::Result<PProfProfile> {
         let mut strings = mem::take(&mut profile.strings);
+        let mut sources = SourceMap::default();
         let mut locations = Vec::new();
         let mut functions = Vec::new();
         let mut samples = Vec::new();
         let mut function_ids = HashMap::new();
         let mut location_ids = HashMap::new();
 
-        for (sample_key, sample_value) in &profile.finished_samples {
+        for (sample_key, sample_value) in &profile.samples {
             let mut location_id = Vec::with_capacity(sample_key.stack.len());
 
-            for name in &sample_key.stack {
-                let function_id = *function_ids.entry(name).or_insert_with(|| {
-                    let id = (functions.len() + 1) as u64;
-
-                    functions.push(Function {
-                        id,
-                        name: name.0,
-                        system_name: name.0,
-                        filename: 0,
-                        start_line: 0,
-                    });
-
-                    id
-                });
-
-                let location = *location_ids.entry(function_id).or_insert_with(|| {
+            for frame in &sample_key.stack {
+                let filename = frame.file.map_or(0, |file| file.0);
+
+                let function_id =
+                    *function_ids
+                        .entry((frame.name, frame.file))
+                        .or_insert_with(|| {
+                            let id = (functions.len() + 1) as u64;
+
+                            functions.push(Function {
+                                id,
+                                name: frame.name.0,
+                                system_name: frame.name.0,
+                                filename,
+                                start_line: 0,
+                            });
+
+                            id
+                        });
+
+                let line = frame
+                    .file
+                    .zip(frame.offset)
+                    .and_then(|(file, offset)| sources.position(file, strings.get(file)?, offset))
+                    .map_or(0, |(line, _)| i64::from(line));
+
+                let location = *location_ids.entry((function_id, line)).or_insert_with(|| {
                     let id = (locations.len() + 1) as u64;
 
                     locations.push(Location {
                         id,
                         mapping_id: 0,
                         address: 0,
-                        line: vec![Line {
-                            function_id,
-                            line: 0,
-                        }],
+                        line: vec![Line { function_id, line }],
                         is_folded: false,
                     });
 
@@ -244,6 +453,9 @@ impl PprofWriter {
             ty: wall_type,
             unit: nanos_unit,
         };
+        let period = profile
+            .interval()
+            .map_or(1, |interval| interval.as_nanos() as i64);
         let string_table = strings.finish();
 
         Ok(PProfProfile {
@@ -267,13 +479,69 @@ impl PprofWriter {
             time_nanos,
             duration_nanos: profile.duration().as_nanos() as i64,
             period_type: Some(period_type),
-            period: 1,
+            period,
             comment: Vec::new(),
             default_sample_type: wall_type,
         })
     }
 }
 
+/// Writes one line per stack, root first and separated by `;`, followed by
+/// its value. This is the input format of `flamegraph.pl` and `inferno`.
+pub struct CollapsedWriter;
+
+impl ProfileWriter for CollapsedWriter {
+    fn write_profile(&mut self, profile: Profile) -> io::Result<Vec<u8>> {
+        Ok(Self::collapse(&profile).into_bytes())
+    }
+}
+
+impl CollapsedWriter {
+    fn collapse(profile: &Profile) -> String {
+        let mut sources = SourceMap::default();
+        let mut lines = Vec::new();
+
+        for (key, value) in profile.self_values() {
+            if value == 0 {
+                continue;
+            }
+
+            let stack = key
+                .stack
+                .iter()
+                .rev()
+                .map(|frame| Self::label(profile, frame, &mut sources))
+                .collect::<Vec<_>>()
+                .join(";");
+
+            lines.push(format!("{stack} {value}\n"));
+        }
+
+        lines.sort_unstable();
+        lines.concat()
+    }
+
+    fn label(profile: &Profile, frame: &FrameKey, sources: &mut SourceMap) -> String {
+        let name = profile.strings.get(frame.name).unwrap_or_default();
+        // `;` separates frames and the value follows the last space
+        let name = name.replace(';', ":");
+
+        let Some(file) = frame.file else {
+            return name;
+        };
+
+        let path = profile.strings.get(file).unwrap_or_default();
+
+        match frame
+            .offset
+            .and_then(|offset| sources.position(file, path, offset))
+        {
+            Some((line, col)) => format!("{name} ({path}:{line}:{col})"),
+            None => format!("{name} ({path})"),
+        }
+    }
+}
+
 pub struct FileProfileWriter {
     path: PathBuf,
     inner: Box<dyn ProfileWriter>,
@@ -292,6 +560,7 @@ impl FileProfileWriter {
         let kind = ProfileWriterKind::from_path(&path)?;
         let inner: Box<dyn ProfileWriter> = match kind {
             ProfileWriterKind::Pprof => Box::new(PprofWriter),
+            ProfileWriterKind::Collapsed => Box::new(CollapsedWriter),
         };
 
         Ok(Self::new(path, inner))
@@ -323,6 +592,12 @@ impl StringTable {
         self.strings.insert_full(value).0 as i64 + 1
     }
 
+    fn get(&self, id: StringId) -> Option<&str> {
+        let index = usize::try_from(id.0 - 1).ok()?;
+
+        self.strings.get_index(index).map(String::as_str)
+    }
+
     fn finish(self) -> Vec<String> {
         let mut result = Vec::with_capacity(self.strings.len() + 1);
         result.push(String::new());
@@ -330,3 +605,63 @@ impl StringTable {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapsed_instrumented_uses_self_time() {
+        let mut profile = Profile::new();
+        let start = Instant::now();
+
+        let outer = profile.add_frame("outer".to_owned(), start);
+        let inner = profile.add_frame("inner".to_owned(), start);
+        profile.end_frame(inner, start + Duration::from_nanos(30));
+        profile.end_frame(outer, start + Duration::from_nanos(100));
+
+        assert_eq!(
+            CollapsedWriter::collapse(&profile),
+            "outer 70\nouter;inner 30\n"
+        );
+    }
+
+    #[test]
+    fn collapsed_samples_resolve_lines() {
+        let path = std::env::temp_dir().join("yavashark_profiler_lines.js");
+        std::fs::write(&path, "let a;\n  foo();\n").unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let mut profile = Profile::new();
+        profile.start_sampling(Duration::from_hours(1));
+
+        let frame = profile.add_frame("foo".to_owned(), Instant::now());
+        profile.set_position(9, || Some(path.clone()));
+
+        profile
+            .sampler
+            .as_ref()
+            .unwrap()
+            .due
+            .store(true, Ordering::Relaxed);
+        profile.sample_if_due();
+
+        let suspended = profile.top_frame().unwrap();
+        profile.end_frame(frame, Instant::now());
+        assert_eq!(profile.top_frame(), None);
+
+        profile.resume_frame(&suspended, Instant::now());
+        profile
+            .sampler
+            .as_ref()
+            .unwrap()
+            .due
+            .store(true, Ordering::Relaxed);
+        profile.sample_if_due();
+
+        assert_eq!(
+            CollapsedWriter::collapse(&profile),
+            format!("(program);foo ({path}:2:3) 2\n")
+        );
+    }
+}
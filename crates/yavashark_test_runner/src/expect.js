@@ -0,0 +1,484 @@
+// The `expect` of the test runner. This is evaluated once per test file and
+// called with the native function that compares snapshots, it returns the
+// `expect` function.
+(function (matchSnapshot) {
+    "use strict";
+
+    const hasOwn = Object.prototype.hasOwnProperty;
+
+    function format(value, indent, seen) {
+        indent = indent || "";
+        seen = seen || [];
+
+        if (typeof value === "string") {
+            return JSON.stringify(value);
+        }
+
+        if (typeof value === "bigint") {
+            return value + "n";
+        }
+
+        if (typeof value === "symbol" || typeof value === "function") {
+            return typeof value === "function"
+                ? "[Function " + (value.name || "anonymous") + "]"
+                : String(value);
+        }
+
+        if (value === null || typeof value !== "object") {
+            return Object.is(value, -0) ? "-0" : String(value);
+        }
+
+        if (seen.indexOf(value) !== -1) {
+            return "[Circular]";
+        }
+
+        if (value instanceof Error) {
+            return "[" + value.name + ": " + value.message + "]";
+        }
+
+        if (value instanceof Date) {
+            return "Date " + value.toISOString();
+        }
+
+        if (value instanceof RegExp) {
+            return String(value);
+        }
+
+        const inner = indent + "  ";
+        const nested = seen.concat([value]);
+        const lines = [];
+        let open = "{";
+        let close = "}";
+        let prefix = "Object ";
+
+        if (Array.isArray(value)) {
+            open = "[";
+            close = "]";
+            prefix = "Array ";
+
+            for (let i = 0; i < value.length; i++) {
+                lines.push(inner + format(value[i], inner, nested) + ",");
+            }
+        } else if (value instanceof Map) {
+            prefix = "Map ";
+
+            value.forEach(function (v, k) {
+                lines.push(inner + format(k, inner, nested) + " => " + format(v, inner, nested) + ",");
+            });
+        } else if (value instanceof Set) {
+            prefix = "Set ";
+
+            value.forEach(function (v) {
+                lines.push(inner + format(v, inner, nested) + ",");
+            });
+        } else {
+            const proto = Object.getPrototypeOf(value);
+
+            if (proto === null) {
+                prefix = "Object ";
+            } else if (proto.constructor && proto.constructor.name && proto.constructor !== Object) {
+                prefix = proto.constructor.name + " ";
+            }
+
+            const keys = Object.keys(value).sort();
+
+            for (let i = 0; i < keys.length; i++) {
+                const key = keys[i];
+                lines.push(inner + JSON.stringify(key) + ": " + format(value[key], inner, nested) + ",");
+            }
+        }
+
+        if (lines.length === 0) {
+            return prefix + open + close;
+        }
+
+        return prefix + open + "\n" + lines.join("\n") + "\n" + indent + close;
+    }
+
+    function equals(a, b, strict, seen) {
+        if (Object.is(a, b)) {
+            return true;
+        }
+
+        if (typeof a !== "object" || typeof b !== "object" || a === null || b === null) {
+            return false;
+        }
+
+        seen = seen || [];
+
+        for (let i = 0; i < seen.length; i++) {
+            if (seen[i][0] === a && seen[i][1] === b) {
+                return true;
+            }
+        }
+
+        seen = seen.concat([[a, b]]);
+
+        if (Object.getPrototypeOf(a) !== Object.getPrototypeOf(b)) {
+            if (strict || Array.isArray(a) !== Array.isArray(b)) {
+                return false;
+            }
+        }
+
+        if (a instanceof Date && b instanceof Date) {
+            return a.getTime() === b.getTime();
+        }
+
+        if (a instanceof RegExp && b instanceof RegExp) {
+            return String(a) === String(b);
+        }
+
+        if (a instanceof Error && b instanceof Error) {
+            return a.name === b.name && a.message === b.message;
+        }
+
+        if (a instanceof Map && b instanceof Map) {
+            if (a.size !== b.size) {
+                return false;
+            }
+
+            let same = true;
+
+            a.forEach(function (v, k) {
+                if (same && (!b.has(k) || !equals(v, b.get(k), strict, seen))) {
+                    same = false;
+                }
+            });
+
+            return same;
+        }
+
+        if (a instanceof Set && b instanceof Set) {
+            if (a.size !== b.size) {
+                return false;
+            }
+
+            let same = true;
+
+            a.forEach(function (v) {
+                if (same && !b.has(v)) {
+                    same = false;
+                }
+            });
+
+            return same;
+        }
+
+        if (Array.isArray(a) && a.length !== b.length) {
+            return false;
+        }
+
+        const keys = function (obj) {
+            return Object.keys(obj).filter(function (key) {
+                return strict || obj[key] !== undefined;
+            });
+        };
+
+        const aKeys = keys(a);
+        const bKeys = keys(b);
+
+        if (aKeys.length !== bKeys.length) {
+            return false;
+        }
+
+        for (let i = 0; i < aKeys.length; i++) {
+            const key = aKeys[i];
+
+            if (!hasOwn.call(b, key) && !(strict === false && b[key] === undefined)) {
+                return false;
+            }
+
+            if (!equals(a[key], b[key], strict, seen)) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    function fail(message) {
+        throw new Error(message);
+    }
+
+    function thrown(fn) {
+        if (typeof fn !== "function") {
+            fail("expected a function, received " + format(fn));
+        }
+
+        try {
+            fn();
+        } catch (e) {
+            return { value: e };
+        }
+
+        return undefined;
+    }
+
+    function matchesError(error, expected) {
+        if (expected === undefined) {
+            return true;
+        }
+
+        const message = error !== null && typeof error === "object" ? error.message : error;
+
+        if (typeof expected === "string") {
+            return String(message).indexOf(expected) !== -1;
+        }
+
+        if (expected instanceof RegExp) {
+            return expected.test(String(message));
+        }
+
+        if (typeof expected === "function") {
+            return error instanceof expected;
+        }
+
+        if (expected instanceof Error) {
+            return message === expected.message;
+        }
+
+        return equals(error, expected, false);
+    }
+
+    // Each matcher returns whether it passed and the message for when the
+    // result isn't what the `not` flag expects.
+    const matchers = {
+        toBe: function (actual, expected) {
+            return [Object.is(actual, expected), "to be " + format(expected)];
+        },
+        toEqual: function (actual, expected) {
+            return [equals(actual, expected, false), "to equal " + format(expected)];
+        },
+        toStrictEqual: function (actual, expected) {
+            return [equals(actual, expected, true), "to strictly equal " + format(expected)];
+        },
+        toBeTruthy: function (actual) {
+            return [!!actual, "to be truthy"];
+        },
+        toBeFalsy: function (actual) {
+            return [!actual, "to be falsy"];
+        },
+        toBeNull: function (actual) {
+            return [actual === null, "to be null"];
+        },
+        toBeUndefined: function (actual) {
+            return [actual === undefined, "to be undefined"];
+        },
+        toBeDefined: function (actual) {
+            return [actual !== undefined, "to be defined"];
+        },
+        toBeNaN: function (actual) {
+            return [Number.isNaN(actual), "to be NaN"];
+        },
+        toBeGreaterThan: function (actual, expected) {
+            return [actual > expected, "to be greater than " + format(expected)];
+        },
+        toBeGreaterThanOrEqual: function (actual, expected) {
+            return [actual >= expected, "to be greater than or equal to " + format(expected)];
+        },
+        toBeLessThan: function (actual, expected) {
+            return [actual < expected, "to be less than " + format(expected)];
+        },
+        toBeLessThanOrEqual: function (actual, expected) {
+            return [actual <= expected, "to be less than or equal to " + format(expected)];
+        },
+        toBeCloseTo: function (actual, expected, digits) {
+            if (digits === undefined) {
+                digits = 2;
+            }
+
+            return [
+                Math.abs(expected - actual) < Math.pow(10, -digits) / 2,
+                "to be close to " + format(expected) + " (" + digits + " digits)",
+            ];
+        },
+        toBeInstanceOf: function (actual, expected) {
+            return [actual instanceof expected, "to be an instance of " + (expected.name || "the class")];
+        },
+        toContain: function (actual, expected) {
+            const found = typeof actual === "string"
+                ? actual.indexOf(expected) !== -1
+                : Array.from(actual).some(function (item) {
+                    return Object.is(item, expected);
+                });
+
+            return [found, "to contain " + format(expected)];
+        },
+        toContainEqual: function (actual, expected) {
+            const found = Array.from(actual).some(function (item) {
+                return equals(item, expected, false);
+            });
+
+            return [found, "to contain an item equal to " + format(expected)];
+        },
+        toHaveLength: function (actual, expected) {
+            return [
+                actual != null && actual.length === expected,
+                "to have length " + expected + ", but its length is " + (actual == null ? actual : actual.length),
+            ];
+        },
+        toHaveProperty: function (actual, path, value) {
+            const keys = Array.isArray(path) ? path : String(path).split(".");
+            let current = actual;
+
+            for (let i = 0; i < keys.length; i++) {
+                if (current == null || !(keys[i] in Object(current))) {
+                    return [false, "to have property " + format(path)];
+                }
+
+                current = current[keys[i]];
+            }
+
+            if (arguments.length > 2) {
+                return [equals(current, value, false), "to have property " + format(path) + " equal to " + format(value)];
+            }
+
+            return [true, "to have property " + format(path)];
+        },
+        toMatch: function (actual, expected) {
+            const matched = typeof expected === "string"
+                ? String(actual).indexOf(expected) !== -1
+                : expected.test(String(actual));
+
+            return [matched, "to match " + format(expected)];
+        },
+        toMatchObject: function (actual, expected) {
+            const subset = function (a, b) {
+                if (typeof b !== "object" || b === null) {
+                    return equals(a, b, false);
+                }
+
+                if (typeof a !== "object" || a === null) {
+                    return false;
+                }
+
+                return Object.keys(b).every(function (key) {
+                    return subset(a[key], b[key]);
+                });
+            };
+
+            return [subset(actual, expected), "to match object " + format(expected)];
+        },
+        toThrow: function (actual, expected) {
+            const error = thrown(actual);
+
+            if (error === undefined) {
+                return [false, "to throw" + (expected === undefined ? "" : " " + format(expected))];
+            }
+
+            return [
+                matchesError(error.value, expected),
+                "to throw " + format(expected) + ", but it threw " + format(error.value),
+            ];
+        },
+    };
+
+    matchers.toThrowError = matchers.toThrow;
+
+    function Expectation(actual, negated, promise) {
+        this.actual = actual;
+        this.negated = negated;
+        this.promise = promise;
+    }
+
+    Object.keys(matchers).forEach(function (name) {
+        const matcher = matchers[name];
+
+        Expectation.prototype[name] = function () {
+            const args = Array.prototype.slice.call(arguments);
+            const negated = this.negated;
+            const received = this.actual;
+            const modifier = this.promise === undefined ? "" : this.promise + ".";
+
+            const check = function (actual) {
+                const result = matcher.apply(undefined, [actual].concat(args));
+
+                if (result[0] === negated) {
+                    fail(
+                        "expect(" + format(received) + ")." + modifier + (negated ? "not." : "") + name + "\n\n" +
+                        "Expected " + format(actual) + (negated ? " not " : " ") + result[1]
+                    );
+                }
+            };
+
+            if (this.promise === undefined) {
+                return check(this.actual);
+            }
+
+            const promise = this.promise;
+            const actual = this.actual;
+
+            if (actual === null || (typeof actual !== "object" && typeof actual !== "function") ||
+                typeof actual.then !== "function") {
+                fail("expect(" + format(actual) + ")." + promise + "." + name + "\n\n" +
+                    "Expected a promise, got " + format(actual));
+            }
+
+            // returning the promise from a callback adopts its state,
+            // `Promise.resolve(actual)` would wrap it
+            return Promise.resolve().then(function () {
+                return actual;
+            }).then(
+                function (value) {
+                    if (promise === "rejects") {
+                        fail("expected the promise to reject, it resolved to " + format(value));
+                    }
+
+                    return check(value);
+                },
+                function (reason) {
+                    if (promise === "resolves") {
+                        fail("expected the promise to resolve, it rejected with " + format(reason));
+                    }
+
+                    if (name === "toThrow" || name === "toThrowError") {
+                        return check(function () {
+                            throw reason;
+                        });
+                    }
+
+                    return check(reason);
+                }
+            );
+        };
+    });
+
+    Expectation.prototype.toMatchSnapshot = function () {
+        if (this.negated) {
+            fail("toMatchSnapshot can't be negated");
+        }
+
+        const serialized = format(this.actual);
+        const expected = matchSnapshot(serialized);
+
+        if (expected !== undefined) {
+            fail("expected the value to match the snapshot\n\n- Snapshot\n" + expected + "\n\n+ Received\n" + serialized);
+        }
+    };
+
+    Object.defineProperty(Expectation.prototype, "not", {
+        get: function () {
+            return new Expectation(this.actual, !this.negated, this.promise);
+        },
+    });
+
+    Object.defineProperty(Expectation.prototype, "resolves", {
+        get: function () {
+            return new Expectation(this.actual, this.negated, "resolves");
+        },
+    });
+
+    Object.defineProperty(Expectation.prototype, "rejects", {
+        get: function () {
+            return new Expectation(this.actual, this.negated, "rejects");
+        },
+    });
+
+    function expect(actual) {
+        return new Expectation(actual, false, undefined);
+    }
+
+    expect.format = format;
+
+    return expect;
+})
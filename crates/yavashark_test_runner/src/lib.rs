@@ -0,0 +1,96 @@
+//! A test runner for JavaScript and TypeScript projects, with a `describe`,
+//! `test` and `expect` API like the one of Jest.
+//!
+//! Every test file runs in its own realm, files run in parallel on a pool of
+//! threads. TypeScript files are stripped of their types before they run, see
+//! [`strip`] for what's supported.
+
+mod report;
+mod runner;
+mod snapshot;
+mod source;
+pub mod strip;
+mod suite;
+
+pub use crate::report::{Failure, FileResult, Outcome, Summary, TestResult};
+pub use crate::runner::{run, run_file};
+pub use crate::snapshot::SnapshotSummary;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The path the frames of `expect` have in stack traces, they are hidden.
+const EXPECT_PATH: &str = "<expect>";
+
+const TEST_SUFFIXES: [&str; 4] = [".test.js", ".test.mjs", ".test.ts", ".test.mts"];
+
+pub struct Options {
+    /// How many files run at the same time.
+    pub jobs: usize,
+    /// Overwrite snapshots that don't match instead of failing.
+    pub update_snapshots: bool,
+    /// How long a test or hook may run, including the promise it returns.
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            jobs: std::thread::available_parallelism().map_or(1, Into::into),
+            update_snapshots: false,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| TEST_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)))
+}
+
+/// The test files in `paths`. Files are taken as they are, directories are
+/// searched for `*.test.{js,mjs,ts,mts}`, skipping `node_modules` and hidden
+/// directories.
+pub fn discover(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            search(path, &mut files)?;
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", path.display()),
+            ));
+        }
+    }
+
+    files.sort();
+    files.dedup();
+
+    Ok(files)
+}
+
+fn search(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            let skip = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name == "node_modules" || name.starts_with('.'));
+
+            if !skip {
+                search(&path, files)?;
+            }
+        } else if is_test_file(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use crate::EXPECT_PATH;
+use crate::snapshot::SnapshotSummary;
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+use std::time::Duration;
+use yavashark_env::error::{ErrorKind, Location, StackTrace};
+use yavashark_env::error_obj::ErrorObj;
+use yavashark_env::print::PrettyPrint;
+use yavashark_env::{Error, Realm, Value};
+
+/// Why a test or file failed.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub message: String,
+    pub stack: StackTrace,
+}
+
+impl Failure {
+    #[must_use]
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            stack: StackTrace::default(),
+        }
+    }
+
+    pub fn from_error(err: Error, realm: &mut Realm) -> Self {
+        if matches!(err.kind, ErrorKind::Throw(_)) {
+            let stack = err.stacktrace.clone();
+
+            return match ErrorObj::error_to_value(err, realm) {
+                Ok(value) => {
+                    let mut failure = Self::from_value(&value, realm);
+
+                    if failure.stack.frames.is_empty() {
+                        failure.stack = stack;
+                    }
+
+                    failure
+                }
+                Err(e) => Self::new(e.to_string()),
+            };
+        }
+
+        // not `to_string`, that includes the stack trace which is printed on
+        // its own
+        let message = err.message_internal();
+
+        Self {
+            message: if message.is_empty() {
+                err.name().to_owned()
+            } else {
+                format!("{}: {message}", err.name())
+            },
+            stack: err.stacktrace,
+        }
+    }
+
+    /// A thrown value or the reason of a rejected promise.
+    pub fn from_value(value: &Value, realm: &mut Realm) -> Self {
+        if let Value::Object(obj) = value
+            && let Some(error) = obj.downcast::<ErrorObj>()
+        {
+            let mut get = |key: &'static str| {
+                obj.get(key, realm)
+                    .and_then(|value| value.to_string(realm))
+                    .map(|s| s.to_string())
+                    .unwrap_or_default()
+            };
+
+            let name = get("name");
+            let message = get("message");
+
+            return Self {
+                message: if message.is_empty() {
+                    name
+                } else {
+                    format!("{name}: {message}")
+                },
+                stack: error.stack_trace().unwrap_or_default(),
+            };
+        }
+
+        Self::new(format!("thrown: {}", value.pretty_print(realm)))
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for line in self.message.lines() {
+            if line.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, "    {line}")?;
+            }
+        }
+
+        let frames = self.stack.frames.iter().filter(|frame| {
+            !matches!(&frame.loc, Location::Source { path, .. } if path.as_os_str() == EXPECT_PATH)
+        });
+
+        let mut first = true;
+
+        for frame in frames {
+            if first {
+                writeln!(f)?;
+                first = false;
+            }
+
+            write!(f, "    {frame}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Passed,
+    Failed(Failure),
+    Skipped,
+}
+
+#[derive(Debug)]
+pub struct TestResult {
+    /// The names of the `describe` blocks and the test, joined with `›`.
+    pub name: String,
+    pub outcome: Outcome,
+    pub duration: Duration,
+}
+
+#[derive(Debug)]
+pub struct FileResult {
+    pub path: PathBuf,
+    pub tests: Vec<TestResult>,
+    /// The file couldn't be loaded or a hook outside of a test failed.
+    pub error: Option<Failure>,
+    pub snapshots: SnapshotSummary,
+    pub duration: Duration,
+}
+
+impl FileResult {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+            && self
+                .tests
+                .iter()
+                .all(|test| !matches!(test.outcome, Outcome::Failed(_)))
+    }
+
+    fn count(&self, f: impl Fn(&Outcome) -> bool) -> usize {
+        self.tests.iter().filter(|test| f(&test.outcome)).count()
+    }
+}
+
+impl Display for FileResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let status = if self.passed() { "PASS" } else { "FAIL" };
+
+        writeln!(
+            f,
+            " {status}  {} ({} ms)",
+            self.path.display(),
+            self.duration.as_millis()
+        )?;
+
+        if let Some(error) = &self.error {
+            writeln!(f, "  ● {}\n", self.path.display())?;
+            writeln!(f, "{error}")?;
+        }
+
+        for test in &self.tests {
+            if let Outcome::Failed(failure) = &test.outcome {
+                writeln!(f, "  ● {}\n", test.name)?;
+                writeln!(f, "{failure}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub files: Vec<FileResult>,
+    pub duration: Duration,
+}
+
+impl Summary {
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.files.iter().all(FileResult::passed)
+    }
+}
+
+/// `1 failed, 2 passed, 3 total` without the parts that are zero.
+fn counts(
+    f: &mut Formatter<'_>,
+    label: &str,
+    parts: &[(usize, &str)],
+    total: usize,
+) -> fmt::Result {
+    write!(f, "{label:<11}")?;
+
+    for (count, name) in parts {
+        if *count > 0 {
+            write!(f, "{count} {name}, ")?;
+        }
+    }
+
+    writeln!(f, "{total} total")
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let failed_files = self.files.iter().filter(|file| !file.passed()).count();
+
+        let mut failed = 0;
+        let mut passed = 0;
+        let mut skipped = 0;
+        let mut snapshots = SnapshotSummary::default();
+
+        for file in &self.files {
+            failed += file.count(|outcome| matches!(outcome, Outcome::Failed(_)));
+            passed += file.count(|outcome| matches!(outcome, Outcome::Passed));
+            skipped += file.count(|outcome| matches!(outcome, Outcome::Skipped));
+            snapshots.add(&file.snapshots);
+        }
+
+        counts(
+            f,
+            "Files:",
+            &[
+                (failed_files, "failed"),
+                (self.files.len() - failed_files, "passed"),
+            ],
+            self.files.len(),
+        )?;
+
+        counts(
+            f,
+            "Tests:",
+            &[(failed, "failed"), (skipped, "skipped"), (passed, "passed")],
+            failed + skipped + passed,
+        )?;
+
+        if snapshots.total() > 0 {
+            counts(
+                f,
+                "Snapshots:",
+                &[
+                    (snapshots.failed, "failed"),
+                    (snapshots.written, "written"),
+                    (snapshots.updated, "updated"),
+                    (snapshots.passed, "passed"),
+                ],
+                snapshots.total(),
+            )?;
+        }
+
+        writeln!(f, "Time:      {:.2} s", self.duration.as_secs_f64())
+    }
+}
@@ -0,0 +1,498 @@
+use crate::report::{Failure, FileResult, Outcome, Summary, TestResult};
+use crate::snapshot::{SnapshotSummary, Snapshots};
+use crate::suite::{Block, Collector, Entry, Test};
+use crate::{EXPECT_PATH, Options, source};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use swc_common::BytePos;
+use swc_common::input::StringInput;
+use swc_ecma_parser::{EsSyntax, Parser, Syntax};
+use tokio::runtime::{Builder, Runtime};
+use yavashark_env::builtins::{Promise, PromiseResult, PromiseState};
+use yavashark_env::realm::limits::InterruptHandle;
+use yavashark_env::scope::Scope;
+use yavashark_env::{Error, NativeFunction, Realm, Res, Value};
+use yavashark_interpreter::Interpreter;
+use yavashark_interpreter::eval::InterpreterEval;
+
+const EXPECT_SOURCE: &str = include_str!("expect.js");
+
+/// Runs `files` on `options.jobs` threads, every file in its own realm.
+/// `on_file` is called with the result of each file once it's done.
+pub fn run(files: &[PathBuf], options: &Options, mut on_file: impl FnMut(&FileResult)) -> Summary {
+    let start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    let mut results = thread::scope(|s| {
+        for _ in 0..options.jobs.clamp(1, files.len().max(1)) {
+            let tx = tx.clone();
+            let next = &next;
+
+            s.spawn(move || {
+                while let Some(file) = files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if tx.send(run_file(file, options)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        drop(tx);
+
+        rx.iter()
+            .inspect(|result| on_file(result))
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Summary {
+        files: results,
+        duration: start.elapsed(),
+    }
+}
+
+/// Runs the tests of one file in a new realm.
+#[must_use]
+pub fn run_file(path: &Path, options: &Options) -> FileResult {
+    let start = Instant::now();
+
+    let mut result = FileResult {
+        path: path.to_path_buf(),
+        tests: Vec::new(),
+        error: None,
+        snapshots: SnapshotSummary::default(),
+        duration: Duration::ZERO,
+    };
+
+    if let Err(failure) = FileRun::run(path, options, &mut result) {
+        result.error = Some(failure);
+    }
+
+    result.duration = start.elapsed();
+
+    result
+}
+
+struct FileRun<'a> {
+    realm: Realm,
+    rt: Runtime,
+    options: &'a Options,
+    snapshots: Rc<RefCell<Snapshots>>,
+    watchdog: Watchdog,
+    /// Whether a test is marked with `only`, then the others are skipped.
+    only: bool,
+    results: &'a mut Vec<TestResult>,
+}
+
+impl<'a> FileRun<'a> {
+    fn run(path: &Path, options: &'a Options, result: &'a mut FileResult) -> Result<(), Failure> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| Failure::new(format!("failed to read the file: {e}")))?;
+
+        let program = source::parse(path, &source)?;
+
+        let snapshots = Snapshots::load(path, options.update_snapshots)
+            .map_err(|e| Failure::new(format!("failed to read the snapshots: {e}")))?;
+        let snapshots = Rc::new(RefCell::new(snapshots));
+
+        let rt = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Failure::new(format!("failed to start the event loop: {e}")))?;
+
+        let collector = Rc::new(RefCell::new(Collector::default()));
+
+        let (realm, mut scope) = setup_realm(path, &collector, &snapshots)
+            .map_err(|e| Failure::new(format!("failed to set up the realm: {e}")))?;
+
+        let watchdog = Watchdog::spawn(realm.interrupt_handle());
+
+        let mut run = FileRun {
+            realm,
+            rt,
+            options,
+            snapshots,
+            watchdog,
+            only: false,
+            results: &mut result.tests,
+        };
+
+        run.watchdog.arm(options.timeout);
+        let loaded = Interpreter::run_program_in(&program, &mut run.realm, &mut scope);
+        if loaded.is_ok() {
+            run.run_event_loop();
+        }
+        run.watchdog.disarm();
+        loaded.map_err(|e| run.failure(e))?;
+
+        if run.realm.interrupt_handle().is_interrupted() {
+            return Err(run.failure(Error::new("interrupted")));
+        }
+
+        let root = collector
+            .try_borrow_mut()
+            .map_or_else(|_| Block::default(), |mut c| c.finish());
+
+        run.only = root.has_only();
+
+        let mut hooks = Hooks::default();
+        let error = run.block(&root, &mut Vec::new(), &mut hooks, false);
+
+        result.snapshots = run.snapshots.borrow().summary;
+
+        run.snapshots
+            .borrow()
+            .save()
+            .map_err(|e| Failure::new(format!("failed to write the snapshots: {e}")))?;
+
+        error.map_or(Ok(()), Err)
+    }
+
+    fn failure(&mut self, err: Error) -> Failure {
+        if self.realm.interrupt_handle().is_interrupted() {
+            self.realm.reset_limits();
+
+            return Failure::new(format!(
+                "exceeded the timeout of {} ms",
+                self.options.timeout.as_millis()
+            ));
+        }
+
+        Failure::from_error(err, &mut self.realm)
+    }
+
+    /// Runs the event loop until it is empty or the timeout is over. The
+    /// watchdog only stops running code, so a loop waiting for a timer is
+    /// given up here and the realm is interrupted instead.
+    fn run_event_loop(&mut self) {
+        let timeout = self.options.timeout;
+        let realm = &mut self.realm;
+
+        let finished = self
+            .rt
+            .block_on(async { tokio::time::timeout(timeout, realm.run_event_loop()).await })
+            .is_ok();
+
+        if !finished {
+            self.realm.interrupt_handle().interrupt();
+        }
+    }
+
+    /// Calls a test or hook and waits for the promise it returns.
+    fn call(&mut self, func: &Value) -> Result<(), Failure> {
+        self.watchdog.arm(self.options.timeout);
+        let result = self.call_inner(func);
+        self.watchdog.disarm();
+
+        result
+    }
+
+    fn call_inner(&mut self, func: &Value) -> Result<(), Failure> {
+        let value = func
+            .call(&mut self.realm, Vec::new(), Value::Undefined)
+            .map_err(|e| self.failure(e))?;
+
+        let Value::Object(obj) = &value else {
+            return Ok(());
+        };
+
+        if obj.downcast::<Promise>().is_none() {
+            return Ok(());
+        }
+
+        self.run_event_loop();
+
+        let Some(promise) = obj.downcast::<Promise>() else {
+            return Ok(());
+        };
+
+        if promise.state.get() == PromiseState::Pending {
+            if self.realm.interrupt_handle().is_interrupted() {
+                return Err(self.failure(Error::new("interrupted")));
+            }
+
+            return Err(Failure::new("the returned promise never settled"));
+        }
+
+        match self.rt.block_on(promise.wait_to_res()) {
+            Ok(PromiseResult::Fulfilled(_)) => Ok(()),
+            Ok(PromiseResult::Rejected(reason)) => {
+                if self.realm.interrupt_handle().is_interrupted() {
+                    return Err(self.failure(Error::new("interrupted")));
+                }
+
+                Err(Failure::from_value(&reason, &mut self.realm))
+            }
+            Err(e) => Err(self.failure(e)),
+        }
+    }
+
+    /// Runs the tests of `block`, returns the failure of an `afterAll` hook.
+    fn block(
+        &mut self,
+        block: &Block,
+        names: &mut Vec<String>,
+        hooks: &mut Hooks,
+        skip: bool,
+    ) -> Option<Failure> {
+        let skip = skip || block.skip;
+        let only = self.only && !block.only;
+        let runs = !skip && block.runs(only);
+
+        if runs {
+            for hook in &block.before_all {
+                if let Err(failure) = self.call(hook) {
+                    self.fail_all(block, names, &failure);
+                    return None;
+                }
+            }
+        }
+
+        let before_each = hooks.before_each.len();
+        let after_each = hooks.after_each.len();
+        hooks.before_each.extend(block.before_each.iter().cloned());
+        hooks.after_each.extend(block.after_each.iter().cloned());
+
+        let outer_only = std::mem::replace(&mut self.only, only);
+        let mut error = None;
+
+        for entry in &block.entries {
+            match entry {
+                Entry::Test(test) => self.test(test, names, hooks, skip),
+                Entry::Block(inner) => {
+                    names.push(inner.name.clone());
+                    let failure = self.block(inner, names, hooks, skip);
+                    names.pop();
+
+                    error = error.or(failure);
+                }
+            }
+        }
+
+        self.only = outer_only;
+        hooks.before_each.truncate(before_each);
+        hooks.after_each.truncate(after_each);
+
+        if runs {
+            for hook in &block.after_all {
+                if let Err(failure) = self.call(hook) {
+                    error = error.or(Some(failure));
+                }
+            }
+        }
+
+        error
+    }
+
+    fn test(&mut self, test: &Test, names: &[String], hooks: &Hooks, skip: bool) {
+        let name = full_name(names, &test.name);
+
+        if skip || test.skip || (self.only && !test.only) {
+            self.results.push(TestResult {
+                name,
+                outcome: Outcome::Skipped,
+                duration: Duration::ZERO,
+            });
+
+            return;
+        }
+
+        if let Ok(mut snapshots) = self.snapshots.try_borrow_mut() {
+            snapshots.start_test(&name);
+        }
+
+        let start = Instant::now();
+
+        let mut result = hooks
+            .before_each
+            .iter()
+            .try_for_each(|hook| self.call(hook))
+            .and_then(|()| self.call(&test.func));
+
+        for hook in hooks.after_each.iter().rev() {
+            let after = self.call(hook);
+
+            if result.is_ok() {
+                result = after;
+            }
+        }
+
+        self.results.push(TestResult {
+            name,
+            outcome: result.map_or_else(Outcome::Failed, |()| Outcome::Passed),
+            duration: start.elapsed(),
+        });
+    }
+
+    /// Reports every test of `block` as failed, when its `beforeAll` failed.
+    fn fail_all(&mut self, block: &Block, names: &mut Vec<String>, failure: &Failure) {
+        for entry in &block.entries {
+            match entry {
+                Entry::Test(test) => self.results.push(TestResult {
+                    name: full_name(names, &test.name),
+                    outcome: Outcome::Failed(failure.clone()),
+                    duration: Duration::ZERO,
+                }),
+                Entry::Block(inner) => {
+                    names.push(inner.name.clone());
+                    self.fail_all(inner, names, failure);
+                    names.pop();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Hooks {
+    before_each: Vec<Value>,
+    /// Outer hooks first, they run in reverse.
+    after_each: Vec<Value>,
+}
+
+fn full_name(names: &[String], name: &str) -> String {
+    names
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(name))
+        .collect::<Vec<_>>()
+        .join(" › ")
+}
+
+/// Creates the realm of a test file with the test API on its global object.
+fn setup_realm(
+    path: &Path,
+    collector: &Rc<RefCell<Collector>>,
+    snapshots: &Rc<RefCell<Snapshots>>,
+) -> Res<(Realm, Scope)> {
+    let mut realm = Realm::new()?;
+    realm.set_eval(InterpreterEval, false)?;
+    yavashark_vm::init(&mut realm)?;
+
+    crate::suite::install(collector, &mut realm)?;
+
+    let input = StringInput::new(
+        EXPECT_SOURCE,
+        BytePos(0),
+        BytePos(EXPECT_SOURCE.len() as u32),
+    );
+    let mut parser = Parser::new(Syntax::Es(EsSyntax::default()), input, None);
+    let script = parser
+        .parse_script()
+        .map_err(|e| Error::syn_error(format!("{EXPECT_PATH}: {}", e.kind().msg())))?;
+
+    let mut scope = Scope::global(&realm, PathBuf::from(EXPECT_PATH));
+    let create = Interpreter::run_in(&script.body, &mut realm, &mut scope)?;
+
+    let snapshots = Rc::clone(snapshots);
+    let match_snapshot = NativeFunction::new(
+        "matchSnapshot",
+        move |args, _, realm| {
+            let serialized = match args.first() {
+                Some(value) => value.to_string(realm)?.to_string(),
+                None => String::new(),
+            };
+
+            Ok(snapshots
+                .try_borrow_mut()?
+                .check(&serialized)
+                .map_or(Value::Undefined, Value::from))
+        },
+        &mut realm,
+    );
+
+    let expect = create.call(&mut realm, vec![match_snapshot.into()], Value::Undefined)?;
+
+    let global = realm.global.clone();
+    global.define_property("expect".into(), expect, &mut realm)?;
+
+    let scope = Scope::global(&realm, path.to_path_buf());
+
+    Ok((realm, scope))
+}
+
+/// Interrupts the realm when a test runs longer than the timeout.
+struct Watchdog {
+    tx: Sender<Option<Duration>>,
+}
+
+impl Watchdog {
+    fn spawn(interrupt: InterruptHandle) -> Self {
+        let (tx, rx) = mpsc::channel::<Option<Duration>>();
+
+        thread::spawn(move || {
+            let mut deadline: Option<Instant> = None;
+
+            loop {
+                let message = deadline.map_or_else(
+                    || rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    |deadline| rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                );
+
+                match message {
+                    Ok(timeout) => deadline = timeout.map(|timeout| Instant::now() + timeout),
+                    Err(RecvTimeoutError::Timeout) => {
+                        interrupt.interrupt();
+                        deadline = None;
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        Self { tx }
+    }
+
+    fn arm(&self, timeout: Duration) {
+        _ = self.tx.send(Some(timeout));
+    }
+
+    fn disarm(&self) {
+        _ = self.tx.send(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_out_top_level_async_work() {
+        let dir = std::env::temp_dir().join(format!("yavashark-runner-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(dir.join("dep.mjs"), "export const x = 1;").unwrap();
+
+        let file = dir.join("hang.test.js");
+        std::fs::write(
+            &file,
+            r#"
+            import("./dep.mjs").then(() => { while (true) {} });
+            test("never runs", () => {});
+            "#,
+        )
+        .unwrap();
+
+        let options = Options {
+            timeout: Duration::from_millis(200),
+            ..Options::default()
+        };
+
+        let result = run_file(&file, &options);
+
+        assert_eq!(
+            result.error.map(|failure| failure.message).as_deref(),
+            Some("exceeded the timeout of 200 ms")
+        );
+        assert!(result.tests.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+//! Snapshots of `toMatchSnapshot`, stored next to the test file in
+//! `__snapshots__/<file>.snap` as a JSON object of the serialized values.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotSummary {
+    pub passed: usize,
+    pub written: usize,
+    pub updated: usize,
+    pub failed: usize,
+}
+
+impl SnapshotSummary {
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.passed + self.written + self.updated + self.failed
+    }
+
+    pub const fn add(&mut self, other: &Self) {
+        self.passed += other.passed;
+        self.written += other.written;
+        self.updated += other.updated;
+        self.failed += other.failed;
+    }
+}
+
+pub struct Snapshots {
+    path: PathBuf,
+    stored: BTreeMap<String, String>,
+    update: bool,
+    dirty: bool,
+    /// The test that is running and how many snapshots it took so far.
+    test: String,
+    count: usize,
+    pub summary: SnapshotSummary,
+}
+
+impl Snapshots {
+    /// Loads the snapshots of `test_file`, `update` overwrites the ones that
+    /// don't match.
+    pub fn load(test_file: &Path, update: bool) -> io::Result<Self> {
+        let name = test_file
+            .file_name()
+            .map(|name| format!("{}.snap", name.to_string_lossy()))
+            .unwrap_or_default();
+
+        let path = test_file
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join("__snapshots__")
+            .join(name);
+
+        let stored = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            path,
+            stored,
+            update,
+            dirty: false,
+            test: String::new(),
+            count: 0,
+            summary: SnapshotSummary::default(),
+        })
+    }
+
+    pub fn start_test(&mut self, name: &str) {
+        name.clone_into(&mut self.test);
+        self.count = 0;
+    }
+
+    /// Compares the next snapshot of the running test, returns the stored
+    /// one if it doesn't match.
+    pub fn check(&mut self, serialized: &str) -> Option<String> {
+        self.count += 1;
+        let key = format!("{} {}", self.test, self.count);
+
+        match self.stored.get(&key) {
+            Some(stored) if stored == serialized => {
+                self.summary.passed += 1;
+                None
+            }
+            Some(_) if self.update => {
+                self.stored.insert(key, serialized.to_owned());
+                self.dirty = true;
+                self.summary.updated += 1;
+                None
+            }
+            Some(stored) => {
+                self.summary.failed += 1;
+                Some(stored.clone())
+            }
+            None => {
+                self.stored.insert(key, serialized.to_owned());
+                self.dirty = true;
+                self.summary.written += 1;
+                None
+            }
+        }
+    }
+
+    /// Writes the file if a snapshot was added or updated.
+    pub fn save(&self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut content = serde_json::to_string_pretty(&self.stored)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        content.push('\n');
+
+        std::fs::write(&self.path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_then_compares() {
+        let dir = std::env::temp_dir().join(format!("yavashark-snap-{}", std::process::id()));
+        let file = dir.join("a.test.js");
+
+        let mut snapshots = Snapshots::load(&file, false).unwrap();
+        snapshots.start_test("adds");
+        assert_eq!(snapshots.check("3"), None);
+        assert_eq!(snapshots.check("4"), None);
+        snapshots.save().unwrap();
+
+        assert!(dir.join("__snapshots__/a.test.js.snap").exists());
+
+        let mut snapshots = Snapshots::load(&file, false).unwrap();
+        snapshots.start_test("adds");
+        assert_eq!(snapshots.check("3"), None);
+        assert_eq!(snapshots.check("5"), Some("4".to_owned()));
+        assert_eq!(
+            snapshots.summary,
+            SnapshotSummary {
+                passed: 1,
+                failed: 1,
+                ..SnapshotSummary::default()
+            }
+        );
+
+        let mut snapshots = Snapshots::load(&file, true).unwrap();
+        snapshots.start_test("adds");
+        snapshots.check("3");
+        assert_eq!(snapshots.check("5"), None);
+        assert_eq!(snapshots.summary.updated, 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use crate::report::Failure;
+use crate::strip::{strip_module, strip_script};
+use std::path::Path;
+use swc_common::input::StringInput;
+use swc_common::{BytePos, Spanned};
+use swc_ecma_ast::Program;
+use swc_ecma_parser::{EsSyntax, Parser, Syntax, TsSyntax};
+use yavashark_env::error::{Location, StackFrame, StackTrace};
+use yavashark_swc_validator::Validator;
+
+fn is_typescript(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "ts" || ext == "mts" || ext == "cts")
+}
+
+fn syntax_error(path: &Path, pos: u32, message: &str) -> Failure {
+    Failure {
+        message: format!("SyntaxError: {message}"),
+        stack: StackTrace {
+            frames: vec![StackFrame {
+                function: String::new(),
+                loc: Location::Source {
+                    range: pos..pos,
+                    path: path.to_path_buf(),
+                },
+            }],
+        },
+    }
+}
+
+/// Parses and validates a test file, TypeScript files are stripped of their
+/// types.
+pub fn parse(path: &Path, source: &str) -> Result<Program, Failure> {
+    let input = StringInput::new(source, BytePos(0), BytePos(source.len() as u32));
+
+    let syntax = if is_typescript(path) {
+        Syntax::Typescript(TsSyntax {
+            decorators: true,
+            ..TsSyntax::default()
+        })
+    } else {
+        Syntax::Es(EsSyntax {
+            decorators: true,
+            import_attributes: true,
+            explicit_resource_management: true,
+            ..EsSyntax::default()
+        })
+    };
+
+    let mut parser = Parser::new(syntax, input, None);
+
+    let mut program = parser.parse_program().map_err(|e| {
+        // errors at the end of the file point past the last character
+        let pos = e.span().lo.0.min(source.len().saturating_sub(1) as u32);
+
+        syntax_error(path, pos, &e.kind().msg())
+    })?;
+
+    let stripped = match &mut program {
+        Program::Module(module) => strip_module(&mut module.body),
+        Program::Script(script) => strip_script(&mut script.body),
+    };
+
+    stripped.map_err(|(pos, message)| syntax_error(path, pos, message))?;
+
+    let mut validator = Validator::new();
+
+    let valid = match &program {
+        Program::Module(module) => validator.validate_module_items(&module.body),
+        Program::Script(script) => validator.validate_statements(&script.body),
+    };
+
+    valid.map_err(|e| Failure::new(format!("SyntaxError: {e}")))?;
+
+    Ok(program)
+}
@@ -0,0 +1,254 @@
+//! Erases the type-only syntax of TypeScript, so the interpreter can run it.
+//!
+//! Only syntax that can be removed without changing what the code does is
+//! supported, the same subset as `--erasableSyntaxOnly` of `tsc`. Enums,
+//! namespaces and parameter properties are reported as errors instead.
+
+use swc_common::{Span, Spanned};
+use swc_ecma_ast::{
+    Class, ClassMember, Decl, DefaultDecl, ExportSpecifier, Expr, ImportSpecifier, ModuleDecl,
+    ModuleItem, ParamOrTsParamProp, SimpleAssignTarget, Stmt,
+};
+use swc_ecma_visit::{VisitMut, VisitMutWith};
+
+/// Removes the types of `items`, returns the byte offset and description of
+/// the first syntax that can't be erased.
+pub fn strip_module(items: &mut Vec<ModuleItem>) -> Result<(), (u32, &'static str)> {
+    let mut stripper = Stripper::default();
+    items.visit_mut_with(&mut stripper);
+
+    stripper.error.map_or(Ok(()), Err)
+}
+
+/// Like [`strip_module`] for a script.
+pub fn strip_script(stmts: &mut Vec<Stmt>) -> Result<(), (u32, &'static str)> {
+    let mut stripper = Stripper::default();
+    stmts.visit_mut_with(&mut stripper);
+
+    stripper.error.map_or(Ok(()), Err)
+}
+
+#[derive(Default)]
+struct Stripper {
+    error: Option<(u32, &'static str)>,
+}
+
+impl Stripper {
+    const fn unsupported(&mut self, span: Span, what: &'static str) {
+        if self.error.is_none() {
+            self.error = Some((span.lo.0, what));
+        }
+    }
+
+    /// Whether `decl` only exists for the type checker.
+    fn is_type_only(&mut self, decl: &Decl) -> bool {
+        match decl {
+            Decl::TsEnum(e) if !e.declare => {
+                self.unsupported(e.span, "enums are not supported");
+                false
+            }
+            Decl::TsModule(m) if !m.declare => {
+                self.unsupported(m.span, "namespaces are not supported");
+                false
+            }
+            Decl::TsInterface(_) | Decl::TsTypeAlias(_) | Decl::TsEnum(_) | Decl::TsModule(_) => {
+                true
+            }
+            Decl::Var(var) => var.declare,
+            Decl::Class(class) => class.declare,
+            Decl::Fn(f) => f.declare || f.function.body.is_none(),
+            Decl::Using(_) => false,
+        }
+    }
+}
+
+/// The expression inside of a type assertion, if `expr` is one.
+fn asserted(expr: &mut Expr) -> Option<Box<Expr>> {
+    let inner = match expr {
+        Expr::TsAs(e) => &mut e.expr,
+        Expr::TsSatisfies(e) => &mut e.expr,
+        Expr::TsNonNull(e) => &mut e.expr,
+        Expr::TsTypeAssertion(e) => &mut e.expr,
+        Expr::TsConstAssertion(e) => &mut e.expr,
+        Expr::TsInstantiation(e) => &mut e.expr,
+        _ => return None,
+    };
+
+    Some(std::mem::take(inner))
+}
+
+impl VisitMut for Stripper {
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        stmts.retain(|stmt| !matches!(stmt, Stmt::Decl(decl) if self.is_type_only(decl)));
+
+        stmts.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_module_items(&mut self, items: &mut Vec<ModuleItem>) {
+        items.retain_mut(|item| {
+            let ModuleItem::ModuleDecl(decl) = item else {
+                return !matches!(item, ModuleItem::Stmt(Stmt::Decl(decl)) if self.is_type_only(decl));
+            };
+
+            match decl {
+                ModuleDecl::Import(import) => {
+                    if import.type_only {
+                        return false;
+                    }
+
+                    let had_specifiers = !import.specifiers.is_empty();
+
+                    import.specifiers.retain(
+                        |spec| !matches!(spec, ImportSpecifier::Named(named) if named.is_type_only),
+                    );
+
+                    !had_specifiers || !import.specifiers.is_empty()
+                }
+                ModuleDecl::ExportNamed(export) => {
+                    if export.type_only {
+                        return false;
+                    }
+
+                    let had_specifiers = !export.specifiers.is_empty();
+
+                    export.specifiers.retain(
+                        |spec| !matches!(spec, ExportSpecifier::Named(named) if named.is_type_only),
+                    );
+
+                    !had_specifiers || !export.specifiers.is_empty()
+                }
+                ModuleDecl::ExportAll(export) => !export.type_only,
+                ModuleDecl::ExportDecl(export) => !self.is_type_only(&export.decl),
+                ModuleDecl::ExportDefaultDecl(export) => {
+                    !matches!(export.decl, DefaultDecl::TsInterfaceDecl(_))
+                }
+                ModuleDecl::TsImportEquals(decl) => {
+                    if !decl.is_type_only {
+                        self.unsupported(decl.span, "`import =` is not supported");
+                    }
+
+                    false
+                }
+                ModuleDecl::TsExportAssignment(decl) => {
+                    self.unsupported(decl.span, "`export =` is not supported");
+                    false
+                }
+                ModuleDecl::TsNamespaceExport(_) => false,
+                ModuleDecl::ExportDefaultExpr(_) => true,
+            }
+        });
+
+        items.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        while let Some(inner) = asserted(expr) {
+            *expr = *inner;
+        }
+
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_simple_assign_target(&mut self, target: &mut SimpleAssignTarget) {
+        let inner = match target {
+            SimpleAssignTarget::TsAs(e) => Some(std::mem::take(&mut e.expr)),
+            SimpleAssignTarget::TsSatisfies(e) => Some(std::mem::take(&mut e.expr)),
+            SimpleAssignTarget::TsNonNull(e) => Some(std::mem::take(&mut e.expr)),
+            SimpleAssignTarget::TsTypeAssertion(e) => Some(std::mem::take(&mut e.expr)),
+            SimpleAssignTarget::TsInstantiation(e) => Some(std::mem::take(&mut e.expr)),
+            _ => None,
+        };
+
+        if let Some(mut inner) = inner {
+            inner.visit_mut_with(self);
+
+            match SimpleAssignTarget::try_from(inner) {
+                Ok(simple) => *target = simple,
+                Err(expr) => self.unsupported(expr.span(), "invalid assignment target"),
+            }
+
+            return;
+        }
+
+        target.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_class(&mut self, class: &mut Class) {
+        class.body.retain(|member| match member {
+            ClassMember::TsIndexSignature(_) => false,
+            ClassMember::ClassProp(prop) => !prop.declare && !prop.is_abstract,
+            ClassMember::Method(method) => method.function.body.is_some(),
+            ClassMember::PrivateMethod(method) => method.function.body.is_some(),
+            ClassMember::Constructor(constructor) => constructor.body.is_some(),
+            _ => true,
+        });
+
+        for member in &class.body {
+            if let ClassMember::Constructor(constructor) = member {
+                for param in &constructor.params {
+                    if let ParamOrTsParamProp::TsParamProp(prop) = param {
+                        self.unsupported(prop.span, "parameter properties are not supported");
+                    }
+                }
+            }
+        }
+
+        class.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swc_common::BytePos;
+    use swc_common::input::StringInput;
+    use swc_ecma_parser::{Parser, Syntax, TsSyntax};
+
+    fn strip(source: &str) -> Result<Vec<ModuleItem>, (u32, &'static str)> {
+        let input = StringInput::new(source, BytePos(0), BytePos(source.len() as u32));
+        let mut parser = Parser::new(Syntax::Typescript(TsSyntax::default()), input, None);
+        let mut module = parser.parse_module().unwrap();
+
+        strip_module(&mut module.body).map(|()| module.body)
+    }
+
+    #[test]
+    fn removes_type_only_syntax() {
+        let items = strip(
+            "import type { A } from './a';\n\
+             import { type B, c } from './b';\n\
+             interface I { x: number }\n\
+             type T = string;\n\
+             declare const d: number;\n\
+             function f(this: I, a: number): number;\n\
+             function f(this: I, a: number) { return (a as number)!; }\n\
+             class K<T> implements I { declare y: T; x = 1; m(): void; m() {} }\n\
+             export type { T };\n",
+        )
+        .unwrap();
+
+        // the import of `c`, the body of `f`, the class
+        assert_eq!(items.len(), 3);
+
+        assert!(matches!(
+            &items[1],
+            ModuleItem::Stmt(Stmt::Decl(Decl::Fn(f))) if f.function.params.len() == 1
+        ));
+        assert!(matches!(
+            &items[2],
+            ModuleItem::Stmt(Stmt::Decl(Decl::Class(class))) if class.class.body.len() == 2
+        ));
+    }
+
+    #[test]
+    fn rejects_runtime_syntax() {
+        assert_eq!(
+            strip("enum E { A }").map(|_| ()),
+            Err((0, "enums are not supported"))
+        );
+        assert_eq!(
+            strip("class A { constructor(private x: number) {} }").map(|_| ()),
+            Err((22, "parameter properties are not supported"))
+        );
+    }
+}
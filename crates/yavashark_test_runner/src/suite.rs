@@ -0,0 +1,256 @@
+//! The tests a file declares with `describe`, `test` and the hooks.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use yavashark_env::{Error, NativeFunction, ObjectHandle, Realm, Res, Value};
+
+pub struct Test {
+    pub name: String,
+    pub func: Value,
+    pub skip: bool,
+    pub only: bool,
+}
+
+pub enum Entry {
+    Test(Test),
+    Block(Block),
+}
+
+/// A `describe` block, the root block is the file.
+#[derive(Default)]
+pub struct Block {
+    pub name: String,
+    pub skip: bool,
+    pub only: bool,
+    pub before_all: Vec<Value>,
+    pub after_all: Vec<Value>,
+    pub before_each: Vec<Value>,
+    pub after_each: Vec<Value>,
+    pub entries: Vec<Entry>,
+}
+
+impl Block {
+    /// Whether a test in this block is marked with `only`.
+    #[must_use]
+    pub fn has_only(&self) -> bool {
+        self.only
+            || self.entries.iter().any(|entry| match entry {
+                Entry::Test(test) => test.only,
+                Entry::Block(block) => block.has_only(),
+            })
+    }
+
+    /// Whether any test of this block runs.
+    #[must_use]
+    pub fn runs(&self, only: bool) -> bool {
+        !self.skip
+            && self.entries.iter().any(|entry| match entry {
+                Entry::Test(test) => !test.skip && (!only || self.only || test.only),
+                Entry::Block(block) => block.runs(only && !self.only),
+            })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    Skip,
+    Only,
+}
+
+#[derive(Clone, Copy)]
+enum Hook {
+    BeforeAll,
+    AfterAll,
+    BeforeEach,
+    AfterEach,
+}
+
+/// Builds the [`Block`] of a file while it runs.
+#[derive(Default)]
+pub struct Collector {
+    root: Block,
+    /// The `describe` blocks that are currently being collected, they are
+    /// added to their parent once their callback returns.
+    open: Vec<Block>,
+    /// Set once the tests run, declaring tests after that is an error.
+    closed: bool,
+}
+
+impl Collector {
+    fn current(&mut self) -> &mut Block {
+        self.open.last_mut().unwrap_or(&mut self.root)
+    }
+
+    fn close_block(&mut self) {
+        if let Some(block) = self.open.pop() {
+            self.current().entries.push(Entry::Block(block));
+        }
+    }
+
+    fn check_open(&self, what: &str) -> Res {
+        if self.closed {
+            return Err(Error::new_error(format!(
+                "{what} can't be called while the tests are running"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Stops collecting and returns the root block.
+    pub fn finish(&mut self) -> Block {
+        self.closed = true;
+
+        std::mem::take(&mut self.root)
+    }
+}
+
+fn name_and_func(args: &[Value], what: &str, realm: &mut Realm) -> Res<(String, Value)> {
+    let name = match args.first() {
+        Some(name) => name.to_string(realm)?.to_string(),
+        None => return Err(Error::ty_error(format!("{what} expects a name"))),
+    };
+
+    let func = args.get(1).cloned().unwrap_or(Value::Undefined);
+
+    if !func.is_callable() {
+        return Err(Error::ty_error(format!(
+            "{what}(\"{name}\") expects a function"
+        )));
+    }
+
+    Ok((name, func))
+}
+
+fn test_function(
+    collector: &Rc<RefCell<Collector>>,
+    mode: Mode,
+    realm: &mut Realm,
+) -> ObjectHandle {
+    let collector = Rc::clone(collector);
+
+    NativeFunction::new(
+        "test",
+        move |args, _, realm| {
+            let (name, func) = name_and_func(&args, "test", realm)?;
+
+            let mut collector = collector.try_borrow_mut()?;
+            collector.check_open("test")?;
+
+            collector.current().entries.push(Entry::Test(Test {
+                name,
+                func,
+                skip: mode == Mode::Skip,
+                only: mode == Mode::Only,
+            }));
+
+            Ok(Value::Undefined)
+        },
+        realm,
+    )
+}
+
+fn describe_function(
+    collector: &Rc<RefCell<Collector>>,
+    mode: Mode,
+    realm: &mut Realm,
+) -> ObjectHandle {
+    let collector = Rc::clone(collector);
+
+    NativeFunction::new(
+        "describe",
+        move |args, _, realm| {
+            let (name, func) = name_and_func(&args, "describe", realm)?;
+
+            {
+                let mut collector = collector.try_borrow_mut()?;
+                collector.check_open("describe")?;
+
+                collector.open.push(Block {
+                    name,
+                    skip: mode == Mode::Skip,
+                    only: mode == Mode::Only,
+                    ..Block::default()
+                });
+            }
+
+            let result = func.call(realm, Vec::new(), Value::Undefined);
+
+            collector.try_borrow_mut()?.close_block();
+
+            result?;
+
+            Ok(Value::Undefined)
+        },
+        realm,
+    )
+}
+
+fn hook_function(
+    collector: &Rc<RefCell<Collector>>,
+    hook: Hook,
+    name: &'static str,
+    realm: &mut Realm,
+) -> ObjectHandle {
+    let collector = Rc::clone(collector);
+
+    NativeFunction::new(
+        name,
+        move |args, _, _| {
+            let func = args.first().cloned().unwrap_or(Value::Undefined);
+
+            if !func.is_callable() {
+                return Err(Error::ty_error(format!("{name} expects a function")));
+            }
+
+            let mut collector = collector.try_borrow_mut()?;
+            collector.check_open(name)?;
+
+            let block = collector.current();
+
+            match hook {
+                Hook::BeforeAll => block.before_all.push(func),
+                Hook::AfterAll => block.after_all.push(func),
+                Hook::BeforeEach => block.before_each.push(func),
+                Hook::AfterEach => block.after_each.push(func),
+            }
+
+            Ok(Value::Undefined)
+        },
+        realm,
+    )
+}
+
+/// Defines `describe`, `test`, `it` and the hooks on the global object.
+pub fn install(collector: &Rc<RefCell<Collector>>, realm: &mut Realm) -> Res {
+    let global = realm.global.clone();
+
+    let test = test_function(collector, Mode::Normal, realm);
+    let skip = test_function(collector, Mode::Skip, realm);
+    let only = test_function(collector, Mode::Only, realm);
+    test.define_property("skip".into(), skip.into(), realm)?;
+    test.define_property("only".into(), only.into(), realm)?;
+
+    let describe = describe_function(collector, Mode::Normal, realm);
+    let skip = describe_function(collector, Mode::Skip, realm);
+    let only = describe_function(collector, Mode::Only, realm);
+    describe.define_property("skip".into(), skip.into(), realm)?;
+    describe.define_property("only".into(), only.into(), realm)?;
+
+    global.define_property("test".into(), test.clone().into(), realm)?;
+    global.define_property("it".into(), test.into(), realm)?;
+    global.define_property("describe".into(), describe.into(), realm)?;
+
+    for (hook, name) in [
+        (Hook::BeforeAll, "beforeAll"),
+        (Hook::AfterAll, "afterAll"),
+        (Hook::BeforeEach, "beforeEach"),
+        (Hook::AfterEach, "afterEach"),
+    ] {
+        let func = hook_function(collector, hook, name, realm);
+        global.define_property(name.into(), func.into(), realm)?;
+    }
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::inline_cache::InlineCaches;
 use crate::params::VMParams;
 use crate::task::BytecodeAsyncTask;
 use std::cell::RefCell;
@@ -15,6 +16,7 @@ use yavashark_string::YSString;
 #[derive(Debug)]
 pub struct AsyncBytecodeFunction {
     code: Rc<BytecodeFunctionCode>,
+    caches: Rc<InlineCaches>,
     scope: Scope,
     params: VMParams,
 }
@@ -32,6 +34,7 @@ impl AsyncBytecodeFunction {
                 object: MutObject::with_proto(realm.intrinsics.func.clone()),
             }),
             code,
+            caches: Rc::default(),
             scope,
             params: VMParams::from(params),
         }
@@ -50,6 +53,7 @@ impl AsyncBytecodeFunction {
                 ),
             }),
             code: Rc::new(BytecodeFunctionCode::default()),
+            caches: Rc::default(),
             scope: Scope::new(realm, PathBuf::new()),
             params: VMParams::default(),
         })
@@ -98,6 +102,9 @@ impl Func for AsyncBytecodeFunction {
 
         scope.declare_var("arguments".to_string(), args.into(), realm)?;
 
-        Ok(BytecodeAsyncTask::new(Rc::clone(&self.code), realm, scope)?.into())
+        Ok(
+            BytecodeAsyncTask::new(Rc::clone(&self.code), Rc::clone(&self.caches), realm, scope)?
+                .into(),
+        )
     }
 }
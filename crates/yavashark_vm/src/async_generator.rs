@@ -1,6 +1,7 @@
 mod task;
 
 use crate::async_generator::task::AsyncGeneratorTask;
+use crate::inline_cache::InlineCaches;
 use crate::params::VMParams;
 use crate::{ResumableVM, VmState};
 use std::cell::RefCell;
@@ -12,6 +13,7 @@ use yavashark_bytecode::{BytecodeFunctionCode, BytecodeFunctionParams};
 use yavashark_env::builtins::Arguments;
 use yavashark_env::conversion::downcast_obj;
 use yavashark_env::error::Error;
+use yavashark_env::profiler::{SuspendedFrame, suspended_frame};
 use yavashark_env::realm::Intrinsic;
 use yavashark_env::scope::Scope;
 use yavashark_env::value::{Func, IntoValue, Obj};
@@ -23,6 +25,7 @@ use yavashark_string::YSString;
 #[derive(Debug)]
 pub struct AsyncGeneratorFunction {
     code: Rc<BytecodeFunctionCode>,
+    caches: Rc<InlineCaches>,
     scope: Scope,
     params: VMParams,
 }
@@ -46,6 +49,7 @@ impl AsyncGeneratorFunction {
                 ),
             }),
             code,
+            caches: Rc::default(),
             scope,
             params: VMParams::from(params),
         })
@@ -64,6 +68,7 @@ impl AsyncGeneratorFunction {
                 ),
             }),
             code: Rc::new(BytecodeFunctionCode::default()),
+            caches: Rc::default(),
             scope: Scope::new(realm, PathBuf::new()),
             params: VMParams::default(),
         })
@@ -160,7 +165,8 @@ impl Func for AsyncGeneratorFunction {
 
         scope.declare_var("arguments".to_string(), args.into(), realm)?;
 
-        let generator = AsyncGenerator::new(realm, Rc::clone(&self.code), scope)?;
+        let generator =
+            AsyncGenerator::new(realm, Rc::clone(&self.code), Rc::clone(&self.caches), scope)?;
 
         Ok(generator.into_value())
     }
@@ -170,11 +176,17 @@ impl Func for AsyncGeneratorFunction {
 pub struct AsyncGenerator {
     state: RefCell<Option<VmState>>,
     notify: Notify,
+    frame: Option<SuspendedFrame>,
 }
 
 impl AsyncGenerator {
-    pub fn new(realm: &mut Realm, code: Rc<BytecodeFunctionCode>, scope: Scope) -> Res<Self> {
-        let state = VmState::new(code, scope);
+    pub fn new(
+        realm: &mut Realm,
+        code: Rc<BytecodeFunctionCode>,
+        caches: Rc<InlineCaches>,
+        scope: Scope,
+    ) -> Res<Self> {
+        let state = VmState::new(code, scope).with_inline_caches(caches);
         Ok(Self {
             inner: RefCell::new(MutableAsyncGenerator {
                 object: MutObject::with_proto(
@@ -188,6 +200,7 @@ impl AsyncGenerator {
             }),
             state: RefCell::new(Some(state)),
             notify: Notify::new(),
+            frame: suspended_frame(realm),
         })
     }
 
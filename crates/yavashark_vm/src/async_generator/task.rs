@@ -7,6 +7,7 @@ use tokio::sync::futures::Notified;
 use yavashark_env::builtins::{Promise, PromiseState};
 use yavashark_env::conversion::downcast_obj;
 use yavashark_env::error_obj::ErrorObj;
+use yavashark_env::profiler::profile_resume;
 use yavashark_env::task_queue::{AsyncTask, AsyncTaskQueue};
 use yavashark_env::value::{BoxedObj, Obj};
 use yavashark_env::{Object, ObjectHandle, Realm, Res, Value};
@@ -60,21 +61,34 @@ impl AsyncGeneratorTask {
 impl AsyncTask for AsyncGeneratorTask {
     fn poll(self: Pin<&mut Self>, cx: &mut Context, realm: &mut Realm) -> Poll<Res> {
         let inner = Pin::into_inner(self);
+        let frame = inner.generator.frame;
 
-        if let Some(gen_notify) = &mut inner.gen_notify {
+        profile_resume(realm, frame.as_ref(), |realm| inner.resume(cx, realm))
+    }
+
+    fn run_first_sync(&mut self, realm: &mut Realm) -> Poll<Res> {
+        let frame = self.generator.frame;
+
+        profile_resume(realm, frame.as_ref(), |realm| self.poll_next(realm))
+    }
+}
+
+impl AsyncGeneratorTask {
+    fn resume(&mut self, cx: &mut Context, realm: &mut Realm) -> Poll<Res> {
+        if let Some(gen_notify) = &mut self.gen_notify {
             let pinned = unsafe { Pin::new_unchecked(&mut **gen_notify) };
             if pinned.poll(cx).is_pending() {
                 return Poll::Pending;
             }
-            inner.state = inner.generator.state.take();
-            inner.gen_notify = None;
+            self.state = self.generator.state.take();
+            self.gen_notify = None;
         }
 
-        if let Some(promise) = &mut inner.await_promise {
+        if let Some(promise) = &mut self.await_promise {
             let pinned = unsafe { Pin::new_unchecked(&mut promise.1) };
             if pinned.poll(cx).is_pending() {
                 return Poll::Pending;
-            } else if let Some(state) = inner.state.as_mut() {
+            } else if let Some(state) = self.state.as_mut() {
                 let val = promise
                     .0
                     .inner
@@ -84,7 +98,7 @@ impl AsyncTask for AsyncGeneratorTask {
                     .unwrap_or(Value::Undefined);
 
                 if promise.2 {
-                    inner.promise.resolve(&val, realm)?;
+                    self.promise.resolve(&val, realm)?;
                     return Poll::Ready(Ok(()));
                 }
 
@@ -96,16 +110,11 @@ impl AsyncTask for AsyncGeneratorTask {
             }
         }
 
-        _ = inner.await_promise.take();
+        _ = self.await_promise.take();
 
-        inner.poll_next(realm)
-    }
-    fn run_first_sync(&mut self, realm: &mut Realm) -> Poll<Res> {
         self.poll_next(realm)
     }
-}
 
-impl AsyncGeneratorTask {
     fn poll_next(&mut self, realm: &mut Realm) -> Poll<Res> {
         if let Some(state) = self.state.take() {
             let vm = ResumableVM::from_state(state, realm);
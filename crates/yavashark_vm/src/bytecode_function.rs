@@ -1,3 +1,4 @@
+use crate::inline_cache::InlineCaches;
 use crate::params::VMParams;
 use crate::{BorrowedVM, VM, VMStateFunctionCode};
 use std::cell::RefCell;
@@ -15,6 +16,7 @@ use yavashark_string::YSString;
 #[derive(Debug)]
 pub struct BytecodeFunction {
     code: Rc<BytecodeFunctionCode>,
+    caches: Rc<InlineCaches>,
     scope: Scope,
     params: VMParams,
 }
@@ -32,6 +34,7 @@ impl BytecodeFunction {
                 object: MutObject::with_proto(realm.intrinsics.func.clone()),
             }),
             code,
+            caches: Rc::default(),
             scope,
             params: VMParams::from(params),
         }
@@ -50,6 +53,7 @@ impl BytecodeFunction {
                 ),
             }),
             code: Rc::new(BytecodeFunctionCode::default()),
+            caches: Rc::default(),
             scope: Scope::new(realm, PathBuf::new()),
             params: VMParams::default(),
         })
@@ -104,7 +108,9 @@ impl Func for BytecodeFunction {
 
                 let ds = self.code.data_section();
 
-                let mut vm = BorrowedVM::with_scope(&self.code.instructions, ds, realm, scope);
+                let mut vm = BorrowedVM::with_scope(&self.code.instructions, ds, realm, scope)
+                    .with_spans(&self.code.spans)
+                    .with_inline_caches(Rc::clone(&self.caches));
 
                 match vm.run() {
                     Ok(()) => {}
@@ -1,6 +1,7 @@
 use crate::VM;
 use crate::function_code::{BytecodeArrowFunction, BytecodeFunction};
 use std::cell::RefCell;
+use std::rc::Rc;
 use yavashark_bytecode::{
     ArrayLiteralBlueprint, ConstValue, DataTypeValue, ObjectLiteralBlueprint,
 };
@@ -30,6 +31,7 @@ impl ConstIntoValue for ConstValue {
                 let func: RefCell<Box<dyn FunctionCode>> =
                     RefCell::new(Box::new(BytecodeFunction {
                         code: bp.code,
+                        caches: Rc::default(),
                         is_async: bp.is_async,
                         is_generator: bp.is_generator,
                     }));
@@ -48,6 +50,7 @@ impl ConstIntoValue for ConstValue {
                 let func: RefCell<Box<dyn FunctionCode>> =
                     RefCell::new(Box::new(BytecodeArrowFunction {
                         code: bp.code,
+                        caches: Rc::default(),
                         this: vm.get_this()?,
                         is_async: bp.is_async,
                         is_generator: bp.is_generator,
@@ -93,6 +96,7 @@ impl ConstIntoValue for ObjectLiteralBlueprint {
                     let func: RefCell<Box<dyn FunctionCode>> =
                         RefCell::new(Box::new(BytecodeFunction {
                             code: bp.code,
+                            caches: Rc::default(),
                             is_async: bp.is_async,
                             is_generator: bp.is_generator,
                         }));
@@ -119,6 +123,7 @@ impl ConstIntoValue for ObjectLiteralBlueprint {
                     let func: RefCell<Box<dyn FunctionCode>> =
                         RefCell::new(Box::new(BytecodeFunction {
                             code: bp.code,
+                            caches: Rc::default(),
                             is_async: bp.is_async,
                             is_generator: bp.is_generator,
                         }));
@@ -170,6 +175,7 @@ impl ConstIntoValue for DataTypeValue {
                 let func: RefCell<Box<dyn FunctionCode>> =
                     RefCell::new(Box::new(BytecodeFunction {
                         code: bp.code,
+                        caches: Rc::default(),
                         is_async: bp.is_async,
                         is_generator: bp.is_generator,
                     }));
@@ -188,6 +194,7 @@ impl ConstIntoValue for DataTypeValue {
                 let func: RefCell<Box<dyn FunctionCode>> =
                     RefCell::new(Box::new(BytecodeFunction {
                         code: bp.code,
+                        caches: Rc::default(),
                         is_async: bp.is_async,
                         is_generator: bp.is_generator,
                     }));
@@ -206,6 +213,7 @@ impl ConstIntoValue for DataTypeValue {
                 let func: RefCell<Box<dyn FunctionCode>> =
                     RefCell::new(Box::new(BytecodeFunction {
                         code: bp.code,
+                        caches: Rc::default(),
                         is_async: bp.is_async,
                         is_generator: bp.is_generator,
                     }));
@@ -224,6 +232,7 @@ impl ConstIntoValue for DataTypeValue {
                 let func: RefCell<Box<dyn FunctionCode>> =
                     RefCell::new(Box::new(BytecodeArrowFunction {
                         code: bp.code,
+                        caches: Rc::default(),
                         this: vm.get_this()?,
                         is_async: bp.is_async,
                         is_generator: bp.is_generator,
@@ -1,3 +1,4 @@
+use crate::inline_cache::InlineCaches;
 use crate::task::BytecodeAsyncTask;
 use crate::{BorrowedVM, OldBorrowedVM, VM};
 use std::any::Any;
@@ -33,6 +34,7 @@ impl FunctionCode for OldBytecodeFunction {
 #[derive(Debug)]
 pub struct BytecodeFunction {
     pub code: Rc<BytecodeFunctionCode>,
+    pub caches: Rc<InlineCaches>,
     pub is_async: bool,
     pub is_generator: bool,
 }
@@ -42,10 +44,18 @@ impl FunctionCode for BytecodeFunction {
         let scope = Scope::with_parent_this(scope, this)?;
 
         if self.is_async {
-            return Ok(BytecodeAsyncTask::new(Rc::clone(&self.code), realm, scope)?.into());
+            return Ok(BytecodeAsyncTask::new(
+                Rc::clone(&self.code),
+                Rc::clone(&self.caches),
+                realm,
+                scope,
+            )?
+            .into());
         }
 
-        let mut vm = BorrowedVM::with_scope(&self.code.instructions, &self.code.ds, realm, scope);
+        let mut vm = BorrowedVM::with_scope(&self.code.instructions, &self.code.ds, realm, scope)
+            .with_spans(&self.code.spans)
+            .with_inline_caches(Rc::clone(&self.caches));
 
         match vm.run() {
             Ok(()) => {}
@@ -77,6 +87,7 @@ impl FunctionCode for BytecodeFunction {
 #[derive(Debug)]
 pub struct BytecodeArrowFunction {
     pub code: Rc<BytecodeFunctionCode>,
+    pub caches: Rc<InlineCaches>,
     pub this: Value,
     pub is_async: bool,
     pub is_generator: bool,
@@ -87,10 +98,18 @@ impl FunctionCode for BytecodeArrowFunction {
         let scope = Scope::with_parent_this(scope, self.this.copy())?;
 
         if self.is_async {
-            return Ok(BytecodeAsyncTask::new(Rc::clone(&self.code), realm, scope)?.into());
+            return Ok(BytecodeAsyncTask::new(
+                Rc::clone(&self.code),
+                Rc::clone(&self.caches),
+                realm,
+                scope,
+            )?
+            .into());
         }
 
-        let mut vm = BorrowedVM::with_scope(&self.code.instructions, &self.code.ds, realm, scope);
+        let mut vm = BorrowedVM::with_scope(&self.code.instructions, &self.code.ds, realm, scope)
+            .with_spans(&self.code.spans)
+            .with_inline_caches(Rc::clone(&self.caches));
 
         match vm.run() {
             Ok(()) => {}
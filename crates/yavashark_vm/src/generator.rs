@@ -1,3 +1,4 @@
+use crate::inline_cache::InlineCaches;
 use crate::params::VMParams;
 use crate::{GeneratorPoll, ResumableVM, VmState};
 use std::cell::RefCell;
@@ -8,6 +9,7 @@ use yavashark_bytecode::{BytecodeFunctionCode, BytecodeFunctionParams};
 use yavashark_env::builtins::Arguments;
 use yavashark_env::builtins::iterator::Iterator as IteratorBuiltin;
 use yavashark_env::error::Error;
+use yavashark_env::profiler::{SuspendedFrame, profile_resume, suspended_frame};
 use yavashark_env::realm::Intrinsic;
 use yavashark_env::scope::Scope;
 use yavashark_env::value::{Func, IntoValue, Obj};
@@ -19,6 +21,7 @@ use yavashark_string::YSString;
 #[derive(Debug)]
 pub struct GeneratorFunction {
     code: Rc<BytecodeFunctionCode>,
+    caches: Rc<InlineCaches>,
     scope: Scope,
     params: VMParams,
 }
@@ -42,6 +45,7 @@ impl GeneratorFunction {
                 ),
             }),
             code,
+            caches: Rc::default(),
             scope,
             params: VMParams::from(params),
         })
@@ -60,6 +64,7 @@ impl GeneratorFunction {
                 ),
             }),
             code: Rc::new(BytecodeFunctionCode::default()),
+            caches: Rc::default(),
             scope: Scope::new(realm, PathBuf::new()),
             params: VMParams::default(),
         })
@@ -147,7 +152,8 @@ impl Func for GeneratorFunction {
 
         scope.declare_var("arguments".to_string(), args.into(), realm)?;
 
-        let generator = Generator::new(realm, Rc::clone(&self.code), scope)?;
+        let generator =
+            Generator::new(realm, Rc::clone(&self.code), Rc::clone(&self.caches), scope)?;
 
         Ok(generator.into_value())
     }
@@ -156,11 +162,17 @@ impl Func for GeneratorFunction {
 #[object]
 pub struct Generator {
     state: RefCell<Option<VmState>>,
+    frame: Option<SuspendedFrame>,
 }
 
 impl Generator {
-    pub fn new(realm: &mut Realm, code: Rc<BytecodeFunctionCode>, scope: Scope) -> Res<Self> {
-        let state = VmState::new(code, scope);
+    pub fn new(
+        realm: &mut Realm,
+        code: Rc<BytecodeFunctionCode>,
+        caches: Rc<InlineCaches>,
+        scope: Scope,
+    ) -> Res<Self> {
+        let state = VmState::new(code, scope).with_inline_caches(caches);
         Ok(Self {
             inner: RefCell::new(MutableGenerator {
                 object: MutObject::with_proto(
@@ -173,6 +185,7 @@ impl Generator {
                 ),
             }),
             state: RefCell::new(Some(state)),
+            frame: suspended_frame(realm),
         })
     }
 
@@ -204,9 +217,11 @@ impl Generator {
             return Ok(obj);
         };
 
-        let vm = ResumableVM::from_state(state, realm);
+        let poll = profile_resume(realm, self.frame.as_ref(), |realm| {
+            ResumableVM::from_state(state, realm).next()
+        });
 
-        match vm.next() {
+        match poll {
             GeneratorPoll::Yield(state, val) => {
                 self.state.replace(Some(state));
 
@@ -257,11 +272,15 @@ impl Generator {
             return Err(Error::new("Generator is already finished"));
         };
 
-        let mut vm = ResumableVM::from_state(state, realm);
+        let poll = profile_resume(realm, self.frame.as_ref(), |realm| {
+            let mut vm = ResumableVM::from_state(state, realm);
+
+            vm.handle_root_error(Error::throw(exception.unwrap_or(Value::Undefined)))?;
 
-        vm.handle_root_error(Error::throw(exception.unwrap_or(Value::Undefined)))?;
+            Ok::<_, Error>(vm.next())
+        })?;
 
-        match vm.next() {
+        match poll {
             GeneratorPoll::Yield(state, val) => {
                 self.state.replace(Some(state));
 
@@ -0,0 +1,343 @@
+use crate::VM;
+use std::cell::{Cell, RefCell, RefMut};
+use std::rc::Rc;
+use yavashark_env::value::{ObjectOrNull, Property};
+use yavashark_env::{
+    InternalPropertyKey, MutObject, ObjectHandle, ObjectProperty, PropertyKey, Res, Shape, Value,
+};
+
+/// Caches that saw more shapes than this go megamorphic.
+const MAX_ENTRIES: usize = 4;
+
+/// Prototype chains longer than this aren't cached.
+const MAX_PROTOTYPE_DEPTH: usize = 8;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Turns the inline caches of all VMs on this thread on or off.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.with(|e| e.set(enabled));
+}
+
+#[must_use]
+pub fn is_enabled() -> bool {
+    ENABLED.with(Cell::get)
+}
+
+/// The inline caches of the property accesses in a block of code.
+///
+/// They are indexed by the instruction's position, live in the function
+/// running the code and are shared by every VM running it.
+#[derive(Debug, Default)]
+pub struct InlineCaches {
+    sites: RefCell<Vec<InlineCache>>,
+}
+
+impl InlineCaches {
+    /// The cache of the instruction at `pc`, unless it is already in use
+    /// further up the stack.
+    #[must_use]
+    pub fn site(&self, pc: usize) -> Option<RefMut<'_, InlineCache>> {
+        if !is_enabled() {
+            return None;
+        }
+
+        let sites = self.sites.try_borrow_mut().ok()?;
+
+        Some(RefMut::map(sites, |sites| {
+            if sites.len() <= pc {
+                sites.resize_with(pc + 1, InlineCache::default);
+            }
+
+            &mut sites[pc]
+        }))
+    }
+}
+
+/// The cache of a single instruction. It starts out empty, becomes
+/// monomorphic with the first shape it sees and polymorphic with the
+/// following ones, up to [`MAX_ENTRIES`].
+#[derive(Debug, Default)]
+pub enum InlineCache {
+    #[default]
+    Empty,
+    Cached {
+        key: PropertyKey,
+        entries: Vec<Entry>,
+    },
+    Megamorphic,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    shape: Rc<Shape>,
+    holder: Holder,
+}
+
+#[derive(Debug)]
+enum Holder {
+    Own(usize),
+    /// The property lives on the last object of the prototype chain, every
+    /// object on the way must still have the same prototype and shape.
+    Prototype {
+        chain: Vec<(ObjectHandle, Rc<Shape>)>,
+        slot: usize,
+    },
+}
+
+impl InlineCache {
+    fn get(&self, obj: &ObjectHandle, key: &Value) -> Option<Property> {
+        let Self::Cached {
+            key: cached,
+            entries,
+        } = self
+        else {
+            return None;
+        };
+
+        if !key_matches(cached, key) {
+            return None;
+        }
+
+        let receiver = obj.shaped_object()?;
+
+        entries
+            .iter()
+            .find(|entry| Rc::ptr_eq(&entry.shape, &receiver.shape))?
+            .get(receiver)
+    }
+
+    fn own_slot(&self, receiver: &MutObject, key: &Value) -> Option<usize> {
+        let Self::Cached {
+            key: cached,
+            entries,
+        } = self
+        else {
+            return None;
+        };
+
+        if !key_matches(cached, key) {
+            return None;
+        }
+
+        entries.iter().find_map(|entry| match entry.holder {
+            Holder::Own(slot) if Rc::ptr_eq(&entry.shape, &receiver.shape) => Some(slot),
+            _ => None,
+        })
+    }
+
+    fn insert(&mut self, key: PropertyKey, entry: Entry) {
+        match self {
+            Self::Cached {
+                key: cached,
+                entries,
+            } if *cached == key => {
+                if entries.len() >= MAX_ENTRIES {
+                    *self = Self::Megamorphic;
+                } else {
+                    entries.push(entry);
+                }
+            }
+            Self::Megamorphic => {}
+            _ => {
+                *self = Self::Cached {
+                    key,
+                    entries: vec![entry],
+                }
+            }
+        }
+    }
+}
+
+impl Entry {
+    fn get(&self, receiver: std::cell::Ref<'_, MutObject>) -> Option<Property> {
+        match &self.holder {
+            Holder::Own(slot) => receiver.slot(*slot).map(ObjectProperty::property),
+            Holder::Prototype { chain, slot } => {
+                let mut current = receiver;
+
+                for (holder, shape) in chain {
+                    if !matches!(&current.prototype, ObjectOrNull::Object(proto) if proto == holder)
+                    {
+                        return None;
+                    }
+
+                    let next = holder.shaped_object()?;
+
+                    if !Rc::ptr_eq(&next.shape, shape) {
+                        return None;
+                    }
+
+                    current = next;
+                }
+
+                current.slot(*slot).map(ObjectProperty::property)
+            }
+        }
+    }
+
+    fn lookup(obj: &ObjectHandle, key: &PropertyKey) -> Option<(Self, Property)> {
+        let receiver = obj.shaped_object()?;
+
+        if !receiver.shape.is_cacheable() {
+            return None;
+        }
+
+        let shape = Rc::clone(&receiver.shape);
+
+        if let Some(slot) = receiver.slot_of(key) {
+            let prop = receiver.slot(slot)?.property();
+
+            return Some((
+                Self {
+                    shape,
+                    holder: Holder::Own(slot),
+                },
+                prop,
+            ));
+        }
+
+        let mut chain = Vec::new();
+        let mut proto = receiver.prototype.clone();
+        drop(receiver);
+
+        while let ObjectOrNull::Object(holder) = proto {
+            if chain.len() >= MAX_PROTOTYPE_DEPTH {
+                return None;
+            }
+
+            let object = holder.shaped_object()?;
+
+            if !object.shape.is_cacheable() {
+                return None;
+            }
+
+            let found = object
+                .slot_of(key)
+                .and_then(|slot| Some((slot, object.slot(slot)?.property())));
+
+            let holder_shape = Rc::clone(&object.shape);
+            proto = object.prototype.clone();
+            drop(object);
+
+            chain.push((holder, holder_shape));
+
+            if let Some((slot, prop)) = found {
+                return Some((
+                    Self {
+                        shape,
+                        holder: Holder::Prototype { chain, slot },
+                    },
+                    prop,
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+fn key_matches(cached: &PropertyKey, key: &Value) -> bool {
+    match (cached, key) {
+        (PropertyKey::String(cached), Value::String(key)) => cached == key,
+        (PropertyKey::Symbol(cached), Value::Symbol(key)) => cached == key,
+        _ => false,
+    }
+}
+
+/// Only named properties are cached, array indices and `__proto__` go through
+/// the generic lookup.
+fn cacheable_key(key: &Value) -> Option<PropertyKey> {
+    match key {
+        Value::String(s) => match InternalPropertyKey::from_ys_string(s.clone()) {
+            InternalPropertyKey::String(s) if s != "__proto__" => Some(PropertyKey::String(s)),
+            _ => None,
+        },
+        Value::Symbol(s) => Some(PropertyKey::Symbol(s.clone())),
+        _ => None,
+    }
+}
+
+fn lookup(obj: &ObjectHandle, key: &Value, vm: &impl VM) -> Option<Property> {
+    let mut cache = vm.inline_cache()?;
+
+    if let Some(prop) = cache.get(obj, key) {
+        return Some(prop);
+    }
+
+    if matches!(*cache, InlineCache::Megamorphic) {
+        return None;
+    }
+
+    let key = cacheable_key(key)?;
+    let (entry, prop) = Entry::lookup(obj, &key)?;
+    cache.insert(key, entry);
+
+    Some(prop)
+}
+
+/// Gets the property `key` of `obj` through the inline cache of the current
+/// instruction. Returns `None` if the generic lookup has to be used.
+pub fn load(obj: &ObjectHandle, key: &Value, vm: &mut impl VM) -> Res<Option<Value>> {
+    let Some(prop) = lookup(obj, key, vm) else {
+        return Ok(None);
+    };
+
+    match prop {
+        Property::Value(value, _) => Ok(Some(value)),
+        Property::Getter(getter, _) => getter
+            .call(Vec::new(), obj.clone().into(), vm.get_realm())
+            .map(Some),
+    }
+}
+
+/// Sets the own writable data property `key` of `obj` through the inline
+/// cache of the current instruction. Hands `value` back if the generic path
+/// has to be used.
+pub fn store(obj: &ObjectHandle, key: &Value, value: Value, vm: &impl VM) -> Result<(), Value> {
+    let Some(mut cache) = vm.inline_cache() else {
+        return Err(value);
+    };
+
+    let Some(mut receiver) = obj.shaped_object_mut() else {
+        return Err(value);
+    };
+
+    let slot = match cache.own_slot(&receiver, key) {
+        Some(slot) => slot,
+        None if matches!(*cache, InlineCache::Megamorphic) || !receiver.shape.is_cacheable() => {
+            return Err(value);
+        }
+        None => {
+            let Some(key) = cacheable_key(key) else {
+                return Err(value);
+            };
+
+            let Some(slot) = receiver.slot_of(&key) else {
+                return Err(value);
+            };
+
+            let entry = Entry {
+                shape: Rc::clone(&receiver.shape),
+                holder: Holder::Own(slot),
+            };
+            cache.insert(key, entry);
+
+            slot
+        }
+    };
+
+    match receiver.slot_mut(slot) {
+        Some(prop)
+            if prop.attributes.is_writable()
+                && prop.get.is_undefined()
+                && prop.set.is_undefined() =>
+        {
+            prop.value = value;
+            Ok(())
+        }
+        _ => Err(value),
+    }
+}
@@ -1,5 +1,6 @@
 use crate::VM;
 use crate::data::{Data, OutputData};
+use crate::inline_cache;
 use crate::instruction::get_private_member;
 use yavashark_env::utils::ValueIterator;
 use yavashark_env::{ControlFlow, ControlResult, Error, Res, Value};
@@ -37,7 +38,7 @@ pub fn call_member(
 
     let args = vm.get_call_args();
 
-    let ret = obj.call_method(&member, vm.get_realm(), args)?;
+    let ret = call_method(&obj, &member, args, vm)?;
 
     output.set(ret, vm)
 }
@@ -48,11 +49,21 @@ pub fn call_member_no_output(obj: impl Data, member: impl Data, vm: &mut impl VM
 
     let args = vm.get_call_args();
 
-    obj.call_method(&member, vm.get_realm(), args)?;
+    call_method(&obj, &member, args, vm)?;
 
     Ok(())
 }
 
+fn call_method(obj: &Value, member: &Value, args: Vec<Value>, vm: &mut impl VM) -> Res<Value> {
+    if let Value::Object(o) = obj
+        && let Some(method) = inline_cache::load(o, member, vm)?
+    {
+        return method.call(vm.get_realm(), args, obj.copy());
+    }
+
+    obj.call_method(member, vm.get_realm(), args)
+}
+
 pub fn call_private_member(
     obj: impl Data,
     member: impl Data,
@@ -1,5 +1,6 @@
 use crate::VM;
 use crate::data::{Data, OutputData};
+use crate::inline_cache;
 use yavashark_bytecode::JmpAddr;
 use yavashark_bytecode::data::{ControlIdx, Label, VarName};
 use yavashark_env::array::Array;
@@ -60,6 +61,12 @@ pub fn load_member(
     let left = left.get(vm)?;
     let right = right.get(vm)?;
 
+    if let Value::Object(obj) = &left
+        && let Some(result) = inline_cache::load(obj, &right, vm)?
+    {
+        return output.set(result, vm);
+    }
+
     let result = left.get_property_opt(&right, vm.get_realm())?;
 
     output.set(result.unwrap_or(Value::Undefined), vm)
@@ -87,7 +94,14 @@ pub fn load_private_member(
 pub fn store_member(obj: impl Data, prop: impl Data, value: impl Data, vm: &mut impl VM) -> Res {
     let obj = obj.get(vm)?;
     let prop = prop.get(vm)?;
-    let value = value.get(vm)?;
+    let mut value = value.get(vm)?;
+
+    if let Value::Object(o) = &obj {
+        match inline_cache::store(o, &prop, value, vm) {
+            Ok(()) => return Ok(()),
+            Err(v) => value = v,
+        }
+    }
 
     obj.define_property(prop, value, vm.get_realm())?;
 
@@ -7,6 +7,7 @@ mod execute;
 mod execute_old;
 pub mod function_code;
 pub mod generator;
+pub mod inline_cache;
 mod instruction;
 mod instructions;
 mod params;
@@ -1,3 +1,4 @@
+use crate::inline_cache::InlineCaches;
 use crate::{AsyncPoll, ResumableVM, VmState};
 use std::future::Future;
 use std::pin::Pin;
@@ -8,6 +9,7 @@ use yavashark_bytecode::BytecodeFunctionCode;
 use yavashark_env::builtins::{Promise, PromiseState};
 use yavashark_env::conversion::downcast_obj;
 use yavashark_env::error_obj::ErrorObj;
+use yavashark_env::profiler::{SuspendedFrame, profile_resume, suspended_frame};
 use yavashark_env::scope::Scope;
 use yavashark_env::task_queue::{AsyncTask, AsyncTaskQueue};
 use yavashark_env::value::{BoxedObj, Obj};
@@ -18,6 +20,7 @@ pub struct BytecodeAsyncTask {
     state: Option<VmState>,
     await_promise: Option<OwningGcGuardRefed<BoxedObj, (&'static Promise, Notified<'static>)>>,
     promise: OwningGcGuard<'static, BoxedObj, Promise>,
+    frame: Option<SuspendedFrame>,
 }
 
 impl Unpin for BytecodeAsyncTask {}
@@ -26,10 +29,11 @@ impl BytecodeAsyncTask {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(
         code: Rc<BytecodeFunctionCode>,
+        caches: Rc<InlineCaches>,
         realm: &mut Realm,
         scope: Scope,
     ) -> Res<ObjectHandle> {
-        let state = VmState::new(code, scope);
+        let state = VmState::new(code, scope).with_inline_caches(caches);
         let promise_obj = Promise::new(realm)?.into_object();
         let promise = downcast_obj::<Promise>(promise_obj.clone().into())?;
 
@@ -37,6 +41,7 @@ impl BytecodeAsyncTask {
             state: Some(state),
             await_promise: None,
             promise,
+            frame: suspended_frame(realm),
         };
 
         AsyncTaskQueue::queue_task(this, realm);
@@ -48,12 +53,23 @@ impl BytecodeAsyncTask {
 impl AsyncTask for BytecodeAsyncTask {
     fn poll(self: Pin<&mut Self>, cx: &mut Context, realm: &mut Realm) -> Poll<Res> {
         let inner = Pin::into_inner(self);
+        let frame = inner.frame;
 
-        if let Some(promise) = &mut inner.await_promise {
+        profile_resume(realm, frame.as_ref(), |realm| inner.resume(cx, realm))
+    }
+
+    fn run_first_sync(&mut self, realm: &mut Realm) -> Poll<Res> {
+        self.poll_next(realm)
+    }
+}
+
+impl BytecodeAsyncTask {
+    fn resume(&mut self, cx: &mut Context, realm: &mut Realm) -> Poll<Res> {
+        if let Some(promise) = &mut self.await_promise {
             let pinned = unsafe { Pin::new_unchecked(&mut promise.1) };
             if pinned.poll(cx).is_pending() {
                 return Poll::Pending;
-            } else if let Some(state) = inner.state.as_mut() {
+            } else if let Some(state) = self.state.as_mut() {
                 let val = promise
                     .0
                     .inner
@@ -70,17 +86,11 @@ impl AsyncTask for BytecodeAsyncTask {
             }
         }
 
-        _ = inner.await_promise.take();
-
-        inner.poll_next(realm)
-    }
+        _ = self.await_promise.take();
 
-    fn run_first_sync(&mut self, realm: &mut Realm) -> Poll<Res> {
         self.poll_next(realm)
     }
-}
 
-impl BytecodeAsyncTask {
     fn poll_next(&mut self, realm: &mut Realm) -> Poll<Res> {
         if let Some(state) = self.state.take() {
             let vm = ResumableVM::from_state(state, realm);
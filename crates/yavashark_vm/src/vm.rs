@@ -9,10 +9,13 @@ pub use old::owned::*;
 pub use owned::*;
 pub use resumable_vm::*;
 
+use crate::inline_cache::InlineCache;
+use std::cell::RefMut;
 use yavashark_bytecode::data::{ControlIdx, Label, OutputData};
-use yavashark_bytecode::{ConstIdx, Reg, VarName};
+use yavashark_bytecode::{ConstIdx, Reg, SpanMark, VarName};
+use yavashark_env::debugger::SourceSpan;
 use yavashark_env::scope::Scope;
-use yavashark_env::{ObjectHandle, Res, Value};
+use yavashark_env::{ObjectHandle, Realm, Res, Value};
 
 pub trait VM {
     fn acc(&self) -> Value;
@@ -66,4 +69,31 @@ pub trait VM {
     fn push_spread(&mut self, elem: Value) -> Res;
     fn end_spread(&mut self, obj: ObjectHandle) -> Res<ObjectHandle>;
     fn end_spread_no_output(&mut self) -> Res;
+
+    /// The inline cache of the instruction that is currently executing.
+    fn inline_cache(&self) -> Option<RefMut<'_, InlineCache>> {
+        None
+    }
+}
+
+/// Reports the source spans that start at `pc` to the coverage of the realm.
+fn cover(realm: &Realm, scope: &Scope, spans: &[SpanMark], pc: usize) {
+    if !realm.is_collecting_coverage() {
+        return;
+    }
+
+    let start = spans.partition_point(|mark| (mark.pc as usize) < pc);
+
+    for mark in spans[start..]
+        .iter()
+        .take_while(|mark| mark.pc as usize == pc)
+    {
+        realm.cover(
+            scope,
+            SourceSpan {
+                lo: mark.lo,
+                hi: mark.hi,
+            },
+        );
+    }
 }
@@ -1,12 +1,15 @@
 use crate::consts::ConstIntoValue;
 use crate::execute::Execute;
+use crate::inline_cache::{InlineCache, InlineCaches};
 use crate::{Registers, Stack, VM};
+use std::cell::{OnceCell, RefMut};
 use std::mem;
 use std::path::PathBuf;
+use std::rc::Rc;
 use yavashark_bytecode::control::{ControlBlock, TryBlock};
 use yavashark_bytecode::data::{ControlIdx, DataSection, Label, OutputData, OutputDataType};
 use yavashark_bytecode::instructions::Instruction;
-use yavashark_bytecode::{ConstIdx, Reg, VarName};
+use yavashark_bytecode::{ConstIdx, Reg, SpanMark, VarName};
 use yavashark_env::error_obj::ErrorObj;
 use yavashark_env::scope::Scope;
 use yavashark_env::value::property_key::IntoPropertyKey;
@@ -22,6 +25,7 @@ pub struct BorrowedVM<'a> {
     pc: usize,
     code: &'a [Instruction],
     data: &'a DataSection,
+    spans: &'a [SpanMark],
 
     pub current_scope: Scope,
 
@@ -35,6 +39,8 @@ pub struct BorrowedVM<'a> {
     try_stack: Vec<TryBlock>,
 
     throw: Option<Error>,
+
+    inline_caches: OnceCell<Rc<InlineCaches>>,
 }
 
 impl<'a> BorrowedVM<'a> {
@@ -51,6 +57,7 @@ impl<'a> BorrowedVM<'a> {
             pc: 0,
             code,
             data,
+            spans: &[],
             current_scope: Scope::new(realm, file),
             acc: Value::Undefined,
             realm,
@@ -58,6 +65,7 @@ impl<'a> BorrowedVM<'a> {
             spread_stack: Vec::new(),
             try_stack: Vec::new(),
             throw: None,
+            inline_caches: OnceCell::new(),
         })
     }
 
@@ -75,6 +83,7 @@ impl<'a> BorrowedVM<'a> {
             pc: 0,
             code,
             data,
+            spans: &[],
             current_scope: scope,
             acc: Value::Undefined,
             realm,
@@ -82,11 +91,34 @@ impl<'a> BorrowedVM<'a> {
             spread_stack: Vec::new(),
             try_stack: Vec::new(),
             throw: None,
+            inline_caches: OnceCell::new(),
         }
     }
 
+    /// Sets the source spans of `code`, which are reported while collecting
+    /// coverage.
+    #[must_use]
+    pub const fn with_spans(mut self, spans: &'a [SpanMark]) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Uses the inline caches of the function running `code` instead of ones
+    /// that only live as long as this VM.
+    #[must_use]
+    pub fn with_inline_caches(self, caches: Rc<InlineCaches>) -> Self {
+        _ = self.inline_caches.set(caches);
+        self
+    }
+
     pub fn run(&mut self) -> ControlResult {
         while self.pc < self.code.len() {
+            self.realm.step()?;
+
+            if !self.spans.is_empty() {
+                super::cover(self.realm, &self.current_scope, self.spans, self.pc);
+            }
+
             let instr = self.code[self.pc];
             self.pc += 1;
 
@@ -105,7 +137,7 @@ impl<'a> BorrowedVM<'a> {
     }
 
     pub fn handle_root_error(&mut self, err: Error) -> Res {
-        if self.try_stack.is_empty() {
+        if self.try_stack.is_empty() || err.is_terminated() {
             return Err(err);
         }
 
@@ -140,13 +172,16 @@ impl VM for BorrowedVM<'_> {
     }
 
     fn get_variable(&mut self, name: VarName) -> Res<Value> {
-        let Some(name) = self.data.var_names.get(name as usize) else {
+        let Some((name, binding)) = self.data.var(name) else {
             return Err(Error::reference("Invalid variable name"));
         };
 
-        self.current_scope
-            .resolve(name, self.realm)?
-            .ok_or(Error::reference("Variable not found"))
+        let value = match binding {
+            Some(pos) => self.current_scope.resolve_at(pos, name, self.realm)?,
+            None => self.current_scope.resolve(name, self.realm)?,
+        };
+
+        value.ok_or(Error::reference("Variable not found"))
     }
 
     fn var_name(&self, name: VarName) -> Option<&str> {
@@ -168,9 +203,19 @@ impl VM for BorrowedVM<'_> {
     }
 
     fn set_variable(&mut self, name: VarName, value: Value) -> Res {
-        let name = self
-            .var_name(name)
+        let (name, binding) = self
+            .data
+            .var(name)
             .ok_or(Error::reference("Invalid variable name"))?;
+
+        if let Some(pos) = binding
+            && self
+                .current_scope
+                .try_update_at(pos, name, &value, self.realm)?
+        {
+            return Ok(());
+        }
+
         self.current_scope
             .update_or_define(name.into(), value, self.realm)
     }
@@ -357,4 +402,10 @@ impl VM for BorrowedVM<'_> {
 
         Ok(())
     }
+
+    fn inline_cache(&self) -> Option<RefMut<'_, InlineCache>> {
+        self.inline_caches
+            .get_or_init(Rc::default)
+            .site(self.pc.checked_sub(1)?)
+    }
 }
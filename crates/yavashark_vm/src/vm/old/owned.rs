@@ -416,6 +416,7 @@ mod test {
                     "console".to_string(),
                     "log".to_string(),
                 ],
+                var_bindings: Vec::new(),
                 labels: Vec::new(),
                 constants: vec![
                     ConstValue::String("Hello, World!".into()),
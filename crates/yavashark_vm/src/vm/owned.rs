@@ -1,8 +1,11 @@
 use crate::consts::ConstIntoValue;
 use crate::execute::Execute;
+use crate::inline_cache::{InlineCache, InlineCaches};
 use crate::{Registers, Stack, VM};
+use std::cell::{OnceCell, RefMut};
 use std::mem;
 use std::path::PathBuf;
+use std::rc::Rc;
 use yavashark_bytecode::control::{ControlBlock, TryBlock};
 use yavashark_bytecode::data::{ControlIdx, DataSection, Label, OutputData, OutputDataType};
 use yavashark_bytecode::instructions::Instruction;
@@ -34,6 +37,8 @@ pub struct OwnedVM {
     try_stack: Vec<TryBlock>,
 
     throw: Option<Error>,
+
+    inline_caches: OnceCell<Rc<InlineCaches>>,
 }
 
 impl OwnedVM {
@@ -58,6 +63,7 @@ impl OwnedVM {
             spread_stack: Vec::new(),
             try_stack: Vec::new(),
             throw: None,
+            inline_caches: OnceCell::new(),
         })
     }
 
@@ -82,6 +88,7 @@ impl OwnedVM {
             spread_stack: Vec::new(),
             try_stack: Vec::new(),
             throw: None,
+            inline_caches: OnceCell::new(),
         }
     }
 
@@ -106,11 +113,14 @@ impl OwnedVM {
             spread_stack: Vec::new(),
             try_stack: Vec::new(),
             throw: None,
+            inline_caches: OnceCell::new(),
         }
     }
 
     pub fn run(&mut self) -> ControlResult {
         while self.pc < self.code.len() {
+            self.realm.step()?;
+
             let instr = self.code[self.pc];
             self.pc += 1;
 
@@ -129,7 +139,7 @@ impl OwnedVM {
     }
 
     pub fn handle_root_error(&mut self, err: Error) -> Res {
-        if self.try_stack.is_empty() {
+        if self.try_stack.is_empty() || err.is_terminated() {
             return Err(err);
         }
 
@@ -163,13 +173,16 @@ impl VM for OwnedVM {
     }
 
     fn get_variable(&mut self, name: VarName) -> Res<Value> {
-        let Some(name) = self.data.var_names.get(name as usize) else {
+        let Some((name, binding)) = self.data.var(name) else {
             return Err(Error::reference("Invalid variable name"));
         };
 
-        self.current_scope
-            .resolve(name, &mut self.realm)?
-            .ok_or(Error::reference("Variable not found"))
+        let value = match binding {
+            Some(pos) => self.current_scope.resolve_at(pos, name, &mut self.realm)?,
+            None => self.current_scope.resolve(name, &mut self.realm)?,
+        };
+
+        value.ok_or(Error::reference("Variable not found"))
     }
 
     fn var_name(&self, name: VarName) -> Option<&str> {
@@ -191,9 +204,19 @@ impl VM for OwnedVM {
     }
 
     fn set_variable(&mut self, name: VarName, value: Value) -> Res {
-        let name = self
-            .var_name(name)
+        let (name, binding) = self
+            .data
+            .var(name)
             .ok_or(Error::reference("Invalid variable name"))?;
+
+        if let Some(pos) = binding
+            && self
+                .current_scope
+                .try_update_at(pos, name, &value, &mut self.realm)?
+        {
+            return Ok(());
+        }
+
         self.current_scope
             .update_or_define(name.into(), value, &mut self.realm)
     }
@@ -380,4 +403,10 @@ impl VM for OwnedVM {
 
         Ok(())
     }
+
+    fn inline_cache(&self) -> Option<RefMut<'_, InlineCache>> {
+        self.inline_caches
+            .get_or_init(Rc::default)
+            .site(self.pc.checked_sub(1)?)
+    }
 }
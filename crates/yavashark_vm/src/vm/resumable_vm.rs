@@ -1,6 +1,8 @@
 use crate::consts::ConstIntoValue;
 use crate::execute::Execute;
+use crate::inline_cache::{InlineCache, InlineCaches};
 use crate::{Registers, Stack, VM};
+use std::cell::{OnceCell, RefMut};
 use std::mem;
 use std::rc::Rc;
 use yavashark_bytecode::control::{ControlBlock, TryBlock};
@@ -33,6 +35,8 @@ pub struct VmState<T: VMStateFunctionCode = Rc<BytecodeFunctionCode>> {
 
     pub spread_stack: Vec<Vec<PropertyKey>>,
     pub throw: Option<Error>,
+
+    pub inline_caches: OnceCell<Rc<InlineCaches>>,
 }
 
 pub trait VMStateFunctionCode: Clone {
@@ -88,8 +92,17 @@ impl<T: VMStateFunctionCode> VmState<T> {
             try_stack: Vec::new(),
             yield_star_val: None,
             throw: None,
+            inline_caches: OnceCell::new(),
         }
     }
+
+    /// Uses the inline caches of the function running `code`.
+    #[must_use]
+    pub fn with_inline_caches(self, caches: Rc<InlineCaches>) -> Self {
+        _ = self.inline_caches.set(caches);
+        self
+    }
+
     pub fn continue_async(&mut self, val: Value, realm: &mut Realm) -> Res {
         if let Some(storage) = self.continue_storage.take() {
             match storage {
@@ -152,7 +165,7 @@ impl<'a, T: VMStateFunctionCode> ResumableVM<'a, T> {
     }
 
     pub fn handle_root_error(&mut self, err: Error) -> Res {
-        if self.state.try_stack.is_empty() {
+        if self.state.try_stack.is_empty() || err.is_terminated() {
             return Err(err);
         }
 
@@ -192,6 +205,17 @@ impl ResumableVM<'_> {
         }
 
         while self.state.pc < self.state.code.instructions.len() {
+            if let Err(e) = self.realm.step() {
+                return GeneratorPoll::Ret(Err(e));
+            }
+
+            super::cover(
+                self.realm,
+                &self.state.current_scope,
+                &self.state.code.spans,
+                self.state.pc,
+            );
+
             let instr = &self.state.code.instructions[self.state.pc];
             self.state.pc += 1;
 
@@ -266,6 +290,17 @@ impl ResumableVM<'_> {
         }
 
         while self.state.pc < self.state.code.instructions.len() {
+            if let Err(e) = self.realm.step() {
+                return AsyncGeneratorPoll::Ret(self.state, Err(e));
+            }
+
+            super::cover(
+                self.realm,
+                &self.state.current_scope,
+                &self.state.code.spans,
+                self.state.pc,
+            );
+
             let instr = &self.state.code.instructions[self.state.pc];
             self.state.pc += 1;
 
@@ -338,6 +373,17 @@ impl ResumableVM<'_> {
     #[must_use]
     pub fn poll(mut self) -> AsyncPoll {
         while self.state.pc < self.state.code.instructions.len() {
+            if let Err(e) = self.realm.step() {
+                return AsyncPoll::Ret(self.state, Err(e));
+            }
+
+            super::cover(
+                self.realm,
+                &self.state.current_scope,
+                &self.state.code.spans,
+                self.state.pc,
+            );
+
             let instr = &self.state.code.instructions[self.state.pc];
             self.state.pc += 1;
 
@@ -409,14 +455,18 @@ impl<T: VMStateFunctionCode> VM for ResumableVM<'_, T> {
     }
 
     fn get_variable(&mut self, name: VarName) -> Res<Value> {
-        let Some(name) = self.state.code.data_section().var_names.get(name as usize) else {
+        let Some((name, binding)) = self.state.code.data_section().var(name) else {
             return Err(Error::reference("Invalid variable name"));
         };
 
-        self.state
-            .current_scope
-            .resolve(name, self.realm)?
-            .ok_or(Error::reference("Variable not found"))
+        let scope = &self.state.current_scope;
+
+        let value = match binding {
+            Some(pos) => scope.resolve_at(pos, name, self.realm)?,
+            None => scope.resolve(name, self.realm)?,
+        };
+
+        value.ok_or(Error::reference("Variable not found"))
     }
 
     fn var_name(&self, name: VarName) -> Option<&str> {
@@ -446,12 +496,22 @@ impl<T: VMStateFunctionCode> VM for ResumableVM<'_, T> {
     }
 
     fn set_variable(&mut self, name: VarName, value: Value) -> Res {
-        let name = self
-            .var_name(name)
+        let (name, binding) = self
+            .state
+            .code
+            .data_section()
+            .var(name)
             .ok_or(Error::reference("Invalid variable name"))?;
-        self.state
-            .current_scope
-            .update_or_define(name.into(), value, self.realm)
+
+        let scope = &mut self.state.current_scope;
+
+        if let Some(pos) = binding
+            && scope.try_update_at(pos, name, &value, self.realm)?
+        {
+            return Ok(());
+        }
+
+        scope.update_or_define(name.into(), value, self.realm)
     }
 
     fn set_register(&mut self, reg: Reg, value: Value) -> Res {
@@ -640,4 +700,11 @@ impl<T: VMStateFunctionCode> VM for ResumableVM<'_, T> {
 
         Ok(())
     }
+
+    fn inline_cache(&self) -> Option<RefMut<'_, InlineCache>> {
+        self.state
+            .inline_caches
+            .get_or_init(Rc::default)
+            .site(self.state.pc.checked_sub(1)?)
+    }
 }
@@ -7,6 +7,12 @@ mod repl;
 #[cfg(not(feature = "minimal"))]
 mod run;
 
+/// Counts what every thread allocates, so `--max-heap` sees the buffers of
+/// values too.
+#[global_allocator]
+static ALLOCATOR: yavashark_env::usage::CountingAllocator =
+    yavashark_env::usage::CountingAllocator(std::alloc::System);
+
 fn main() {
     #[cfg(not(feature = "minimal"))]
     run::main();
@@ -1,42 +1,113 @@
 mod helper;
+mod top_level_await;
 
 use crate::conf::Conf;
 use crate::repl::helper::ReplHelper;
 use rustyline::error::ReadlineError;
 use rustyline::{CompletionType, Config, EditMode, Editor};
-use std::path::Path;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use swc_common::BytePos;
 use swc_common::input::StringInput;
+use swc_ecma_ast::{ModuleItem, Stmt};
 use swc_ecma_parser::{EsSyntax, Parser, Syntax};
 use tokio::runtime::{Builder, Runtime};
+use yavashark_env::builtins::{Promise, PromiseResult, PromiseState};
 use yavashark_env::print::PrettyPrint;
 use yavashark_env::scope::Scope;
-use yavashark_env::{Realm, Res};
+use yavashark_env::{Error, Realm, Res, Value};
 use yavashark_interpreter::eval::InterpreterEval;
 use yavashark_swc_validator::Validator;
 
-pub fn repl(conf: Conf) -> Res {
-    let path = Path::new("repl.js");
+/// The dot commands of the REPL with their description.
+pub const COMMANDS: &[(&str, &str)] = &[
+    (".clear", "Reset the REPL to a fresh realm"),
+    (".help", "Print this help"),
+    (".load", "Run a file in the REPL: .load <file>"),
+    (
+        ".save",
+        "Save the inputs of this session to a file: .save <file>",
+    ),
+];
+
+/// A realm and the global scope the REPL input runs in.
+pub struct Session {
+    pub realm: Realm,
+    pub scope: Scope,
+}
 
-    let mut interpreter_realm = Realm::new()?;
+impl Session {
+    fn interpreter(path: &Path) -> Res<Self> {
+        let mut realm = Realm::new()?;
 
-    #[cfg(feature = "vm")]
-    crate::optimizer::define_optimizer(&mut interpreter_realm)?;
-    #[cfg(feature = "vm")]
-    yavashark_vm::init(&mut interpreter_realm)?;
-    interpreter_realm.set_eval(InterpreterEval, false)?;
-    let mut interpreter_scope = Scope::global(&interpreter_realm, path.to_path_buf());
+        #[cfg(feature = "vm")]
+        crate::optimizer::define_optimizer(&mut realm)?;
+        #[cfg(feature = "vm")]
+        yavashark_vm::init(&mut realm)?;
+        realm.set_eval(InterpreterEval, false)?;
+        let scope = Scope::global(&realm, path.to_path_buf());
 
-    let mut vm_realm = Realm::new()?;
-    vm_realm.set_eval(InterpreterEval, false)?;
-    #[cfg(feature = "vm")]
-    yavashark_vm::init(&mut vm_realm)?;
-    let vm_scope = Scope::global(&vm_realm, path.to_path_buf());
+        Ok(Self { realm, scope })
+    }
 
-    let mut old_vm_realm = Realm::new()?;
-    old_vm_realm.set_eval(InterpreterEval, false)?;
-    #[cfg(feature = "vm")]
-    yavashark_vm::init(&mut old_vm_realm)?;
+    fn vm(path: &Path) -> Res<Self> {
+        let mut realm = Realm::new()?;
+        realm.set_eval(InterpreterEval, false)?;
+        #[cfg(feature = "vm")]
+        yavashark_vm::init(&mut realm)?;
+        let scope = Scope::global(&realm, path.to_path_buf());
+
+        Ok(Self { realm, scope })
+    }
+
+    /// Declares `names` as global variables, unless they already exist.
+    fn declare(&mut self, names: &[String]) -> Res {
+        for name in names {
+            if self.scope.resolve(name, &mut self.realm)?.is_none() {
+                self.scope
+                    .declare_global_var(name.clone(), Value::Undefined, &mut self.realm)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `$XDG_DATA_HOME/yavashark/repl_history`, falling back to the platform's
+/// data directory.
+fn history_file() -> Option<PathBuf> {
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                std::env::var_os("APPDATA").map(PathBuf::from)
+            } else if cfg!(target_os = "macos") {
+                std::env::home_dir().map(|home| home.join("Library/Application Support"))
+            } else {
+                std::env::home_dir().map(|home| home.join(".local/share"))
+            }
+        })?;
+
+    Some(data.join("yavashark").join("repl_history"))
+}
+
+fn print_help() {
+    for (command, description) in COMMANDS {
+        println!("{command:<10} {description}");
+    }
+
+    println!();
+    println!("!<file>    Run a file in the REPL");
+    println!("Press Ctrl-D to exit, Tab to complete names and properties.");
+}
+
+pub fn repl(conf: Conf) -> Res {
+    let path = Path::new("repl.js");
+
+    let interpreter = Rc::new(RefCell::new(Session::interpreter(path)?));
+    let vm = Rc::new(RefCell::new(Session::vm(path)?));
 
     let config = Config::builder()
         .history_ignore_space(true)
@@ -46,11 +117,18 @@ pub fn repl(conf: Conf) -> Res {
 
     let mut rl = Editor::with_config(config)?;
 
-    let h = ReplHelper::new(interpreter_scope.clone(), vm_scope.clone(), conf);
+    let completed = if conf.interpreter { &interpreter } else { &vm };
+    rl.set_helper(Some(ReplHelper::new(Rc::clone(completed))));
 
-    rl.set_helper(Some(h));
+    let history = history_file();
+
+    if let Some(history) = &history {
+        // there is no history on the first start
+        _ = rl.load_history(history);
+    }
 
     let mut count = 1u32;
+    let mut inputs = Vec::new();
 
     let rt = Builder::new_current_thread().enable_all().build()?;
 
@@ -81,41 +159,59 @@ pub fn repl(conf: Conf) -> Res {
         rl.add_history_entry(input.as_str())?;
         count += 1;
 
+        if let Some(command) = input.strip_prefix('.')
+            && command.starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            let (command, arg) = command
+                .split_once(' ')
+                .map_or((command, ""), |(command, arg)| (command, arg.trim()));
+
+            match command {
+                "help" => print_help(),
+                "clear" => {
+                    *interpreter.borrow_mut() = Session::interpreter(path)?;
+                    *vm.borrow_mut() = Session::vm(path)?;
+                    inputs.clear();
+                }
+                "load" => match std::fs::read_to_string(arg) {
+                    Ok(code) => {
+                        run_input(&code, conf, &interpreter, &vm, &rt);
+                        inputs.push(code);
+                    }
+                    Err(e) => eprintln!("Failed to load {arg}: {e}"),
+                },
+                "save" => match std::fs::write(arg, inputs.join("\n")) {
+                    Ok(()) => println!("Saved the session to {arg}"),
+                    Err(e) => eprintln!("Failed to save {arg}: {e}"),
+                },
+                _ => eprintln!("Unknown command .{command}, see .help"),
+            }
+
+            continue;
+        }
+
         if let Some(file) = input.strip_prefix('!') {
             let file = file.trim();
             input = std::fs::read_to_string(file)?;
         }
 
-        run_input(
-            &input,
-            conf,
-            &mut interpreter_realm,
-            &mut interpreter_scope,
-            &mut vm_realm,
-            &vm_scope,
-            &rt,
-        );
+        run_input(&input, conf, &interpreter, &vm, &rt);
+        inputs.push(input);
     }
 
-    Ok(())
-}
+    if let Some(history) = &history {
+        if let Some(dir) = history.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
 
-#[allow(clippy::too_many_arguments)]
-fn run_input(
-    input: &str,
-    conf: Conf,
-    interpreter_realm: &mut Realm,
-    interpreter_scope: &mut Scope,
-    vm_realm: &mut Realm,
-    vm_scope: &Scope,
-    rt: &Runtime,
-) {
-    if input.is_empty() {
-        return;
+        rl.save_history(history)?;
     }
 
-    let input = StringInput::new(input, BytePos(0), BytePos(input.len() as u32));
-    let syn = Syntax::Es(EsSyntax {
+    Ok(())
+}
+
+pub const fn syntax() -> Syntax {
+    Syntax::Es(EsSyntax {
         jsx: false,
         fn_bind: false,
         decorators: true,
@@ -126,12 +222,72 @@ fn run_input(
         allow_return_outside_function: false,
         auto_accessors: true,
         explicit_resource_management: true,
-    });
+    })
+}
+
+/// Parses `input` as a script, or as a module if it only parses with
+/// top-level `await`. The flag tells whether it has to be wrapped.
+fn parse(input: &str) -> Result<(Vec<Stmt>, bool), swc_ecma_parser::error::Error> {
+    let parser = || {
+        let input = StringInput::new(input, BytePos(0), BytePos(input.len() as u32));
+        Parser::new(syntax(), input, None)
+    };
+
+    if let Ok(module) = parser().parse_module() {
+        let stmts = module
+            .body
+            .into_iter()
+            .map(|item| match item {
+                ModuleItem::Stmt(stmt) => Some(stmt),
+                ModuleItem::ModuleDecl(_) => None,
+            })
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(stmts) = stmts
+            && top_level_await::has_top_level_await(&stmts)
+        {
+            return Ok((stmts, true));
+        }
+    }
+
+    parser().parse_script().map(|script| (script.body, false))
+}
+
+/// The value of the promise returned by the wrapper for top-level `await`,
+/// once the event loop ran.
+fn settle(value: Value, rt: &Runtime) -> Res<Value> {
+    let Value::Object(obj) = &value else {
+        return Ok(value);
+    };
+
+    let Some(promise) = obj.downcast::<Promise>() else {
+        return Ok(value);
+    };
 
-    let mut p = Parser::new(syn, input, None);
+    if matches!(promise.state.get(), PromiseState::Pending) {
+        return Ok(value);
+    }
+
+    match rt.block_on(promise.wait_to_res())? {
+        PromiseResult::Fulfilled(value) => Ok(value),
+        PromiseResult::Rejected(value) => Err(Error::throw(value)),
+    }
+}
+
+#[cfg_attr(not(feature = "vm"), allow(unused_variables))]
+fn run_input(
+    input: &str,
+    conf: Conf,
+    interpreter: &RefCell<Session>,
+    vm: &RefCell<Session>,
+    rt: &Runtime,
+) {
+    if input.trim().is_empty() {
+        return;
+    }
 
-    let script = match p.parse_script() {
-        Ok(s) => s,
+    let (mut stmts, is_async) = match parse(input) {
+        Ok(parsed) => parsed,
         Err(e) => {
             // HANDLER.with(|h| {
             //     let mut diagnostic = e.into_diagnostic(h);
@@ -147,40 +303,67 @@ fn run_input(
         }
     };
 
-    if let Err(e) = Validator::new().validate_statements(&script.body) {
+    if let Err(e) = Validator::new().validate_statements(&stmts) {
         eprintln!("SyntaxError: {e}");
         return;
     }
 
     if conf.ast {
-        println!("AST:\n{script:#?}");
+        println!("AST:\n{stmts:#?}");
+    }
+
+    let mut names = Vec::new();
+
+    if is_async {
+        (stmts, names) = top_level_await::wrap(stmts);
     }
 
     if conf.interpreter {
-        let result = match yavashark_interpreter::Interpreter::run_in(
-            &script.body,
-            interpreter_realm,
-            interpreter_scope,
-        ) {
+        let session = &mut *interpreter.borrow_mut();
+
+        if let Err(e) = session.declare(&names) {
+            eprintln!("Uncaught {}", e.pretty_print(&mut session.realm));
+            return;
+        }
+
+        let Session { realm, scope } = session;
+
+        let result = match yavashark_interpreter::Interpreter::run_in(&stmts, realm, scope) {
             Ok(r) => r,
             Err(e) => {
-                eprintln!("Uncaught {}", e.pretty_print(interpreter_realm));
+                eprintln!("Uncaught {}", e.pretty_print(realm));
                 return;
             }
         };
 
+        let result = if is_async {
+            rt.block_on(realm.run_event_loop());
+
+            match settle(result, rt) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Uncaught {}", e.pretty_print(realm));
+                    return;
+                }
+            }
+        } else {
+            result
+        };
+
         if conf.bytecode {
-            println!("Interpreter: {}", result.pretty_print(interpreter_realm));
+            println!("Interpreter: {}", result.pretty_print(realm));
         } else {
-            println!("{}", result.pretty_print(interpreter_realm));
+            println!("{}", result.pretty_print(realm));
         }
 
-        rt.block_on(interpreter_realm.run_event_loop());
+        if !is_async {
+            rt.block_on(realm.run_event_loop());
+        }
     }
 
     #[cfg(feature = "vm")]
     if conf.bytecode || conf.instructions {
-        let bc = match yavashark_compiler::Compiler::compile(&script.body) {
+        let bc = match yavashark_compiler::Compiler::compile(&stmts) {
             Ok(bc) => bc,
             Err(e) => {
                 eprintln!("Failed to compile code: {e:?}");
@@ -196,17 +379,37 @@ fn run_input(
         if conf.bytecode {
             use yavashark_vm::yavashark_bytecode::data::DataSection;
             use yavashark_vm::{BorrowedVM, VM};
+
+            let session = &mut *vm.borrow_mut();
+
+            if let Err(e) = session.declare(&names) {
+                eprintln!("Uncaught: {e:?}");
+                return;
+            }
+
+            let Session { realm, scope } = session;
+
             let data = DataSection::new(bc.variables, Vec::new(), bc.literals, bc.control);
-            let mut vm =
-                BorrowedVM::with_scope(&bc.instructions, &data, vm_realm, vm_scope.clone());
+            let mut vm = BorrowedVM::with_scope(&bc.instructions, &data, realm, scope.clone());
 
             if let Err(e) = vm.run() {
                 eprintln!("Uncaught: {e:?}");
             }
 
-            println!("Bytecode: {:?}", vm.acc());
+            let result = vm.acc();
 
-            rt.block_on(vm_realm.run_event_loop());
+            if is_async {
+                rt.block_on(realm.run_event_loop());
+
+                match settle(result, rt) {
+                    Ok(result) => println!("Bytecode: {result:?}"),
+                    Err(e) => eprintln!("Uncaught: {e:?}"),
+                }
+            } else {
+                println!("Bytecode: {result:?}");
+
+                rt.block_on(realm.run_event_loop());
+            }
         }
     }
 }
@@ -1,18 +1,119 @@
-use crate::conf::Conf;
+use crate::repl::{COMMANDS, Session, syntax};
 use rustyline::completion::{Completer, FilenameCompleter, Pair};
 use rustyline::highlight::{CmdKind, Highlighter, MatchingBracketHighlighter};
 use rustyline::hint::HistoryHinter;
-use rustyline::validate::MatchingBracketValidator;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
 use rustyline::{Context, Helper};
 use rustyline_derive::{Completer, Hinter, Validator};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+use swc_common::input::StringInput;
+use swc_common::{BytePos, Spanned};
+use swc_ecma_parser::Parser;
+use swc_ecma_parser::error::SyntaxError;
 use yavashark_env::scope::Scope;
+use yavashark_env::utils::coerce_object;
+use yavashark_env::{ObjectOrNull, PropertyKey, Realm, Res};
+
+/// How many prototypes are searched for property names.
+const MAX_PROTOTYPE_DEPTH: usize = 32;
 
 pub struct ScopeCompleter {
     filename: FilenameCompleter,
-    #[allow(unused)]
-    scope: Scope,
+    session: Rc<RefCell<Session>>,
+}
+
+impl ScopeCompleter {
+    /// The variable names or, after a `.`, the property names that start with
+    /// the word before `pos`.
+    fn complete_word(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let before = &line[..pos];
+        let start = before
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == '.'))
+            .map_or(0, |i| {
+                i + before[i..].chars().next().map_or(1, char::len_utf8)
+            });
+
+        let word = &before[start..];
+
+        // numbers like `1.5` don't have properties worth completing
+        if word.starts_with(|c: char| c.is_ascii_digit()) {
+            return (pos, Vec::new());
+        }
+
+        let (path, prefix) = word.rsplit_once('.').unwrap_or(("", word));
+
+        let Ok(mut session) = self.session.try_borrow_mut() else {
+            return (pos, Vec::new());
+        };
+
+        let Session { realm, scope } = &mut *session;
+
+        let names = if word.contains('.') {
+            property_names(path, scope, realm)
+        } else {
+            scope
+                .get_variable_names(realm)
+                .map(|names| names.into_iter().collect())
+        };
+
+        let candidates = names
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| Pair {
+                display: name.clone(),
+                replacement: name,
+            })
+            .collect();
+
+        (pos - prefix.len(), candidates)
+    }
+}
+
+/// All property names of the value `path` evaluates to, including the ones
+/// it inherits.
+///
+/// Only plain property chains like `a.b.c` are evaluated, anything else could
+/// have side effects.
+fn property_names(path: &str, scope: &Scope, realm: &mut Realm) -> Res<BTreeSet<String>> {
+    let mut parts = path.split('.');
+    let mut names = BTreeSet::new();
+
+    let Some(mut value) = scope.resolve(parts.next().unwrap_or_default(), realm)? else {
+        return Ok(names);
+    };
+
+    for part in parts {
+        if part.is_empty() {
+            return Ok(names);
+        }
+
+        value = value.get_property(part.to_owned(), realm)?;
+    }
+
+    if value.is_nullish() {
+        return Ok(names);
+    }
+
+    let mut obj = coerce_object(value, realm)?;
+
+    for _ in 0..MAX_PROTOTYPE_DEPTH {
+        for key in obj.keys(realm)? {
+            if let PropertyKey::String(name) = key {
+                names.insert(name.to_string());
+            }
+        }
+
+        match obj.prototype(realm)? {
+            ObjectOrNull::Object(proto) => obj = proto,
+            ObjectOrNull::Null => break,
+        }
+    }
+
+    Ok(names)
 }
 
 impl Completer for ScopeCompleter {
@@ -38,23 +139,71 @@ impl Completer for ScopeCompleter {
             return Ok((pos, pairs));
         }
 
-        // let Ok(names) = self.scope.get_variable_names(realm) else {
-        //     return Ok((0, vec![]));
-        // };
+        if line.starts_with('.') {
+            if let Some((command, _)) = line.split_once(' ') {
+                if matches!(command, ".load" | ".save") && pos > command.len() {
+                    let offset = command.len() + 1;
+                    let (pos, pairs) =
+                        self.filename.complete(&line[offset..], pos - offset, ctx)?;
 
-        let names = HashSet::<String>::default();
+                    return Ok((pos + offset, pairs));
+                }
 
-        let mut completions = vec![];
-        for name in names {
-            if name.starts_with(line) {
-                completions.push(Pair {
-                    display: name.clone(),
-                    replacement: name.clone(),
-                });
+                return Ok((pos, Vec::new()));
             }
+
+            let pairs = COMMANDS
+                .iter()
+                .filter(|(command, _)| command.starts_with(&line[..pos]))
+                .map(|(command, _)| Pair {
+                    display: (*command).to_string(),
+                    replacement: (*command).to_string(),
+                })
+                .collect();
+
+            return Ok((0, pairs));
         }
 
-        Ok((0, completions))
+        Ok(self.complete_word(line, pos))
+    }
+}
+
+/// Asks for more lines while the input is an unfinished statement, like an
+/// open block or a template literal that isn't closed yet.
+pub struct InputValidator;
+
+impl InputValidator {
+    fn is_incomplete(input: &str) -> bool {
+        let trimmed = input.trim_end();
+
+        if trimmed.is_empty() || trimmed.starts_with('.') || trimmed.starts_with('!') {
+            return false;
+        }
+
+        let end = BytePos(trimmed.len() as u32);
+        let mut parser = Parser::new(syntax(), StringInput::new(trimmed, BytePos(0), end), None);
+
+        let result = parser.parse_module().err();
+
+        // the parser recovers from some errors, like a missing `}`
+        result.into_iter().chain(parser.take_errors()).any(|err| {
+            matches!(
+                err.kind(),
+                SyntaxError::Eof
+                    | SyntaxError::UnterminatedTpl
+                    | SyntaxError::UnterminatedBlockComment
+            ) || err.span().lo >= end
+        })
+    }
+}
+
+impl Validator for InputValidator {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if Self::is_incomplete(ctx.input()) {
+            return Ok(ValidationResult::Incomplete);
+        }
+
+        Ok(ValidationResult::Valid(None))
     }
 }
 
@@ -64,30 +213,22 @@ pub struct ReplHelper {
     pub completer: ScopeCompleter,
     pub highlighter: MatchingBracketHighlighter,
     #[rustyline(Validator)]
-    pub validator: MatchingBracketValidator,
+    pub validator: InputValidator,
     #[rustyline(Hinter)]
     pub hinter: HistoryHinter,
     pub colored_prompt: String,
 }
 
 impl ReplHelper {
-    pub fn new(int: Scope, vm: Scope, conf: Conf) -> Self {
-        let completer = if conf.interpreter {
-            ScopeCompleter {
-                filename: FilenameCompleter::new(),
-                scope: vm,
-            }
-        } else {
-            ScopeCompleter {
-                filename: FilenameCompleter::new(),
-                scope: int,
-            }
-        };
-
+    /// Completes names from the realm and global scope of `session`.
+    pub fn new(session: Rc<RefCell<Session>>) -> Self {
         Self {
-            completer,
+            completer: ScopeCompleter {
+                filename: FilenameCompleter::new(),
+                session,
+            },
             highlighter: MatchingBracketHighlighter::new(),
-            validator: MatchingBracketValidator::new(),
+            validator: InputValidator,
             hinter: HistoryHinter {},
             colored_prompt: String::new(),
         }
@@ -119,3 +260,73 @@ impl Highlighter for ReplHelper {
         self.highlighter.highlight_char(line, pos, kind)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incomplete_input() {
+        for input in [
+            "function f() {",
+            "if (x) {\n  y();",
+            "let a = [1, 2,",
+            "foo(",
+            "let s = `a ${b}",
+            "/* comment",
+            "if (x)",
+        ] {
+            assert!(InputValidator::is_incomplete(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn complete_or_invalid_input() {
+        for input in [
+            "",
+            "   ",
+            "1 + 1",
+            "function f() {}",
+            "let s = `a ${b}`;",
+            "let = = 1;",
+            "}",
+            ".help",
+            "!ls",
+        ] {
+            assert!(!InputValidator::is_incomplete(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn completes_names_and_properties() {
+        let session = Session::interpreter(std::path::Path::new("")).unwrap();
+        let session = Rc::new(RefCell::new(session));
+
+        {
+            let Session { realm, scope } = &mut *session.borrow_mut();
+            scope
+                .declare_global_var("answer".to_string(), 42.into(), realm)
+                .unwrap();
+        }
+
+        let completer = ScopeCompleter {
+            filename: FilenameCompleter::new(),
+            session,
+        };
+
+        let (start, pairs) = completer.complete_word("1 + answ", 8);
+        assert_eq!(start, 4);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].replacement, "answer");
+
+        let (start, pairs) = completer.complete_word("Math.fl", 7);
+        assert_eq!(start, 5);
+        assert!(pairs.iter().any(|pair| pair.replacement == "floor"));
+
+        let (_, pairs) = completer.complete_word("answer.toFix", 12);
+        assert!(pairs.iter().any(|pair| pair.replacement == "toFixed"));
+
+        let (_, pairs) = completer.complete_word("1.5", 3);
+        assert!(pairs.is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+use swc_common::DUMMY_SP;
+use swc_ecma_ast::{
+    ArrowExpr, AssignExpr, AssignOp, AssignTarget, AwaitExpr, BindingIdent, CallExpr, Callee,
+    Class, ClassExpr, Decl, Expr, ExprStmt, FnExpr, ForOfStmt, Function, FunctionBody, ParenExpr,
+    Pat, ReturnStmt, Stmt,
+};
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// Whether `stmts` use `await` outside of a function.
+pub fn has_top_level_await(stmts: &[Stmt]) -> bool {
+    let mut finder = AwaitFinder(false);
+    stmts.visit_with(&mut finder);
+
+    finder.0
+}
+
+struct AwaitFinder(bool);
+
+impl Visit for AwaitFinder {
+    fn visit_await_expr(&mut self, _: &AwaitExpr) {
+        self.0 = true;
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        self.0 |= stmt.is_await;
+        stmt.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_class(&mut self, _: &Class) {}
+}
+
+/// Wraps `stmts` into an immediately called async function, so they can use
+/// `await`.
+///
+/// Declarations would be local to that function, so they are turned into
+/// assignments and their names are returned, these have to be declared in the
+/// REPL scope before running the wrapper. `const` loses its constness on the
+/// way. If the last statement is an expression, the function returns its
+/// value, otherwise `undefined`.
+pub fn wrap(stmts: Vec<Stmt>) -> (Vec<Stmt>, Vec<String>) {
+    let mut names = Vec::new();
+    let mut functions = Vec::new();
+    let mut body = Vec::with_capacity(stmts.len());
+
+    let last = stmts.len().saturating_sub(1);
+
+    for (i, stmt) in stmts.into_iter().enumerate() {
+        match stmt {
+            Stmt::Decl(Decl::Var(decl)) => {
+                for decl in decl.decls {
+                    collect_names(&decl.name, &mut names);
+
+                    let Some(init) = decl.init else {
+                        continue;
+                    };
+
+                    if let Ok(target) = AssignTarget::try_from(decl.name) {
+                        body.push(assign(target, init));
+                    }
+                }
+            }
+            Stmt::Decl(Decl::Fn(decl)) => {
+                names.push(decl.ident.sym.to_string());
+
+                let function = Expr::Fn(FnExpr {
+                    ident: Some(decl.ident.clone()),
+                    function: decl.function,
+                });
+
+                // function declarations are hoisted
+                functions.push(assign(AssignTarget::from(decl.ident), Box::new(function)));
+            }
+            Stmt::Decl(Decl::Class(decl)) => {
+                names.push(decl.ident.sym.to_string());
+
+                let class = Expr::Class(ClassExpr {
+                    ident: Some(decl.ident.clone()),
+                    class: decl.class,
+                });
+
+                body.push(assign(AssignTarget::from(decl.ident), Box::new(class)));
+            }
+            Stmt::Expr(stmt) if i == last => {
+                body.push(Stmt::Return(ReturnStmt {
+                    span: stmt.span,
+                    arg: Some(stmt.expr),
+                }));
+            }
+            stmt => body.push(stmt),
+        }
+    }
+
+    // the bytecode VM would return whatever the last instruction left behind
+    if !matches!(body.last(), Some(Stmt::Return(_))) {
+        body.push(Stmt::Return(ReturnStmt {
+            span: DUMMY_SP,
+            arg: None,
+        }));
+    }
+
+    functions.append(&mut body);
+
+    let function = Function {
+        body: Some(FunctionBody {
+            span: DUMMY_SP,
+            stmts: functions,
+        }),
+        is_async: true,
+        ..Default::default()
+    };
+
+    let callee = Expr::Paren(ParenExpr {
+        span: DUMMY_SP,
+        expr: Box::new(Expr::Fn(FnExpr {
+            ident: None,
+            function: Box::new(function),
+        })),
+    });
+
+    let call = Expr::Call(CallExpr {
+        callee: Callee::Expr(Box::new(callee)),
+        ..Default::default()
+    });
+
+    let stmt = Stmt::Expr(ExprStmt {
+        span: DUMMY_SP,
+        expr: Box::new(call),
+    });
+
+    (vec![stmt], names)
+}
+
+fn collect_names(pat: &Pat, names: &mut Vec<String>) {
+    let mut collector = NameCollector(names);
+    pat.visit_with(&mut collector);
+}
+
+struct NameCollector<'a>(&'a mut Vec<String>);
+
+impl Visit for NameCollector<'_> {
+    fn visit_binding_ident(&mut self, ident: &BindingIdent) {
+        self.0.push(ident.id.sym.to_string());
+    }
+
+    // default values and computed keys don't declare anything
+    fn visit_expr(&mut self, _: &Expr) {}
+}
+
+fn assign(target: AssignTarget, value: Box<Expr>) -> Stmt {
+    Stmt::Expr(ExprStmt {
+        span: DUMMY_SP,
+        expr: Box::new(Expr::Assign(AssignExpr {
+            span: DUMMY_SP,
+            op: AssignOp::Assign,
+            left: target,
+            right: value,
+        })),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::{Session, parse, settle};
+    use std::path::Path;
+    use tokio::runtime::{Builder, Runtime};
+    use yavashark_env::Value;
+    use yavashark_interpreter::Interpreter;
+
+    fn stmts(input: &str) -> Vec<Stmt> {
+        let (stmts, is_async) = parse(input).unwrap();
+        assert!(is_async, "{input} has no top-level await");
+
+        stmts
+    }
+
+    /// Runs `input` the way the REPL does with the tree-walk interpreter.
+    fn run(session: &mut Session, rt: &Runtime, input: &str) -> Value {
+        let (stmts, is_async) = parse(input).unwrap();
+        let (stmts, names) = if is_async {
+            wrap(stmts)
+        } else {
+            (stmts, Vec::new())
+        };
+
+        session.declare(&names).unwrap();
+
+        let Session { realm, scope } = session;
+        let result = Interpreter::run_in(&stmts, realm, scope).unwrap();
+
+        rt.block_on(realm.run_event_loop());
+
+        settle(result, rt).unwrap()
+    }
+
+    #[test]
+    fn detects_await() {
+        assert!(has_top_level_await(&stmts("await 1;")));
+        assert!(has_top_level_await(&stmts("if (x) { let y = await x; }")));
+        assert!(has_top_level_await(&stmts("for await (const x of xs) {}")));
+        assert!(has_top_level_await(&stmts(
+            "for (const x of xs) { for await (const y of x) {} }"
+        )));
+
+        for input in [
+            "async function f() { await 1; }",
+            "const f = async () => { for await (const x of xs) {} };",
+            "class A { async m() { await 1; } }",
+            "for (const x of xs) {}",
+        ] {
+            let (stmts, _) = parse(input).unwrap();
+
+            assert!(!has_top_level_await(&stmts), "{input}");
+        }
+    }
+
+    #[test]
+    fn declarations_become_globals() {
+        let (_, names) = wrap(stmts(
+            "await 0; var a = 1, u; let [b, { c = d }] = []; const { e, ...f } = {}; \
+             function g() {} class H {}",
+        ));
+
+        assert_eq!(names, ["a", "u", "b", "c", "e", "f", "g", "H"]);
+    }
+
+    #[test]
+    fn declarations_are_visible_afterwards() {
+        let rt = Builder::new_current_thread().enable_all().build().unwrap();
+        let mut session = Session::interpreter(Path::new("")).unwrap();
+
+        // the wrapper is compiled for the bytecode VM, which has no classes
+        // yet, so they are only covered by `declarations_become_globals`
+        let value = run(
+            &mut session,
+            &rt,
+            "var a = await 1; let [b] = [2]; const { c } = { c: 3 }; \
+             function f() { return 4; }",
+        );
+        assert_eq!(value, Value::Undefined);
+
+        let sum = run(&mut session, &rt, "a + b + c + f()");
+        assert_eq!(sum, Value::Number(10.0));
+    }
+
+    #[test]
+    fn functions_are_hoisted() {
+        let rt = Builder::new_current_thread().enable_all().build().unwrap();
+        let mut session = Session::interpreter(Path::new("")).unwrap();
+
+        let value = run(
+            &mut session,
+            &rt,
+            "const x = await g(); function g() { return 6; } x + 1",
+        );
+
+        assert_eq!(value, Value::Number(7.0));
+    }
+}
@@ -10,17 +10,24 @@ use pprof::protos::Message;
 use std::fs::File;
 #[cfg(feature = "pprof")]
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
 use swc_common::BytePos;
 use swc_common::input::StringInput;
 use swc_ecma_ast::Program;
 use swc_ecma_parser::{EsSyntax, Parser, Syntax};
 use tokio::runtime::Builder;
+use yavashark_env::coverage::FileItems;
 use yavashark_env::print::PrettyPrint;
+use yavashark_env::realm::limits::Limits;
 use yavashark_env::scope::Scope;
-use yavashark_env::{ControlFlow, Realm};
+use yavashark_env::snapshot::Snapshot;
+use yavashark_env::{ControlFlow, Error, Realm, Res};
+use yavashark_inspector::Inspector;
 use yavashark_interpreter::eval::InterpreterEval;
 use yavashark_swc_validator::Validator;
+use yavashark_test_runner::Options as TestOptions;
 
 #[allow(clippy::unwrap_used)]
 pub fn main() {
@@ -95,11 +102,19 @@ pub fn main() {
         )
         .arg(
             clap::Arg::new("profile-out")
-                .help("Write JS profiler output to this path")
+                .help("Write JS profiler output to this path, pprof for .pb/.gz and collapsed stacks for .folded/.collapsed/.txt")
                 .long("profile-out")
                 .value_name("PATH")
                 .required(false),
         )
+        .arg(
+            clap::Arg::new("profile-interval")
+                .help("Sample the JS stack every MICROS microseconds instead of recording every call")
+                .long("profile-interval")
+                .value_name("MICROS")
+                .value_parser(clap::value_parser!(u64))
+                .required(false),
+        )
         .arg(
             clap::Arg::new("native-profile-out")
                 .help("Write native pprof output to this path")
@@ -113,6 +128,129 @@ pub fn main() {
                 .long("native-profile")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            clap::Arg::new("heap-stats")
+                .help("Print heap statistics after running")
+                .long("heap-stats")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("heap-snapshot")
+                .help("Write a V8 heap snapshot to this path after running")
+                .long("heap-snapshot")
+                .value_name("PATH")
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("max-steps")
+                .help("Abort after this many statements or instructions")
+                .long("max-steps")
+                .value_name("STEPS")
+                .value_parser(clap::value_parser!(u64))
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("max-heap")
+                .help("Abort when the heap grows beyond this many bytes")
+                .long("max-heap")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(usize))
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("max-call-depth")
+                .help("Throw a RangeError when calls are nested deeper than this")
+                .long("max-call-depth")
+                .value_name("DEPTH")
+                .value_parser(clap::value_parser!(usize))
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("timeout")
+                .help("Abort after this many milliseconds")
+                .long("timeout")
+                .value_name("MS")
+                .value_parser(clap::value_parser!(u64))
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("inspect")
+                .help("Accept Chrome DevTools Protocol clients on localhost, 9229 by default")
+                .long("inspect")
+                .value_name("PORT")
+                .value_parser(clap::value_parser!(u16))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("9229"),
+        )
+        .arg(
+            clap::Arg::new("inspect-brk")
+                .help("Like --inspect, but pause before the first statement")
+                .long("inspect-brk")
+                .value_name("PORT")
+                .value_parser(clap::value_parser!(u16))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("9229")
+                .conflicts_with("inspect"),
+        )
+        .arg(
+            clap::Arg::new("coverage")
+                .help("Write lcov.info and coverage-final.json to this directory")
+                .long("coverage")
+                .value_name("DIR")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("snapshot")
+                .help("Restore the globals and modules saved in this snapshot before running")
+                .long("snapshot")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(false),
+        )
+        .arg(
+            clap::Arg::new("write-snapshot")
+                .help("Write the globals and modules the code added to the realm to this file after running")
+                .long("write-snapshot")
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .required(false),
+        )
+        .subcommand(
+            clap::Command::new("test")
+                .about("Run the *.test.js and *.test.ts files in the given paths")
+                .arg(
+                    clap::Arg::new("paths")
+                        .help("Test files or directories to search, the current directory by default")
+                        .num_args(0..)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    clap::Arg::new("update-snapshots")
+                        .help("Overwrite the snapshots that don't match")
+                        .short('u')
+                        .long("update-snapshots")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::Arg::new("jobs")
+                        .help("How many files run at the same time, one per CPU by default")
+                        .short('j')
+                        .long("jobs")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    clap::Arg::new("timeout")
+                        .help("Fail a test or hook that runs longer than this, 5000 by default")
+                        .long("timeout")
+                        .value_name("MS")
+                        .value_parser(clap::value_parser!(u64)),
+                ),
+        )
+        .args_conflicts_with_subcommands(true)
         .arg(
             clap::Arg::new("eval")
                 .help("Evaluate the provided JavaScript code")
@@ -123,6 +261,10 @@ pub fn main() {
         )
         .get_matches();
 
+    if let Some(("test", matches)) = matches.subcommand() {
+        std::process::exit(run_tests(matches));
+    }
+
     let mut interpreter = matches.get_flag("interpreter");
     let bytecode = matches.get_flag("bytecode");
     let ast = matches.get_flag("ast");
@@ -130,8 +272,40 @@ pub fn main() {
     let shell = matches.get_flag("shell");
     let eval_code = matches.get_one::<String>("eval");
     let js_profile_out = matches.get_one::<String>("profile-out").cloned();
+    let js_profile_interval = matches
+        .get_one::<u64>("profile-interval")
+        .map(|micros| Duration::from_micros(*micros));
     let native_profile_out = matches.get_one::<String>("native-profile-out").cloned();
     let native_profile = matches.get_flag("native-profile");
+    let heap = HeapReport {
+        stats: matches.get_flag("heap-stats"),
+        snapshot: matches.get_one::<String>("heap-snapshot").cloned(),
+    };
+    let limits = RunLimits {
+        limits: Limits {
+            max_heap_bytes: matches.get_one::<usize>("max-heap").copied(),
+            max_steps: matches.get_one::<u64>("max-steps").copied(),
+            max_call_depth: matches.get_one::<usize>("max-call-depth").copied(),
+        },
+        timeout: matches
+            .get_one::<u64>("timeout")
+            .copied()
+            .map(Duration::from_millis),
+    };
+    let inspect = Inspect {
+        port: matches
+            .get_one::<u16>("inspect")
+            .or_else(|| matches.get_one::<u16>("inspect-brk"))
+            .copied(),
+        brk: matches.contains_id("inspect-brk"),
+    };
+    let coverage = CoverageOutput {
+        dir: matches.get_one::<PathBuf>("coverage").cloned(),
+    };
+    let snapshot = UserSnapshot {
+        load: matches.get_one::<PathBuf>("snapshot").cloned(),
+        save: matches.get_one::<PathBuf>("write-snapshot").cloned(),
+    };
 
     if !(interpreter || bytecode || ast || instructions) {
         interpreter = true;
@@ -164,8 +338,14 @@ pub fn main() {
             bytecode,
             instructions,
             js_profile_out.as_deref(),
+            js_profile_interval,
             native_profile,
             native_profile_out.as_deref(),
+            &heap,
+            &limits,
+            &inspect,
+            &coverage,
+            &snapshot,
         );
         return;
     }
@@ -193,8 +373,14 @@ pub fn main() {
             bytecode,
             instructions,
             js_profile_out.as_deref(),
+            js_profile_interval,
             native_profile,
             native_profile_out.as_deref(),
+            &heap,
+            &limits,
+            &inspect,
+            &coverage,
+            &snapshot,
         );
     }
 
@@ -210,6 +396,46 @@ pub fn main() {
     }
 }
 
+/// The `test` subcommand, returns the exit code.
+fn run_tests(matches: &clap::ArgMatches) -> i32 {
+    let paths = matches.get_many::<PathBuf>("paths").map_or_else(
+        || vec![PathBuf::from(".")],
+        |paths| paths.cloned().collect(),
+    );
+
+    let defaults = TestOptions::default();
+    let options = TestOptions {
+        jobs: matches
+            .get_one::<usize>("jobs")
+            .copied()
+            .unwrap_or(defaults.jobs),
+        update_snapshots: matches.get_flag("update-snapshots"),
+        timeout: matches
+            .get_one::<u64>("timeout")
+            .map_or(defaults.timeout, |ms| Duration::from_millis(*ms)),
+    };
+
+    let files = match yavashark_test_runner::discover(&paths) {
+        Ok(files) => files,
+        Err(e) => {
+            println!("Error: {e}");
+            return 1;
+        }
+    };
+
+    if files.is_empty() {
+        println!("No test files found");
+        return 1;
+    }
+
+    let summary = yavashark_test_runner::run(&files, &options, |file| print!("{file}"));
+
+    println!();
+    print!("{summary}");
+
+    i32::from(!summary.passed())
+}
+
 #[allow(
     clippy::unwrap_used,
     clippy::too_many_arguments,
@@ -223,8 +449,14 @@ fn run_code(
     #[allow(unused_variables)] bytecode: bool,
     #[allow(unused_variables)] instructions: bool,
     #[allow(unused_variables)] js_profile_out: Option<&str>,
+    #[allow(unused_variables)] js_profile_interval: Option<Duration>,
     native_profile: bool,
     #[allow(unused_variables)] native_profile_out: Option<&str>,
+    heap: &HeapReport,
+    limits: &RunLimits,
+    inspect: &Inspect,
+    coverage: &CoverageOutput,
+    snapshot: &UserSnapshot,
 ) {
     let string_input = StringInput::new(input, BytePos(0), BytePos(input.len() as u32));
 
@@ -292,6 +524,7 @@ fn run_code(
         let mut realm = Realm::new().unwrap();
         let mut scope = Scope::global(&realm, path.clone());
         realm.set_eval(InterpreterEval, false).unwrap();
+        limits.apply(&mut realm);
         #[cfg(feature = "profiler")]
         if let Some(profile_out) = js_profile_out {
             let p = match yavashark_profiler::FileProfileWriter::from_path(
@@ -305,9 +538,25 @@ fn run_code(
             };
 
             realm.set_profile_writer(p);
+
+            if let Some(interval) = js_profile_interval {
+                realm.profile.start_sampling(interval);
+            }
         }
         yavashark_vm::init(&mut realm).unwrap();
 
+        if let Err(e) = snapshot.restore(&mut realm) {
+            println!("Error: {e}");
+            return;
+        }
+
+        if let Err(e) = inspect.attach(&mut realm, &scope, &path, input) {
+            println!("Error: {e}");
+            return;
+        }
+
+        coverage.start(&mut realm, &path, input, &prog);
+
         let result =
             match yavashark_interpreter::Interpreter::run_program_in(&prog, &mut realm, &mut scope)
             {
@@ -327,6 +576,10 @@ fn run_code(
 
         rt.block_on(realm.run_event_loop());
 
+        heap.report();
+        coverage.write(&mut realm);
+        snapshot.write(&mut realm);
+
         #[cfg(feature = "profiler")]
         if let Some(profile_out) = js_profile_out {
             match realm.write_profile() {
@@ -363,6 +616,7 @@ fn run_code(
 
         let data = DataSection::new(bc.variables, Vec::new(), bc.literals, bc.control);
         let mut vm = OwnedVM::new(bc.instructions, data, path).unwrap();
+        limits.apply(vm.get_realm());
 
         match vm.run() {
             Ok(()) => {}
@@ -407,6 +661,8 @@ fn run_code(
                 println!("{ret:?}");
             }
         }
+
+        heap.report();
     }
 
     #[cfg(feature = "vm")]
@@ -424,6 +680,160 @@ fn run_code(
     }
 }
 
+/// What to report about the heap after running, see `--heap-stats` and
+/// `--heap-snapshot`.
+struct HeapReport {
+    stats: bool,
+    snapshot: Option<String>,
+}
+
+impl HeapReport {
+    #[cfg(feature = "heap_stats")]
+    fn report(&self) {
+        use yavashark_env::heap;
+
+        if self.stats {
+            eprintln!("{}", heap::stats());
+        }
+
+        if let Some(path) = &self.snapshot {
+            let snapshot = heap::snapshot();
+
+            match std::fs::File::create(path)
+                .and_then(|file| snapshot.write_json(std::io::BufWriter::new(file)))
+            {
+                Ok(()) => eprintln!("wrote heap snapshot to {path}"),
+                Err(e) => eprintln!("Error writing heap snapshot: {e}"),
+            }
+        }
+    }
+
+    #[cfg(not(feature = "heap_stats"))]
+    fn report(&self) {
+        if self.stats || self.snapshot.is_some() {
+            eprintln!(
+                "Heap statistics requested but not enabled at compile time. Rebuild with --features heap_stats."
+            );
+        }
+    }
+}
+
+/// The `--inspect` and `--inspect-brk` options.
+struct Inspect {
+    port: Option<u16>,
+    brk: bool,
+}
+
+impl Inspect {
+    /// Starts the inspector and waits for a client to tell the script to run.
+    fn attach(&self, realm: &mut Realm, scope: &Scope, path: &Path, source: &str) -> Res {
+        let Some(port) = self.port else {
+            return Ok(());
+        };
+
+        let inspector = Inspector::listen(port, path, source)
+            .map_err(|e| Error::new_error(format!("Failed to start the inspector: {e}")))?;
+
+        eprintln!("Debugger listening on {}", inspector.url());
+        eprintln!("For help, see: https://nodejs.org/en/docs/inspector");
+
+        realm.set_debugger(Rc::<Inspector>::clone(&inspector));
+
+        inspector.wait_for_debugger(realm, scope, self.brk)
+    }
+}
+
+/// The `--coverage` option.
+struct CoverageOutput {
+    dir: Option<PathBuf>,
+}
+
+impl CoverageOutput {
+    /// Starts collecting coverage with `prog` as the first file.
+    fn start(&self, realm: &mut Realm, path: &Path, source: &str, prog: &Program) {
+        if self.dir.is_none() {
+            return;
+        }
+
+        let items = match prog {
+            Program::Script(script) => FileItems::script(&script.body),
+            Program::Module(module) => FileItems::module(&module.body),
+        };
+
+        realm.start_coverage();
+        realm.register_coverage(path.to_path_buf(), source, items);
+    }
+
+    fn write(&self, realm: &mut Realm) {
+        let (Some(dir), Some(report)) = (&self.dir, realm.stop_coverage()) else {
+            return;
+        };
+
+        match report.write_to_dir(dir) {
+            Ok(()) => eprintln!("wrote coverage to {}", dir.display()),
+            Err(e) => eprintln!("Error writing coverage: {e}"),
+        }
+    }
+}
+
+/// The `--snapshot` and `--write-snapshot` options. A snapshot only holds the
+/// user state, it is restored on top of the fresh realm.
+struct UserSnapshot {
+    load: Option<PathBuf>,
+    save: Option<PathBuf>,
+}
+
+impl UserSnapshot {
+    /// Restores the snapshot to load into the fresh `realm`.
+    fn restore(&self, realm: &mut Realm) -> Res {
+        let Some(path) = &self.load else {
+            return Ok(());
+        };
+
+        let bytes = std::fs::read(path)
+            .map_err(|e| Error::new_error(format!("can't read {}: {e}", path.display())))?;
+
+        Snapshot::from_bytes(&bytes)?.restore(realm)
+    }
+
+    fn write(&self, realm: &mut Realm) {
+        let Some(path) = &self.save else {
+            return;
+        };
+
+        let written = Snapshot::capture(realm).and_then(|snapshot| {
+            std::fs::write(path, snapshot.to_bytes()).map_err(|e| Error::new_error(e.to_string()))
+        });
+
+        match written {
+            Ok(()) => eprintln!("wrote snapshot to {}", path.display()),
+            Err(e) => eprintln!("Error writing snapshot: {e}"),
+        }
+    }
+}
+
+/// Limits for the realm the code runs in, see `--max-steps`, `--max-heap`,
+/// `--max-call-depth` and `--timeout`.
+struct RunLimits {
+    limits: Limits,
+    timeout: Option<Duration>,
+}
+
+impl RunLimits {
+    fn apply(&self, realm: &mut Realm) {
+        realm.set_limits(self.limits);
+
+        if let Some(timeout) = self.timeout {
+            let interrupt = realm.interrupt_handle();
+
+            std::thread::spawn(move || {
+                std::thread::sleep(timeout);
+                interrupt.interrupt();
+            });
+        }
+    }
+}
+
 #[cfg(feature = "pprof")]
 #[allow(clippy::unwrap_used)]
 fn write_native_profile(